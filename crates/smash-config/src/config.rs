@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
@@ -25,6 +25,8 @@ pub enum LineNumberMode {
     Absolute,
     /// Show relative line numbers from cursor.
     Relative,
+    /// Show relative line numbers, with the absolute number on the cursor line.
+    Hybrid,
     /// Hide line numbers.
     None,
 }
@@ -96,6 +98,44 @@ pub struct EditorConfig {
     /// Defaults to `true` on macOS, `false` elsewhere.
     #[serde(default = "default_option_as_alt")]
     pub option_as_alt: bool,
+    /// Forward `smash <file>` to an already-running instance for the
+    /// same workspace instead of starting a second editor. Unix only.
+    /// The running instance rings the terminal bell but cannot bring its
+    /// own window to the front.
+    #[serde(default)]
+    pub single_instance: bool,
+    /// Reopen the files from the last session on startup when no file
+    /// is given on the command line.
+    #[serde(default)]
+    pub restore_session: bool,
+    /// Maximum undo entries kept per buffer before the oldest are pruned.
+    #[serde(default = "default_undo_max_entries")]
+    pub undo_max_entries: usize,
+    /// Approximate undo memory budget per buffer, in MiB.
+    #[serde(default = "default_undo_max_memory_mb")]
+    pub undo_max_memory_mb: usize,
+    /// Warn when the buffer's text, undo history, highlights and markers
+    /// together exceed this many MiB. `0` turns the warning off.
+    #[serde(default = "default_memory_budget_mb")]
+    pub memory_budget_mb: usize,
+    /// System clipboard provider: `"auto"` to detect one, or a provider
+    /// name such as `"wl-clipboard"`, `"xclip"`, `"tmux"` or `"osc52"`.
+    #[serde(default = "default_clipboard")]
+    pub clipboard: String,
+    /// Save through a temporary file renamed over the original, so a
+    /// crash mid-save cannot truncate it. Turn off for filesystems that
+    /// refuse the rename.
+    #[serde(default = "default_true")]
+    pub atomic_save: bool,
+    /// Command `SudoSave` pipes the buffer into, with the file's path
+    /// appended, e.g. `"sudo -A tee"` or `"pkexec tee"`. It cannot ask
+    /// for a password on the editor's terminal.
+    #[serde(default = "default_sudo_command")]
+    pub sudo_command: String,
+    /// Milliseconds without input after which idle work runs, such as
+    /// `lsp.hover_on_idle`. `0` turns it off.
+    #[serde(default = "default_idle_delay_ms")]
+    pub idle_delay_ms: u64,
 }
 
 fn default_tab_size() -> u8 {
@@ -105,6 +145,28 @@ fn default_true() -> bool {
     true
 }
 
+fn default_undo_max_entries() -> usize {
+    10_000
+}
+fn default_undo_max_memory_mb() -> usize {
+    16
+}
+fn default_memory_budget_mb() -> usize {
+    256
+}
+
+fn default_clipboard() -> String {
+    "auto".to_string()
+}
+
+fn default_sudo_command() -> String {
+    "sudo -n tee".to_string()
+}
+
+fn default_idle_delay_ms() -> u64 {
+    500
+}
+
 fn default_option_as_alt() -> bool {
     cfg!(target_os = "macos")
 }
@@ -120,10 +182,105 @@ impl Default for EditorConfig {
             auto_close_brackets: true,
             trim_trailing_whitespace: false,
             option_as_alt: default_option_as_alt(),
+            single_instance: false,
+            restore_session: false,
+            undo_max_entries: default_undo_max_entries(),
+            undo_max_memory_mb: default_undo_max_memory_mb(),
+            memory_budget_mb: default_memory_budget_mb(),
+            clipboard: default_clipboard(),
+            atomic_save: true,
+            sudo_command: default_sudo_command(),
+            idle_delay_ms: default_idle_delay_ms(),
         }
     }
 }
 
+/// Per-language overrides of `[editor]` settings and the `[lsp]` save
+/// hooks, from `[languages.<id>]`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LanguageConfig {
+    /// Overrides `editor.tab_size`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab_size: Option<u8>,
+    /// Overrides `editor.insert_spaces`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insert_spaces: Option<bool>,
+    /// Overrides `editor.auto_indent`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_indent: Option<bool>,
+    /// Overrides `lsp.format_on_save`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format_on_save: Option<bool>,
+    /// Overrides `lsp.organize_imports_on_save`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organize_imports_on_save: Option<bool>,
+    /// Source ↔ test file patterns, replacing the built-in ones for the
+    /// language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub companions: Option<Vec<CompanionConfig>>,
+    /// Abbreviations for the language, added to the top-level
+    /// `[abbreviations]` and replacing any with the same trigger.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub abbreviations: BTreeMap<String, String>,
+}
+
+/// A source file pattern and the pattern of its test, for
+/// GoToCompanionFile. Paths are relative to the workspace; `{dir}`
+/// stands for a directory prefix and `{name}` for a file name part.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompanionConfig {
+    /// e.g. `src/{dir}{name}.rs`.
+    pub source: String,
+    /// e.g. `tests/{dir}{name}.rs`.
+    pub test: String,
+    /// Contents of a new test file, with `{dir}` and `{name}` filled in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl CompanionConfig {
+    fn new(source: &str, test: &str, template: Option<&str>) -> Self {
+        Self {
+            source: source.to_string(),
+            test: test.to_string(),
+            template: template.map(str::to_string),
+        }
+    }
+
+    /// Built-in patterns for a language ID.
+    pub fn defaults(language: &str) -> Vec<CompanionConfig> {
+        match language {
+            "rust" => vec![Self::new(
+                "src/{dir}{name}.rs",
+                "tests/{dir}{name}.rs",
+                Some("#[test]\nfn {name}() {\n}\n"),
+            )],
+            "python" => vec![Self::new(
+                "{dir}{name}.py",
+                "{dir}test_{name}.py",
+                Some("def test_{name}():\n    pass\n"),
+            )],
+            "go" => vec![Self::new("{dir}{name}.go", "{dir}{name}_test.go", None)],
+            "javascript" => vec![Self::new("{dir}{name}.js", "{dir}{name}.test.js", None)],
+            "typescript" => vec![Self::new("{dir}{name}.ts", "{dir}{name}.test.ts", None)],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl EditorConfig {
+    /// These settings with a language's overrides applied.
+    pub fn with_overrides(&self, language: Option<&LanguageConfig>) -> EditorConfig {
+        let mut editor = self.clone();
+        if let Some(language) = language {
+            editor.tab_size = language.tab_size.unwrap_or(editor.tab_size);
+            editor.insert_spaces = language.insert_spaces.unwrap_or(editor.insert_spaces);
+            editor.auto_indent = language.auto_indent.unwrap_or(editor.auto_indent);
+        }
+        editor
+    }
+}
+
 /// Display / UI settings.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DisplayConfig {
@@ -139,12 +296,80 @@ pub struct DisplayConfig {
     /// Blink the cursor.
     #[serde(default = "default_true")]
     pub cursor_blink: bool,
+    /// Gutter and status bar signs for each diagnostic severity.
+    #[serde(default)]
+    pub diagnostic_signs: DiagnosticSignsConfig,
+    /// Lines beyond the edges of the view highlighted ahead of scrolling.
+    #[serde(default = "default_highlight_margin")]
+    pub highlight_margin: usize,
+    /// Most frames drawn per second; changes arriving faster are drawn
+    /// together.
+    #[serde(default = "default_max_fps")]
+    pub max_fps: u32,
+    /// Line lengths marked by a ruler in the column just past them.
+    #[serde(default)]
+    pub rulers: Vec<usize>,
+    /// Give the cursor line a background of its own.
+    #[serde(default)]
+    pub highlight_current_line: bool,
+    /// Draw a guide at each indent level of leading whitespace.
+    #[serde(default)]
+    pub indent_guides: bool,
+    /// Show a scrollbar marking diagnostics, search matches and changes.
+    #[serde(default)]
+    pub scrollbar: bool,
+}
+
+/// One character per diagnostic severity, so severities differ in shape
+/// and not only in colour.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticSignsConfig {
+    #[serde(default = "default_error_sign")]
+    pub error: char,
+    #[serde(default = "default_warning_sign")]
+    pub warning: char,
+    #[serde(default = "default_info_sign")]
+    pub info: char,
+    #[serde(default = "default_hint_sign")]
+    pub hint: char,
+}
+
+fn default_error_sign() -> char {
+    '\u{25cf}' // ●
+}
+fn default_warning_sign() -> char {
+    '\u{25b2}' // ▲
+}
+fn default_info_sign() -> char {
+    '\u{25c6}' // ◆
+}
+fn default_hint_sign() -> char {
+    '\u{25cb}' // ○
+}
+
+impl Default for DiagnosticSignsConfig {
+    fn default() -> Self {
+        Self {
+            error: default_error_sign(),
+            warning: default_warning_sign(),
+            info: default_info_sign(),
+            hint: default_hint_sign(),
+        }
+    }
 }
 
 fn default_theme() -> String {
     "dark".to_string()
 }
 
+fn default_highlight_margin() -> usize {
+    200
+}
+
+fn default_max_fps() -> u32 {
+    60
+}
+
 impl Default for DisplayConfig {
     fn default() -> Self {
         Self {
@@ -152,6 +377,13 @@ impl Default for DisplayConfig {
             line_numbers: LineNumberMode::Absolute,
             show_minimap: false,
             cursor_blink: true,
+            diagnostic_signs: DiagnosticSignsConfig::default(),
+            highlight_margin: default_highlight_margin(),
+            max_fps: default_max_fps(),
+            rulers: Vec::new(),
+            highlight_current_line: false,
+            indent_guides: false,
+            scrollbar: false,
         }
     }
 }
@@ -164,6 +396,9 @@ pub struct LogConfig {
     pub level: LogLevel,
     /// Optional path to a log file.
     pub file: Option<PathBuf>,
+    /// Extra per-target directives, e.g. `smash_lsp::rpc=debug`.
+    #[serde(default)]
+    pub targets: Vec<String>,
 }
 
 impl Default for LogConfig {
@@ -171,6 +406,7 @@ impl Default for LogConfig {
         Self {
             level: LogLevel::Info,
             file: None,
+            targets: Vec::new(),
         }
     }
 }
@@ -186,6 +422,9 @@ pub struct LspServerEntry {
     /// File extensions this server handles.
     #[serde(default)]
     pub extensions: Vec<String>,
+    /// Features to switch off for this server, e.g. `["formatting"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disable: Vec<String>,
 }
 
 /// LSP configuration.
@@ -197,6 +436,20 @@ pub struct LspConfig {
     /// Per-language server configurations, keyed by language ID.
     #[serde(default)]
     pub servers: HashMap<String, LspServerEntry>,
+    /// Format with the language server before writing a file.
+    #[serde(default)]
+    pub format_on_save: bool,
+    /// Run the server's `source.organizeImports` code action before
+    /// writing a file, ahead of any formatting.
+    #[serde(default)]
+    pub organize_imports_on_save: bool,
+    /// Show hover information for the symbol under the cursor once
+    /// input has paused for `editor.idle_delay_ms`.
+    #[serde(default)]
+    pub hover_on_idle: bool,
+    /// Features to switch off for every server.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disable: Vec<String>,
 }
 
 impl Default for LspConfig {
@@ -204,6 +457,10 @@ impl Default for LspConfig {
         Self {
             enabled: true,
             servers: HashMap::new(),
+            format_on_save: false,
+            organize_imports_on_save: false,
+            hover_on_idle: false,
+            disable: Vec::new(),
         }
     }
 }
@@ -220,6 +477,10 @@ pub struct Config {
     /// Keymap settings.
     #[serde(default)]
     pub keymap: KeymapConfig,
+    /// User key bindings layered over the preset: chord (`"ctrl-x ctrl-f"`)
+    /// to command name (`"open"`). Bind a key to `"noop"` to disable it.
+    #[serde(default)]
+    pub keys: BTreeMap<String, String>,
     /// Override the terminal shell executable.
     #[serde(default)]
     pub terminal_shell: Option<String>,
@@ -229,6 +490,18 @@ pub struct Config {
     /// LSP configuration.
     #[serde(default)]
     pub lsp: LspConfig,
+    /// Per-language editor settings, keyed by language ID.
+    #[serde(default)]
+    pub languages: BTreeMap<String, LanguageConfig>,
+    /// Abbreviations expanded as you type: a trigger word (`"teh"`) to the
+    /// text replacing it (`"the"`) when a space, punctuation or Enter
+    /// follows it.
+    #[serde(default)]
+    pub abbreviations: BTreeMap<String, String>,
+    /// Tasks run by RunTask: a name (`"build"`) to the shell command it
+    /// runs (`"cargo build"`) in the working directory.
+    #[serde(default)]
+    pub tasks: BTreeMap<String, String>,
     /// Auto-save interval in seconds (0 = disabled, minimum 5).
     #[serde(default = "default_auto_save")]
     pub auto_save_interval_secs: u64,
@@ -244,9 +517,13 @@ impl Default for Config {
             editor: EditorConfig::default(),
             display: DisplayConfig::default(),
             keymap: KeymapConfig::default(),
+            keys: BTreeMap::new(),
             terminal_shell: None,
             log: LogConfig::default(),
             lsp: LspConfig::default(),
+            languages: BTreeMap::new(),
+            abbreviations: BTreeMap::new(),
+            tasks: BTreeMap::new(),
             auto_save_interval_secs: 30,
         }
     }
@@ -256,6 +533,27 @@ impl Default for Config {
 mod tests {
     use super::*;
 
+    #[test]
+    fn language_overrides_apply_to_editor_settings() {
+        let cfg: Config = toml::from_str(
+            r#"
+[editor]
+tab_size = 4
+
+[languages.go]
+tab_size = 8
+insert_spaces = false
+"#,
+        )
+        .unwrap();
+        let go = cfg.editor.with_overrides(cfg.languages.get("go"));
+        assert_eq!(go.tab_size, 8);
+        assert!(!go.insert_spaces);
+        assert!(go.auto_indent);
+        let rust = cfg.editor.with_overrides(cfg.languages.get("rust"));
+        assert_eq!(rust, cfg.editor);
+    }
+
     #[test]
     fn default_config_has_expected_values() {
         let cfg = Config::default();
@@ -267,14 +565,27 @@ mod tests {
         assert!(cfg.editor.auto_close_brackets);
         assert!(!cfg.editor.trim_trailing_whitespace);
         assert_eq!(cfg.editor.option_as_alt, cfg!(target_os = "macos"));
+        assert!(!cfg.editor.single_instance);
+        assert!(!cfg.editor.restore_session);
+        assert_eq!(cfg.editor.memory_budget_mb, 256);
         assert_eq!(cfg.display.theme, "dark");
         assert_eq!(cfg.display.line_numbers, LineNumberMode::Absolute,);
         assert!(!cfg.display.show_minimap);
         assert!(cfg.display.cursor_blink);
+        assert_eq!(cfg.display.highlight_margin, 200);
+        assert_eq!(cfg.display.max_fps, 60);
+        assert!(cfg.display.rulers.is_empty());
+        assert!(!cfg.display.highlight_current_line);
+        assert!(!cfg.display.indent_guides);
+        assert!(!cfg.display.scrollbar);
+        assert_eq!(cfg.editor.idle_delay_ms, 500);
+        assert!(!cfg.lsp.hover_on_idle);
         assert_eq!(cfg.keymap.preset, "default");
         assert!(cfg.terminal_shell.is_none());
         assert_eq!(cfg.log.level, LogLevel::Info);
         assert!(cfg.log.file.is_none());
+        assert!(cfg.abbreviations.is_empty());
+        assert!(cfg.tasks.is_empty());
         assert_eq!(cfg.auto_save_interval_secs, 30);
     }
 
@@ -290,25 +601,71 @@ mod tests {
                 auto_close_brackets: false,
                 trim_trailing_whitespace: true,
                 option_as_alt: true,
+                single_instance: true,
+                restore_session: true,
+                undo_max_entries: 500,
+                undo_max_memory_mb: 4,
+                memory_budget_mb: 64,
+                clipboard: "tmux".into(),
+                atomic_save: false,
+                sudo_command: "doas tee".into(),
+                idle_delay_ms: 0,
             },
             display: DisplayConfig {
                 theme: "light".into(),
                 line_numbers: LineNumberMode::Relative,
                 show_minimap: true,
                 cursor_blink: false,
+                diagnostic_signs: DiagnosticSignsConfig {
+                    error: 'E',
+                    ..DiagnosticSignsConfig::default()
+                },
+                highlight_margin: 50,
+                max_fps: 30,
+                rulers: vec![80, 100],
+                highlight_current_line: true,
+                indent_guides: true,
+                scrollbar: true,
             },
             keymap: KeymapConfig {
                 preset: "emacs".into(),
             },
+            keys: BTreeMap::from([("ctrl-x ctrl-f".to_string(), "open".to_string())]),
             terminal_shell: Some("/bin/bash".into()),
             log: LogConfig {
                 level: LogLevel::Debug,
                 file: Some(PathBuf::from("/tmp/smash.log")),
+                targets: vec!["smash_lsp=trace".into()],
             },
             lsp: LspConfig {
                 enabled: false,
                 servers: HashMap::new(),
+                format_on_save: true,
+                organize_imports_on_save: true,
+                hover_on_idle: true,
+                disable: vec!["inlay_hints".into()],
             },
+            languages: BTreeMap::from([(
+                "go".to_string(),
+                LanguageConfig {
+                    tab_size: Some(8),
+                    insert_spaces: Some(false),
+                    auto_indent: None,
+                    format_on_save: Some(false),
+                    organize_imports_on_save: None,
+                    companions: Some(vec![CompanionConfig::new(
+                        "{dir}{name}.go",
+                        "{dir}{name}_test.go",
+                        Some("package main\n"),
+                    )]),
+                    abbreviations: BTreeMap::from([(
+                        "iferr".to_string(),
+                        "if err != nil {\n\treturn err\n}".to_string(),
+                    )]),
+                },
+            )]),
+            abbreviations: BTreeMap::from([("teh".to_string(), "the".to_string())]),
+            tasks: BTreeMap::from([("build".to_string(), "cargo build".to_string())]),
             auto_save_interval_secs: 60,
         };
 
@@ -343,4 +700,16 @@ line_numbers = "relative"
         let cfg: Config = toml::from_str("").expect("parse empty toml");
         assert_eq!(cfg, Config::default());
     }
+
+    #[test]
+    fn diagnostic_signs_parse_single_characters() {
+        let cfg: Config =
+            toml::from_str("[display.diagnostic_signs]\nerror = \"E\"\nhint = \"·\"\n")
+                .expect("parse toml");
+        let signs = &cfg.display.diagnostic_signs;
+        assert_eq!((signs.error, signs.hint), ('E', '·'));
+        assert_eq!(signs.warning, '\u{25b2}');
+        let err = toml::from_str::<Config>("[display.diagnostic_signs]\nerror = \"EE\"\n");
+        assert!(err.is_err());
+    }
 }
@@ -6,4 +6,7 @@ pub mod validate;
 
 pub use config::{Config, LspConfig, LspServerEntry};
 pub use error::ConfigError;
-pub use load::{load_config, load_from_str};
+pub use load::{
+    ensure_config_file, expand_paths, load_config, load_from_str, project_config_path,
+    user_config_path,
+};
@@ -26,6 +26,11 @@ const DEFAULT_CONFIG_CONTENT: &str = r#"# SMASH configuration
 
 # [log]
 # level = "info"
+# targets = ["smash_lsp::rpc=debug"]
+
+# [keys]
+# "ctrl-x ctrl-f" = "open"
+# "ctrl-q" = "noop"
 "#;
 
 /// Load and merge configuration.
@@ -43,7 +48,7 @@ const DEFAULT_CONFIG_CONTENT: &str = r#"# SMASH configuration
 /// Returns [`ConfigError`] on I/O failure, parse failure, or
 /// validation failure.
 pub fn load_config(config_dir: &Path, project_dir: Option<&Path>) -> Result<Config, ConfigError> {
-    let global_path = config_dir.join("config.toml");
+    let global_path = user_config_path(config_dir);
 
     // Ensure config dir exists
     if !config_dir.exists() {
@@ -84,10 +89,77 @@ pub fn load_config(config_dir: &Path, project_dir: Option<&Path>) -> Result<Conf
                 message: "validation failed".to_string(),
             })
     })?;
+    expand_paths(&mut config)?;
 
     Ok(config)
 }
 
+/// Expand `~` and environment variables in the config values that name
+/// files or programs: `log.file`, `terminal_shell` and LSP server
+/// commands.
+///
+/// # Errors
+///
+/// Returns [`ConfigError::Validation`] naming the field whose value
+/// refers to an unset variable.
+pub fn expand_paths(config: &mut Config) -> Result<(), ConfigError> {
+    let expand = |field: &str, value: &str| {
+        smash_platform::expand_path(value).map_err(|e| ConfigError::Validation {
+            field: field.to_string(),
+            message: e.to_string(),
+        })
+    };
+    if let Some(file) = &config.log.file {
+        config.log.file = Some(expand("log.file", &file.to_string_lossy())?);
+    }
+    if let Some(shell) = &config.terminal_shell {
+        let expanded = expand("terminal_shell", shell)?;
+        config.terminal_shell = Some(expanded.to_string_lossy().into_owned());
+    }
+    for (language, server) in config.lsp.servers.iter_mut() {
+        let field = format!("lsp.servers.{}.command", language);
+        server.command = expand(&field, &server.command)?
+            .to_string_lossy()
+            .into_owned();
+    }
+    Ok(())
+}
+
+/// Path of the global config file inside `config_dir`.
+pub fn user_config_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("config.toml")
+}
+
+/// Path of the project config that applies to `project_dir`.
+///
+/// Returns the nearest existing `.smash/config.toml` found walking
+/// upward, or `project_dir/.smash/config.toml` if there is none yet.
+pub fn project_config_path(project_dir: &Path) -> PathBuf {
+    find_project_config(project_dir)
+        .unwrap_or_else(|| project_dir.join(".smash").join("config.toml"))
+}
+
+/// Create `path` (and its parent directories) from the commented
+/// default template if it does not exist yet.
+///
+/// Returns `true` if the file was created.
+///
+/// # Errors
+///
+/// Returns [`ConfigError::CreateDefault`] if the file cannot be written.
+pub fn ensure_config_file(path: &Path) -> Result<bool, ConfigError> {
+    if path.exists() {
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| ConfigError::CreateDefault(e.to_string()))?;
+    }
+    std::fs::write(path, DEFAULT_CONFIG_CONTENT)
+        .map_err(|e| ConfigError::CreateDefault(e.to_string()))?;
+    tracing::info!("Created config template at {}", path.display());
+    Ok(true)
+}
+
 /// Walk from `start` upward looking for `.smash/config.toml`.
 fn find_project_config(start: &Path) -> Option<PathBuf> {
     let mut dir = start.to_path_buf();
@@ -119,7 +191,8 @@ fn has_non_comment_content(content: &str) -> bool {
 ///
 /// Returns [`ConfigError`] on parse or validation failure.
 pub fn load_from_str(toml_str: &str) -> Result<Config, ConfigError> {
-    let config: Config = toml::from_str(toml_str).map_err(|e| ConfigError::Parse(e.to_string()))?;
+    let mut config: Config =
+        toml::from_str(toml_str).map_err(|e| ConfigError::Parse(e.to_string()))?;
 
     validate(&config).map_err(|errors| {
         errors
@@ -131,6 +204,8 @@ pub fn load_from_str(toml_str: &str) -> Result<Config, ConfigError> {
             })
     })?;
 
+    expand_paths(&mut config)?;
+
     Ok(config)
 }
 
@@ -234,6 +309,54 @@ mod tests {
         assert!(!has_non_comment_content(DEFAULT_CONFIG_CONTENT));
     }
 
+    #[test]
+    fn project_config_path_defaults_to_dot_smash() {
+        let tmp = TempDir::new().unwrap();
+        assert_eq!(
+            project_config_path(tmp.path()),
+            tmp.path().join(".smash").join("config.toml")
+        );
+    }
+
+    #[test]
+    fn ensure_config_file_creates_template_once() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join(".smash").join("config.toml");
+        assert!(ensure_config_file(&path).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            DEFAULT_CONFIG_CONTENT
+        );
+        std::fs::write(&path, "[editor]\ntab_size = 2\n").unwrap();
+        assert!(!ensure_config_file(&path).unwrap());
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("tab_size = 2"));
+    }
+
+    #[test]
+    fn paths_in_config_are_expanded() {
+        let config = load_from_str(
+            "[log]\nfile = \"~/smash.log\"\n[lsp.servers.rust]\ncommand = \"~/bin/ra\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.log.file,
+            Some(smash_platform::expand_path("~/smash.log").unwrap())
+        );
+        assert!(!config.lsp.servers["rust"].command.starts_with('~'));
+
+        let err = load_from_str("terminal_shell = \"$SMASH_TEST_UNSET_VARIABLE/sh\"\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("terminal_shell"), "{}", err);
+        assert!(
+            err.contains("SMASH_TEST_UNSET_VARIABLE is not set"),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn has_non_comment_content_detects_values() {
         assert!(!has_non_comment_content(""));
@@ -1,3 +1,7 @@
+use std::collections::BTreeMap;
+
+use smash_platform::ClipboardProvider;
+
 use crate::config::Config;
 use crate::error::ConfigError;
 
@@ -16,6 +20,88 @@ pub fn validate(config: &Config) -> Result<(), Vec<ConfigError>> {
         });
     }
 
+    for (name, language) in &config.languages {
+        if let Some(tab_size) = language.tab_size.filter(|&t| t == 0 || t > 16) {
+            errors.push(ConfigError::Validation {
+                field: format!("languages.{}.tab_size", name),
+                message: format!("must be 1\u{2013}16, got {}", tab_size),
+            });
+        }
+        validate_abbreviations(
+            &format!("languages.{}.abbreviations", name),
+            &language.abbreviations,
+            &mut errors,
+        );
+    }
+    validate_abbreviations("abbreviations", &config.abbreviations, &mut errors);
+
+    for (name, command) in &config.tasks {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            errors.push(ConfigError::Validation {
+                field: format!("tasks.{}", name),
+                message: "name must not be empty or contain spaces".to_string(),
+            });
+        } else if command.trim().is_empty() {
+            errors.push(ConfigError::Validation {
+                field: format!("tasks.{}", name),
+                message: "command must not be empty".to_string(),
+            });
+        }
+    }
+
+    // undo budget: at least one entry and 1 MiB
+    if config.editor.undo_max_entries == 0 {
+        errors.push(ConfigError::Validation {
+            field: "editor.undo_max_entries".to_string(),
+            message: "must be \u{2265} 1, got 0".to_string(),
+        });
+    }
+    if config.editor.undo_max_memory_mb == 0 {
+        errors.push(ConfigError::Validation {
+            field: "editor.undo_max_memory_mb".to_string(),
+            message: "must be \u{2265} 1, got 0".to_string(),
+        });
+    }
+
+    // clipboard: "auto" or a known provider
+    if config.editor.clipboard != "auto"
+        && ClipboardProvider::from_name(&config.editor.clipboard).is_none()
+    {
+        let names: Vec<&str> = ClipboardProvider::ALL.iter().map(|p| p.name()).collect();
+        errors.push(ConfigError::Validation {
+            field: "editor.clipboard".to_string(),
+            message: format!(
+                "must be \"auto\" or one of {}, got \"{}\"",
+                names.join(", "),
+                config.editor.clipboard,
+            ),
+        });
+    }
+
+    // sudo_command: a program to run
+    if config.editor.sudo_command.trim().is_empty() {
+        errors.push(ConfigError::Validation {
+            field: "editor.sudo_command".to_string(),
+            message: "must not be empty".to_string(),
+        });
+    }
+
+    // max_fps: 1–240
+    if config.display.max_fps == 0 || config.display.max_fps > 240 {
+        errors.push(ConfigError::Validation {
+            field: "display.max_fps".to_string(),
+            message: format!("must be 1\u{2013}240, got {}", config.display.max_fps),
+        });
+    }
+
+    // rulers: each past at least one column
+    if config.display.rulers.contains(&0) {
+        errors.push(ConfigError::Validation {
+            field: "display.rulers".to_string(),
+            message: "must be at least 1, got 0".to_string(),
+        });
+    }
+
     // theme: non-empty
     if config.display.theme.is_empty() {
         errors.push(ConfigError::Validation {
@@ -42,6 +128,23 @@ pub fn validate(config: &Config) -> Result<(), Vec<ConfigError>> {
     }
 }
 
+/// Abbreviation triggers must be one word (letters, digits and `_`), as
+/// they are matched against the word typed before a boundary.
+fn validate_abbreviations(
+    field: &str,
+    abbreviations: &BTreeMap<String, String>,
+    errors: &mut Vec<ConfigError>,
+) {
+    for trigger in abbreviations.keys() {
+        if trigger.is_empty() || !trigger.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            errors.push(ConfigError::Validation {
+                field: format!("{}.{}", field, trigger),
+                message: "trigger must be a single word".to_string(),
+            });
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -62,6 +165,55 @@ mod tests {
         assert!(msg.contains("editor.tab_size"));
     }
 
+    #[test]
+    fn invalid_language_tab_size() {
+        let mut cfg = Config::default();
+        cfg.languages.insert(
+            "go".to_string(),
+            crate::config::LanguageConfig {
+                tab_size: Some(0),
+                ..Default::default()
+            },
+        );
+        let errs = validate(&cfg).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert!(format!("{}", errs[0]).contains("languages.go.tab_size"));
+    }
+
+    #[test]
+    fn abbreviation_triggers_must_be_one_word() {
+        let mut cfg = Config::default();
+        cfg.abbreviations
+            .insert("teh".to_string(), "the".to_string());
+        cfg.abbreviations
+            .insert("a b".to_string(), "ab".to_string());
+        cfg.languages.insert(
+            "go".to_string(),
+            crate::config::LanguageConfig {
+                abbreviations: BTreeMap::from([("".to_string(), "x".to_string())]),
+                ..Default::default()
+            },
+        );
+        let errs = validate(&cfg).unwrap_err();
+        assert_eq!(errs.len(), 2);
+        assert!(format!("{}", errs[0]).contains("languages.go.abbreviations"));
+        assert!(format!("{}", errs[1]).contains("abbreviations.a b"));
+    }
+
+    #[test]
+    fn tasks_need_a_name_and_a_command() {
+        let mut cfg = Config::default();
+        cfg.tasks
+            .insert("build".to_string(), "cargo build".to_string());
+        cfg.tasks.insert("lint".to_string(), " ".to_string());
+        cfg.tasks
+            .insert("run tests".to_string(), "cargo test".to_string());
+        let errs = validate(&cfg).unwrap_err();
+        assert_eq!(errs.len(), 2);
+        assert!(format!("{}", errs[0]).contains("command must not be empty"));
+        assert!(format!("{}", errs[1]).contains("tasks.run tests"));
+    }
+
     #[test]
     fn invalid_tab_size_seventeen() {
         let mut cfg = Config::default();
@@ -72,6 +224,61 @@ mod tests {
         assert!(msg.contains("editor.tab_size"));
     }
 
+    #[test]
+    fn zero_undo_budget_rejected() {
+        let mut cfg = Config::default();
+        cfg.editor.undo_max_entries = 0;
+        cfg.editor.undo_max_memory_mb = 0;
+        let errs = validate(&cfg).unwrap_err();
+        assert_eq!(errs.len(), 2);
+        assert!(format!("{}", errs[0]).contains("editor.undo_max_entries"));
+        assert!(format!("{}", errs[1]).contains("editor.undo_max_memory_mb"));
+    }
+
+    #[test]
+    fn unknown_clipboard_provider_rejected() {
+        let mut cfg = Config::default();
+        cfg.editor.clipboard = "xclip".to_string();
+        assert!(validate(&cfg).is_ok());
+        cfg.editor.clipboard = "klipper".to_string();
+        let errs = validate(&cfg).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert!(format!("{}", errs[0]).contains("editor.clipboard"));
+    }
+
+    #[test]
+    fn empty_sudo_command_rejected() {
+        let mut cfg = Config::default();
+        cfg.editor.sudo_command = " ".to_string();
+        let errs = validate(&cfg).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert!(format!("{}", errs[0]).contains("editor.sudo_command"));
+    }
+
+    #[test]
+    fn max_fps_out_of_range_rejected() {
+        let mut cfg = Config::default();
+        cfg.display.max_fps = 240;
+        assert!(validate(&cfg).is_ok());
+        for fps in [0, 241] {
+            cfg.display.max_fps = fps;
+            let errs = validate(&cfg).unwrap_err();
+            assert_eq!(errs.len(), 1);
+            assert!(format!("{}", errs[0]).contains("display.max_fps"));
+        }
+    }
+
+    #[test]
+    fn zero_ruler_rejected() {
+        let mut cfg = Config::default();
+        cfg.display.rulers = vec![80, 100];
+        assert!(validate(&cfg).is_ok());
+        cfg.display.rulers = vec![0, 80];
+        let errs = validate(&cfg).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert!(format!("{}", errs[0]).contains("display.rulers"));
+    }
+
     #[test]
     fn empty_theme_rejected() {
         let mut cfg = Config::default();
@@ -1,23 +1,38 @@
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 
-use ropey::Rope;
-
-/// Re-export `ropey::RopeSlice` so downstream crates can reference it
-/// without adding a direct `ropey` dependency.
+/// Re-export `ropey::Rope` and `ropey::RopeSlice` so downstream crates can
+/// reference them without adding a direct `ropey` dependency.
+pub use ropey::Rope;
 pub type RopeSlice<'a> = ropey::RopeSlice<'a>;
 
 use crate::cursor::{Cursor, CursorSet};
 use crate::edit::{EditCommand, EditEvent};
-use crate::encoding::{detect_line_ending, LineEnding};
+use crate::encoding::{decode_detected, detect_line_ending, LineEnding, TextEncoding};
 use crate::error::EditError;
+use crate::grapheme;
 use crate::position::{Position, Range};
-use crate::search::SearchState;
-use crate::undo::UndoTree;
+use crate::safe_write::{write_file, WriteMode};
+use crate::search::{SearchMatch, SearchState};
+use crate::undo::{UndoBudget, UndoTree};
 
 /// Global counter for generating unique buffer IDs.
 static NEXT_BUFFER_ID: AtomicU64 = AtomicU64::new(1);
 
+/// Chars taken by the line break at the end of `line`: 2 for `\r\n`, 1
+/// for `\n` or `\r`, 0 for the last line.
+pub fn line_break_len(line: RopeSlice<'_>) -> usize {
+    let len = line.len_chars();
+    match (
+        len.checked_sub(2).map(|i| line.char(i)),
+        len.checked_sub(1).map(|i| line.char(i)),
+    ) {
+        (Some('\r'), Some('\n')) => 2,
+        (_, Some('\n' | '\r')) => 1,
+        _ => 0,
+    }
+}
+
 /// Unique identifier for a buffer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct BufferId(pub u64);
@@ -37,9 +52,16 @@ pub struct Buffer {
     path: Option<PathBuf>,
     dirty: bool,
     line_ending: LineEnding,
+    /// The file's encoding on disk; the rope is always UTF-8.
+    encoding: TextEncoding,
     undo_tree: UndoTree,
     cursors: CursorSet,
     search: SearchState,
+    read_only: bool,
+    /// Edits, undos and redos since the rope was last compacted.
+    edits_since_compact: usize,
+    /// Where the most recent edit, undo or redo ended.
+    last_edit: Option<Position>,
 }
 
 impl Buffer {
@@ -51,9 +73,13 @@ impl Buffer {
             path: None,
             dirty: false,
             line_ending: LineEnding::default(),
+            encoding: TextEncoding::default(),
             undo_tree: UndoTree::new(),
             cursors: CursorSet::new(Cursor::new(Position::default())),
             search: SearchState::new(),
+            read_only: false,
+            edits_since_compact: 0,
+            last_edit: None,
         }
     }
 
@@ -66,29 +92,34 @@ impl Buffer {
             path: None,
             dirty: false,
             line_ending,
+            encoding: TextEncoding::default(),
             undo_tree: UndoTree::new(),
             cursors: CursorSet::new(Cursor::new(Position::default())),
             search: SearchState::new(),
+            read_only: false,
+            edits_since_compact: 0,
+            last_edit: None,
         }
     }
 
-    /// Create a buffer by reading a file from disk.
+    /// Create a buffer by reading a file from disk, in whatever encoding
+    /// it turns out to be in (see [`decode_detected`]).
     pub fn from_file(id: BufferId, path: &Path) -> Result<Self, EditError> {
         if !path.exists() {
             return Err(EditError::FileNotFound(path.to_path_buf()));
         }
-        let text = std::fs::read_to_string(path)?;
-        let line_ending = detect_line_ending(&text);
-        Ok(Self {
-            id,
-            rope: Rope::from_str(&text),
-            path: Some(path.to_path_buf()),
-            dirty: false,
-            line_ending,
-            undo_tree: UndoTree::new(),
-            cursors: CursorSet::new(Cursor::new(Position::default())),
-            search: SearchState::new(),
-        })
+        let (text, encoding) = decode_detected(std::fs::read(path)?)?;
+        let mut buf = Self::from_loaded(id, path, &text);
+        buf.encoding = encoding;
+        Ok(buf)
+    }
+
+    /// Create a buffer for `path` from contents that were already read,
+    /// e.g. by a [`crate::loader::FileLoader`].
+    pub fn from_loaded(id: BufferId, path: &Path, text: &str) -> Self {
+        let mut buf = Self::from_text(id, text);
+        buf.path = Some(path.to_path_buf());
+        buf
     }
 
     /// Open a file if it exists, or create an empty buffer with the
@@ -108,26 +139,42 @@ impl Buffer {
                 path: Some(path.to_path_buf()),
                 dirty: false,
                 line_ending: LineEnding::default(),
+                encoding: TextEncoding::default(),
                 undo_tree: UndoTree::new(),
                 cursors: CursorSet::new(Cursor::new(Position::default())),
                 search: SearchState::new(),
+                read_only: false,
+                edits_since_compact: 0,
+                last_edit: None,
             })
         }
     }
 
     /// Save buffer contents to the associated file path.
     pub fn save(&mut self) -> Result<(), EditError> {
+        self.save_with(WriteMode::default())
+    }
+
+    /// Save buffer contents to the associated file path, written as `mode`
+    /// says.
+    pub fn save_with(&mut self, mode: WriteMode) -> Result<(), EditError> {
         let path = self
             .path
             .clone()
             .ok_or_else(|| EditError::FileNotFound(PathBuf::from("<no path>")))?;
-        self.save_as(&path)
+        self.save_as_with(&path, mode)
     }
 
-    /// Save buffer contents to a specific path.
+    /// Save buffer contents to a specific path, in the buffer's encoding.
     pub fn save_as(&mut self, path: &Path) -> Result<(), EditError> {
+        self.save_as_with(path, WriteMode::default())
+    }
+
+    /// Save buffer contents to a specific path, written as `mode` says.
+    pub fn save_as_with(&mut self, path: &Path, mode: WriteMode) -> Result<(), EditError> {
         let text = self.rope.to_string();
-        std::fs::write(path, &text)?;
+        let bytes = self.encoding.encode(&text)?;
+        write_file(path, mode, |out| out.write_all(&bytes))?;
         self.path = Some(path.to_path_buf());
         self.dirty = false;
         Ok(())
@@ -172,11 +219,50 @@ impl Buffer {
         self.dirty
     }
 
+    /// Record that `text` was written to disk by a background save.
+    /// The buffer only becomes clean if it still holds exactly `text`;
+    /// returns whether it did.
+    pub fn mark_saved(&mut self, text: &Rope) -> bool {
+        if self.rope == *text {
+            self.dirty = false;
+        }
+        !self.dirty
+    }
+
+    /// Take on `text`, just read back from the file after another program
+    /// changed it. Only the lines that differ are replaced, as one edit
+    /// that can be undone, so cursors elsewhere stay put; the buffer ends
+    /// up clean. Works on read-only buffers too.
+    pub fn reload(&mut self, text: &str) -> Result<Vec<EditEvent>, EditError> {
+        let Some(change) = crate::diff::line_change(&self.rope, &Rope::from_str(text)) else {
+            self.dirty = false;
+            return Ok(Vec::new());
+        };
+        let read_only = std::mem::replace(&mut self.read_only, false);
+        // Not an edit made here, so not one to jump back to.
+        let last_edit = self.last_edit;
+        let result = self.apply_edit(EditCommand::Replace {
+            range: change.range,
+            text: change.text,
+        });
+        self.read_only = read_only;
+        self.last_edit = last_edit;
+        self.line_ending = detect_line_ending(text);
+        self.dirty = false;
+        result
+    }
+
     /// The file path associated with this buffer, if any.
     pub fn path(&self) -> Option<&Path> {
         self.path.as_deref()
     }
 
+    /// Point the buffer at `path` without writing anything, e.g. after
+    /// its file was renamed on disk.
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+
     /// The buffer's unique ID.
     pub fn id(&self) -> BufferId {
         self.id
@@ -187,6 +273,38 @@ impl Buffer {
         self.line_ending
     }
 
+    /// Rewrite every line break in the buffer as `ending`, as one edit
+    /// that can be undone, and use `ending` from now on.
+    pub fn convert_line_endings(
+        &mut self,
+        ending: LineEnding,
+    ) -> Result<Vec<EditEvent>, EditError> {
+        if self.read_only {
+            return Err(EditError::ReadOnly);
+        }
+        let text = self.rope.to_string();
+        let converted = ending.normalize(&text);
+        let events = match crate::diff::line_change(&self.rope, &Rope::from_str(&converted)) {
+            Some(change) => self.apply_edit(EditCommand::Replace {
+                range: change.range,
+                text: change.text,
+            })?,
+            None => Vec::new(),
+        };
+        self.line_ending = ending;
+        Ok(events)
+    }
+
+    /// The encoding the file is read and written in.
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    /// Read and write the file in `encoding` from now on.
+    pub fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+    }
+
     /// Reference to the cursor set.
     pub fn cursors(&self) -> &CursorSet {
         &self.cursors
@@ -218,21 +336,78 @@ impl Buffer {
             return Position::new(0, 0);
         }
         let line = pos.line.min(line_count - 1);
-        let line_chars = self.rope.line(line).len_chars();
+        let slice = self.rope.line(line);
         // For non-last lines the trailing '\n' (or '\r\n') is included in
         // len_chars(); the maximum valid cursor column is just before the
-        // newline.  For the very last line there is no trailing newline so
-        // the max column equals len_chars().
-        let max_col = if line + 1 < line_count {
-            line_chars.saturating_sub(1)
-        } else {
-            line_chars
-        };
+        // line break.  The very last line has none, so the max column
+        // equals len_chars().
+        let max_col = slice.len_chars() - line_break_len(slice);
         Position::new(line, pos.col.min(max_col))
     }
 
+    /// Move the primary cursor to `line` (clamped to the buffer), in its
+    /// goal column or at the end of the line if that is shorter.
+    pub fn move_primary_to_line(&mut self, line: usize) {
+        let mut cursor = self.cursors.primary().clone();
+        self.move_cursor_to_line(&mut cursor, line);
+        *self.cursors.primary_mut() = cursor;
+    }
+
+    /// Move `cursor` to `line` (clamped to the buffer) the way moving up or
+    /// down does: to the grapheme at its goal display column, or the end
+    /// of the line if that is narrower.
+    pub fn move_cursor_to_line(&self, cursor: &mut Cursor, line: usize) {
+        let goal = cursor
+            .goal_col()
+            .unwrap_or_else(|| self.display_col(cursor.position()));
+        let line = self.clamp_position(Position::new(line, 0)).line;
+        let pos = Position::new(line, self.col_at_display(line, goal));
+        let goal = Some(goal).filter(|&goal| goal != self.display_col(pos));
+        cursor.set_vertical_position(pos, goal);
+    }
+
+    /// The text of `line` without its line break.
+    fn line_text(&self, line: usize) -> String {
+        let text = self.line(line).map(String::from).unwrap_or_default();
+        text.trim_end_matches(['\n', '\r']).to_string()
+    }
+
+    /// The position one grapheme after `pos`, staying on its line.
+    pub fn next_grapheme(&self, pos: Position) -> Position {
+        let col = grapheme::next_boundary(&self.line_text(pos.line), pos.col);
+        self.clamp_position(Position::new(pos.line, col))
+    }
+
+    /// The position one grapheme before `pos`, staying on its line.
+    pub fn prev_grapheme(&self, pos: Position) -> Position {
+        Position::new(
+            pos.line,
+            grapheme::prev_boundary(&self.line_text(pos.line), pos.col),
+        )
+    }
+
+    /// The screen column `pos` is drawn at, counting wide characters as
+    /// two cells.
+    pub fn display_col(&self, pos: Position) -> usize {
+        grapheme::display_col(&self.line_text(pos.line), pos.col)
+    }
+
+    /// The char column of the grapheme on `line` drawn at screen column
+    /// `x`, or the end of the line if it is narrower.
+    pub fn col_at_display(&self, line: usize, x: usize) -> usize {
+        grapheme::col_at_display(&self.line_text(line), x)
+    }
+
+    /// Return the text covered by `range`.
+    pub fn text_in_range(&self, range: Range) -> Result<String, EditError> {
+        let start_idx = self.position_to_char_idx(range.start)?;
+        let end_idx = self.position_to_char_idx(range.end)?;
+        Ok(self.rope.slice(start_idx..end_idx).into())
+    }
+
     /// Apply an edit command and return the resulting edit events.
     pub fn apply_edit(&mut self, cmd: EditCommand) -> Result<Vec<EditEvent>, EditError> {
+        self.check_writable()?;
         let cursor_before = self.cursors.primary().position();
         let (events, inverse) = self.apply_edit_inner(&cmd)?;
         self.undo_tree.record(inverse, cmd, cursor_before);
@@ -240,8 +415,20 @@ impl Buffer {
         Ok(events)
     }
 
+    /// Start an undo group: edits until the matching
+    /// [`Buffer::end_undo_group`] undo and redo as one step.
+    pub fn begin_undo_group(&mut self) {
+        self.undo_tree.begin_group();
+    }
+
+    /// Close the undo group opened by [`Buffer::begin_undo_group`].
+    pub fn end_undo_group(&mut self) {
+        self.undo_tree.end_group();
+    }
+
     /// Undo the last edit.
     pub fn undo(&mut self) -> Result<Option<Vec<EditEvent>>, EditError> {
+        self.check_writable()?;
         match self.undo_tree.undo() {
             Some((inverse_cmd, cursor_pos)) => {
                 let (events, re_inverse) = self.apply_edit_inner(&inverse_cmd)?;
@@ -257,6 +444,7 @@ impl Buffer {
 
     /// Redo the last undone edit.
     pub fn redo(&mut self) -> Result<Option<Vec<EditEvent>>, EditError> {
+        self.check_writable()?;
         match self.undo_tree.redo() {
             Some((forward_cmd, _cursor_pos)) => {
                 // The forward op is the original edit; apply it.
@@ -267,6 +455,76 @@ impl Buffer {
         }
     }
 
+    /// Whether edits are rejected with [`EditError::ReadOnly`].
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Mark the buffer read-only (or writable again).
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    fn check_writable(&self) -> Result<(), EditError> {
+        if self.read_only {
+            Err(EditError::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the limits on this buffer's undo history, pruning if needed.
+    pub fn set_undo_budget(&mut self, budget: UndoBudget) {
+        self.undo_tree.set_budget(budget);
+    }
+
+    /// Discard all undo/redo history. The buffer content is unchanged.
+    pub fn clear_undo_history(&mut self) {
+        self.undo_tree.clear();
+    }
+
+    /// Where the most recent edit, undo or redo ended, for jumping back
+    /// to it. Later edits above it are not accounted for.
+    pub fn last_edit(&self) -> Option<Position> {
+        self.last_edit
+    }
+
+    /// Number of recorded undo entries.
+    pub fn undo_len(&self) -> usize {
+        self.undo_tree.len() - 1
+    }
+
+    /// Approximate memory held by the undo history, in bytes.
+    pub fn undo_memory_usage(&self) -> usize {
+        self.undo_tree.memory_usage()
+    }
+
+    /// Memory held by the text, in bytes. The rope stores text in
+    /// fixed-size chunks, so edits leave this above [`Buffer::len_bytes`].
+    pub fn text_memory_usage(&self) -> usize {
+        self.rope.capacity()
+    }
+
+    /// Approximate memory held by cursors and search matches, in bytes.
+    pub fn marker_memory_usage(&self) -> usize {
+        self.cursors.len() * std::mem::size_of::<Cursor>()
+            + self.search.match_count() * std::mem::size_of::<SearchMatch>()
+    }
+
+    /// Edits, undos and redos since the text was last compacted.
+    pub fn edits_since_compact(&self) -> usize {
+        self.edits_since_compact
+    }
+
+    /// Repack the text into as few chunks as possible, releasing the
+    /// slack that many small edits leave behind. Returns the bytes freed.
+    pub fn compact(&mut self) -> usize {
+        let before = self.rope.capacity();
+        self.rope.shrink_to_fit();
+        self.edits_since_compact = 0;
+        before.saturating_sub(self.rope.capacity())
+    }
+
     /// Convert a (line, col) position to a char index in the rope.
     fn position_to_char_idx(&self, pos: Position) -> Result<usize, EditError> {
         let line_count = self.rope.len_lines();
@@ -292,7 +550,7 @@ impl Buffer {
             .map(|c| (c.position(), c.selection_range()))
             .collect();
         // Sort in reverse order by position so edits don't invalidate each other
-        cursor_data.sort_by(|a, b| b.0.cmp(&a.0));
+        cursor_data.sort_by_key(|c| std::cmp::Reverse(c.0));
 
         let mut cmds = Vec::new();
         for (pos, sel) in &cursor_data {
@@ -326,7 +584,9 @@ impl Buffer {
         let mut col_delta: isize = 0;
         let mut prev_line: Option<usize> = None;
 
-        let text_lines: Vec<&str> = text.split('\n').collect();
+        // Line breaks of any style each end one line.
+        let lf_text = LineEnding::Lf.normalize(text);
+        let text_lines: Vec<&str> = lf_text.split('\n').collect();
         let text_line_count = text_lines.len();
         let text_last_line_len = text_lines.last().map_or(0, |l| l.chars().count());
 
@@ -383,6 +643,91 @@ impl Buffer {
         Ok(events)
     }
 
+    /// Delete at every cursor as one undoable edit: each cursor's selection
+    /// if it has one, otherwise the grapheme after it (`forward`) or
+    /// before it, joining lines at a line boundary. Overlapping deletions
+    /// are merged, and each cursor ends up where its text was removed.
+    pub fn delete_at_cursors(&mut self, forward: bool) -> Result<Vec<EditEvent>, EditError> {
+        self.check_writable()?;
+        let len = self.rope.len_chars();
+        let mut spans = Vec::with_capacity(self.cursors.len());
+        for cursor in self.cursors.iter() {
+            let span = match cursor.selection_range().filter(|r| !r.is_empty()) {
+                Some(range) => (
+                    self.position_to_char_idx(range.start)?,
+                    self.position_to_char_idx(range.end)?,
+                ),
+                None => {
+                    let pos = cursor.position();
+                    let at = self.position_to_char_idx(pos)?;
+                    // A whole grapheme within the line, else the whole line
+                    // break, so `\r\n` goes as one.
+                    if forward {
+                        let next = self.next_grapheme(pos);
+                        let end = if next == pos {
+                            at + line_break_len(self.rope.line(pos.line))
+                        } else {
+                            self.position_to_char_idx(next)?
+                        };
+                        (at, end.min(len))
+                    } else if pos.col == 0 {
+                        let break_len = match pos.line.checked_sub(1) {
+                            Some(prev) => line_break_len(self.rope.line(prev)),
+                            None => 0,
+                        };
+                        (at - break_len, at)
+                    } else {
+                        (self.position_to_char_idx(self.prev_grapheme(pos))?, at)
+                    }
+                }
+            };
+            spans.push(span);
+        }
+        spans.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+        for (start, end) in spans {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        // Back to front, so each range is still valid when it is deleted.
+        let mut cmds: Vec<EditCommand> = merged
+            .iter()
+            .rev()
+            .filter(|(start, end)| start < end)
+            .map(|&(start, end)| EditCommand::Delete {
+                range: Range::new(
+                    self.char_idx_to_position(start),
+                    self.char_idx_to_position(end),
+                ),
+            })
+            .collect();
+        if cmds.is_empty() {
+            return Ok(Vec::new());
+        }
+        // A lone delete stays unbatched so keystrokes can coalesce in undo.
+        let cmd = if cmds.len() == 1 {
+            cmds.remove(0)
+        } else {
+            EditCommand::Batch(cmds)
+        };
+        let events = self.apply_edit(cmd)?;
+
+        let mut removed = 0;
+        let mut carets = Vec::with_capacity(merged.len());
+        for (start, end) in merged {
+            carets.push(start - removed);
+            removed += end - start;
+        }
+        self.cursors = carets
+            .into_iter()
+            .map(|idx| Cursor::new(self.char_idx_to_position(idx)))
+            .collect();
+        Ok(events)
+    }
+
     /// Convert a char index back to a Position.
     fn char_idx_to_position(&self, char_idx: usize) -> Position {
         let line = self.rope.char_to_line(char_idx);
@@ -397,6 +742,18 @@ impl Buffer {
         &mut self,
         cmd: &EditCommand,
     ) -> Result<(Vec<EditEvent>, EditCommand), EditError> {
+        let (events, inverse) = self.apply_command(cmd)?;
+        if let Some(event) = events.last() {
+            self.last_edit = Some(event.new_end_position);
+        }
+        Ok((events, inverse))
+    }
+
+    fn apply_command(
+        &mut self,
+        cmd: &EditCommand,
+    ) -> Result<(Vec<EditEvent>, EditCommand), EditError> {
+        self.edits_since_compact += 1;
         match cmd {
             EditCommand::Insert { pos, text } => {
                 let char_idx = self.position_to_char_idx(*pos)?;
@@ -416,6 +773,7 @@ impl Buffer {
                     start_position: *pos,
                     old_end_position: *pos,
                     new_end_position: new_end_pos,
+                    new_text: text.clone(),
                 };
 
                 let inverse = EditCommand::Delete {
@@ -445,6 +803,7 @@ impl Buffer {
                     start_position: range.start,
                     old_end_position: range.end,
                     new_end_position: range.start,
+                    new_text: String::new(),
                 };
 
                 let inverse = EditCommand::Insert {
@@ -478,6 +837,7 @@ impl Buffer {
                     start_position: range.start,
                     old_end_position: range.end,
                     new_end_position: new_end_pos,
+                    new_text: text.clone(),
                 };
 
                 let new_range = Range::new(range.start, new_end_pos);
@@ -570,6 +930,85 @@ impl Buffer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn delete_at_cursors_removes_selections_and_characters_in_one_undo() {
+        let mut buf = Buffer::from_text(BufferId(1), "abc\ndef\nghi\n");
+        buf.cursors = [
+            Cursor::with_selection(Position::new(0, 3), Position::new(0, 1)),
+            Cursor::new(Position::new(1, 0)),
+            Cursor::new(Position::new(2, 2)),
+        ]
+        .into_iter()
+        .collect();
+        buf.delete_at_cursors(false).unwrap();
+        assert_eq!(buf.text().to_string(), "adef\ngi\n");
+        let carets: Vec<Position> = buf.cursors().iter().map(|c| c.position()).collect();
+        // The selection and the joined line meet at the same spot.
+        assert_eq!(carets, vec![Position::new(0, 1), Position::new(1, 1)]);
+
+        buf.undo().unwrap();
+        assert_eq!(buf.text().to_string(), "abc\ndef\nghi\n");
+
+        buf.cursors = [Position::new(0, 3), Position::new(2, 3)]
+            .into_iter()
+            .map(Cursor::new)
+            .collect();
+        buf.delete_at_cursors(true).unwrap();
+        assert_eq!(buf.text().to_string(), "abcdef\nghi");
+
+        // Nothing before the start of the buffer to delete.
+        let mut buf = Buffer::from_text(BufferId(1), "x");
+        assert!(buf.delete_at_cursors(false).unwrap().is_empty());
+        assert!(!buf.is_dirty());
+    }
+
+    #[test]
+    fn delete_at_cursors_joins_crlf_lines_whole() {
+        let mut buf = Buffer::from_text(BufferId(1), "ab\r\ncd");
+        buf.cursors = [Cursor::new(Position::new(1, 0))].into_iter().collect();
+        buf.delete_at_cursors(false).unwrap();
+        assert_eq!(buf.text().to_string(), "abcd");
+        assert_eq!(buf.cursors().primary().position(), Position::new(0, 2));
+
+        let mut buf = Buffer::from_text(BufferId(1), "ab\r\ncd");
+        buf.cursors = [Cursor::new(Position::new(0, 2))].into_iter().collect();
+        buf.delete_at_cursors(true).unwrap();
+        assert_eq!(buf.text().to_string(), "abcd");
+    }
+
+    #[test]
+    fn delete_at_cursors_removes_whole_graphemes() {
+        let mut buf = Buffer::from_text(BufferId(1), "ae\u{301}b👍🏽\n");
+        buf.cursors = [Position::new(0, 3), Position::new(0, 6)]
+            .into_iter()
+            .map(Cursor::new)
+            .collect();
+        buf.delete_at_cursors(false).unwrap();
+        assert_eq!(buf.text().to_string(), "ab\n");
+
+        buf.cursors = [Cursor::new(Position::new(0, 0))].into_iter().collect();
+        buf.delete_at_cursors(true).unwrap();
+        assert_eq!(buf.text().to_string(), "b\n");
+    }
+
+    #[test]
+    fn vertical_moves_keep_the_display_column_across_wide_characters() {
+        let mut buf = Buffer::from_text(BufferId(1), "abcdef\n字字字\nab\n");
+        buf.cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(0, 4));
+        buf.move_primary_to_line(1);
+        assert_eq!(buf.cursors().primary().position(), Position::new(1, 2));
+        buf.move_primary_to_line(2);
+        assert_eq!(buf.cursors().primary().position(), Position::new(2, 2));
+        buf.move_primary_to_line(0);
+        assert_eq!(buf.cursors().primary().position(), Position::new(0, 4));
+
+        assert_eq!(buf.display_col(Position::new(1, 2)), 4);
+        assert_eq!(buf.col_at_display(1, 3), 1);
+        assert_eq!(buf.next_grapheme(Position::new(1, 3)), Position::new(1, 3));
+    }
+
     #[test]
     fn new_empty_buffer() {
         let buf = Buffer::new(BufferId(1));
@@ -721,6 +1160,56 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn clear_undo_history_keeps_text() {
+        let mut buf = Buffer::from_text(BufferId(27), "ab");
+        buf.apply_edit(EditCommand::Insert {
+            pos: Position::new(0, 1),
+            text: "X".to_string(),
+        })
+        .unwrap();
+        assert_eq!(buf.undo_len(), 1);
+        assert!(buf.undo_memory_usage() > 0);
+
+        buf.clear_undo_history();
+        assert_eq!(buf.undo_len(), 0);
+        assert_eq!(buf.undo_memory_usage(), 0);
+        assert!(buf.undo().unwrap().is_none());
+        assert_eq!(buf.text().to_string(), "aXb");
+    }
+
+    #[test]
+    fn undo_budget_limits_entries() {
+        let mut buf = Buffer::from_text(BufferId(28), "");
+        buf.set_undo_budget(UndoBudget {
+            max_entries: 2,
+            max_bytes: usize::MAX,
+        });
+        for _ in 0..5 {
+            buf.apply_edit(EditCommand::Insert {
+                pos: Position::new(0, 0),
+                text: "x".to_string(),
+            })
+            .unwrap();
+        }
+        assert_eq!(buf.undo_len(), 2);
+    }
+
+    #[test]
+    fn read_only_buffer_rejects_edits() {
+        let mut buf = Buffer::from_text(BufferId(29), "ab");
+        buf.set_read_only(true);
+        assert!(buf.is_read_only());
+        let result = buf.apply_edit(EditCommand::Insert {
+            pos: Position::new(0, 0),
+            text: "X".to_string(),
+        });
+        assert!(matches!(result, Err(EditError::ReadOnly)));
+        assert!(matches!(buf.undo(), Err(EditError::ReadOnly)));
+        assert_eq!(buf.text().to_string(), "ab");
+        assert!(!buf.is_dirty());
+    }
+
     #[test]
     fn dirty_flag_after_edit_and_save() {
         let dir = tempfile::tempdir().unwrap();
@@ -755,12 +1244,60 @@ mod tests {
         assert_eq!(buf.path(), Some(file_path.as_path()));
     }
 
+    #[test]
+    fn file_is_saved_back_in_its_own_encoding() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("latin1.txt");
+        std::fs::write(&file_path, b"caf\xe9\n").unwrap();
+
+        let mut buf = Buffer::from_file(BufferId::next(), &file_path).unwrap();
+        assert_eq!(buf.text().to_string(), "café\n");
+        assert_eq!(buf.encoding().to_string(), "Latin-1");
+
+        buf.apply_edit(EditCommand::Insert {
+            pos: Position::new(0, 4),
+            text: "s à gogo".to_string(),
+        })
+        .unwrap();
+        buf.save().unwrap();
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"caf\xe9s \xe0 gogo\n");
+
+        buf.apply_edit(EditCommand::Insert {
+            pos: Position::new(0, 0),
+            text: "字".to_string(),
+        })
+        .unwrap();
+        assert!(matches!(buf.save(), Err(EditError::Encoding(_))));
+        assert!(buf.is_dirty());
+    }
+
     #[test]
     fn from_file_not_found() {
         let result = Buffer::from_file(BufferId(17), Path::new("/nonexistent"));
         assert!(result.is_err());
     }
 
+    #[test]
+    fn mark_saved_ignores_stale_snapshots() {
+        let mut buf = Buffer::from_text(BufferId(30), "abc");
+        buf.apply_edit(EditCommand::Insert {
+            pos: Position::new(0, 3),
+            text: "d".into(),
+        })
+        .unwrap();
+        let snapshot = buf.text().clone();
+        buf.apply_edit(EditCommand::Insert {
+            pos: Position::new(0, 4),
+            text: "e".into(),
+        })
+        .unwrap();
+        assert!(!buf.mark_saved(&snapshot));
+        assert!(buf.is_dirty());
+        let current = buf.text().clone();
+        assert!(buf.mark_saved(&current));
+        assert!(!buf.is_dirty());
+    }
+
     #[test]
     fn save_as_new_path() {
         let dir = tempfile::tempdir().unwrap();
@@ -855,6 +1392,27 @@ mod tests {
         assert_eq!(e.start_byte, 1);
         assert_eq!(e.old_end_byte, 1); // insert: old end == start
         assert_eq!(e.new_end_byte, 3); // 1 + 2 bytes for "XY"
+        assert_eq!(e.new_text, "XY");
+    }
+
+    #[test]
+    fn edit_event_carries_replacement_text() {
+        let mut buf = Buffer::from_text(BufferId(23), "abc");
+        let events = buf
+            .apply_edit(EditCommand::Replace {
+                range: Range::new(Position::new(0, 0), Position::new(0, 2)),
+                text: "Z".to_string(),
+            })
+            .unwrap();
+        assert_eq!(events[0].new_text, "Z");
+        assert_eq!(events[0].old_end_position, Position::new(0, 2));
+
+        let events = buf
+            .apply_edit(EditCommand::Delete {
+                range: Range::new(Position::new(0, 0), Position::new(0, 1)),
+            })
+            .unwrap();
+        assert!(events[0].new_text.is_empty());
     }
 
     #[test]
@@ -1011,6 +1569,44 @@ mod tests {
         assert_eq!(buf.text().to_string(), "Xaaa\nXbbb\nXccc");
     }
 
+    #[test]
+    fn multi_cursor_edit_places_cursors_after_crlf_text() {
+        let mut buf = Buffer::from_text(BufferId::next(), "ab\r\ncd");
+        buf.cursors = CursorSet::new(Cursor::new(Position::new(0, 1)));
+        buf.cursors_mut().add(Cursor::new(Position::new(1, 1)));
+
+        buf.apply_multi_cursor_edit("1\r\n2").unwrap();
+        assert_eq!(buf.text().to_string(), "a1\r\n2b\r\nc1\r\n2d");
+        let positions: Vec<Position> = buf.cursors().iter().map(|c| c.position()).collect();
+        assert_eq!(positions, vec![Position::new(1, 1), Position::new(3, 1)]);
+    }
+
+    #[test]
+    fn clamp_position_stops_before_crlf() {
+        let buf = Buffer::from_text(BufferId::next(), "ab\r\ncd\re");
+        assert_eq!(buf.clamp_position(Position::new(0, 9)), Position::new(0, 2));
+        assert_eq!(buf.clamp_position(Position::new(1, 9)), Position::new(1, 2));
+        assert_eq!(buf.clamp_position(Position::new(2, 9)), Position::new(2, 1));
+    }
+
+    #[test]
+    fn convert_line_endings_rewrites_the_text_as_one_edit() {
+        let mut buf = Buffer::from_text(BufferId::next(), "a\r\nb\nc\r\n");
+        assert_eq!(buf.line_ending(), LineEnding::CrLf);
+        buf.convert_line_endings(LineEnding::Lf).unwrap();
+        assert_eq!(buf.text().to_string(), "a\nb\nc\n");
+        assert_eq!(buf.line_ending(), LineEnding::Lf);
+        assert!(buf.is_dirty());
+        buf.undo().unwrap();
+        assert_eq!(buf.text().to_string(), "a\r\nb\nc\r\n");
+
+        buf.set_read_only(true);
+        assert!(matches!(
+            buf.convert_line_endings(LineEnding::Cr),
+            Err(EditError::ReadOnly)
+        ));
+    }
+
     #[test]
     fn clamp_position_col_within_line_unchanged() {
         // Line 0: "hello\n" (5 visible chars), Line 1: "hi" (2 chars)
@@ -1056,4 +1652,102 @@ mod tests {
         let clamped = buf.clamp_position(pos);
         assert_eq!(clamped, Position::new(0, 5));
     }
+
+    #[test]
+    fn text_in_range_single_line() {
+        let buf = Buffer::from_text(BufferId::next(), "hello world");
+        let range = Range::new(Position::new(0, 6), Position::new(0, 11));
+        assert_eq!(buf.text_in_range(range).unwrap(), "world");
+    }
+
+    #[test]
+    fn text_in_range_spans_lines() {
+        let buf = Buffer::from_text(BufferId::next(), "abc\ndef\nghi");
+        let range = Range::new(Position::new(0, 1), Position::new(2, 1));
+        assert_eq!(buf.text_in_range(range).unwrap(), "bc\ndef\ng");
+    }
+
+    #[test]
+    fn text_in_range_out_of_bounds_errors() {
+        let buf = Buffer::from_text(BufferId::next(), "abc");
+        let range = Range::new(Position::new(0, 0), Position::new(4, 0));
+        assert!(buf.text_in_range(range).is_err());
+    }
+
+    #[test]
+    fn reload_replaces_changed_lines_as_one_undo_step() {
+        let mut buf = Buffer::from_text(BufferId(41), "one\ntwo\nthree\n");
+        buf.cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(2, 3));
+        buf.apply_edit(EditCommand::Insert {
+            pos: Position::new(0, 0),
+            text: "!".to_string(),
+        })
+        .unwrap();
+        buf.set_read_only(true);
+
+        let events = buf.reload("one\n2\nthree\n").unwrap();
+        assert!(!events.is_empty());
+        assert_eq!(buf.text().to_string(), "one\n2\nthree\n");
+        assert!(!buf.is_dirty());
+        assert!(buf.is_read_only());
+        assert_eq!(buf.cursors().primary().position(), Position::new(2, 3));
+
+        assert!(buf.reload("one\n2\nthree\n").unwrap().is_empty());
+        buf.set_read_only(false);
+        buf.undo().unwrap();
+        assert_eq!(buf.text().to_string(), "!one\ntwo\nthree\n");
+    }
+
+    #[test]
+    fn last_edit_follows_edits_undo_and_redo_but_not_reloads() {
+        let mut buf = Buffer::from_text(BufferId(42), "one\ntwo\n");
+        assert_eq!(buf.last_edit(), None);
+        buf.apply_edit(EditCommand::Insert {
+            pos: Position::new(1, 3),
+            text: "!!".to_string(),
+        })
+        .unwrap();
+        assert_eq!(buf.last_edit(), Some(Position::new(1, 5)));
+
+        buf.reload("ONE\ntwo!!\n").unwrap();
+        assert_eq!(buf.last_edit(), Some(Position::new(1, 5)));
+
+        buf.undo().unwrap();
+        buf.undo().unwrap();
+        assert_eq!(buf.last_edit(), Some(Position::new(1, 3)));
+        buf.redo().unwrap();
+        assert_eq!(buf.last_edit(), Some(Position::new(1, 5)));
+    }
+
+    #[test]
+    fn compact_releases_slack_left_by_edits() {
+        let line = "x".repeat(99);
+        let text = format!("{}\n", line).repeat(2000);
+        let mut buf = Buffer::from_text(BufferId(42), &text);
+        let packed = buf.text_memory_usage();
+        assert!(packed >= buf.len_bytes());
+        // Inserting into full chunks splits them.
+        for i in (0..2000).step_by(7) {
+            buf.apply_edit(EditCommand::Insert {
+                pos: Position::new(i, 50),
+                text: "y".to_string(),
+            })
+            .unwrap();
+        }
+        assert_eq!(buf.edits_since_compact(), 286);
+        let fragmented = buf.text_memory_usage();
+        assert!(fragmented > packed);
+
+        let edited = buf.text().to_string();
+        let freed = buf.compact();
+        assert_eq!(freed, fragmented - buf.text_memory_usage());
+        assert!(freed > 0);
+        assert_eq!(buf.text().to_string(), edited);
+        assert_eq!(buf.edits_since_compact(), 0);
+        // Undo still works on the repacked text.
+        buf.undo().unwrap();
+        assert_eq!(buf.edits_since_compact(), 1);
+    }
 }
@@ -0,0 +1,99 @@
+//! Companion-file patterns pairing a source file with its test, such as
+//! `src/foo.rs` ↔ `tests/foo.rs` or `foo.py` ↔ `test_foo.py`.
+//!
+//! Patterns are `/`-separated paths relative to the workspace with two
+//! placeholders:
+//! - `{dir}` matches a directory prefix ending in `/`, possibly empty
+//! - `{name}` matches a run of characters without `/`
+//!
+//! Each placeholder may appear at most once in a pattern.
+
+use regex::Regex;
+
+/// Build a regex matching the whole of `pattern`.
+fn pattern_regex(pattern: &str) -> Option<Regex> {
+    let mut re = String::from("^");
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        re.push_str(&regex::escape(&rest[..open]));
+        let after = &rest[open..];
+        if let Some(tail) = after.strip_prefix("{dir}") {
+            re.push_str("(?P<dir>(?:[^/]+/)*)");
+            rest = tail;
+        } else if let Some(tail) = after.strip_prefix("{name}") {
+            re.push_str("(?P<name>[^/]+)");
+            rest = tail;
+        } else {
+            re.push_str(r"\{");
+            rest = &after[1..];
+        }
+    }
+    re.push_str(&regex::escape(rest));
+    re.push('$');
+    Regex::new(&re).ok()
+}
+
+/// Replace `{dir}` and `{name}` in `template`.
+pub fn fill(template: &str, dir: &str, name: &str) -> String {
+    template.replace("{dir}", dir).replace("{name}", name)
+}
+
+/// The `{dir}` and `{name}` captured by matching `path` against
+/// `pattern`, or `None` if it does not match.
+pub fn capture(pattern: &str, path: &str) -> Option<(String, String)> {
+    let caps = pattern_regex(pattern)?.captures(path)?;
+    let group = |name| caps.name(name).map_or("", |m| m.as_str()).to_string();
+    Some((group("dir"), group("name")))
+}
+
+/// Map `path`, matching the pattern `from`, onto the pattern `to`.
+pub fn map_path(from: &str, to: &str, path: &str) -> Option<String> {
+    let (dir, name) = capture(from, path)?;
+    Some(fill(to, &dir, &name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_sources_to_tests_and_back() {
+        assert_eq!(
+            map_path(
+                "src/{dir}{name}.rs",
+                "tests/{dir}{name}.rs",
+                "src/net/http.rs"
+            ),
+            Some("tests/net/http.rs".to_string())
+        );
+        assert_eq!(
+            map_path("{dir}test_{name}.py", "{dir}{name}.py", "pkg/test_util.py"),
+            Some("pkg/util.py".to_string())
+        );
+        assert_eq!(
+            map_path("{dir}{name}.py", "{dir}test_{name}.py", "util.py"),
+            Some("test_util.py".to_string())
+        );
+        assert_eq!(
+            map_path("src/{dir}{name}.rs", "tests/{dir}{name}.rs", "benches/a.rs"),
+            None
+        );
+    }
+
+    #[test]
+    fn literal_text_is_not_a_pattern() {
+        assert_eq!(
+            map_path("{dir}{name}.c++", "{dir}{name}_test.c++", "a/b.c++"),
+            Some("a/b_test.c++".to_string())
+        );
+        assert!(capture("{dir}{name}.rs", "a/bcrs").is_none());
+        assert_eq!(
+            capture("{other}/{name}.rs", "{other}/x.rs"),
+            Some((String::new(), "x".to_string()))
+        );
+        assert_eq!(
+            fill("fn {name}_works() {}", "", "parse"),
+            "fn parse_works() {}"
+        );
+    }
+}
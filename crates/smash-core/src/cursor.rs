@@ -5,6 +5,9 @@ use crate::position::{Position, Range};
 pub struct Cursor {
     position: Position,
     anchor: Option<Position>,
+    /// Display column that vertical movement returns to once lines are
+    /// long enough again. `None` means the current column.
+    goal_col: Option<usize>,
 }
 
 impl Cursor {
@@ -13,6 +16,7 @@ impl Cursor {
         Self {
             position: pos,
             anchor: None,
+            goal_col: None,
         }
     }
 
@@ -21,6 +25,7 @@ impl Cursor {
         Self {
             position: pos,
             anchor: Some(anchor),
+            goal_col: None,
         }
     }
 
@@ -34,9 +39,29 @@ impl Cursor {
         self.anchor
     }
 
-    /// Set the cursor position.
+    /// Set the cursor position. The goal column becomes the new column.
     pub fn set_position(&mut self, pos: Position) {
         self.position = pos;
+        self.goal_col = None;
+    }
+
+    /// The display column vertical movement aims for: where the cursor
+    /// was before moving up or down through shorter lines. `None` while
+    /// that is the cursor's own column.
+    pub fn goal_col(&self) -> Option<usize> {
+        self.goal_col
+    }
+
+    /// Move to `pos` on another line, keeping `goal_col` as the display
+    /// column to return to on lines long enough for it.
+    pub fn set_vertical_position(&mut self, pos: Position, goal_col: Option<usize>) {
+        self.position = pos;
+        self.goal_col = goal_col;
+    }
+
+    /// Anchor a selection at `anchor`, keeping the cursor position.
+    pub fn set_anchor(&mut self, anchor: Position) {
+        self.anchor = Some(anchor);
     }
 
     /// Returns `true` if a selection is active.
@@ -111,7 +136,11 @@ impl CursorSet {
     /// `edit_pos..old_end` to `edit_pos..new_end`.
     pub fn remap_after_edit(&mut self, edit_pos: Position, old_end: Position, new_end: Position) {
         for cursor in &mut self.cursors {
-            cursor.position = remap_position(cursor.position, edit_pos, old_end, new_end);
+            let moved = remap_position(cursor.position, edit_pos, old_end, new_end);
+            if moved != cursor.position {
+                cursor.position = moved;
+                cursor.goal_col = None;
+            }
             if let Some(anchor) = cursor.anchor {
                 cursor.anchor = Some(remap_position(anchor, edit_pos, old_end, new_end));
             }
@@ -237,6 +266,23 @@ impl CursorSet {
     }
 }
 
+impl FromIterator<Cursor> for CursorSet {
+    /// Build a cursor set from an iterator of cursors.
+    ///
+    /// The result is sorted and merged like [`CursorSet::add`]. An empty
+    /// iterator yields a single cursor at the origin so the set is never
+    /// without a primary cursor.
+    fn from_iter<I: IntoIterator<Item = Cursor>>(iter: I) -> Self {
+        let mut cursors: Vec<Cursor> = iter.into_iter().collect();
+        if cursors.is_empty() {
+            cursors.push(Cursor::new(Position::default()));
+        }
+        let mut set = Self { cursors };
+        set.normalize();
+        set
+    }
+}
+
 /// Remap a position after an edit that replaced
 /// `edit_pos..old_end` with `edit_pos..new_end`.
 fn remap_position(
@@ -305,6 +351,18 @@ mod tests {
         assert_eq!(c.position(), Position::new(3, 0));
     }
 
+    #[test]
+    fn cursor_set_anchor_starts_selection() {
+        let mut c = Cursor::new(Position::new(2, 4));
+        c.set_anchor(Position::new(1, 0));
+        assert!(c.has_selection());
+        assert_eq!(c.position(), Position::new(2, 4));
+        assert_eq!(
+            c.selection_range().unwrap(),
+            Range::new(Position::new(1, 0), Position::new(2, 4))
+        );
+    }
+
     #[test]
     fn cursor_set_position() {
         let mut c = Cursor::new(Position::new(0, 0));
@@ -312,6 +370,18 @@ mod tests {
         assert_eq!(c.position(), Position::new(5, 3));
     }
 
+    #[test]
+    fn goal_col_survives_vertical_moves_only() {
+        let mut c = Cursor::new(Position::new(0, 8));
+        assert_eq!(c.goal_col(), None);
+        c.set_vertical_position(Position::new(1, 2), Some(8));
+        assert_eq!(c.goal_col(), Some(8));
+        c.set_vertical_position(Position::new(2, 0), c.goal_col());
+        assert_eq!(c.goal_col(), Some(8));
+        c.set_position(Position::new(2, 1));
+        assert_eq!(c.goal_col(), None);
+    }
+
     // --- CursorSet tests ---
 
     #[test]
@@ -385,6 +455,26 @@ mod tests {
         assert_eq!(cs.primary().position(), Position::new(10, 5));
     }
 
+    #[test]
+    fn cursor_set_from_iter_sorts_and_merges() {
+        let cs: CursorSet = vec![
+            Cursor::new(Position::new(4, 0)),
+            Cursor::new(Position::new(1, 0)),
+            Cursor::new(Position::new(4, 0)),
+        ]
+        .into_iter()
+        .collect();
+        let positions: Vec<Position> = cs.iter().map(|c| c.position()).collect();
+        assert_eq!(positions, vec![Position::new(1, 0), Position::new(4, 0)]);
+    }
+
+    #[test]
+    fn cursor_set_from_empty_iter_has_primary() {
+        let cs: CursorSet = std::iter::empty().collect();
+        assert_eq!(cs.len(), 1);
+        assert_eq!(cs.primary().position(), Position::new(0, 0));
+    }
+
     // --- Remap tests ---
 
     #[test]
@@ -0,0 +1,417 @@
+//! Differences between two versions of a text: the single changed block
+//! sent to a language server, and line diffs for the diff view.
+
+use ropey::Rope;
+
+use crate::position::{Position, Range};
+
+/// A single replacement that turns one text into another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineChange {
+    /// Range in the old text that was replaced.
+    pub range: Range,
+    /// Text that replaces it.
+    pub text: String,
+}
+
+impl LineChange {
+    /// The line in the new text that lines up with `line` of the old
+    /// text. Lines before the change keep their number, lines after it
+    /// shift by the change in length, and lines inside it map to the
+    /// matching line of the replacement, or its last line if that is
+    /// shorter.
+    pub fn map_line(&self, line: usize) -> usize {
+        let start = self.range.start.line;
+        let old_end = if self.range.end.col == 0 {
+            self.range.end.line
+        } else {
+            self.range.end.line + 1
+        };
+        let new_len = self.text.matches('\n').count()
+            + usize::from(!self.text.is_empty() && !self.text.ends_with('\n'));
+        if line < start {
+            line
+        } else if line >= old_end {
+            line - old_end + start + new_len
+        } else {
+            start + (line - start).min(new_len.saturating_sub(1))
+        }
+    }
+}
+
+/// Find the block of lines that differs between `old` and `new`.
+///
+/// Lines shared at the start and end are skipped, so the change covers
+/// whole lines from the first difference to the last. Returns `None` if
+/// the texts are equal.
+pub fn line_change(old: &Rope, new: &Rope) -> Option<LineChange> {
+    let old_lines = old.len_lines();
+    let new_lines = new.len_lines();
+    let shortest = old_lines.min(new_lines);
+
+    let prefix = (0..shortest)
+        .take_while(|&i| old.line(i) == new.line(i))
+        .count();
+    if prefix == old_lines && prefix == new_lines {
+        return None;
+    }
+    let suffix = (0..shortest - prefix)
+        .take_while(|&i| old.line(old_lines - 1 - i) == new.line(new_lines - 1 - i))
+        .count();
+
+    // Ropey's last line never has a line break, so a shared suffix always
+    // starts at the beginning of a line; without one the change runs to
+    // the end of the document.
+    let end = if suffix > 0 {
+        Position::new(old_lines - suffix, 0)
+    } else {
+        let last = old_lines - 1;
+        Position::new(last, old.line(last).len_chars())
+    };
+    let text_end = if suffix > 0 {
+        new.line_to_char(new_lines - suffix)
+    } else {
+        new.len_chars()
+    };
+    let text = new.slice(new.line_to_char(prefix)..text_end).to_string();
+    Some(LineChange {
+        range: Range::new(Position::new(prefix, 0), end),
+        text,
+    })
+}
+
+/// Edit distance past which [`diff_lines`] stops looking for the
+/// shortest diff and shows the differing middle as replaced wholesale.
+const MAX_EDIT_DISTANCE: usize = 2000;
+
+/// Whether a line of a diff is in both texts, only the old or only the
+/// new.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    Context,
+    Removed,
+    Added,
+}
+
+/// A line of a diff, with its line break if it has one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// A run of changed lines and the unchanged lines around them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// First line of the hunk in the old text, 0-based.
+    pub old_start: usize,
+    pub old_len: usize,
+    /// First line of the hunk in the new text, 0-based.
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+impl Hunk {
+    /// The `@@ -1,3 +1,4 @@` header of a unified diff, 1-based.
+    pub fn header(&self) -> String {
+        let start = |start: usize, len: usize| if len == 0 { start } else { start + 1 };
+        format!(
+            "@@ -{},{} +{},{} @@",
+            start(self.old_start, self.old_len),
+            self.old_len,
+            start(self.new_start, self.new_len),
+            self.new_len
+        )
+    }
+
+    /// The hunk's lines as they are in the old text, which put back in
+    /// place of its new lines undo the change.
+    pub fn old_text(&self) -> String {
+        self.lines
+            .iter()
+            .filter(|l| l.kind != DiffKind::Added)
+            .map(|l| l.text.as_str())
+            .collect()
+    }
+}
+
+/// Line by line differences between `old` and `new`, as the hunks of a
+/// unified diff with up to `context` unchanged lines around each change.
+/// Returns no hunks if the texts are equal.
+pub fn diff_lines(old: &str, new: &str, context: usize) -> Vec<Hunk> {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = edit_script(&a, &b);
+
+    // Each op with the old and new line it sits before.
+    let mut entries = Vec::with_capacity(ops.len());
+    let (mut i, mut j) = (0, 0);
+    for kind in ops {
+        let text = match kind {
+            DiffKind::Added => b[j],
+            _ => a[i],
+        };
+        entries.push((kind, i, j, text));
+        match kind {
+            DiffKind::Context => {
+                i += 1;
+                j += 1;
+            }
+            DiffKind::Removed => i += 1,
+            DiffKind::Added => j += 1,
+        }
+    }
+
+    // Spans of entries around the changes, merged where they touch.
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.0 == DiffKind::Context {
+            continue;
+        }
+        let start = index.saturating_sub(context);
+        let end = (index + 1 + context).min(entries.len());
+        match spans.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => spans.push((start, end)),
+        }
+    }
+
+    spans
+        .into_iter()
+        .map(|(start, end)| {
+            let run = &entries[start..end];
+            let count = |kind: DiffKind| run.iter().filter(|e| e.0 != kind).count();
+            Hunk {
+                old_start: run[0].1,
+                old_len: count(DiffKind::Added),
+                new_start: run[0].2,
+                new_len: count(DiffKind::Removed),
+                lines: run
+                    .iter()
+                    .map(|&(kind, _, _, text)| DiffLine {
+                        kind,
+                        text: text.to_string(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// The shortest sequence of kept, removed and added lines that turns `a`
+/// into `b` (Myers' algorithm). Removals come before additions in each
+/// changed run.
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<DiffKind> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let mid_a = &a[prefix..a.len() - suffix];
+    let mid_b = &b[prefix..b.len() - suffix];
+
+    let mut ops = vec![DiffKind::Context; prefix];
+    match shortest_middle(mid_a, mid_b) {
+        Some(middle) => ops.extend(middle),
+        None => {
+            ops.resize(ops.len() + mid_a.len(), DiffKind::Removed);
+            ops.resize(ops.len() + mid_b.len(), DiffKind::Added);
+        }
+    }
+    ops.resize(ops.len() + suffix, DiffKind::Context);
+    ops
+}
+
+/// Myers' shortest edit script, or `None` if it needs more than
+/// [`MAX_EDIT_DISTANCE`] edits.
+fn shortest_middle(a: &[&str], b: &[&str]) -> Option<Vec<DiffKind>> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = (a.len() + b.len()).min(MAX_EDIT_DISTANCE) as isize;
+    let offset = max + 1;
+    // v[offset + k]: furthest x reached on diagonal k.
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // trace[d]: v at the start of round d, for diagonals -d..=d.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut found = None;
+    'rounds: for d in 0..=max {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let at = |k: isize| v[(offset + k) as usize];
+            let mut x = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+                at(k + 1)
+            } else {
+                at(k - 1) + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x >= n && y >= m {
+                found = Some(d);
+                break 'rounds;
+            }
+        }
+    }
+    let rounds = found?;
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (0..=rounds).rev() {
+        if d == 0 {
+            ops.resize(ops.len() + x as usize, DiffKind::Context);
+            break;
+        }
+        let v = &trace[d as usize];
+        let at = |k: isize| v[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(DiffKind::Context);
+            x -= 1;
+            y -= 1;
+        }
+        ops.push(if x == prev_x {
+            DiffKind::Added
+        } else {
+            DiffKind::Removed
+        });
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    Some(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(old: &str, change: &LineChange) -> String {
+        let mut rope = Rope::from_str(old);
+        let start = rope.line_to_char(change.range.start.line) + change.range.start.col;
+        let end = rope.line_to_char(change.range.end.line) + change.range.end.col;
+        rope.remove(start..end);
+        rope.insert(start, &change.text);
+        rope.to_string()
+    }
+
+    #[test]
+    fn equal_texts_have_no_change() {
+        let text = Rope::from_str("a\nb\n");
+        assert_eq!(line_change(&text, &text.clone()), None);
+        assert_eq!(line_change(&Rope::new(), &Rope::new()), None);
+    }
+
+    #[test]
+    fn change_covers_only_differing_lines() {
+        let old = "one\ntwo\nthree\nfour\n";
+        let new = "one\n2\n3\nfour\n";
+        let change = line_change(&Rope::from_str(old), &Rope::from_str(new)).unwrap();
+        assert_eq!(
+            change.range,
+            Range::new(Position::new(1, 0), Position::new(3, 0))
+        );
+        assert_eq!(change.text, "2\n3\n");
+        assert_eq!(apply(old, &change), new);
+    }
+
+    #[test]
+    fn lines_map_across_the_change() {
+        let change =
+            |old: &str, new: &str| line_change(&Rope::from_str(old), &Rope::from_str(new)).unwrap();
+        // Two lines become three.
+        let grown = change("a\nb\nc\nd\n", "a\nx\ny\nz\nd\n");
+        let mapped: Vec<usize> = (0..5).map(|l| grown.map_line(l)).collect();
+        assert_eq!(mapped, vec![0, 1, 2, 4, 5]);
+        // A deleted line maps to where it was.
+        let shrunk = change("a\nb\nc\n", "a\nc\n");
+        let mapped: Vec<usize> = (0..4).map(|l| shrunk.map_line(l)).collect();
+        assert_eq!(mapped, vec![0, 1, 1, 2]);
+    }
+
+    /// Put each hunk's new lines back in place of its old ones.
+    fn patch(old: &str, hunks: &[Hunk]) -> String {
+        let lines: Vec<&str> = old.split_inclusive('\n').collect();
+        let mut out = String::new();
+        let mut next = 0;
+        for hunk in hunks {
+            out.extend(lines[next..hunk.old_start].iter().copied());
+            for line in hunk.lines.iter().filter(|l| l.kind != DiffKind::Removed) {
+                out.push_str(&line.text);
+            }
+            next = hunk.old_start + hunk.old_len;
+        }
+        out.extend(lines[next..].iter().copied());
+        out
+    }
+
+    #[test]
+    fn diff_lines_gives_unified_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let hunks = diff_lines(old, new, 1);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].header(), "@@ -1,3 +1,3 @@");
+        let kinds: Vec<DiffKind> = hunks[0].lines.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DiffKind::Context,
+                DiffKind::Removed,
+                DiffKind::Added,
+                DiffKind::Context
+            ]
+        );
+        assert_eq!(hunks[0].old_text(), "a\nb\nc\n");
+        assert_eq!(hunks[1].header(), "@@ -10,1 +10,2 @@");
+        assert_eq!(patch(old, &hunks), new);
+
+        // Close changes share a hunk.
+        assert_eq!(diff_lines(old, new, 4).len(), 1);
+        assert!(diff_lines(old, old, 3).is_empty());
+    }
+
+    #[test]
+    fn diff_lines_round_trips() {
+        for (old, new) in [
+            ("", "a\n"),
+            ("a\n", ""),
+            ("a\nb", "a\nb\n"),
+            ("x\ny\nz\n", "z\ny\nx\n"),
+            ("1\n2\n3\n4\n5\n", "0\n1\n3\n4\n4\n6\n"),
+        ] {
+            let hunks = diff_lines(old, new, 0);
+            assert_eq!(patch(old, &hunks), new, "{old:?} -> {new:?}");
+            let hunks = diff_lines(old, new, 2);
+            assert_eq!(patch(old, &hunks), new, "{old:?} -> {new:?}");
+        }
+    }
+
+    #[test]
+    fn insertions_deletions_and_document_end() {
+        for (old, new) in [
+            ("a\nb\n", "a\nx\nb\n"),
+            ("a\nx\nb\n", "a\nb\n"),
+            ("a\nb", "a\nbc"),
+            ("a\nb", "a\nb\n"),
+            ("", "hello"),
+            ("hello\n", ""),
+            ("x\nx\nx\n", "x\nx\n"),
+        ] {
+            let change = line_change(&Rope::from_str(old), &Rope::from_str(new)).unwrap();
+            assert_eq!(apply(old, &change), new, "{old:?} -> {new:?}");
+        }
+    }
+}
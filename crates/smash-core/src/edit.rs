@@ -46,6 +46,9 @@ pub struct EditEvent {
     pub start_position: Position,
     pub old_end_position: Position,
     pub new_end_position: Position,
+    /// Text inserted between `start_position` and `new_end_position`
+    /// (empty for pure deletions).
+    pub new_text: String,
 }
 
 #[cfg(test)]
@@ -161,6 +164,7 @@ mod tests {
             start_position: Position::new(0, 0),
             old_end_position: Position::new(0, 5),
             new_end_position: Position::new(0, 10),
+            new_text: "0123456789".to_string(),
         };
         // Ensure Debug is implemented
         let _ = format!("{evt:?}");
@@ -1,3 +1,11 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::path::Path;
+
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+use crate::error::EditError;
+
 /// Represents the line-ending convention used in a text buffer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
 pub enum LineEnding {
@@ -16,6 +24,51 @@ impl LineEnding {
             Self::Cr => "\r",
         }
     }
+
+    /// Short name for the status bar: `LF`, `CRLF` or `CR`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+            Self::Cr => "CR",
+        }
+    }
+
+    /// The line ending called `name`, in any case: `lf` (or `unix`),
+    /// `crlf` (or `dos`) or `cr`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "lf" | "unix" => Some(Self::Lf),
+            "crlf" | "dos" => Some(Self::CrLf),
+            "cr" => Some(Self::Cr),
+            _ => None,
+        }
+    }
+
+    /// `text` with every line break, whatever its style, written as this
+    /// line ending.
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let done = match self {
+            Self::Lf => !text.contains('\r'),
+            Self::CrLf | Self::Cr => !text.contains(['\r', '\n']),
+        };
+        if done {
+            return Cow::Borrowed(text);
+        }
+        let mut out = String::with_capacity(text.len() + text.len() / 16);
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\r' => {
+                    chars.next_if_eq(&'\n');
+                    out.push_str(self.as_str());
+                }
+                '\n' => out.push_str(self.as_str()),
+                c => out.push(c),
+            }
+        }
+        Cow::Owned(out)
+    }
 }
 
 /// Detect the dominant line ending in `text` by counting occurrences.
@@ -62,6 +115,168 @@ pub fn detect_line_ending(text: &str) -> LineEnding {
     }
 }
 
+/// The character encoding of a file on disk, and whether it starts with a
+/// byte order mark. Buffers always hold UTF-8; files are decoded from
+/// their encoding when read and encoded back to it when written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextEncoding {
+    encoding: &'static Encoding,
+    bom: bool,
+}
+
+impl Default for TextEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.encoding == WINDOWS_1252 {
+            // What most people know it as.
+            f.write_str("Latin-1")
+        } else if self.encoding == UTF_8 && self.bom {
+            f.write_str("UTF-8 BOM")
+        } else {
+            f.write_str(self.encoding.name())
+        }
+    }
+}
+
+impl TextEncoding {
+    /// The encoding named by `label`: a WHATWG label such as `utf-8`,
+    /// `shift_jis`, `sjis` or `latin1`, or `utf-8-bom`. `None` for unknown
+    /// labels and for encodings text cannot be written in.
+    pub fn for_label(label: &str) -> Option<Self> {
+        let label = label.trim().to_ascii_lowercase();
+        if matches!(label.as_str(), "utf-8-bom" | "utf8-bom" | "utf-8 bom") {
+            return Some(Self {
+                encoding: UTF_8,
+                bom: true,
+            });
+        }
+        let encoding = Encoding::for_label(label.as_bytes())?;
+        let writable = encoding.output_encoding() == encoding || is_utf16(encoding);
+        writable.then_some(Self {
+            encoding,
+            bom: is_utf16(encoding),
+        })
+    }
+
+    /// Whether this is plain UTF-8, the buffer's own encoding.
+    pub fn is_utf8(&self) -> bool {
+        self.encoding == UTF_8 && !self.bom
+    }
+
+    /// Decode `bytes` in this encoding, dropping a byte order mark.
+    /// Malformed sequences become U+FFFD; the flag says whether there
+    /// were any.
+    pub fn decode(&self, bytes: &[u8]) -> (String, bool) {
+        let (text, had_errors) = self.encoding.decode_with_bom_removal(bytes);
+        (text.into_owned(), had_errors)
+    }
+
+    /// Read `path` and decode it in this encoding.
+    pub fn read(&self, path: &Path) -> Result<String, EditError> {
+        Ok(self.decode(&std::fs::read(path)?).0)
+    }
+
+    /// Encode `text` for writing, with a byte order mark if the file had
+    /// one. Fails if `text` has a character the encoding cannot
+    /// represent, rather than writing a substitute.
+    pub fn encode<'a>(&self, text: &'a str) -> Result<Cow<'a, [u8]>, EditError> {
+        if self.is_utf8() {
+            return Ok(Cow::Borrowed(text.as_bytes()));
+        }
+        let mut out = Vec::with_capacity(text.len() + 3);
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            // encoding_rs only decodes UTF-16.
+            let le = self.encoding == UTF_16LE;
+            let units = std::iter::once(0xfeff).filter(|_| self.bom);
+            for unit in units.chain(text.encode_utf16()) {
+                out.extend_from_slice(&if le {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                });
+            }
+            return Ok(Cow::Owned(out));
+        }
+        if self.bom {
+            out.extend_from_slice(b"\xef\xbb\xbf");
+        }
+        let (bytes, _, had_errors) = self.encoding.encode(text);
+        if had_errors {
+            let bad = text
+                .chars()
+                .find(|c| self.encoding.encode(c.encode_utf8(&mut [0; 4])).2)
+                .unwrap_or(char::REPLACEMENT_CHARACTER);
+            return Err(EditError::Encoding(format!(
+                "{:?} cannot be written as {}",
+                bad, self
+            )));
+        }
+        out.extend_from_slice(&bytes);
+        Ok(Cow::Owned(out))
+    }
+}
+
+fn is_utf16(encoding: &'static Encoding) -> bool {
+    encoding == UTF_16LE || encoding == UTF_16BE
+}
+
+/// Kana, which nearly all Japanese text has and text in other encodings
+/// almost never decodes to.
+fn is_kana(c: char) -> bool {
+    ('\u{3041}'..='\u{30ff}').contains(&c)
+}
+
+/// Whether a file's contents look like binary rather than text: they have
+/// a NUL byte and no byte order mark (UTF-16 text is full of NULs).
+pub fn is_binary(bytes: &[u8]) -> bool {
+    Encoding::for_bom(bytes).is_none() && bytes.contains(&0)
+}
+
+/// Decode the contents of a file, working out its encoding: a byte order
+/// mark if there is one, else UTF-8 if the bytes are valid UTF-8, else
+/// Shift_JIS if they decode cleanly to Japanese text, else Latin-1, which
+/// every byte sequence is. Binary files (see [`is_binary`]) are rejected.
+pub fn decode_detected(bytes: Vec<u8>) -> Result<(String, TextEncoding), EditError> {
+    if is_binary(&bytes) {
+        return Err(EditError::Binary);
+    }
+    if let Some((encoding, bom_len)) = Encoding::for_bom(&bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        let encoding = TextEncoding {
+            encoding,
+            bom: true,
+        };
+        return Ok((text.into_owned(), encoding));
+    }
+    let bytes = match String::from_utf8(bytes) {
+        Ok(text) => return Ok((text, TextEncoding::default())),
+        Err(e) => e.into_bytes(),
+    };
+    if let Some(text) = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(&bytes) {
+        if text.chars().any(is_kana) {
+            let encoding = TextEncoding {
+                encoding: SHIFT_JIS,
+                bom: false,
+            };
+            return Ok((text.into_owned(), encoding));
+        }
+    }
+    let (text, _) = WINDOWS_1252.decode_without_bom_handling(&bytes);
+    let encoding = TextEncoding {
+        encoding: WINDOWS_1252,
+        bom: false,
+    };
+    Ok((text.into_owned(), encoding))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,6 +288,25 @@ mod tests {
         assert_eq!(LineEnding::Cr.as_str(), "\r");
     }
 
+    #[test]
+    fn line_ending_names_round_trip() {
+        for ending in [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr] {
+            assert_eq!(LineEnding::from_name(ending.name()), Some(ending));
+        }
+        assert_eq!(LineEnding::from_name(" Dos "), Some(LineEnding::CrLf));
+        assert_eq!(LineEnding::from_name("lfcr"), None);
+    }
+
+    #[test]
+    fn normalize_rewrites_every_line_break() {
+        let mixed = "a\nb\r\nc\rd";
+        assert_eq!(LineEnding::Lf.normalize(mixed), "a\nb\nc\nd");
+        assert_eq!(LineEnding::CrLf.normalize(mixed), "a\r\nb\r\nc\r\nd");
+        assert_eq!(LineEnding::Cr.normalize(mixed), "a\rb\rc\rd");
+        assert!(matches!(LineEnding::Lf.normalize("a\nb"), Cow::Borrowed(_)));
+        assert!(matches!(LineEnding::CrLf.normalize("ab"), Cow::Borrowed(_)));
+    }
+
     #[test]
     fn line_ending_default_is_lf() {
         assert_eq!(LineEnding::default(), LineEnding::Lf);
@@ -141,4 +375,91 @@ mod tests {
     fn detect_single_cr() {
         assert_eq!(detect_line_ending("\r"), LineEnding::Cr);
     }
+
+    #[test]
+    fn utf8_is_detected_and_written_back_unchanged() {
+        let (text, enc) = decode_detected("naïve\n".as_bytes().to_vec()).unwrap();
+        assert_eq!(text, "naïve\n");
+        assert!(enc.is_utf8());
+        assert_eq!(enc.to_string(), "UTF-8");
+        assert_eq!(enc.encode(&text).unwrap().as_ref(), "naïve\n".as_bytes());
+    }
+
+    #[test]
+    fn byte_order_marks_are_dropped_and_restored() {
+        let bytes = b"\xef\xbb\xbfhi\n".to_vec();
+        let (text, enc) = decode_detected(bytes.clone()).unwrap();
+        assert_eq!(text, "hi\n");
+        assert_eq!(enc.to_string(), "UTF-8 BOM");
+        assert_eq!(enc.encode(&text).unwrap().as_ref(), bytes.as_slice());
+
+        let bytes = b"\xff\xfeh\x00i\x00".to_vec();
+        let (text, enc) = decode_detected(bytes.clone()).unwrap();
+        assert_eq!(text, "hi");
+        assert_eq!(enc.to_string(), "UTF-16LE");
+        assert_eq!(enc.encode(&text).unwrap().as_ref(), bytes.as_slice());
+    }
+
+    #[test]
+    fn shift_jis_is_detected_from_japanese_text() {
+        // "こんにちは" in Shift_JIS.
+        let bytes = b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd\n".to_vec();
+        let (text, enc) = decode_detected(bytes.clone()).unwrap();
+        assert_eq!(text, "こんにちは\n");
+        assert_eq!(enc.to_string(), "Shift_JIS");
+        assert_eq!(enc.encode(&text).unwrap().as_ref(), bytes.as_slice());
+    }
+
+    #[test]
+    fn other_bytes_are_read_as_latin1() {
+        // "été" in Latin-1 is also a valid Shift_JIS sequence, but no kana.
+        let bytes = b"\xe9t\xe9 caf\xe9\n".to_vec();
+        let (text, enc) = decode_detected(bytes.clone()).unwrap();
+        assert_eq!(text, "été café\n");
+        assert_eq!(enc.to_string(), "Latin-1");
+        assert_eq!(enc.encode(&text).unwrap().as_ref(), bytes.as_slice());
+    }
+
+    #[test]
+    fn binary_files_are_rejected() {
+        assert!(is_binary(b"\x7fELF\x00"));
+        assert!(!is_binary(b"\xff\xfeh\x00"));
+        let err = decode_detected(vec![b'a', 0, b'b']).unwrap_err();
+        assert!(matches!(err, EditError::Binary));
+    }
+
+    #[test]
+    fn unencodable_text_fails_to_encode() {
+        let latin1 = TextEncoding::for_label("latin1").unwrap();
+        let err = latin1.encode("ok 字").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "encoding error: '字' cannot be written as Latin-1"
+        );
+    }
+
+    #[test]
+    fn labels_name_writable_encodings() {
+        assert_eq!(
+            TextEncoding::for_label(" SJIS ").unwrap().to_string(),
+            "Shift_JIS"
+        );
+        assert_eq!(
+            TextEncoding::for_label("utf-8-bom").unwrap().to_string(),
+            "UTF-8 BOM"
+        );
+        assert!(TextEncoding::for_label("utf-8").unwrap().is_utf8());
+        assert_eq!(TextEncoding::for_label("klingon"), None);
+        // Decodes to U+FFFD only; nothing can be written in it.
+        assert_eq!(TextEncoding::for_label("iso-2022-kr"), None);
+    }
+
+    #[test]
+    fn decoding_in_a_chosen_encoding_reports_bad_bytes() {
+        let sjis = TextEncoding::for_label("shift_jis").unwrap();
+        let (text, had_errors) = sjis.decode(b"\x82\xa0");
+        assert_eq!(text, "あ");
+        assert!(!had_errors);
+        assert!(sjis.decode(b"\x82").1);
+    }
 }
@@ -20,6 +20,9 @@ pub enum EditError {
     #[error("encoding error: {0}")]
     Encoding(String),
 
+    #[error("binary file")]
+    Binary,
+
     #[error("file I/O error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -28,6 +31,29 @@ pub enum EditError {
 
     #[error("file not found: {0}")]
     FileNotFound(PathBuf),
+
+    #[error("buffer is read-only")]
+    ReadOnly,
+}
+
+/// Errors reading or writing a state file (see [`crate::state_file`]).
+#[derive(Debug, Error)]
+pub enum StateFileError {
+    #[error("file I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("write failed: {0}")]
+    Write(#[from] EditError),
+
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{what} format {version} is newer than supported ({supported})")]
+    TooNew {
+        what: &'static str,
+        version: u32,
+        supported: u32,
+    },
 }
 
 #[cfg(test)]
@@ -55,6 +81,11 @@ mod tests {
         assert_eq!(err.to_string(), "encoding error: invalid UTF-8");
     }
 
+    #[test]
+    fn edit_error_binary_displays() {
+        assert_eq!(EditError::Binary.to_string(), "binary file");
+    }
+
     #[test]
     fn edit_error_io_from_std_io_error() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
@@ -73,4 +104,9 @@ mod tests {
         let err = EditError::FileNotFound(PathBuf::from("/tmp/missing.txt"));
         assert_eq!(err.to_string(), "file not found: /tmp/missing.txt");
     }
+
+    #[test]
+    fn edit_error_read_only_displays() {
+        assert_eq!(EditError::ReadOnly.to_string(), "buffer is read-only");
+    }
 }
@@ -0,0 +1,157 @@
+//! Indexing the workspace for the file finder in the background.
+//!
+//! [`FileIndexer::spawn`] walks the workspace on its own thread, leaving
+//! out what [`walk_workspace`](crate::workspace_search::walk_workspace)
+//! leaves out, and sends the files back in batches, so the finder can
+//! search the first ones while the rest of a large tree is still being
+//! walked.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+
+use tracing::debug;
+
+use crate::workspace_search::walk_workspace_each;
+
+/// Files sent back at a time.
+const BATCH: usize = 512;
+
+#[derive(Debug)]
+enum IndexMessage {
+    Files(Vec<PathBuf>),
+    Done,
+}
+
+/// A workspace walk running in the background.
+///
+/// Dropping it stops the walk.
+#[derive(Debug)]
+pub struct FileIndexer {
+    rx: Receiver<IndexMessage>,
+    cancel: Arc<AtomicBool>,
+    files: Vec<PathBuf>,
+    done: bool,
+}
+
+impl FileIndexer {
+    /// Start listing up to `max_files` files under `root`.
+    pub fn spawn(root: PathBuf, max_files: usize) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        std::thread::spawn(move || {
+            let mut batch = Vec::new();
+            let mut count = 0;
+            walk_workspace_each(&root, &mut |rel| {
+                if flag.load(Ordering::Relaxed) || count >= max_files {
+                    return false;
+                }
+                count += 1;
+                batch.push(rel);
+                if batch.len() < BATCH {
+                    return true;
+                }
+                tx.send(IndexMessage::Files(std::mem::take(&mut batch)))
+                    .is_ok()
+            });
+            debug!("indexed {} files under {}", count, root.display());
+            let _ = tx.send(IndexMessage::Files(batch));
+            let _ = tx.send(IndexMessage::Done);
+        });
+        Self {
+            rx,
+            cancel,
+            files: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Files found since the last poll, relative to the root. They are
+    /// also kept, for [`FileIndexer::into_files`].
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut out = Vec::new();
+        while !self.done {
+            match self.rx.try_recv() {
+                Ok(IndexMessage::Files(files)) => out.extend(files),
+                Ok(IndexMessage::Done) => self.done = true,
+                Err(TryRecvError::Empty) => break,
+                // The walk panicked; nothing more will arrive.
+                Err(TryRecvError::Disconnected) => self.done = true,
+            }
+        }
+        self.files.extend_from_slice(&out);
+        out
+    }
+
+    /// Whether the whole workspace has been walked.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Number of files found so far.
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Every file found, sorted.
+    pub fn into_files(mut self) -> Vec<PathBuf> {
+        let mut files = std::mem::take(&mut self.files);
+        files.sort();
+        files
+    }
+}
+
+impl Drop for FileIndexer {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn finish(indexer: &mut FileIndexer) -> Vec<PathBuf> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut streamed = Vec::new();
+        while !indexer.is_done() {
+            assert!(Instant::now() < deadline, "indexing timed out");
+            streamed.extend(indexer.poll());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        streamed
+    }
+
+    #[test]
+    fn streams_the_workspace_files_skipping_ignored_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(root.join("src/main.rs"), "").unwrap();
+        std::fs::write(root.join("README.md"), "").unwrap();
+        std::fs::write(root.join("target/out.rs"), "").unwrap();
+        std::fs::write(root.join("build.log"), "").unwrap();
+        for i in 0..BATCH {
+            std::fs::write(root.join(format!("src/m{}.rs", i)), "").unwrap();
+        }
+
+        let mut indexer = FileIndexer::spawn(root.to_path_buf(), usize::MAX);
+        let streamed = finish(&mut indexer);
+        assert_eq!(streamed.len(), BATCH + 2);
+        assert_eq!(indexer.file_count(), BATCH + 2);
+        let files = indexer.into_files();
+        assert_eq!(files[0], PathBuf::from("README.md"));
+        assert!(files.contains(&PathBuf::from("src/main.rs")));
+        assert!(!files.iter().any(|f| f.starts_with("target")));
+        assert!(!files.contains(&PathBuf::from("build.log")));
+
+        let mut limited = FileIndexer::spawn(root.to_path_buf(), 10);
+        finish(&mut limited);
+        assert_eq!(limited.into_files().len(), 10);
+    }
+}
@@ -5,6 +5,8 @@
 
 use std::path::{Path, PathBuf};
 
+use crate::workspace_search::walk_workspace;
+
 /// A match result from the fuzzy finder.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FileMatch {
@@ -74,29 +76,11 @@ pub fn fuzzy_score(query: &str, target: &str) -> Option<i64> {
         return Some(0);
     }
 
-    let query_lower: Vec<char> = query.to_lowercase().chars().collect();
-    let target_lower: Vec<char> = target.to_lowercase().chars().collect();
+    let positions = fuzzy_positions(query, target)?;
+    let query_len = query.to_lowercase().chars().count();
+    let target_len = target.to_lowercase().chars().count();
     let target_chars: Vec<char> = target.chars().collect();
 
-    if query_lower.len() > target_lower.len() {
-        return None;
-    }
-
-    // Check if all query chars exist in order
-    let mut qi = 0;
-    let mut positions = Vec::with_capacity(query_lower.len());
-
-    for (ti, &tc) in target_lower.iter().enumerate() {
-        if qi < query_lower.len() && tc == query_lower[qi] {
-            positions.push(ti);
-            qi += 1;
-        }
-    }
-
-    if qi < query_lower.len() {
-        return None;
-    }
-
     // Score the match
     let mut score: i64 = 0;
     let base_points = 10;
@@ -130,7 +114,7 @@ pub fn fuzzy_score(query: &str, target: &str) -> Option<i64> {
     }
 
     // Prefer shorter paths
-    let length_penalty = (target_lower.len() as i64).saturating_sub(query_lower.len() as i64);
+    let length_penalty = (target_len as i64).saturating_sub(query_len as i64);
     score -= length_penalty;
 
     // Bonus for matching at the end (filename portion)
@@ -144,6 +128,20 @@ pub fn fuzzy_score(query: &str, target: &str) -> Option<i64> {
     Some(score)
 }
 
+/// Character indices of `target` that [`fuzzy_score`] matches the
+/// characters of `query` with, ignoring case: the first occurrence of
+/// each, in order. `None` if `query` does not match.
+pub fn fuzzy_positions(query: &str, target: &str) -> Option<Vec<usize>> {
+    let query_lower: Vec<char> = query.to_lowercase().chars().collect();
+    let mut positions = Vec::with_capacity(query_lower.len());
+    for (ti, tc) in target.to_lowercase().chars().enumerate() {
+        if positions.len() < query_lower.len() && tc == query_lower[positions.len()] {
+            positions.push(ti);
+        }
+    }
+    (positions.len() == query_lower.len()).then_some(positions)
+}
+
 /// Default directories and file patterns to ignore.
 const DEFAULT_IGNORE_DIRS: &[&str] = &[
     ".git",
@@ -169,6 +167,12 @@ fn is_ignored_dir(name: &str) -> bool {
     DEFAULT_IGNORE_DIRS.contains(&name)
 }
 
+/// Whether a directory entry called `name` is left out of workspace
+/// listings: hidden entries and well-known build/VCS directories.
+pub fn is_ignored(name: &str, is_dir: bool) -> bool {
+    (name.starts_with('.') && name != ".") || (is_dir && is_ignored_dir(name))
+}
+
 /// Walk a directory tree and collect all file paths.
 ///
 /// Respects common ignore patterns (e.g., .git, node_modules, target).
@@ -196,15 +200,13 @@ pub fn walk_directory(root: &Path, max_files: usize) -> Vec<PathBuf> {
             let name = entry.file_name();
             let name_str = name.to_string_lossy();
 
-            // Skip hidden files/dirs (starting with .)
-            if name_str.starts_with('.') && name_str != "." {
+            let is_dir = path.is_dir();
+            if is_ignored(&name_str, is_dir) {
                 continue;
             }
 
-            if path.is_dir() {
-                if !is_ignored_dir(&name_str) {
-                    stack.push(path);
-                }
+            if is_dir {
+                stack.push(path);
             } else if path.is_file() {
                 if let Ok(rel) = path.strip_prefix(root) {
                     files.push(rel.to_path_buf());
@@ -217,6 +219,13 @@ pub fn walk_directory(root: &Path, max_files: usize) -> Vec<PathBuf> {
     files
 }
 
+/// Files indexed by [`FileFinder::new`].
+pub const DEFAULT_MAX_FILES: usize = 100_000;
+
+/// Score added to the most recently opened file in
+/// [`FileFinder::search_with_recent`]; one less for each older one.
+const RECENT_BONUS: i64 = 40;
+
 /// The fuzzy file finder.
 ///
 /// Indexes files in a workspace directory and provides fuzzy matching.
@@ -236,7 +245,7 @@ impl FileFinder {
         Self {
             root,
             files: Vec::new(),
-            max_files: 100_000,
+            max_files: DEFAULT_MAX_FILES,
         }
     }
 
@@ -249,9 +258,16 @@ impl FileFinder {
         }
     }
 
-    /// Index the workspace directory.
+    /// Index the workspace directory, leaving out `.gitignore`d files.
+    /// [`FileIndexer`](crate::file_index::FileIndexer) does the same
+    /// without blocking.
     pub fn index(&mut self) {
-        self.files = walk_directory(&self.root, self.max_files);
+        self.files = walk_workspace(&self.root, self.max_files);
+    }
+
+    /// Maximum number of files indexed.
+    pub fn max_files(&self) -> usize {
+        self.max_files
     }
 
     /// Get the number of indexed files.
@@ -268,17 +284,38 @@ impl FileFinder {
     ///
     /// Returns results sorted by match score (best first), limited to `max_results`.
     pub fn search(&self, query: &str, max_results: usize) -> Vec<FileMatch> {
+        self.search_with_recent(query, max_results, &[])
+    }
+
+    /// Like [`FileFinder::search`], but ranking the files in `recent`
+    /// (relative paths, most recent first) higher. An empty query lists
+    /// them first, in that order.
+    pub fn search_with_recent(
+        &self,
+        query: &str,
+        max_results: usize,
+        recent: &[PathBuf],
+    ) -> Vec<FileMatch> {
+        let bonus = |path: &Path| {
+            recent
+                .iter()
+                .position(|r| r == path)
+                .map_or(0, |rank| (RECENT_BONUS - rank as i64).max(1))
+        };
         if query.is_empty() {
             // Return all files (up to limit) when query is empty
-            return self
+            let mut matches: Vec<FileMatch> = self
                 .files
                 .iter()
-                .take(max_results)
                 .map(|p| {
                     let rel = p.to_string_lossy().to_string();
-                    FileMatch::new(self.root.join(p), rel, 0)
+                    FileMatch::new(self.root.join(p), rel, bonus(p))
                 })
                 .collect();
+            // Stable, so the rest stay in path order.
+            matches.sort_by_key(|m| std::cmp::Reverse(m.score()));
+            matches.truncate(max_results);
+            return matches;
         }
 
         let mut matches: Vec<FileMatch> = self
@@ -286,8 +323,13 @@ impl FileFinder {
             .iter()
             .filter_map(|path| {
                 let path_str = path.to_string_lossy();
-                fuzzy_score(query, &path_str)
-                    .map(|score| FileMatch::new(self.root.join(path), path_str.to_string(), score))
+                fuzzy_score(query, &path_str).map(|score| {
+                    FileMatch::new(
+                        self.root.join(path),
+                        path_str.to_string(),
+                        score + bonus(path),
+                    )
+                })
             })
             .collect();
 
@@ -304,6 +346,20 @@ impl FileFinder {
         }
     }
 
+    /// Add files found by a background walk, up to the file limit.
+    pub fn add_files(&mut self, relative_paths: Vec<PathBuf>) {
+        let room = self.max_files.saturating_sub(self.files.len());
+        self.files.extend(relative_paths.into_iter().take(room));
+        self.files.sort();
+        self.files.dedup();
+    }
+
+    /// Replace the index with a fresh listing.
+    pub fn set_files(&mut self, mut relative_paths: Vec<PathBuf>) {
+        relative_paths.truncate(self.max_files);
+        self.files = relative_paths;
+    }
+
     /// Remove a file path from the index.
     pub fn remove_file(&mut self, relative_path: &Path) {
         self.files.retain(|p| p != relative_path);
@@ -522,6 +578,10 @@ mod tests {
         assert!(is_ignored_dir("__pycache__"));
         assert!(!is_ignored_dir("src"));
         assert!(!is_ignored_dir("lib"));
+        assert!(is_ignored(".env", false));
+        assert!(is_ignored("target", true));
+        assert!(!is_ignored("target", false));
+        assert!(!is_ignored("src", true));
     }
 
     // --- FileFinder tests ---
@@ -563,6 +623,57 @@ mod tests {
         assert_eq!(finder.file_count(), 0);
     }
 
+    #[test]
+    fn fuzzy_positions_are_the_matched_characters() {
+        assert_eq!(fuzzy_positions("mr", "src/main.rs"), Some(vec![4, 9]));
+        assert_eq!(
+            fuzzy_positions("MAIN", "src/main.rs"),
+            Some(vec![4, 5, 6, 7])
+        );
+        assert_eq!(fuzzy_positions("", "a"), Some(vec![]));
+        assert_eq!(fuzzy_positions("x", "src/main.rs"), None);
+    }
+
+    #[test]
+    fn file_finder_ranks_recent_files_higher() {
+        let mut finder = FileFinder::new(PathBuf::from("/ws"));
+        for f in ["src/app.rs", "src/apply.rs", "docs/notes.md", "a.txt"] {
+            finder.add_file(PathBuf::from(f));
+        }
+        let recent = [
+            PathBuf::from("docs/notes.md"),
+            PathBuf::from("src/apply.rs"),
+        ];
+        let labels = |matches: Vec<FileMatch>| {
+            matches
+                .iter()
+                .map(|m| m.relative_path().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            labels(finder.search_with_recent("", 10, &recent)),
+            vec!["docs/notes.md", "src/apply.rs", "a.txt", "src/app.rs"]
+        );
+        assert_eq!(labels(finder.search("app", 1)), vec!["src/app.rs"]);
+        assert_eq!(
+            labels(finder.search_with_recent("app", 1, &recent)),
+            vec!["src/apply.rs"]
+        );
+    }
+
+    #[test]
+    fn file_finder_add_files_in_batches_up_to_the_limit() {
+        let mut finder = FileFinder::with_max_files(PathBuf::from("/ws"), 3);
+        finder.add_files(vec![PathBuf::from("b.rs"), PathBuf::from("a.rs")]);
+        finder.add_files(vec![PathBuf::from("c.rs"), PathBuf::from("d.rs")]);
+        assert_eq!(finder.file_count(), 3);
+        assert_eq!(finder.search("", 10)[0].relative_path(), "a.rs");
+
+        finder.set_files(vec![PathBuf::from("e.rs")]);
+        assert_eq!(finder.file_count(), 1);
+        assert_eq!(finder.search("", 10)[0].relative_path(), "e.rs");
+    }
+
     #[test]
     fn file_finder_search_empty_query() {
         let mut finder = FileFinder::new(PathBuf::from("/ws"));
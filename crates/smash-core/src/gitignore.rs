@@ -0,0 +1,169 @@
+//! `.gitignore` rules for workspace walks.
+//!
+//! Supports the common subset of the format: comments, blank lines,
+//! `!` negation, a trailing `/` for directories only, and patterns that
+//! are anchored to the `.gitignore`'s directory when they contain a `/`.
+//! Wildcards are matched with [`glob_match`].
+
+use std::path::{Path, PathBuf};
+
+use crate::glob::glob_match;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    /// Matched against the whole path below the base directory rather
+    /// than just the final component.
+    anchored: bool,
+}
+
+/// The rules of one `.gitignore` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitIgnore {
+    base: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl GitIgnore {
+    /// Parse `contents` as the `.gitignore` of directory `base`.
+    pub fn parse(base: impl Into<PathBuf>, contents: &str) -> Self {
+        let rules = contents.lines().filter_map(parse_rule).collect();
+        Self {
+            base: base.into(),
+            rules,
+        }
+    }
+
+    /// Read `dir/.gitignore`, if there is one.
+    pub fn load(dir: &Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(dir.join(".gitignore")).ok()?;
+        Some(Self::parse(dir, &contents))
+    }
+
+    /// Directory the rules are relative to.
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    /// What the last rule matching `path` says: `Some(true)` if it is
+    /// ignored, `Some(false)` if a `!` rule re-includes it, `None` if no
+    /// rule applies (including paths outside the base directory).
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let rel = path.strip_prefix(&self.base).ok()?;
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        if rel.is_empty() {
+            return None;
+        }
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule_matches(rule, &rel, is_dir))
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// Whether `path` is ignored by `ignores`, ordered from the outermost
+/// `.gitignore` to the innermost. Inner files take precedence.
+pub fn is_gitignored(ignores: &[GitIgnore], path: &Path, is_dir: bool) -> bool {
+    ignores
+        .iter()
+        .rev()
+        .find_map(|ignore| ignore.matched(path, is_dir))
+        .unwrap_or(false)
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // `\#` and `\!` escape a leading special character.
+    let line = line.strip_prefix('\\').unwrap_or(line);
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let anchored = line.contains('/');
+    let pattern = line.trim_start_matches('/');
+    if pattern.is_empty() {
+        return None;
+    }
+    Some(Rule {
+        pattern: pattern.to_string(),
+        negated,
+        dir_only,
+        anchored,
+    })
+}
+
+fn rule_matches(rule: &Rule, rel: &str, is_dir: bool) -> bool {
+    if rule.dir_only && !is_dir {
+        return false;
+    }
+    if !rule.anchored {
+        return glob_match(&rule.pattern, rel);
+    }
+    // `glob_match` compares a slash-free pattern with the file name
+    // only; an anchored one like `/build` must match from the base.
+    if !rule.pattern.contains('/') && rel.contains('/') {
+        return false;
+    }
+    glob_match(&rule.pattern, rel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignored(ignore: &GitIgnore, path: &str, is_dir: bool) -> bool {
+        is_gitignored(
+            std::slice::from_ref(ignore),
+            &Path::new("/repo").join(path),
+            is_dir,
+        )
+    }
+
+    #[test]
+    fn matches_names_anywhere_and_anchored_paths() {
+        let ignore = GitIgnore::parse(
+            "/repo",
+            "# build output\n\n*.log\n/dist\ndocs/*.html\ncache/\n",
+        );
+        assert!(ignored(&ignore, "app.log", false));
+        assert!(ignored(&ignore, "src/deep/app.log", false));
+        assert!(ignored(&ignore, "dist", true));
+        assert!(!ignored(&ignore, "src/dist", true));
+        assert!(ignored(&ignore, "docs/index.html", false));
+        assert!(!ignored(&ignore, "docs/api/index.html", false));
+        assert!(ignored(&ignore, "src/cache", true));
+        assert!(!ignored(&ignore, "src/cache", false));
+        assert!(!ignored(&ignore, "src/main.rs", false));
+        assert!(!is_gitignored(
+            &[ignore],
+            Path::new("/elsewhere/app.log"),
+            false
+        ));
+    }
+
+    #[test]
+    fn negation_and_inner_files_take_precedence() {
+        let outer = GitIgnore::parse("/repo", "*.txt\n!keep.txt\n");
+        assert!(ignored(&outer, "notes.txt", false));
+        assert!(!ignored(&outer, "keep.txt", false));
+
+        let inner = GitIgnore::parse("/repo/fixtures", "!*.txt\n");
+        let both = [outer, inner];
+        assert!(!is_gitignored(
+            &both,
+            Path::new("/repo/fixtures/data.txt"),
+            false
+        ));
+        assert!(is_gitignored(&both, Path::new("/repo/data.txt"), false));
+    }
+}
@@ -0,0 +1,103 @@
+/// Match `path` against a shell-style glob `pattern`.
+///
+/// Supported syntax:
+/// - `*` matches any run of characters except `/`
+/// - `**` matches any run of characters including `/`
+/// - `?` matches a single character except `/`
+/// - `{a,b}` matches any one of the comma-separated alternatives
+///
+/// A pattern without a `/` is matched against the final path component
+/// only, so `*.rs` matches `src/main.rs`.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    let target = if pattern.contains('/') {
+        path.as_str()
+    } else {
+        path.rsplit('/').next().unwrap_or(&path)
+    };
+    expand_braces(pattern).iter().any(|p| {
+        let pat: Vec<char> = p.chars().collect();
+        let txt: Vec<char> = target.chars().collect();
+        match_from(&pat, &txt)
+    })
+}
+
+/// Expand the first `{a,b}` group recursively into plain patterns.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let Some(close) = pattern[open..].find('}').map(|i| open + i) else {
+        return vec![pattern.to_string()];
+    };
+    let (prefix, rest) = (&pattern[..open], &pattern[close + 1..]);
+    pattern[open + 1..close]
+        .split(',')
+        .flat_map(|alt| expand_braces(&format!("{prefix}{alt}{rest}")))
+        .collect()
+}
+
+fn match_from(pat: &[char], txt: &[char]) -> bool {
+    match pat.first() {
+        None => txt.is_empty(),
+        Some('*') if pat.get(1) == Some(&'*') => {
+            // `**/` may also match zero directories.
+            let rest = &pat[2..];
+            if rest.first() == Some(&'/') && match_from(&rest[1..], txt) {
+                return true;
+            }
+            (0..=txt.len()).any(|i| match_from(rest, &txt[i..]))
+        }
+        Some('*') => {
+            let rest = &pat[1..];
+            for i in 0..=txt.len() {
+                if match_from(rest, &txt[i..]) {
+                    return true;
+                }
+                if txt.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            matches!(txt.first(), Some(c) if *c != '/') && match_from(&pat[1..], &txt[1..])
+        }
+        Some(c) => txt.first() == Some(c) && match_from(&pat[1..], &txt[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn star_matches_file_name_only() {
+        assert!(glob_match("*.rs", "src/app/mod.rs"));
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "main.rst"));
+        assert!(!glob_match("src/*.rs", "src/app/mod.rs"));
+    }
+
+    #[test]
+    fn double_star_crosses_directories() {
+        assert!(glob_match("src/**/*.rs", "src/app/mod.rs"));
+        assert!(glob_match("src/**/*.rs", "src/main.rs"));
+        assert!(glob_match("**", "a/b/c"));
+        assert!(!glob_match("src/**/*.rs", "tests/a.rs"));
+    }
+
+    #[test]
+    fn question_mark_and_braces() {
+        assert!(glob_match("?.c", "a.c"));
+        assert!(!glob_match("?.c", "ab.c"));
+        assert!(glob_match("*.{c,h}", "x.h"));
+        assert!(glob_match("*.{c,h}", "x.c"));
+        assert!(!glob_match("*.{c,h}", "x.cpp"));
+    }
+
+    #[test]
+    fn backslashes_are_treated_as_separators() {
+        assert!(glob_match("src/*.rs", "src\\main.rs"));
+    }
+}
@@ -0,0 +1,113 @@
+//! Grapheme clusters and display widths.
+//!
+//! Buffer columns count chars, but the cursor steps over whole
+//! user-perceived characters (a letter with combining accents, an emoji
+//! with a skin tone) and the screen lays text out in terminal cells, where
+//! CJK characters and most emoji take two.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Terminal cells taken by grapheme cluster `g`: two for wide
+/// characters, otherwise one. Zero-width and control characters still
+/// get a cell so the cursor has somewhere to sit.
+pub fn grapheme_width(g: &str) -> usize {
+    g.width().clamp(1, 2)
+}
+
+/// The grapheme clusters of `line`, each with the char column it starts
+/// at.
+pub fn graphemes(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.graphemes(true).scan(0, |col, g| {
+        let start = *col;
+        *col += g.chars().count();
+        Some((start, g))
+    })
+}
+
+/// The char column of the grapheme boundary after `col` in `line`, or
+/// the end of the line.
+pub fn next_boundary(line: &str, col: usize) -> usize {
+    graphemes(line)
+        .map(|(start, g)| start + g.chars().count())
+        .find(|&end| end > col)
+        .unwrap_or_else(|| line.chars().count().max(col))
+}
+
+/// The char column of the grapheme boundary before `col` in `line`, or 0.
+pub fn prev_boundary(line: &str, col: usize) -> usize {
+    graphemes(line)
+        .map(|(start, _)| start)
+        .take_while(|&start| start < col)
+        .last()
+        .unwrap_or(0)
+}
+
+/// Terminal cells taken by `s`.
+pub fn display_width(s: &str) -> usize {
+    s.graphemes(true).map(grapheme_width).sum()
+}
+
+/// The display column of char column `col` in `line`: the cells taken by
+/// the graphemes before it. A column inside a cluster counts as its
+/// start.
+pub fn display_col(line: &str, col: usize) -> usize {
+    graphemes(line)
+        .take_while(|&(start, g)| start + g.chars().count() <= col)
+        .map(|(_, g)| grapheme_width(g))
+        .sum()
+}
+
+/// The char column of the grapheme covering display column `x` in
+/// `line`, or the end of the line if it is narrower than `x`.
+pub fn col_at_display(line: &str, x: usize) -> usize {
+    let mut cells = 0;
+    for (start, g) in graphemes(line) {
+        cells += grapheme_width(g);
+        if cells > x {
+            return start;
+        }
+    }
+    line.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "e" + combining acute, a wide CJK char, a thumbs-up with a skin
+    // tone and "!": graphemes of 2, 1, 2 and 1 chars.
+    const LINE: &str = "e\u{301}字👍🏽!";
+
+    #[test]
+    fn boundaries_step_over_whole_graphemes() {
+        assert_eq!(next_boundary(LINE, 0), 2);
+        assert_eq!(next_boundary(LINE, 2), 3);
+        assert_eq!(next_boundary(LINE, 3), 5);
+        assert_eq!(next_boundary(LINE, 6), 6);
+        assert_eq!(prev_boundary(LINE, 5), 3);
+        assert_eq!(prev_boundary(LINE, 4), 3);
+        assert_eq!(prev_boundary(LINE, 2), 0);
+        assert_eq!(prev_boundary(LINE, 0), 0);
+    }
+
+    #[test]
+    fn wide_characters_take_two_cells() {
+        assert_eq!(display_width(LINE), 6);
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_col(LINE, 2), 1);
+        assert_eq!(display_col(LINE, 3), 3);
+        assert_eq!(display_col(LINE, 4), 3);
+        assert_eq!(display_col(LINE, 5), 5);
+    }
+
+    #[test]
+    fn display_columns_map_back_to_grapheme_starts() {
+        assert_eq!(col_at_display(LINE, 0), 0);
+        assert_eq!(col_at_display(LINE, 1), 2);
+        assert_eq!(col_at_display(LINE, 2), 2);
+        assert_eq!(col_at_display(LINE, 4), 3);
+        assert_eq!(col_at_display(LINE, 5), 5);
+        assert_eq!(col_at_display(LINE, 40), 6);
+    }
+}
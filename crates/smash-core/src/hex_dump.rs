@@ -0,0 +1,75 @@
+//! Hex dumps of binary files, which are shown read-only instead of being
+//! decoded as text.
+
+use std::fmt::Write;
+
+/// Bytes shown per line of the dump.
+pub const BYTES_PER_LINE: usize = 16;
+
+/// Bytes dumped at most; the rest of a larger file is only counted.
+pub const DUMP_LIMIT: usize = 16 * 1024 * 1024;
+
+/// `bytes` laid out like `hexdump -C`: per line the offset, sixteen bytes
+/// in hex and the same bytes as ASCII, with `.` for anything not
+/// printable. The last line is the total length.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(DUMP_LIMIT)];
+    let mut out = String::with_capacity(shown.len() / BYTES_PER_LINE * 80 + 80);
+    for (i, chunk) in shown.chunks(BYTES_PER_LINE).enumerate() {
+        let _ = write!(out, "{:08x} ", i * BYTES_PER_LINE);
+        for j in 0..BYTES_PER_LINE {
+            if j % 8 == 0 {
+                out.push(' ');
+            }
+            match chunk.get(j) {
+                Some(b) => {
+                    let _ = write!(out, "{:02x} ", b);
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|&b| match b {
+            b' '..=b'~' => b as char,
+            _ => '.',
+        }));
+        out.push_str("|\n");
+    }
+    if bytes.len() > shown.len() {
+        let _ = write!(out, "… {} more bytes not shown", bytes.len() - shown.len());
+    } else {
+        let _ = write!(out, "{:08x}", bytes.len());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_show_offset_hex_and_ascii() {
+        let mut bytes = b"\x7fELF\x02\x01\x01\x00".to_vec();
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(b"hi!\n");
+        assert_eq!(
+            hex_dump(&bytes),
+            "00000000  7f 45 4c 46 02 01 01 00  00 00 00 00 00 00 00 00  |.ELF............|\n\
+             00000010  68 69 21 0a                                       |hi!.|\n\
+             00000014"
+        );
+    }
+
+    #[test]
+    fn empty_input_is_just_the_length() {
+        assert_eq!(hex_dump(&[]), "00000000");
+    }
+
+    #[test]
+    fn large_input_is_cut_off() {
+        let bytes = vec![0u8; DUMP_LIMIT + 5];
+        let dump = hex_dump(&bytes);
+        assert_eq!(dump.lines().count(), DUMP_LIMIT / BYTES_PER_LINE + 1);
+        assert!(dump.ends_with("… 5 more bytes not shown"));
+    }
+}
@@ -0,0 +1,290 @@
+//! Indentation for new lines.
+//!
+//! Given the text on either side of the cursor, works out the leading
+//! whitespace for the line a newline starts: the previous line's indent,
+//! one level deeper after an opening bracket (or `:` where the language
+//! opens blocks with it), and one level shallower before a closer.
+
+/// How one level of indentation is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndentStyle {
+    /// Columns per indentation level and per tab stop.
+    pub tab_size: usize,
+    /// Indent with spaces rather than tab characters.
+    pub insert_spaces: bool,
+}
+
+impl Default for IndentStyle {
+    fn default() -> Self {
+        Self {
+            tab_size: 4,
+            insert_spaces: true,
+        }
+    }
+}
+
+impl IndentStyle {
+    /// The whitespace for one indentation level.
+    pub fn unit(&self) -> String {
+        if self.insert_spaces {
+            " ".repeat(self.tab_size.max(1))
+        } else {
+            "\t".to_string()
+        }
+    }
+
+    /// Display width of leading whitespace, expanding tabs.
+    pub fn width(&self, whitespace: &str) -> usize {
+        let tab = self.tab_size.max(1);
+        whitespace.chars().fold(0, |col, ch| match ch {
+            '\t' => (col / tab + 1) * tab,
+            _ => col + 1,
+        })
+    }
+
+    /// Whitespace reaching `width` columns in this style.
+    pub fn whitespace(&self, width: usize) -> String {
+        if self.insert_spaces {
+            " ".repeat(width)
+        } else {
+            let tab = self.tab_size.max(1);
+            let mut ws = "\t".repeat(width / tab);
+            ws.push_str(&" ".repeat(width % tab));
+            ws
+        }
+    }
+
+    /// `whitespace` with one level removed, or unchanged if it is empty.
+    pub fn dedent(&self, whitespace: &str) -> String {
+        let width = self.width(whitespace);
+        let tab = self.tab_size.max(1);
+        // Snap to the previous tab stop, so a ragged indent lines up.
+        let target = match width % tab {
+            0 => width.saturating_sub(tab),
+            ragged => width - ragged,
+        };
+        self.whitespace(target)
+    }
+}
+
+/// Which characters change the indentation of the following line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndentRules {
+    /// A line ending in one of these indents the next line.
+    pub openers: Vec<char>,
+    /// A line starting with one of these goes back one level.
+    pub closers: Vec<char>,
+    /// A line ending in `:` indents the next line, as in Python.
+    pub colon_opens: bool,
+}
+
+impl Default for IndentRules {
+    /// Brackets of all three kinds, as in C-like languages.
+    fn default() -> Self {
+        Self {
+            openers: vec!['{', '(', '['],
+            closers: vec!['}', ')', ']'],
+            colon_opens: false,
+        }
+    }
+}
+
+impl IndentRules {
+    /// Rules that only carry the previous line's indent forward.
+    pub fn copy_only() -> Self {
+        Self {
+            openers: Vec::new(),
+            closers: Vec::new(),
+            colon_opens: false,
+        }
+    }
+
+    fn opens(&self, line: &str) -> bool {
+        match line.trim_end().chars().last() {
+            Some(':') if self.colon_opens => true,
+            Some(ch) => self.openers.contains(&ch),
+            None => false,
+        }
+    }
+
+    /// Whether `line` opens more brackets than it closes.
+    fn leaves_open(&self, line: &str) -> bool {
+        let depth = line.chars().fold(0i32, |depth, ch| {
+            if self.openers.contains(&ch) {
+                depth + 1
+            } else if self.closers.contains(&ch) {
+                depth - 1
+            } else {
+                depth
+            }
+        });
+        depth > 0
+    }
+
+    fn closes(&self, line: &str) -> bool {
+        line.trim_start()
+            .chars()
+            .next()
+            .is_some_and(|ch| self.closers.contains(&ch))
+    }
+}
+
+/// Indentation for splitting a line at the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewlineIndent {
+    /// Leading whitespace of the new cursor line.
+    pub indent: String,
+    /// When the cursor sat between an opener and its closer, the closer
+    /// moves to a line of its own with this indent.
+    pub closer_indent: Option<String>,
+}
+
+/// Leading whitespace of `line`.
+pub fn leading_whitespace(line: &str) -> &str {
+    let end = line.len() - line.trim_start_matches([' ', '\t']).len();
+    &line[..end]
+}
+
+/// Work out the indentation when Enter splits a line into `before` (up
+/// to the cursor) and `after` (the rest of the line).
+pub fn newline_indent(
+    before: &str,
+    after: &str,
+    rules: &IndentRules,
+    style: &IndentStyle,
+) -> NewlineIndent {
+    let base = leading_whitespace(before);
+    let opens = rules.opens(before);
+    let closes = rules.closes(after);
+    match (opens, closes) {
+        (true, true) => NewlineIndent {
+            indent: format!("{}{}", base, style.unit()),
+            closer_indent: Some(base.to_string()),
+        },
+        (true, false) => NewlineIndent {
+            indent: format!("{}{}", base, style.unit()),
+            closer_indent: None,
+        },
+        // A closer for a bracket opened earlier on this line lines up
+        // with the line; otherwise it ends an enclosing block.
+        (false, true) if rules.leaves_open(before) => NewlineIndent {
+            indent: base.to_string(),
+            closer_indent: None,
+        },
+        (false, true) => NewlineIndent {
+            indent: style.dedent(base),
+            closer_indent: None,
+        },
+        (false, false) => NewlineIndent {
+            indent: base.to_string(),
+            closer_indent: None,
+        },
+    }
+}
+
+/// New leading whitespace for a line when `typed` is entered after
+/// `before`, or `None` if the indent should stay. Typing a closer as
+/// the first character on a line moves it back one level.
+pub fn indent_for_closer(
+    before: &str,
+    typed: char,
+    rules: &IndentRules,
+    style: &IndentStyle,
+) -> Option<String> {
+    if before.is_empty() || !before.trim().is_empty() || !rules.closers.contains(&typed) {
+        return None;
+    }
+    Some(style.dedent(before))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spaces(n: usize) -> IndentStyle {
+        IndentStyle {
+            tab_size: n,
+            insert_spaces: true,
+        }
+    }
+
+    #[test]
+    fn copies_previous_indent() {
+        let r = newline_indent("    let x = 1;", "", &IndentRules::default(), &spaces(4));
+        assert_eq!(r.indent, "    ");
+        assert_eq!(r.closer_indent, None);
+    }
+
+    #[test]
+    fn indents_after_opener() {
+        let r = newline_indent("  fn main() {  ", "", &IndentRules::default(), &spaces(2));
+        assert_eq!(r.indent, "    ");
+    }
+
+    #[test]
+    fn splits_bracket_pair_onto_three_lines() {
+        let r = newline_indent("    foo(", ")", &IndentRules::default(), &spaces(4));
+        assert_eq!(r.indent, "        ");
+        assert_eq!(r.closer_indent.as_deref(), Some("    "));
+    }
+
+    #[test]
+    fn dedents_before_closer() {
+        let r = newline_indent("        x", "}", &IndentRules::default(), &spaces(4));
+        assert_eq!(r.indent, "    ");
+        // The closer matches an opener on the same line.
+        let r = newline_indent("    foo(a, b", ")", &IndentRules::default(), &spaces(4));
+        assert_eq!(r.indent, "    ");
+    }
+
+    #[test]
+    fn colon_opens_only_when_enabled() {
+        let python = IndentRules {
+            colon_opens: true,
+            ..IndentRules::default()
+        };
+        assert_eq!(
+            newline_indent("if x:", "", &python, &spaces(4)).indent,
+            "    "
+        );
+        let r = newline_indent("case 1:", "", &IndentRules::default(), &spaces(4));
+        assert_eq!(r.indent, "");
+    }
+
+    #[test]
+    fn copy_only_rules_ignore_brackets() {
+        let r = newline_indent("  - item {", "", &IndentRules::copy_only(), &spaces(4));
+        assert_eq!(r.indent, "  ");
+    }
+
+    #[test]
+    fn tabs_style_indents_with_tabs() {
+        let tabs = IndentStyle {
+            tab_size: 8,
+            insert_spaces: false,
+        };
+        let r = newline_indent("\tif (x) {", "", &IndentRules::default(), &tabs);
+        assert_eq!(r.indent, "\t\t");
+        assert_eq!(tabs.dedent("\t\t"), "\t");
+        assert_eq!(tabs.width("\t  \t"), 16);
+    }
+
+    #[test]
+    fn dedent_snaps_to_tab_stop() {
+        assert_eq!(spaces(4).dedent("      "), "    ");
+        assert_eq!(spaces(4).dedent("  "), "");
+        assert_eq!(spaces(4).dedent(""), "");
+    }
+
+    #[test]
+    fn closer_on_blank_line_dedents() {
+        let rules = IndentRules::default();
+        assert_eq!(
+            indent_for_closer("        ", '}', &rules, &spaces(4)).as_deref(),
+            Some("    ")
+        );
+        assert_eq!(indent_for_closer("    x", '}', &rules, &spaces(4)), None);
+        assert_eq!(indent_for_closer("    ", 'x', &rules, &spaces(4)), None);
+        assert_eq!(indent_for_closer("", '}', &rules, &spaces(4)), None);
+    }
+}
@@ -0,0 +1,90 @@
+//! The kill ring: text recently copied or cut, newest first, so an
+//! earlier clipboard entry can be pasted again.
+
+use std::collections::VecDeque;
+
+/// Entries kept by default.
+pub const DEFAULT_KILL_RING_SIZE: usize = 30;
+
+/// Recently copied or cut text, newest first.
+#[derive(Debug, Clone)]
+pub struct KillRing {
+    entries: VecDeque<String>,
+    capacity: usize,
+}
+
+impl Default for KillRing {
+    fn default() -> Self {
+        Self::new(DEFAULT_KILL_RING_SIZE)
+    }
+}
+
+impl KillRing {
+    /// An empty ring keeping at most `capacity` entries (at least one).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Remember `text` as the newest entry. An earlier copy of it moves
+    /// to the front instead of being kept twice; empty text is ignored.
+    pub fn push(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(index) = self.entries.iter().position(|e| e == text) {
+            let entry = self.entries.remove(index).unwrap_or_default();
+            self.entries.push_front(entry);
+            return;
+        }
+        self.entries.push_front(text.to_string());
+        self.entries.truncate(self.capacity);
+    }
+
+    /// The entry `index` places back from the newest.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// The newest entry.
+    pub fn newest(&self) -> Option<&str> {
+        self.get(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries, newest first.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_newest_first_without_duplicates() {
+        let mut ring = KillRing::new(3);
+        ring.push("one");
+        ring.push("two");
+        ring.push("");
+        ring.push("one");
+        assert_eq!(ring.iter().collect::<Vec<_>>(), ["one", "two"]);
+
+        ring.push("three");
+        ring.push("four");
+        assert_eq!(ring.len(), 3);
+        assert_eq!(ring.newest(), Some("four"));
+        assert_eq!(ring.get(2), Some("one"));
+        assert_eq!(ring.get(3), None);
+    }
+}
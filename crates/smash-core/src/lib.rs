@@ -1,13 +1,32 @@
 pub mod buffer;
+pub mod companion;
 pub mod cursor;
+pub mod diff;
 pub mod edit;
 pub mod encoding;
 pub mod error;
+pub mod file_index;
 pub mod fuzzy_finder;
+pub mod gitignore;
+pub mod glob;
+pub mod grapheme;
+pub mod hex_dump;
+pub mod indent;
+pub mod kill_ring;
+pub mod links;
+pub mod loader;
 pub mod logging;
 pub mod message;
 pub mod position;
+pub mod quickfix;
+pub mod recent_files;
 pub mod recovery;
+pub mod registers;
+pub mod safe_write;
 pub mod search;
+pub mod search_history;
 pub mod selection;
+pub mod session;
+pub mod state_file;
 pub mod undo;
+pub mod workspace_search;
@@ -0,0 +1,151 @@
+//! Links in buffer text: URLs and `path:line:col` locations, such as the
+//! ones in compiler output or logs, so they can be shown and followed.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Where a link leads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// An `http`, `https`, `ftp` or `file` URL.
+    Url(String),
+    /// A file location; `line` and `col` are 1-based as written.
+    File {
+        path: String,
+        line: usize,
+        col: Option<usize>,
+    },
+}
+
+/// A link found in one line of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextLink {
+    /// First column of the link, in characters.
+    pub start: usize,
+    /// Column just past the link, in characters.
+    pub end: usize,
+    pub target: LinkTarget,
+}
+
+impl TextLink {
+    /// Whether the character at `col` is part of the link.
+    pub fn contains(&self, col: usize) -> bool {
+        self.start <= col && col < self.end
+    }
+}
+
+fn url_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"\b(?:https?|ftp|file)://[^\s<>"'`)\]]+"#).expect("URL regex is valid")
+    })
+}
+
+/// A path whose file name has an extension, then `:line` and optionally
+/// `:col`, e.g. `src/main.rs:42:10` or `C:\src\app.py:3`.
+fn location_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?:[A-Za-z]:)?[\w.~/\\-]*\.[A-Za-z][A-Za-z0-9]*:(\d+)(?::(\d+))?")
+            .expect("location regex is valid")
+    })
+}
+
+/// The links in `line`, in order. A URL's trailing punctuation, as at
+/// the end of a sentence, is left out of it.
+pub fn find_links(line: &str) -> Vec<TextLink> {
+    let col_of = |byte: usize| line[..byte].chars().count();
+    let mut links: Vec<TextLink> = url_regex()
+        .find_iter(line)
+        .map(|m| {
+            let url = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            let start = col_of(m.start());
+            TextLink {
+                start,
+                end: start + url.chars().count(),
+                target: LinkTarget::Url(url.to_string()),
+            }
+        })
+        .collect();
+    for caps in location_regex().captures_iter(line) {
+        let whole = caps.get(0).expect("match has a whole group");
+        let (start, end) = (col_of(whole.start()), col_of(whole.end()));
+        if links.iter().any(|l| start < l.end && l.start < end) {
+            continue;
+        }
+        let number = |i: usize| caps.get(i).and_then(|m| m.as_str().parse().ok());
+        let Some(line_number) = number(1) else {
+            continue;
+        };
+        let path_end = whole.as_str().len()
+            - caps[1].len()
+            - 1
+            - caps.get(2).map_or(0, |m| m.as_str().len() + 1);
+        links.push(TextLink {
+            start,
+            end,
+            target: LinkTarget::File {
+                path: whole.as_str()[..path_end].to_string(),
+                line: line_number,
+                col: number(2),
+            },
+        });
+    }
+    links.sort_by_key(|l| l.start);
+    links
+}
+
+/// The link in `line` covering column `col`, if any.
+pub fn link_at(line: &str, col: usize) -> Option<TextLink> {
+    find_links(line).into_iter().find(|l| l.contains(col))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, line: usize, col: Option<usize>) -> LinkTarget {
+        LinkTarget::File {
+            path: path.to_string(),
+            line,
+            col,
+        }
+    }
+
+    #[test]
+    fn finds_urls_without_trailing_punctuation() {
+        let links = find_links("See https://example.com/a?b=1. Or (ftp://host/f).");
+        let targets: Vec<_> = links.iter().map(|l| l.target.clone()).collect();
+        assert_eq!(
+            targets,
+            vec![
+                LinkTarget::Url("https://example.com/a?b=1".into()),
+                LinkTarget::Url("ftp://host/f".into()),
+            ]
+        );
+        assert_eq!((links[0].start, links[0].end), (4, 29));
+        assert!(find_links("no links, just text: 12.5 and v1.2:3").is_empty());
+    }
+
+    #[test]
+    fn finds_file_locations_with_line_and_column() {
+        let links = find_links("error at src/main.rs:42:10, see lib.rs:7");
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].target, file("src/main.rs", 42, Some(10)));
+        assert_eq!((links[0].start, links[0].end), (9, 26));
+        assert_eq!(links[1].target, file("lib.rs", 7, None));
+        // A bare path is not a link; one inside a URL is not a second link.
+        assert!(find_links("edit src/main.rs").is_empty());
+        assert_eq!(find_links("file:///tmp/a.rs:3").len(), 1);
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let line = "→ ü.rs:1 ok";
+        let link = link_at(line, 3).expect("link");
+        assert_eq!((link.start, link.end), (2, 8));
+        assert_eq!(link.target, file("ü.rs", 1, None));
+        assert_eq!(link_at(line, 8), None);
+    }
+}
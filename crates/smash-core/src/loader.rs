@@ -0,0 +1,241 @@
+//! Background file loading, so a slow filesystem cannot stall the UI.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+
+use crate::encoding::{decode_detected, is_binary, TextEncoding};
+use crate::error::EditError;
+use crate::hex_dump::hex_dump;
+
+/// Bytes read per chunk; progress is reported after each one.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// A file's contents, once read.
+#[derive(Debug)]
+pub enum LoadedFile {
+    /// Decoded text and the encoding it was in.
+    Text {
+        text: String,
+        encoding: TextEncoding,
+    },
+    /// A binary file, as a [`hex_dump`] to show instead of its contents.
+    Binary { dump: String, len: usize },
+}
+
+/// State of a [`FileLoader`] as seen by the caller.
+#[derive(Debug)]
+pub enum LoadStatus {
+    /// Still reading: bytes read so far and the file size, if known.
+    Loading { read: u64, total: Option<u64> },
+    /// Finished; holds the file contents or the error that stopped it.
+    Done(Result<LoadedFile, EditError>),
+}
+
+enum LoadMessage {
+    Size(u64),
+    Progress(u64),
+    Done(Result<LoadedFile, EditError>),
+}
+
+/// Reads a file on a worker thread and reports progress.
+///
+/// Dropping the loader (or calling [`FileLoader::cancel`]) stops the
+/// worker after its current chunk.
+#[derive(Debug)]
+pub struct FileLoader {
+    path: PathBuf,
+    read: u64,
+    total: Option<u64>,
+    cancelled: Arc<AtomicBool>,
+    rx: Receiver<LoadMessage>,
+}
+
+impl FileLoader {
+    /// Start reading `path` in the background.
+    pub fn spawn(path: &Path) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancelled);
+        let worker_path = path.to_path_buf();
+        std::thread::spawn(move || {
+            let result = read_chunks(&worker_path, &flag, |size, read| match size {
+                Some(size) => {
+                    let _ = tx.send(LoadMessage::Size(size));
+                }
+                None => {
+                    let _ = tx.send(LoadMessage::Progress(read));
+                }
+            });
+            if !flag.load(Ordering::Relaxed) {
+                let _ = tx.send(LoadMessage::Done(result));
+            }
+        });
+        Self {
+            path: path.to_path_buf(),
+            read: 0,
+            total: None,
+            cancelled,
+            rx,
+        }
+    }
+
+    /// The file being loaded.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Drain progress from the worker. Returns `Done` once, when the
+    /// contents (or an error) are available.
+    pub fn poll(&mut self) -> LoadStatus {
+        loop {
+            match self.rx.try_recv() {
+                Ok(LoadMessage::Size(n)) => self.total = Some(n),
+                Ok(LoadMessage::Progress(n)) => self.read = n,
+                Ok(LoadMessage::Done(result)) => return LoadStatus::Done(result),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    return LoadStatus::Done(Err(EditError::Io(std::io::Error::new(
+                        std::io::ErrorKind::Interrupted,
+                        "file load stopped",
+                    ))));
+                }
+            }
+        }
+        LoadStatus::Loading {
+            read: self.read,
+            total: self.total,
+        }
+    }
+
+    /// Fraction of the file read so far, if the size is known.
+    pub fn progress(&self) -> Option<f64> {
+        match self.total {
+            Some(0) | None => None,
+            Some(total) => Some((self.read as f64 / total as f64).min(1.0)),
+        }
+    }
+
+    /// Ask the worker to stop. Any result it produces is discarded.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for FileLoader {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Read `path` chunk by chunk. `report` is called once with the file
+/// size (`Some(size)`, `0`) and then with `(None, bytes_read)` per chunk.
+fn read_chunks(
+    path: &Path,
+    cancelled: &AtomicBool,
+    mut report: impl FnMut(Option<u64>, u64),
+) -> Result<LoadedFile, EditError> {
+    // Stat and open happen here too: on a network mount either can hang.
+    let mut file = File::open(path)?;
+    if let Ok(meta) = file.metadata() {
+        report(Some(meta.len()), 0);
+    }
+    let mut data = Vec::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(EditError::Io(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "file load cancelled",
+            )));
+        }
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                data.extend_from_slice(&buf[..n]);
+                report(None, data.len() as u64);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    if is_binary(&data) {
+        // Formatted here too, so a large file cannot stall the UI.
+        return Ok(LoadedFile::Binary {
+            dump: hex_dump(&data),
+            len: data.len(),
+        });
+    }
+    let (text, encoding) = decode_detected(data)?;
+    Ok(LoadedFile::Text { text, encoding })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait(loader: &mut FileLoader) -> Result<LoadedFile, EditError> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if let LoadStatus::Done(result) = loader.poll() {
+                return result;
+            }
+            assert!(Instant::now() < deadline, "load timed out");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn loads_file_and_reports_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big.txt");
+        let text = "x".repeat(CHUNK_SIZE * 2 + 10);
+        std::fs::write(&path, &text).unwrap();
+
+        let mut loader = FileLoader::spawn(&path);
+        assert_eq!(loader.path(), path.as_path());
+        match wait(&mut loader).unwrap() {
+            LoadedFile::Text { text: loaded, .. } => assert_eq!(loaded, text),
+            other => panic!("expected text, got {:?}", other),
+        }
+        assert_eq!(loader.progress(), Some(1.0));
+    }
+
+    #[test]
+    fn reports_missing_file_and_dumps_binary_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut missing = FileLoader::spawn(&dir.path().join("nope"));
+        assert!(matches!(wait(&mut missing), Err(EditError::Io(_))));
+
+        let path = dir.path().join("bin");
+        std::fs::write(&path, [0x7f, 0x45, 0x4c, 0x46, 0x00]).unwrap();
+        let mut binary = FileLoader::spawn(&path);
+        match wait(&mut binary).unwrap() {
+            LoadedFile::Binary { dump, len } => {
+                assert_eq!(len, 5);
+                assert!(dump.starts_with("00000000  7f 45 4c 46 00"));
+            }
+            other => panic!("expected binary, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cancelled_read_stops_early() {
+        let cancelled = AtomicBool::new(true);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("f.txt");
+        std::fs::write(&path, "hello").unwrap();
+        let mut chunks = 0;
+        let result = read_chunks(&path, &cancelled, |size, _| {
+            if size.is_none() {
+                chunks += 1;
+            }
+        });
+        assert!(result.is_err());
+        assert_eq!(chunks, 0);
+    }
+}
@@ -1,6 +1,7 @@
 //! Logging subsystem helpers (REQ-NFR-020, REQ-NFR-021).
 //!
-//! Provides log-file rotation, default path resolution, and level conversion.
+//! Provides log-file rotation, default path resolution, level conversion,
+//! and parsing of per-target filter directives (`smash_lsp=debug`).
 //! The actual `tracing-subscriber` setup lives in the binary crate (`src/main.rs`)
 //! because `tracing-subscriber` is only a binary dependency.
 
@@ -107,8 +108,58 @@ pub fn log_level_to_filter(level: &str) -> &'static str {
     }
 }
 
+/// Parse a comma-separated filter such as `info,smash_lsp::rpc=debug`.
+///
+/// Each directive is either a bare level or `target=level`, where the
+/// target is a module path. Levels are lower-cased; `off` disables a
+/// target. Returns the normalised filter string, or a message naming the
+/// first invalid directive.
+pub fn parse_log_filter(spec: &str) -> Result<String, String> {
+    let mut directives = Vec::new();
+    for raw in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let (target, level) = match raw.split_once('=') {
+            Some((target, level)) => (Some(target.trim()), level.trim()),
+            None => (None, raw),
+        };
+        let level = level.to_ascii_lowercase();
+        if !matches!(
+            level.as_str(),
+            "trace" | "debug" | "info" | "warn" | "error" | "off"
+        ) {
+            return Err(format!("invalid level in '{}'", raw));
+        }
+        match target {
+            Some(t) if !is_valid_target(t) => {
+                return Err(format!("invalid target in '{}'", raw));
+            }
+            Some(t) => directives.push(format!("{}={}", t, level)),
+            None => directives.push(level),
+        }
+    }
+    if directives.is_empty() {
+        return Err("empty log filter".to_string());
+    }
+    Ok(directives.join(","))
+}
+
+/// Combine the base level with extra per-target directives.
+pub fn build_log_filter(level: &str, targets: &[String]) -> String {
+    std::iter::once(log_level_to_filter(level).to_string())
+        .chain(targets.iter().map(|t| t.trim().to_string()))
+        .filter(|d| !d.is_empty())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 // ── internal helpers ────────────────────────────────────────────────────────
 
+fn is_valid_target(target: &str) -> bool {
+    !target.is_empty()
+        && target
+            .split("::")
+            .all(|seg| !seg.is_empty() && seg.chars().all(|c| c.is_alphanumeric() || c == '_'))
+}
+
 fn rotated_path(base: &Path, index: u32) -> PathBuf {
     let name = base.file_name().unwrap_or_default().to_string_lossy();
     let parent = base.parent().unwrap_or_else(|| Path::new("."));
@@ -268,4 +319,29 @@ mod tests {
         assert_eq!(log_level_to_filter(""), "info");
         assert_eq!(log_level_to_filter("verbose"), "info");
     }
+
+    #[test]
+    fn parse_log_filter_accepts_levels_and_targets() {
+        assert_eq!(parse_log_filter("DEBUG").unwrap(), "debug");
+        assert_eq!(
+            parse_log_filter("info, smash_lsp::rpc=Trace ,smash_terminal=off").unwrap(),
+            "info,smash_lsp::rpc=trace,smash_terminal=off"
+        );
+    }
+
+    #[test]
+    fn parse_log_filter_rejects_bad_directives() {
+        assert!(parse_log_filter("").is_err());
+        assert!(parse_log_filter("loud").unwrap_err().contains("loud"));
+        assert!(parse_log_filter("smash lsp=debug").is_err());
+        assert!(parse_log_filter("smash_lsp::=debug").is_err());
+        assert!(parse_log_filter("=debug").is_err());
+    }
+
+    #[test]
+    fn build_log_filter_appends_targets() {
+        assert_eq!(build_log_filter("warn", &[]), "warn");
+        let targets = vec!["smash_lsp=debug".to_string(), " ".to_string()];
+        assert_eq!(build_log_filter("Info", &targets), "info,smash_lsp=debug");
+    }
 }
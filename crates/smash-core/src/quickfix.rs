@@ -0,0 +1,273 @@
+//! Problems reported in compiler and tool output, such as
+//! `src/main.c:4:5: error: ...`, collected into a quickfix list.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// How serious a reported problem is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+
+    fn parse(word: &str) -> Self {
+        match word {
+            "warning" => Severity::Warning,
+            "note" | "info" => Severity::Note,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// A problem at a place in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The path as written, relative to where the tool ran.
+    pub path: String,
+    /// 1-based, as written.
+    pub line: usize,
+    /// 1-based, as written; not all tools give one.
+    pub col: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+fn ansi_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*m").expect("ANSI regex is valid"))
+}
+
+/// `path:line[:col]: [severity:] message`, as from gcc, clang, go and
+/// most linters. The path needs a letter in it, so a time of day is not
+/// taken for one.
+fn gnu_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^((?:[A-Za-z]:)?[^\s:]*[A-Za-z][^\s:]*):(\d+):(?:(\d+):)?\s*(?:(fatal error|error|warning|note|info)(?:\[[^\]]*\])?:\s*)?(.*)$",
+        )
+        .expect("GNU error regex is valid")
+    })
+}
+
+/// `path(line[,col]): severity message`, as from MSVC and tsc.
+fn msvc_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^((?:[A-Za-z]:)?[^\s(]+)\((\d+)(?:,(\d+))?\)\s*:\s*(fatal error|error|warning|note)\b:?\s*(.*)$",
+        )
+        .expect("MSVC error regex is valid")
+    })
+}
+
+/// rustc's `error[E0308]: message`, whose location follows on a
+/// `  --> path:line:col` line.
+fn rustc_header_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^(error|warning)(?:\[[^\]]+\])?: (.+)$").expect("rustc header regex is valid")
+    })
+}
+
+fn rustc_location_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^\s*--> (.+?):(\d+):(\d+)$").expect("rustc location regex is valid")
+    })
+}
+
+/// Reads tool output a line at a time, remembering what it needs to
+/// match problems reported over several lines.
+#[derive(Debug, Default)]
+pub struct ErrorParser {
+    /// The last rustc-style header, waiting for its location.
+    header: Option<(Severity, String)>,
+}
+
+impl ErrorParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The problem `line` completes, if any.
+    pub fn parse_line(&mut self, line: &str) -> Option<Problem> {
+        let line = ansi_regex().replace_all(line, "");
+        let line = line.trim_end();
+        let number = |m: Option<regex::Match>| m.and_then(|m| m.as_str().parse().ok());
+
+        if let Some(caps) = rustc_location_regex().captures(line) {
+            let (severity, message) = self.header.take()?;
+            return Some(Problem {
+                path: caps[1].to_string(),
+                line: number(caps.get(2))?,
+                col: number(caps.get(3)),
+                severity,
+                message,
+            });
+        }
+        if let Some(caps) = rustc_header_regex().captures(line) {
+            self.header = Some((Severity::parse(&caps[1]), caps[2].to_string()));
+            return None;
+        }
+        let caps = gnu_regex()
+            .captures(line)
+            .or_else(|| msvc_regex().captures(line))?;
+        let severity = caps
+            .get(4)
+            .map_or(Severity::Error, |m| Severity::parse(m.as_str()));
+        let message = caps.get(5).map_or("", |m| m.as_str()).trim();
+        Some(Problem {
+            path: caps[1].to_string(),
+            line: number(caps.get(2))?,
+            col: number(caps.get(3)),
+            severity,
+            message: if message.is_empty() {
+                severity.label().to_string()
+            } else {
+                message.to_string()
+            },
+        })
+    }
+}
+
+/// The problems reported in `lines`, in order.
+pub fn parse_problems<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<Problem> {
+    let mut parser = ErrorParser::new();
+    lines
+        .into_iter()
+        .filter_map(|line| parser.parse_line(line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(
+        path: &str,
+        line: usize,
+        col: Option<usize>,
+        severity: Severity,
+        message: &str,
+    ) -> Problem {
+        Problem {
+            path: path.to_string(),
+            line,
+            col,
+            severity,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_gnu_style_lines() {
+        let output = [
+            "gcc -c main.c",
+            "main.c:4:5: error: 'x' undeclared (first use in this function)",
+            "lib/util.h:10: warning: unused parameter",
+            "Makefile:3: *** missing separator.  Stop.",
+            "\x1b[1mmain.c:7:1:\x1b[0m note: declared here",
+            "12:30:45: build started",
+        ];
+        assert_eq!(
+            parse_problems(output),
+            vec![
+                problem(
+                    "main.c",
+                    4,
+                    Some(5),
+                    Severity::Error,
+                    "'x' undeclared (first use in this function)"
+                ),
+                problem(
+                    "lib/util.h",
+                    10,
+                    None,
+                    Severity::Warning,
+                    "unused parameter"
+                ),
+                problem(
+                    "Makefile",
+                    3,
+                    None,
+                    Severity::Error,
+                    "*** missing separator.  Stop."
+                ),
+                problem("main.c", 7, Some(1), Severity::Note, "declared here"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_rustc_diagnostics_over_two_lines() {
+        let output = [
+            "   Compiling demo v0.1.0 (/work/demo)",
+            "error[E0308]: mismatched types",
+            " --> src/main.rs:2:18",
+            "  |",
+            "warning: unused variable: `y`",
+            "  --> src/lib.rs:10:9",
+            "   ::: src/other.rs:1:1",
+            "error: could not compile `demo` (bin \"demo\") due to 1 previous error",
+        ];
+        assert_eq!(
+            parse_problems(output),
+            vec![
+                problem(
+                    "src/main.rs",
+                    2,
+                    Some(18),
+                    Severity::Error,
+                    "mismatched types"
+                ),
+                problem(
+                    "src/lib.rs",
+                    10,
+                    Some(9),
+                    Severity::Warning,
+                    "unused variable: `y`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_msvc_and_tsc_style_lines() {
+        let output = [
+            r"C:\src\app.cpp(12): error C2065: 'x': undeclared identifier",
+            "src/index.ts(3,7): error TS2322: Type 'string' is not assignable",
+        ];
+        assert_eq!(
+            parse_problems(output),
+            vec![
+                problem(
+                    r"C:\src\app.cpp",
+                    12,
+                    None,
+                    Severity::Error,
+                    "C2065: 'x': undeclared identifier"
+                ),
+                problem(
+                    "src/index.ts",
+                    3,
+                    Some(7),
+                    Severity::Error,
+                    "TS2322: Type 'string' is not assignable"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Recently opened files, most recent first, kept across sessions for the
+//! recent-files picker and the file finder.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::StateFileError;
+use crate::state_file::{load_json, save_json};
+
+/// File name of the recent files list inside the state directory.
+pub const RECENT_FILES_FILE: &str = "recent.json";
+
+/// Current on-disk format version.
+const RECENT_VERSION: u32 = 1;
+
+/// Files remembered.
+pub const MAX_RECENT: usize = 100;
+
+/// The recently opened files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentFiles {
+    pub version: u32,
+    /// Absolute paths, most recent first.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+}
+
+impl Default for RecentFiles {
+    fn default() -> Self {
+        Self {
+            version: RECENT_VERSION,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl RecentFiles {
+    /// Remember `path` as the most recently opened, dropping an earlier
+    /// entry for it and the oldest beyond [`MAX_RECENT`].
+    pub fn record(&mut self, path: PathBuf) {
+        self.files.retain(|p| *p != path);
+        self.files.insert(0, path);
+        self.files.truncate(MAX_RECENT);
+    }
+
+    /// Forget `path`, e.g. once it no longer exists.
+    pub fn remove(&mut self, path: &Path) {
+        self.files.retain(|p| p != path);
+    }
+
+    /// Write the list to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), StateFileError> {
+        save_json(path, self)
+    }
+
+    /// Read the list from `path`. A missing file is an empty list.
+    pub fn load(path: &Path) -> Result<Self, StateFileError> {
+        Ok(load_json(path, "recent files", RECENT_VERSION)?.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_files_are_newest_first_without_duplicates() {
+        let mut recent = RecentFiles::default();
+        recent.record(PathBuf::from("/a"));
+        recent.record(PathBuf::from("/b"));
+        recent.record(PathBuf::from("/a"));
+        assert_eq!(recent.files, [PathBuf::from("/a"), PathBuf::from("/b")]);
+
+        for i in 0..MAX_RECENT {
+            recent.record(PathBuf::from(format!("/f{}", i)));
+        }
+        assert_eq!(recent.files.len(), MAX_RECENT);
+        assert!(!recent.files.contains(&PathBuf::from("/b")));
+
+        recent.remove(Path::new("/f0"));
+        assert_eq!(recent.files.len(), MAX_RECENT - 1);
+    }
+
+    #[test]
+    fn recent_files_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join(RECENT_FILES_FILE);
+        assert_eq!(RecentFiles::load(&path).unwrap(), RecentFiles::default());
+
+        let mut recent = RecentFiles::default();
+        recent.record(PathBuf::from("/src/main.rs"));
+        recent.save(&path).unwrap();
+        assert_eq!(RecentFiles::load(&path).unwrap(), recent);
+
+        std::fs::write(&path, r#"{"version": 99, "files": []}"#).unwrap();
+        assert!(matches!(
+            RecentFiles::load(&path),
+            Err(StateFileError::TooNew { .. })
+        ));
+    }
+}
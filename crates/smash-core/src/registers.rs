@@ -0,0 +1,162 @@
+//! Vim-style registers: text yanked and deleted, kept under one-character
+//! names so it can be pasted again later.
+//!
+//! The unnamed register (`"`) and `+` / `*` are the system clipboard and
+//! are not stored here; the editor reads and writes the clipboard for
+//! them. `_` is the black hole register: text sent there is dropped.
+
+use std::collections::BTreeMap;
+
+/// Register names in the order the register viewer lists them.
+pub const REGISTER_ORDER: &str = "0123456789-abcdefghijklmnopqrstuvwxyz";
+
+/// Text held by a register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterContent {
+    pub text: String,
+    /// Whether the text was whole lines (`yy`, `dd`), pasted as lines.
+    pub linewise: bool,
+}
+
+impl RegisterContent {
+    pub fn new(text: impl Into<String>, linewise: bool) -> Self {
+        Self {
+            text: text.into(),
+            linewise,
+        }
+    }
+}
+
+/// Whether `name` can follow `"` to name a register.
+pub fn is_register_name(name: char) -> bool {
+    name.is_ascii_alphanumeric() || matches!(name, '"' | '-' | '_' | '+' | '*')
+}
+
+/// Whether `name` is the system clipboard rather than a stored register.
+pub fn is_clipboard_register(name: char) -> bool {
+    matches!(name, '"' | '+' | '*')
+}
+
+/// The numbered, small-delete and named registers.
+#[derive(Debug, Clone, Default)]
+pub struct Registers {
+    registers: BTreeMap<char, RegisterContent>,
+}
+
+impl Registers {
+    /// Store text just yanked (`deleted == false`) or deleted. Into a
+    /// named register `a`–`z` when `name` is one, appending for `A`–`Z`;
+    /// otherwise a yank goes to `0`, a delete of lines to `1` (shifting
+    /// the older ones up to `9`) and a smaller delete to `-`.
+    pub fn record(&mut self, name: Option<char>, content: RegisterContent, deleted: bool) {
+        match name {
+            Some('_') => {}
+            Some(c) if c.is_ascii_lowercase() => {
+                self.registers.insert(c, content);
+            }
+            Some(c) if c.is_ascii_uppercase() => {
+                let lower = c.to_ascii_lowercase();
+                match self.registers.get_mut(&lower) {
+                    Some(existing) => {
+                        if content.linewise && !existing.linewise {
+                            existing.text.push('\n');
+                        }
+                        existing.text.push_str(&content.text);
+                        existing.linewise |= content.linewise;
+                        if existing.linewise && !existing.text.ends_with('\n') {
+                            existing.text.push('\n');
+                        }
+                    }
+                    None => {
+                        self.registers.insert(lower, content);
+                    }
+                }
+            }
+            _ if !deleted => {
+                self.registers.insert('0', content);
+            }
+            _ if content.linewise || content.text.contains('\n') => {
+                for n in (1..9).rev() {
+                    let from = char::from(b'0' + n);
+                    if let Some(older) = self.registers.remove(&from) {
+                        self.registers.insert(char::from(b'1' + n), older);
+                    }
+                }
+                self.registers.insert('1', content);
+            }
+            _ => {
+                self.registers.insert('-', content);
+            }
+        }
+    }
+
+    /// The content of register `name`; `A`–`Z` read `a`–`z`.
+    pub fn get(&self, name: char) -> Option<&RegisterContent> {
+        self.registers.get(&name.to_ascii_lowercase())
+    }
+
+    /// Registers holding text, in [`REGISTER_ORDER`].
+    pub fn iter(&self) -> impl Iterator<Item = (char, &RegisterContent)> {
+        REGISTER_ORDER
+            .chars()
+            .filter_map(|name| self.registers.get(&name).map(|c| (name, c)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(registers: &Registers, name: char) -> Option<&str> {
+        registers.get(name).map(|c| c.text.as_str())
+    }
+
+    #[test]
+    fn yanks_and_deletes_fill_the_numbered_registers() {
+        let mut registers = Registers::default();
+        registers.record(None, RegisterContent::new("word", false), false);
+        registers.record(None, RegisterContent::new("one\n", true), true);
+        registers.record(None, RegisterContent::new("two\n", true), true);
+        registers.record(None, RegisterContent::new("x", false), true);
+        assert_eq!(text(&registers, '0'), Some("word"));
+        assert_eq!(text(&registers, '1'), Some("two\n"));
+        assert_eq!(text(&registers, '2'), Some("one\n"));
+        assert_eq!(text(&registers, '-'), Some("x"));
+
+        for i in 0..10 {
+            registers.record(None, RegisterContent::new(format!("{}\n", i), true), true);
+        }
+        assert_eq!(text(&registers, '1'), Some("9\n"));
+        assert_eq!(text(&registers, '9'), Some("1\n"));
+
+        let names: String = registers.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, "0123456789-");
+    }
+
+    #[test]
+    fn named_registers_replace_append_and_ignore_the_black_hole() {
+        let mut registers = Registers::default();
+        registers.record(Some('a'), RegisterContent::new("foo", false), true);
+        registers.record(Some('A'), RegisterContent::new("bar", false), false);
+        assert_eq!(text(&registers, 'a'), Some("foobar"));
+        assert_eq!(text(&registers, 'A'), Some("foobar"));
+
+        registers.record(Some('A'), RegisterContent::new("line\n", true), false);
+        assert_eq!(
+            registers.get('a'),
+            Some(&RegisterContent::new("foobar\nline\n", true))
+        );
+        // Named yanks and deletes leave the numbered registers alone.
+        assert_eq!(registers.get('0'), None);
+        assert_eq!(registers.get('-'), None);
+
+        registers.record(Some('_'), RegisterContent::new("gone", false), true);
+        assert_eq!(registers.iter().count(), 1);
+        assert!(is_register_name('_') && is_clipboard_register('+'));
+        assert!(!is_register_name('!'));
+    }
+}
@@ -0,0 +1,263 @@
+//! Writing files without risking their contents: the new text goes to a
+//! temporary file next to the original, which is then renamed over it, so
+//! a crash mid-write leaves the old file intact.
+
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use tracing::debug;
+
+use crate::error::EditError;
+
+/// How a file is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteMode {
+    /// Write a temporary file and rename it over the original, keeping the
+    /// original's permissions and owner. Falls back to writing in place
+    /// where that cannot be done or would break something.
+    #[default]
+    Atomic,
+    /// Overwrite the file where it is. Works on any filesystem, but a
+    /// crash mid-write can leave it truncated.
+    InPlace,
+}
+
+/// Write `path` with whatever `write` produces.
+///
+/// Symlinks are followed, so the link stays and its target is updated.
+/// An atomic write falls back to writing in place for a file with other
+/// hard links (a rename would split them), when the directory is not
+/// writable, when the owner cannot be kept, or when the rename is
+/// refused (e.g. a file bind-mounted into a container). `write` may be
+/// called twice.
+pub fn write_file(
+    path: &Path,
+    mode: WriteMode,
+    write: impl Fn(&mut dyn Write) -> io::Result<()>,
+) -> Result<(), EditError> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if mode == WriteMode::Atomic && write_atomic(&target, &write)? {
+        return Ok(());
+    }
+    let mut out = BufWriter::new(File::create(&target)?);
+    write(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Whether `path` can be written: `false` if its permission bits make it
+/// read-only or opening it for writing is refused. A file that does not
+/// exist yet counts as writable.
+pub fn is_writable(path: &Path) -> bool {
+    match fs::metadata(path) {
+        Ok(meta) if meta.permissions().readonly() => false,
+        Ok(_) => match OpenOptions::new().write(true).open(path) {
+            Ok(_) => true,
+            Err(e) => e.kind() != io::ErrorKind::PermissionDenied,
+        },
+        Err(_) => true,
+    }
+}
+
+/// Write `path` through a temporary file. Returns `false`, having changed
+/// nothing, if it should be written in place instead.
+fn write_atomic(
+    path: &Path,
+    write: &impl Fn(&mut dyn Write) -> io::Result<()>,
+) -> Result<bool, EditError> {
+    let original = fs::metadata(path).ok();
+    if original.as_ref().is_some_and(has_other_links) {
+        debug!("{} has hard links; writing in place", path.display());
+        return Ok(false);
+    }
+    let tmp = temp_path(path);
+    let file = match OpenOptions::new().write(true).create_new(true).open(&tmp) {
+        Ok(file) => file,
+        Err(e) => {
+            debug!("cannot create {}: {}; writing in place", tmp.display(), e);
+            return Ok(false);
+        }
+    };
+    let written = fill(file, original.as_ref(), write);
+    let result = match written {
+        Ok(true) => fs::rename(&tmp, path).map_err(|e| {
+            debug!(
+                "cannot rename over {}: {}; writing in place",
+                path.display(),
+                e
+            );
+        }),
+        Ok(false) => Err(()),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+    };
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+        return Ok(false);
+    }
+    sync_dir(path);
+    Ok(true)
+}
+
+/// Write the new contents to `file`, give it `original`'s permissions and
+/// owner, and flush it to disk. Returns `false` if the owner cannot be
+/// kept.
+fn fill(
+    file: File,
+    original: Option<&Metadata>,
+    write: &impl Fn(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<bool> {
+    let mut out = BufWriter::new(file);
+    write(&mut out)?;
+    let file = out.into_inner().map_err(|e| e.into_error())?;
+    if let Some(original) = original {
+        if !keep_owner(&file, original) {
+            return Ok(false);
+        }
+        file.set_permissions(original.permissions())?;
+    }
+    file.sync_all()?;
+    Ok(true)
+}
+
+/// A hidden file next to `path`, unique to this process.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.smash-{}.tmp", name, std::process::id()))
+}
+
+#[cfg(unix)]
+fn has_other_links(meta: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn has_other_links(_meta: &Metadata) -> bool {
+    false
+}
+
+/// Give `file` the owner and group of `original`, if they differ.
+#[cfg(unix)]
+fn keep_owner(file: &File, original: &Metadata) -> bool {
+    use std::os::unix::fs::{fchown, MetadataExt};
+    let Ok(current) = file.metadata() else {
+        return false;
+    };
+    if (current.uid(), current.gid()) == (original.uid(), original.gid()) {
+        return true;
+    }
+    fchown(file, Some(original.uid()), Some(original.gid())).is_ok()
+}
+
+#[cfg(not(unix))]
+fn keep_owner(_file: &File, _original: &Metadata) -> bool {
+    true
+}
+
+/// Flush the rename to disk. Best effort: not every platform can open a
+/// directory.
+fn sync_dir(path: &Path) {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(text: &'static str) -> impl Fn(&mut dyn Write) -> io::Result<()> {
+        move |out| out.write_all(text.as_bytes())
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn atomic_write_replaces_the_file_and_leaves_no_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        write_file(&path, WriteMode::Atomic, contents("new\n")).unwrap();
+        fs::write(&path, "old\n").unwrap();
+        write_file(&path, WriteMode::Atomic, contents("newer\n")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "newer\n");
+        assert_eq!(entries(dir.path()), vec!["a.txt"]);
+    }
+
+    #[test]
+    fn failed_write_leaves_the_original_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "precious\n").unwrap();
+        let result = write_file(&path, WriteMode::Atomic, |out| {
+            out.write_all(b"half")?;
+            Err(io::Error::other("disk full"))
+        });
+        assert!(matches!(result, Err(EditError::Io(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "precious\n");
+        assert_eq!(entries(dir.path()), vec!["a.txt"]);
+    }
+
+    #[test]
+    fn in_place_write_overwrites_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "a much longer old text\n").unwrap();
+        write_file(&path, WriteMode::InPlace, contents("short\n")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "short\n");
+    }
+
+    #[test]
+    fn read_only_files_are_not_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        assert!(is_writable(&path));
+        fs::write(&path, "text\n").unwrap();
+        assert!(is_writable(&path));
+        let mut perms = fs::metadata(&path).unwrap().permissions();
+        perms.set_readonly(true);
+        fs::set_permissions(&path, perms).unwrap();
+        assert!(!is_writable(&path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_symlinks_and_hard_links_are_kept() {
+        use std::os::unix::fs::{symlink, MetadataExt, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("script.sh");
+        fs::write(&path, "echo old\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+        write_file(&path, WriteMode::Atomic, contents("echo new\n")).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+
+        let link = dir.path().join("link.sh");
+        symlink(&path, &link).unwrap();
+        write_file(&link, WriteMode::Atomic, contents("echo linked\n")).unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "echo linked\n");
+
+        let hard = dir.path().join("hard.sh");
+        fs::hard_link(&path, &hard).unwrap();
+        write_file(&path, WriteMode::Atomic, contents("echo both\n")).unwrap();
+        assert_eq!(fs::read_to_string(&hard).unwrap(), "echo both\n");
+        assert_eq!(fs::metadata(&path).unwrap().nlink(), 2);
+    }
+}
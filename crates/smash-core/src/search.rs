@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::position::{Position, Range};
 
 /// A search query: either a plain-text pattern or a regex.
@@ -23,6 +25,59 @@ impl SearchQuery {
     }
 }
 
+/// Toggles that decide how a typed pattern is searched for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Treat the pattern as a regular expression.
+    pub regex: bool,
+    /// Match letter case exactly.
+    pub case_sensitive: bool,
+    /// Only match whole words.
+    pub whole_word: bool,
+}
+
+impl SearchOptions {
+    /// Build the query for `pattern`. Fails only if the pattern is not
+    /// a valid regex.
+    pub fn query(&self, pattern: &str) -> Result<SearchQuery, regex::Error> {
+        if !self.regex && !self.whole_word {
+            return Ok(SearchQuery::Plain {
+                pattern: pattern.to_string(),
+                case_sensitive: self.case_sensitive,
+            });
+        }
+        let body = if self.regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let body = if self.whole_word {
+            format!(r"\b(?:{})\b", body)
+        } else {
+            body
+        };
+        regex::RegexBuilder::new(&body)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map(SearchQuery::Regex)
+    }
+
+    /// Short names of the toggles that are on, e.g. `regex, case`.
+    pub fn describe(&self) -> String {
+        let names: Vec<&str> = [
+            (self.regex, "regex"),
+            (self.case_sensitive, "case"),
+            (self.whole_word, "word"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, name)| *name)
+        .collect();
+        names.join(", ")
+    }
+}
+
 /// A single search match.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SearchMatch {
@@ -87,6 +142,11 @@ impl SearchState {
         Some(&self.matches[idx])
     }
 
+    /// All matches, in buffer order.
+    pub fn matches(&self) -> &[SearchMatch] {
+        &self.matches
+    }
+
     /// Number of matches found.
     pub fn match_count(&self) -> usize {
         self.matches.len()
@@ -108,23 +168,44 @@ impl Default for SearchState {
 
 // --- Private helpers ---
 
-/// Convert a byte offset into (line, col) position within `text`.
-fn byte_offset_to_position(text: &str, byte_offset: usize) -> Position {
-    let mut line = 0;
-    let mut col = 0;
-    for (i, ch) in text.char_indices() {
-        if i == byte_offset {
-            return Position::new(line, col);
+/// Converts byte offsets into (line, col) positions within a text, walking
+/// forward from the last offset asked for so that increasing offsets cost
+/// one pass over the text in total.
+struct PositionFinder<'a> {
+    text: &'a str,
+    byte: usize,
+    pos: Position,
+}
+
+impl<'a> PositionFinder<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            byte: 0,
+            pos: Position::new(0, 0),
         }
-        if ch == '\n' {
-            line += 1;
-            col = 0;
-        } else {
-            col += 1;
+    }
+
+    fn position(&mut self, byte_offset: usize) -> Position {
+        if byte_offset < self.byte {
+            *self = Self::new(self.text);
+        }
+        // An offset of text.len() means the position after the last char.
+        let mut reached = self.text.len();
+        for (i, ch) in self.text[self.byte..].char_indices() {
+            if self.byte + i >= byte_offset {
+                reached = self.byte + i;
+                break;
+            }
+            if ch == '\n' {
+                self.pos = Position::new(self.pos.line + 1, 0);
+            } else {
+                self.pos.col += 1;
+            }
         }
+        self.byte = reached;
+        self.pos
     }
-    // byte_offset == text.len() means the position after the last char
-    Position::new(line, col)
 }
 
 fn find_plain(text: &str, pattern: &str, case_sensitive: bool) -> Vec<SearchMatch> {
@@ -133,14 +214,18 @@ fn find_plain(text: &str, pattern: &str, case_sensitive: bool) -> Vec<SearchMatc
     }
 
     let mut results = Vec::new();
+    // Plain matches may overlap, so starts and ends are tracked separately
+    // to keep both walks moving forward.
+    let mut starts = PositionFinder::new(text);
+    let mut ends = PositionFinder::new(text);
 
     if case_sensitive {
         let mut start = 0;
         while let Some(idx) = text[start..].find(pattern) {
             let byte_start = start + idx;
             let byte_end = byte_start + pattern.len();
-            let start_pos = byte_offset_to_position(text, byte_start);
-            let end_pos = byte_offset_to_position(text, byte_end);
+            let start_pos = starts.position(byte_start);
+            let end_pos = ends.position(byte_end);
             results.push(SearchMatch {
                 range: Range::new(start_pos, end_pos),
                 byte_range: byte_start..byte_end,
@@ -154,8 +239,8 @@ fn find_plain(text: &str, pattern: &str, case_sensitive: bool) -> Vec<SearchMatc
         while let Some(idx) = lower_text[start..].find(&lower_pattern) {
             let byte_start = start + idx;
             let byte_end = byte_start + lower_pattern.len();
-            let start_pos = byte_offset_to_position(text, byte_start);
-            let end_pos = byte_offset_to_position(text, byte_end);
+            let start_pos = starts.position(byte_start);
+            let end_pos = ends.position(byte_end);
             results.push(SearchMatch {
                 range: Range::new(start_pos, end_pos),
                 byte_range: byte_start..byte_end,
@@ -168,10 +253,11 @@ fn find_plain(text: &str, pattern: &str, case_sensitive: bool) -> Vec<SearchMatc
 }
 
 fn find_regex(text: &str, re: &regex::Regex) -> Vec<SearchMatch> {
+    let mut positions = PositionFinder::new(text);
     re.find_iter(text)
         .map(|m| {
-            let start_pos = byte_offset_to_position(text, m.start());
-            let end_pos = byte_offset_to_position(text, m.end());
+            let start_pos = positions.position(m.start());
+            let end_pos = positions.position(m.end());
             SearchMatch {
                 range: Range::new(start_pos, end_pos),
                 byte_range: m.start()..m.end(),
@@ -341,12 +427,50 @@ mod tests {
     }
 
     #[test]
-    fn byte_offset_to_position_basic() {
+    fn position_finder_basic() {
         let text = "ab\ncd\nef";
-        assert_eq!(byte_offset_to_position(text, 0), Position::new(0, 0));
-        assert_eq!(byte_offset_to_position(text, 2), Position::new(0, 2));
-        assert_eq!(byte_offset_to_position(text, 3), Position::new(1, 0));
-        assert_eq!(byte_offset_to_position(text, 6), Position::new(2, 0));
-        assert_eq!(byte_offset_to_position(text, 8), Position::new(2, 2));
+        let mut finder = PositionFinder::new(text);
+        assert_eq!(finder.position(0), Position::new(0, 0));
+        assert_eq!(finder.position(2), Position::new(0, 2));
+        assert_eq!(finder.position(3), Position::new(1, 0));
+        assert_eq!(finder.position(6), Position::new(2, 0));
+        assert_eq!(finder.position(8), Position::new(2, 2));
+        // Going back starts the walk over.
+        assert_eq!(finder.position(4), Position::new(1, 1));
+    }
+
+    #[test]
+    fn options_build_plain_and_regex_queries() {
+        let find = |options: SearchOptions, pattern: &str, text: &str| {
+            let query = options.query(pattern).unwrap();
+            query
+                .find_all(text)
+                .into_iter()
+                .map(|m| m.byte_range)
+                .collect::<Vec<_>>()
+        };
+        let text = "Foo foobar foo";
+        let plain = SearchOptions::default();
+        assert_eq!(find(plain, "foo", text), vec![0..3, 4..7, 11..14]);
+        let case = SearchOptions {
+            case_sensitive: true,
+            ..plain
+        };
+        assert_eq!(find(case, "foo", text), vec![4..7, 11..14]);
+        let word = SearchOptions {
+            whole_word: true,
+            ..plain
+        };
+        assert_eq!(find(word, "foo", text), vec![0..3, 11..14]);
+        // Without the regex toggle, metacharacters are literal.
+        assert_eq!(find(word, "a.b", "a.b axb"), vec![0..3]);
+        let regex = SearchOptions {
+            regex: true,
+            ..plain
+        };
+        assert_eq!(find(regex, "f.o", text), vec![0..3, 4..7, 11..14]);
+        assert!(regex.query("(").is_err());
+        assert_eq!(regex.describe(), "regex");
+        assert_eq!(plain.describe(), "");
     }
 }
@@ -0,0 +1,233 @@
+//! Recent searches and named search presets, kept per project so complex
+//! patterns need not be retyped in the next session.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::StateFileError;
+use crate::search::SearchOptions;
+use crate::state_file::{load_json, save_json};
+
+/// File name of the search history inside the state directory.
+pub const SEARCH_HISTORY_FILE: &str = "search_history.json";
+
+/// Current on-disk format version.
+const HISTORY_VERSION: u32 = 1;
+
+/// Recent searches kept per project.
+pub const MAX_RECENT: usize = 50;
+
+/// A search as it was run: the pattern, its options and, for a
+/// find-and-replace, the replacement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub pattern: String,
+    #[serde(default)]
+    pub options: SearchOptions,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+}
+
+/// A search saved under a name, such as "TODO scan".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchPreset {
+    pub name: String,
+    #[serde(flatten)]
+    pub search: SavedSearch,
+}
+
+/// The searches of one project.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectSearches {
+    /// Workspace directory; `None` for files opened outside one.
+    pub project: Option<PathBuf>,
+    /// Most recent first.
+    #[serde(default)]
+    pub recent: Vec<SavedSearch>,
+    /// In the order they were first saved.
+    #[serde(default)]
+    pub presets: Vec<SearchPreset>,
+}
+
+impl ProjectSearches {
+    /// Remember `search` as the most recent, dropping an earlier copy and
+    /// the oldest entries beyond [`MAX_RECENT`].
+    pub fn record(&mut self, search: SavedSearch) {
+        self.recent.retain(|s| *s != search);
+        self.recent.insert(0, search);
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    /// Save `search` as `name`, replacing a preset of the same name.
+    pub fn save_preset(&mut self, name: &str, search: SavedSearch) {
+        let preset = SearchPreset {
+            name: name.to_string(),
+            search,
+        };
+        match self.presets.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    /// Delete the preset called `name`. Returns whether there was one.
+    pub fn remove_preset(&mut self, name: &str) -> bool {
+        let before = self.presets.len();
+        self.presets.retain(|p| p.name != name);
+        self.presets.len() != before
+    }
+}
+
+/// Search history for every project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchHistory {
+    pub version: u32,
+    #[serde(default)]
+    pub projects: Vec<ProjectSearches>,
+}
+
+impl Default for SearchHistory {
+    fn default() -> Self {
+        Self {
+            version: HISTORY_VERSION,
+            projects: Vec::new(),
+        }
+    }
+}
+
+impl SearchHistory {
+    /// The searches of `project`, if any were recorded.
+    pub fn project(&self, project: Option<&Path>) -> Option<&ProjectSearches> {
+        self.projects
+            .iter()
+            .find(|p| p.project.as_deref() == project)
+    }
+
+    /// The searches of `project`, added if it has none yet.
+    pub fn project_mut(&mut self, project: Option<&Path>) -> &mut ProjectSearches {
+        let index = match self
+            .projects
+            .iter()
+            .position(|p| p.project.as_deref() == project)
+        {
+            Some(index) => index,
+            None => {
+                self.projects.push(ProjectSearches {
+                    project: project.map(Path::to_path_buf),
+                    ..Default::default()
+                });
+                self.projects.len() - 1
+            }
+        };
+        &mut self.projects[index]
+    }
+
+    /// Write the history to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), StateFileError> {
+        save_json(path, self)
+    }
+
+    /// Read the history from `path`. A missing file is an empty history.
+    pub fn load(path: &Path) -> Result<Self, StateFileError> {
+        Ok(load_json(path, "search history", HISTORY_VERSION)?.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(pattern: &str) -> SavedSearch {
+        SavedSearch {
+            pattern: pattern.to_string(),
+            options: SearchOptions::default(),
+            replacement: None,
+        }
+    }
+
+    #[test]
+    fn recent_searches_are_newest_first_without_duplicates() {
+        let mut searches = ProjectSearches::default();
+        searches.record(search("foo"));
+        searches.record(search("bar"));
+        searches.record(search("foo"));
+        let patterns: Vec<&str> = searches.recent.iter().map(|s| s.pattern.as_str()).collect();
+        assert_eq!(patterns, ["foo", "bar"]);
+
+        for i in 0..MAX_RECENT {
+            searches.record(search(&i.to_string()));
+        }
+        assert_eq!(searches.recent.len(), MAX_RECENT);
+        assert_eq!(searches.recent[0].pattern, (MAX_RECENT - 1).to_string());
+    }
+
+    #[test]
+    fn presets_are_replaced_by_name() {
+        let mut searches = ProjectSearches::default();
+        searches.save_preset("todo", search("TODO"));
+        searches.save_preset("fixme", search("FIXME"));
+        searches.save_preset("todo", search("TODO|FIXME"));
+        assert_eq!(searches.presets.len(), 2);
+        assert_eq!(searches.presets[0].search.pattern, "TODO|FIXME");
+        assert!(searches.remove_preset("fixme"));
+        assert!(!searches.remove_preset("fixme"));
+        assert_eq!(searches.presets.len(), 1);
+    }
+
+    #[test]
+    fn history_is_kept_per_project_and_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join(SEARCH_HISTORY_FILE);
+        assert_eq!(
+            SearchHistory::load(&path).unwrap(),
+            SearchHistory::default()
+        );
+
+        let mut history = SearchHistory::default();
+        let work = Path::new("/work");
+        history.project_mut(Some(work)).record(SavedSearch {
+            pattern: r"TODO\(\w+\)".to_string(),
+            options: SearchOptions {
+                regex: true,
+                ..Default::default()
+            },
+            replacement: Some("DONE".to_string()),
+        });
+        history
+            .project_mut(Some(work))
+            .save_preset("todo", search("TODO"));
+        history.project_mut(None).record(search("scratch"));
+        assert_eq!(history.projects.len(), 2);
+        assert!(history.project(Some(Path::new("/other"))).is_none());
+
+        history.save(&path).unwrap();
+        let loaded = SearchHistory::load(&path).unwrap();
+        assert_eq!(loaded, history);
+        let work = loaded.project(Some(work)).unwrap();
+        assert!(work.recent[0].options.regex);
+        assert_eq!(work.presets[0].name, "todo");
+        assert_eq!(loaded.project(None).unwrap().recent[0].pattern, "scratch");
+    }
+
+    #[test]
+    fn bad_and_future_files_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SEARCH_HISTORY_FILE);
+        std::fs::write(&path, "[").unwrap();
+        assert!(matches!(
+            SearchHistory::load(&path),
+            Err(StateFileError::Json(_))
+        ));
+
+        let future = SearchHistory {
+            version: HISTORY_VERSION + 1,
+            ..Default::default()
+        };
+        future.save(&path).unwrap();
+        assert!(matches!(
+            SearchHistory::load(&path),
+            Err(StateFileError::TooNew { .. })
+        ));
+    }
+}
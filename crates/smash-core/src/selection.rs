@@ -71,8 +71,7 @@ impl SelectionSet {
     /// Sort by start position and merge overlapping selections.
     fn normalize(&mut self) {
         // Sort by the start of each selection's range
-        self.selections
-            .sort_by(|a, b| a.range().start.cmp(&b.range().start));
+        self.selections.sort_by_key(|s| s.range().start);
 
         let mut merged: Vec<Selection> = Vec::with_capacity(self.selections.len());
         for sel in self.selections.drain(..) {
@@ -0,0 +1,184 @@
+//! Saved editor sessions: which files were open and where, so the next
+//! start can pick up where the last one left off.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::StateFileError;
+use crate::position::Position;
+use crate::state_file::{load_json, save_json};
+
+/// File name of the session inside the state directory.
+pub const SESSION_FILE: &str = "session.json";
+
+/// Current on-disk format version.
+const SESSION_VERSION: u32 = 1;
+
+/// Everything needed to restore the workspace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    /// Directory the editor was started in.
+    pub workspace: Option<PathBuf>,
+    /// Open files, in order.
+    pub buffers: Vec<SessionBuffer>,
+    /// Index into `buffers` of the file that had focus.
+    pub active_buffer: usize,
+    /// Pane split layout.
+    #[serde(default)]
+    pub panes: PaneLayout,
+    /// Pane that had focus.
+    #[serde(default)]
+    pub active_pane: usize,
+    /// Jump-back history, oldest first.
+    #[serde(default)]
+    pub jumps: Vec<SessionJump>,
+    /// Where the most recent edit was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_edit: Option<SessionJump>,
+    /// Named marks, in name order.
+    #[serde(default)]
+    pub marks: Vec<SessionMark>,
+}
+
+/// A location in a file, as kept in the jump list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionJump {
+    pub path: PathBuf,
+    pub position: Position,
+}
+
+/// A named mark and where it is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionMark {
+    pub name: char,
+    #[serde(flatten)]
+    pub location: SessionJump,
+}
+
+/// One open file and the view onto it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionBuffer {
+    pub path: PathBuf,
+    pub cursor: Position,
+    /// First visible line.
+    pub top_line: usize,
+    /// First visible column.
+    pub left_col: usize,
+}
+
+/// A pane tree: a single pane or a split of two sub-layouts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PaneLayout {
+    Leaf {
+        id: usize,
+    },
+    Split {
+        /// `true` for side-by-side panes, `false` for stacked ones.
+        vertical: bool,
+        /// Share of the space given to `first` (0.0 to 1.0).
+        ratio: f64,
+        first: Box<PaneLayout>,
+        second: Box<PaneLayout>,
+    },
+}
+
+impl Default for PaneLayout {
+    fn default() -> Self {
+        Self::Leaf { id: 0 }
+    }
+}
+
+impl Session {
+    /// An empty session for `workspace`.
+    pub fn new(workspace: Option<PathBuf>) -> Self {
+        Self {
+            version: SESSION_VERSION,
+            workspace,
+            buffers: Vec::new(),
+            active_buffer: 0,
+            panes: PaneLayout::default(),
+            active_pane: 0,
+            jumps: Vec::new(),
+            last_edit: None,
+            marks: Vec::new(),
+        }
+    }
+
+    /// The buffer that had focus, if any.
+    pub fn active(&self) -> Option<&SessionBuffer> {
+        self.buffers.get(self.active_buffer)
+    }
+
+    /// Write the session to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), StateFileError> {
+        save_json(path, self)
+    }
+
+    /// Read a session from `path`. Returns `Ok(None)` if there is none.
+    pub fn load(path: &Path) -> Result<Option<Self>, StateFileError> {
+        load_json(path, "session", SESSION_VERSION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join(SESSION_FILE);
+        let mut session = Session::new(Some(PathBuf::from("/work")));
+        session.buffers.push(SessionBuffer {
+            path: PathBuf::from("/work/src/main.rs"),
+            cursor: Position::new(41, 7),
+            top_line: 30,
+            left_col: 0,
+        });
+        session.panes = PaneLayout::Split {
+            vertical: true,
+            ratio: 0.5,
+            first: Box::new(PaneLayout::Leaf { id: 0 }),
+            second: Box::new(PaneLayout::Leaf { id: 1 }),
+        };
+        session.active_pane = 1;
+        session.jumps.push(SessionJump {
+            path: PathBuf::from("/work/src/lib.rs"),
+            position: Position::new(3, 0),
+        });
+        session.last_edit = session.jumps.first().cloned();
+        session.marks.push(SessionMark {
+            name: 'a',
+            location: SessionJump {
+                path: PathBuf::from("/work/src/main.rs"),
+                position: Position::new(10, 2),
+            },
+        });
+
+        session.save(&path).unwrap();
+        let loaded = Session::load(&path).unwrap().unwrap();
+        assert_eq!(loaded, session);
+        assert_eq!(loaded.active().unwrap().cursor, Position::new(41, 7));
+    }
+
+    #[test]
+    fn missing_and_bad_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SESSION_FILE);
+        assert!(Session::load(&path).unwrap().is_none());
+
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(matches!(Session::load(&path), Err(StateFileError::Json(_))));
+
+        let mut future = Session::new(None);
+        future.version = SESSION_VERSION + 1;
+        future.save(&path).unwrap();
+        assert!(matches!(
+            Session::load(&path),
+            Err(StateFileError::TooNew { .. })
+        ));
+    }
+}
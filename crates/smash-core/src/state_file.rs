@@ -0,0 +1,98 @@
+//! The JSON files the editor keeps its state in between runs (session,
+//! recent files, search history). Each carries a format `version`; a file
+//! written by a newer editor is refused rather than misread.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::StateFileError;
+use crate::safe_write::{write_file, WriteMode};
+
+/// Just the format version, read before the rest of the file.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+/// Write `value` to `path` as JSON, creating its directory if needed.
+/// The file is written through a temporary file that is synced and
+/// renamed over it (see [`write_file`]), so a crash leaves either the old
+/// or the new contents.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), StateFileError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec_pretty(value)?;
+    write_file(path, WriteMode::Atomic, |out| out.write_all(&json))?;
+    Ok(())
+}
+
+/// Read the `what` file at `path`, written by [`save_json`]. Returns
+/// `Ok(None)` if there is none, and an error if its version is newer than
+/// `supported`.
+pub fn load_json<T: DeserializeOwned>(
+    path: &Path,
+    what: &'static str,
+    supported: u32,
+) -> Result<Option<T>, StateFileError> {
+    let json = match std::fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let header: Header = serde_json::from_str(&json)?;
+    if header.version > supported {
+        return Err(StateFileError::TooNew {
+            what,
+            version: header.version,
+            supported,
+        });
+    }
+    Ok(Some(serde_json::from_str(&json)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct State {
+        version: u32,
+        names: Vec<String>,
+    }
+
+    #[test]
+    fn round_trips_and_creates_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("test.json");
+        assert!(load_json::<State>(&path, "test", 1).unwrap().is_none());
+
+        let state = State {
+            version: 1,
+            names: vec!["a".to_string()],
+        };
+        save_json(&path, &state).unwrap();
+        assert_eq!(load_json::<State>(&path, "test", 1).unwrap(), Some(state));
+        let leftovers = std::fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(leftovers, 1, "no temporary file is left behind");
+    }
+
+    #[test]
+    fn bad_and_newer_files_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.json");
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(matches!(
+            load_json::<State>(&path, "test", 1),
+            Err(StateFileError::Json(_))
+        ));
+
+        // A newer format is refused even if it would not parse.
+        std::fs::write(&path, r#"{"version": 2, "names": {}}"#).unwrap();
+        let err = load_json::<State>(&path, "test", 1).unwrap_err();
+        assert!(matches!(err, StateFileError::TooNew { version: 2, .. }));
+        assert_eq!(err.to_string(), "test format 2 is newer than supported (1)");
+    }
+}
@@ -1,10 +1,37 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::edit::EditCommand;
-use crate::position::Position;
+use crate::position::{Position, Range};
 
-/// Maximum number of undo nodes before pruning.
-const MAX_UNDO_NODES: usize = 10_000;
+/// Default maximum number of undo nodes before pruning.
+pub const MAX_UNDO_NODES: usize = 10_000;
+
+/// Default approximate memory budget of an undo tree (16 MiB).
+pub const DEFAULT_UNDO_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Longest pause between keystrokes that still undo together.
+pub const COALESCE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Limits on how large a buffer's undo history may grow.
+///
+/// When either limit is exceeded the oldest off-path branches are pruned
+/// first, then the oldest edits on the current path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoBudget {
+    /// Maximum number of recorded edits.
+    pub max_entries: usize,
+    /// Approximate maximum memory used by recorded edits, in bytes.
+    pub max_bytes: usize,
+}
+
+impl Default for UndoBudget {
+    fn default() -> Self {
+        Self {
+            max_entries: MAX_UNDO_NODES,
+            max_bytes: DEFAULT_UNDO_MEMORY_BYTES,
+        }
+    }
+}
 
 /// A node in the undo tree.
 #[derive(Debug, Clone)]
@@ -19,11 +46,67 @@ struct UndoNode {
     children: Vec<usize>,
     /// Index of the parent node (None for root).
     parent: Option<usize>,
-    /// When this edit was recorded.
-    #[allow(dead_code)]
+    /// When this edit (or the latest edit merged into it) was recorded.
     timestamp: Instant,
 }
 
+/// A one-character insert or delete, which may be merged with the
+/// keystrokes around it into a single undo step.
+#[derive(Debug, Clone, Copy)]
+enum Keystroke {
+    /// `ch` typed at `at`.
+    Insert { at: Position, ch: char },
+    /// `ch` deleted from `range`.
+    Delete { range: Range, ch: char },
+}
+
+impl Keystroke {
+    fn of(backward: &EditCommand, forward: &EditCommand) -> Option<Self> {
+        let single = |text: &str| {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) if ch != '\n' => Some(ch),
+                _ => None,
+            }
+        };
+        match (forward, backward) {
+            (EditCommand::Insert { pos, text }, _) => Some(Self::Insert {
+                at: *pos,
+                ch: single(text)?,
+            }),
+            (EditCommand::Delete { range }, EditCommand::Insert { text, .. })
+                if range.start.line == range.end.line && range.end.col == range.start.col + 1 =>
+            {
+                Some(Self::Delete {
+                    range: *range,
+                    ch: single(text)?,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `next` carries on the same run of typing or deleting: it is
+    /// right next to this one and does not start a new word.
+    fn continues_with(&self, next: &Self) -> bool {
+        let (adjacent, prev_ch, ch) = match (*self, *next) {
+            (Self::Insert { at: a, ch: p }, Self::Insert { at: b, ch }) => {
+                (b.line == a.line && b.col == a.col + 1, p, ch)
+            }
+            (Self::Delete { range: a, ch: p }, Self::Delete { range: b, ch }) => {
+                // Backspace walks left; Delete stays put.
+                (b.end == a.start || b.start == a.start, p, ch)
+            }
+            _ => return false,
+        };
+        adjacent && (is_word_char(prev_ch) || !is_word_char(ch))
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
 /// An arena-based undo tree.
 ///
 /// The tree starts with a sentinel root node (index 0). Edits are
@@ -33,11 +116,26 @@ struct UndoNode {
 pub struct UndoTree {
     nodes: Vec<UndoNode>,
     current: usize,
+    budget: UndoBudget,
+    /// Approximate bytes held by all non-root nodes.
+    bytes: usize,
+    /// The last keystroke merged into the current node, while further
+    /// keystrokes may still join it.
+    run: Option<Keystroke>,
+    /// Nesting depth of [`UndoTree::begin_group`].
+    group_depth: usize,
+    /// Whether the open group has recorded its node yet.
+    group_started: bool,
 }
 
 impl UndoTree {
     /// Create a new undo tree with a sentinel root.
     pub fn new() -> Self {
+        Self::with_budget(UndoBudget::default())
+    }
+
+    /// Create a new undo tree that prunes itself to `budget`.
+    pub fn with_budget(budget: UndoBudget) -> Self {
         let root = UndoNode {
             backward: EditCommand::Batch(Vec::new()),
             forward: EditCommand::Batch(Vec::new()),
@@ -49,36 +147,154 @@ impl UndoTree {
         Self {
             nodes: vec![root],
             current: 0,
+            budget,
+            bytes: 0,
+            run: None,
+            group_depth: 0,
+            group_started: false,
+        }
+    }
+
+    /// The active budget.
+    pub fn budget(&self) -> UndoBudget {
+        self.budget
+    }
+
+    /// Change the budget, pruning immediately if the tree exceeds it.
+    pub fn set_budget(&mut self, budget: UndoBudget) {
+        self.budget = budget;
+        if self.over_budget() {
+            self.prune();
         }
     }
 
+    /// Approximate memory used by the recorded edits, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.bytes
+    }
+
+    /// Drop all history, keeping the budget.
+    pub fn clear(&mut self) {
+        *self = Self::with_budget(self.budget);
+    }
+
+    /// Start a group: every edit recorded until the matching
+    /// [`UndoTree::end_group`] is undone and redone as one step. Groups
+    /// nest; only the outermost one counts.
+    pub fn begin_group(&mut self) {
+        if self.group_depth == 0 {
+            self.group_started = false;
+            self.run = None;
+        }
+        self.group_depth += 1;
+    }
+
+    /// Close the group opened by the matching [`UndoTree::begin_group`].
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+    }
+
     /// Record an edit. `backward` is the inverse (undo) op,
     /// `forward` is the original edit (redo) op.
+    ///
+    /// Typing or deleting one character right after the previous one, within
+    /// [`COALESCE_WINDOW`] and without starting a new word, joins the
+    /// previous undo step instead of making a new one.
     pub fn record(&mut self, backward: EditCommand, forward: EditCommand, cursor_before: Position) {
+        self.record_at(backward, forward, cursor_before, Instant::now());
+    }
+
+    /// [`UndoTree::record`] with the time of the edit given.
+    pub fn record_at(
+        &mut self,
+        backward: EditCommand,
+        forward: EditCommand,
+        cursor_before: Position,
+        at: Instant,
+    ) {
+        if self.group_depth > 0 {
+            if self.group_started && self.current != 0 {
+                self.merge_into_current(backward, forward, at);
+            } else {
+                self.push(backward, forward, cursor_before, at);
+                self.group_started = true;
+            }
+            return;
+        }
+        let keystroke = Keystroke::of(&backward, &forward);
+        let joins = match (&self.run, &keystroke) {
+            (Some(prev), Some(next)) => {
+                let node = &self.nodes[self.current];
+                node.children.is_empty()
+                    && at.saturating_duration_since(node.timestamp) <= COALESCE_WINDOW
+                    && prev.continues_with(next)
+            }
+            _ => false,
+        };
+        if joins {
+            self.merge_into_current(backward, forward, at);
+        } else {
+            self.push(backward, forward, cursor_before, at);
+        }
+        self.run = keystroke;
+    }
+
+    /// Fold an edit into the current node, after what it already holds.
+    fn merge_into_current(&mut self, backward: EditCommand, forward: EditCommand, at: Instant) {
+        let node = &mut self.nodes[self.current];
+        let before = node_bytes(&node.backward, &node.forward);
+        let old_forward = std::mem::replace(&mut node.forward, EditCommand::Batch(Vec::new()));
+        node.forward = concat(old_forward, forward);
+        let old_backward = std::mem::replace(&mut node.backward, EditCommand::Batch(Vec::new()));
+        node.backward = concat(backward, old_backward);
+        node.timestamp = at;
+        let after = node_bytes(&node.backward, &node.forward);
+        self.bytes = (self.bytes + after).saturating_sub(before);
+        if self.over_budget() {
+            self.prune();
+        }
+    }
+
+    fn push(
+        &mut self,
+        backward: EditCommand,
+        forward: EditCommand,
+        cursor_before: Position,
+        timestamp: Instant,
+    ) {
         let new_idx = self.nodes.len();
+        self.bytes += node_bytes(&backward, &forward);
         let node = UndoNode {
             backward,
             forward,
             cursor_before,
             children: Vec::new(),
             parent: Some(self.current),
-            timestamp: Instant::now(),
+            timestamp,
         };
         self.nodes.push(node);
         self.nodes[self.current].children.push(new_idx);
         self.current = new_idx;
 
-        if self.nodes.len() > MAX_UNDO_NODES {
+        if self.over_budget() {
             self.prune();
         }
     }
 
+    /// Whether the tree holds more than its budget allows.
+    fn over_budget(&self) -> bool {
+        // The sentinel root does not count as an entry.
+        self.nodes.len() - 1 > self.budget.max_entries || self.bytes > self.budget.max_bytes
+    }
+
     /// Undo: move to parent, returning the backward (undo) operation
     /// and the cursor position before that edit.
     pub fn undo(&mut self) -> Option<(EditCommand, Position)> {
         if self.current == 0 {
             return None;
         }
+        self.run = None;
+        self.group_started = false;
         let node = &self.nodes[self.current];
         let result = (node.backward.clone(), node.cursor_before);
         if let Some(parent) = node.parent {
@@ -96,6 +312,8 @@ impl UndoTree {
         }
         // Pick the last child (most recent branch)
         let child_idx = *children.last()?;
+        self.run = None;
+        self.group_started = false;
         self.current = child_idx;
         let node = &self.nodes[self.current];
         Some((node.forward.clone(), node.cursor_before))
@@ -136,8 +354,8 @@ impl UndoTree {
             }
         }
 
-        // Phase 1: remove off-path leaf nodes
-        while self.nodes.len() > MAX_UNDO_NODES {
+        // Phase 1: remove off-path leaf nodes, oldest first
+        while self.over_budget() {
             let found =
                 (1..self.nodes.len()).find(|&i| !path_set[i] && self.nodes[i].children.is_empty());
             match found {
@@ -149,7 +367,7 @@ impl UndoTree {
         }
 
         // Phase 2: collapse old path nodes near root
-        while self.nodes.len() > MAX_UNDO_NODES {
+        while self.over_budget() {
             let path_child = self.nodes[0]
                 .children
                 .iter()
@@ -176,6 +394,10 @@ impl UndoTree {
 
     /// Remove a leaf node and clean up parent references.
     fn remove_node(&mut self, idx: usize, path_set: &mut [bool]) {
+        let removed = &self.nodes[idx];
+        self.bytes = self
+            .bytes
+            .saturating_sub(node_bytes(&removed.backward, &removed.forward));
         if let Some(parent) = self.nodes[idx].parent {
             self.nodes[parent].children.retain(|&c| c != idx);
         }
@@ -209,6 +431,37 @@ impl UndoTree {
     }
 }
 
+/// `first` followed by `second`, as one command. Batches are flattened so
+/// long runs stay a single level deep.
+fn concat(first: EditCommand, second: EditCommand) -> EditCommand {
+    let mut cmds = match first {
+        EditCommand::Batch(cmds) => cmds,
+        cmd => vec![cmd],
+    };
+    match second {
+        EditCommand::Batch(more) => cmds.extend(more),
+        cmd => cmds.push(cmd),
+    }
+    EditCommand::Batch(cmds)
+}
+
+/// Approximate heap + inline size of a node holding these commands.
+fn node_bytes(backward: &EditCommand, forward: &EditCommand) -> usize {
+    std::mem::size_of::<UndoNode>() + command_bytes(backward) + command_bytes(forward)
+}
+
+fn command_bytes(cmd: &EditCommand) -> usize {
+    match cmd {
+        EditCommand::Insert { text, .. } | EditCommand::Replace { text, .. } => text.capacity(),
+        EditCommand::Delete { .. } => 0,
+        EditCommand::IndentLines { lines, .. } => lines.capacity() * std::mem::size_of::<usize>(),
+        EditCommand::Batch(cmds) => cmds
+            .iter()
+            .map(|c| std::mem::size_of::<EditCommand>() + command_bytes(c))
+            .sum(),
+    }
+}
+
 impl Default for UndoTree {
     fn default() -> Self {
         Self::new()
@@ -362,6 +615,189 @@ mod tests {
         assert!(tree.can_undo());
     }
 
+    #[test]
+    fn entry_budget_prunes_oldest_edits() {
+        let mut tree = UndoTree::with_budget(UndoBudget {
+            max_entries: 3,
+            max_bytes: usize::MAX,
+        });
+        for i in 0..5 {
+            tree.record(
+                delete_cmd(),
+                insert_cmd(&format!("{i}")),
+                Position::new(0, 0),
+            );
+        }
+        assert_eq!(tree.len(), 4); // root + 3 entries
+        assert!(tree.undo().is_some());
+        assert!(tree.undo().is_some());
+        assert!(tree.undo().is_some());
+        assert!(tree.undo().is_none());
+    }
+
+    #[test]
+    fn entry_budget_prunes_off_path_branches_first() {
+        let mut tree = UndoTree::with_budget(UndoBudget {
+            max_entries: 2,
+            max_bytes: usize::MAX,
+        });
+        tree.record(delete_cmd(), insert_cmd("a"), Position::new(0, 0));
+        tree.record(delete_cmd(), insert_cmd("b"), Position::new(0, 0));
+        tree.undo();
+        // Branch off "a": the abandoned "b" leaf is pruned, not "a".
+        tree.record(delete_cmd(), insert_cmd("c"), Position::new(0, 0));
+        assert_eq!(tree.len(), 3);
+        assert!(tree.undo().is_some());
+        assert!(tree.undo().is_some());
+        assert!(tree.undo().is_none());
+    }
+
+    #[test]
+    fn memory_budget_prunes_large_edits() {
+        let big = "x".repeat(4096);
+        let mut tree = UndoTree::with_budget(UndoBudget {
+            max_entries: usize::MAX,
+            max_bytes: 10_000,
+        });
+        for _ in 0..10 {
+            tree.record(delete_cmd(), insert_cmd(&big), Position::new(0, 0));
+        }
+        assert!(tree.memory_usage() <= 10_000);
+        assert!(tree.can_undo());
+    }
+
+    #[test]
+    fn memory_usage_tracks_record_and_clear() {
+        let mut tree = UndoTree::new();
+        assert_eq!(tree.memory_usage(), 0);
+        tree.record(delete_cmd(), insert_cmd("hello"), Position::new(0, 0));
+        assert!(tree.memory_usage() >= 5);
+        tree.clear();
+        assert_eq!(tree.memory_usage(), 0);
+        assert!(tree.is_empty());
+        assert_eq!(tree.budget(), UndoBudget::default());
+    }
+
+    #[test]
+    fn set_budget_prunes_immediately() {
+        let mut tree = UndoTree::new();
+        for i in 0..10 {
+            tree.record(
+                delete_cmd(),
+                insert_cmd(&format!("{i}")),
+                Position::new(0, 0),
+            );
+        }
+        tree.set_budget(UndoBudget {
+            max_entries: 4,
+            max_bytes: usize::MAX,
+        });
+        assert_eq!(tree.len(), 5);
+    }
+
+    fn typed(col: usize, ch: char) -> (EditCommand, EditCommand) {
+        let at = Position::new(0, col);
+        (
+            EditCommand::Delete {
+                range: Range::new(at, Position::new(0, col + 1)),
+            },
+            EditCommand::Insert {
+                pos: at,
+                text: ch.to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn keystrokes_coalesce_until_a_pause_or_new_word() {
+        let mut tree = UndoTree::new();
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        for (col, ch) in "ab cd".chars().enumerate() {
+            let (backward, forward) = typed(col, ch);
+            tree.record_at(
+                backward,
+                forward,
+                Position::new(0, col),
+                start + ms(col as u64),
+            );
+        }
+        // "ab " and "cd": a new word starts a new step.
+        assert_eq!(tree.len(), 3);
+        let (undo, cursor) = tree.undo().unwrap();
+        assert_eq!(cursor, Position::new(0, 3));
+        match undo {
+            EditCommand::Batch(cmds) => {
+                // Newest keystroke first.
+                assert_eq!(
+                    cmds[0],
+                    EditCommand::Delete {
+                        range: Range::new(Position::new(0, 4), Position::new(0, 5))
+                    }
+                );
+                assert_eq!(cmds.len(), 2);
+            }
+            other => panic!("expected a batch, got {:?}", other),
+        }
+        assert_eq!(tree.undo().unwrap().1, Position::new(0, 0));
+
+        // After an undo, typing starts over rather than joining.
+        let (backward, forward) = typed(0, 'x');
+        tree.record_at(backward, forward, Position::new(0, 0), start);
+        let (backward, forward) = typed(1, 'y');
+        let later = start + COALESCE_WINDOW + ms(1);
+        tree.record_at(backward, forward, Position::new(0, 1), later);
+        assert_eq!(tree.len(), 5, "a pause starts a new step");
+    }
+
+    #[test]
+    fn backspaces_coalesce() {
+        let mut tree = UndoTree::new();
+        let at = Instant::now();
+        for col in (0..3).rev() {
+            let range = Range::new(Position::new(0, col), Position::new(0, col + 1));
+            let backward = EditCommand::Insert {
+                pos: range.start,
+                text: "a".into(),
+            };
+            tree.record_at(
+                backward,
+                EditCommand::Delete { range },
+                Position::new(0, col + 1),
+                at,
+            );
+        }
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.undo().unwrap().1, Position::new(0, 3));
+    }
+
+    #[test]
+    fn groups_make_one_step() {
+        let mut tree = UndoTree::new();
+        tree.begin_group();
+        tree.record(insert_cmd("a"), insert_cmd("bb"), Position::new(0, 0));
+        tree.begin_group();
+        tree.record(insert_cmd("c"), insert_cmd("dd"), Position::new(1, 0));
+        tree.end_group();
+        tree.record(insert_cmd("e"), insert_cmd("ff"), Position::new(2, 0));
+        tree.end_group();
+        tree.record(insert_cmd("g"), insert_cmd("hh"), Position::new(3, 0));
+        assert_eq!(tree.len(), 3);
+
+        tree.undo();
+        let (undo, cursor) = tree.undo().unwrap();
+        assert_eq!(cursor, Position::new(0, 0));
+        assert_eq!(
+            undo,
+            EditCommand::Batch(vec![insert_cmd("e"), insert_cmd("c"), insert_cmd("a")])
+        );
+        let (redo, _) = tree.redo().unwrap();
+        assert_eq!(
+            redo,
+            EditCommand::Batch(vec![insert_cmd("bb"), insert_cmd("dd"), insert_cmd("ff")])
+        );
+    }
+
     #[test]
     fn is_empty_after_record_is_false() {
         let mut tree = UndoTree::new();
@@ -0,0 +1,375 @@
+//! Project-wide text search.
+//!
+//! [`WorkspaceSearch::spawn`] walks the workspace on a background thread,
+//! skipping what the fuzzy finder skips and anything matched by a
+//! `.gitignore`, then searches the files on a pool of worker threads.
+//! Matches stream back through [`WorkspaceSearch::poll`] as each file is
+//! finished, so results can be shown while the search is still running.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+
+use tracing::debug;
+
+use crate::fuzzy_finder::is_ignored;
+use crate::gitignore::{is_gitignored, GitIgnore};
+use crate::search::SearchQuery;
+
+/// Files larger than this are not searched.
+const MAX_FILE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Matched lines longer than this are cut short in [`WorkspaceMatch::text`].
+const MAX_LINE_CHARS: usize = 240;
+
+/// Upper bound on search worker threads.
+const MAX_WORKERS: usize = 8;
+
+/// A line containing a match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceMatch {
+    /// Path relative to the workspace root.
+    pub path: PathBuf,
+    /// Zero-based line of the first match on the line.
+    pub line: usize,
+    /// Zero-based character column of that match.
+    pub col: usize,
+    /// The matched line, without its line ending.
+    pub text: String,
+}
+
+#[derive(Debug)]
+enum SearchMessage {
+    Matches(Vec<WorkspaceMatch>),
+    Done { files: usize, truncated: bool },
+}
+
+/// A search running in the background.
+///
+/// Dropping it (or calling [`WorkspaceSearch::cancel`]) stops the workers
+/// after the file each is on.
+#[derive(Debug)]
+pub struct WorkspaceSearch {
+    rx: Receiver<SearchMessage>,
+    cancel: Arc<AtomicBool>,
+    max_results: usize,
+    found: usize,
+    files_searched: usize,
+    truncated: bool,
+    done: bool,
+}
+
+impl WorkspaceSearch {
+    /// Start searching the files under `root` for `query`, stopping once
+    /// `max_results` matching lines have been found.
+    pub fn spawn(root: PathBuf, query: SearchQuery, max_results: usize) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        std::thread::spawn(move || run(&root, &query, max_results, &flag, &tx));
+        Self {
+            rx,
+            cancel,
+            max_results,
+            found: 0,
+            files_searched: 0,
+            truncated: false,
+            done: false,
+        }
+    }
+
+    /// Matches found since the last poll, in no particular order.
+    pub fn poll(&mut self) -> Vec<WorkspaceMatch> {
+        let mut out = Vec::new();
+        while !self.done {
+            match self.rx.try_recv() {
+                Ok(SearchMessage::Matches(mut matches)) => {
+                    let room = self.max_results - self.found;
+                    if matches.len() > room {
+                        matches.truncate(room);
+                        self.truncated = true;
+                    }
+                    self.found += matches.len();
+                    out.extend(matches);
+                }
+                Ok(SearchMessage::Done { files, truncated }) => {
+                    self.files_searched = files;
+                    self.truncated |= truncated;
+                    self.done = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                // The search thread panicked; nothing more will arrive.
+                Err(TryRecvError::Disconnected) => self.done = true,
+            }
+        }
+        out
+    }
+
+    /// Whether every file has been searched (or the search was cut short).
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Number of files searched; known once the search is done.
+    pub fn files_searched(&self) -> usize {
+        self.files_searched
+    }
+
+    /// Whether the search stopped at the result limit.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Stop the search. Matches already sent can still be polled.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for WorkspaceSearch {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn run(
+    root: &Path,
+    query: &SearchQuery,
+    max_results: usize,
+    cancel: &AtomicBool,
+    tx: &Sender<SearchMessage>,
+) {
+    let files = walk_workspace(root, usize::MAX);
+    let next = AtomicUsize::new(0);
+    let found = AtomicUsize::new(0);
+    let searched = AtomicUsize::new(0);
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .clamp(1, MAX_WORKERS);
+    debug!(
+        "workspace search: {} files on {} workers",
+        files.len(),
+        workers
+    );
+    std::thread::scope(|scope| {
+        let (files, next, found, searched) = (&files, &next, &found, &searched);
+        for _ in 0..workers {
+            let tx = tx.clone();
+            scope.spawn(move || loop {
+                if cancel.load(Ordering::Relaxed) || found.load(Ordering::Relaxed) >= max_results {
+                    break;
+                }
+                let Some(rel) = files.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    break;
+                };
+                searched.fetch_add(1, Ordering::Relaxed);
+                let matches = search_file(root, rel, query);
+                if matches.is_empty() {
+                    continue;
+                }
+                found.fetch_add(matches.len(), Ordering::Relaxed);
+                if tx.send(SearchMessage::Matches(matches)).is_err() {
+                    break;
+                }
+            });
+        }
+    });
+    let found = found.load(Ordering::Relaxed);
+    let searched = searched.load(Ordering::Relaxed);
+    let _ = tx.send(SearchMessage::Done {
+        files: searched,
+        truncated: found > max_results || (found == max_results && searched < files.len()),
+    });
+}
+
+fn search_file(root: &Path, rel: &Path, query: &SearchQuery) -> Vec<WorkspaceMatch> {
+    let path = root.join(rel);
+    let too_big = std::fs::metadata(&path).map_or(true, |m| m.len() > MAX_FILE_BYTES);
+    if too_big {
+        return Vec::new();
+    }
+    let Ok(bytes) = std::fs::read(&path) else {
+        return Vec::new();
+    };
+    // Binary files: a NUL near the start, or not UTF-8 at all.
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        return Vec::new();
+    }
+    match std::str::from_utf8(&bytes) {
+        Ok(text) => search_lines(rel, text, query),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// One [`WorkspaceMatch`] per line of `text` that `query` matches.
+fn search_lines(rel: &Path, text: &str, query: &SearchQuery) -> Vec<WorkspaceMatch> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(line, content)| {
+            let first = query.find_all(content).into_iter().next()?;
+            Some(WorkspaceMatch {
+                path: rel.to_path_buf(),
+                line,
+                col: first.range.start.col,
+                text: content.chars().take(MAX_LINE_CHARS).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Collect up to `max_files` file paths under `root`, relative to it,
+/// leaving out what [`is_ignored`] and the `.gitignore` files along the
+/// way exclude. Paths are sorted.
+pub fn walk_workspace(root: &Path, max_files: usize) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    walk_workspace_each(root, &mut |rel| {
+        if files.len() >= max_files {
+            return false;
+        }
+        files.push(rel);
+        true
+    });
+    files.sort();
+    files
+}
+
+/// Hand each file [`walk_workspace`] would collect to `visit` as it is
+/// found, in directory order. The walk stops when `visit` returns
+/// `false`.
+pub fn walk_workspace_each(root: &Path, visit: &mut dyn FnMut(PathBuf) -> bool) {
+    let mut ignores = Vec::new();
+    walk_dir(root, root, &mut ignores, visit);
+}
+
+/// Returns `false` once `visit` has asked to stop.
+fn walk_dir(
+    root: &Path,
+    dir: &Path,
+    ignores: &mut Vec<GitIgnore>,
+    visit: &mut dyn FnMut(PathBuf) -> bool,
+) -> bool {
+    let Ok(read) = std::fs::read_dir(dir) else {
+        return true;
+    };
+    let pushed = match GitIgnore::load(dir) {
+        Some(ignore) => {
+            ignores.push(ignore);
+            true
+        }
+        None => false,
+    };
+    let mut keep_going = true;
+    for entry in read.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let is_dir = path.is_dir();
+        if is_ignored(&name.to_string_lossy(), is_dir) || is_gitignored(ignores, &path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            keep_going = walk_dir(root, &path, ignores, visit);
+        } else if path.is_file() {
+            if let Ok(rel) = path.strip_prefix(root) {
+                keep_going = visit(rel.to_path_buf());
+            }
+        }
+        if !keep_going {
+            break;
+        }
+    }
+    if pushed {
+        ignores.pop();
+    }
+    keep_going
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::create_dir_all(root.join("generated")).unwrap();
+        std::fs::write(root.join(".gitignore"), "generated/\n*.log\n").unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn run() {}\n// RUN it\n").unwrap();
+        std::fs::write(root.join("target/out.rs"), "run\n").unwrap();
+        std::fs::write(root.join("generated/api.rs"), "run\n").unwrap();
+        std::fs::write(root.join("build.log"), "run\n").unwrap();
+        std::fs::write(root.join("blob.bin"), b"run\0\x01").unwrap();
+        dir
+    }
+
+    fn collect(search: &mut WorkspaceSearch) -> Vec<WorkspaceMatch> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut all = Vec::new();
+        while !search.is_done() {
+            assert!(Instant::now() < deadline, "search did not finish");
+            all.extend(search.poll());
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        all.extend(search.poll());
+        all.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+        all
+    }
+
+    #[test]
+    fn walk_respects_gitignore() {
+        let dir = workspace();
+        let files = walk_workspace(dir.path(), 100);
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("blob.bin"),
+                PathBuf::from("src/lib.rs"),
+                PathBuf::from("src/main.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_search_streams_line_matches() {
+        let dir = workspace();
+        let query = SearchQuery::Plain {
+            pattern: "run".to_string(),
+            case_sensitive: false,
+        };
+        let mut search = WorkspaceSearch::spawn(dir.path().to_path_buf(), query, 100);
+        let matches = collect(&mut search);
+        let found: Vec<(String, usize, usize)> = matches
+            .iter()
+            .map(|m| (m.path.display().to_string(), m.line, m.col))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("src/lib.rs".to_string(), 0, 7),
+                ("src/lib.rs".to_string(), 1, 3),
+                ("src/main.rs".to_string(), 1, 4),
+            ]
+        );
+        assert_eq!(matches[2].text, "    run();");
+        assert_eq!(search.files_searched(), 3);
+        assert!(!search.is_truncated());
+    }
+
+    #[test]
+    fn regex_search_stops_at_the_limit() {
+        let dir = workspace();
+        let query = SearchQuery::Regex(regex::Regex::new(r"^\s*run\(").unwrap());
+        let mut search = WorkspaceSearch::spawn(dir.path().to_path_buf(), query, 100);
+        let matches = collect(&mut search);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, PathBuf::from("src/main.rs"));
+
+        let query = SearchQuery::Regex(regex::Regex::new("(?i)run").unwrap());
+        let mut search = WorkspaceSearch::spawn(dir.path().to_path_buf(), query, 2);
+        assert_eq!(collect(&mut search).len(), 2);
+        assert!(search.is_truncated());
+    }
+}
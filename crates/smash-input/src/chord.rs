@@ -0,0 +1,160 @@
+//! Parsing of key chords written as text, e.g. `ctrl-s` or `ctrl-x ctrl-f`.
+
+use crate::error::InputError;
+use crate::event::{Key, KeyEvent, Modifiers};
+use crate::keymap::KeySequence;
+
+/// Parse a whitespace-separated sequence of chords such as `ctrl-x ctrl-f`.
+///
+/// # Errors
+///
+/// Returns `InputError::InvalidKeySequence` if the text is empty or any
+/// chord cannot be parsed.
+pub fn parse_key_sequence(text: &str) -> Result<KeySequence, InputError> {
+    let seq = text
+        .split_whitespace()
+        .map(parse_key_event)
+        .collect::<Result<KeySequence, _>>()?;
+    if seq.is_empty() {
+        return Err(InputError::InvalidKeySequence(text.to_string()));
+    }
+    Ok(seq)
+}
+
+/// Parse a single chord: zero or more modifiers (`ctrl`, `alt`, `shift`,
+/// `super`) joined by `-` or `+`, then a key name or character.
+///
+/// Modifier and key names are case-insensitive. Letters are normalised
+/// the way terminals report them: lower-case, or upper-case with `shift`.
+///
+/// # Errors
+///
+/// Returns `InputError::InvalidKeySequence` for unknown modifiers or keys.
+pub fn parse_key_event(chord: &str) -> Result<KeyEvent, InputError> {
+    let invalid = || InputError::InvalidKeySequence(chord.to_string());
+    let mut modifiers = Modifiers::NONE;
+    let mut rest = chord;
+    // The key itself may be `-` or `+`, so only split where a modifier
+    // name is followed by a separator and something after it.
+    loop {
+        let last = rest.char_indices().last().map_or(0, |(i, _)| i);
+        let Some(idx) = rest[..last].find(['-', '+']) else {
+            break;
+        };
+        let modifier = match rest[..idx].to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Modifiers::CTRL,
+            "alt" | "meta" | "option" => Modifiers::ALT,
+            "shift" => Modifiers::SHIFT,
+            "super" | "cmd" => Modifiers::SUPER,
+            _ => return Err(invalid()),
+        };
+        modifiers = modifiers | modifier;
+        rest = &rest[idx + 1..];
+    }
+
+    let mut chars = rest.chars();
+    let key = match (chars.next(), chars.next()) {
+        (Some(c), None) => Key::Char(c),
+        _ => named_key(&rest.to_ascii_lowercase()).ok_or_else(invalid)?,
+    };
+    let key = match key {
+        Key::Char(c) if c.is_alphabetic() && modifiers.shift() => {
+            Key::Char(c.to_uppercase().next().unwrap_or(c))
+        }
+        Key::Char(c) if c.is_alphabetic() && modifiers != Modifiers::NONE => {
+            Key::Char(c.to_lowercase().next().unwrap_or(c))
+        }
+        key => key,
+    };
+    Ok(KeyEvent::new(key, modifiers))
+}
+
+fn named_key(name: &str) -> Option<Key> {
+    let key = match name {
+        "enter" | "return" => Key::Enter,
+        "esc" | "escape" => Key::Esc,
+        "tab" => Key::Tab,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" | "pgup" => Key::PageUp,
+        "pagedown" | "pgdn" => Key::PageDown,
+        "space" => Key::Char(' '),
+        _ => {
+            let n: u8 = name.strip_prefix('f')?.parse().ok()?;
+            if !(1..=24).contains(&n) {
+                return None;
+            }
+            Key::F(n)
+        }
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_letters() {
+        assert_eq!(parse_key_event("ctrl-s").unwrap(), KeyEvent::ctrl('s'));
+        assert_eq!(parse_key_event("Ctrl+S").unwrap(), KeyEvent::ctrl('s'));
+        assert_eq!(
+            parse_key_event("ctrl-shift-f").unwrap(),
+            KeyEvent::new(Key::Char('F'), Modifiers::CTRL | Modifiers::SHIFT)
+        );
+        assert_eq!(parse_key_event("alt-x").unwrap(), KeyEvent::alt('x'));
+        assert_eq!(parse_key_event("A").unwrap(), KeyEvent::char('A'));
+    }
+
+    #[test]
+    fn parses_named_keys_and_separator_characters() {
+        assert_eq!(
+            parse_key_event("shift-F3").unwrap(),
+            KeyEvent::new(Key::F(3), Modifiers::SHIFT)
+        );
+        assert_eq!(parse_key_event("ctrl-space").unwrap(), KeyEvent::ctrl(' '));
+        assert_eq!(
+            parse_key_event("PageDown").unwrap(),
+            KeyEvent::new(Key::PageDown, Modifiers::NONE)
+        );
+        assert_eq!(parse_key_event("ctrl--").unwrap(), KeyEvent::ctrl('-'));
+        assert_eq!(parse_key_event("alt-+").unwrap(), KeyEvent::alt('+'));
+        assert_eq!(parse_key_event("-").unwrap(), KeyEvent::char('-'));
+        assert_eq!(parse_key_event("alt-é").unwrap(), KeyEvent::alt('é'));
+    }
+
+    #[test]
+    fn parses_sequences() {
+        assert_eq!(
+            parse_key_sequence("ctrl-x  ctrl-f").unwrap(),
+            vec![KeyEvent::ctrl('x'), KeyEvent::ctrl('f')]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_chords() {
+        for bad in [
+            "",
+            "   ",
+            "hyper-x",
+            "ctrl-",
+            "ctrl-nope",
+            "f25",
+            "ctrl-x bogus",
+        ] {
+            assert!(
+                matches!(
+                    parse_key_sequence(bad),
+                    Err(InputError::InvalidKeySequence(_))
+                ),
+                "{bad:?} should be rejected"
+            );
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::InputError;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Direction {
     Left,
@@ -14,6 +16,15 @@ pub enum Command {
     DeleteBackward,
     DeleteForward,
     DeleteLine,
+    DuplicateLine,
+    MoveLineUp,
+    MoveLineDown,
+    JoinLines,
+    UppercaseSelection,
+    LowercaseSelection,
+    TitleCaseSelection,
+    TransposeChars,
+    TransposeWords,
     // Cursor movement
     MoveLeft,
     MoveRight,
@@ -22,56 +33,160 @@ pub enum Command {
     MoveWordLeft,
     MoveWordRight,
     MoveLineStart,
+    /// Move to the first non-blank character, or to column 0 from there.
+    SmartHome,
     MoveLineEnd,
     MoveBufferStart,
     MoveBufferEnd,
     PageUp,
     PageDown,
+    // Scrolling the view around the cursor
+    CenterCursor,
+    CursorToTop,
+    CursorToBottom,
+    /// Scroll the view one line without moving the cursor, unless it
+    /// would leave the view.
+    ScrollLineUp,
+    ScrollLineDown,
     // Selection
     SelectAll,
     ExtendSelection(Direction),
+    SelectWordLeft,
+    SelectWordRight,
+    SelectLineStart,
+    SelectSmartHome,
+    SelectLineEnd,
+    SelectBufferStart,
+    SelectBufferEnd,
     AddCursorAbove,
     AddCursorBelow,
+    AddCursorAtNextMatch,
+    // Clipboard
+    Copy,
+    Cut,
+    Paste,
+    PasteFromHistory,
+    /// Paste a Vim-style register by name.
+    PasteFromRegister(char),
+    ShowRegisters,
+    /// Replace the text just pasted with the next older kill ring entry.
+    YankPop,
+    CopyPath,
+    CopyRelativePath,
+    CopyFileUri,
+    PasteMarkdownLink,
     // File ops
+    NewBuffer,
     Save,
     SaveAs,
+    SaveAll,
     Open,
     Close,
+    ExportHighlighted,
+    ReopenWithEncoding,
+    SetLineEnding,
+    ToggleReadOnly,
+    SudoSave,
     // Search
     Find,
     FindReplace,
+    FindReplaceInteractive,
     FindNext,
     FindPrev,
+    ToggleSearchRegex,
+    ToggleSearchCase,
+    ToggleSearchWholeWord,
+    SearchPresets,
+    SaveSearchPreset,
+    WorkspaceSearch,
     // Undo
     Undo,
     Redo,
+    ClearUndoHistory,
     // Panes
     SplitVertical,
     SplitHorizontal,
     FocusNext,
     FocusPrev,
     ClosePane,
+    ToggleFileTree,
+    ToggleLineNumbers,
+    ToggleRulers,
+    ToggleCurrentLineHighlight,
+    ToggleIndentGuides,
+    ToggleMinimap,
+    ToggleScrollbar,
+    ToggleGitBlame,
     // Nav
     GoToLine,
+    OpenCommandLine,
     OpenCommandPalette,
     OpenFileFinder,
+    OpenRecent,
+    // Watch
+    WatchCommand,
+    RunShellCommand,
+    RunTask,
     // Terminal
     ToggleTerminal,
     NewTerminal,
+    TerminalInterrupt,
+    TerminalTerminate,
+    TerminalKill,
+    TerminalRespawn,
+    // Git
+    RebasePick,
+    RebaseReword,
+    RebaseSquash,
+    RebaseFixup,
+    AbortEdit,
+    DiffAgainstDisk,
+    DiffAgainstHead,
     // LSP
     LspHover,
     LspGotoDefinition,
+    LspGotoTypeDefinition,
+    LspGotoImplementation,
+    LspGotoDeclaration,
     LspFindReferences,
     LspCompletion,
     LspFormat,
+    LspFormatSelection,
     LspRename,
     LspCodeAction,
+    LspDocumentSymbols,
+    LspIncomingCalls,
+    LspOutgoingCalls,
+    LspWorkspaceSymbols,
     LspDiagnosticNext,
     LspDiagnosticPrev,
+    ShowDiagnosticAtCursor,
+    ShowDiagnostics,
     LspRestart,
     // Jump navigation
     JumpBack,
     JumpForward,
+    JumpToLastEdit,
+    SetMark(char),
+    GotoMark(char),
+    ListMarks,
+    AlternateBuffer,
+    GoToCompanionFile,
+    OpenLinkUnderCursor,
+    QuickfixNext,
+    QuickfixPrev,
+    // Info
+    FileInfo,
+    ShowHelp,
+    ShowHealth,
+    ToggleRenderStats,
+    // Config
+    OpenUserConfig,
+    OpenProjectConfig,
+    OpenLogFile,
+    ReloadConfig,
+    SetLogLevel,
+    SelectTheme,
     // Lifecycle
     Quit,
     ForceQuit,
@@ -79,6 +194,488 @@ pub enum Command {
     Noop,
 }
 
+/// Human-readable metadata describing a [`Command`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandInfo {
+    /// Group the command is listed under in help output.
+    pub category: &'static str,
+    /// One-line description of what the command does.
+    pub description: &'static str,
+}
+
+impl CommandInfo {
+    const fn new(category: &'static str, description: &'static str) -> Self {
+        Self {
+            category,
+            description,
+        }
+    }
+}
+
+impl Command {
+    /// Category and description used to document this command.
+    pub fn info(&self) -> CommandInfo {
+        use Command::*;
+        let (category, description) = match self {
+            InsertChar(_) => ("Editing", "Insert a character"),
+            InsertNewline => ("Editing", "Insert a newline"),
+            DeleteBackward => ("Editing", "Delete the character before the cursor"),
+            DeleteForward => ("Editing", "Delete the character after the cursor"),
+            DeleteLine => ("Editing", "Delete the current line"),
+            DuplicateLine => ("Editing", "Duplicate the current or selected lines"),
+            MoveLineUp => ("Editing", "Move the current or selected lines up"),
+            MoveLineDown => ("Editing", "Move the current or selected lines down"),
+            JoinLines => (
+                "Editing",
+                "Join the selected lines, or this line and the next",
+            ),
+            UppercaseSelection => ("Editing", "Upper-case the selection or word"),
+            LowercaseSelection => ("Editing", "Lower-case the selection or word"),
+            TitleCaseSelection => ("Editing", "Title-case the selection or word"),
+            TransposeChars => ("Editing", "Swap the characters around the cursor"),
+            TransposeWords => ("Editing", "Swap the word at the cursor with the next"),
+            MoveLeft => ("Movement", "Move left"),
+            MoveRight => ("Movement", "Move right"),
+            MoveUp => ("Movement", "Move up"),
+            MoveDown => ("Movement", "Move down"),
+            MoveWordLeft => ("Movement", "Move to the previous word"),
+            MoveWordRight => ("Movement", "Move to the next word"),
+            MoveLineStart => ("Movement", "Move to line start"),
+            SmartHome => (
+                "Movement",
+                "Move to the first non-blank character, then to line start",
+            ),
+            MoveLineEnd => ("Movement", "Move to line end"),
+            MoveBufferStart => ("Movement", "Move to buffer start"),
+            MoveBufferEnd => ("Movement", "Move to buffer end"),
+            PageUp => ("Movement", "Scroll up one page"),
+            PageDown => ("Movement", "Scroll down one page"),
+            CenterCursor => (
+                "Movement",
+                "Scroll the cursor line to the middle of the view",
+            ),
+            CursorToTop => ("Movement", "Scroll the cursor line to the top of the view"),
+            CursorToBottom => (
+                "Movement",
+                "Scroll the cursor line to the bottom of the view",
+            ),
+            ScrollLineUp => ("Movement", "Scroll the view up one line"),
+            ScrollLineDown => ("Movement", "Scroll the view down one line"),
+            SelectAll => ("Selection", "Select the whole buffer"),
+            ExtendSelection(Direction::Left) => ("Selection", "Extend selection left"),
+            ExtendSelection(Direction::Right) => ("Selection", "Extend selection right"),
+            ExtendSelection(Direction::Up) => ("Selection", "Extend selection up"),
+            ExtendSelection(Direction::Down) => ("Selection", "Extend selection down"),
+            SelectWordLeft => ("Selection", "Extend selection to the previous word"),
+            SelectWordRight => ("Selection", "Extend selection to the next word"),
+            SelectLineStart => ("Selection", "Extend selection to line start"),
+            SelectSmartHome => (
+                "Selection",
+                "Extend selection to the first non-blank character, then to line start",
+            ),
+            SelectLineEnd => ("Selection", "Extend selection to line end"),
+            SelectBufferStart => ("Selection", "Extend selection to buffer start"),
+            SelectBufferEnd => ("Selection", "Extend selection to buffer end"),
+            AddCursorAbove => ("Selection", "Add a cursor on the line above"),
+            AddCursorBelow => ("Selection", "Add a cursor on the line below"),
+            AddCursorAtNextMatch => (
+                "Selection",
+                "Select the word, or add a cursor at the next match of the selection",
+            ),
+            Copy => ("Clipboard", "Copy selection"),
+            Cut => ("Clipboard", "Cut selection"),
+            Paste => ("Clipboard", "Paste"),
+            PasteFromHistory => ("Clipboard", "Paste from the clipboard history"),
+            PasteFromRegister(_) => ("Clipboard", "Paste a named register"),
+            ShowRegisters => ("Clipboard", "Show the registers and paste one"),
+            YankPop => (
+                "Clipboard",
+                "Replace the text just pasted with an older clipboard entry",
+            ),
+            CopyPath => ("Clipboard", "Copy the absolute path of the file"),
+            CopyRelativePath => (
+                "Clipboard",
+                "Copy the path of the file relative to the workspace",
+            ),
+            CopyFileUri => ("Clipboard", "Copy the file:// URI of the file"),
+            PasteMarkdownLink => ("Clipboard", "Paste a copied path or URL as a Markdown link"),
+            Save => ("File", "Save the current file"),
+            SaveAs => ("File", "Save under a new name"),
+            ReopenWithEncoding => ("File", "Read the file again in another encoding"),
+            NewBuffer => ("File", "Start an empty, unnamed scratch buffer"),
+            SetLineEnding => ("File", "Convert the line endings to LF, CRLF or CR"),
+            ToggleReadOnly => ("File", "Allow or block editing the buffer"),
+            SudoSave => (
+                "File",
+                "Save through editor.sudo_command, for files you cannot write",
+            ),
+            SaveAll => ("File", "Save every modified file"),
+            Open => ("File", "Open a file"),
+            Close => ("File", "Close the editor"),
+            ExportHighlighted => ("File", "Export highlighted buffer or selection"),
+            Find => ("Search", "Find"),
+            FindReplace => ("Search", "Find and replace"),
+            FindReplaceInteractive => ("Search", "Find and replace, confirming each match"),
+            FindNext => ("Search", "Find next match"),
+            FindPrev => ("Search", "Find previous match"),
+            ToggleSearchRegex => ("Search", "Toggle regex search"),
+            ToggleSearchCase => ("Search", "Toggle case-sensitive search"),
+            ToggleSearchWholeWord => ("Search", "Toggle whole-word search"),
+            SearchPresets => ("Search", "Pick a saved or recent search"),
+            SaveSearchPreset => ("Search", "Save the last search as a preset"),
+            WorkspaceSearch => ("Search", "Search in workspace files"),
+            Undo => ("Undo", "Undo"),
+            Redo => ("Undo", "Redo"),
+            ClearUndoHistory => ("Undo", "Clear undo history"),
+            SplitVertical => ("Panes", "Split pane vertically"),
+            SplitHorizontal => ("Panes", "Split pane horizontally"),
+            FocusNext => ("Panes", "Focus next pane"),
+            FocusPrev => ("Panes", "Focus previous pane"),
+            ClosePane => ("Panes", "Close pane"),
+            ToggleFileTree => ("Panes", "Toggle the file tree sidebar"),
+            ToggleLineNumbers => ("Panes", "Show or hide line numbers"),
+            ToggleRulers => ("Panes", "Show or hide the column rulers"),
+            ToggleCurrentLineHighlight => ("Panes", "Turn cursor line highlighting on or off"),
+            ToggleIndentGuides => ("Panes", "Show or hide indent guides"),
+            ToggleMinimap => ("Panes", "Show or hide the minimap of the buffer"),
+            ToggleScrollbar => (
+                "Panes",
+                "Show or hide the scrollbar with its diagnostic, match and change marks",
+            ),
+            ToggleGitBlame => (
+                "Panes",
+                "Show or hide who last changed the cursor line (git blame)",
+            ),
+            GoToLine => ("Navigation", "Go to line"),
+            OpenCommandLine => ("Editing", "Run a command line such as :10,20d or :%s/a/b/g"),
+            OpenCommandPalette => ("Navigation", "Open command palette"),
+            OpenFileFinder => ("Navigation", "Find file"),
+            OpenRecent => ("Navigation", "Open a recently opened file"),
+            JumpBack => ("Navigation", "Jump back"),
+            JumpForward => ("Navigation", "Jump forward"),
+            JumpToLastEdit => ("Navigation", "Jump to where the last edit was made"),
+            SetMark(_) => ("Navigation", "Set a named mark at the cursor"),
+            GotoMark(_) => ("Navigation", "Jump to a named mark"),
+            ListMarks => ("Navigation", "Pick a mark to jump to"),
+            AlternateBuffer => ("Navigation", "Switch to the previously open file"),
+            GoToCompanionFile => (
+                "Navigation",
+                "Go to the file's test, or from a test to its source",
+            ),
+            OpenLinkUnderCursor => (
+                "Navigation",
+                "Open the URL or go to the file:line under the cursor",
+            ),
+            QuickfixNext => ("Navigation", "Go to the next item in the quickfix list"),
+            QuickfixPrev => ("Navigation", "Go to the previous item in the quickfix list"),
+            WatchCommand => ("Tools", "Run a command on save of matching files"),
+            RunShellCommand => (
+                "Tools",
+                "Run a shell command, or filter the selection through one",
+            ),
+            RunTask => ("Tools", "Run a task from the config and list its errors"),
+            ToggleTerminal => ("Tools", "Toggle terminal"),
+            NewTerminal => ("Tools", "Open a new terminal"),
+            TerminalInterrupt => ("Tools", "Send SIGINT to the terminal's foreground job"),
+            TerminalTerminate => ("Tools", "Send SIGTERM to the terminal shell"),
+            TerminalKill => ("Tools", "Send SIGKILL to the terminal shell"),
+            TerminalRespawn => ("Tools", "Restart the terminal shell"),
+            RebasePick => ("Git", "Rebase todo: pick the commits on the selected lines"),
+            RebaseReword => (
+                "Git",
+                "Rebase todo: reword the commits on the selected lines",
+            ),
+            RebaseSquash => (
+                "Git",
+                "Rebase todo: squash the commits on the selected lines",
+            ),
+            RebaseFixup => (
+                "Git",
+                "Rebase todo: fixup the commits on the selected lines",
+            ),
+            AbortEdit => (
+                "Git",
+                "Quit without saving and exit with an error, aborting the git commit or rebase",
+            ),
+            DiffAgainstDisk => ("File", "Diff the buffer against the saved file"),
+            DiffAgainstHead => ("Git", "Diff the buffer against the file in git HEAD"),
+            LspHover => ("LSP", "Show hover information"),
+            LspGotoDefinition => ("LSP", "Go to definition"),
+            LspGotoTypeDefinition => ("LSP", "Go to type definition"),
+            LspGotoImplementation => ("LSP", "Go to implementation"),
+            LspGotoDeclaration => ("LSP", "Go to declaration"),
+            LspFindReferences => ("LSP", "Find references"),
+            LspCompletion => ("LSP", "Trigger completion"),
+            LspFormat => ("LSP", "Format document"),
+            LspFormatSelection => ("LSP", "Format selection"),
+            LspRename => ("LSP", "Rename symbol"),
+            LspCodeAction => ("LSP", "Code actions"),
+            LspDocumentSymbols => ("LSP", "Go to symbol in file"),
+            LspIncomingCalls => ("LSP", "Show callers of the function at the cursor"),
+            LspOutgoingCalls => ("LSP", "Show calls made by the function at the cursor"),
+            LspWorkspaceSymbols => ("LSP", "Go to symbol in workspace"),
+            LspDiagnosticNext => ("LSP", "Next diagnostic"),
+            LspDiagnosticPrev => ("LSP", "Previous diagnostic"),
+            ShowDiagnosticAtCursor => ("LSP", "Show diagnostics on the cursor line"),
+            ShowDiagnostics => ("LSP", "List the file's diagnostics in the quickfix pane"),
+            LspRestart => ("LSP", "Restart language server"),
+            FileInfo => ("Help", "Show file, undo and memory statistics"),
+            ShowHelp => ("Help", "Show this keymap help"),
+            ShowHealth => ("Help", "Show clipboard, language server and path status"),
+            ToggleRenderStats => ("Help", "Toggle the render timing overlay"),
+            OpenUserConfig => ("Config", "Open user config"),
+            OpenProjectConfig => ("Config", "Open project config"),
+            OpenLogFile => ("Config", "Open log file"),
+            ReloadConfig => ("Config", "Reload config"),
+            SelectTheme => ("Config", "Switch the colour theme"),
+            SetLogLevel => ("Config", "Set log level and per-target filters"),
+            Quit => ("Lifecycle", "Quit"),
+            ForceQuit => ("Lifecycle", "Quit without saving"),
+            Noop => ("Lifecycle", "Do nothing"),
+        };
+        CommandInfo::new(category, description)
+    }
+
+    /// Look up an argument-free command by name, as written in the
+    /// `[keys]` config table.
+    ///
+    /// Matching ignores case, `_`, `-` and spaces, so `lsp_hover`,
+    /// `LspHover` and `lsp-hover` are equivalent. Selection extension is
+    /// named `extend_selection_<direction>`, setting and jumping to a mark
+    /// `set_mark_<c>` and `goto_mark_<c>`, and pasting a register
+    /// `paste_from_register_<c>`; `noop` unbinds a key.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InputError::UnknownCommand` if no command has that name.
+    pub fn from_name(name: &str) -> Result<Command, InputError> {
+        let wanted = normalize_name(name);
+        let direction = wanted
+            .strip_prefix("extendselection")
+            .and_then(|dir| match dir {
+                "left" => Some(Direction::Left),
+                "right" => Some(Direction::Right),
+                "up" => Some(Direction::Up),
+                "down" => Some(Direction::Down),
+                _ => None,
+            });
+        if let Some(dir) = direction {
+            return Ok(Command::ExtendSelection(dir));
+        }
+        let named = |prefix: &str| {
+            let mut rest = wanted.strip_prefix(prefix)?.chars();
+            match (rest.next(), rest.next()) {
+                (Some(c), None) if c.is_ascii_alphanumeric() => Some(c),
+                _ => None,
+            }
+        };
+        if let Some(c) = named("setmark") {
+            return Ok(Command::SetMark(c));
+        }
+        if let Some(c) = named("gotomark") {
+            return Ok(Command::GotoMark(c));
+        }
+        if let Some(c) = named("pastefromregister") {
+            return Ok(Command::PasteFromRegister(c));
+        }
+        PALETTE_COMMANDS
+            .iter()
+            .chain(EDITING_COMMANDS)
+            .find(|cmd| normalize_name(&format!("{:?}", cmd)) == wanted)
+            .cloned()
+            .ok_or_else(|| InputError::UnknownCommand(name.to_string()))
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Argument-free commands that are bindable but not offered by the
+/// palette (cursor motion and basic editing).
+const EDITING_COMMANDS: &[Command] = &[
+    Command::InsertNewline,
+    Command::DeleteBackward,
+    Command::DeleteForward,
+    Command::MoveLeft,
+    Command::MoveRight,
+    Command::MoveUp,
+    Command::MoveDown,
+    Command::MoveWordLeft,
+    Command::MoveWordRight,
+    Command::MoveLineStart,
+    Command::SmartHome,
+    Command::MoveLineEnd,
+    Command::MoveBufferStart,
+    Command::MoveBufferEnd,
+    Command::PageUp,
+    Command::PageDown,
+    Command::CenterCursor,
+    Command::CursorToTop,
+    Command::CursorToBottom,
+    Command::ScrollLineUp,
+    Command::ScrollLineDown,
+    Command::SelectWordLeft,
+    Command::SelectWordRight,
+    Command::SelectLineStart,
+    Command::SelectSmartHome,
+    Command::SelectLineEnd,
+    Command::SelectBufferStart,
+    Command::SelectBufferEnd,
+    Command::OpenCommandPalette,
+    Command::Noop,
+];
+
+/// Order in which command categories are presented in help output.
+pub const COMMAND_CATEGORIES: &[&str] = &[
+    "File",
+    "Editing",
+    "Movement",
+    "Selection",
+    "Clipboard",
+    "Search",
+    "Undo",
+    "Navigation",
+    "Panes",
+    "LSP",
+    "Tools",
+    "Git",
+    "Config",
+    "Help",
+    "Lifecycle",
+];
+
+/// Commands offered by the command palette: every command that takes no
+/// argument and makes sense to invoke by name.
+pub const PALETTE_COMMANDS: &[Command] = &[
+    Command::Save,
+    Command::SaveAs,
+    Command::SaveAll,
+    Command::Open,
+    Command::Close,
+    Command::ExportHighlighted,
+    Command::ReopenWithEncoding,
+    Command::SetLineEnding,
+    Command::NewBuffer,
+    Command::ToggleReadOnly,
+    Command::SudoSave,
+    Command::DeleteLine,
+    Command::DuplicateLine,
+    Command::MoveLineUp,
+    Command::MoveLineDown,
+    Command::JoinLines,
+    Command::UppercaseSelection,
+    Command::LowercaseSelection,
+    Command::TitleCaseSelection,
+    Command::TransposeChars,
+    Command::TransposeWords,
+    Command::SelectAll,
+    Command::AddCursorAbove,
+    Command::AddCursorBelow,
+    Command::AddCursorAtNextMatch,
+    Command::Copy,
+    Command::Cut,
+    Command::Paste,
+    Command::PasteFromHistory,
+    Command::ShowRegisters,
+    Command::YankPop,
+    Command::CopyPath,
+    Command::CopyRelativePath,
+    Command::CopyFileUri,
+    Command::PasteMarkdownLink,
+    Command::Find,
+    Command::FindReplace,
+    Command::FindReplaceInteractive,
+    Command::FindNext,
+    Command::FindPrev,
+    Command::ToggleSearchRegex,
+    Command::ToggleSearchCase,
+    Command::ToggleSearchWholeWord,
+    Command::SearchPresets,
+    Command::SaveSearchPreset,
+    Command::WorkspaceSearch,
+    Command::Undo,
+    Command::Redo,
+    Command::ClearUndoHistory,
+    Command::SplitVertical,
+    Command::SplitHorizontal,
+    Command::FocusNext,
+    Command::FocusPrev,
+    Command::ClosePane,
+    Command::ToggleFileTree,
+    Command::ToggleLineNumbers,
+    Command::ToggleRulers,
+    Command::ToggleCurrentLineHighlight,
+    Command::ToggleIndentGuides,
+    Command::ToggleMinimap,
+    Command::ToggleScrollbar,
+    Command::ToggleGitBlame,
+    Command::GoToLine,
+    Command::OpenCommandLine,
+    Command::OpenFileFinder,
+    Command::OpenRecent,
+    Command::JumpBack,
+    Command::JumpForward,
+    Command::JumpToLastEdit,
+    Command::ListMarks,
+    Command::AlternateBuffer,
+    Command::GoToCompanionFile,
+    Command::OpenLinkUnderCursor,
+    Command::QuickfixNext,
+    Command::QuickfixPrev,
+    Command::WatchCommand,
+    Command::RunShellCommand,
+    Command::RunTask,
+    Command::ToggleTerminal,
+    Command::NewTerminal,
+    Command::TerminalInterrupt,
+    Command::TerminalTerminate,
+    Command::TerminalKill,
+    Command::TerminalRespawn,
+    Command::RebasePick,
+    Command::RebaseReword,
+    Command::RebaseSquash,
+    Command::RebaseFixup,
+    Command::AbortEdit,
+    Command::DiffAgainstDisk,
+    Command::DiffAgainstHead,
+    Command::LspHover,
+    Command::LspGotoDefinition,
+    Command::LspGotoTypeDefinition,
+    Command::LspGotoImplementation,
+    Command::LspGotoDeclaration,
+    Command::LspFindReferences,
+    Command::LspCompletion,
+    Command::LspFormat,
+    Command::LspFormatSelection,
+    Command::LspRename,
+    Command::LspCodeAction,
+    Command::LspDocumentSymbols,
+    Command::LspIncomingCalls,
+    Command::LspOutgoingCalls,
+    Command::LspWorkspaceSymbols,
+    Command::LspDiagnosticNext,
+    Command::LspDiagnosticPrev,
+    Command::ShowDiagnosticAtCursor,
+    Command::ShowDiagnostics,
+    Command::LspRestart,
+    Command::FileInfo,
+    Command::ShowHelp,
+    Command::ShowHealth,
+    Command::ToggleRenderStats,
+    Command::OpenUserConfig,
+    Command::OpenProjectConfig,
+    Command::OpenLogFile,
+    Command::ReloadConfig,
+    Command::SetLogLevel,
+    Command::SelectTheme,
+    Command::Quit,
+    Command::ForceQuit,
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -142,6 +739,39 @@ mod tests {
         assert_eq!(movements.len(), 12);
     }
 
+    #[test]
+    fn command_info_has_known_category() {
+        for cmd in [
+            Command::Save,
+            Command::InsertChar('a'),
+            Command::ExtendSelection(Direction::Up),
+            Command::LspHover,
+            Command::ShowHelp,
+        ] {
+            let info = cmd.info();
+            assert!(COMMAND_CATEGORIES.contains(&info.category), "{:?}", cmd);
+            assert!(!info.description.is_empty());
+        }
+        assert_eq!(Command::Undo.info().category, "Undo");
+    }
+
+    #[test]
+    fn palette_commands_have_no_payload_or_noop() {
+        for cmd in PALETTE_COMMANDS {
+            assert!(!matches!(
+                cmd,
+                Command::InsertChar(_)
+                    | Command::ExtendSelection(_)
+                    | Command::SetMark(_)
+                    | Command::GotoMark(_)
+                    | Command::PasteFromRegister(_)
+                    | Command::Noop
+                    | Command::OpenCommandPalette
+            ));
+            assert!(COMMAND_CATEGORIES.contains(&cmd.info().category));
+        }
+    }
+
     #[test]
     fn command_all_file_ops() {
         let ops = [
@@ -152,4 +782,47 @@ mod tests {
         ];
         assert_eq!(ops.len(), 4);
     }
+
+    #[test]
+    fn from_name_ignores_case_and_separators() {
+        assert_eq!(Command::from_name("save").unwrap(), Command::Save);
+        assert_eq!(Command::from_name("LspHover").unwrap(), Command::LspHover);
+        assert_eq!(
+            Command::from_name("lsp-find_references").unwrap(),
+            Command::LspFindReferences
+        );
+        assert_eq!(
+            Command::from_name("move_word_left").unwrap(),
+            Command::MoveWordLeft
+        );
+        assert_eq!(Command::from_name("noop").unwrap(), Command::Noop);
+        assert_eq!(
+            Command::from_name("extend_selection_up").unwrap(),
+            Command::ExtendSelection(Direction::Up)
+        );
+        assert_eq!(
+            Command::from_name("set_mark_a").unwrap(),
+            Command::SetMark('a')
+        );
+        assert_eq!(
+            Command::from_name("goto-mark-1").unwrap(),
+            Command::GotoMark('1')
+        );
+        assert_eq!(
+            Command::from_name("paste_from_register_a").unwrap(),
+            Command::PasteFromRegister('a')
+        );
+    }
+
+    #[test]
+    fn from_name_rejects_unknown_and_payload_commands() {
+        assert!(matches!(
+            Command::from_name("frobnicate"),
+            Err(InputError::UnknownCommand(name)) if name == "frobnicate"
+        ));
+        assert!(Command::from_name("insert_char").is_err());
+        assert!(Command::from_name("extend_selection_sideways").is_err());
+        assert!(Command::from_name("set_mark").is_err());
+        assert!(Command::from_name("goto_mark_ab").is_err());
+    }
 }
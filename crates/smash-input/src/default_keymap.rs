@@ -1,4 +1,4 @@
-use crate::command::Command;
+use crate::command::{Command, Direction};
 use crate::event::{Key, KeyEvent, Modifiers};
 use crate::keymap::KeymapLayer;
 
@@ -7,9 +7,59 @@ pub fn create_default_keymap() -> KeymapLayer {
 
     // File ops
     layer.bind(vec![KeyEvent::ctrl('s')], Command::Save);
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('S'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::SaveAll,
+    );
     layer.bind(vec![KeyEvent::ctrl('q')], Command::Quit);
     layer.bind(vec![KeyEvent::ctrl('o')], Command::Open);
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('r'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )],
+        Command::OpenRecent,
+    );
     layer.bind(vec![KeyEvent::ctrl('w')], Command::ClosePane);
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('E'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::ExportHighlighted,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('W'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::WatchCommand,
+    );
+    layer.bind(vec![KeyEvent::alt('!')], Command::RunShellCommand);
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('B'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::RunTask,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::F(4), Modifiers::NONE)],
+        Command::QuickfixNext,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::F(4), Modifiers::SHIFT)],
+        Command::QuickfixPrev,
+    );
+
+    // Help
+    layer.bind(
+        vec![KeyEvent::new(Key::F(1), Modifiers::NONE)],
+        Command::ShowHelp,
+    );
 
     // Edit
     layer.bind(vec![KeyEvent::ctrl('z')], Command::Undo);
@@ -20,13 +70,35 @@ pub fn create_default_keymap() -> KeymapLayer {
         )],
         Command::Redo,
     );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('z'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )],
+        Command::ClearUndoHistory,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('G'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::FileInfo,
+    );
 
     // Search
     layer.bind(vec![KeyEvent::ctrl('f')], Command::Find);
     layer.bind(vec![KeyEvent::ctrl('h')], Command::FindReplace);
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('H'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::FindReplaceInteractive,
+    );
     layer.bind(vec![KeyEvent::ctrl('g')], Command::GoToLine);
     layer.bind(vec![KeyEvent::ctrl('p')], Command::OpenCommandPalette);
-    layer.bind(vec![KeyEvent::ctrl('d')], Command::AddCursorBelow);
+    layer.bind(vec![KeyEvent::alt(';')], Command::OpenCommandLine);
+    layer.bind(vec![KeyEvent::ctrl('d')], Command::AddCursorAtNextMatch);
     layer.bind(
         vec![KeyEvent::new(Key::F(3), Modifiers::NONE)],
         Command::FindNext,
@@ -43,6 +115,17 @@ pub fn create_default_keymap() -> KeymapLayer {
         )],
         Command::FindPrev,
     );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('f'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )],
+        Command::WorkspaceSearch,
+    );
+    layer.bind(vec![KeyEvent::alt('r')], Command::ToggleSearchRegex);
+    layer.bind(vec![KeyEvent::alt('c')], Command::ToggleSearchCase);
+    layer.bind(vec![KeyEvent::alt('w')], Command::ToggleSearchWholeWord);
+    layer.bind(vec![KeyEvent::alt('p')], Command::SearchPresets);
 
     // Navigation (no modifier)
     layer.bind(
@@ -81,7 +164,7 @@ pub fn create_default_keymap() -> KeymapLayer {
 
     layer.bind(
         vec![KeyEvent::new(Key::Home, Modifiers::NONE)],
-        Command::MoveLineStart,
+        Command::SmartHome,
     );
     layer.bind(
         vec![KeyEvent::new(Key::End, Modifiers::NONE)],
@@ -91,6 +174,25 @@ pub fn create_default_keymap() -> KeymapLayer {
         vec![KeyEvent::new(Key::Home, Modifiers::CTRL)],
         Command::MoveBufferStart,
     );
+    layer.bind(
+        vec![KeyEvent::new(Key::Up, Modifiers::ALT)],
+        Command::MoveLineUp,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Down, Modifiers::ALT)],
+        Command::MoveLineDown,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Down, Modifiers::ALT | Modifiers::SHIFT)],
+        Command::DuplicateLine,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('J'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::JoinLines,
+    );
     layer.bind(
         vec![KeyEvent::new(Key::End, Modifiers::CTRL)],
         Command::MoveBufferEnd,
@@ -113,21 +215,141 @@ pub fn create_default_keymap() -> KeymapLayer {
         vec![KeyEvent::new(Key::PageDown, Modifiers::NONE)],
         Command::PageDown,
     );
+    layer.bind(
+        vec![KeyEvent::new(Key::Up, Modifiers::CTRL)],
+        Command::ScrollLineUp,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Down, Modifiers::CTRL)],
+        Command::ScrollLineDown,
+    );
+    layer.bind(vec![KeyEvent::ctrl('l')], Command::CenterCursor);
 
     // Select all
     layer.bind(vec![KeyEvent::ctrl('a')], Command::SelectAll);
 
+    // Selection (Shift + movement)
+    layer.bind(
+        vec![KeyEvent::new(Key::Left, Modifiers::SHIFT)],
+        Command::ExtendSelection(Direction::Left),
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Right, Modifiers::SHIFT)],
+        Command::ExtendSelection(Direction::Right),
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Up, Modifiers::SHIFT)],
+        Command::ExtendSelection(Direction::Up),
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Down, Modifiers::SHIFT)],
+        Command::ExtendSelection(Direction::Down),
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Up, Modifiers::CTRL | Modifiers::ALT)],
+        Command::AddCursorAbove,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Down, Modifiers::CTRL | Modifiers::ALT)],
+        Command::AddCursorBelow,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('c'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )],
+        Command::CopyRelativePath,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('p'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )],
+        Command::CopyPath,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Left, Modifiers::CTRL | Modifiers::SHIFT)],
+        Command::SelectWordLeft,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Right,
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::SelectWordRight,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Home, Modifiers::SHIFT)],
+        Command::SelectSmartHome,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::End, Modifiers::SHIFT)],
+        Command::SelectLineEnd,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Home, Modifiers::CTRL | Modifiers::SHIFT)],
+        Command::SelectBufferStart,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::End, Modifiers::CTRL | Modifiers::SHIFT)],
+        Command::SelectBufferEnd,
+    );
+
+    // Clipboard
+    layer.bind(vec![KeyEvent::ctrl('c')], Command::Copy);
+    layer.bind(vec![KeyEvent::ctrl('x')], Command::Cut);
+    layer.bind(vec![KeyEvent::ctrl('v')], Command::Paste);
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('V'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::PasteFromHistory,
+    );
+
     // Terminal
     layer.bind(
         vec![KeyEvent::new(Key::Char('\\'), Modifiers::CTRL)],
         Command::ToggleTerminal,
     );
 
+    // File tree
+    layer.bind(vec![KeyEvent::ctrl('b')], Command::ToggleFileTree);
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('b'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )],
+        Command::ToggleGitBlame,
+    );
+
     // LSP
     layer.bind(
         vec![KeyEvent::new(Key::F(12), Modifiers::NONE)],
         Command::LspGotoDefinition,
     );
+    layer.bind(
+        vec![KeyEvent::new(Key::F(12), Modifiers::CTRL)],
+        Command::LspGotoImplementation,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::F(12),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::LspGotoTypeDefinition,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::F(12), Modifiers::ALT)],
+        Command::LspGotoDeclaration,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('H'),
+            Modifiers::ALT | Modifiers::SHIFT,
+        )],
+        Command::LspIncomingCalls,
+    );
     layer.bind(
         vec![KeyEvent::new(Key::F(12), Modifiers::SHIFT)],
         Command::LspFindReferences,
@@ -147,10 +369,19 @@ pub fn create_default_keymap() -> KeymapLayer {
         )],
         Command::LspFormat,
     );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('F'),
+            Modifiers::ALT | Modifiers::SHIFT,
+        )],
+        Command::LspFormatSelection,
+    );
     layer.bind(
         vec![KeyEvent::new(Key::Char('.'), Modifiers::CTRL)],
         Command::LspCodeAction,
     );
+    layer.bind(vec![KeyEvent::ctrl('r')], Command::LspDocumentSymbols);
+    layer.bind(vec![KeyEvent::ctrl('t')], Command::LspWorkspaceSymbols);
     layer.bind(
         vec![KeyEvent::new(Key::Char('K'), Modifiers::CTRL)],
         Command::LspHover,
@@ -163,6 +394,17 @@ pub fn create_default_keymap() -> KeymapLayer {
         vec![KeyEvent::new(Key::F(8), Modifiers::SHIFT)],
         Command::LspDiagnosticPrev,
     );
+    layer.bind(
+        vec![KeyEvent::new(Key::F(8), Modifiers::CTRL)],
+        Command::ShowDiagnosticAtCursor,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('M'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::ShowDiagnostics,
+    );
 
     // Jump navigation
     layer.bind(
@@ -173,6 +415,31 @@ pub fn create_default_keymap() -> KeymapLayer {
         vec![KeyEvent::new(Key::Char('I'), Modifiers::CTRL)],
         Command::JumpForward,
     );
+    layer.bind(
+        vec![KeyEvent::ctrl('k'), KeyEvent::ctrl('q')],
+        Command::JumpToLastEdit,
+    );
+    layer.bind(
+        vec![KeyEvent::ctrl('k'), KeyEvent::ctrl('u')],
+        Command::UppercaseSelection,
+    );
+    layer.bind(
+        vec![KeyEvent::ctrl('k'), KeyEvent::ctrl('l')],
+        Command::LowercaseSelection,
+    );
+    // Ctrl+^ arrives as Ctrl+6 from most terminals.
+    layer.bind(
+        vec![KeyEvent::new(Key::Char('6'), Modifiers::CTRL)],
+        Command::AlternateBuffer,
+    );
+    layer.bind(vec![KeyEvent::alt('t')], Command::GoToCompanionFile);
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::Char('o'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )],
+        Command::OpenLinkUnderCursor,
+    );
 
     layer
 }
@@ -181,6 +448,67 @@ pub fn create_default_keymap() -> KeymapLayer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn default_keymap_binds_alternate_and_companion_files() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::ctrl('6')];
+        assert_eq!(layer.get(&seq), Some(&Command::AlternateBuffer));
+        let seq = vec![KeyEvent::alt('t')];
+        assert_eq!(layer.get(&seq), Some(&Command::GoToCompanionFile));
+    }
+
+    #[test]
+    fn default_keymap_ctrl_alt_o_opens_link() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(
+            Key::Char('o'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::OpenLinkUnderCursor));
+    }
+
+    #[test]
+    fn default_keymap_binds_code_navigation() {
+        let layer = create_default_keymap();
+        let f12 = |modifiers| vec![KeyEvent::new(Key::F(12), modifiers)];
+        assert_eq!(
+            layer.get(&f12(Modifiers::NONE)),
+            Some(&Command::LspGotoDefinition)
+        );
+        assert_eq!(
+            layer.get(&f12(Modifiers::CTRL)),
+            Some(&Command::LspGotoImplementation)
+        );
+        assert_eq!(
+            layer.get(&f12(Modifiers::CTRL | Modifiers::SHIFT)),
+            Some(&Command::LspGotoTypeDefinition)
+        );
+        assert_eq!(
+            layer.get(&f12(Modifiers::ALT)),
+            Some(&Command::LspGotoDeclaration)
+        );
+        let seq = vec![KeyEvent::new(
+            Key::Char('H'),
+            Modifiers::ALT | Modifiers::SHIFT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::LspIncomingCalls));
+    }
+
+    #[test]
+    fn default_keymap_formats_document_and_selection() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(
+            Key::Char('F'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::LspFormat));
+        let seq = vec![KeyEvent::new(
+            Key::Char('F'),
+            Modifiers::ALT | Modifiers::SHIFT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::LspFormatSelection));
+    }
+
     #[test]
     fn default_keymap_ctrl_s_is_save() {
         let layer = create_default_keymap();
@@ -188,6 +516,77 @@ mod tests {
         assert_eq!(layer.get(&seq), Some(&Command::Save));
     }
 
+    #[test]
+    fn default_keymap_ctrl_shift_s_is_save_all() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(
+            Key::Char('S'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::SaveAll));
+    }
+
+    #[test]
+    fn default_keymap_ctrl_alt_f_searches_workspace() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(
+            Key::Char('f'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::WorkspaceSearch));
+    }
+
+    #[test]
+    fn default_keymap_ctrl_k_ctrl_q_jumps_to_last_edit() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::ctrl('k'), KeyEvent::ctrl('q')];
+        assert_eq!(layer.get(&seq), Some(&Command::JumpToLastEdit));
+    }
+
+    #[test]
+    fn default_keymap_ctrl_k_ctrl_u_and_l_change_case() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::ctrl('k'), KeyEvent::ctrl('u')];
+        assert_eq!(layer.get(&seq), Some(&Command::UppercaseSelection));
+        let seq = vec![KeyEvent::ctrl('k'), KeyEvent::ctrl('l')];
+        assert_eq!(layer.get(&seq), Some(&Command::LowercaseSelection));
+    }
+
+    #[test]
+    fn default_keymap_ctrl_alt_r_opens_recent_files() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(
+            Key::Char('r'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::OpenRecent));
+    }
+
+    #[test]
+    fn default_keymap_ctrl_r_and_ctrl_t_go_to_symbol() {
+        let layer = create_default_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::ctrl('r')]),
+            Some(&Command::LspDocumentSymbols)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::ctrl('t')]),
+            Some(&Command::LspWorkspaceSymbols)
+        );
+    }
+
+    #[test]
+    fn default_keymap_ctrl_b_toggles_file_tree_and_ctrl_alt_b_blame() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::ctrl('b')];
+        assert_eq!(layer.get(&seq), Some(&Command::ToggleFileTree));
+        let seq = vec![KeyEvent::new(
+            Key::Char('b'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::ToggleGitBlame));
+    }
+
     #[test]
     fn default_keymap_ctrl_q_is_quit() {
         let layer = create_default_keymap();
@@ -254,6 +653,147 @@ mod tests {
         assert_eq!(layer.get(&seq), Some(&Command::Find));
     }
 
+    #[test]
+    fn default_keymap_shift_arrows_extend_selection() {
+        let layer = create_default_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Left, Modifiers::SHIFT)]),
+            Some(&Command::ExtendSelection(Direction::Left))
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Down, Modifiers::SHIFT)]),
+            Some(&Command::ExtendSelection(Direction::Down))
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::new(
+                Key::Right,
+                Modifiers::CTRL | Modifiers::SHIFT
+            )]),
+            Some(&Command::SelectWordRight)
+        );
+    }
+
+    #[test]
+    fn default_keymap_multi_cursor_bindings() {
+        let layer = create_default_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::ctrl('d')]),
+            Some(&Command::AddCursorAtNextMatch)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Up, Modifiers::CTRL | Modifiers::ALT)]),
+            Some(&Command::AddCursorAbove)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Down, Modifiers::CTRL | Modifiers::ALT)]),
+            Some(&Command::AddCursorBelow)
+        );
+    }
+
+    #[test]
+    fn default_keymap_clipboard_bindings() {
+        let layer = create_default_keymap();
+        assert_eq!(layer.get(&[KeyEvent::ctrl('c')]), Some(&Command::Copy));
+        assert_eq!(layer.get(&[KeyEvent::ctrl('x')]), Some(&Command::Cut));
+        assert_eq!(layer.get(&[KeyEvent::ctrl('v')]), Some(&Command::Paste));
+        assert_eq!(
+            layer.get(&[KeyEvent::new(
+                Key::Char('V'),
+                Modifiers::CTRL | Modifiers::SHIFT,
+            )]),
+            Some(&Command::PasteFromHistory)
+        );
+        let copy_relative = vec![KeyEvent::new(
+            Key::Char('c'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )];
+        assert_eq!(layer.get(&copy_relative), Some(&Command::CopyRelativePath));
+        let copy_absolute = vec![KeyEvent::new(
+            Key::Char('p'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )];
+        assert_eq!(layer.get(&copy_absolute), Some(&Command::CopyPath));
+    }
+
+    #[test]
+    fn default_keymap_ctrl_shift_e_exports() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(
+            Key::Char('E'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::ExportHighlighted));
+    }
+
+    #[test]
+    fn default_keymap_undo_history_and_file_info_bindings() {
+        let layer = create_default_keymap();
+        let clear = vec![KeyEvent::new(
+            Key::Char('z'),
+            Modifiers::CTRL | Modifiers::ALT,
+        )];
+        assert_eq!(layer.get(&clear), Some(&Command::ClearUndoHistory));
+        let info = vec![KeyEvent::new(
+            Key::Char('G'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )];
+        assert_eq!(layer.get(&info), Some(&Command::FileInfo));
+    }
+
+    #[test]
+    fn default_keymap_ctrl_shift_w_watches() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(
+            Key::Char('W'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::WatchCommand));
+    }
+
+    #[test]
+    fn default_keymap_binds_tasks_and_the_quickfix_list() {
+        let layer = create_default_keymap();
+        let ctrl_shift = |c| {
+            vec![KeyEvent::new(
+                Key::Char(c),
+                Modifiers::CTRL | Modifiers::SHIFT,
+            )]
+        };
+        assert_eq!(layer.get(&ctrl_shift('B')), Some(&Command::RunTask));
+        assert_eq!(layer.get(&ctrl_shift('M')), Some(&Command::ShowDiagnostics));
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::F(4), Modifiers::NONE)]),
+            Some(&Command::QuickfixNext)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::F(4), Modifiers::SHIFT)]),
+            Some(&Command::QuickfixPrev)
+        );
+    }
+
+    #[test]
+    fn default_keymap_alt_bang_runs_shell_command() {
+        let layer = create_default_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('!')]),
+            Some(&Command::RunShellCommand)
+        );
+    }
+
+    #[test]
+    fn default_keymap_f1_shows_help() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(Key::F(1), Modifiers::NONE)];
+        assert_eq!(layer.get(&seq), Some(&Command::ShowHelp));
+    }
+
+    #[test]
+    fn default_keymap_ctrl_f8_shows_diagnostic() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(Key::F(8), Modifiers::CTRL)];
+        assert_eq!(layer.get(&seq), Some(&Command::ShowDiagnosticAtCursor));
+    }
+
     #[test]
     fn default_keymap_ctrl_a_is_select_all() {
         let layer = create_default_keymap();
@@ -281,6 +821,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn default_keymap_scrolls_the_view() {
+        let layer = create_default_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Up, Modifiers::CTRL)]),
+            Some(&Command::ScrollLineUp)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Down, Modifiers::CTRL)]),
+            Some(&Command::ScrollLineDown)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::ctrl('l')]),
+            Some(&Command::CenterCursor)
+        );
+    }
+
     #[test]
     fn default_keymap_ctrl_word_movement() {
         let layer = create_default_keymap();
@@ -299,7 +856,7 @@ mod tests {
         let layer = create_default_keymap();
         assert_eq!(
             layer.get(&[KeyEvent::new(Key::Home, Modifiers::NONE)]),
-            Some(&Command::MoveLineStart)
+            Some(&Command::SmartHome)
         );
         assert_eq!(
             layer.get(&[KeyEvent::new(Key::End, Modifiers::NONE)]),
@@ -307,6 +864,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn default_keymap_alt_arrows_move_and_duplicate_lines() {
+        let layer = create_default_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Up, Modifiers::ALT)]),
+            Some(&Command::MoveLineUp)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Down, Modifiers::ALT)]),
+            Some(&Command::MoveLineDown)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Down, Modifiers::ALT | Modifiers::SHIFT)]),
+            Some(&Command::DuplicateLine)
+        );
+        let join = vec![KeyEvent::new(
+            Key::Char('J'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )];
+        assert_eq!(layer.get(&join), Some(&Command::JoinLines));
+    }
+
     #[test]
     fn default_keymap_ctrl_home_end_buffer() {
         let layer = create_default_keymap();
@@ -340,6 +919,46 @@ mod tests {
         assert_eq!(layer.get(&seq), Some(&Command::FindPrev));
     }
 
+    #[test]
+    fn default_keymap_ctrl_shift_h_replaces_interactively() {
+        let layer = create_default_keymap();
+        let seq = vec![KeyEvent::new(
+            Key::Char('H'),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )];
+        assert_eq!(layer.get(&seq), Some(&Command::FindReplaceInteractive));
+    }
+
+    #[test]
+    fn default_keymap_alt_semicolon_opens_command_line() {
+        let layer = create_default_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::alt(';')]),
+            Some(&Command::OpenCommandLine)
+        );
+    }
+
+    #[test]
+    fn default_keymap_alt_keys_toggle_search_options() {
+        let layer = create_default_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('r')]),
+            Some(&Command::ToggleSearchRegex)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('c')]),
+            Some(&Command::ToggleSearchCase)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('w')]),
+            Some(&Command::ToggleSearchWholeWord)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('p')]),
+            Some(&Command::SearchPresets)
+        );
+    }
+
     #[test]
     fn default_keymap_ctrl_n_is_find_next() {
         let layer = create_default_keymap();
@@ -22,6 +22,11 @@
 //!   Ctrl-k          — kill (delete) to end of line
 //!   Ctrl-/          — undo
 //!   Ctrl-z          — undo (convenience alias)
+//!   Alt-w           — copy region
+//!   Ctrl-y          — yank (paste)
+//!   Ctrl-t / Alt-t  — transpose characters / words
+//!   Alt-u / Alt-l / Alt-c — upcase / downcase / capitalize word
+//!   Shift-arrows    — extend selection
 //!
 //! ## Search
 //!   Ctrl-s          — incremental search (find)
@@ -34,8 +39,9 @@
 //!   Alt-x           — command palette (M-x)
 //!   Ctrl-g          — cancel / keyboard-quit (→ Noop)
 //!   Ctrl-\\          — toggle terminal
+//!   F1              — keymap help
 
-use crate::command::Command;
+use crate::command::{Command, Direction};
 use crate::event::{Key, KeyEvent, Modifiers};
 use crate::keymap::KeymapLayer;
 
@@ -100,6 +106,8 @@ pub fn create_emacs_keymap() -> KeymapLayer {
         vec![KeyEvent::new(Key::Char('v'), Modifiers::ALT)],
         Command::PageUp,
     );
+    // Ctrl-l — recenter on the cursor line
+    layer.bind(vec![KeyEvent::ctrl('l')], Command::CenterCursor);
     // Alt-< — beginning of buffer
     layer.bind(
         vec![KeyEvent::new(Key::Char('<'), Modifiers::ALT)],
@@ -123,6 +131,24 @@ pub fn create_emacs_keymap() -> KeymapLayer {
     layer.bind(vec![KeyEvent::ctrl('/')], Command::Undo);
     // Ctrl-z — undo (convenience alias)
     layer.bind(vec![KeyEvent::ctrl('z')], Command::Undo);
+    // Alt-w — copy region
+    layer.bind(
+        vec![KeyEvent::new(Key::Char('w'), Modifiers::ALT)],
+        Command::Copy,
+    );
+    // Ctrl-y — yank
+    layer.bind(vec![KeyEvent::ctrl('y')], Command::Paste);
+    // Alt-y — yank-pop: cycle the yank through older kills
+    layer.bind(vec![KeyEvent::alt('y')], Command::YankPop);
+    // Ctrl-t / Alt-t — transpose characters / words
+    layer.bind(vec![KeyEvent::ctrl('t')], Command::TransposeChars);
+    layer.bind(vec![KeyEvent::alt('t')], Command::TransposeWords);
+    // Alt-u / Alt-l / Alt-c — upcase / downcase / capitalize word
+    layer.bind(vec![KeyEvent::alt('u')], Command::UppercaseSelection);
+    layer.bind(vec![KeyEvent::alt('l')], Command::LowercaseSelection);
+    layer.bind(vec![KeyEvent::alt('c')], Command::TitleCaseSelection);
+    // Alt-! — shell-command
+    layer.bind(vec![KeyEvent::alt('!')], Command::RunShellCommand);
 
     // ── Search ──────────────────────────────────────────────────
 
@@ -130,6 +156,14 @@ pub fn create_emacs_keymap() -> KeymapLayer {
     layer.bind(vec![KeyEvent::ctrl('s')], Command::Find);
     // Ctrl-r — reverse search
     layer.bind(vec![KeyEvent::ctrl('r')], Command::FindPrev);
+    // Alt-s g — search the workspace (grep)
+    layer.bind(
+        vec![
+            KeyEvent::new(Key::Char('s'), Modifiers::ALT),
+            KeyEvent::char('g'),
+        ],
+        Command::WorkspaceSearch,
+    );
 
     // ── File / Meta operations ──────────────────────────────────
 
@@ -153,6 +187,16 @@ pub fn create_emacs_keymap() -> KeymapLayer {
         vec![KeyEvent::ctrl('x'), KeyEvent::ctrl('s')],
         Command::Save,
     );
+    // Ctrl-x s — save all modified buffers
+    layer.bind(
+        vec![KeyEvent::ctrl('x'), KeyEvent::char('s')],
+        Command::SaveAll,
+    );
+    // Ctrl-x d — file tree (directory editor)
+    layer.bind(
+        vec![KeyEvent::ctrl('x'), KeyEvent::char('d')],
+        Command::ToggleFileTree,
+    );
     // Ctrl-x Ctrl-c — quit
     layer.bind(
         vec![KeyEvent::ctrl('x'), KeyEvent::ctrl('c')],
@@ -183,6 +227,23 @@ pub fn create_emacs_keymap() -> KeymapLayer {
         vec![KeyEvent::new(Key::Down, Modifiers::NONE)],
         Command::MoveDown,
     );
+    // Shift-arrows — extend selection
+    layer.bind(
+        vec![KeyEvent::new(Key::Left, Modifiers::SHIFT)],
+        Command::ExtendSelection(Direction::Left),
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Right, Modifiers::SHIFT)],
+        Command::ExtendSelection(Direction::Right),
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Up, Modifiers::SHIFT)],
+        Command::ExtendSelection(Direction::Up),
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::Down, Modifiers::SHIFT)],
+        Command::ExtendSelection(Direction::Down),
+    );
     // Home / End
     layer.bind(
         vec![KeyEvent::new(Key::Home, Modifiers::NONE)],
@@ -239,6 +300,16 @@ pub fn create_emacs_keymap() -> KeymapLayer {
         vec![KeyEvent::ctrl('c'), KeyEvent::ctrl('a')],
         Command::LspCodeAction,
     );
+    // Ctrl-c Ctrl-s — symbols in this file
+    layer.bind(
+        vec![KeyEvent::ctrl('c'), KeyEvent::ctrl('s')],
+        Command::LspDocumentSymbols,
+    );
+    // Ctrl-c Ctrl-w — symbols in the workspace
+    layer.bind(
+        vec![KeyEvent::ctrl('c'), KeyEvent::ctrl('w')],
+        Command::LspWorkspaceSymbols,
+    );
     // Alt-n / Alt-p — next/prev diagnostic
     layer.bind(
         vec![KeyEvent::new(Key::Char('n'), Modifiers::ALT)],
@@ -248,11 +319,59 @@ pub fn create_emacs_keymap() -> KeymapLayer {
         vec![KeyEvent::new(Key::Char('p'), Modifiers::ALT)],
         Command::LspDiagnosticPrev,
     );
-    // F12 — go to definition (also standard)
+    // Ctrl-c Ctrl-e — show diagnostics on the cursor line
+    layer.bind(
+        vec![KeyEvent::ctrl('c'), KeyEvent::ctrl('e')],
+        Command::ShowDiagnosticAtCursor,
+    );
+    // Ctrl-c ! l — list the file's diagnostics (as flycheck-list-errors)
+    layer.bind(
+        vec![
+            KeyEvent::ctrl('c'),
+            KeyEvent::char('!'),
+            KeyEvent::char('l'),
+        ],
+        Command::ShowDiagnostics,
+    );
+    // Ctrl-x p c — run a task (project-compile)
+    layer.bind(
+        vec![
+            KeyEvent::ctrl('x'),
+            KeyEvent::char('p'),
+            KeyEvent::char('c'),
+        ],
+        Command::RunTask,
+    );
+    // Alt-g n / Alt-g p — next/previous error
+    layer.bind(
+        vec![KeyEvent::alt('g'), KeyEvent::char('n')],
+        Command::QuickfixNext,
+    );
+    layer.bind(
+        vec![KeyEvent::alt('g'), KeyEvent::char('p')],
+        Command::QuickfixPrev,
+    );
+    // F12 — go to definition (also standard), with Ctrl for
+    // implementations, Ctrl-Shift for the type and Alt for the declaration
     layer.bind(
         vec![KeyEvent::new(Key::F(12), Modifiers::NONE)],
         Command::LspGotoDefinition,
     );
+    layer.bind(
+        vec![KeyEvent::new(Key::F(12), Modifiers::CTRL)],
+        Command::LspGotoImplementation,
+    );
+    layer.bind(
+        vec![KeyEvent::new(
+            Key::F(12),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        )],
+        Command::LspGotoTypeDefinition,
+    );
+    layer.bind(
+        vec![KeyEvent::new(Key::F(12), Modifiers::ALT)],
+        Command::LspGotoDeclaration,
+    );
 
     // Jump navigation
     layer.bind(
@@ -263,6 +382,17 @@ pub fn create_emacs_keymap() -> KeymapLayer {
         vec![KeyEvent::new(Key::Char('I'), Modifiers::CTRL)],
         Command::JumpForward,
     );
+    // Ctrl+^ arrives as Ctrl+6 from most terminals.
+    layer.bind(
+        vec![KeyEvent::new(Key::Char('6'), Modifiers::CTRL)],
+        Command::AlternateBuffer,
+    );
+
+    // F1 — keymap help
+    layer.bind(
+        vec![KeyEvent::new(Key::F(1), Modifiers::NONE)],
+        Command::ShowHelp,
+    );
 
     layer
 }
@@ -390,6 +520,15 @@ mod tests {
         assert_eq!(layer.get(&seq), Some(&Command::PageUp));
     }
 
+    #[test]
+    fn emacs_ctrl_l_recenters() {
+        let layer = create_emacs_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::ctrl('l')]),
+            Some(&Command::CenterCursor)
+        );
+    }
+
     #[test]
     fn emacs_alt_less_moves_buffer_start() {
         let layer = create_emacs_keymap();
@@ -490,6 +629,43 @@ mod tests {
         assert_eq!(layer.get(&seq), Some(&Command::Save));
     }
 
+    #[test]
+    fn emacs_ctrl_x_s_saves_all() {
+        let layer = create_emacs_keymap();
+        let seq = vec![KeyEvent::ctrl('x'), KeyEvent::char('s')];
+        assert_eq!(layer.get(&seq), Some(&Command::SaveAll));
+    }
+
+    #[test]
+    fn emacs_alt_s_g_searches_workspace() {
+        let layer = create_emacs_keymap();
+        let seq = vec![
+            KeyEvent::new(Key::Char('s'), Modifiers::ALT),
+            KeyEvent::char('g'),
+        ];
+        assert_eq!(layer.get(&seq), Some(&Command::WorkspaceSearch));
+    }
+
+    #[test]
+    fn emacs_ctrl_c_ctrl_s_and_ctrl_w_go_to_symbol() {
+        let layer = create_emacs_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::ctrl('c'), KeyEvent::ctrl('s')]),
+            Some(&Command::LspDocumentSymbols)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::ctrl('c'), KeyEvent::ctrl('w')]),
+            Some(&Command::LspWorkspaceSymbols)
+        );
+    }
+
+    #[test]
+    fn emacs_ctrl_x_d_toggles_file_tree() {
+        let layer = create_emacs_keymap();
+        let seq = vec![KeyEvent::ctrl('x'), KeyEvent::char('d')];
+        assert_eq!(layer.get(&seq), Some(&Command::ToggleFileTree));
+    }
+
     #[test]
     fn emacs_ctrl_x_ctrl_c_quits() {
         let layer = create_emacs_keymap();
@@ -536,6 +712,85 @@ mod tests {
         );
     }
 
+    #[test]
+    fn emacs_shift_arrows_extend_selection() {
+        let layer = create_emacs_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Right, Modifiers::SHIFT)]),
+            Some(&Command::ExtendSelection(Direction::Right))
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Up, Modifiers::SHIFT)]),
+            Some(&Command::ExtendSelection(Direction::Up))
+        );
+    }
+
+    #[test]
+    fn emacs_copy_and_yank() {
+        let layer = create_emacs_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::Char('w'), Modifiers::ALT)]),
+            Some(&Command::Copy)
+        );
+        assert_eq!(layer.get(&[KeyEvent::ctrl('y')]), Some(&Command::Paste));
+        assert_eq!(layer.get(&[KeyEvent::alt('y')]), Some(&Command::YankPop));
+    }
+
+    #[test]
+    fn emacs_transpose_and_case_keys() {
+        let layer = create_emacs_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::ctrl('t')]),
+            Some(&Command::TransposeChars)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('t')]),
+            Some(&Command::TransposeWords)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('c')]),
+            Some(&Command::TitleCaseSelection)
+        );
+    }
+
+    #[test]
+    fn emacs_compile_and_next_error_keys() {
+        let layer = create_emacs_keymap();
+        assert_eq!(
+            layer.get(&[
+                KeyEvent::ctrl('x'),
+                KeyEvent::char('p'),
+                KeyEvent::char('c')
+            ]),
+            Some(&Command::RunTask)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('g'), KeyEvent::char('n')]),
+            Some(&Command::QuickfixNext)
+        );
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('g'), KeyEvent::char('p')]),
+            Some(&Command::QuickfixPrev)
+        );
+        assert_eq!(
+            layer.get(&[
+                KeyEvent::ctrl('c'),
+                KeyEvent::char('!'),
+                KeyEvent::char('l')
+            ]),
+            Some(&Command::ShowDiagnostics)
+        );
+    }
+
+    #[test]
+    fn emacs_alt_bang_runs_shell_command() {
+        let layer = create_emacs_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::alt('!')]),
+            Some(&Command::RunShellCommand)
+        );
+    }
+
     #[test]
     fn emacs_home_end_keys_work() {
         let layer = create_emacs_keymap();
@@ -594,4 +849,22 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn emacs_f1_shows_help() {
+        let layer = create_emacs_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::new(Key::F(1), Modifiers::NONE)]),
+            Some(&Command::ShowHelp)
+        );
+    }
+
+    #[test]
+    fn emacs_ctrl_c_ctrl_e_shows_diagnostic() {
+        let layer = create_emacs_keymap();
+        assert_eq!(
+            layer.get(&[KeyEvent::ctrl('c'), KeyEvent::ctrl('e')]),
+            Some(&Command::ShowDiagnosticAtCursor)
+        );
+    }
 }
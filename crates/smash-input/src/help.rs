@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use crate::command::{Command, COMMAND_CATEGORIES};
+use crate::event::KeyEvent;
+use crate::keymap::Keymap;
+
+/// Generate a plain-text help page documenting every effective binding
+/// in `keymap`, grouped by command category.
+///
+/// Bindings in higher layers shadow the same key sequence in lower ones.
+/// Commands bound to several sequences are listed once with all keys.
+pub fn keymap_help(keymap: &Keymap, preset: &str) -> String {
+    let mut effective: BTreeMap<String, &Command> = BTreeMap::new();
+    for layer in keymap.layers() {
+        for (seq, cmd) in layer.bindings() {
+            effective.insert(format_sequence(seq), cmd);
+        }
+    }
+
+    // category -> description -> keys
+    let mut groups: BTreeMap<&str, BTreeMap<&str, Vec<String>>> = BTreeMap::new();
+    for (keys, cmd) in &effective {
+        if **cmd == Command::Noop {
+            continue;
+        }
+        let info = cmd.info();
+        groups
+            .entry(info.category)
+            .or_default()
+            .entry(info.description)
+            .or_default()
+            .push(keys.clone());
+    }
+
+    let key_width = groups
+        .values()
+        .flat_map(|cmds| cmds.values())
+        .map(|keys| keys.join(", ").chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut out = format!("smash keymap help \u{2014} preset: {}\n", preset);
+    for category in COMMAND_CATEGORIES {
+        let Some(cmds) = groups.get(category) else {
+            continue;
+        };
+        out.push('\n');
+        out.push_str(category);
+        out.push('\n');
+        for (description, keys) in cmds {
+            out.push_str(&format!(
+                "  {:<width$}  {}\n",
+                keys.join(", "),
+                description,
+                width = key_width
+            ));
+        }
+    }
+    out
+}
+
+/// Format a key sequence as space-separated chords, e.g. `Ctrl-X Ctrl-S`.
+fn format_sequence(seq: &[KeyEvent]) -> String {
+    seq.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::KeymapLayer;
+    use crate::{create_default_keymap, create_emacs_keymap};
+
+    #[test]
+    fn help_groups_bindings_by_category() {
+        let help = keymap_help(&Keymap::new(create_default_keymap()), "default");
+        assert!(help.starts_with("smash keymap help \u{2014} preset: default"));
+        let file = help.find("\nFile\n").expect("File section");
+        let lsp = help.find("\nLSP\n").expect("LSP section");
+        assert!(file < lsp);
+        assert!(help.contains("Ctrl-S"));
+        assert!(help.contains("Save the current file"));
+    }
+
+    #[test]
+    fn help_lists_emacs_chords() {
+        let help = keymap_help(&Keymap::new(create_emacs_keymap()), "emacs");
+        assert!(help.contains("Ctrl-X Ctrl-S"));
+    }
+
+    #[test]
+    fn help_merges_keys_and_respects_layer_shadowing() {
+        let mut base = KeymapLayer::new("base");
+        base.bind(vec![KeyEvent::ctrl('z')], Command::Undo);
+        base.bind(vec![KeyEvent::ctrl('u')], Command::Undo);
+        base.bind(vec![KeyEvent::ctrl('q')], Command::Quit);
+        base.bind(vec![KeyEvent::ctrl('n')], Command::Noop);
+        let mut top = KeymapLayer::new("top");
+        top.bind(vec![KeyEvent::ctrl('q')], Command::Save);
+        let mut keymap = Keymap::new(base);
+        keymap.push_layer(top);
+
+        let help = keymap_help(&keymap, "custom");
+        assert!(help.contains("Ctrl-U, Ctrl-Z"));
+        assert!(help.contains("Ctrl-Q"));
+        assert!(!help.contains("\nLifecycle\n"));
+        assert!(!help.contains("Ctrl-N"));
+    }
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
+use crate::chord::parse_key_sequence;
 use crate::command::Command;
+use crate::error::InputError;
 use crate::event::KeyEvent;
 
 pub type KeySequence = Vec<KeyEvent>;
@@ -44,6 +46,24 @@ impl KeymapLayer {
     pub fn bindings(&self) -> &HashMap<KeySequence, Command> {
         &self.bindings
     }
+
+    /// Build a layer from textual bindings such as
+    /// `("ctrl-x ctrl-f", "open")`. Entries with an unparseable chord or
+    /// unknown command are skipped and returned as errors.
+    pub fn from_bindings<'a>(
+        name: impl Into<String>,
+        bindings: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> (Self, Vec<InputError>) {
+        let mut layer = Self::new(name);
+        let mut errors = Vec::new();
+        for (chord, command) in bindings {
+            match (parse_key_sequence(chord), Command::from_name(command)) {
+                (Ok(seq), Ok(cmd)) => layer.bind(seq, cmd),
+                (Err(e), _) | (_, Err(e)) => errors.push(e),
+            }
+        }
+        (layer, errors)
+    }
 }
 
 /// Keymap: a stack of layers. Top layer has priority.
@@ -85,6 +105,11 @@ impl Keymap {
     pub fn layer_count(&self) -> usize {
         self.layers.len()
     }
+
+    /// Layers from lowest to highest priority.
+    pub fn layers(&self) -> &[KeymapLayer] {
+        &self.layers
+    }
 }
 
 #[cfg(test)]
@@ -249,4 +274,31 @@ mod tests {
         assert_eq!(layer.name(), "");
         assert!(layer.bindings().is_empty());
     }
+
+    #[test]
+    fn from_bindings_overrides_lower_layer_and_reports_errors() {
+        let mut base = KeymapLayer::new("base");
+        base.bind(vec![KeyEvent::ctrl('s')], Command::Save);
+        let mut keymap = Keymap::new(base);
+
+        let (user, errors) = KeymapLayer::from_bindings(
+            "user",
+            [
+                ("ctrl-s", "noop"),
+                ("ctrl-x ctrl-f", "open"),
+                ("ctrl-q", "frobnicate"),
+                ("hyper-q", "quit"),
+            ],
+        );
+        assert_eq!(errors.len(), 2);
+        assert_eq!(user.bindings().len(), 2);
+        keymap.push_layer(user);
+
+        assert_eq!(keymap.resolve(&[KeyEvent::ctrl('s')]), Some(&Command::Noop));
+        assert_eq!(
+            keymap.resolve(&[KeyEvent::ctrl('x'), KeyEvent::ctrl('f')]),
+            Some(&Command::Open)
+        );
+        assert!(keymap.has_prefix(&[KeyEvent::ctrl('x')]));
+    }
 }
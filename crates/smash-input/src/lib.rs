@@ -1,15 +1,21 @@
+pub mod chord;
 pub mod command;
 pub mod default_keymap;
 pub mod emacs_keymap;
 pub mod error;
 pub mod event;
+pub mod help;
 pub mod keymap;
 pub mod resolver;
+pub mod vim;
 
-pub use command::Command;
+pub use chord::{parse_key_event, parse_key_sequence};
+pub use command::{Command, CommandInfo, PALETTE_COMMANDS};
 pub use default_keymap::create_default_keymap;
 pub use emacs_keymap::create_emacs_keymap;
 pub use error::InputError;
 pub use event::{InputEvent, Key, KeyEvent, Modifiers, MouseEvent};
+pub use help::keymap_help;
 pub use keymap::{Keymap, KeymapLayer};
 pub use resolver::{KeyResolver, ResolveResult};
+pub use vim::{Vim, VimAction, VimMode};
@@ -0,0 +1,679 @@
+//! Vim-style modal editing: modes, counts and operator-pending state.
+//!
+//! [`Vim`] turns keystrokes typed in Normal and Visual mode into
+//! [`VimAction`]s. It does not touch the buffer; the editor performs the
+//! actions and switches modes. Insert mode uses the ordinary keymap, so
+//! [`Vim::feed`] is only meant for Normal and Visual mode keys.
+
+use smash_core::registers::is_register_name;
+
+use crate::event::{Key, KeyEvent, Modifiers};
+
+/// The editing mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+    /// Character-wise selection (`v`).
+    Visual,
+    /// Line-wise selection (`V`).
+    VisualLine,
+    /// Typing an ex command after `:`.
+    CommandLine,
+}
+
+impl VimMode {
+    /// Name shown in the status bar.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Normal => "NORMAL",
+            Self::Insert => "INSERT",
+            Self::Visual => "VISUAL",
+            Self::VisualLine => "V-LINE",
+            Self::CommandLine => "COMMAND",
+        }
+    }
+
+    /// Whether a selection is being made.
+    pub fn is_visual(self) -> bool {
+        matches!(self, Self::Visual | Self::VisualLine)
+    }
+}
+
+/// A cursor motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// `w`: start of the next word.
+    WordForward,
+    /// `b`: start of the previous word.
+    WordBackward,
+    /// `e`: end of the current or next word.
+    WordEnd,
+    /// `0`
+    LineStart,
+    /// `^`
+    FirstNonBlank,
+    /// `$`
+    LineEnd,
+    /// `gg`, or line `N` with a count.
+    FirstLine,
+    /// `G`, or line `N` with a count.
+    LastLine,
+}
+
+impl Motion {
+    /// Operators on line-wise motions act on whole lines (`dj`, `dG`).
+    pub fn is_linewise(self) -> bool {
+        matches!(
+            self,
+            Self::Up | Self::Down | Self::FirstLine | Self::LastLine
+        )
+    }
+
+    /// Inclusive motions also cover the character they land on (`de`).
+    pub fn is_inclusive(self) -> bool {
+        matches!(self, Self::WordEnd)
+    }
+}
+
+/// An operator waiting for a motion (`d`, `c`, `y`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+/// Where Insert mode starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertAt {
+    /// `i`
+    Cursor,
+    /// `a`
+    AfterCursor,
+    /// `I`
+    FirstNonBlank,
+    /// `A`
+    LineEnd,
+    /// `o`
+    LineBelow,
+    /// `O`
+    LineAbove,
+}
+
+/// What a keystroke asks the editor to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VimAction {
+    /// Move the cursor. `None` means no count was typed.
+    Move(Motion, Option<usize>),
+    /// Apply an operator over a motion, e.g. `3dw` or `d$`.
+    Operate(Operator, Motion, Option<usize>),
+    /// Apply an operator to `count` whole lines (`dd`, `3yy`, `cc`).
+    OperateLines(Operator, usize),
+    /// Apply an operator to the Visual selection.
+    OperateSelection(Operator),
+    /// Enter Insert mode.
+    Insert(InsertAt),
+    /// `x`: delete characters under the cursor.
+    DeleteChar(usize),
+    /// `p` / `P`: put the register after or before the cursor.
+    Paste {
+        before: bool,
+        count: usize,
+    },
+    Undo(usize),
+    Redo(usize),
+    /// `v` / `V`: start (or switch to) Visual mode; `true` for line-wise.
+    Visual(bool),
+    /// Leave Visual mode, or abandon a pending count/operator.
+    Escape,
+    /// `:`: open the command line.
+    CommandLine,
+    /// `m{c}`: set mark `c` at the cursor.
+    SetMark(char),
+    /// `'{c}` or `` `{c} ``: jump to mark `c`.
+    GotoMark(char),
+    /// `gx`: open the link under the cursor.
+    OpenLink,
+    /// `zz`: scroll the cursor line to the middle of the view.
+    CenterCursor,
+    /// `zt`: scroll the cursor line to the top of the view.
+    CursorToTop,
+    /// `zb`: scroll the cursor line to the bottom of the view.
+    CursorToBottom,
+    /// `Ctrl-Y` / `Ctrl-E`: scroll the view `count` lines up or down,
+    /// leaving the cursor where it is while it stays in view.
+    ScrollLines {
+        up: bool,
+        count: usize,
+    },
+    /// More keys are needed (a count, operator or `g`/`z` prefix).
+    Pending,
+    /// Not a Vim key; the editor's normal keymap should handle it.
+    Passthrough,
+    /// A plain key with no meaning here. Ignored rather than inserted.
+    Ignored,
+}
+
+/// Modal state: the current mode plus any partially typed command.
+#[derive(Debug, Clone, Default)]
+pub struct Vim {
+    mode: VimMode,
+    count: Option<usize>,
+    operator: Option<(Operator, Option<usize>)>,
+    g_prefix: bool,
+    z_prefix: bool,
+    /// `m`, `'` or `` ` `` typed; the next key names the mark.
+    mark_prefix: Option<char>,
+    /// `"` typed; the next key names a register.
+    register_prefix: bool,
+    /// Register named with `"x` for the command being typed.
+    typed_register: Option<char>,
+    /// Register named for the action [`Vim::feed`] last returned.
+    action_register: Option<char>,
+}
+
+impl Vim {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mode(&self) -> VimMode {
+        self.mode
+    }
+
+    /// Switch modes, discarding any half-typed command.
+    pub fn set_mode(&mut self, mode: VimMode) {
+        self.mode = mode;
+        self.reset();
+    }
+
+    /// Forget a pending count, operator or `g`/`z` prefix.
+    pub fn reset(&mut self) {
+        self.count = None;
+        self.operator = None;
+        self.g_prefix = false;
+        self.z_prefix = false;
+        self.mark_prefix = None;
+        self.register_prefix = false;
+        self.typed_register = None;
+    }
+
+    /// Keys typed so far for an unfinished command, e.g. `3d`.
+    pub fn pending_keys(&self) -> String {
+        let mut out = String::new();
+        if let Some(name) = self.typed_register {
+            out.push('"');
+            out.push(name);
+        }
+        if self.register_prefix {
+            out.push('"');
+        }
+        if let Some((op, count)) = self.operator {
+            if let Some(n) = count {
+                out.push_str(&n.to_string());
+            }
+            out.push(operator_key(op));
+        }
+        if let Some(n) = self.count {
+            out.push_str(&n.to_string());
+        }
+        if self.g_prefix {
+            out.push('g');
+        }
+        if self.z_prefix {
+            out.push('z');
+        }
+        out.extend(self.mark_prefix);
+        out
+    }
+
+    /// Interpret a key typed in Normal or Visual mode.
+    pub fn feed(&mut self, key: &KeyEvent) -> VimAction {
+        let action = self.interpret(key);
+        self.action_register = None;
+        if action != VimAction::Pending {
+            self.action_register = self.typed_register.take();
+            self.reset();
+        }
+        action
+    }
+
+    /// The register named with `"x` for the action [`Vim::feed`] just
+    /// returned, e.g. `a` for `"ayy`.
+    pub fn register(&self) -> Option<char> {
+        self.action_register
+    }
+
+    fn interpret(&mut self, key: &KeyEvent) -> VimAction {
+        if key.key == Key::Esc {
+            return VimAction::Escape;
+        }
+        let c = match (key.key, key.modifiers) {
+            (Key::Char('r'), Modifiers::CTRL) => return VimAction::Redo(self.take_count()),
+            (Key::Char('y' | 'e'), Modifiers::CTRL) if self.operator.is_none() => {
+                return VimAction::ScrollLines {
+                    up: key.key == Key::Char('y'),
+                    count: self.take_count(),
+                };
+            }
+            (Key::Char(c), m) if m == Modifiers::NONE || m == Modifiers::SHIFT => c,
+            (Key::Left, Modifiers::NONE) => 'h',
+            (Key::Right, Modifiers::NONE) => 'l',
+            (Key::Up, Modifiers::NONE) => 'k',
+            (Key::Down, Modifiers::NONE) => 'j',
+            (Key::Home, Modifiers::NONE) => '0',
+            (Key::End, Modifiers::NONE) => '$',
+            _ => {
+                return if self.is_idle() {
+                    VimAction::Passthrough
+                } else {
+                    VimAction::Ignored
+                };
+            }
+        };
+
+        if std::mem::take(&mut self.register_prefix) {
+            if !is_register_name(c) {
+                return VimAction::Ignored;
+            }
+            self.typed_register = Some(c);
+            return VimAction::Pending;
+        }
+
+        if let Some(prefix) = self.mark_prefix.take() {
+            return match (prefix, c) {
+                (_, c) if !c.is_ascii_alphanumeric() => VimAction::Ignored,
+                ('m', c) => VimAction::SetMark(c),
+                (_, c) => VimAction::GotoMark(c),
+            };
+        }
+
+        if std::mem::take(&mut self.g_prefix) {
+            return match c {
+                'g' => self.motion(Motion::FirstLine),
+                'x' if self.operator.is_none() => {
+                    self.count = None;
+                    VimAction::OpenLink
+                }
+                _ => VimAction::Ignored,
+            };
+        }
+
+        if std::mem::take(&mut self.z_prefix) {
+            self.count = None;
+            return match c {
+                'z' => VimAction::CenterCursor,
+                't' => VimAction::CursorToTop,
+                'b' => VimAction::CursorToBottom,
+                _ => VimAction::Ignored,
+            };
+        }
+
+        if let Some(d) = c.to_digit(10) {
+            if d != 0 || self.count.is_some() {
+                let n = self.count.unwrap_or(0).saturating_mul(10);
+                self.count = Some(n.saturating_add(d as usize));
+                return VimAction::Pending;
+            }
+        }
+
+        if c == '"' && self.operator.is_none() {
+            self.register_prefix = true;
+            return VimAction::Pending;
+        }
+
+        if let Some(motion) = motion_for(c) {
+            return self.motion(motion);
+        }
+        if c == 'g' {
+            self.g_prefix = true;
+            return VimAction::Pending;
+        }
+        if c == 'z' && self.operator.is_none() {
+            self.z_prefix = true;
+            return VimAction::Pending;
+        }
+
+        let visual = self.mode.is_visual();
+        if let Some(op) = operator_for(c) {
+            if visual {
+                return VimAction::OperateSelection(op);
+            }
+            return match self.operator.take() {
+                // `dd`, `yy`, `cc`
+                Some((pending, count)) if pending == op => {
+                    VimAction::OperateLines(op, total_count(count, self.count.take()))
+                }
+                Some(_) => VimAction::Ignored,
+                None => {
+                    self.operator = Some((op, self.count.take()));
+                    VimAction::Pending
+                }
+            };
+        }
+        if self.operator.is_some() {
+            return VimAction::Ignored;
+        }
+
+        if visual {
+            return match c {
+                'x' => VimAction::OperateSelection(Operator::Delete),
+                'v' => VimAction::Visual(false),
+                'V' => VimAction::Visual(true),
+                ':' => VimAction::CommandLine,
+                _ => VimAction::Ignored,
+            };
+        }
+
+        match c {
+            'i' => VimAction::Insert(InsertAt::Cursor),
+            'a' => VimAction::Insert(InsertAt::AfterCursor),
+            'I' => VimAction::Insert(InsertAt::FirstNonBlank),
+            'A' => VimAction::Insert(InsertAt::LineEnd),
+            'o' => VimAction::Insert(InsertAt::LineBelow),
+            'O' => VimAction::Insert(InsertAt::LineAbove),
+            'x' => VimAction::DeleteChar(self.take_count()),
+            'D' => VimAction::Operate(Operator::Delete, Motion::LineEnd, None),
+            'C' => VimAction::Operate(Operator::Change, Motion::LineEnd, None),
+            'Y' => VimAction::OperateLines(Operator::Yank, self.take_count()),
+            'p' => VimAction::Paste {
+                before: false,
+                count: self.take_count(),
+            },
+            'P' => VimAction::Paste {
+                before: true,
+                count: self.take_count(),
+            },
+            'u' => VimAction::Undo(self.take_count()),
+            'm' | '\'' | '`' => {
+                self.mark_prefix = Some(c);
+                VimAction::Pending
+            }
+            'v' => VimAction::Visual(false),
+            'V' => VimAction::Visual(true),
+            ':' => VimAction::CommandLine,
+            _ => VimAction::Ignored,
+        }
+    }
+
+    /// Finish a motion: either a plain move or the target of a pending
+    /// operator. Counts before the operator and the motion multiply.
+    fn motion(&mut self, motion: Motion) -> VimAction {
+        let count = self.count.take();
+        match self.operator.take() {
+            Some((op, op_count)) => {
+                let count = match (op_count, count) {
+                    (None, None) => None,
+                    (a, b) => Some(total_count(a, b)),
+                };
+                VimAction::Operate(op, motion, count)
+            }
+            None => VimAction::Move(motion, count),
+        }
+    }
+
+    fn take_count(&mut self) -> usize {
+        self.count.take().unwrap_or(1).max(1)
+    }
+
+    fn is_idle(&self) -> bool {
+        self.count.is_none()
+            && self.operator.is_none()
+            && !self.g_prefix
+            && !self.z_prefix
+            && self.mark_prefix.is_none()
+            && !self.register_prefix
+            && self.typed_register.is_none()
+    }
+}
+
+fn total_count(a: Option<usize>, b: Option<usize>) -> usize {
+    a.unwrap_or(1).saturating_mul(b.unwrap_or(1)).max(1)
+}
+
+fn motion_for(c: char) -> Option<Motion> {
+    Some(match c {
+        'h' => Motion::Left,
+        'l' | ' ' => Motion::Right,
+        'j' => Motion::Down,
+        'k' => Motion::Up,
+        'w' => Motion::WordForward,
+        'b' => Motion::WordBackward,
+        'e' => Motion::WordEnd,
+        '0' => Motion::LineStart,
+        '^' => Motion::FirstNonBlank,
+        '$' => Motion::LineEnd,
+        'G' => Motion::LastLine,
+        _ => return None,
+    })
+}
+
+fn operator_for(c: char) -> Option<Operator> {
+    Some(match c {
+        'd' => Operator::Delete,
+        'c' => Operator::Change,
+        'y' => Operator::Yank,
+        _ => return None,
+    })
+}
+
+fn operator_key(op: Operator) -> char {
+    match op {
+        Operator::Delete => 'd',
+        Operator::Change => 'c',
+        Operator::Yank => 'y',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(vim: &mut Vim, keys: &str) -> Vec<VimAction> {
+        keys.chars().map(|c| vim.feed(&KeyEvent::char(c))).collect()
+    }
+
+    fn last(vim: &mut Vim, keys: &str) -> VimAction {
+        feed_all(vim, keys).pop().unwrap()
+    }
+
+    #[test]
+    fn motions_take_counts() {
+        let mut vim = Vim::new();
+        assert_eq!(
+            last(&mut vim, "w"),
+            VimAction::Move(Motion::WordForward, None)
+        );
+        assert_eq!(
+            last(&mut vim, "12j"),
+            VimAction::Move(Motion::Down, Some(12))
+        );
+        assert_eq!(
+            last(&mut vim, "0"),
+            VimAction::Move(Motion::LineStart, None)
+        );
+        assert_eq!(
+            last(&mut vim, "10G"),
+            VimAction::Move(Motion::LastLine, Some(10))
+        );
+        assert_eq!(
+            last(&mut vim, "gg"),
+            VimAction::Move(Motion::FirstLine, None)
+        );
+    }
+
+    #[test]
+    fn operators_combine_with_motions_and_counts() {
+        let mut vim = Vim::new();
+        assert_eq!(
+            last(&mut vim, "3dw"),
+            VimAction::Operate(Operator::Delete, Motion::WordForward, Some(3))
+        );
+        assert_eq!(
+            last(&mut vim, "2d3w"),
+            VimAction::Operate(Operator::Delete, Motion::WordForward, Some(6))
+        );
+        assert_eq!(
+            last(&mut vim, "d$"),
+            VimAction::Operate(Operator::Delete, Motion::LineEnd, None)
+        );
+        assert_eq!(
+            last(&mut vim, "cw"),
+            VimAction::Operate(Operator::Change, Motion::WordForward, None)
+        );
+        assert_eq!(
+            last(&mut vim, "dgg"),
+            VimAction::Operate(Operator::Delete, Motion::FirstLine, None)
+        );
+        assert_eq!(
+            last(&mut vim, "3dd"),
+            VimAction::OperateLines(Operator::Delete, 3)
+        );
+        assert_eq!(
+            last(&mut vim, "yy"),
+            VimAction::OperateLines(Operator::Yank, 1)
+        );
+    }
+
+    #[test]
+    fn pending_keys_are_reported_and_escape_clears_them() {
+        let mut vim = Vim::new();
+        assert_eq!(feed_all(&mut vim, "2d"), vec![VimAction::Pending; 2]);
+        assert_eq!(vim.pending_keys(), "2d");
+        assert_eq!(
+            vim.feed(&KeyEvent::new(Key::Esc, Modifiers::NONE)),
+            VimAction::Escape
+        );
+        assert_eq!(vim.pending_keys(), "");
+        assert_eq!(last(&mut vim, "dy"), VimAction::Ignored);
+        assert_eq!(vim.pending_keys(), "");
+    }
+
+    #[test]
+    fn simple_commands() {
+        let mut vim = Vim::new();
+        assert_eq!(last(&mut vim, "A"), VimAction::Insert(InsertAt::LineEnd));
+        assert_eq!(last(&mut vim, "o"), VimAction::Insert(InsertAt::LineBelow));
+        assert_eq!(last(&mut vim, "3x"), VimAction::DeleteChar(3));
+        assert_eq!(
+            last(&mut vim, "P"),
+            VimAction::Paste {
+                before: true,
+                count: 1
+            }
+        );
+        assert_eq!(last(&mut vim, "2u"), VimAction::Undo(2));
+        assert_eq!(vim.feed(&KeyEvent::ctrl('r')), VimAction::Redo(1));
+        assert_eq!(last(&mut vim, ":"), VimAction::CommandLine);
+        assert_eq!(last(&mut vim, "Q"), VimAction::Ignored);
+    }
+
+    #[test]
+    fn registers_are_named_before_the_command() {
+        let mut vim = Vim::new();
+        assert_eq!(vim.feed(&KeyEvent::char('"')), VimAction::Pending);
+        assert_eq!(vim.feed(&KeyEvent::char('a')), VimAction::Pending);
+        assert_eq!(vim.pending_keys(), "\"a");
+        assert_eq!(
+            last(&mut vim, "2yy"),
+            VimAction::OperateLines(Operator::Yank, 2)
+        );
+        assert_eq!(vim.register(), Some('a'));
+
+        assert_eq!(
+            last(&mut vim, "\"Bp"),
+            VimAction::Paste {
+                before: false,
+                count: 1
+            }
+        );
+        assert_eq!(vim.register(), Some('B'));
+        assert_eq!(last(&mut vim, "x"), VimAction::DeleteChar(1));
+        assert_eq!(vim.register(), None);
+
+        assert_eq!(last(&mut vim, "\"!"), VimAction::Ignored);
+        assert!(vim.pending_keys().is_empty());
+    }
+
+    #[test]
+    fn marks_are_set_and_jumped_to_by_name() {
+        let mut vim = Vim::new();
+        assert_eq!(vim.feed(&KeyEvent::char('m')), VimAction::Pending);
+        assert_eq!(vim.pending_keys(), "m");
+        assert_eq!(vim.feed(&KeyEvent::char('a')), VimAction::SetMark('a'));
+        assert_eq!(vim.pending_keys(), "");
+        assert_eq!(last(&mut vim, "'a"), VimAction::GotoMark('a'));
+        assert_eq!(last(&mut vim, "`B"), VimAction::GotoMark('B'));
+        assert_eq!(last(&mut vim, "m."), VimAction::Ignored);
+        assert_eq!(vim.feed(&KeyEvent::ctrl('s')), VimAction::Passthrough);
+    }
+
+    #[test]
+    fn gx_opens_the_link_under_the_cursor() {
+        let mut vim = Vim::new();
+        assert_eq!(last(&mut vim, "gx"), VimAction::OpenLink);
+        assert!(vim.is_idle());
+        assert_eq!(last(&mut vim, "dgx"), VimAction::Ignored);
+    }
+
+    #[test]
+    fn z_and_ctrl_e_y_scroll_the_view() {
+        let mut vim = Vim::new();
+        assert_eq!(vim.feed(&KeyEvent::char('z')), VimAction::Pending);
+        assert_eq!(vim.pending_keys(), "z");
+        assert_eq!(vim.feed(&KeyEvent::char('z')), VimAction::CenterCursor);
+        assert_eq!(last(&mut vim, "zt"), VimAction::CursorToTop);
+        assert_eq!(last(&mut vim, "zb"), VimAction::CursorToBottom);
+        assert_eq!(last(&mut vim, "zq"), VimAction::Ignored);
+        assert!(vim.is_idle());
+        assert_eq!(last(&mut vim, "dz"), VimAction::Ignored);
+        assert_eq!(
+            vim.feed(&KeyEvent::ctrl('e')),
+            VimAction::ScrollLines {
+                up: false,
+                count: 1
+            }
+        );
+        vim.feed(&KeyEvent::char('3'));
+        assert_eq!(
+            vim.feed(&KeyEvent::ctrl('y')),
+            VimAction::ScrollLines { up: true, count: 3 }
+        );
+    }
+
+    #[test]
+    fn modified_keys_pass_through_when_idle() {
+        let mut vim = Vim::new();
+        assert_eq!(vim.feed(&KeyEvent::ctrl('s')), VimAction::Passthrough);
+        assert_eq!(
+            vim.feed(&KeyEvent::new(Key::F(1), Modifiers::NONE)),
+            VimAction::Passthrough
+        );
+        vim.feed(&KeyEvent::char('d'));
+        assert_eq!(vim.feed(&KeyEvent::ctrl('s')), VimAction::Ignored);
+    }
+
+    #[test]
+    fn visual_mode_operators_act_on_selection() {
+        let mut vim = Vim::new();
+        vim.set_mode(VimMode::Visual);
+        assert_eq!(last(&mut vim, "2j"), VimAction::Move(Motion::Down, Some(2)));
+        assert_eq!(
+            last(&mut vim, "d"),
+            VimAction::OperateSelection(Operator::Delete)
+        );
+        assert_eq!(
+            last(&mut vim, "x"),
+            VimAction::OperateSelection(Operator::Delete)
+        );
+        assert_eq!(last(&mut vim, "V"), VimAction::Visual(true));
+        assert_eq!(vim.mode(), VimMode::Visual);
+        assert_eq!(VimMode::VisualLine.label(), "V-LINE");
+    }
+}
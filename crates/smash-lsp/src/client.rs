@@ -4,27 +4,36 @@
 //! interchange, and notification routing.
 use std::sync::Arc;
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use tokio::process::{Child, Command as TokioCommand};
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::{timeout, Duration};
 
 use crate::diagnostics::DiagnosticStore;
-use crate::dispatcher::{DispatchResult, Dispatcher};
+use crate::dispatcher::{DispatchResult, Dispatcher, NotificationHandler, RequestHandler};
 use crate::error::LspError;
+use crate::features::{mask_capabilities, remove_client_capabilities};
+use crate::log_target;
 use crate::transport::{
-    frame_message, next_request_id, parse_message, serialize_notification, serialize_request,
-    JsonRpcMessage,
+    frame_message, next_request_id, parse_batch, serialize_notification, serialize_request,
+    FrameReader, JsonRpcMessage,
 };
 use crate::types::{
-    client_capabilities, CodeAction, CompletionItem, Diagnostic, Hover, Location, LspCapabilities,
-    LspClientId, LspPosition, LspRange, LspServerConfig, SymbolInformation, TextEdit,
-    WorkspaceEdit,
+    client_capabilities, CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall,
+    CodeAction, CompletionItem, Diagnostic, DocumentSymbol, GotoTarget, Hover, Location,
+    LspCapabilities, LspClientId, LspPosition, LspRange, LspServerConfig, SaveNotification,
+    ServerCommand, SignatureHelp, SymbolInformation, TextDocumentContentChangeEvent,
+    TextDocumentSyncKind, TextEdit, WorkspaceEdit,
 };
+use crate::window::{MessageType, ProgressTracker, ServerMessage};
 
 /// Default timeout for requests (seconds).
 const REQUEST_TIMEOUT_SECS: u64 = 10;
 
+/// Timeout for `workspace/executeCommand` (seconds). Commands such as
+/// project-wide fixes can take far longer than an ordinary request.
+const EXECUTE_COMMAND_TIMEOUT_SECS: u64 = 300;
+
 /// State of the LSP client lifecycle.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ClientState {
@@ -50,6 +59,7 @@ pub struct LspClient {
     writer_tx: Option<mpsc::Sender<Vec<u8>>>,
     child: Option<Child>,
     diagnostics: Arc<Mutex<DiagnosticStore>>,
+    progress: Arc<Mutex<ProgressTracker>>,
 }
 
 impl LspClient {
@@ -64,6 +74,7 @@ impl LspClient {
             writer_tx: None,
             child: None,
             diagnostics: Arc::new(Mutex::new(DiagnosticStore::new())),
+            progress: Arc::new(Mutex::new(ProgressTracker::new())),
         }
     }
 
@@ -92,6 +103,11 @@ impl LspClient {
         self.diagnostics.clone()
     }
 
+    /// Get access to the server's work-done progress.
+    pub fn progress(&self) -> Arc<Mutex<ProgressTracker>> {
+        self.progress.clone()
+    }
+
     /// Start the language server process and perform initialization.
     pub async fn start(&mut self) -> Result<(), LspError> {
         self.state = ClientState::Initializing;
@@ -104,6 +120,12 @@ impl LspClient {
             .stderr(std::process::Stdio::null())
             .spawn()
             .map_err(|e| LspError::SpawnFailed(format!("{}: {}", self.config.command, e)))?;
+        tracing::info!(
+            target: log_target::LIFECYCLE,
+            "spawned {} (pid {:?})",
+            self.config.command,
+            child.id()
+        );
 
         let stdin = child
             .stdin
@@ -130,76 +152,17 @@ impl LspClient {
 
         // Reader task: reads messages from the server
         let dispatcher = self.dispatcher.clone();
+        dispatcher.lock().await.set_writer(writer_tx.clone());
         let diagnostics = self.diagnostics.clone();
-        tokio::spawn(async move {
-            let mut reader = BufReader::new(stdout);
-            let mut buf = String::new();
-
-            loop {
-                buf.clear();
-                // Read header lines until empty line
-                let mut content_length: Option<usize> = None;
-                loop {
-                    let mut line = String::new();
-                    match reader.read_line(&mut line).await {
-                        Ok(0) => return, // EOF
-                        Err(_) => return,
-                        Ok(_) => {}
-                    }
-                    let trimmed = line.trim();
-                    if trimmed.is_empty() {
-                        break;
-                    }
-                    if let Some(val) = trimmed.strip_prefix("Content-Length:") {
-                        content_length = val.trim().parse().ok();
-                    }
-                }
-
-                let length = match content_length {
-                    Some(l) => l,
-                    None => continue,
-                };
-
-                // Read body
-                let mut body_buf = vec![0u8; length];
-                match tokio::io::AsyncReadExt::read_exact(&mut reader, &mut body_buf).await {
-                    Ok(_) => {}
-                    Err(_) => return,
-                }
-
-                let body = match String::from_utf8(body_buf) {
-                    Ok(s) => s,
-                    Err(_) => continue,
-                };
-
-                let message = match parse_message(&body) {
-                    Ok(m) => m,
-                    Err(_) => continue,
-                };
-
-                // Handle diagnostics notifications specially
-                if let JsonRpcMessage::Notification {
-                    ref method,
-                    ref params,
-                } = message
-                {
-                    if method == "textDocument/publishDiagnostics" {
-                        if let (Some(uri), Some(diag_arr)) =
-                            (params["uri"].as_str(), params["diagnostics"].as_array())
-                        {
-                            let diags: Vec<Diagnostic> = diag_arr
-                                .iter()
-                                .filter_map(|d| serde_json::from_value(d.clone()).ok())
-                                .collect();
-                            diagnostics.lock().await.publish(uri.to_string(), diags);
-                        }
-                    }
-                }
-
-                let mut disp = dispatcher.lock().await;
-                let _ = disp.dispatch(message);
-            }
-        });
+        let progress = self.progress.clone();
+        let server = self.config.command.clone();
+        tokio::spawn(read_server_output(
+            stdout,
+            server,
+            dispatcher,
+            diagnostics,
+            progress,
+        ));
 
         self.writer_tx = Some(writer_tx);
         self.child = Some(child);
@@ -208,14 +171,22 @@ impl LspClient {
         self.initialize().await?;
 
         self.state = ClientState::Running;
+        tracing::info!(
+            target: log_target::LIFECYCLE,
+            "{} initialized",
+            self.config.command
+        );
         Ok(())
     }
 
     /// Send the initialize request to the server.
     async fn initialize(&mut self) -> Result<(), LspError> {
+        let disabled = self.config.disabled_features.clone();
+        let mut capabilities = client_capabilities();
+        remove_client_capabilities(&mut capabilities, &disabled);
         let params = serde_json::json!({
             "processId": std::process::id(),
-            "capabilities": client_capabilities(),
+            "capabilities": capabilities,
             "rootUri": self.config.root_uri,
             "clientInfo": {
                 "name": "smash",
@@ -228,6 +199,7 @@ impl LspClient {
         // Parse server capabilities
         if let Some(caps) = result.get("capabilities") {
             self.capabilities = LspCapabilities::from_server_capabilities(caps);
+            mask_capabilities(&mut self.capabilities, &disabled);
         }
 
         // Send initialized notification
@@ -243,34 +215,33 @@ impl LspClient {
         method: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value, LspError> {
-        let writer_tx = self.writer_tx.as_ref().ok_or(LspError::ServerCrashed)?;
-
-        let id = next_request_id();
-        let body = serialize_request(id, method, params);
-        let framed = frame_message(&body);
-
-        let rx = {
-            let mut disp = self.dispatcher.lock().await;
-            disp.register_request(id)
-        };
+        let writer_tx = self.writer_tx.clone().ok_or(LspError::ServerCrashed)?;
+        request(
+            self.dispatcher.clone(),
+            writer_tx,
+            method,
+            params,
+            REQUEST_TIMEOUT_SECS,
+        )
+        .await
+    }
 
-        writer_tx
-            .send(framed)
-            .await
-            .map_err(|_| LspError::ServerCrashed)?;
+    /// Set the handler for requests the server sends, such as
+    /// `workspace/applyEdit`. Requests arriving before this is set are
+    /// refused.
+    pub async fn set_request_handler(&self, handler: RequestHandler) {
+        self.dispatcher.lock().await.set_request_handler(handler);
+    }
 
-        let result = timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS), rx)
+    /// Set the handler for notifications the server sends, such as
+    /// `window/showMessage`. Diagnostics and progress are also recorded
+    /// in [`diagnostics`](Self::diagnostics) and
+    /// [`progress`](Self::progress) before reaching it.
+    pub async fn set_notification_handler(&self, handler: NotificationHandler) {
+        self.dispatcher
+            .lock()
             .await
-            .map_err(|_| LspError::Timeout(REQUEST_TIMEOUT_SECS))?
-            .map_err(|_| LspError::ServerCrashed)?;
-
-        match result {
-            DispatchResult::Success(val) => Ok(val),
-            DispatchResult::Error(err) => Err(LspError::Rpc {
-                code: err.code,
-                message: err.message,
-            }),
-        }
+            .set_notification_handler(handler);
     }
 
     /// Send a notification (no response expected).
@@ -281,7 +252,9 @@ impl LspClient {
     ) -> Result<(), LspError> {
         let writer_tx = self.writer_tx.as_ref().ok_or(LspError::ServerCrashed)?;
 
+        tracing::debug!(target: log_target::RPC, "-> notification {}", method);
         let body = serialize_notification(method, params);
+        tracing::trace!(target: log_target::RPC, "-> {}", body);
         let framed = frame_message(&body);
 
         writer_tx
@@ -306,30 +279,80 @@ impl LspClient {
     }
 
     /// Send textDocument/didChange notification.
-    pub async fn did_change(&self, uri: &str, version: i32, text: &str) -> Result<(), LspError> {
+    ///
+    /// `changes` are applied by the server in order. Ranged changes are
+    /// only sent to servers that negotiated incremental sync; callers
+    /// should pass a single full-document change otherwise (see
+    /// [`LspCapabilities::text_document_sync`]). Nothing is sent to
+    /// servers that opted out of document sync.
+    pub async fn did_change(
+        &self,
+        uri: &str,
+        version: i32,
+        changes: &[TextDocumentContentChangeEvent],
+    ) -> Result<(), LspError> {
+        match self.capabilities.text_document_sync {
+            TextDocumentSyncKind::None => return Ok(()),
+            TextDocumentSyncKind::Full if changes.iter().any(|c| c.range.is_some()) => {
+                return Err(LspError::Unsupported("incremental sync".into()));
+            }
+            _ => {}
+        }
         let params = serde_json::json!({
             "textDocument": {
                 "uri": uri,
                 "version": version
             },
-            "contentChanges": [{
-                "text": text
-            }]
+            "contentChanges": changes
         });
         self.send_notification("textDocument/didChange", params)
             .await
     }
 
-    /// Send textDocument/didSave notification.
-    pub async fn did_save(&self, uri: &str) -> Result<(), LspError> {
-        let params = serde_json::json!({
+    /// Send textDocument/didSave notification, with the saved `text` if
+    /// the server asked for it. Servers that did not ask for `didSave`
+    /// are not sent one.
+    pub async fn did_save(&self, uri: &str, text: Option<&str>) -> Result<(), LspError> {
+        let mut params = serde_json::json!({
             "textDocument": {
                 "uri": uri
             }
         });
+        match self.capabilities.save {
+            SaveNotification::None => return Ok(()),
+            SaveNotification::IncludeText => {
+                if let Some(text) = text {
+                    params["text"] = serde_json::json!(text);
+                }
+            }
+            SaveNotification::Notify => {}
+        }
         self.send_notification("textDocument/didSave", params).await
     }
 
+    /// Ask for edits to make before the document is written
+    /// (textDocument/willSaveWaitUntil, for a manual save). Empty if the
+    /// server does not support it.
+    pub async fn will_save_wait_until(&self, uri: &str) -> Result<Vec<TextEdit>, LspError> {
+        if !self.capabilities.will_save_wait_until {
+            return Ok(Vec::new());
+        }
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "reason": 1
+        });
+        let result = self
+            .send_request("textDocument/willSaveWaitUntil", params)
+            .await?;
+
+        if result.is_null() {
+            return Ok(Vec::new());
+        }
+
+        serde_json::from_value(result)
+            .map_err(|e| LspError::Serialization(format!("willSaveWaitUntil parse: {}", e)))
+    }
+
     /// Send textDocument/didClose notification.
     pub async fn did_close(&self, uri: &str) -> Result<(), LspError> {
         let params = serde_json::json!({
@@ -384,30 +407,83 @@ impl LspClient {
         Ok(Some(hover))
     }
 
+    /// Request the signatures of the call around `position`. Returns
+    /// `None` when the cursor is not inside a call.
+    pub async fn signature_help(
+        &self,
+        uri: &str,
+        position: LspPosition,
+    ) -> Result<Option<SignatureHelp>, LspError> {
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": position
+        });
+        let result = self
+            .send_request("textDocument/signatureHelp", params)
+            .await?;
+        parse_signature_help(result)
+    }
+
     /// Request go-to-definition.
     pub async fn goto_definition(
         &self,
         uri: &str,
         position: LspPosition,
+    ) -> Result<Vec<Location>, LspError> {
+        self.goto(GotoTarget::Definition, uri, position).await
+    }
+
+    /// Request go-to-type-definition.
+    pub async fn goto_type_definition(
+        &self,
+        uri: &str,
+        position: LspPosition,
+    ) -> Result<Vec<Location>, LspError> {
+        self.goto(GotoTarget::TypeDefinition, uri, position).await
+    }
+
+    /// Request go-to-implementation.
+    pub async fn goto_implementation(
+        &self,
+        uri: &str,
+        position: LspPosition,
+    ) -> Result<Vec<Location>, LspError> {
+        self.goto(GotoTarget::Implementation, uri, position).await
+    }
+
+    /// Request go-to-declaration.
+    pub async fn goto_declaration(
+        &self,
+        uri: &str,
+        position: LspPosition,
+    ) -> Result<Vec<Location>, LspError> {
+        self.goto(GotoTarget::Declaration, uri, position).await
+    }
+
+    /// Request the locations of `target` for the symbol at `position`.
+    pub async fn goto(
+        &self,
+        target: GotoTarget,
+        uri: &str,
+        position: LspPosition,
     ) -> Result<Vec<Location>, LspError> {
         let params = serde_json::json!({
             "textDocument": { "uri": uri },
             "position": position
         });
-        let result = self.send_request("textDocument/definition", params).await?;
+        let result = self.send_request(target.method(), params).await?;
 
         if result.is_null() {
             return Ok(Vec::new());
         }
 
         // May be Location, Location[], or LocationLink[]
+        let parse_error = |e| LspError::Serialization(format!("{} parse: {}", target.label(), e));
         if result.is_array() {
-            let locations: Vec<Location> = serde_json::from_value(result)
-                .map_err(|e| LspError::Serialization(format!("definition parse: {}", e)))?;
+            let locations: Vec<Location> = serde_json::from_value(result).map_err(parse_error)?;
             Ok(locations)
         } else {
-            let location: Location = serde_json::from_value(result)
-                .map_err(|e| LspError::Serialization(format!("definition parse: {}", e)))?;
+            let location: Location = serde_json::from_value(result).map_err(parse_error)?;
             Ok(vec![location])
         }
     }
@@ -434,27 +510,56 @@ impl LspClient {
         Ok(locations)
     }
 
-    /// Request code actions for a range.
+    /// Request code actions for a range. `only` restricts the result to
+    /// the given action kinds (e.g. `quickfix`); empty means all kinds.
     pub async fn code_action(
         &self,
         uri: &str,
         range: LspRange,
         diagnostics: Vec<Diagnostic>,
+        only: Vec<String>,
     ) -> Result<Vec<CodeAction>, LspError> {
+        let mut context = serde_json::json!({ "diagnostics": diagnostics });
+        if !only.is_empty() {
+            context["only"] = serde_json::json!(only);
+        }
         let params = serde_json::json!({
             "textDocument": { "uri": uri },
             "range": range,
-            "context": { "diagnostics": diagnostics }
+            "context": context
         });
         let result = self.send_request("textDocument/codeAction", params).await?;
+        parse_code_actions(result)
+    }
 
-        if result.is_null() {
-            return Ok(Vec::new());
+    /// Ask the server to run `command` (`workspace/executeCommand`).
+    /// Any edits it makes come back as separate `workspace/applyEdit`
+    /// requests; the result is whatever the command returns.
+    ///
+    /// The returned future does not borrow the client, so callers need
+    /// not hold on to it while a long-running command works.
+    pub fn execute_command(
+        &self,
+        command: &ServerCommand,
+    ) -> impl std::future::Future<Output = Result<serde_json::Value, LspError>> + Send + 'static
+    {
+        let mut params = serde_json::json!({ "command": command.command });
+        if !command.arguments.is_empty() {
+            params["arguments"] = serde_json::json!(command.arguments);
+        }
+        let dispatcher = self.dispatcher.clone();
+        let writer_tx = self.writer_tx.clone();
+        async move {
+            let writer_tx = writer_tx.ok_or(LspError::ServerCrashed)?;
+            request(
+                dispatcher,
+                writer_tx,
+                "workspace/executeCommand",
+                params,
+                EXECUTE_COMMAND_TIMEOUT_SECS,
+            )
+            .await
         }
-
-        let actions: Vec<CodeAction> = serde_json::from_value(result)
-            .map_err(|e| LspError::Serialization(format!("code action parse: {}", e)))?;
-        Ok(actions)
     }
 
     /// Request document formatting.
@@ -477,6 +582,33 @@ impl LspClient {
         Ok(edits)
     }
 
+    /// Request formatting of `range` only.
+    pub async fn range_format(
+        &self,
+        uri: &str,
+        range: LspRange,
+    ) -> Result<Vec<TextEdit>, LspError> {
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "range": range,
+            "options": {
+                "tabSize": 4,
+                "insertSpaces": true
+            }
+        });
+        let result = self
+            .send_request("textDocument/rangeFormatting", params)
+            .await?;
+
+        if result.is_null() {
+            return Ok(Vec::new());
+        }
+
+        let edits: Vec<TextEdit> = serde_json::from_value(result)
+            .map_err(|e| LspError::Serialization(format!("range format parse: {}", e)))?;
+        Ok(edits)
+    }
+
     /// Request rename.
     pub async fn rename(
         &self,
@@ -496,7 +628,9 @@ impl LspClient {
         Ok(edit)
     }
 
-    /// Request document symbols.
+    /// Request the symbols defined in a document, flattened in document
+    /// order. Servers may answer with flat `SymbolInformation` or nested
+    /// `DocumentSymbol`s; nested ones record their parent as container.
     pub async fn document_symbols(&self, uri: &str) -> Result<Vec<SymbolInformation>, LspError> {
         let params = serde_json::json!({
             "textDocument": { "uri": uri }
@@ -504,14 +638,56 @@ impl LspClient {
         let result = self
             .send_request("textDocument/documentSymbol", params)
             .await?;
+        parse_document_symbols(uri, result)
+    }
 
-        if result.is_null() {
-            return Ok(Vec::new());
-        }
+    /// Search the symbols of the whole workspace for `query`. The server
+    /// decides how the query matches; an empty query may return nothing.
+    pub async fn workspace_symbols(&self, query: &str) -> Result<Vec<SymbolInformation>, LspError> {
+        let params = serde_json::json!({ "query": query });
+        let result = self.send_request("workspace/symbol", params).await?;
+        parse_workspace_symbols(result)
+    }
 
-        let symbols: Vec<SymbolInformation> = serde_json::from_value(result)
-            .map_err(|e| LspError::Serialization(format!("symbol parse: {}", e)))?;
-        Ok(symbols)
+    /// Find the function or method at `position` to start a call
+    /// hierarchy from. Usually one item; none if the cursor is not on one.
+    pub async fn prepare_call_hierarchy(
+        &self,
+        uri: &str,
+        position: LspPosition,
+    ) -> Result<Vec<CallHierarchyItem>, LspError> {
+        let params = serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": position
+        });
+        let result = self
+            .send_request("textDocument/prepareCallHierarchy", params)
+            .await?;
+        parse_optional_list(result, "call hierarchy")
+    }
+
+    /// Functions that call `item`.
+    pub async fn incoming_calls(
+        &self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyIncomingCall>, LspError> {
+        let params = serde_json::json!({ "item": item });
+        let result = self
+            .send_request("callHierarchy/incomingCalls", params)
+            .await?;
+        parse_optional_list(result, "incoming calls")
+    }
+
+    /// Functions that `item` calls.
+    pub async fn outgoing_calls(
+        &self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyOutgoingCall>, LspError> {
+        let params = serde_json::json!({ "item": item });
+        let result = self
+            .send_request("callHierarchy/outgoingCalls", params)
+            .await?;
+        parse_optional_list(result, "outgoing calls")
     }
 
     /// Shutdown the language server.
@@ -521,6 +697,11 @@ impl LspClient {
         }
 
         self.state = ClientState::ShuttingDown;
+        tracing::info!(
+            target: log_target::LIFECYCLE,
+            "shutting down {}",
+            self.config.command
+        );
 
         // Send shutdown request
         let _result = self.send_request("shutdown", serde_json::Value::Null).await;
@@ -535,7 +716,14 @@ impl LspClient {
 
         // Wait for the child
         if let Some(ref mut child) = self.child {
-            let _ = child.wait().await;
+            if let Ok(status) = child.wait().await {
+                tracing::info!(
+                    target: log_target::LIFECYCLE,
+                    "{} exited: {}",
+                    self.config.command,
+                    status
+                );
+            }
         }
 
         self.state = ClientState::Stopped;
@@ -554,9 +742,371 @@ impl std::fmt::Debug for LspClient {
     }
 }
 
+/// Send a request through `writer_tx` and wait up to `timeout_secs` for
+/// the response.
+async fn request(
+    dispatcher: Arc<Mutex<Dispatcher>>,
+    writer_tx: mpsc::Sender<Vec<u8>>,
+    method: &str,
+    params: serde_json::Value,
+    timeout_secs: u64,
+) -> Result<serde_json::Value, LspError> {
+    let id = next_request_id();
+    tracing::debug!(target: log_target::RPC, "-> request #{} {}", id, method);
+    let body = serialize_request(id, method, params);
+    tracing::trace!(target: log_target::RPC, "-> {}", body);
+    let framed = frame_message(&body);
+
+    let rx = dispatcher.lock().await.register_request(id);
+
+    writer_tx
+        .send(framed)
+        .await
+        .map_err(|_| LspError::ServerCrashed)?;
+
+    let result = timeout(Duration::from_secs(timeout_secs), rx)
+        .await
+        .map_err(|_| {
+            tracing::warn!(
+                target: log_target::RPC,
+                "request #{} {} timed out",
+                id,
+                method
+            );
+            LspError::Timeout(timeout_secs)
+        })?
+        .map_err(|_| LspError::ServerCrashed)?;
+
+    match result {
+        DispatchResult::Success(val) => Ok(val),
+        DispatchResult::Error(err) => Err(LspError::Rpc {
+            code: err.code,
+            message: err.message,
+        }),
+    }
+}
+
+/// Read and route everything a server writes until it closes its output.
+///
+/// Messages are handled one at a time in the order they arrive, batch
+/// entries included, so diagnostics and responses are never reordered.
+async fn read_server_output<R: AsyncRead + Unpin>(
+    mut reader: R,
+    server: String,
+    dispatcher: Arc<Mutex<Dispatcher>>,
+    diagnostics: Arc<Mutex<DiagnosticStore>>,
+    progress: Arc<Mutex<ProgressTracker>>,
+) {
+    let mut frames = FrameReader::new();
+    let mut chunk = vec![0u8; 8192];
+
+    loop {
+        let body = match frames.next_frame() {
+            Ok(Some(body)) => body,
+            Ok(None) => {
+                match reader.read(&mut chunk).await {
+                    Ok(0) => {
+                        tracing::info!(
+                            target: log_target::LIFECYCLE,
+                            "{} closed its output",
+                            server
+                        );
+                        return;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            target: log_target::LIFECYCLE,
+                            "{} read failed: {}",
+                            server,
+                            e
+                        );
+                        return;
+                    }
+                    Ok(n) => frames.push(&chunk[..n]),
+                }
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(target: log_target::RPC, "{}: bad frame: {}", server, e);
+                continue;
+            }
+        };
+
+        tracing::trace!(target: log_target::RPC, "<- {}", body);
+        let messages = match parse_batch(&body) {
+            Ok(messages) => messages,
+            Err(e) => {
+                tracing::warn!(target: log_target::RPC, "unparseable message: {}", e);
+                continue;
+            }
+        };
+        for message in messages {
+            match message {
+                Ok(message) => {
+                    route_message(message, &server, &dispatcher, &diagnostics, &progress).await
+                }
+                Err(e) => tracing::warn!(target: log_target::RPC, "unparseable message: {}", e),
+            }
+        }
+    }
+}
+
+/// Record diagnostics and progress carried by `message` and log server
+/// log messages, then hand it to the dispatcher.
+async fn route_message(
+    message: JsonRpcMessage,
+    server: &str,
+    dispatcher: &Mutex<Dispatcher>,
+    diagnostics: &Mutex<DiagnosticStore>,
+    progress: &Mutex<ProgressTracker>,
+) {
+    // Handle diagnostics notifications specially
+    if let JsonRpcMessage::Notification {
+        ref method,
+        ref params,
+    } = message
+    {
+        if method == "textDocument/publishDiagnostics" {
+            if let (Some(uri), Some(diag_arr)) =
+                (params["uri"].as_str(), params["diagnostics"].as_array())
+            {
+                let diags: Vec<Diagnostic> = diag_arr
+                    .iter()
+                    .filter_map(|d| serde_json::from_value(d.clone()).ok())
+                    .collect();
+                let version = params["version"].as_i64();
+                let count = diags.len();
+                if diagnostics
+                    .lock()
+                    .await
+                    .publish_versioned(uri.to_string(), version, diags)
+                {
+                    tracing::debug!(
+                        target: log_target::DIAGNOSTICS,
+                        "{} diagnostics for {}",
+                        count,
+                        uri
+                    );
+                } else {
+                    tracing::debug!(
+                        target: log_target::DIAGNOSTICS,
+                        "dropping stale diagnostics for {} (version {:?})",
+                        uri,
+                        version
+                    );
+                }
+            }
+        } else if method == "$/progress" {
+            progress.lock().await.apply(params);
+        } else if method == "window/logMessage" {
+            if let Some(msg) = ServerMessage::from_params(params) {
+                match msg.kind {
+                    MessageType::Error => {
+                        tracing::error!(target: log_target::SERVER, "{}: {}", server, msg.text)
+                    }
+                    MessageType::Warning => {
+                        tracing::warn!(target: log_target::SERVER, "{}: {}", server, msg.text)
+                    }
+                    MessageType::Info => {
+                        tracing::info!(target: log_target::SERVER, "{}: {}", server, msg.text)
+                    }
+                    MessageType::Log => {
+                        tracing::debug!(target: log_target::SERVER, "{}: {}", server, msg.text)
+                    }
+                }
+            }
+        }
+    }
+
+    let _ = dispatcher.lock().await.dispatch(message);
+}
+
+fn parse_document_symbols(
+    uri: &str,
+    result: serde_json::Value,
+) -> Result<Vec<SymbolInformation>, LspError> {
+    let err = |e: serde_json::Error| LspError::Serialization(format!("symbol parse: {}", e));
+    let items = match result {
+        serde_json::Value::Array(items) => items,
+        _ => return Ok(Vec::new()),
+    };
+    // `SymbolInformation` carries a location; `DocumentSymbol` does not.
+    if items.iter().any(|item| item.get("location").is_some()) {
+        return serde_json::from_value(serde_json::Value::Array(items)).map_err(err);
+    }
+    let nested: Vec<DocumentSymbol> =
+        serde_json::from_value(serde_json::Value::Array(items)).map_err(err)?;
+    let mut out = Vec::new();
+    for symbol in nested {
+        symbol.flatten(uri, None, &mut out);
+    }
+    Ok(out)
+}
+
+/// A list result that may be `null` for none.
+fn parse_optional_list<T: serde::de::DeserializeOwned>(
+    result: serde_json::Value,
+    what: &str,
+) -> Result<Vec<T>, LspError> {
+    if result.is_null() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_value(result)
+        .map_err(|e| LspError::Serialization(format!("{} parse: {}", what, e)))
+}
+
+fn parse_workspace_symbols(result: serde_json::Value) -> Result<Vec<SymbolInformation>, LspError> {
+    let mut items = match result {
+        serde_json::Value::Array(items) => items,
+        _ => return Ok(Vec::new()),
+    };
+    // A `WorkspaceSymbol` may leave out the range, to be resolved later;
+    // point at the top of the file instead.
+    for item in &mut items {
+        if let Some(location) = item.get_mut("location").and_then(|l| l.as_object_mut()) {
+            location
+                .entry("range")
+                .or_insert_with(|| serde_json::json!(LspRange::default()));
+        }
+    }
+    serde_json::from_value(serde_json::Value::Array(items))
+        .map_err(|e| LspError::Serialization(format!("workspace symbol parse: {}", e)))
+}
+
+fn parse_code_actions(result: serde_json::Value) -> Result<Vec<CodeAction>, LspError> {
+    let items = match result {
+        serde_json::Value::Array(items) => items,
+        _ => return Ok(Vec::new()),
+    };
+    let err = |e: serde_json::Error| LspError::Serialization(format!("code action parse: {}", e));
+    items
+        .into_iter()
+        .map(|item| {
+            // A bare `Command` names its command with a string where a
+            // `CodeAction` has a nested command object.
+            if item.get("command").is_some_and(|c| c.is_string()) {
+                let command: ServerCommand = serde_json::from_value(item).map_err(err)?;
+                Ok(CodeAction {
+                    title: command.title.clone(),
+                    kind: None,
+                    edit: None,
+                    diagnostics: Vec::new(),
+                    is_preferred: false,
+                    command: Some(command),
+                })
+            } else {
+                serde_json::from_value(item).map_err(err)
+            }
+        })
+        .collect()
+}
+
+fn parse_signature_help(result: serde_json::Value) -> Result<Option<SignatureHelp>, LspError> {
+    if result.is_null() {
+        return Ok(None);
+    }
+    let help: SignatureHelp = serde_json::from_value(result)
+        .map_err(|e| LspError::Serialization(format!("signature help parse: {}", e)))?;
+    Ok(Some(help).filter(|h| !h.signatures.is_empty()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::VecDeque;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// A server's stdout that yields a fixed script of reads, one chunk
+    /// per read, so frame splits happen exactly where a test puts them.
+    struct ScriptedOutput(VecDeque<Vec<u8>>);
+
+    impl ScriptedOutput {
+        fn new(chunks: Vec<Vec<u8>>) -> Self {
+            Self(chunks.into())
+        }
+    }
+
+    impl AsyncRead for ScriptedOutput {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            if let Some(mut chunk) = self.0.pop_front() {
+                let n = chunk.len().min(buf.remaining());
+                buf.put_slice(&chunk[..n]);
+                if n < chunk.len() {
+                    self.0.push_front(chunk.split_off(n));
+                }
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn diagnostics_frame(uri: &str, version: i64, message: &str) -> String {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {
+                "uri": uri,
+                "version": version,
+                "diagnostics": [{
+                    "range": {
+                        "start": {"line": 0, "character": 0},
+                        "end": {"line": 0, "character": 1}
+                    },
+                    "message": message
+                }]
+            }
+        })
+        .to_string()
+    }
+
+    fn response_frame(id: i64, result: &str) -> String {
+        crate::transport::serialize_response(id, serde_json::json!(result))
+    }
+
+    /// Run `chunks` through the reader and return what each request got,
+    /// the notifications seen (in order) and the final diagnostics.
+    async fn run_script(
+        ids: &[i64],
+        chunks: Vec<Vec<u8>>,
+    ) -> (Vec<Option<String>>, Vec<String>, DiagnosticStore) {
+        let dispatcher = Arc::new(Mutex::new(Dispatcher::new()));
+        let diagnostics = Arc::new(Mutex::new(DiagnosticStore::new()));
+        let progress = Arc::new(Mutex::new(ProgressTracker::new()));
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let receivers: Vec<_> = {
+            let mut disp = dispatcher.lock().await;
+            let seen = seen.clone();
+            disp.set_notification_handler(Box::new(move |method, params| {
+                let uri = params["uri"].as_str().unwrap_or_default().to_string();
+                seen.lock().unwrap().push(format!("{} {}", method, uri));
+            }));
+            ids.iter().map(|&id| disp.register_request(id)).collect()
+        };
+
+        read_server_output(
+            ScriptedOutput::new(chunks),
+            "scripted".to_string(),
+            dispatcher.clone(),
+            diagnostics.clone(),
+            progress,
+        )
+        .await;
+
+        let mut results = Vec::new();
+        for mut rx in receivers {
+            results.push(match rx.try_recv() {
+                Ok(DispatchResult::Success(v)) => v.as_str().map(str::to_string),
+                _ => None,
+            });
+        }
+        let seen = seen.lock().unwrap().clone();
+        let store = std::mem::take(&mut *diagnostics.lock().await);
+        (results, seen, store)
+    }
 
     fn test_config() -> LspServerConfig {
         LspServerConfig {
@@ -564,6 +1114,7 @@ mod tests {
             args: vec![],
             language_id: "test".to_string(),
             root_uri: Some("file:///test".to_string()),
+            disabled_features: Vec::new(),
         }
     }
 
@@ -627,6 +1178,26 @@ mod tests {
         assert_eq!(state, cloned);
     }
 
+    #[tokio::test]
+    async fn did_change_rejects_ranged_changes_for_full_sync() {
+        let client = LspClient::new(LspClientId::new(1), test_config());
+        let change = TextDocumentContentChangeEvent::ranged(LspRange::default(), "x");
+        let result = client.did_change("file:///test/a.rs", 2, &[change]).await;
+        assert!(matches!(result, Err(LspError::Unsupported(_))));
+    }
+
+    #[tokio::test]
+    async fn save_requests_are_skipped_unless_the_server_asked() {
+        // Not started, so anything actually sent would fail.
+        let client = LspClient::new(LspClientId::new(1), test_config());
+        assert!(client
+            .did_save("file:///test/a.rs", Some("x"))
+            .await
+            .is_ok());
+        let edits = client.will_save_wait_until("file:///test/a.rs").await;
+        assert!(edits.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn client_spawn_nonexistent_command() {
         let config = LspServerConfig {
@@ -634,6 +1205,7 @@ mod tests {
             args: vec![],
             language_id: "test".to_string(),
             root_uri: None,
+            disabled_features: Vec::new(),
         };
         let mut client = LspClient::new(LspClientId::new(1), config);
         let result = client.start().await;
@@ -645,4 +1217,190 @@ mod tests {
             other => panic!("expected SpawnFailed, got: {:?}", other),
         }
     }
+
+    #[test]
+    fn document_symbols_accept_flat_and_nested_responses() {
+        let range = serde_json::json!({
+            "start": { "line": 4, "character": 3 },
+            "end": { "line": 4, "character": 6 }
+        });
+        let flat = serde_json::json!([{
+            "name": "run",
+            "kind": 12,
+            "location": { "uri": "file:///a.rs", "range": range }
+        }]);
+        let symbols = parse_document_symbols("file:///a.rs", flat).unwrap();
+        assert_eq!(symbols[0].name, "run");
+
+        let nested = serde_json::json!([{
+            "name": "App",
+            "kind": 23,
+            "range": range,
+            "selectionRange": range,
+            "children": [{
+                "name": "new",
+                "kind": 6,
+                "range": range,
+                "selectionRange": range
+            }]
+        }]);
+        let symbols = parse_document_symbols("file:///a.rs", nested).unwrap();
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["App", "new"]);
+        assert_eq!(symbols[1].location.uri, "file:///a.rs");
+        assert_eq!(symbols[1].container_name.as_deref(), Some("App"));
+
+        assert!(
+            parse_document_symbols("file:///a.rs", serde_json::Value::Null)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn workspace_symbols_fill_in_missing_ranges() {
+        let result = serde_json::json!([{
+            "name": "Config",
+            "kind": 23,
+            "location": { "uri": "file:///src/config.rs" },
+            "containerName": "config"
+        }]);
+        let symbols = parse_workspace_symbols(result).unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].location.range, LspRange::default());
+        assert_eq!(symbols[0].container_name.as_deref(), Some("config"));
+    }
+
+    #[test]
+    fn call_hierarchy_items_keep_server_data() {
+        let range = serde_json::json!({
+            "start": { "line": 3, "character": 0 },
+            "end": { "line": 9, "character": 1 }
+        });
+        let result = serde_json::json!([{
+            "from": {
+                "name": "main",
+                "kind": 12,
+                "uri": "file:///src/main.rs",
+                "range": range,
+                "selectionRange": range,
+                "data": { "id": 7 }
+            },
+            "fromRanges": [range]
+        }]);
+        let calls: Vec<CallHierarchyIncomingCall> =
+            parse_optional_list(result, "incoming calls").unwrap();
+        assert_eq!(calls[0].from.name, "main");
+        assert_eq!(calls[0].from.kind, crate::types::SymbolKind::Function);
+        assert_eq!(calls[0].from_ranges[0].start.line, 3);
+        // The item is sent back as it came, including `data`.
+        let sent = serde_json::to_value(&calls[0].from).unwrap();
+        assert_eq!(sent["data"]["id"], 7);
+        assert_eq!(sent["selectionRange"]["end"]["character"], 1);
+        assert!(sent.get("detail").is_none());
+
+        let none: Vec<CallHierarchyItem> =
+            parse_optional_list(serde_json::Value::Null, "call hierarchy").unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn code_actions_accept_bare_commands() {
+        let result = serde_json::json!([
+            { "title": "Run test", "command": "test.run", "arguments": [{ "name": "it_works" }] },
+            {
+                "title": "Organize imports",
+                "kind": "source.organizeImports",
+                "command": { "title": "Organize", "command": "imports.organize" }
+            }
+        ]);
+        let actions = parse_code_actions(result).unwrap();
+        assert_eq!(actions.len(), 2);
+        let run = actions[0].command.as_ref().unwrap();
+        assert_eq!(actions[0].title, "Run test");
+        assert_eq!(run.command, "test.run");
+        assert_eq!(run.arguments[0]["name"], "it_works");
+        assert_eq!(actions[1].kind.as_deref(), Some("source.organizeImports"));
+        assert_eq!(
+            actions[1].command.as_ref().unwrap().command,
+            "imports.organize"
+        );
+        assert!(parse_code_actions(serde_json::Value::Null)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn signature_help_without_signatures_is_none() {
+        assert_eq!(parse_signature_help(serde_json::Value::Null).unwrap(), None);
+        let empty = serde_json::json!({ "signatures": [] });
+        assert_eq!(parse_signature_help(empty).unwrap(), None);
+        let one = serde_json::json!({ "signatures": [{ "label": "f(x)" }] });
+        let help = parse_signature_help(one).unwrap().unwrap();
+        assert_eq!(help.signatures[0].label, "f(x)");
+        assert!(parse_signature_help(serde_json::json!({ "signatures": 3 })).is_err());
+    }
+
+    #[tokio::test]
+    async fn out_of_order_responses_interleaved_with_diagnostics() {
+        let a = "file:///a.rs";
+        let v2 = frame_message(&diagnostics_frame(a, 2, "v2"));
+        let (v2_head, v2_tail) = v2.split_at(20);
+        let chunks = vec![
+            frame_message(&diagnostics_frame(a, 1, "v1")),
+            frame_message(&response_frame(2, "two")),
+            v2_head.to_vec(),
+            [v2_tail, &frame_message(&response_frame(1, "one"))].concat(),
+            // Computed before the edit that produced v2.
+            frame_message(&diagnostics_frame(a, 1, "late v1")),
+        ];
+        let (results, seen, store) = run_script(&[1, 2], chunks).await;
+
+        assert_eq!(results, vec![Some("one".into()), Some("two".into())]);
+        let publish = format!("textDocument/publishDiagnostics {}", a);
+        assert_eq!(seen, vec![publish.clone(), publish.clone(), publish]);
+        assert_eq!(store.get(a)[0].message, "v2");
+    }
+
+    #[tokio::test]
+    async fn batches_are_routed_in_order() {
+        let batch = format!(
+            "[{},{},{}]",
+            diagnostics_frame("file:///b.rs", 1, "b"),
+            response_frame(3, "three"),
+            r#"{"jsonrpc":"2.0","method":"window/logMessage","params":{}}"#
+        );
+        let chunks = vec![
+            frame_message(&batch),
+            frame_message(r#"{"jsonrpc":"2.0","method":"$/progress","params":{}}"#),
+        ];
+        let (results, seen, store) = run_script(&[3], chunks).await;
+
+        assert_eq!(results, vec![Some("three".into())]);
+        assert_eq!(
+            seen,
+            vec![
+                "textDocument/publishDiagnostics file:///b.rs".to_string(),
+                "window/logMessage ".to_string(),
+                "$/progress ".to_string(),
+            ]
+        );
+        assert!(store.has_diagnostics("file:///b.rs"));
+    }
+
+    #[tokio::test]
+    async fn duplicate_and_unknown_responses_are_dropped() {
+        let chunks = vec![
+            frame_message(&response_frame(5, "first")),
+            frame_message(&response_frame(5, "again")),
+            frame_message(&response_frame(404, "nobody asked")),
+            b"Content-Length: nope\r\n\r\n".to_vec(),
+            frame_message("not json"),
+            frame_message(&response_frame(6, "six")),
+        ];
+        let (results, seen, _) = run_script(&[5, 6], chunks).await;
+
+        assert_eq!(results, vec![Some("first".into()), Some("six".into())]);
+        assert!(seen.is_empty());
+    }
 }
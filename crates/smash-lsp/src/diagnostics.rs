@@ -10,6 +10,8 @@ use crate::types::Diagnostic;
 pub struct DiagnosticStore {
     /// Diagnostics per document URI.
     store: HashMap<String, Vec<Diagnostic>>,
+    /// Newest document version diagnostics were published for, per URI.
+    versions: HashMap<String, i64>,
     /// Callback invoked when diagnostics for a URI change.
     #[allow(clippy::type_complexity)]
     on_update: Option<Box<dyn Fn(&str, &[Diagnostic]) + Send + Sync>>,
@@ -35,6 +37,7 @@ impl DiagnosticStore {
     pub fn new() -> Self {
         Self {
             store: HashMap::new(),
+            versions: HashMap::new(),
             on_update: None,
         }
     }
@@ -52,6 +55,28 @@ impl DiagnosticStore {
     /// If the diagnostics list is empty, the entry is removed (cleared).
     /// Fires the on_update callback if set.
     pub fn publish(&mut self, uri: String, diagnostics: Vec<Diagnostic>) {
+        self.publish_versioned(uri, None, diagnostics);
+    }
+
+    /// Update diagnostics for a URI computed against document `version`.
+    ///
+    /// Diagnostics for an older version than the last one published are
+    /// stale and ignored, so a late message cannot roll a document back.
+    /// Returns whether the store was updated.
+    pub fn publish_versioned(
+        &mut self,
+        uri: String,
+        version: Option<i64>,
+        diagnostics: Vec<Diagnostic>,
+    ) -> bool {
+        if let Some(version) = version {
+            match self.versions.get(&uri) {
+                Some(&newest) if version < newest => return false,
+                _ => {
+                    self.versions.insert(uri.clone(), version);
+                }
+            }
+        }
         if diagnostics.is_empty() {
             self.store.remove(&uri);
         } else {
@@ -61,6 +86,7 @@ impl DiagnosticStore {
             let diags = self.get(&uri);
             callback(&uri, diags);
         }
+        true
     }
 
     /// Get diagnostics for a specific URI.
@@ -91,11 +117,13 @@ impl DiagnosticStore {
     /// Clear all diagnostics.
     pub fn clear_all(&mut self) {
         self.store.clear();
+        self.versions.clear();
     }
 
     /// Clear diagnostics for a specific URI.
     pub fn clear(&mut self, uri: &str) {
         self.store.remove(uri);
+        self.versions.remove(uri);
     }
 
     /// Get an iterator over all (uri, diagnostics) pairs.
@@ -124,6 +152,8 @@ mod tests {
             message: message.to_string(),
             source: Some("test".to_string()),
             code: None,
+            code_description: None,
+            related_information: Vec::new(),
         }
     }
 
@@ -140,6 +170,23 @@ mod tests {
         assert_eq!(store.total_count(), 0);
     }
 
+    #[test]
+    fn store_ignores_diagnostics_for_older_versions() {
+        let mut store = DiagnosticStore::new();
+        let uri = "file:///a.rs".to_string();
+        let new = vec![make_diagnostic(1, "new", DiagnosticSeverity::Error)];
+        let old = vec![make_diagnostic(2, "old", DiagnosticSeverity::Error)];
+        assert!(store.publish_versioned(uri.clone(), Some(3), new));
+        assert!(!store.publish_versioned(uri.clone(), Some(2), old.clone()));
+        assert_eq!(store.get(&uri)[0].message, "new");
+        // Unversioned and same-version publishes still apply.
+        assert!(store.publish_versioned(uri.clone(), None, old.clone()));
+        assert!(store.publish_versioned(uri.clone(), Some(3), Vec::new()));
+        assert!(!store.has_diagnostics(&uri));
+        store.clear(&uri);
+        assert!(store.publish_versioned(uri.clone(), Some(1), old));
+    }
+
     #[test]
     fn store_publish_and_get() {
         let mut store = DiagnosticStore::new();
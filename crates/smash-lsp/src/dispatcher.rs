@@ -2,22 +2,132 @@
 //!
 //! Tracks pending requests by ID, routes responses to waiting callers
 //! via oneshot channels, and handles server-initiated notifications.
-use std::collections::HashMap;
+//!
+//! Messages are handled strictly in the order they are dispatched, so
+//! notifications and responses keep the order the server sent them in.
+//! Responses may resolve requests in any order; a second response for
+//! the same id, or one for an id never sent, is logged and dropped.
+//! Requests from the server go to the request handler, which must answer
+//! each one; without a handler they are refused.
+use std::collections::{HashMap, VecDeque};
 
-use tokio::sync::oneshot;
+use tokio::sync::{mpsc, oneshot};
 
 use crate::error::LspError;
-use crate::transport::{JsonRpcMessage, RpcError};
+use crate::log_target;
+use crate::transport::{
+    frame_message, serialize_error_response, serialize_response, JsonRpcMessage, RpcError,
+};
+
+/// How many answered request ids are remembered to recognise duplicates.
+const RECENT_IDS: usize = 64;
+
+/// JSON-RPC "method not found" code.
+pub const METHOD_NOT_FOUND: i32 = -32601;
+
+/// JSON-RPC "invalid params" code.
+pub const INVALID_PARAMS: i32 = -32602;
+
+/// JSON-RPC "internal error" code, used for locally failed requests.
+pub const INTERNAL_ERROR: i32 = -32603;
 
 /// Callback type for handling notifications from the server.
 pub type NotificationHandler = Box<dyn Fn(String, serde_json::Value) + Send + Sync>;
 
+/// Callback type for handling requests from the server.
+pub type RequestHandler = Box<dyn Fn(ServerRequest) + Send + Sync>;
+
+/// A request from the server, such as `workspace/applyEdit`.
+///
+/// The server waits for an answer, so one is always sent: either through
+/// [`reply`](Self::reply) or [`reply_error`](Self::reply_error), or as an
+/// internal error when the request is dropped unanswered.
+pub struct ServerRequest {
+    /// The id to answer with.
+    pub id: i64,
+    /// The method the server called.
+    pub method: String,
+    /// The request parameters.
+    pub params: serde_json::Value,
+    writer: Option<mpsc::Sender<Vec<u8>>>,
+}
+
+impl ServerRequest {
+    /// Create a request whose answer is written to `writer`.
+    pub fn new(
+        id: i64,
+        method: String,
+        params: serde_json::Value,
+        writer: mpsc::Sender<Vec<u8>>,
+    ) -> Self {
+        Self {
+            id,
+            method,
+            params,
+            writer: Some(writer),
+        }
+    }
+
+    /// Answer with `result`. Does not wait, so it may be called from any
+    /// thread.
+    pub fn reply(mut self, result: serde_json::Value) -> Result<(), LspError> {
+        let body = serialize_response(self.id, result);
+        self.send(body)
+    }
+
+    /// Answer with an error.
+    pub fn reply_error(mut self, code: i32, message: &str) -> Result<(), LspError> {
+        let body = serialize_error_response(self.id, code, message);
+        self.send(body)
+    }
+
+    fn send(&mut self, body: String) -> Result<(), LspError> {
+        let writer = self.writer.take().ok_or(LspError::ServerCrashed)?;
+        tracing::debug!(target: log_target::RPC, "-> response #{} {}", self.id, self.method);
+        tracing::trace!(target: log_target::RPC, "-> {}", body);
+        writer
+            .try_send(frame_message(&body))
+            .map_err(|_| LspError::ServerCrashed)
+    }
+}
+
+impl Drop for ServerRequest {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            tracing::warn!(
+                target: log_target::RPC,
+                "server request #{} {} dropped unanswered",
+                self.id,
+                self.method
+            );
+            let body = serialize_error_response(self.id, INTERNAL_ERROR, "request was not handled");
+            let _ = self.send(body);
+        }
+    }
+}
+
+impl std::fmt::Debug for ServerRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerRequest")
+            .field("id", &self.id)
+            .field("method", &self.method)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
 /// Manages pending requests and routes responses.
 pub struct Dispatcher {
     /// Map of request ID to pending response sender.
     pending: HashMap<i64, oneshot::Sender<DispatchResult>>,
+    /// Recently answered request ids, oldest first.
+    answered: VecDeque<i64>,
     /// Handler for server-initiated notifications.
     notification_handler: Option<NotificationHandler>,
+    /// Handler for server-initiated requests.
+    request_handler: Option<RequestHandler>,
+    /// Where answers to server requests are written.
+    writer: Option<mpsc::Sender<Vec<u8>>>,
 }
 
 /// The result dispatched to a waiting request.
@@ -34,7 +144,10 @@ impl Dispatcher {
     pub fn new() -> Self {
         Self {
             pending: HashMap::new(),
+            answered: VecDeque::new(),
             notification_handler: None,
+            request_handler: None,
+            writer: None,
         }
     }
 
@@ -43,10 +156,31 @@ impl Dispatcher {
         self.notification_handler = Some(handler);
     }
 
+    /// Set the handler for server-initiated requests.
+    pub fn set_request_handler(&mut self, handler: RequestHandler) {
+        self.request_handler = Some(handler);
+    }
+
+    /// Set where answers to server requests are written. Until this is
+    /// set, server requests are only logged.
+    pub fn set_writer(&mut self, writer: mpsc::Sender<Vec<u8>>) {
+        self.writer = Some(writer);
+    }
+
     /// Register a pending request and return a receiver for the response.
+    ///
+    /// Reusing the id of a request still pending fails the earlier one,
+    /// since the server's answer could not be told apart.
     pub fn register_request(&mut self, id: i64) -> oneshot::Receiver<DispatchResult> {
         let (tx, rx) = oneshot::channel();
-        self.pending.insert(id, tx);
+        if let Some(previous) = self.pending.insert(id, tx) {
+            tracing::warn!(target: log_target::RPC, "request id {} reused while pending", id);
+            let _ = previous.send(DispatchResult::Error(RpcError {
+                code: INTERNAL_ERROR,
+                message: format!("request id {} was reused before a response arrived", id),
+            }));
+        }
+        self.answered.retain(|&answered| answered != id);
         rx
     }
 
@@ -59,11 +193,18 @@ impl Dispatcher {
     ///
     /// - Responses are matched to pending requests by ID.
     /// - Notifications are forwarded to the notification handler.
-    /// - Requests from the server are logged but not handled.
+    /// - Requests from the server go to the request handler, or are
+    ///   answered with "method not found" if there is none.
     pub fn dispatch(&mut self, message: JsonRpcMessage) -> Result<(), LspError> {
         match message {
             JsonRpcMessage::Response { id, result, error } => {
                 if let Some(sender) = self.pending.remove(&id) {
+                    tracing::debug!(
+                        target: log_target::RPC,
+                        "<- response #{}{}",
+                        id,
+                        if error.is_some() { " (error)" } else { "" }
+                    );
                     let dispatch_result = if let Some(err) = error {
                         DispatchResult::Error(err)
                     } else {
@@ -71,22 +212,59 @@ impl Dispatcher {
                     };
                     // If the receiver was dropped, that's ok
                     let _ = sender.send(dispatch_result);
+                    if self.answered.len() == RECENT_IDS {
+                        self.answered.pop_front();
+                    }
+                    self.answered.push_back(id);
+                    Ok(())
+                } else if self.answered.contains(&id) {
+                    tracing::warn!(
+                        target: log_target::RPC,
+                        "ignoring duplicate response for request id: {}",
+                        id
+                    );
                     Ok(())
                 } else {
-                    tracing::warn!("received response for unknown request id: {}", id);
+                    tracing::warn!(
+                        target: log_target::RPC,
+                        "received response for unknown request id: {}",
+                        id
+                    );
                     Ok(())
                 }
             }
             JsonRpcMessage::Notification { method, params } => {
+                tracing::debug!(target: log_target::RPC, "<- notification {}", method);
                 if let Some(handler) = &self.notification_handler {
                     handler(method, params);
                 } else {
-                    tracing::debug!("unhandled notification: {}", method);
+                    tracing::debug!(target: log_target::RPC, "unhandled notification: {}", method);
                 }
                 Ok(())
             }
-            JsonRpcMessage::Request { method, .. } => {
-                tracing::debug!("received server request (unhandled): {}", method);
+            JsonRpcMessage::Request { id, method, params } => {
+                tracing::debug!(target: log_target::RPC, "<- server request #{} {}", id, method);
+                let Some(writer) = self.writer.clone() else {
+                    tracing::debug!(
+                        target: log_target::RPC,
+                        "received server request (unhandled): {}",
+                        method
+                    );
+                    return Ok(());
+                };
+                let request = ServerRequest::new(id, method, params, writer);
+                // Progress tokens need no setup; `$/progress` for them is
+                // tracked by the client.
+                if request.method == "window/workDoneProgress/create" {
+                    return request.reply(serde_json::Value::Null);
+                }
+                match &self.request_handler {
+                    Some(handler) => handler(request),
+                    None => {
+                        let message = format!("unsupported request: {}", request.method);
+                        request.reply_error(METHOD_NOT_FOUND, &message)?;
+                    }
+                }
                 Ok(())
             }
         }
@@ -229,6 +407,79 @@ mod tests {
         assert!(disp.dispatch(request).is_ok());
     }
 
+    fn answer(rx: &mut mpsc::Receiver<Vec<u8>>) -> serde_json::Value {
+        let framed = rx.try_recv().expect("an answer was written");
+        let framed = String::from_utf8(framed).unwrap();
+        let body = framed.split("\r\n\r\n").nth(1).unwrap();
+        serde_json::from_str(body).unwrap()
+    }
+
+    fn apply_edit_request(id: i64) -> JsonRpcMessage {
+        JsonRpcMessage::Request {
+            id,
+            method: "workspace/applyEdit".into(),
+            params: serde_json::json!({ "edit": {} }),
+        }
+    }
+
+    #[test]
+    fn dispatcher_refuses_requests_without_handler() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let mut disp = Dispatcher::new();
+        disp.set_writer(tx);
+        disp.dispatch(apply_edit_request(7)).unwrap();
+        let answer = answer(&mut rx);
+        assert_eq!(answer["id"], 7);
+        assert_eq!(answer["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn dispatcher_accepts_progress_tokens() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let mut disp = Dispatcher::new();
+        disp.set_writer(tx);
+        disp.dispatch(JsonRpcMessage::Request {
+            id: 9,
+            method: "window/workDoneProgress/create".into(),
+            params: serde_json::json!({ "token": "indexing" }),
+        })
+        .unwrap();
+        let answer = answer(&mut rx);
+        assert_eq!(answer["id"], 9);
+        assert!(answer["result"].is_null());
+        assert!(answer.get("error").is_none());
+    }
+
+    #[test]
+    fn dispatcher_routes_requests_to_handler() {
+        use std::sync::{Arc, Mutex};
+
+        let (tx, mut rx) = mpsc::channel(4);
+        let held = Arc::new(Mutex::new(Vec::new()));
+        let held_clone = held.clone();
+        let mut disp = Dispatcher::new();
+        disp.set_writer(tx);
+        disp.set_request_handler(Box::new(move |request| {
+            held_clone.lock().unwrap().push(request);
+        }));
+        disp.dispatch(apply_edit_request(3)).unwrap();
+        disp.dispatch(apply_edit_request(4)).unwrap();
+        assert!(rx.try_recv().is_err(), "answers wait for the handler");
+
+        let mut requests = std::mem::take(&mut *held.lock().unwrap());
+        assert_eq!(requests[0].method, "workspace/applyEdit");
+        let second = requests.pop().unwrap();
+        let first = requests.pop().unwrap();
+        first.reply(serde_json::json!({ "applied": true })).unwrap();
+        assert_eq!(answer(&mut rx)["result"]["applied"], true);
+
+        // Dropping a request still answers it, so the server never hangs.
+        drop(second);
+        let answer = answer(&mut rx);
+        assert_eq!(answer["id"], 4);
+        assert_eq!(answer["error"]["code"], INTERNAL_ERROR);
+    }
+
     #[test]
     fn dispatcher_cancel_existing() {
         let mut disp = Dispatcher::new();
@@ -335,4 +586,39 @@ mod tests {
         let debug = format!("{:?}", result);
         assert!(debug.contains("Success"));
     }
+
+    #[tokio::test]
+    async fn dispatcher_reused_id_fails_earlier_request() {
+        let mut disp = Dispatcher::new();
+        let first = disp.register_request(7);
+        let second = disp.register_request(7);
+        assert_eq!(disp.pending_count(), 1);
+        match first.await.unwrap() {
+            DispatchResult::Error(err) => assert!(err.message.contains("reused")),
+            _ => panic!("expected error"),
+        }
+        disp.dispatch(JsonRpcMessage::Response {
+            id: 7,
+            result: Some(serde_json::json!("ok")),
+            error: None,
+        })
+        .unwrap();
+        assert!(matches!(second.await.unwrap(), DispatchResult::Success(_)));
+    }
+
+    #[test]
+    fn dispatcher_remembers_a_bounded_number_of_answered_ids() {
+        let mut disp = Dispatcher::new();
+        for id in 0..(RECENT_IDS as i64 + 10) {
+            let _rx = disp.register_request(id);
+            disp.dispatch(JsonRpcMessage::Response {
+                id,
+                result: None,
+                error: None,
+            })
+            .unwrap();
+        }
+        assert_eq!(disp.answered.len(), RECENT_IDS);
+        assert_eq!(disp.answered.front(), Some(&10));
+    }
 }
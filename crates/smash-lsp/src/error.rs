@@ -46,6 +46,10 @@ pub enum LspError {
     /// Invalid response from server.
     #[error("invalid response: {0}")]
     InvalidResponse(String),
+
+    /// The server did not negotiate the capability an operation needs.
+    #[error("capability not supported by server: {0}")]
+    Unsupported(String),
 }
 
 #[cfg(test)]
@@ -119,6 +123,15 @@ mod tests {
         assert_eq!(err.to_string(), "invalid response: missing result");
     }
 
+    #[test]
+    fn error_unsupported_display() {
+        let err = LspError::Unsupported("incremental sync".into());
+        assert_eq!(
+            err.to_string(),
+            "capability not supported by server: incremental sync"
+        );
+    }
+
     #[test]
     fn error_is_debug() {
         let err = LspError::Timeout(5);
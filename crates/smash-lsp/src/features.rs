@@ -0,0 +1,205 @@
+//! Features that can be switched off per server, such as a server's
+//! formatting when another tool formats the language.
+//!
+//! A disabled feature is left out of the capabilities the client
+//! advertises in `initialize`, masked out of what the server offers, and
+//! refused when routing requests through the registry.
+use std::fmt;
+
+use crate::types::LspCapabilities;
+
+/// One switchable capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LspFeature {
+    Completion,
+    Hover,
+    /// Go to definition, and to type definitions, implementations and
+    /// declarations.
+    Definition,
+    References,
+    CodeActions,
+    Formatting,
+    Rename,
+    Diagnostics,
+    DocumentSymbols,
+    WorkspaceSymbols,
+    SignatureHelp,
+    /// `willSaveWaitUntil` edits before a save.
+    WillSave,
+    InlayHints,
+    CallHierarchy,
+}
+
+impl LspFeature {
+    /// Every feature, in the order they are documented.
+    pub const ALL: [LspFeature; 14] = [
+        LspFeature::Completion,
+        LspFeature::Hover,
+        LspFeature::Definition,
+        LspFeature::References,
+        LspFeature::CodeActions,
+        LspFeature::Formatting,
+        LspFeature::Rename,
+        LspFeature::Diagnostics,
+        LspFeature::DocumentSymbols,
+        LspFeature::WorkspaceSymbols,
+        LspFeature::SignatureHelp,
+        LspFeature::WillSave,
+        LspFeature::InlayHints,
+        LspFeature::CallHierarchy,
+    ];
+
+    /// The name used in config files, e.g. `code_actions`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LspFeature::Completion => "completion",
+            LspFeature::Hover => "hover",
+            LspFeature::Definition => "definition",
+            LspFeature::References => "references",
+            LspFeature::CodeActions => "code_actions",
+            LspFeature::Formatting => "formatting",
+            LspFeature::Rename => "rename",
+            LspFeature::Diagnostics => "diagnostics",
+            LspFeature::DocumentSymbols => "document_symbols",
+            LspFeature::WorkspaceSymbols => "workspace_symbols",
+            LspFeature::SignatureHelp => "signature_help",
+            LspFeature::WillSave => "will_save",
+            LspFeature::InlayHints => "inlay_hints",
+            LspFeature::CallHierarchy => "call_hierarchy",
+        }
+    }
+
+    /// Look a feature up by its config name.
+    pub fn from_name(name: &str) -> Option<LspFeature> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// Where the feature sits in the client capabilities, as JSON
+    /// pointers to the entries to remove.
+    fn client_capabilities(self) -> &'static [&'static str] {
+        match self {
+            LspFeature::Completion => &["/textDocument/completion"],
+            LspFeature::Hover => &["/textDocument/hover"],
+            LspFeature::Definition => &[
+                "/textDocument/definition",
+                "/textDocument/typeDefinition",
+                "/textDocument/implementation",
+                "/textDocument/declaration",
+            ],
+            LspFeature::References => &["/textDocument/references"],
+            LspFeature::CodeActions => &["/textDocument/codeAction"],
+            LspFeature::Formatting => {
+                &["/textDocument/formatting", "/textDocument/rangeFormatting"]
+            }
+            LspFeature::Rename => &["/textDocument/rename"],
+            LspFeature::Diagnostics => &["/textDocument/publishDiagnostics"],
+            LspFeature::DocumentSymbols => &["/textDocument/documentSymbol"],
+            LspFeature::WorkspaceSymbols => &["/workspace/symbol"],
+            LspFeature::SignatureHelp => &["/textDocument/signatureHelp"],
+            LspFeature::WillSave => &["/textDocument/synchronization/willSaveWaitUntil"],
+            LspFeature::InlayHints => &["/textDocument/inlayHint"],
+            LspFeature::CallHierarchy => &["/textDocument/callHierarchy"],
+        }
+    }
+}
+
+impl fmt::Display for LspFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Remove `disabled` features from client capabilities built by
+/// [`crate::types::client_capabilities`].
+pub fn remove_client_capabilities(caps: &mut serde_json::Value, disabled: &[LspFeature]) {
+    let pointers = disabled.iter().flat_map(|f| f.client_capabilities());
+    for pointer in pointers {
+        let Some((parent, key)) = pointer.rsplit_once('/') else {
+            continue;
+        };
+        if let Some(parent) = caps.pointer_mut(parent).and_then(|p| p.as_object_mut()) {
+            parent.remove(key);
+        }
+    }
+}
+
+/// Mask `disabled` features out of what a server offers.
+pub fn mask_capabilities(caps: &mut LspCapabilities, disabled: &[LspFeature]) {
+    for feature in disabled {
+        match feature {
+            LspFeature::Completion => {
+                caps.completion = false;
+                caps.completion_trigger_characters.clear();
+            }
+            LspFeature::Hover => caps.hover = false,
+            LspFeature::Definition => caps.goto_definition = false,
+            LspFeature::References => caps.find_references = false,
+            LspFeature::CodeActions => caps.code_actions = false,
+            LspFeature::Formatting => {
+                caps.formatting = false;
+                caps.range_formatting = false;
+            }
+            LspFeature::Rename => caps.rename = false,
+            LspFeature::Diagnostics => caps.diagnostics = false,
+            LspFeature::DocumentSymbols => caps.document_symbols = false,
+            LspFeature::WorkspaceSymbols => caps.workspace_symbols = false,
+            LspFeature::SignatureHelp => {
+                caps.signature_help = false;
+                caps.signature_trigger_characters.clear();
+            }
+            LspFeature::WillSave => caps.will_save_wait_until = false,
+            LspFeature::InlayHints => caps.inlay_hints = false,
+            LspFeature::CallHierarchy => caps.call_hierarchy = false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::client_capabilities;
+
+    #[test]
+    fn names_round_trip() {
+        for feature in LspFeature::ALL {
+            assert_eq!(LspFeature::from_name(feature.name()), Some(feature));
+        }
+        assert_eq!(LspFeature::from_name("semantic_tokens"), None);
+    }
+
+    #[test]
+    fn disabled_features_are_not_advertised() {
+        let mut caps = client_capabilities();
+        remove_client_capabilities(
+            &mut caps,
+            &[
+                LspFeature::Formatting,
+                LspFeature::WorkspaceSymbols,
+                LspFeature::WillSave,
+            ],
+        );
+        assert!(caps["textDocument"]["formatting"].is_null());
+        assert!(caps["textDocument"]["rangeFormatting"].is_null());
+        assert!(caps["workspace"]["symbol"].is_null());
+        assert!(caps["textDocument"]["synchronization"]["willSaveWaitUntil"].is_null());
+        // Everything else is kept.
+        assert!(caps["textDocument"]["publishDiagnostics"].is_object());
+        assert_eq!(caps["textDocument"]["synchronization"]["didSave"], true);
+    }
+
+    #[test]
+    fn disabled_features_are_masked_from_the_server() {
+        let mut caps = LspCapabilities::from_server_capabilities(&serde_json::json!({
+            "documentFormattingProvider": true,
+            "documentRangeFormattingProvider": true,
+            "hoverProvider": true,
+            "completionProvider": { "triggerCharacters": ["."] }
+        }));
+        mask_capabilities(&mut caps, &[LspFeature::Formatting, LspFeature::Completion]);
+        assert!(!caps.formatting);
+        assert!(!caps.range_formatting);
+        assert!(!caps.completion);
+        assert!(caps.completion_trigger_characters.is_empty());
+        assert!(caps.hover);
+    }
+}
@@ -7,17 +7,29 @@ pub mod client;
 pub mod diagnostics;
 pub mod dispatcher;
 pub mod error;
+pub mod features;
+pub mod log_target;
 pub mod registry;
 pub mod transport;
 pub mod types;
+pub mod window;
 
 // Re-export key types for convenience.
 pub use client::{ClientState, LspClient};
 pub use diagnostics::DiagnosticStore;
+pub use dispatcher::ServerRequest;
 pub use error::LspError;
+pub use features::LspFeature;
 pub use registry::LspRegistry;
+pub use types::{lsp_position_for, position_from_lsp};
 pub use types::{
-    CodeAction, CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, Hover,
-    Location, LspCapabilities, LspClientId, LspPosition, LspRange, LspServerConfig, MarkupContent,
-    SymbolInformation, SymbolKind, TextEdit, WorkspaceEdit,
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, CodeAction,
+    CodeDescription, CompletionItem, CompletionItemKind, Diagnostic, DiagnosticRelatedInformation,
+    DiagnosticSeverity, DocumentChange, DocumentSymbol, Documentation, GotoTarget, Hover, Location,
+    LspCapabilities, LspClientId, LspPosition, LspRange, LspServerConfig, MarkupContent,
+    ParameterInformation, ParameterLabel, PositionEncoding, ResourceOperation, ResourceOptions,
+    SaveNotification, ServerCommand, SignatureHelp, SignatureInformation, SymbolInformation,
+    SymbolKind, TextDocumentContentChangeEvent, TextDocumentEdit, TextDocumentSyncKind, TextEdit,
+    VersionedTextDocumentIdentifier, WorkspaceEdit, ORGANIZE_IMPORTS,
 };
+pub use window::{MessageType, Progress, ProgressTracker, ServerMessage};
@@ -0,0 +1,16 @@
+//! `tracing` targets for the LSP client subsystems.
+//!
+//! Each target can be filtered on its own, e.g. `smash_lsp::rpc=debug`
+//! logs every message without enabling debug output elsewhere.
+
+/// Server process spawn, initialize handshake, shutdown, and exit.
+pub const LIFECYCLE: &str = "smash_lsp::lifecycle";
+
+/// JSON-RPC requests, responses, and notifications.
+pub const RPC: &str = "smash_lsp::rpc";
+
+/// Diagnostics published by servers.
+pub const DIAGNOSTICS: &str = "smash_lsp::diagnostics";
+
+/// Messages servers write to their log (`window/logMessage`).
+pub const SERVER: &str = "smash_lsp::server";
@@ -1,17 +1,22 @@
 //! LSP registry managing multiple language server clients.
 //!
 //! Maps language IDs to `LspClient` instances and orchestrates
-//! server lifecycle across all active languages.
+//! server lifecycle across all active languages. Open documents are
+//! tracked by URI so requests about a document reach the server for its
+//! language.
 use std::collections::HashMap;
 
 use crate::client::{ClientState, LspClient};
 use crate::error::LspError;
+use crate::features::LspFeature;
 use crate::types::{LspClientId, LspServerConfig};
 
 /// Manages multiple LSP clients, one per language.
 pub struct LspRegistry {
     /// Map of language ID to client.
     clients: HashMap<String, LspClient>,
+    /// Language ID of each open document, by URI.
+    documents: HashMap<String, String>,
     /// Counter for generating unique client IDs.
     next_id: u64,
 }
@@ -21,6 +26,7 @@ impl LspRegistry {
     pub fn new() -> Self {
         Self {
             clients: HashMap::new(),
+            documents: HashMap::new(),
             next_id: 1,
         }
     }
@@ -58,6 +64,42 @@ impl LspRegistry {
         self.clients.get_mut(language_id)
     }
 
+    /// The running client for a language, if any.
+    pub fn running(&self, language_id: &str) -> Option<&LspClient> {
+        self.clients
+            .get(language_id)
+            .filter(|c| c.state() == ClientState::Running)
+    }
+
+    /// Record that `uri` is open as a `language_id` document.
+    pub fn open_document(&mut self, uri: &str, language_id: &str) {
+        self.documents
+            .insert(uri.to_string(), language_id.to_string());
+    }
+
+    /// Forget `uri`. Returns the language it was open as.
+    pub fn close_document(&mut self, uri: &str) -> Option<String> {
+        self.documents.remove(uri)
+    }
+
+    /// Language ID `uri` was opened as.
+    pub fn document_language(&self, uri: &str) -> Option<&str> {
+        self.documents.get(uri).map(String::as_str)
+    }
+
+    /// The running client for the language `uri` was opened as.
+    pub fn client_for_document(&self, uri: &str) -> Option<&LspClient> {
+        self.document_language(uri)
+            .and_then(|lang| self.running(lang))
+    }
+
+    /// The server for the document at `uri`, unless `feature` is
+    /// switched off for it.
+    pub fn client_for_feature(&self, uri: &str, feature: LspFeature) -> Option<&LspClient> {
+        self.client_for_document(uri)
+            .filter(|client| client.config().enables(feature))
+    }
+
     /// Check if a server is running for a given language.
     pub fn has_server(&self, language_id: &str) -> bool {
         self.clients
@@ -110,6 +152,7 @@ impl std::fmt::Debug for LspRegistry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("LspRegistry")
             .field("client_count", &self.clients.len())
+            .field("document_count", &self.documents.len())
             .field("next_id", &self.next_id)
             .finish()
     }
@@ -144,6 +187,25 @@ mod tests {
         assert!(!reg.has_server("rust"));
     }
 
+    #[test]
+    fn registry_tracks_document_languages() {
+        let mut reg = LspRegistry::new();
+        reg.open_document("file:///a.rs", "rust");
+        reg.open_document("file:///b.py", "python");
+        assert_eq!(reg.document_language("file:///a.rs"), Some("rust"));
+        assert_eq!(reg.document_language("file:///b.py"), Some("python"));
+        // No server is running for either language.
+        assert!(reg.client_for_document("file:///a.rs").is_none());
+        assert!(reg.running("rust").is_none());
+
+        // Reopening under another language moves the document.
+        reg.open_document("file:///a.rs", "toml");
+        assert_eq!(reg.document_language("file:///a.rs"), Some("toml"));
+        assert_eq!(reg.close_document("file:///a.rs").as_deref(), Some("toml"));
+        assert!(reg.document_language("file:///a.rs").is_none());
+        assert!(reg.close_document("file:///a.rs").is_none());
+    }
+
     #[test]
     fn registry_debug_format() {
         let reg = LspRegistry::new();
@@ -177,6 +239,7 @@ mod tests {
             args: vec![],
             language_id: "test".to_string(),
             root_uri: None,
+            disabled_features: Vec::new(),
         };
         let result = reg.start_server(config).await;
         assert!(result.is_err());
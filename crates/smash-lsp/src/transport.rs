@@ -1,10 +1,23 @@
 //! JSON-RPC transport for LSP communication.
 //!
 //! Implements Content-Length header framing per the LSP specification.
+//! Reading is lenient about what servers actually send: header names in
+//! any case, no Content-Type, bare `\n` line endings, and frames split
+//! across (or packed into) arbitrary reads.
 use std::sync::atomic::{AtomicI64, Ordering};
 
 use crate::error::LspError;
 
+/// Largest message body accepted from a server. Bigger messages are
+/// skipped rather than buffered.
+pub const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Longest header block accepted before giving up on finding its end.
+pub const MAX_HEADER_BYTES: usize = 8 * 1024;
+
+/// Bytes of offending input quoted in framing errors.
+const PREVIEW_BYTES: usize = 64;
+
 /// Global request ID counter.
 static NEXT_REQUEST_ID: AtomicI64 = AtomicI64::new(1);
 
@@ -106,27 +119,186 @@ pub fn serialize_error_response(id: i64, code: i32, message: &str) -> String {
 
 /// Parse the Content-Length value from raw header bytes.
 ///
+/// The header name is matched case-insensitively and other headers,
+/// including Content-Type, are optional and ignored.
 /// Returns the body length if the header is valid.
 pub fn parse_content_length(header: &str) -> Result<usize, LspError> {
+    content_length(header).map_err(LspError::InvalidResponse)
+}
+
+fn content_length(header: &str) -> Result<usize, String> {
     for line in header.lines() {
-        let line = line.trim();
-        if let Some(value) = line.strip_prefix("Content-Length:") {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if name.trim().eq_ignore_ascii_case("Content-Length") {
             let value = value.trim();
-            return value.parse::<usize>().map_err(|_| {
-                LspError::InvalidResponse(format!("invalid Content-Length: {}", value))
-            });
+            return value
+                .parse::<usize>()
+                .map_err(|_| format!("invalid Content-Length: {:?}", value));
         }
     }
-    Err(LspError::InvalidResponse(
-        "missing Content-Length header".to_string(),
-    ))
+    Err("missing Content-Length header".to_string())
+}
+
+/// Find the blank line ending a header block, accepting `\r\n\r\n` or
+/// `\n\n`, whichever comes first. Returns the header length and the offset
+/// of the body. Only the first [`MAX_HEADER_BYTES`] are searched, so a large
+/// buffered body is never rescanned.
+fn find_header_end(input: &[u8]) -> Option<(usize, usize)> {
+    let head = &input[..input.len().min(MAX_HEADER_BYTES + 4)];
+    (0..head.len()).find_map(|i| {
+        let rest = &head[i..];
+        if rest.starts_with(b"\r\n\r\n") {
+            Some((i, i + 4))
+        } else if rest.starts_with(b"\n\n") {
+            Some((i, i + 2))
+        } else {
+            None
+        }
+    })
+}
+
+/// Quote the start of `bytes` for an error message, escaping anything
+/// that is not printable ASCII.
+fn preview(bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(PREVIEW_BYTES)];
+    let mut out = format!("\"{}\"", shown.escape_ascii());
+    if bytes.len() > PREVIEW_BYTES {
+        out.push_str(&format!(" (+{} bytes)", bytes.len() - PREVIEW_BYTES));
+    }
+    out
+}
+
+/// Incremental decoder for a server's output stream.
+///
+/// Feed it bytes as they arrive with [`push`](Self::push) and take
+/// complete message bodies out with [`next_frame`](Self::next_frame).
+/// A framing error drops the bad frame and leaves the reader ready for
+/// the next one, so one malformed message does not end the session.
+#[derive(Debug)]
+pub struct FrameReader {
+    buf: Vec<u8>,
+    max_body: usize,
+    /// Body bytes of a rejected message still to be thrown away.
+    discard: usize,
+}
+
+impl Default for FrameReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameReader {
+    /// Create a reader that accepts bodies up to [`MAX_MESSAGE_BYTES`].
+    pub fn new() -> Self {
+        Self::with_max_body(MAX_MESSAGE_BYTES)
+    }
+
+    /// Create a reader that rejects bodies longer than `max_body` bytes.
+    pub fn with_max_body(max_body: usize) -> Self {
+        Self {
+            buf: Vec::new(),
+            max_body,
+            discard: 0,
+        }
+    }
+
+    /// Append bytes read from the server.
+    pub fn push(&mut self, bytes: &[u8]) {
+        let skipped = self.discard.min(bytes.len());
+        self.discard -= skipped;
+        self.buf.extend_from_slice(&bytes[skipped..]);
+    }
+
+    /// Bytes received but not yet returned as a frame.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Take the next complete message body.
+    ///
+    /// Returns `Ok(None)` until a whole frame has arrived. Errors describe
+    /// the problem and quote the offending bytes.
+    pub fn next_frame(&mut self) -> Result<Option<String>, LspError> {
+        let Some((header_len, body_start)) = find_header_end(&self.buf) else {
+            if self.buf.len() > MAX_HEADER_BYTES {
+                let err = format!(
+                    "no end of header within {} bytes: {}",
+                    MAX_HEADER_BYTES,
+                    preview(&self.buf)
+                );
+                self.buf.clear();
+                return Err(LspError::InvalidResponse(err));
+            }
+            return Ok(None);
+        };
+
+        let header = &self.buf[..header_len];
+        let length = match std::str::from_utf8(header)
+            .map_err(|_| "header is not UTF-8".to_string())
+            .and_then(content_length)
+        {
+            Ok(length) => length,
+            Err(reason) => {
+                let err = format!("{} in header {}", reason, preview(header));
+                self.buf.drain(..body_start);
+                return Err(LspError::InvalidResponse(err));
+            }
+        };
+
+        if length > self.max_body {
+            self.buf.drain(..body_start);
+            let skipped = length.min(self.buf.len());
+            self.buf.drain(..skipped);
+            self.discard = length - skipped;
+            return Err(LspError::InvalidResponse(format!(
+                "message of {} bytes exceeds the {} byte limit",
+                length, self.max_body
+            )));
+        }
+
+        let body_end = body_start + length;
+        if self.buf.len() < body_end {
+            return Ok(None);
+        }
+        let body: Vec<u8> = self.buf.drain(..body_end).skip(body_start).collect();
+        String::from_utf8(body).map(Some).map_err(|e| {
+            let at = e.utf8_error().valid_up_to();
+            LspError::InvalidResponse(format!(
+                "body is not UTF-8 at byte {}: {}",
+                at,
+                preview(&e.as_bytes()[at..])
+            ))
+        })
+    }
 }
 
 /// Parse a JSON-RPC message from a JSON string.
 pub fn parse_message(json_str: &str) -> Result<JsonRpcMessage, LspError> {
     let value: serde_json::Value = serde_json::from_str(json_str)
         .map_err(|e| LspError::Serialization(format!("invalid JSON: {}", e)))?;
+    parse_value(&value)
+}
 
+/// Parse a frame body that may hold a single message or a JSON-RPC batch.
+///
+/// Batch entries are returned in the order the server sent them; an entry
+/// that is not a valid message yields an error without affecting the rest.
+pub fn parse_batch(json_str: &str) -> Result<Vec<Result<JsonRpcMessage, LspError>>, LspError> {
+    let value: serde_json::Value = serde_json::from_str(json_str)
+        .map_err(|e| LspError::Serialization(format!("invalid JSON: {}", e)))?;
+    match value {
+        serde_json::Value::Array(entries) if entries.is_empty() => {
+            Err(LspError::InvalidResponse("empty batch".to_string()))
+        }
+        serde_json::Value::Array(entries) => Ok(entries.iter().map(parse_value).collect()),
+        value => Ok(vec![parse_value(&value)]),
+    }
+}
+
+fn parse_value(value: &serde_json::Value) -> Result<JsonRpcMessage, LspError> {
     let has_id = value.get("id").is_some();
     let has_method = value.get("method").is_some();
 
@@ -187,14 +359,12 @@ pub fn read_message_from_bytes(input: &[u8]) -> Result<(JsonRpcMessage, usize),
     let input_str = std::str::from_utf8(input)
         .map_err(|_| LspError::InvalidResponse("invalid UTF-8 in input".into()))?;
 
-    let header_end = input_str
-        .find("\r\n\r\n")
+    let (header_end, body_start) = find_header_end(input)
         .ok_or_else(|| LspError::InvalidResponse("incomplete header".into()))?;
 
     let header = &input_str[..header_end];
     let content_length = parse_content_length(header)?;
 
-    let body_start = header_end + 4; // Skip \r\n\r\n
     let body_end = body_start + content_length;
 
     if input_str.len() < body_end {
@@ -381,6 +551,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_batch_keeps_order_and_isolates_bad_entries() {
+        let json = r#"[
+            {"jsonrpc":"2.0","method":"a"},
+            {"jsonrpc":"2.0"},
+            {"jsonrpc":"2.0","id":4,"result":null}
+        ]"#;
+        let batch = parse_batch(json).unwrap();
+        assert_eq!(batch.len(), 3);
+        assert!(
+            matches!(&batch[0], Ok(JsonRpcMessage::Notification { method, .. }) if method == "a")
+        );
+        assert!(batch[1].is_err());
+        assert!(matches!(
+            batch[2],
+            Ok(JsonRpcMessage::Response { id: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn parse_batch_accepts_single_message_and_rejects_empty() {
+        let batch = parse_batch(r#"{"jsonrpc":"2.0","method":"a"}"#).unwrap();
+        assert_eq!(batch.len(), 1);
+        assert!(parse_batch("[]").is_err());
+    }
+
     #[test]
     fn read_message_from_bytes_valid() {
         let body = r#"{"jsonrpc":"2.0","id":1,"method":"test","params":{}}"#;
@@ -430,6 +626,104 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_content_length_ignores_case() {
+        assert_eq!(parse_content_length("content-length: 7").unwrap(), 7);
+        assert_eq!(parse_content_length("CONTENT-LENGTH:7").unwrap(), 7);
+    }
+
+    #[test]
+    fn read_message_from_bytes_accepts_bare_newlines() {
+        let body = r#"{"jsonrpc":"2.0","method":"x"}"#;
+        let input = format!("content-length: {}\n\n{}", body.len(), body);
+        let (_, consumed) = read_message_from_bytes(input.as_bytes()).unwrap();
+        assert_eq!(consumed, input.len());
+    }
+
+    #[test]
+    fn header_end_ignores_blank_lines_in_the_body() {
+        let input = b"Content-Length: 4\r\n\r\na\n\nb";
+        assert_eq!(find_header_end(input), Some((17, 21)));
+        assert_eq!(
+            find_header_end(b"Content-Length: 4\n\n\r\n\r\n"),
+            Some((17, 19))
+        );
+        let mut far = vec![b'x'; MAX_HEADER_BYTES + 8];
+        far.extend_from_slice(b"\n\n");
+        assert_eq!(find_header_end(&far), None);
+    }
+
+    #[test]
+    fn frame_reader_reassembles_split_frames() {
+        let body1 = r#"{"jsonrpc":"2.0","id":1,"method":"a"}"#;
+        let body2 = r#"{"jsonrpc":"2.0","id":2,"method":"b"}"#;
+        let mut data = frame_message(body1);
+        data.extend_from_slice(&frame_message(body2));
+
+        let mut reader = FrameReader::new();
+        let mut frames = Vec::new();
+        for byte in &data {
+            reader.push(std::slice::from_ref(byte));
+            while let Some(frame) = reader.next_frame().unwrap() {
+                frames.push(frame);
+            }
+        }
+        assert_eq!(frames, vec![body1.to_string(), body2.to_string()]);
+        assert_eq!(reader.buffered(), 0);
+    }
+
+    #[test]
+    fn frame_reader_returns_packed_frames_one_at_a_time() {
+        let mut reader = FrameReader::new();
+        let mut data = frame_message("{}");
+        data.extend_from_slice(b"Content-Length: 2\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n[]");
+        reader.push(&data);
+        assert_eq!(reader.next_frame().unwrap().as_deref(), Some("{}"));
+        assert_eq!(reader.next_frame().unwrap().as_deref(), Some("[]"));
+        assert_eq!(reader.next_frame().unwrap(), None);
+    }
+
+    #[test]
+    fn frame_reader_reports_bad_header_and_recovers() {
+        let mut reader = FrameReader::new();
+        reader.push(b"Content-Length: x1\r\n\r\n");
+        reader.push(&frame_message("{}"));
+        let err = reader.next_frame().unwrap_err().to_string();
+        assert!(err.contains("invalid Content-Length"), "{}", err);
+        assert!(err.contains("Content-Length: x1"), "{}", err);
+        assert_eq!(reader.next_frame().unwrap().as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn frame_reader_skips_oversized_messages() {
+        let mut reader = FrameReader::with_max_body(4);
+        reader.push(b"Content-Length: 10\r\n\r\n01234");
+        let err = reader.next_frame().unwrap_err().to_string();
+        assert!(err.contains("10 bytes exceeds the 4 byte limit"), "{}", err);
+        // The rest of the oversized body arrives in a later read.
+        reader.push(b"56789");
+        reader.push(&frame_message("{}"));
+        assert_eq!(reader.next_frame().unwrap().as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn frame_reader_quotes_unterminated_header() {
+        let mut reader = FrameReader::new();
+        reader.push(&vec![b'\x01'; MAX_HEADER_BYTES + 1]);
+        let err = reader.next_frame().unwrap_err().to_string();
+        assert!(err.contains(r"\x01\x01"), "{}", err);
+        assert!(err.contains("bytes)"), "{}", err);
+        assert_eq!(reader.buffered(), 0);
+    }
+
+    #[test]
+    fn frame_reader_rejects_invalid_utf8_body() {
+        let mut reader = FrameReader::new();
+        reader.push(b"Content-Length: 3\r\n\r\na\xffb");
+        let err = reader.next_frame().unwrap_err().to_string();
+        assert!(err.contains(r#"at byte 1: "\xffb""#), "{}", err);
+    }
+
     #[test]
     fn rpc_error_debug() {
         let err = RpcError {
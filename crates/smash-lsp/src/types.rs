@@ -2,7 +2,14 @@
 //!
 //! These types mirror the Language Server Protocol specification (v3.17+)
 //! and are used for communication between the editor and language servers.
+use std::ops::Range;
+
 use serde::{Deserialize, Serialize};
+use smash_core::buffer::Rope;
+use smash_core::edit::EditEvent;
+use smash_core::position::Position;
+
+use crate::features::LspFeature;
 
 /// Opaque identifier for an LSP client instance.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -31,6 +38,15 @@ pub struct LspServerConfig {
     pub language_id: String,
     /// Root URI of the workspace.
     pub root_uri: Option<String>,
+    /// Features switched off for this server.
+    pub disabled_features: Vec<LspFeature>,
+}
+
+impl LspServerConfig {
+    /// Whether `feature` is left on for this server.
+    pub fn enables(&self, feature: LspFeature) -> bool {
+        !self.disabled_features.contains(&feature)
+    }
 }
 
 /// LSP Position — 0-based line and character.
@@ -49,23 +65,78 @@ impl LspPosition {
     }
 }
 
-/// Convert from smash_core Position to LSP Position.
-impl From<smash_core::position::Position> for LspPosition {
-    fn from(pos: smash_core::position::Position) -> Self {
-        Self {
-            line: pos.line as u32,
-            character: pos.col as u32,
+/// How [`LspPosition::character`] counts: UTF-16 code units unless the
+/// server agreed to UTF-8 bytes during `initialize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    /// Bytes of UTF-8.
+    Utf8,
+    /// UTF-16 code units, the protocol's default.
+    #[default]
+    Utf16,
+}
+
+impl PositionEncoding {
+    /// The encoding's name in the protocol.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PositionEncoding::Utf8 => "utf-8",
+            PositionEncoding::Utf16 => "utf-16",
+        }
+    }
+
+    /// Read the server's `positionEncoding`. Servers that do not give one
+    /// count in UTF-16.
+    pub fn from_server_capabilities(caps: &serde_json::Value) -> Self {
+        match caps.get("positionEncoding").and_then(|v| v.as_str()) {
+            Some("utf-8") => PositionEncoding::Utf8,
+            _ => PositionEncoding::Utf16,
         }
     }
-}
 
-/// Convert from LSP Position to smash_core Position.
-impl From<LspPosition> for smash_core::position::Position {
-    fn from(pos: LspPosition) -> Self {
-        Self::new(pos.line as usize, pos.character as usize)
+    /// Code units `c` takes.
+    fn len(self, c: char) -> usize {
+        match self {
+            PositionEncoding::Utf8 => c.len_utf8(),
+            PositionEncoding::Utf16 => c.len_utf16(),
+        }
     }
 }
 
+/// The LSP position of `pos` in `text`, its char column counted in
+/// `encoding` code units instead.
+pub fn lsp_position_for(text: &Rope, pos: Position, encoding: PositionEncoding) -> LspPosition {
+    let character: usize = text.get_line(pos.line).map_or(0, |line| {
+        line.chars()
+            .take(pos.col)
+            .take_while(|&c| c != '\n' && c != '\r')
+            .map(|c| encoding.len(c))
+            .sum()
+    });
+    LspPosition::new(pos.line as u32, character as u32)
+}
+
+/// The position in `text` of `pos`, whose column counts `encoding` code
+/// units. A column inside a character lands after it, and one past the
+/// end of the line at its end.
+pub fn position_from_lsp(text: &Rope, pos: LspPosition, encoding: PositionEncoding) -> Position {
+    let line = pos.line as usize;
+    let target = pos.character as usize;
+    let Some(slice) = text.get_line(line) else {
+        return Position::new(line, target);
+    };
+    let mut units = 0;
+    let col = slice
+        .chars()
+        .take_while(|&c| {
+            let inside = units < target && c != '\n' && c != '\r';
+            units += encoding.len(c);
+            inside
+        })
+        .count();
+    Position::new(line, col)
+}
+
 /// LSP Range — start and end positions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct LspRange {
@@ -82,8 +153,8 @@ impl LspRange {
     }
 }
 
-/// Diagnostic severity levels.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Diagnostic severity levels. Serialized as the protocol's integer codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DiagnosticSeverity {
     /// Reports an error.
     Error = 1,
@@ -95,6 +166,27 @@ pub enum DiagnosticSeverity {
     Hint = 4,
 }
 
+impl Serialize for DiagnosticSeverity {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for DiagnosticSeverity {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match u8::deserialize(deserializer)? {
+            1 => Ok(Self::Error),
+            2 => Ok(Self::Warning),
+            3 => Ok(Self::Information),
+            4 => Ok(Self::Hint),
+            n => Err(serde::de::Error::custom(format!(
+                "invalid diagnostic severity {}",
+                n
+            ))),
+        }
+    }
+}
+
 /// A diagnostic message from the language server.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Diagnostic {
@@ -107,7 +199,52 @@ pub struct Diagnostic {
     /// The diagnostic's source (e.g. "rustc", "clippy").
     pub source: Option<String>,
     /// The diagnostic's code (string or number).
+    #[serde(default, deserialize_with = "deserialize_diagnostic_code")]
     pub code: Option<String>,
+    /// Link to documentation for the code.
+    #[serde(
+        default,
+        rename = "codeDescription",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub code_description: Option<CodeDescription>,
+    /// Other locations involved, e.g. where a conflicting item is declared.
+    #[serde(
+        default,
+        rename = "relatedInformation",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub related_information: Vec<DiagnosticRelatedInformation>,
+}
+
+/// Documentation link for a diagnostic code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeDescription {
+    /// URI of the documentation page.
+    pub href: String,
+}
+
+/// A location related to a diagnostic, with an explanation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticRelatedInformation {
+    /// Where the related code is.
+    pub location: Location,
+    /// Why it is related.
+    pub message: String,
+}
+
+/// Servers send `code` as either a string or a number; keep it as text.
+fn deserialize_diagnostic_code<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::String(s)) => Some(s),
+            Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        },
+    )
 }
 
 /// Completion item kind.
@@ -173,6 +310,132 @@ pub struct Hover {
     pub range: Option<LspRange>,
 }
 
+/// Documentation sent either as a plain string or as markup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Documentation {
+    Text(String),
+    Markup(MarkupContent),
+}
+
+impl Documentation {
+    /// The documentation text, whatever its format.
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Text(text) => text,
+            Self::Markup(markup) => &markup.value,
+        }
+    }
+}
+
+/// The label of a parameter: its text, or `[start, end)` UTF-16 offsets
+/// into the signature label.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParameterLabel {
+    Text(String),
+    Offsets([u32; 2]),
+}
+
+/// A parameter of a callable signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParameterInformation {
+    /// The parameter's label within the signature label.
+    pub label: ParameterLabel,
+    /// The documentation of this parameter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<Documentation>,
+}
+
+/// One signature of a callable, such as a function overload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureInformation {
+    /// The whole signature, e.g. `fn push(&mut self, value: T)`.
+    pub label: String,
+    /// The documentation of this signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<Documentation>,
+    /// The parameters of this signature.
+    #[serde(default)]
+    pub parameters: Vec<ParameterInformation>,
+    /// Overrides [`SignatureHelp::active_parameter`] for this signature.
+    #[serde(
+        rename = "activeParameter",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_parameter: Option<u32>,
+}
+
+impl SignatureInformation {
+    /// Character range of parameter `index` within [`Self::label`].
+    pub fn parameter_range(&self, index: usize) -> Option<Range<usize>> {
+        match &self.parameters.get(index)?.label {
+            ParameterLabel::Text(text) if text.is_empty() => None,
+            ParameterLabel::Text(text) => {
+                // Look after the opening parenthesis so a parameter named
+                // like the function is not matched in the function name.
+                let from = self.label.find('(').unwrap_or(0);
+                let start = from + self.label[from..].find(text.as_str())?;
+                let chars = self.label[..start].chars().count();
+                Some(chars..chars + text.chars().count())
+            }
+            ParameterLabel::Offsets([start, end]) => {
+                let char_at = |offset: u32| {
+                    let mut units = 0;
+                    self.label
+                        .chars()
+                        .take_while(|c| {
+                            units += c.len_utf16() as u32;
+                            units <= offset
+                        })
+                        .count()
+                };
+                let (start, end) = (char_at(*start), char_at(*end));
+                (start < end).then_some(start..end)
+            }
+        }
+    }
+}
+
+/// The signatures of the call around the cursor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureHelp {
+    /// The candidate signatures.
+    pub signatures: Vec<SignatureInformation>,
+    /// The signature to show; defaults to the first.
+    #[serde(
+        rename = "activeSignature",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_signature: Option<u32>,
+    /// The parameter the cursor is in; defaults to the first.
+    #[serde(
+        rename = "activeParameter",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_parameter: Option<u32>,
+}
+
+impl SignatureHelp {
+    /// The active signature and the index of its active parameter.
+    pub fn active(&self) -> Option<(&SignatureInformation, usize)> {
+        let index = self.active_signature.unwrap_or(0) as usize;
+        // Out-of-range values are allowed by the spec; fall back to the first.
+        let signature = self
+            .signatures
+            .get(index)
+            .or_else(|| self.signatures.first())?;
+        let parameter = signature
+            .active_parameter
+            .or(self.active_parameter)
+            .unwrap_or(0);
+        Some((signature, parameter as usize))
+    }
+}
+
 /// A location in a document.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Location {
@@ -182,8 +445,9 @@ pub struct Location {
     pub range: LspRange,
 }
 
-/// Symbol kinds used in document symbols.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Symbol kinds used in document and workspace symbols. Serialized as
+/// the protocol's integer codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SymbolKind {
     /// A file symbol.
     File = 1,
@@ -191,6 +455,8 @@ pub enum SymbolKind {
     Module = 2,
     /// A namespace symbol.
     Namespace = 3,
+    /// A package symbol.
+    Package = 4,
     /// A class symbol.
     Class = 5,
     /// A method symbol.
@@ -203,14 +469,117 @@ pub enum SymbolKind {
     Constructor = 9,
     /// An enum symbol.
     Enum = 10,
+    /// An interface symbol.
+    Interface = 11,
     /// A function symbol.
     Function = 12,
     /// A variable symbol.
     Variable = 13,
     /// A constant symbol.
     Constant = 14,
+    /// A string symbol.
+    String = 15,
+    /// A number symbol.
+    Number = 16,
+    /// A boolean symbol.
+    Boolean = 17,
+    /// An array symbol.
+    Array = 18,
+    /// An object symbol.
+    Object = 19,
+    /// A key symbol.
+    Key = 20,
+    /// A null symbol.
+    Null = 21,
+    /// An enum member symbol.
+    EnumMember = 22,
     /// A struct symbol.
     Struct = 23,
+    /// An event symbol.
+    Event = 24,
+    /// An operator symbol.
+    Operator = 25,
+    /// A type parameter symbol.
+    TypeParameter = 26,
+}
+
+impl SymbolKind {
+    const ALL: [SymbolKind; 26] = [
+        Self::File,
+        Self::Module,
+        Self::Namespace,
+        Self::Package,
+        Self::Class,
+        Self::Method,
+        Self::Property,
+        Self::Field,
+        Self::Constructor,
+        Self::Enum,
+        Self::Interface,
+        Self::Function,
+        Self::Variable,
+        Self::Constant,
+        Self::String,
+        Self::Number,
+        Self::Boolean,
+        Self::Array,
+        Self::Object,
+        Self::Key,
+        Self::Null,
+        Self::EnumMember,
+        Self::Struct,
+        Self::Event,
+        Self::Operator,
+        Self::TypeParameter,
+    ];
+
+    /// Lower-case name for display, e.g. `enum member`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Module => "module",
+            Self::Namespace => "namespace",
+            Self::Package => "package",
+            Self::Class => "class",
+            Self::Method => "method",
+            Self::Property => "property",
+            Self::Field => "field",
+            Self::Constructor => "constructor",
+            Self::Enum => "enum",
+            Self::Interface => "interface",
+            Self::Function => "function",
+            Self::Variable => "variable",
+            Self::Constant => "constant",
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Array => "array",
+            Self::Object => "object",
+            Self::Key => "key",
+            Self::Null => "null",
+            Self::EnumMember => "enum member",
+            Self::Struct => "struct",
+            Self::Event => "event",
+            Self::Operator => "operator",
+            Self::TypeParameter => "type parameter",
+        }
+    }
+}
+
+impl Serialize for SymbolKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for SymbolKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let n = u8::deserialize(deserializer)?;
+        Self::ALL
+            .into_iter()
+            .find(|kind| *kind as u8 == n)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid symbol kind {}", n)))
+    }
 }
 
 /// Information about a symbol in a document.
@@ -223,18 +592,104 @@ pub struct SymbolInformation {
     /// The location of this symbol.
     pub location: Location,
     /// The name of the containing symbol.
+    #[serde(
+        rename = "containerName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub container_name: Option<String>,
 }
 
+/// A symbol in the hierarchical `textDocument/documentSymbol` response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentSymbol {
+    /// The name of this symbol.
+    pub name: String,
+    /// More detail, such as the signature of a function.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// The kind of this symbol.
+    pub kind: SymbolKind,
+    /// The whole extent of the symbol, including its body.
+    pub range: LspRange,
+    /// The part to reveal when the symbol is picked, e.g. its name.
+    #[serde(rename = "selectionRange")]
+    pub selection_range: LspRange,
+    /// Nested symbols, such as the methods of a class.
+    #[serde(default)]
+    pub children: Vec<DocumentSymbol>,
+}
+
+impl DocumentSymbol {
+    /// Flatten this symbol and its children, depth first, into
+    /// [`SymbolInformation`] for document `uri`. Each child records its
+    /// parent's name as its container.
+    pub fn flatten(self, uri: &str, container: Option<&str>, out: &mut Vec<SymbolInformation>) {
+        out.push(SymbolInformation {
+            name: self.name.clone(),
+            kind: self.kind,
+            location: Location {
+                uri: uri.to_string(),
+                range: self.selection_range,
+            },
+            container_name: container.map(str::to_string),
+        });
+        for child in self.children {
+            child.flatten(uri, Some(&self.name), out);
+        }
+    }
+}
+
 /// A text edit to be applied to a document.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TextEdit {
     /// The range of the text document to be manipulated.
     pub range: LspRange,
     /// The string to be inserted.
+    #[serde(rename = "newText")]
     pub new_text: String,
 }
 
+/// A function or method in a call hierarchy, from
+/// `textDocument/prepareCallHierarchy` or the calls around it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// More detail, such as the signature of a function.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub uri: String,
+    /// The whole extent of the function, including its body.
+    pub range: LspRange,
+    /// The part to reveal when the item is picked, e.g. its name.
+    #[serde(rename = "selectionRange")]
+    pub selection_range: LspRange,
+    /// Kept by the server between requests; sent back unchanged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+/// A caller found by `callHierarchy/incomingCalls`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallHierarchyIncomingCall {
+    /// The calling function.
+    pub from: CallHierarchyItem,
+    /// Where in `from` the calls are.
+    #[serde(rename = "fromRanges")]
+    pub from_ranges: Vec<LspRange>,
+}
+
+/// A callee found by `callHierarchy/outgoingCalls`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallHierarchyOutgoingCall {
+    /// The called function.
+    pub to: CallHierarchyItem,
+    /// Where in the item asked about the calls are.
+    #[serde(rename = "fromRanges")]
+    pub from_ranges: Vec<LspRange>,
+}
+
 /// A code action returned by the server.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CodeAction {
@@ -244,6 +699,47 @@ pub struct CodeAction {
     pub kind: Option<String>,
     /// The workspace edit this code action performs.
     pub edit: Option<WorkspaceEdit>,
+    /// The diagnostics this action resolves.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether the server marks this as the preferred fix.
+    #[serde(default, rename = "isPreferred")]
+    pub is_preferred: bool,
+    /// A command to run after `edit` is applied, or instead of it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<ServerCommand>,
+}
+
+impl CodeAction {
+    /// Whether this is a `quickfix` action (including sub-kinds such as
+    /// `quickfix.import`).
+    pub fn is_quickfix(&self) -> bool {
+        self.kind
+            .as_deref()
+            .is_some_and(|k| k == "quickfix" || k.starts_with("quickfix."))
+    }
+
+    /// Whether this is a `source.organizeImports` action (or a sub-kind).
+    pub fn is_organize_imports(&self) -> bool {
+        self.kind.as_deref().is_some_and(|k| {
+            k == ORGANIZE_IMPORTS || k.starts_with(&format!("{}.", ORGANIZE_IMPORTS))
+        })
+    }
+}
+
+/// Code action kind that sorts and prunes a file's imports.
+pub const ORGANIZE_IMPORTS: &str = "source.organizeImports";
+
+/// A command the server runs on request (`workspace/executeCommand`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerCommand {
+    /// A short, human-readable title.
+    pub title: String,
+    /// The identifier the server registered the command under.
+    pub command: String,
+    /// Arguments to pass back to the server.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<serde_json::Value>,
 }
 
 /// A workspace edit represents changes to many resources.
@@ -251,6 +747,282 @@ pub struct CodeAction {
 pub struct WorkspaceEdit {
     /// Holds changes to existing resources, keyed by URI.
     pub changes: Option<std::collections::HashMap<String, Vec<TextEdit>>>,
+    /// Edits and file operations to apply in order. Servers send these
+    /// instead of `changes` when the client supports them.
+    #[serde(
+        default,
+        rename = "documentChanges",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub document_changes: Option<Vec<DocumentChange>>,
+}
+
+impl WorkspaceEdit {
+    /// Every change in the order it should be applied: `documentChanges`
+    /// if present, otherwise `changes` as one text edit per file, sorted
+    /// by URI.
+    pub fn operations(&self) -> Vec<DocumentChange> {
+        if let Some(changes) = &self.document_changes {
+            return changes.clone();
+        }
+        let mut by_uri: Vec<_> = self.changes.iter().flatten().collect();
+        by_uri.sort_by(|a, b| a.0.cmp(b.0));
+        by_uri
+            .into_iter()
+            .map(|(uri, edits)| {
+                DocumentChange::Edit(TextDocumentEdit {
+                    text_document: VersionedTextDocumentIdentifier {
+                        uri: uri.clone(),
+                        version: None,
+                    },
+                    edits: edits.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// The text edits to `uri`, from its first entry. File operations
+    /// and edits to other documents are left out.
+    pub fn edits_for(&self, uri: &str) -> Vec<TextEdit> {
+        self.operations()
+            .into_iter()
+            .find_map(|op| match op {
+                DocumentChange::Edit(edit) if edit.text_document.uri == uri => Some(edit.edits),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether the edit changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.operations().iter().all(|op| match op {
+            DocumentChange::Edit(edit) => edit.edits.is_empty(),
+            DocumentChange::Resource(_) => false,
+        })
+    }
+}
+
+/// One entry of [`WorkspaceEdit::document_changes`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DocumentChange {
+    /// Text edits to one document.
+    Edit(TextDocumentEdit),
+    /// Creating, renaming or deleting a file.
+    Resource(ResourceOperation),
+}
+
+/// Text edits to a single document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextDocumentEdit {
+    /// The document to change.
+    #[serde(rename = "textDocument")]
+    pub text_document: VersionedTextDocumentIdentifier,
+    /// The edits, all relative to the document before any is applied.
+    pub edits: Vec<TextEdit>,
+}
+
+/// A document URI, with the version the edits were computed against.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionedTextDocumentIdentifier {
+    /// The URI of the document.
+    pub uri: String,
+    /// The version, or `None` for the file as it is on disk.
+    #[serde(default)]
+    pub version: Option<i32>,
+}
+
+/// A file operation in a [`WorkspaceEdit`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum ResourceOperation {
+    /// Create a file.
+    #[serde(rename = "create")]
+    Create {
+        uri: String,
+        #[serde(default)]
+        options: ResourceOptions,
+    },
+    /// Rename (move) a file.
+    #[serde(rename = "rename")]
+    Rename {
+        #[serde(rename = "oldUri")]
+        old_uri: String,
+        #[serde(rename = "newUri")]
+        new_uri: String,
+        #[serde(default)]
+        options: ResourceOptions,
+    },
+    /// Delete a file or folder.
+    #[serde(rename = "delete")]
+    Delete {
+        uri: String,
+        #[serde(default)]
+        options: ResourceOptions,
+    },
+}
+
+/// Options of a [`ResourceOperation`]; each applies to some kinds only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct ResourceOptions {
+    /// Create/rename: replace an existing target.
+    #[serde(default)]
+    pub overwrite: bool,
+    /// Create/rename: leave an existing target alone and skip.
+    #[serde(default, rename = "ignoreIfExists")]
+    pub ignore_if_exists: bool,
+    /// Delete: remove a folder with its contents.
+    #[serde(default)]
+    pub recursive: bool,
+    /// Delete: skip if the file does not exist.
+    #[serde(default, rename = "ignoreIfNotExists")]
+    pub ignore_if_not_exists: bool,
+}
+
+/// How the server wants document changes synchronized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextDocumentSyncKind {
+    /// Documents should not be synced at all.
+    None = 0,
+    /// Documents are synced by always sending the full content.
+    #[default]
+    Full = 1,
+    /// Documents are synced by sending incremental ranged updates.
+    Incremental = 2,
+}
+
+impl TextDocumentSyncKind {
+    /// Read the sync kind from the server's `textDocumentSync`
+    /// capability, which is either a bare number or an options object
+    /// with a `change` field. Falls back to [`TextDocumentSyncKind::Full`]
+    /// when the server does not say.
+    pub fn from_server_capabilities(caps: &serde_json::Value) -> Self {
+        let sync = caps.get("textDocumentSync");
+        let kind = sync
+            .and_then(|v| v.as_u64())
+            .or_else(|| sync.and_then(|v| v.get("change")).and_then(|v| v.as_u64()));
+        match kind {
+            Some(0) => Self::None,
+            Some(2) => Self::Incremental,
+            _ => Self::Full,
+        }
+    }
+}
+
+/// What the server wants to hear when a document is saved, from the
+/// `save` field of its `textDocumentSync` capability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaveNotification {
+    /// Do not send `didSave`.
+    #[default]
+    None,
+    /// Send `didSave` with just the document's URI.
+    Notify,
+    /// Send `didSave` with the saved text as well.
+    IncludeText,
+}
+
+impl SaveNotification {
+    /// Read the server's `textDocumentSync.save`, which is either a bool
+    /// or an options object with `includeText`. A server that gives only
+    /// a bare sync kind predates the field and still gets `didSave`.
+    pub fn from_server_capabilities(caps: &serde_json::Value) -> Self {
+        let Some(sync) = caps.get("textDocumentSync") else {
+            return Self::None;
+        };
+        if sync.is_number() {
+            return Self::Notify;
+        }
+        match sync.get("save") {
+            Some(serde_json::Value::Bool(true)) => Self::Notify,
+            Some(save) if save.is_object() => {
+                if save.get("includeText").and_then(|v| v.as_bool()) == Some(true) {
+                    Self::IncludeText
+                } else {
+                    Self::Notify
+                }
+            }
+            _ => Self::None,
+        }
+    }
+}
+
+/// What a go-to request looks for from the symbol under the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GotoTarget {
+    /// Where the symbol is defined.
+    #[default]
+    Definition,
+    /// Where the symbol's type is defined.
+    TypeDefinition,
+    /// Implementations of a trait, interface or abstract method.
+    Implementation,
+    /// Where the symbol is declared, e.g. a C prototype.
+    Declaration,
+}
+
+impl GotoTarget {
+    /// The request method.
+    pub fn method(self) -> &'static str {
+        match self {
+            GotoTarget::Definition => "textDocument/definition",
+            GotoTarget::TypeDefinition => "textDocument/typeDefinition",
+            GotoTarget::Implementation => "textDocument/implementation",
+            GotoTarget::Declaration => "textDocument/declaration",
+        }
+    }
+
+    /// What is being looked for, for messages, e.g. `type definition`.
+    pub fn label(self) -> &'static str {
+        match self {
+            GotoTarget::Definition => "definition",
+            GotoTarget::TypeDefinition => "type definition",
+            GotoTarget::Implementation => "implementation",
+            GotoTarget::Declaration => "declaration",
+        }
+    }
+}
+
+/// A single change in a `textDocument/didChange` notification.
+///
+/// A change without a range replaces the whole document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextDocumentContentChangeEvent {
+    /// The range of the document that changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<LspRange>,
+    /// The new text for the range, or the whole document.
+    pub text: String,
+}
+
+impl TextDocumentContentChangeEvent {
+    /// Create a change replacing the whole document.
+    pub fn full(text: impl Into<String>) -> Self {
+        Self {
+            range: None,
+            text: text.into(),
+        }
+    }
+
+    /// Create a change replacing `range` with `text`.
+    pub fn ranged(range: LspRange, text: impl Into<String>) -> Self {
+        Self {
+            range: Some(range),
+            text: text.into(),
+        }
+    }
+
+    /// A ranged change for buffer edit `evt`, where `before` is the
+    /// document text the edit was made to.
+    pub fn from_edit(evt: &EditEvent, before: &Rope, encoding: PositionEncoding) -> Self {
+        Self::ranged(
+            LspRange::new(
+                lsp_position_for(before, evt.start_position, encoding),
+                lsp_position_for(before, evt.old_end_position, encoding),
+            ),
+            evt.new_text.clone(),
+        )
+    }
 }
 
 /// Negotiated capabilities after initialization.
@@ -268,14 +1040,35 @@ pub struct LspCapabilities {
     pub code_actions: bool,
     /// Server supports document formatting.
     pub formatting: bool,
+    /// Server can format a range of a document.
+    pub range_formatting: bool,
     /// Server supports rename.
     pub rename: bool,
     /// Server supports document symbols.
     pub document_symbols: bool,
+    /// Server supports workspace symbol search.
+    pub workspace_symbols: bool,
     /// Server supports diagnostics (always assumed if server runs).
     pub diagnostics: bool,
+    /// Characters that should ask for completion when typed.
+    pub completion_trigger_characters: Vec<String>,
     /// Server supports signature help.
     pub signature_help: bool,
+    /// Characters that should ask for signature help when typed.
+    pub signature_trigger_characters: Vec<String>,
+    /// Document synchronization mode requested by the server.
+    pub text_document_sync: TextDocumentSyncKind,
+    /// Whether and how the server wants `didSave`.
+    pub save: SaveNotification,
+    /// Server wants `willSaveWaitUntil` and may edit the document before
+    /// it is written.
+    pub will_save_wait_until: bool,
+    /// Server offers inlay hints.
+    pub inlay_hints: bool,
+    /// Server finds callers and callees of functions.
+    pub call_hierarchy: bool,
+    /// How the server counts columns in positions.
+    pub position_encoding: PositionEncoding,
 }
 
 impl LspCapabilities {
@@ -283,6 +1076,7 @@ impl LspCapabilities {
     pub fn from_server_capabilities(caps: &serde_json::Value) -> Self {
         Self {
             completion: caps.get("completionProvider").is_some(),
+            completion_trigger_characters: trigger_characters(caps, "/completionProvider"),
             hover: caps
                 .get("hoverProvider")
                 .and_then(|v| v.as_bool())
@@ -316,6 +1110,13 @@ impl LspCapabilities {
                 || caps
                     .get("documentFormattingProvider")
                     .is_some_and(|v| v.is_object()),
+            range_formatting: caps
+                .get("documentRangeFormattingProvider")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+                || caps
+                    .get("documentRangeFormattingProvider")
+                    .is_some_and(|v| v.is_object()),
             rename: caps.get("renameProvider").is_some(),
             document_symbols: caps
                 .get("documentSymbolProvider")
@@ -324,15 +1125,61 @@ impl LspCapabilities {
                 || caps
                     .get("documentSymbolProvider")
                     .is_some_and(|v| v.is_object()),
+            workspace_symbols: caps
+                .get("workspaceSymbolProvider")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+                || caps
+                    .get("workspaceSymbolProvider")
+                    .is_some_and(|v| v.is_object()),
             diagnostics: true, // Always assumed
             signature_help: caps.get("signatureHelpProvider").is_some(),
+            signature_trigger_characters: trigger_characters(caps, "/signatureHelpProvider"),
+            text_document_sync: TextDocumentSyncKind::from_server_capabilities(caps),
+            save: SaveNotification::from_server_capabilities(caps),
+            will_save_wait_until: caps
+                .pointer("/textDocumentSync/willSaveWaitUntil")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            inlay_hints: caps
+                .get("inlayHintProvider")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+                || caps.get("inlayHintProvider").is_some_and(|v| v.is_object()),
+            call_hierarchy: caps
+                .get("callHierarchyProvider")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+                || caps
+                    .get("callHierarchyProvider")
+                    .is_some_and(|v| v.is_object()),
+            position_encoding: PositionEncoding::from_server_capabilities(caps),
         }
     }
 }
 
+/// The `triggerCharacters` of the provider at `pointer`, if any.
+fn trigger_characters(caps: &serde_json::Value, pointer: &str) -> Vec<String> {
+    caps.pointer(&format!("{}/triggerCharacters", pointer))
+        .and_then(|v| v.as_array())
+        .map(|chars| {
+            chars
+                .iter()
+                .filter_map(|c| c.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Client capabilities sent during initialization.
 pub fn client_capabilities() -> serde_json::Value {
     serde_json::json!({
+        "general": {
+            "positionEncodings": [
+                PositionEncoding::Utf8.as_str(),
+                PositionEncoding::Utf16.as_str()
+            ]
+        },
         "textDocument": {
             "completion": {
                 "completionItem": {
@@ -347,15 +1194,40 @@ pub fn client_capabilities() -> serde_json::Value {
                 "dynamicRegistration": false,
                 "linkSupport": false
             },
+            "typeDefinition": {
+                "dynamicRegistration": false,
+                "linkSupport": false
+            },
+            "implementation": {
+                "dynamicRegistration": false,
+                "linkSupport": false
+            },
+            "declaration": {
+                "dynamicRegistration": false,
+                "linkSupport": false
+            },
             "references": {
                 "dynamicRegistration": false
             },
             "codeAction": {
-                "dynamicRegistration": false
+                "dynamicRegistration": false,
+                "isPreferredSupport": true,
+                "codeActionLiteralSupport": {
+                    "codeActionKind": {
+                        "valueSet": [
+                            "", "quickfix", "refactor", "refactor.extract",
+                            "refactor.inline", "refactor.rewrite", "source",
+                            "source.organizeImports", "source.fixAll"
+                        ]
+                    }
+                }
             },
             "formatting": {
                 "dynamicRegistration": false
             },
+            "rangeFormatting": {
+                "dynamicRegistration": false
+            },
             "rename": {
                 "dynamicRegistration": false,
                 "prepareSupport": false
@@ -365,20 +1237,44 @@ pub fn client_capabilities() -> serde_json::Value {
             },
             "documentSymbol": {
                 "dynamicRegistration": false,
-                "hierarchicalDocumentSymbolSupport": false
+                "hierarchicalDocumentSymbolSupport": true
             },
             "signatureHelp": {
+                "dynamicRegistration": false,
+                "signatureInformation": {
+                    "documentationFormat": ["plaintext"],
+                    "parameterInformation": { "labelOffsetSupport": true },
+                    "activeParameterSupport": true
+                }
+            },
+            "callHierarchy": {
                 "dynamicRegistration": false
             },
             "synchronization": {
                 "didSave": true,
                 "willSave": false,
+                "willSaveWaitUntil": true,
                 "dynamicRegistration": false
             }
         },
         "workspace": {
+            "applyEdit": true,
+            "symbol": {
+                "dynamicRegistration": false
+            },
+            "executeCommand": {
+                "dynamicRegistration": false
+            },
+            "workspaceEdit": {
+                "documentChanges": true,
+                "resourceOperations": ["create", "rename", "delete"]
+            },
             "workspaceFolders": false,
             "configuration": false
+        },
+        "window": {
+            "workDoneProgress": true,
+            "showMessage": {}
         }
     })
 }
@@ -424,19 +1320,38 @@ mod tests {
     }
 
     #[test]
-    fn lsp_position_from_core() {
-        let core_pos = smash_core::position::Position::new(5, 10);
-        let lsp_pos: LspPosition = core_pos.into();
-        assert_eq!(lsp_pos.line, 5);
-        assert_eq!(lsp_pos.character, 10);
+    fn positions_convert_to_and_from_code_units() {
+        // "é" is 2 UTF-8 bytes and 1 UTF-16 unit, "😀" 4 bytes and 2 units.
+        let text = Rope::from_str("aé😀b\nx\n");
+        let utf16 = PositionEncoding::Utf16;
+        let utf8 = PositionEncoding::Utf8;
+        let pos = Position::new(0, 3);
+        assert_eq!(lsp_position_for(&text, pos, utf16), LspPosition::new(0, 4));
+        assert_eq!(lsp_position_for(&text, pos, utf8), LspPosition::new(0, 7));
+        assert_eq!(position_from_lsp(&text, LspPosition::new(0, 4), utf16), pos);
+        assert_eq!(position_from_lsp(&text, LspPosition::new(0, 7), utf8), pos);
+
+        // Inside the emoji rounds up past it; past the end stops at it.
+        let inside = LspPosition::new(0, 3);
+        assert_eq!(position_from_lsp(&text, inside, utf16), pos);
+        let past = LspPosition::new(1, 40);
+        assert_eq!(position_from_lsp(&text, past, utf16), Position::new(1, 1));
+        let end = Position::new(0, 99);
+        assert_eq!(lsp_position_for(&text, end, utf16), LspPosition::new(0, 5));
     }
 
     #[test]
-    fn core_position_from_lsp() {
-        let lsp_pos = LspPosition::new(3, 7);
-        let core_pos: smash_core::position::Position = lsp_pos.into();
-        assert_eq!(core_pos.line, 3);
-        assert_eq!(core_pos.col, 7);
+    fn position_encoding_is_negotiated() {
+        let caps = client_capabilities();
+        assert_eq!(
+            caps["general"]["positionEncodings"],
+            serde_json::json!(["utf-8", "utf-16"])
+        );
+        let server = serde_json::json!({ "positionEncoding": "utf-8" });
+        let caps = LspCapabilities::from_server_capabilities(&server);
+        assert_eq!(caps.position_encoding, PositionEncoding::Utf8);
+        let caps = LspCapabilities::from_server_capabilities(&serde_json::json!({}));
+        assert_eq!(caps.position_encoding, PositionEncoding::Utf16);
     }
 
     #[test]
@@ -461,6 +1376,17 @@ mod tests {
         assert_eq!(DiagnosticSeverity::Hint as i32, 4);
     }
 
+    #[test]
+    fn diagnostic_severity_uses_protocol_integers() {
+        assert_eq!(
+            serde_json::to_value(DiagnosticSeverity::Warning).unwrap(),
+            serde_json::json!(2)
+        );
+        let sev: DiagnosticSeverity = serde_json::from_value(serde_json::json!(4)).unwrap();
+        assert_eq!(sev, DiagnosticSeverity::Hint);
+        assert!(serde_json::from_value::<DiagnosticSeverity>(serde_json::json!(9)).is_err());
+    }
+
     #[test]
     fn diagnostic_serialize_deserialize() {
         let diag = Diagnostic {
@@ -469,6 +1395,16 @@ mod tests {
             message: "undefined variable".to_string(),
             source: Some("rustc".to_string()),
             code: Some("E0425".to_string()),
+            code_description: Some(CodeDescription {
+                href: "https://doc.rust-lang.org/error_codes/E0425.html".to_string(),
+            }),
+            related_information: vec![DiagnosticRelatedInformation {
+                location: Location {
+                    uri: "file:///src/lib.rs".to_string(),
+                    range: LspRange::default(),
+                },
+                message: "similar name defined here".to_string(),
+            }],
         };
         let json = serde_json::to_string(&diag).unwrap();
         let deser: Diagnostic = serde_json::from_str(&json).unwrap();
@@ -483,6 +1419,8 @@ mod tests {
             message: "something wrong".into(),
             source: None,
             code: None,
+            code_description: None,
+            related_information: Vec::new(),
         };
         let json = serde_json::to_string(&diag).unwrap();
         let deser: Diagnostic = serde_json::from_str(&json).unwrap();
@@ -490,6 +1428,23 @@ mod tests {
         assert_eq!(deser.source, None);
     }
 
+    #[test]
+    fn diagnostic_numeric_code_is_kept_as_text() {
+        let json = r#"{
+            "range": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 1}},
+            "message": "unused",
+            "code": 6133,
+            "codeDescription": {"href": "https://example.com/6133"}
+        }"#;
+        let diag: Diagnostic = serde_json::from_str(json).unwrap();
+        assert_eq!(diag.code.as_deref(), Some("6133"));
+        assert_eq!(
+            diag.code_description.map(|d| d.href).as_deref(),
+            Some("https://example.com/6133")
+        );
+        assert!(diag.related_information.is_empty());
+    }
+
     #[test]
     fn completion_item_serialize_deserialize() {
         let item = CompletionItem {
@@ -564,6 +1519,64 @@ mod tests {
         assert_eq!(deser, sym);
     }
 
+    #[test]
+    fn symbol_kinds_parse_from_protocol_codes() {
+        let kind: SymbolKind = serde_json::from_value(serde_json::json!(22)).unwrap();
+        assert_eq!(kind, SymbolKind::EnumMember);
+        assert_eq!(kind.label(), "enum member");
+        assert_eq!(
+            serde_json::to_value(SymbolKind::TypeParameter).unwrap(),
+            serde_json::json!(26)
+        );
+        assert!(serde_json::from_value::<SymbolKind>(serde_json::json!(27)).is_err());
+    }
+
+    #[test]
+    fn symbol_information_reads_container_name() {
+        let sym: SymbolInformation = serde_json::from_value(serde_json::json!({
+            "name": "run",
+            "kind": 6,
+            "location": {
+                "uri": "file:///src/app.rs",
+                "range": {
+                    "start": { "line": 3, "character": 4 },
+                    "end": { "line": 3, "character": 7 }
+                }
+            },
+            "containerName": "App"
+        }))
+        .unwrap();
+        assert_eq!(sym.container_name.as_deref(), Some("App"));
+        assert_eq!(sym.kind, SymbolKind::Method);
+    }
+
+    #[test]
+    fn document_symbols_flatten_with_containers() {
+        let range = |line| LspRange::new(LspPosition::new(line, 0), LspPosition::new(line, 3));
+        let app = DocumentSymbol {
+            name: "App".to_string(),
+            detail: None,
+            kind: SymbolKind::Struct,
+            range: range(0),
+            selection_range: range(0),
+            children: vec![DocumentSymbol {
+                name: "run".to_string(),
+                detail: Some("fn run(&self)".to_string()),
+                kind: SymbolKind::Method,
+                range: range(2),
+                selection_range: range(2),
+                children: Vec::new(),
+            }],
+        };
+        let mut out = Vec::new();
+        app.flatten("file:///a.rs", None, &mut out);
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].container_name, None);
+        assert_eq!(out[1].name, "run");
+        assert_eq!(out[1].container_name.as_deref(), Some("App"));
+        assert_eq!(out[1].location.range.start.line, 2);
+    }
+
     #[test]
     fn text_edit_roundtrip() {
         let edit = TextEdit {
@@ -581,12 +1594,96 @@ mod tests {
             title: "Remove unused import".to_string(),
             kind: Some("quickfix".to_string()),
             edit: None,
+            diagnostics: Vec::new(),
+            is_preferred: false,
+            command: None,
         };
         let json = serde_json::to_string(&action).unwrap();
         let deser: CodeAction = serde_json::from_str(&json).unwrap();
         assert_eq!(deser, action);
     }
 
+    #[test]
+    fn code_action_from_server_json() {
+        let json = serde_json::json!({
+            "title": "Import `HashMap`",
+            "kind": "quickfix.import",
+            "isPreferred": true,
+            "diagnostics": [{
+                "range": { "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 11 } },
+                "severity": 1,
+                "message": "unresolved"
+            }],
+            "edit": { "changes": { "file:///a.rs": [{
+                "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } },
+                "newText": "use std::collections::HashMap;\n"
+            }] } }
+        });
+        let action: CodeAction = serde_json::from_value(json).unwrap();
+        assert!(action.is_quickfix());
+        assert!(action.is_preferred);
+        assert_eq!(action.diagnostics.len(), 1);
+        let edits = &action.edit.unwrap().changes.unwrap()["file:///a.rs"];
+        assert_eq!(edits[0].new_text, "use std::collections::HashMap;\n");
+
+        let refactor: CodeAction =
+            serde_json::from_value(serde_json::json!({ "title": "Extract", "kind": "refactor" }))
+                .unwrap();
+        assert!(!refactor.is_quickfix());
+        assert!(!refactor.is_organize_imports());
+        let organize: CodeAction = serde_json::from_value(serde_json::json!({
+            "title": "Organize imports",
+            "kind": "source.organizeImports.ruff"
+        }))
+        .unwrap();
+        assert!(organize.is_organize_imports());
+    }
+
+    #[test]
+    fn workspace_edit_document_changes_keep_their_order() {
+        let json = serde_json::json!({
+            "changes": { "file:///ignored.rs": [] },
+            "documentChanges": [
+                { "kind": "create", "uri": "file:///new.rs", "options": { "ignoreIfExists": true } },
+                { "textDocument": { "uri": "file:///new.rs", "version": null }, "edits": [{
+                    "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } },
+                    "newText": "mod a;\n"
+                }] },
+                { "kind": "rename", "oldUri": "file:///a.rs", "newUri": "file:///b.rs" },
+                { "kind": "delete", "uri": "file:///old", "options": { "recursive": true } }
+            ]
+        });
+        let edit: WorkspaceEdit = serde_json::from_value(json).unwrap();
+        let ops = edit.operations();
+        assert_eq!(ops.len(), 4);
+        assert!(matches!(
+            &ops[0],
+            DocumentChange::Resource(ResourceOperation::Create { options, .. })
+                if options.ignore_if_exists
+        ));
+        match &ops[1] {
+            DocumentChange::Edit(e) => {
+                assert_eq!(e.text_document.uri, "file:///new.rs");
+                assert_eq!(e.edits[0].new_text, "mod a;\n");
+            }
+            other => panic!("expected a text edit, got {:?}", other),
+        }
+        assert!(matches!(
+            &ops[2],
+            DocumentChange::Resource(ResourceOperation::Rename { old_uri, new_uri, .. })
+                if old_uri == "file:///a.rs" && new_uri == "file:///b.rs"
+        ));
+        assert!(matches!(
+            &ops[3],
+            DocumentChange::Resource(ResourceOperation::Delete { options, .. })
+                if options.recursive
+        ));
+        assert!(!edit.is_empty());
+        assert!(WorkspaceEdit::default().is_empty());
+        assert_eq!(edit.edits_for("file:///new.rs")[0].new_text, "mod a;\n");
+        assert!(edit.edits_for("file:///b.rs").is_empty());
+    }
+
     #[test]
     fn workspace_edit_default() {
         let edit = WorkspaceEdit::default();
@@ -605,6 +1702,7 @@ mod tests {
         );
         let edit = WorkspaceEdit {
             changes: Some(changes),
+            document_changes: None,
         };
         let json = serde_json::to_string(&edit).unwrap();
         let deser: WorkspaceEdit = serde_json::from_str(&json).unwrap();
@@ -620,9 +1718,12 @@ mod tests {
             "referencesProvider": true,
             "codeActionProvider": true,
             "documentFormattingProvider": true,
+            "documentRangeFormattingProvider": true,
             "renameProvider": { "prepareProvider": true },
             "documentSymbolProvider": true,
-            "signatureHelpProvider": { "triggerCharacters": ["(", ","] }
+            "signatureHelpProvider": { "triggerCharacters": ["(", ","] },
+            "inlayHintProvider": true,
+            "callHierarchyProvider": true
         });
         let lsp_caps = LspCapabilities::from_server_capabilities(&caps);
         assert!(lsp_caps.completion);
@@ -631,10 +1732,15 @@ mod tests {
         assert!(lsp_caps.find_references);
         assert!(lsp_caps.code_actions);
         assert!(lsp_caps.formatting);
+        assert!(lsp_caps.range_formatting);
         assert!(lsp_caps.rename);
         assert!(lsp_caps.document_symbols);
         assert!(lsp_caps.diagnostics);
         assert!(lsp_caps.signature_help);
+        assert!(lsp_caps.inlay_hints);
+        assert!(lsp_caps.call_hierarchy);
+        assert_eq!(lsp_caps.signature_trigger_characters, vec!["(", ","]);
+        assert_eq!(lsp_caps.completion_trigger_characters, vec!["."]);
     }
 
     #[test]
@@ -649,10 +1755,15 @@ mod tests {
         assert!(!lsp_caps.find_references);
         assert!(!lsp_caps.code_actions);
         assert!(!lsp_caps.formatting);
+        assert!(!lsp_caps.range_formatting);
         assert!(!lsp_caps.rename);
         assert!(!lsp_caps.document_symbols);
         assert!(lsp_caps.diagnostics); // Always true
         assert!(!lsp_caps.signature_help);
+        assert!(!lsp_caps.inlay_hints);
+        assert!(!lsp_caps.call_hierarchy);
+        assert!(lsp_caps.signature_trigger_characters.is_empty());
+        assert!(lsp_caps.completion_trigger_characters.is_empty());
     }
 
     #[test]
@@ -672,7 +1783,9 @@ mod tests {
             "referencesProvider": {},
             "codeActionProvider": {},
             "documentFormattingProvider": {},
-            "documentSymbolProvider": {}
+            "documentRangeFormattingProvider": {},
+            "documentSymbolProvider": {},
+            "inlayHintProvider": { "resolveProvider": false }
         });
         let lsp_caps = LspCapabilities::from_server_capabilities(&caps);
         assert!(lsp_caps.hover);
@@ -680,7 +1793,9 @@ mod tests {
         assert!(lsp_caps.find_references);
         assert!(lsp_caps.code_actions);
         assert!(lsp_caps.formatting);
+        assert!(lsp_caps.range_formatting);
         assert!(lsp_caps.document_symbols);
+        assert!(lsp_caps.inlay_hints);
     }
 
     #[test]
@@ -708,6 +1823,9 @@ mod tests {
     fn client_capabilities_has_definition() {
         let caps = client_capabilities();
         assert!(caps["textDocument"]["definition"].is_object());
+        assert!(caps["textDocument"]["typeDefinition"].is_object());
+        assert!(caps["textDocument"]["implementation"].is_object());
+        assert!(caps["textDocument"]["declaration"].is_object());
     }
 
     #[test]
@@ -721,6 +1839,58 @@ mod tests {
         let caps = client_capabilities();
         let sync = &caps["textDocument"]["synchronization"];
         assert_eq!(sync["didSave"], true);
+        assert_eq!(sync["willSaveWaitUntil"], true);
+    }
+
+    #[test]
+    fn client_capabilities_accept_workspace_edits() {
+        let caps = client_capabilities();
+        assert_eq!(caps["workspace"]["applyEdit"], true);
+        assert!(caps["workspace"]["executeCommand"].is_object());
+    }
+
+    #[test]
+    fn signature_help_highlights_the_active_parameter() {
+        let help: SignatureHelp = serde_json::from_value(serde_json::json!({
+            "signatures": [{
+                "label": "fn split(s: &str, sep: char) -> Vec<&str>",
+                "documentation": { "kind": "plaintext", "value": "Split s." },
+                "parameters": [{ "label": "s: &str" }, { "label": [18, 27] }]
+            }],
+            "activeParameter": 1
+        }))
+        .unwrap();
+        let (signature, parameter) = help.active().unwrap();
+        assert_eq!(parameter, 1);
+        assert_eq!(signature.documentation.as_ref().unwrap().text(), "Split s.");
+        // The text label is found after `(`, not in the name `split`.
+        assert_eq!(signature.parameter_range(0), Some(9..16));
+        assert_eq!(signature.parameter_range(1), Some(18..27));
+        assert_eq!(signature.parameter_range(2), None);
+
+        // UTF-16 offsets are converted to characters.
+        let wide = SignatureInformation {
+            label: "f(é: u8, 𝔁: u8)".to_string(),
+            documentation: Some(Documentation::Text("doc".to_string())),
+            parameters: vec![ParameterInformation {
+                label: ParameterLabel::Offsets([9, 15]),
+                documentation: None,
+            }],
+            active_parameter: None,
+        };
+        assert_eq!(wide.parameter_range(0), Some(9..14));
+    }
+
+    #[test]
+    fn signature_help_falls_back_to_the_first_signature() {
+        let help: SignatureHelp = serde_json::from_value(serde_json::json!({
+            "signatures": [{ "label": "f()" }],
+            "activeSignature": 4
+        }))
+        .unwrap();
+        let (signature, parameter) = help.active().unwrap();
+        assert_eq!(signature.label, "f()");
+        assert_eq!(parameter, 0);
     }
 
     #[test]
@@ -730,6 +1900,7 @@ mod tests {
             args: vec!["--stdio".to_string()],
             language_id: "rust".to_string(),
             root_uri: Some("file:///project".to_string()),
+            disabled_features: Vec::new(),
         };
         assert_eq!(config.command, "rust-analyzer");
         assert_eq!(config.args.len(), 1);
@@ -744,6 +1915,7 @@ mod tests {
             args: vec![],
             language_id: "python".to_string(),
             root_uri: None,
+            disabled_features: Vec::new(),
         };
         let cloned = config.clone();
         assert_eq!(cloned, config);
@@ -767,4 +1939,122 @@ mod tests {
         let deser: MarkupContent = serde_json::from_str(&json).unwrap();
         assert_eq!(deser, mc);
     }
+
+    #[test]
+    fn sync_kind_from_number_and_options() {
+        let num = serde_json::json!({ "textDocumentSync": 2 });
+        assert_eq!(
+            TextDocumentSyncKind::from_server_capabilities(&num),
+            TextDocumentSyncKind::Incremental
+        );
+        let opts = serde_json::json!({ "textDocumentSync": { "openClose": true, "change": 0 } });
+        assert_eq!(
+            TextDocumentSyncKind::from_server_capabilities(&opts),
+            TextDocumentSyncKind::None
+        );
+        let missing = serde_json::json!({});
+        assert_eq!(
+            TextDocumentSyncKind::from_server_capabilities(&missing),
+            TextDocumentSyncKind::Full
+        );
+    }
+
+    #[test]
+    fn capabilities_record_sync_kind() {
+        let caps = serde_json::json!({ "textDocumentSync": { "change": 2 } });
+        let lsp_caps = LspCapabilities::from_server_capabilities(&caps);
+        assert_eq!(
+            lsp_caps.text_document_sync,
+            TextDocumentSyncKind::Incremental
+        );
+    }
+
+    #[test]
+    fn save_notification_from_bool_options_and_bare_kind() {
+        let cases = [
+            (serde_json::json!({}), SaveNotification::None),
+            (
+                serde_json::json!({ "textDocumentSync": 1 }),
+                SaveNotification::Notify,
+            ),
+            (
+                serde_json::json!({ "textDocumentSync": { "change": 2 } }),
+                SaveNotification::None,
+            ),
+            (
+                serde_json::json!({ "textDocumentSync": { "save": false } }),
+                SaveNotification::None,
+            ),
+            (
+                serde_json::json!({ "textDocumentSync": { "save": true } }),
+                SaveNotification::Notify,
+            ),
+            (
+                serde_json::json!({ "textDocumentSync": { "save": {} } }),
+                SaveNotification::Notify,
+            ),
+            (
+                serde_json::json!({ "textDocumentSync": { "save": { "includeText": true } } }),
+                SaveNotification::IncludeText,
+            ),
+        ];
+        for (caps, expected) in cases {
+            assert_eq!(
+                SaveNotification::from_server_capabilities(&caps),
+                expected,
+                "{}",
+                caps
+            );
+        }
+    }
+
+    #[test]
+    fn capabilities_record_save_options() {
+        let caps = serde_json::json!({
+            "textDocumentSync": {
+                "change": 1,
+                "save": { "includeText": true },
+                "willSaveWaitUntil": true
+            }
+        });
+        let lsp_caps = LspCapabilities::from_server_capabilities(&caps);
+        assert_eq!(lsp_caps.save, SaveNotification::IncludeText);
+        assert!(lsp_caps.will_save_wait_until);
+
+        let plain = LspCapabilities::from_server_capabilities(&serde_json::json!({}));
+        assert_eq!(plain.save, SaveNotification::None);
+        assert!(!plain.will_save_wait_until);
+    }
+
+    #[test]
+    fn full_content_change_omits_range() {
+        let change = TextDocumentContentChangeEvent::full("hello");
+        let json = serde_json::to_value(&change).unwrap();
+        assert_eq!(json, serde_json::json!({ "text": "hello" }));
+    }
+
+    #[test]
+    fn content_change_from_edit_event() {
+        // "😀b" in "a😀bc" replaced with "x".
+        let evt = EditEvent {
+            start_byte: 1,
+            old_end_byte: 6,
+            new_end_byte: 2,
+            start_position: Position::new(0, 1),
+            old_end_position: Position::new(0, 3),
+            new_end_position: Position::new(0, 2),
+            new_text: "x".to_string(),
+        };
+        let before = Rope::from_str("a😀bc");
+        let change =
+            TextDocumentContentChangeEvent::from_edit(&evt, &before, PositionEncoding::Utf16);
+        assert_eq!(
+            change.range,
+            Some(LspRange::new(
+                LspPosition::new(0, 1),
+                LspPosition::new(0, 4)
+            ))
+        );
+        assert_eq!(change.text, "x");
+    }
 }
@@ -0,0 +1,245 @@
+//! Server feedback sent through the `window` part of the protocol:
+//! work-done progress (`$/progress`) and messages (`window/showMessage`,
+//! `window/logMessage`).
+use std::fmt;
+
+/// One long-running operation a server reports, such as indexing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// What the operation is, e.g. "Indexing".
+    pub title: String,
+    /// The current step, e.g. a crate or file name.
+    pub message: Option<String>,
+    /// Percent done, when the server knows.
+    pub percentage: Option<u32>,
+}
+
+impl fmt::Display for Progress {
+    /// `Indexing 42% serde`, leaving out the parts the server did not send.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.title)?;
+        if let Some(percentage) = self.percentage {
+            write!(f, " {}%", percentage)?;
+        }
+        if let Some(message) = self.message.as_deref().filter(|m| !m.is_empty()) {
+            write!(f, " {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Operations in progress on one server, by progress token.
+pub struct ProgressTracker {
+    /// Active operations, oldest first.
+    tasks: Vec<(String, Progress)>,
+    /// Callback invoked with the newest operation whenever one changes.
+    #[allow(clippy::type_complexity)]
+    on_update: Option<Box<dyn Fn(Option<&Progress>) + Send + Sync>>,
+}
+
+impl Default for ProgressTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ProgressTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressTracker")
+            .field("tasks", &self.tasks)
+            .field("has_on_update", &self.on_update.is_some())
+            .finish()
+    }
+}
+
+impl ProgressTracker {
+    /// Create a tracker with nothing in progress.
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            on_update: None,
+        }
+    }
+
+    /// Set a callback that fires whenever an operation begins, reports or
+    /// ends, with the newest operation still running.
+    pub fn set_on_update<F>(&mut self, callback: F)
+    where
+        F: Fn(Option<&Progress>) + Send + Sync + 'static,
+    {
+        self.on_update = Some(Box::new(callback));
+    }
+
+    /// Apply the params of a `$/progress` notification. Reports and ends
+    /// for tokens that never began are ignored, as are non-work-done
+    /// progress values. Returns whether anything changed.
+    pub fn apply(&mut self, params: &serde_json::Value) -> bool {
+        let token = match &params["token"] {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Number(n) => n.to_string(),
+            _ => return false,
+        };
+        let value = &params["value"];
+        let text = |key: &str| value[key].as_str().map(str::to_string);
+        let percentage = value["percentage"].as_u64().map(|p| p.min(100) as u32);
+        let index = self.tasks.iter().position(|(t, _)| *t == token);
+        match (value["kind"].as_str(), index) {
+            (Some("begin"), _) => {
+                if let Some(i) = index {
+                    self.tasks.remove(i);
+                }
+                let progress = Progress {
+                    title: text("title").unwrap_or_default(),
+                    message: text("message"),
+                    percentage,
+                };
+                self.tasks.push((token, progress));
+            }
+            (Some("report"), Some(i)) => {
+                let progress = &mut self.tasks[i].1;
+                if let Some(message) = text("message") {
+                    progress.message = Some(message);
+                }
+                if percentage.is_some() {
+                    progress.percentage = percentage;
+                }
+            }
+            (Some("end"), Some(i)) => {
+                self.tasks.remove(i);
+            }
+            _ => return false,
+        }
+        if let Some(callback) = &self.on_update {
+            callback(self.current());
+        }
+        true
+    }
+
+    /// The most recently begun operation still running.
+    pub fn current(&self) -> Option<&Progress> {
+        self.tasks.last().map(|(_, progress)| progress)
+    }
+
+    /// How many operations are running.
+    pub fn active_count(&self) -> usize {
+        self.tasks.len()
+    }
+}
+
+/// Importance of a server message (`MessageType`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    Error,
+    Warning,
+    Info,
+    Log,
+}
+
+/// A `window/showMessage` or `window/logMessage` from a server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerMessage {
+    pub kind: MessageType,
+    pub text: String,
+}
+
+impl ServerMessage {
+    /// Parse the params of a show or log message notification.
+    pub fn from_params(params: &serde_json::Value) -> Option<Self> {
+        let kind = match params["type"].as_u64()? {
+            1 => MessageType::Error,
+            2 => MessageType::Warning,
+            3 => MessageType::Info,
+            _ => MessageType::Log,
+        };
+        let text = params["message"].as_str()?.to_string();
+        Some(Self { kind, text })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn progress(token: serde_json::Value, value: serde_json::Value) -> serde_json::Value {
+        json!({ "token": token, "value": value })
+    }
+
+    #[test]
+    fn progress_begins_reports_and_ends() {
+        let mut tracker = ProgressTracker::new();
+        assert!(tracker.apply(&progress(
+            json!("rustAnalyzer/Indexing"),
+            json!({ "kind": "begin", "title": "Indexing", "percentage": 0 }),
+        )));
+        assert_eq!(tracker.current().unwrap().to_string(), "Indexing 0%");
+
+        tracker.apply(&progress(
+            json!("rustAnalyzer/Indexing"),
+            json!({ "kind": "report", "message": "4/10 (serde)", "percentage": 40 }),
+        ));
+        assert_eq!(
+            tracker.current().unwrap().to_string(),
+            "Indexing 40% 4/10 (serde)"
+        );
+
+        // A second operation shows over the first until it ends.
+        tracker.apply(&progress(
+            json!(7),
+            json!({ "kind": "begin", "title": "Loading" }),
+        ));
+        assert_eq!(tracker.current().unwrap().to_string(), "Loading");
+        assert_eq!(tracker.active_count(), 2);
+        tracker.apply(&progress(json!(7), json!({ "kind": "end" })));
+        assert_eq!(tracker.current().unwrap().title, "Indexing");
+
+        tracker.apply(&progress(
+            json!("rustAnalyzer/Indexing"),
+            json!({ "kind": "end", "message": "done" }),
+        ));
+        assert!(tracker.current().is_none());
+    }
+
+    #[test]
+    fn unknown_progress_is_ignored() {
+        let mut tracker = ProgressTracker::new();
+        assert!(!tracker.apply(&progress(json!("t"), json!({ "kind": "report" }))));
+        assert!(!tracker.apply(&progress(json!("t"), json!({ "kind": "end" }))));
+        assert!(!tracker.apply(&progress(json!("t"), json!({ "partial": [] }))));
+        assert!(!tracker.apply(&json!({ "value": { "kind": "begin" } })));
+        assert_eq!(tracker.active_count(), 0);
+    }
+
+    #[test]
+    fn progress_updates_reach_the_callback() {
+        use std::sync::{Arc, Mutex};
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = seen.clone();
+        let mut tracker = ProgressTracker::new();
+        tracker.set_on_update(move |p| {
+            seen_clone.lock().unwrap().push(p.map(|p| p.to_string()));
+        });
+        tracker.apply(&progress(
+            json!(1),
+            json!({ "kind": "begin", "title": "Build" }),
+        ));
+        tracker.apply(&progress(json!(1), json!({ "kind": "end" })));
+        assert_eq!(*seen.lock().unwrap(), vec![Some("Build".to_string()), None]);
+    }
+
+    #[test]
+    fn server_messages_parse_their_type() {
+        let msg = ServerMessage::from_params(&json!({ "type": 2, "message": "no cargo" }));
+        assert_eq!(
+            msg,
+            Some(ServerMessage {
+                kind: MessageType::Warning,
+                text: "no cargo".to_string()
+            })
+        );
+        let log = ServerMessage::from_params(&json!({ "type": 4, "message": "x" })).unwrap();
+        assert_eq!(log.kind, MessageType::Log);
+        assert!(ServerMessage::from_params(&json!({ "message": "x" })).is_none());
+    }
+}
@@ -8,85 +8,321 @@ pub trait Clipboard: Send + Sync {
     fn get(&self) -> Result<String, PlatformError>;
     /// Sets the clipboard content.
     fn set(&self, content: &str) -> Result<(), PlatformError>;
+    /// Short description of where the content goes, for diagnostics.
+    fn describe(&self) -> String;
 }
 
-/// System clipboard that shells out to OS-specific commands.
-///
-/// On macOS, uses `pbcopy` / `pbpaste`.
-/// On Linux, uses `xclip` (with `-selection clipboard`).
-pub struct SystemClipboard;
+/// A way of reaching the system clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardProvider {
+    /// `pbcopy` / `pbpaste` (macOS).
+    Pbcopy,
+    /// `wl-copy` / `wl-paste` (Wayland).
+    WlClipboard,
+    /// `xclip` (X11).
+    Xclip,
+    /// `xsel` (X11).
+    Xsel,
+    /// The tmux paste buffer, when running inside tmux.
+    Tmux,
+    /// The OSC 52 escape sequence, which asks the terminal itself to set
+    /// its clipboard. Works over SSH, but cannot read the clipboard back.
+    Osc52,
+}
 
-impl Clipboard for SystemClipboard {
-    fn get(&self) -> Result<String, PlatformError> {
-        let cmd = if cfg!(target_os = "macos") {
-            ("pbpaste", Vec::new())
-        } else if cfg!(target_os = "linux") {
-            ("xclip", vec!["-selection", "clipboard", "-o"])
-        } else {
-            return Err(PlatformError::Unsupported {
-                os: std::env::consts::OS.into(),
-                detail: "clipboard get not supported".into(),
-            });
-        };
+impl ClipboardProvider {
+    /// Every provider, in the order auto-detection tries them.
+    pub const ALL: [ClipboardProvider; 6] = [
+        Self::Pbcopy,
+        Self::WlClipboard,
+        Self::Xclip,
+        Self::Xsel,
+        Self::Tmux,
+        Self::Osc52,
+    ];
 
-        let output = std::process::Command::new(cmd.0)
-            .args(&cmd.1)
-            .output()
-            .map_err(|e| PlatformError::Clipboard(format!("failed to run {}: {}", cmd.0, e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(PlatformError::Clipboard(format!(
-                "{} failed: {}",
-                cmd.0, stderr
-            )));
+    /// Name used in the config (`editor.clipboard`) and the health view.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pbcopy => "pbcopy",
+            Self::WlClipboard => "wl-clipboard",
+            Self::Xclip => "xclip",
+            Self::Xsel => "xsel",
+            Self::Tmux => "tmux",
+            Self::Osc52 => "osc52",
         }
+    }
 
-        String::from_utf8(output.stdout).map_err(|e| {
-            PlatformError::Clipboard(format!("clipboard content is not valid UTF-8: {}", e))
-        })
+    /// The provider called `name`, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
     }
 
-    fn set(&self, content: &str) -> Result<(), PlatformError> {
-        let cmd = if cfg!(target_os = "macos") {
-            ("pbcopy", Vec::new())
-        } else if cfg!(target_os = "linux") {
-            ("xclip", vec!["-selection", "clipboard"])
-        } else {
-            return Err(PlatformError::Unsupported {
-                os: std::env::consts::OS.into(),
-                detail: "clipboard set not supported".into(),
-            });
+    /// Program and arguments that copy stdin to the clipboard.
+    fn copy_command(self) -> Option<(&'static str, &'static [&'static str])> {
+        match self {
+            Self::Pbcopy => Some(("pbcopy", &[])),
+            Self::WlClipboard => Some(("wl-copy", &[])),
+            Self::Xclip => Some(("xclip", &["-selection", "clipboard"])),
+            Self::Xsel => Some(("xsel", &["--clipboard", "--input"])),
+            // `-w` also forwards the buffer to the outer terminal's
+            // clipboard where tmux is set up for it.
+            Self::Tmux => Some(("tmux", &["load-buffer", "-w", "-"])),
+            Self::Osc52 => None,
+        }
+    }
+
+    /// Program and arguments that print the clipboard.
+    fn paste_command(self) -> Option<(&'static str, &'static [&'static str])> {
+        match self {
+            Self::Pbcopy => Some(("pbpaste", &[])),
+            Self::WlClipboard => Some(("wl-paste", &["--no-newline"])),
+            Self::Xclip => Some(("xclip", &["-selection", "clipboard", "-o"])),
+            Self::Xsel => Some(("xsel", &["--clipboard", "--output"])),
+            Self::Tmux => Some(("tmux", &["save-buffer", "-"])),
+            Self::Osc52 => None,
+        }
+    }
+
+    /// Whether this provider can work in the environment described by
+    /// `var` (environment lookup) and `has_program` (`PATH` lookup).
+    fn available(self, var: &dyn Fn(&str) -> bool, has_program: &dyn Fn(&str) -> bool) -> bool {
+        let programs = |p: Self| {
+            [p.copy_command(), p.paste_command()]
+                .into_iter()
+                .flatten()
+                .all(|(program, _)| has_program(program))
         };
+        match self {
+            Self::Pbcopy => cfg!(target_os = "macos") && programs(self),
+            Self::WlClipboard => var("WAYLAND_DISPLAY") && programs(self),
+            Self::Xclip | Self::Xsel => var("DISPLAY") && programs(self),
+            Self::Tmux => var("TMUX") && programs(self),
+            Self::Osc52 => true,
+        }
+    }
 
-        use std::io::Write;
-        let mut child = std::process::Command::new(cmd.0)
-            .args(&cmd.1)
-            .stdin(std::process::Stdio::piped())
-            .spawn()
-            .map_err(|e| PlatformError::Clipboard(format!("failed to spawn {}: {}", cmd.0, e)))?;
-
-        if let Some(ref mut stdin) = child.stdin {
-            stdin.write_all(content.as_bytes()).map_err(|e| {
-                PlatformError::Clipboard(format!("failed to write to {}: {}", cmd.0, e))
-            })?;
+    /// The providers usable here, best first. OSC 52 always comes last.
+    pub fn detect() -> Vec<Self> {
+        Self::detect_with(
+            &|name| std::env::var_os(name).is_some_and(|v| !v.is_empty()),
+            &in_path,
+        )
+    }
+
+    fn detect_with(var: &dyn Fn(&str) -> bool, has_program: &dyn Fn(&str) -> bool) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|p| p.available(var, has_program))
+            .collect()
+    }
+}
+
+/// Whether `program` is an executable file in a `PATH` directory.
+fn in_path(program: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        let candidate = dir.join(program);
+        candidate.is_file() || (cfg!(windows) && candidate.with_extension("exe").is_file())
+    })
+}
+
+/// System clipboard that shells out to OS-specific commands.
+///
+/// Holds a chain of [`ClipboardProvider`]s, tried in order: when one
+/// fails (say `xclip` after the X server went away), the next takes
+/// over and stays in use.
+pub struct SystemClipboard {
+    chain: Vec<ClipboardProvider>,
+    /// Index into `chain` of the provider that last worked.
+    active: Mutex<usize>,
+    /// Text last copied through OSC 52, which cannot be read back.
+    osc52_text: Mutex<String>,
+}
+
+impl SystemClipboard {
+    /// Chain every provider detected on this system.
+    pub fn detect() -> Self {
+        Self::with_chain(ClipboardProvider::detect())
+    }
+
+    /// Use the providers given, in order.
+    pub fn with_chain(chain: Vec<ClipboardProvider>) -> Self {
+        Self {
+            chain,
+            active: Mutex::new(0),
+            osc52_text: Mutex::new(String::new()),
+        }
+    }
+
+    /// Build from the `editor.clipboard` setting: `"auto"` detects, a
+    /// provider name uses only that provider.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PlatformError::Clipboard` for an unknown name.
+    pub fn from_setting(setting: &str) -> Result<Self, PlatformError> {
+        if setting == "auto" {
+            return Ok(Self::detect());
         }
+        ClipboardProvider::from_name(setting)
+            .map(|p| Self::with_chain(vec![p]))
+            .ok_or_else(|| PlatformError::Clipboard(format!("unknown provider '{}'", setting)))
+    }
 
-        let status = child
-            .wait()
-            .map_err(|e| PlatformError::Clipboard(format!("failed to wait on {}: {}", cmd.0, e)))?;
+    /// The provider currently in use.
+    pub fn provider(&self) -> Option<ClipboardProvider> {
+        let active = *self.active.lock().unwrap_or_else(|e| e.into_inner());
+        self.chain.get(active).copied()
+    }
 
-        if !status.success() {
-            return Err(PlatformError::Clipboard(format!(
-                "{} exited with status: {}",
-                cmd.0, status
-            )));
+    /// Run `op` on each provider from the active one on, keeping the
+    /// first that succeeds active.
+    fn with_fallback<T>(
+        &self,
+        op: impl Fn(ClipboardProvider) -> Result<T, PlatformError>,
+    ) -> Result<T, PlatformError> {
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let mut last_err = PlatformError::Clipboard("no clipboard provider available".into());
+        for (i, &provider) in self.chain.iter().enumerate().skip(*active) {
+            match op(provider) {
+                Ok(value) => {
+                    if i != *active {
+                        tracing::info!("clipboard: falling back to {}", provider.name());
+                        *active = i;
+                    }
+                    return Ok(value);
+                }
+                Err(e) => {
+                    tracing::debug!("clipboard: {} failed: {}", provider.name(), e);
+                    last_err = e;
+                }
+            }
         }
+        Err(last_err)
+    }
 
+    fn set_osc52(&self, content: &str) -> Result<(), PlatformError> {
+        use std::io::Write;
+        let sequence = osc52_sequence(content, std::env::var_os("TMUX").is_some());
+        let mut out = std::io::stdout();
+        out.write_all(sequence.as_bytes())
+            .and_then(|()| out.flush())
+            .map_err(|e| PlatformError::Clipboard(format!("failed to write OSC 52: {}", e)))?;
+        *self.osc52_text.lock().unwrap_or_else(|e| e.into_inner()) = content.to_string();
         Ok(())
     }
 }
 
+/// The OSC 52 sequence setting the clipboard to `content`, wrapped in a
+/// DCS passthrough when running inside tmux.
+fn osc52_sequence(content: &str, tmux: bool) -> String {
+    let osc = format!("\x1b]52;c;{}\x07", base64(content.as_bytes()));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", osc.replace('\x1b', "\x1b\x1b"))
+    } else {
+        osc
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn run_paste(program: &str, args: &[&str]) -> Result<String, PlatformError> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|e| PlatformError::Clipboard(format!("failed to run {}: {}", program, e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(PlatformError::Clipboard(format!(
+            "{} failed: {}",
+            program, stderr
+        )));
+    }
+
+    String::from_utf8(output.stdout).map_err(|e| {
+        PlatformError::Clipboard(format!("clipboard content is not valid UTF-8: {}", e))
+    })
+}
+
+fn run_copy(program: &str, args: &[&str], content: &str) -> Result<(), PlatformError> {
+    use std::io::Write;
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| PlatformError::Clipboard(format!("failed to spawn {}: {}", program, e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content.as_bytes()).map_err(|e| {
+            PlatformError::Clipboard(format!("failed to write to {}: {}", program, e))
+        })?;
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| PlatformError::Clipboard(format!("failed to wait on {}: {}", program, e)))?;
+
+    if !status.success() {
+        return Err(PlatformError::Clipboard(format!(
+            "{} exited with status: {}",
+            program, status
+        )));
+    }
+
+    Ok(())
+}
+
+impl Clipboard for SystemClipboard {
+    fn get(&self) -> Result<String, PlatformError> {
+        self.with_fallback(|provider| match provider.paste_command() {
+            Some((program, args)) => run_paste(program, args),
+            None => Ok(self
+                .osc52_text
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()),
+        })
+    }
+
+    fn set(&self, content: &str) -> Result<(), PlatformError> {
+        self.with_fallback(|provider| match provider.copy_command() {
+            Some((program, args)) => run_copy(program, args, content),
+            None => self.set_osc52(content),
+        })
+    }
+
+    fn describe(&self) -> String {
+        match self.provider() {
+            Some(provider) => provider.name().to_string(),
+            None => "none".to_string(),
+        }
+    }
+}
+
 /// In-memory clipboard for testing purposes.
 pub struct InMemoryClipboard {
     content: Mutex<String>,
@@ -124,6 +360,10 @@ impl Clipboard for InMemoryClipboard {
         *guard = content.to_string();
         Ok(())
     }
+
+    fn describe(&self) -> String {
+        "in-memory".to_string()
+    }
 }
 
 #[cfg(test)]
@@ -189,8 +429,13 @@ mod tests {
 
     #[test]
     fn system_clipboard_round_trip() {
-        // This test only runs on macOS/Linux where pbcopy/xclip exists
-        let cb = SystemClipboard;
+        // This test only runs where a clipboard tool works
+        let cb = SystemClipboard::with_chain(
+            ClipboardProvider::detect()
+                .into_iter()
+                .filter(|p| *p != ClipboardProvider::Osc52)
+                .collect(),
+        );
         let unique = format!("smash_test_{}", std::process::id());
         if cb.set(&unique).is_ok() {
             let got = cb.get().expect("get should succeed");
@@ -198,4 +443,74 @@ mod tests {
         }
         // If set fails (no clipboard tool), test is skipped
     }
+
+    #[test]
+    fn detection_follows_the_environment() {
+        let detect = |vars: &[&str], programs: &[&str]| {
+            ClipboardProvider::detect_with(&|v| vars.contains(&v), &|p| programs.contains(&p))
+        };
+        use ClipboardProvider::*;
+        assert_eq!(
+            detect(
+                &["WAYLAND_DISPLAY", "DISPLAY"],
+                &["wl-copy", "wl-paste", "xsel"]
+            ),
+            vec![WlClipboard, Xsel, Osc52]
+        );
+        // wl-copy alone is not enough; both directions are needed.
+        assert_eq!(detect(&["WAYLAND_DISPLAY"], &["wl-copy"]), vec![Osc52]);
+        assert_eq!(detect(&["TMUX"], &["tmux", "xclip"]), vec![Tmux, Osc52]);
+        assert_eq!(detect(&[], &[]), vec![Osc52]);
+    }
+
+    #[test]
+    fn setting_names_a_single_provider() {
+        for provider in ClipboardProvider::ALL {
+            assert_eq!(
+                ClipboardProvider::from_name(provider.name()),
+                Some(provider)
+            );
+        }
+        let cb = SystemClipboard::from_setting("xsel").expect("known");
+        assert_eq!(cb.provider(), Some(ClipboardProvider::Xsel));
+        assert_eq!(cb.describe(), "xsel");
+        assert!(SystemClipboard::from_setting("clippy").is_err());
+    }
+
+    #[test]
+    fn failing_provider_falls_back_down_the_chain() {
+        let cb =
+            SystemClipboard::with_chain(vec![ClipboardProvider::Xsel, ClipboardProvider::Osc52]);
+        let calls = Mutex::new(Vec::new());
+        let result = cb.with_fallback(|p| {
+            calls.lock().unwrap().push(p);
+            match p {
+                ClipboardProvider::Osc52 => Ok(()),
+                _ => Err(PlatformError::Clipboard("no display".into())),
+            }
+        });
+        assert!(result.is_ok());
+        assert_eq!(cb.provider(), Some(ClipboardProvider::Osc52));
+        // The broken provider is not retried.
+        cb.with_fallback(|p| {
+            calls.lock().unwrap().push(p);
+            Ok(())
+        })
+        .unwrap();
+        use ClipboardProvider::*;
+        assert_eq!(*calls.lock().unwrap(), vec![Xsel, Osc52, Osc52]);
+    }
+
+    #[test]
+    fn osc52_encodes_base64_and_wraps_for_tmux() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(
+            osc52_sequence("hi", true),
+            "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\"
+        );
+    }
 }
@@ -9,12 +9,18 @@ pub enum PlatformError {
     #[error("path error: {0}")]
     Path(String),
 
+    #[error("environment variable {0} is not set")]
+    UndefinedVariable(String),
+
     #[error("process spawn failed: {0}")]
     ProcessSpawn(#[from] std::io::Error),
 
     #[error("signal handler error: {0}")]
     Signal(String),
 
+    #[error("timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
     #[error("unsupported operation on {os}: {detail}")]
     Unsupported { os: String, detail: String },
 }
@@ -55,6 +61,12 @@ mod tests {
         assert!(err.to_string().contains("cmd not found"));
     }
 
+    #[test]
+    fn timeout_error_display_contains_duration() {
+        let err = PlatformError::Timeout(std::time::Duration::from_secs(30));
+        assert_eq!(err.to_string(), "timed out after 30s");
+    }
+
     #[test]
     fn signal_error_display_contains_message() {
         let err = PlatformError::Signal("SIGTERM".into());
@@ -0,0 +1,123 @@
+//! Expansion of `~` and environment variables in paths.
+//!
+//! Paths typed into prompts or written in config may start with `~` (the
+//! home directory) and refer to variables as `$NAME` or `${NAME}`. A `$`
+//! not followed by a name is kept as is; a variable that is not set is an
+//! error rather than silently becoming empty.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::PlatformError;
+
+/// Expand `~` and environment variables in `input` using the process
+/// environment.
+///
+/// # Errors
+///
+/// Returns [`PlatformError::UndefinedVariable`] for a variable that is
+/// not set, and [`PlatformError::Path`] for an unterminated `${` or a
+/// `~` when the home directory is unknown.
+pub fn expand_path(input: &str) -> Result<PathBuf, PlatformError> {
+    let home = dirs::home_dir();
+    expand_with(input, home.as_deref(), |name| std::env::var(name).ok()).map(PathBuf::from)
+}
+
+/// Expand `input` against `home` and the variables `var` returns.
+///
+/// # Errors
+///
+/// As for [`expand_path`].
+pub fn expand_with(
+    input: &str,
+    home: Option<&Path>,
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<String, PlatformError> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    // `~` alone or `~/...`; `~user` is left alone.
+    if let Some(after) = rest.strip_prefix('~') {
+        if after.is_empty() || after.starts_with('/') || after.starts_with('\\') {
+            let home = home.ok_or_else(|| {
+                PlatformError::Path(format!("cannot expand ~ in {}: no home directory", input))
+            })?;
+            out.push_str(&home.to_string_lossy());
+            rest = after;
+        }
+    }
+
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        let (name, tail) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| PlatformError::Path(format!("unterminated ${{ in {}", input)))?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            // A name starts with a letter or `_`, so `$1` stays literal.
+            let len = if after.starts_with(|c: char| c == '_' || c.is_ascii_alphabetic()) {
+                after
+                    .find(|c: char| !(c == '_' || c.is_ascii_alphanumeric()))
+                    .unwrap_or(after.len())
+            } else {
+                0
+            };
+            (&after[..len], &after[len..])
+        };
+        if name.is_empty() {
+            out.push('$');
+            rest = after;
+            continue;
+        }
+        let value = var(name).ok_or_else(|| PlatformError::UndefinedVariable(name.to_string()))?;
+        out.push_str(&value);
+        rest = tail;
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: &str) -> Result<String, PlatformError> {
+        let env = |name: &str| match name {
+            "HOME" => Some("/home/me".to_string()),
+            "PROJ" => Some("/work/proj".to_string()),
+            _ => None,
+        };
+        expand_with(input, Some(Path::new("/home/me")), env)
+    }
+
+    #[test]
+    fn tilde_and_variables_expand() {
+        assert_eq!(expand("~").unwrap(), "/home/me");
+        assert_eq!(expand("~/notes.md").unwrap(), "/home/me/notes.md");
+        assert_eq!(expand("$HOME/a").unwrap(), "/home/me/a");
+        assert_eq!(
+            expand("${PROJ}/src/${PROJ}").unwrap(),
+            "/work/proj/src//work/proj"
+        );
+        assert_eq!(expand("$PROJ.log").unwrap(), "/work/proj.log");
+    }
+
+    #[test]
+    fn other_text_is_left_alone() {
+        assert_eq!(expand("plain/path.rs").unwrap(), "plain/path.rs");
+        assert_eq!(expand("~user/x").unwrap(), "~user/x");
+        assert_eq!(expand("a/~/b").unwrap(), "a/~/b");
+        assert_eq!(expand("cost$").unwrap(), "cost$");
+        assert_eq!(expand("$1 $-").unwrap(), "$1 $-");
+    }
+
+    #[test]
+    fn undefined_and_malformed_variables_fail() {
+        let err = expand("$NOPE/x").unwrap_err();
+        assert!(matches!(err, PlatformError::UndefinedVariable(ref n) if n == "NOPE"));
+        assert!(err.to_string().contains("NOPE"));
+        assert!(expand("${PROJ").is_err());
+        assert!(expand_with("~/x", None, |_| None).is_err());
+    }
+}
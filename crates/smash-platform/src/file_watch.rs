@@ -0,0 +1,129 @@
+//! Noticing files changed by other programs.
+//!
+//! Watching is done by polling each file's modification time and size,
+//! which works the same on every platform and filesystem (network mounts
+//! included) and costs one `stat` per file per poll.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// What a file looked like on disk when last checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    /// Stamp `path` as it is now; `None` if it does not exist.
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+        })
+    }
+}
+
+/// A change to a watched file seen by [`FileWatcher::poll`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    /// Written since the last check (or created again after removal).
+    Modified(PathBuf),
+    /// No longer exists.
+    Removed(PathBuf),
+}
+
+/// Reports watched files whose modification time or size changed.
+#[derive(Debug, Default)]
+pub struct FileWatcher {
+    files: HashMap<PathBuf, Option<FileStamp>>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watch `path` from its current state on. Also used after writing
+    /// the file ourselves, so our own save is not reported.
+    pub fn watch(&mut self, path: &Path) {
+        self.files.insert(path.to_path_buf(), FileStamp::of(path));
+    }
+
+    pub fn unwatch(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    pub fn is_watching(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    /// Check every watched file, reporting each change once.
+    pub fn poll(&mut self) -> Vec<FileChange> {
+        let mut changes = Vec::new();
+        for (path, stamp) in &mut self.files {
+            let now = FileStamp::of(path);
+            if now == *stamp {
+                continue;
+            }
+            changes.push(match now {
+                Some(_) => FileChange::Modified(path.clone()),
+                None => FileChange::Removed(path.clone()),
+            });
+            *stamp = now;
+        }
+        changes.sort_by(|a, b| change_path(a).cmp(change_path(b)));
+        changes
+    }
+}
+
+fn change_path(change: &FileChange) -> &Path {
+    match change {
+        FileChange::Modified(path) | FileChange::Removed(path) => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_writes_and_removal_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "one").unwrap();
+        let mut watcher = FileWatcher::new();
+        watcher.watch(&path);
+        assert!(watcher.poll().is_empty());
+
+        std::fs::write(&path, "three").unwrap();
+        assert_eq!(watcher.poll(), vec![FileChange::Modified(path.clone())]);
+        assert!(watcher.poll().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.poll(), vec![FileChange::Removed(path.clone())]);
+        assert!(watcher.poll().is_empty());
+
+        std::fs::write(&path, "back").unwrap();
+        assert_eq!(watcher.poll(), vec![FileChange::Modified(path.clone())]);
+    }
+
+    #[test]
+    fn rewatching_forgets_our_own_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        let mut watcher = FileWatcher::new();
+        watcher.watch(&path);
+        assert!(watcher.is_watching(&path));
+
+        std::fs::write(&path, "saved by us").unwrap();
+        watcher.watch(&path);
+        assert!(watcher.poll().is_empty());
+
+        watcher.unwatch(&path);
+        std::fs::write(&path, "changed").unwrap();
+        assert!(watcher.poll().is_empty());
+    }
+}
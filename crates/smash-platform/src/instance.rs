@@ -0,0 +1,478 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::PlatformError;
+
+/// A request from a second `smash` invocation asking the running
+/// instance to open a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenRequest {
+    /// Absolute path of the file to open.
+    pub path: PathBuf,
+    /// Optional 1-based line to jump to after opening.
+    pub line: Option<usize>,
+}
+
+impl OpenRequest {
+    /// Creates a new open request.
+    pub fn new(path: PathBuf, line: Option<usize>) -> Self {
+        Self { path, line }
+    }
+
+    /// Encodes the request as a single protocol line (`open\t<line>\t<path>\n`).
+    ///
+    /// A missing line number is encoded as `0`. Backslashes, tabs and line
+    /// breaks in the path are escaped so that any path fits on one line.
+    pub fn encode(&self) -> String {
+        format!(
+            "open\t{}\t{}\n",
+            self.line.unwrap_or(0),
+            escape(&self.path.to_string_lossy())
+        )
+    }
+
+    /// Parses a protocol line produced by [`OpenRequest::encode`].
+    ///
+    /// Returns `None` for malformed input.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let mut parts = line.splitn(3, '\t');
+        if parts.next()? != "open" {
+            return None;
+        }
+        let line_no: usize = parts.next()?.parse().ok()?;
+        let path = unescape(parts.next()?)?;
+        if path.is_empty() {
+            return None;
+        }
+        Some(Self {
+            path: PathBuf::from(path),
+            line: (line_no > 0).then_some(line_no),
+        })
+    }
+}
+
+/// Escapes `\\`, `\t`, `\r` and `\n` so that `text` contains no protocol
+/// separators.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Reverses [`escape`]. Returns `None` for an unknown or unfinished escape.
+fn unescape(text: &str) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'r' => '\r',
+            'n' => '\n',
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+/// Returns the socket path used by the instance serving `workspace`.
+///
+/// The socket lives in `data_dir` and is named after a stable hash of
+/// the workspace path so that each project gets its own instance.
+pub fn socket_path(data_dir: &Path, workspace: &Path) -> PathBuf {
+    data_dir.join(format!(
+        "instance-{:016x}.sock",
+        fnv1a(workspace.to_string_lossy().as_bytes())
+    ))
+}
+
+/// 64-bit FNV-1a; stable across runs and toolchains, unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in bytes {
+        hash ^= u64::from(*b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Reply sent by the running instance once a request has been queued.
+#[cfg(unix)]
+const ACK: &str = "ok";
+
+/// Reply sent by the running instance for a request it could not parse.
+#[cfg(unix)]
+const NACK: &str = "error";
+
+/// How long [`forward`] waits for the running instance to acknowledge.
+#[cfg(unix)]
+const ACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Tries to hand `request` to an instance listening on `socket`.
+///
+/// Returns `Ok(true)` once a running instance has acknowledged the
+/// request, and `Ok(false)` if no instance is listening or none
+/// acknowledges it within a short timeout, in which case the caller
+/// should open the file itself.
+///
+/// # Errors
+///
+/// Returns `PlatformError::Unsupported` on platforms without Unix
+/// domain sockets, or `PlatformError::ProcessSpawn` if the request
+/// cannot be written.
+#[cfg(unix)]
+pub fn forward(socket: &Path, request: &OpenRequest) -> Result<bool, PlatformError> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = match UnixStream::connect(socket) {
+        Ok(s) => s,
+        Err(_) => return Ok(false),
+    };
+    stream.set_read_timeout(Some(ACK_TIMEOUT))?;
+    stream.write_all(request.encode().as_bytes())?;
+    stream.flush()?;
+    let mut reply = String::new();
+    match BufReader::new(&stream).read_line(&mut reply) {
+        Ok(_) => Ok(reply.trim_end() == ACK),
+        // Timed out or reset: the instance is stuck or gone.
+        Err(_) => Ok(false),
+    }
+}
+
+/// Tries to hand `request` to a running instance.
+///
+/// # Errors
+///
+/// Always returns `PlatformError::Unsupported`: single-instance mode
+/// needs Unix domain sockets.
+#[cfg(not(unix))]
+pub fn forward(_socket: &Path, _request: &OpenRequest) -> Result<bool, PlatformError> {
+    Err(unsupported())
+}
+
+/// Listener owned by the running instance. Connections are accepted and
+/// read on a background thread, so a slow or stuck client never blocks
+/// the event loop; [`InstanceServer::poll`] only collects what arrived.
+///
+/// Only available on Unix: elsewhere [`InstanceServer::bind`] and
+/// [`forward`] return `PlatformError::Unsupported`.
+///
+/// The socket file is removed when the server is dropped.
+#[cfg(unix)]
+pub struct InstanceServer {
+    path: PathBuf,
+    requests: std::sync::mpsc::Receiver<OpenRequest>,
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(unix)]
+impl InstanceServer {
+    /// Binds a listener at `socket` and starts accepting on a background
+    /// thread, replacing a stale socket file left behind by a crashed
+    /// instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PlatformError::Path` if another instance is already
+    /// listening, or `PlatformError::ProcessSpawn` on I/O failure.
+    pub fn bind(socket: &Path) -> Result<Self, PlatformError> {
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        if socket.exists() {
+            if UnixStream::connect(socket).is_ok() {
+                return Err(PlatformError::Path(format!(
+                    "instance already running at {}",
+                    socket.display()
+                )));
+            }
+            std::fs::remove_file(socket)?;
+        }
+        if let Some(parent) = socket.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let listener = UnixListener::bind(socket)?;
+        let (tx, requests) = std::sync::mpsc::channel();
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let stopped = std::sync::Arc::clone(&stop);
+        std::thread::Builder::new()
+            .name("smash-instance".into())
+            .spawn(move || accept_requests(&listener, &stopped, &tx))?;
+        Ok(Self {
+            path: socket.to_path_buf(),
+            requests,
+            stop,
+        })
+    }
+
+    /// Returns the path of the bound socket.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the requests received since the last call, without
+    /// blocking.
+    pub fn poll(&self) -> Vec<OpenRequest> {
+        self.requests.try_iter().collect()
+    }
+}
+
+/// Accepts connections until `stop` is set, sending each well-formed
+/// request to `tx` and then acknowledging it to the client. Malformed
+/// requests are logged and answered with an error.
+#[cfg(unix)]
+fn accept_requests(
+    listener: &std::os::unix::net::UnixListener,
+    stop: &std::sync::atomic::AtomicBool,
+    tx: &std::sync::mpsc::Sender<OpenRequest>,
+) {
+    use std::io::{BufRead, BufReader, Write};
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    for stream in listener.incoming() {
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(stream) = stream else { continue };
+        // A client that connects and never finishes only holds up this
+        // thread, and only for so long.
+        if stream
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .is_err()
+        {
+            continue;
+        }
+        for line in BufReader::new(&stream).lines() {
+            let Ok(line) = line else { break };
+            let reply = match OpenRequest::parse(&line) {
+                Some(req) => {
+                    if tx.send(req).is_err() {
+                        return;
+                    }
+                    ACK
+                }
+                None => {
+                    tracing::warn!("ignoring malformed instance request: {:?}", line);
+                    NACK
+                }
+            };
+            // The client may already have given up waiting; that is its
+            // problem, not ours.
+            let _ = writeln!(&stream, "{}", reply);
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for InstanceServer {
+    fn drop(&mut self) {
+        // Wake the accept thread so it sees `stop` and exits.
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        let _ = std::os::unix::net::UnixStream::connect(&self.path);
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Placeholder for the listener on platforms without Unix domain
+/// sockets; it can never be bound.
+#[cfg(not(unix))]
+pub struct InstanceServer;
+
+#[cfg(not(unix))]
+impl InstanceServer {
+    /// Would bind a listener at `socket`.
+    ///
+    /// # Errors
+    ///
+    /// Always returns `PlatformError::Unsupported`.
+    pub fn bind(_socket: &Path) -> Result<Self, PlatformError> {
+        Err(unsupported())
+    }
+
+    /// Returns no requests; there is no listener to receive them.
+    pub fn poll(&self) -> Vec<OpenRequest> {
+        Vec::new()
+    }
+}
+
+#[cfg(not(unix))]
+fn unsupported() -> PlatformError {
+    PlatformError::Unsupported {
+        os: std::env::consts::OS.into(),
+        detail: "single-instance mode requires Unix domain sockets".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_request_roundtrip_with_line() {
+        let req = OpenRequest::new(PathBuf::from("/tmp/a file.txt"), Some(42));
+        assert_eq!(OpenRequest::parse(&req.encode()), Some(req));
+    }
+
+    #[test]
+    fn open_request_roundtrip_without_line() {
+        let req = OpenRequest::new(PathBuf::from("/tmp/b.rs"), None);
+        assert_eq!(req.encode(), "open\t0\t/tmp/b.rs\n");
+        assert_eq!(OpenRequest::parse(&req.encode()), Some(req));
+    }
+
+    #[test]
+    fn open_request_escapes_separators_in_the_path() {
+        let req = OpenRequest::new(PathBuf::from("/tmp/odd\nname\tx\\y\r"), Some(3));
+        let line = req.encode();
+        assert_eq!(line.matches('\n').count(), 1);
+        assert_eq!(line.matches('\t').count(), 2);
+        assert_eq!(OpenRequest::parse(&line), Some(req));
+        assert_eq!(OpenRequest::parse("open\t0\t/tmp/a\\q"), None);
+        assert_eq!(OpenRequest::parse("open\t0\t/tmp/a\\"), None);
+    }
+
+    #[test]
+    fn open_request_parse_rejects_malformed() {
+        assert_eq!(OpenRequest::parse(""), None);
+        assert_eq!(OpenRequest::parse("close\t0\t/tmp/a"), None);
+        assert_eq!(OpenRequest::parse("open\tx\t/tmp/a"), None);
+        assert_eq!(OpenRequest::parse("open\t3\t"), None);
+    }
+
+    #[test]
+    fn socket_path_is_stable_and_per_workspace() {
+        let data = Path::new("/data");
+        let a1 = socket_path(data, Path::new("/work/a"));
+        let a2 = socket_path(data, Path::new("/work/a"));
+        let b = socket_path(data, Path::new("/work/b"));
+        assert_eq!(a1, a2);
+        assert_ne!(a1, b);
+        assert!(a1.starts_with(data));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn forward_without_server_returns_false() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sock = dir.path().join("none.sock");
+        let req = OpenRequest::new(PathBuf::from("/tmp/x"), None);
+        assert!(!forward(&sock, &req).expect("forward"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn server_receives_forwarded_request() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sock = dir.path().join("smash.sock");
+        let server = InstanceServer::bind(&sock).expect("bind");
+        let req = OpenRequest::new(PathBuf::from("/tmp/x.rs"), Some(7));
+        assert!(forward(&sock, &req).expect("forward"));
+        assert_eq!(poll_until_some(&server), vec![req]);
+        assert!(server.poll().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stalled_client_does_not_block_poll_or_later_clients() {
+        use std::os::unix::net::UnixStream;
+        use std::time::{Duration, Instant};
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sock = dir.path().join("smash.sock");
+        let server = InstanceServer::bind(&sock).expect("bind");
+        // Connects but never sends a full line.
+        let _stalled = UnixStream::connect(&sock).expect("connect");
+        let started = Instant::now();
+        assert!(server.poll().is_empty());
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        let req = OpenRequest::new(PathBuf::from("/tmp/y.rs"), None);
+        assert!(forward(&sock, &req).expect("forward"));
+        assert_eq!(poll_until_some(&server), vec![req]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn forward_without_acknowledgement_returns_false() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sock = dir.path().join("mute.sock");
+        let listener = UnixListener::bind(&sock).expect("bind");
+        // Accepts the connection and closes it without replying.
+        let mute = std::thread::spawn(move || drop(listener.accept()));
+        let req = OpenRequest::new(PathBuf::from("/tmp/x"), None);
+        assert!(!forward(&sock, &req).expect("forward"));
+        mute.join().expect("listener thread");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn server_answers_malformed_request_with_error() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixStream;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sock = dir.path().join("smash.sock");
+        let server = InstanceServer::bind(&sock).expect("bind");
+        let mut stream = UnixStream::connect(&sock).expect("connect");
+        stream.write_all(b"close\t0\t/tmp/x\n").expect("write");
+        let mut reply = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut reply)
+            .expect("reply");
+        assert_eq!(reply, "error\n");
+        assert!(server.poll().is_empty());
+    }
+
+    /// Polls `server` until a request arrives from the accept thread.
+    #[cfg(unix)]
+    fn poll_until_some(server: &InstanceServer) -> Vec<OpenRequest> {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            let requests = server.poll();
+            if !requests.is_empty() {
+                return requests;
+            }
+            assert!(std::time::Instant::now() < deadline, "no request arrived");
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bind_fails_while_instance_running() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sock = dir.path().join("smash.sock");
+        let _server = InstanceServer::bind(&sock).expect("bind");
+        assert!(matches!(
+            InstanceServer::bind(&sock),
+            Err(PlatformError::Path(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bind_replaces_stale_socket_and_drop_removes_it() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sock = dir.path().join("smash.sock");
+        std::fs::write(&sock, b"").expect("stale file");
+        let server = InstanceServer::bind(&sock).expect("bind over stale");
+        assert_eq!(server.path(), sock.as_path());
+        drop(server);
+        assert!(!sock.exists());
+    }
+}
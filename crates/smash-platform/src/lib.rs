@@ -1,12 +1,24 @@
 pub mod clipboard;
 pub mod error;
+pub mod expand;
+pub mod file_watch;
+pub mod instance;
+pub mod open;
 pub mod paths;
+pub mod shell;
 pub mod system_info;
+pub mod uri;
 
-pub use clipboard::{Clipboard, InMemoryClipboard, SystemClipboard};
+pub use clipboard::{Clipboard, ClipboardProvider, InMemoryClipboard, SystemClipboard};
 pub use error::PlatformError;
-pub use paths::{DefaultPaths, PlatformPaths};
+pub use expand::{expand_path, expand_with};
+pub use file_watch::{FileChange, FileStamp, FileWatcher};
+pub use instance::{InstanceServer, OpenRequest};
+pub use open::open_url;
+pub use paths::{DefaultPaths, Migration, PlatformPaths, CONFIG_DIR_ENV};
+pub use shell::{shell_command, ShellJob, ShellOutput};
 pub use system_info::{Arch, OsKind, SystemInfo};
+pub use uri::{path_to_uri, uri_to_path};
 
 /// Container holding all platform services.
 pub struct Platform {
@@ -33,7 +45,7 @@ impl Platform {
     /// Returns `PlatformError` if the home directory cannot be resolved.
     pub fn default_platform() -> Result<Self, PlatformError> {
         Ok(Self::new(
-            Box::new(SystemClipboard),
+            Box::new(SystemClipboard::detect()),
             Box::new(DefaultPaths::new()?),
         ))
     }
@@ -0,0 +1,64 @@
+use std::process::{Command, Stdio};
+
+use crate::error::PlatformError;
+use crate::system_info::{OsKind, SystemInfo};
+
+/// Program and arguments that open `url` in the desktop's default
+/// handler on `os`, such as the web browser for an `https` URL.
+pub fn open_command(os: &OsKind, url: &str) -> Option<(&'static str, Vec<String>)> {
+    match os {
+        OsKind::MacOs => Some(("open", vec![url.to_string()])),
+        OsKind::Linux => Some(("xdg-open", vec![url.to_string()])),
+        // `start` would need the URL quoted for cmd; this takes it as is.
+        OsKind::Windows => Some((
+            "rundll32",
+            vec!["url.dll,FileProtocolHandler".to_string(), url.to_string()],
+        )),
+        OsKind::Unknown => None,
+    }
+}
+
+/// Open `url` with the desktop's default handler without waiting for it.
+///
+/// # Errors
+///
+/// Returns `PlatformError::Unsupported` on an OS with no known handler,
+/// or `PlatformError::ProcessSpawn` if the handler cannot be started.
+pub fn open_url(url: &str) -> Result<(), PlatformError> {
+    let info = SystemInfo::detect();
+    let Some((program, args)) = open_command(info.os(), url) else {
+        return Err(PlatformError::Unsupported {
+            os: format!("{:?}", info.os()),
+            detail: "no program to open URLs".to_string(),
+        });
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the handler once it exits so it does not linger as a zombie.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_command_uses_each_platforms_handler() {
+        let url = "https://example.com/?a=1&b=2";
+        let (program, args) = open_command(&OsKind::Linux, url).unwrap();
+        assert_eq!(
+            (program, args.as_slice()),
+            ("xdg-open", &[url.to_string()][..])
+        );
+        assert_eq!(open_command(&OsKind::MacOs, url).unwrap().0, "open");
+        let (program, args) = open_command(&OsKind::Windows, url).unwrap();
+        assert_eq!(program, "rundll32");
+        assert_eq!(args.last().map(String::as_str), Some(url));
+        assert!(open_command(&OsKind::Unknown, url).is_none());
+    }
+}
@@ -1,15 +1,31 @@
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 
 use crate::error::PlatformError;
+use crate::system_info::{OsKind, SystemInfo};
+
+/// Environment variable that replaces the configuration directory on
+/// every platform, e.g. to keep it in a dotfiles checkout.
+pub const CONFIG_DIR_ENV: &str = "SMASH_CONFIG_DIR";
+
+/// Directory name used under each base directory.
+const APP_DIR: &str = "smash";
 
 /// Trait providing standard directory paths for the application.
+///
+/// Locations follow the platform's conventions: the XDG base directories
+/// on Linux, `~/Library` on macOS and `%APPDATA%` / `%LOCALAPPDATA%` on
+/// Windows.
 pub trait PlatformPaths: Send + Sync {
-    /// Returns the configuration directory (`~/.config/smash`).
+    /// Returns the configuration directory (`~/.config/smash` on Linux).
     fn config_dir(&self) -> PathBuf;
-    /// Returns the data directory (`~/.local/share/smash`).
+    /// Returns the data directory (`~/.local/share/smash` on Linux).
     fn data_dir(&self) -> PathBuf;
-    /// Returns the cache directory (`~/.cache/smash`).
+    /// Returns the cache directory (`~/.cache/smash` on Linux).
     fn cache_dir(&self) -> PathBuf;
+    /// Returns the state directory for sessions and history
+    /// (`~/.local/state/smash` on Linux).
+    fn state_dir(&self) -> PathBuf;
     /// Returns the log directory (`<data_dir>/logs`).
     fn log_dir(&self) -> PathBuf;
     /// Returns the default shell executable path.
@@ -22,10 +38,27 @@ pub trait PlatformPaths: Send + Sync {
 /// environment variables.
 pub struct DefaultPaths {
     home: PathBuf,
+    config: PathBuf,
+    data: PathBuf,
+    cache: PathBuf,
+    state: PathBuf,
+    /// Whether [`CONFIG_DIR_ENV`] chose the config directory.
+    config_overridden: bool,
+}
+
+/// A directory moved from a legacy location by
+/// [`DefaultPaths::migrate_legacy_dirs`].
+#[derive(Debug)]
+pub struct Migration {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Why some entries were left behind, if any were.
+    pub error: Option<String>,
 }
 
 impl DefaultPaths {
-    /// Creates a new `DefaultPaths` instance, resolving the home directory.
+    /// Creates a new `DefaultPaths` instance, resolving the home directory
+    /// and the directories for the running OS.
     ///
     /// # Errors
     ///
@@ -35,21 +68,160 @@ impl DefaultPaths {
         let home = dirs::home_dir()
             .or_else(|| std::env::var("HOME").ok().map(PathBuf::from))
             .ok_or_else(|| PlatformError::Path("could not determine home directory".into()))?;
-        Ok(Self { home })
+        Ok(Self::resolve(home, SystemInfo::detect().os(), |name| {
+            std::env::var_os(name)
+        }))
+    }
+
+    /// Directories for `os` under `home`, reading overrides such as
+    /// `XDG_CONFIG_HOME` through `var`.
+    ///
+    /// Relative values are ignored, as the XDG specification requires.
+    pub fn resolve(home: PathBuf, os: &OsKind, var: impl Fn(&str) -> Option<OsString>) -> Self {
+        let dir = |name: &str| var(name).map(PathBuf::from).filter(|p| p.is_absolute());
+        let (config, data, cache, state) = match os {
+            OsKind::MacOs => {
+                let library = home.join("Library");
+                let support = library.join("Application Support").join(APP_DIR);
+                (
+                    support.clone(),
+                    support.clone(),
+                    library.join("Caches").join(APP_DIR),
+                    support.join("state"),
+                )
+            }
+            OsKind::Windows => {
+                let roaming =
+                    dir("APPDATA").unwrap_or_else(|| home.join("AppData").join("Roaming"));
+                let local =
+                    dir("LOCALAPPDATA").unwrap_or_else(|| home.join("AppData").join("Local"));
+                let local = local.join(APP_DIR);
+                (
+                    roaming.join(APP_DIR),
+                    local.clone(),
+                    local.join("cache"),
+                    local.join("state"),
+                )
+            }
+            OsKind::Linux | OsKind::Unknown => {
+                let xdg = |name: &str, default: &[&str]| {
+                    dir(name)
+                        .unwrap_or_else(|| default.iter().fold(home.clone(), |p, c| p.join(c)))
+                        .join(APP_DIR)
+                };
+                (
+                    xdg("XDG_CONFIG_HOME", &[".config"]),
+                    xdg("XDG_DATA_HOME", &[".local", "share"]),
+                    xdg("XDG_CACHE_HOME", &[".cache"]),
+                    xdg("XDG_STATE_HOME", &[".local", "state"]),
+                )
+            }
+        };
+        let override_dir = dir(CONFIG_DIR_ENV);
+        Self {
+            config_overridden: override_dir.is_some(),
+            config: override_dir.unwrap_or(config),
+            data,
+            cache,
+            state,
+            home,
+        }
+    }
+
+    /// Move files from the directories used before the paths followed
+    /// platform conventions (`~/.config/smash`, `~/.local/share/smash`,
+    /// `~/.local/state/smash`) into the current ones.
+    ///
+    /// Entries already present at the destination are left where they
+    /// are, and an emptied legacy directory is removed, so this only does
+    /// work once. Returns what was moved, for logging.
+    pub fn migrate_legacy_dirs(&self) -> Vec<Migration> {
+        let legacy = |parts: &[&str]| {
+            parts
+                .iter()
+                .fold(self.home.clone(), |p, c| p.join(c))
+                .join(APP_DIR)
+        };
+        let mut pairs = vec![
+            (legacy(&[".local", "share"]), self.data.clone()),
+            (legacy(&[".local", "state"]), self.state.clone()),
+        ];
+        // An explicit override says where the config lives; leave it be.
+        if !self.config_overridden {
+            pairs.insert(0, (legacy(&[".config"]), self.config.clone()));
+        }
+        pairs
+            .into_iter()
+            .filter(|(from, to)| from != to && from.is_dir())
+            .filter_map(|(from, to)| {
+                let (moved, error) = merge_dir(&from, &to);
+                (moved > 0 || error.is_some()).then_some(Migration { from, to, error })
+            })
+            .collect()
+    }
+}
+
+/// Move each entry of `from` into `to` unless `to` already has one of
+/// that name. Returns how many moved and the first error, if any.
+fn merge_dir(from: &Path, to: &Path) -> (usize, Option<String>) {
+    let result = (|| -> std::io::Result<usize> {
+        let mut moved = 0;
+        let mut skipped = false;
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            let target = to.join(entry.file_name());
+            if target.exists() {
+                skipped = true;
+                continue;
+            }
+            move_path(&entry.path(), &target)?;
+            moved += 1;
+        }
+        if !skipped {
+            std::fs::remove_dir(from)?;
+        }
+        Ok(moved)
+    })();
+    match result {
+        Ok(moved) => (moved, None),
+        Err(e) => (0, Some(e.to_string())),
+    }
+}
+
+/// Rename, falling back to copy-and-delete across file systems.
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            move_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        std::fs::remove_dir(from)
+    } else {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)
     }
 }
 
 impl PlatformPaths for DefaultPaths {
     fn config_dir(&self) -> PathBuf {
-        self.home.join(".config").join("smash")
+        self.config.clone()
     }
 
     fn data_dir(&self) -> PathBuf {
-        self.home.join(".local").join("share").join("smash")
+        self.data.clone()
     }
 
     fn cache_dir(&self) -> PathBuf {
-        self.home.join(".cache").join("smash")
+        self.cache.clone()
+    }
+
+    fn state_dir(&self) -> PathBuf {
+        self.state.clone()
     }
 
     fn log_dir(&self) -> PathBuf {
@@ -57,10 +229,16 @@ impl PlatformPaths for DefaultPaths {
     }
 
     fn default_shell(&self) -> PathBuf {
-        std::env::var("SHELL")
+        // `SHELL` is usually unset on Windows, where `COMSPEC` names cmd.exe.
+        let (var, fallback) = if cfg!(windows) {
+            ("COMSPEC", "cmd.exe")
+        } else {
+            ("SHELL", "/bin/sh")
+        };
+        std::env::var(var)
             .ok()
             .map(PathBuf::from)
-            .unwrap_or_else(|| PathBuf::from("/bin/sh"))
+            .unwrap_or_else(|| PathBuf::from(fallback))
     }
 
     fn home_dir(&self) -> PathBuf {
@@ -76,9 +254,18 @@ mod tests {
         DefaultPaths::new().expect("should resolve home directory")
     }
 
+    /// Paths for `os` under `home` with only the given variables set.
+    fn resolve(home: &Path, os: OsKind, vars: &[(&str, &Path)]) -> DefaultPaths {
+        DefaultPaths::resolve(home.to_path_buf(), &os, |name| {
+            vars.iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.as_os_str().to_owned())
+        })
+    }
+
     #[test]
     fn config_dir_ends_with_config_smash() {
-        let paths = make_paths();
+        let paths = resolve(&std::env::temp_dir(), OsKind::Linux, &[]);
         let config = paths.config_dir();
         assert!(
             config.ends_with(".config/smash"),
@@ -111,6 +298,17 @@ mod tests {
         assert!(s.contains("smash"), "cache_dir should contain 'smash'");
     }
 
+    #[test]
+    fn state_dir_ends_with_local_state_smash() {
+        let paths = resolve(&std::env::temp_dir(), OsKind::Linux, &[]);
+        let state = paths.state_dir();
+        assert!(
+            state.ends_with(".local/state/smash"),
+            "state_dir should end with .local/state/smash, got: {:?}",
+            state
+        );
+    }
+
     #[test]
     fn log_dir_contains_smash() {
         let paths = make_paths();
@@ -149,4 +347,100 @@ mod tests {
         fn assert_send_sync<T: Send + Sync>() {}
         assert_send_sync::<DefaultPaths>();
     }
+
+    #[test]
+    fn linux_honours_absolute_xdg_variables() {
+        let home = std::env::temp_dir().join("home");
+        let xdg = std::env::temp_dir().join("xdg");
+        let paths = resolve(
+            &home,
+            OsKind::Linux,
+            &[
+                ("XDG_CONFIG_HOME", &xdg.join("config")),
+                ("XDG_DATA_HOME", &xdg.join("data")),
+                ("XDG_STATE_HOME", &xdg.join("state")),
+                ("XDG_CACHE_HOME", Path::new("relative/cache")),
+            ],
+        );
+        assert_eq!(paths.config_dir(), xdg.join("config").join("smash"));
+        assert_eq!(paths.data_dir(), xdg.join("data").join("smash"));
+        assert_eq!(paths.state_dir(), xdg.join("state").join("smash"));
+        // Relative values are ignored.
+        assert_eq!(paths.cache_dir(), home.join(".cache").join("smash"));
+    }
+
+    #[test]
+    fn macos_and_windows_use_their_own_locations() {
+        let home = std::env::temp_dir().join("home");
+        let mac = resolve(&home, OsKind::MacOs, &[]);
+        let support = home
+            .join("Library")
+            .join("Application Support")
+            .join("smash");
+        assert_eq!(mac.config_dir(), support);
+        assert_eq!(mac.state_dir(), support.join("state"));
+        assert_eq!(
+            mac.cache_dir(),
+            home.join("Library").join("Caches").join("smash")
+        );
+
+        let roaming = std::env::temp_dir().join("Roaming");
+        let win = resolve(&home, OsKind::Windows, &[("APPDATA", &roaming)]);
+        assert_eq!(win.config_dir(), roaming.join("smash"));
+        let local = home.join("AppData").join("Local").join("smash");
+        assert_eq!(win.data_dir(), local);
+        assert_eq!(win.log_dir(), local.join("logs"));
+    }
+
+    #[test]
+    fn config_dir_override_wins_everywhere() {
+        let home = std::env::temp_dir().join("home");
+        let custom = std::env::temp_dir().join("dotfiles").join("smash");
+        for os in [OsKind::Linux, OsKind::MacOs, OsKind::Windows] {
+            let paths = resolve(&home, os, &[(CONFIG_DIR_ENV, &custom)]);
+            assert_eq!(paths.config_dir(), custom);
+        }
+    }
+
+    #[test]
+    fn legacy_dirs_are_migrated_once() {
+        let home = tempfile::tempdir().expect("tempdir");
+        let legacy_config = home.path().join(".config").join("smash");
+        let legacy_state = home.path().join(".local").join("state").join("smash");
+        std::fs::create_dir_all(legacy_config.join("themes")).unwrap();
+        std::fs::write(legacy_config.join("config.toml"), "x = 1").unwrap();
+        std::fs::write(legacy_config.join("themes").join("a.toml"), "").unwrap();
+        std::fs::create_dir_all(&legacy_state).unwrap();
+        std::fs::write(legacy_state.join("session.json"), "{}").unwrap();
+
+        let paths = resolve(home.path(), OsKind::MacOs, &[]);
+        // Already present at the destination: kept where it was.
+        std::fs::create_dir_all(paths.state_dir()).unwrap();
+        std::fs::write(paths.state_dir().join("session.json"), "new").unwrap();
+
+        let moved = paths.migrate_legacy_dirs();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].from, legacy_config);
+        assert!(moved[0].error.is_none());
+        let config = paths.config_dir();
+        assert_eq!(
+            std::fs::read_to_string(config.join("config.toml")).unwrap(),
+            "x = 1"
+        );
+        assert!(config.join("themes").join("a.toml").exists());
+        assert!(!legacy_config.exists());
+        assert_eq!(
+            std::fs::read_to_string(paths.state_dir().join("session.json")).unwrap(),
+            "new"
+        );
+        assert!(legacy_state.join("session.json").exists());
+
+        assert!(paths.migrate_legacy_dirs().is_empty());
+
+        // Where nothing changed (Linux defaults) there is nothing to do.
+        let linux = resolve(home.path(), OsKind::Linux, &[]);
+        std::fs::create_dir_all(&legacy_config).unwrap();
+        assert!(linux.migrate_legacy_dirs().is_empty());
+        assert!(legacy_config.exists());
+    }
 }
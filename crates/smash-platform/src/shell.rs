@@ -0,0 +1,203 @@
+//! Running a shell command in the background, optionally feeding it
+//! text on stdin, and collecting everything it writes.
+
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use crate::error::PlatformError;
+
+/// How long to wait for the rest of a command's output after it exits.
+const STREAM_GRACE: Duration = Duration::from_millis(500);
+
+/// A `Command` that runs `command` through the platform shell: `sh -c`
+/// on Unix and `cmd /C` on Windows.
+pub fn shell_command(command: &str) -> Command {
+    #[cfg(windows)]
+    let (shell, flag) = ("cmd", "/C");
+    #[cfg(not(windows))]
+    let (shell, flag) = ("sh", "-c");
+    let mut cmd = Command::new(shell);
+    cmd.arg(flag).arg(command);
+    cmd
+}
+
+/// What a finished shell command wrote and how it exited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// The exit code, or `None` when the command was ended by a signal.
+    pub code: Option<i32>,
+    pub success: bool,
+}
+
+/// A shell command running in the background.
+pub struct ShellJob {
+    command: String,
+    child: Option<Child>,
+    deadline: Instant,
+    timeout: Duration,
+    stdout: Receiver<String>,
+    stderr: Receiver<String>,
+}
+
+impl ShellJob {
+    /// Start `command` with `input` on its stdin, or nothing when `None`.
+    /// It is killed if still running once `timeout` has passed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PlatformError::ProcessSpawn` if the shell cannot be
+    /// started.
+    pub fn spawn(
+        command: &str,
+        input: Option<&str>,
+        timeout: Duration,
+    ) -> Result<Self, PlatformError> {
+        let mut child = shell_command(command)
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+            // Written from a thread so a command that prints before it has
+            // read all of its input cannot deadlock against us.
+            let input = input.to_string();
+            std::thread::spawn(move || {
+                let _ = stdin.write_all(input.as_bytes());
+            });
+        }
+        let stdout = read_to_end(child.stdout.take());
+        let stderr = read_to_end(child.stderr.take());
+        Ok(Self {
+            command: command.to_string(),
+            child: Some(child),
+            deadline: Instant::now() + timeout,
+            timeout,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// The command line being run.
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// The result once the command has exited, or `None` while it is
+    /// still running.
+    ///
+    /// # Errors
+    ///
+    /// The result is `PlatformError::Timeout` if the command ran past its
+    /// timeout and was killed, or `PlatformError::ProcessSpawn` if waiting
+    /// on it failed.
+    pub fn poll(&mut self) -> Option<Result<ShellOutput, PlatformError>> {
+        let child = self.child.as_mut()?;
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                self.child = None;
+                // The streams close when the process exits, unless it left
+                // a background process holding them; don't wait on that.
+                let stdout = self.stdout.recv_timeout(STREAM_GRACE).unwrap_or_default();
+                let stderr = self.stderr.recv_timeout(STREAM_GRACE).unwrap_or_default();
+                Some(Ok(ShellOutput {
+                    stdout,
+                    stderr,
+                    code: status.code(),
+                    success: status.success(),
+                }))
+            }
+            Ok(None) if Instant::now() >= self.deadline => {
+                self.kill();
+                Some(Err(PlatformError::Timeout(self.timeout)))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                self.kill();
+                Some(Err(e.into()))
+            }
+        }
+    }
+
+    /// Block until the command finishes or times out.
+    ///
+    /// # Errors
+    ///
+    /// As for [`ShellJob::poll`].
+    pub fn wait(mut self) -> Result<ShellOutput, PlatformError> {
+        loop {
+            match self.poll() {
+                Some(result) => return result,
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+    }
+
+    fn kill(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for ShellJob {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// Read `stream` to the end on a thread, sending the text once it closes.
+fn read_to_end(stream: Option<impl Read + Send + 'static>) -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut stream) = stream {
+            let _ = stream.read_to_end(&mut bytes);
+        }
+        let _ = tx.send(String::from_utf8_lossy(&bytes).into_owned());
+    });
+    rx
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn collects_stdout_stderr_and_exit_code() {
+        let job = ShellJob::spawn("echo out; echo err >&2; exit 3", None, TIMEOUT).unwrap();
+        assert_eq!(job.command(), "echo out; echo err >&2; exit 3");
+        let output = job.wait().unwrap();
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+        assert_eq!(output.code, Some(3));
+        assert!(!output.success);
+    }
+
+    #[test]
+    fn feeds_input_on_stdin() {
+        let job = ShellJob::spawn("sort", Some("b\nc\na\n"), TIMEOUT).unwrap();
+        let output = job.wait().unwrap();
+        assert!(output.success);
+        assert_eq!(output.stdout, "a\nb\nc\n");
+    }
+
+    #[test]
+    fn kills_a_command_that_runs_past_its_timeout() {
+        let start = Instant::now();
+        let job = ShellJob::spawn("sleep 5", None, Duration::from_millis(50)).unwrap();
+        let err = job.wait().unwrap_err();
+        assert!(matches!(err, PlatformError::Timeout(_)));
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+}
@@ -0,0 +1,229 @@
+//! Conversion between filesystem paths and `file://` URIs.
+//!
+//! LSP servers identify documents by URI. A URI path always uses `/`,
+//! percent-encodes anything outside a small safe set (spaces, `#`,
+//! non-ASCII bytes as UTF-8), and on Windows carries the drive letter as
+//! `/C:/...` or a UNC share as the URI host (`file://server/share/...`).
+//!
+//! The conversions work on either path style so both can be tested on any
+//! host; [`path_to_uri`] and [`uri_to_path`] pick the native one.
+
+use std::path::{Path, PathBuf};
+
+/// Whether paths on this host use Windows syntax.
+const WINDOWS: bool = cfg!(windows);
+
+/// `file://` URI for `path`, which should be absolute.
+pub fn path_to_uri(path: &Path) -> String {
+    encode_path(&path_bytes(path), WINDOWS)
+}
+
+/// Filesystem path of a `file://` URI, or `None` for other schemes and
+/// for hosts that cannot be reached as a path on this platform.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    decode_uri(uri, WINDOWS).map(path_from_bytes)
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Bytes left as they are in a URI path; everything else is `%XX`.
+fn is_path_safe(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/".contains(&b)
+}
+
+fn percent_encode(bytes: &[u8], out: &mut String) {
+    for &b in bytes {
+        if is_path_safe(b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+}
+
+fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn is_drive(bytes: &[u8]) -> bool {
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// Encode a path given as bytes, in Windows or POSIX syntax.
+fn encode_path(path: &[u8], windows: bool) -> String {
+    let mut out = String::from("file://");
+    if !windows {
+        percent_encode(path, &mut out);
+        return out;
+    }
+    let path: Vec<u8> = path
+        .iter()
+        .map(|&b| if b == b'\\' { b'/' } else { b })
+        .collect();
+    // Verbatim paths: `\\?\C:\x` and `\\?\UNC\server\share\x`.
+    let path = match path.strip_prefix(b"//?/") {
+        Some(rest) => match rest.strip_prefix(b"UNC/") {
+            Some(unc) => [b"//".as_slice(), unc].concat(),
+            None => rest.to_vec(),
+        },
+        None => path,
+    };
+    if let Some(unc) = path.strip_prefix(b"//") {
+        // The server becomes the URI host.
+        let split = unc.iter().position(|&b| b == b'/').unwrap_or(unc.len());
+        percent_encode(&unc[..split], &mut out);
+        percent_encode(&unc[split..], &mut out);
+    } else {
+        if is_drive(&path) {
+            out.push('/');
+        }
+        percent_encode(&path, &mut out);
+    }
+    out
+}
+
+/// Decode a `file://` URI to path bytes in Windows or POSIX syntax.
+fn decode_uri(uri: &str, windows: bool) -> Option<Vec<u8>> {
+    let scheme = uri.get(..7)?;
+    if !scheme.eq_ignore_ascii_case("file://") {
+        return None;
+    }
+    let rest = &uri[7..];
+    // A query or fragment is not part of the path.
+    let rest = &rest[..rest.find(['?', '#']).unwrap_or(rest.len())];
+    let split = rest.find('/').unwrap_or(rest.len());
+    let (host, path) = rest.split_at(split);
+    let mut path = percent_decode(path);
+    let local = host.is_empty() || host.eq_ignore_ascii_case("localhost");
+    if !windows {
+        return local.then_some(path);
+    }
+    if !local {
+        let mut unc = b"\\\\".to_vec();
+        unc.extend(percent_decode(host));
+        path.splice(0..0, unc);
+    } else if path.first() == Some(&b'/') && is_drive(&path[1..]) {
+        path.remove(0);
+    }
+    for b in &mut path {
+        if *b == b'/' {
+            *b = b'\\';
+        }
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_uri(path: &str, windows: bool) -> String {
+        encode_path(path.as_bytes(), windows)
+    }
+
+    fn to_path(uri: &str, windows: bool) -> Option<String> {
+        decode_uri(uri, windows).map(|b| String::from_utf8(b).unwrap())
+    }
+
+    #[test]
+    fn posix_paths_round_trip_with_encoding() {
+        let cases = [
+            ("/home/user/main.rs", "file:///home/user/main.rs"),
+            (
+                "/tmp/my project/a b.rs",
+                "file:///tmp/my%20project/a%20b.rs",
+            ),
+            ("/src/über/naïve.rs", "file:///src/%C3%BCber/na%C3%AFve.rs"),
+            ("/a/#1?.rs", "file:///a/%231%3F.rs"),
+            ("/a/100%.rs", "file:///a/100%25.rs"),
+        ];
+        for (path, uri) in cases {
+            assert_eq!(to_uri(path, false), uri);
+            assert_eq!(to_path(uri, false).as_deref(), Some(path));
+        }
+    }
+
+    #[test]
+    fn windows_drive_and_unc_paths_round_trip() {
+        let cases = [
+            (r"C:\Users\me\main.rs", "file:///C:/Users/me/main.rs"),
+            (r"d:\My Files\x.rs", "file:///d:/My%20Files/x.rs"),
+            (r"\\server\share\dir\f.rs", "file://server/share/dir/f.rs"),
+        ];
+        for (path, uri) in cases {
+            assert_eq!(to_uri(path, true), uri);
+            assert_eq!(to_path(uri, true).as_deref(), Some(path));
+        }
+        // Verbatim prefixes are dropped.
+        assert_eq!(to_uri(r"\\?\C:\x.rs", true), "file:///C:/x.rs");
+        assert_eq!(
+            to_uri(r"\\?\UNC\server\share\x.rs", true),
+            "file://server/share/x.rs"
+        );
+    }
+
+    #[test]
+    fn decodes_what_servers_send() {
+        // VS Code style: encoded drive colon, lower-case scheme variants.
+        assert_eq!(
+            to_path("file:///c%3A/src/main.rs", true).as_deref(),
+            Some(r"c:\src\main.rs")
+        );
+        assert_eq!(
+            to_path("FILE://localhost/etc/hosts", false).as_deref(),
+            Some("/etc/hosts")
+        );
+        assert_eq!(to_path("file:///a.rs#L10", false).as_deref(), Some("/a.rs"));
+        assert_eq!(to_path("file://server/share/x", false), None);
+        assert_eq!(to_path("https://example.com/a.rs", false), None);
+        assert_eq!(to_path("file:", false), None);
+    }
+
+    #[test]
+    fn native_round_trip() {
+        let path = std::env::temp_dir().join("smash uri test").join("é.rs");
+        let uri = path_to_uri(&path);
+        assert!(uri.starts_with("file://"));
+        assert!(!uri.contains(' '));
+        assert_eq!(uri_to_path(&uri), Some(path));
+    }
+}
@@ -1,5 +1,7 @@
 use std::path::Path;
 
+use smash_core::indent::IndentRules;
+
 /// Known languages for Phase 1.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LanguageId {
@@ -14,6 +16,10 @@ pub enum LanguageId {
     Json,
     Markdown,
     Shell,
+    /// A commit, merge or tag message git opened the editor for.
+    GitCommit,
+    /// The todo list of an interactive rebase.
+    GitRebase,
     Plain,
 }
 
@@ -52,6 +58,10 @@ impl LanguageId {
                     return Self::Toml;
                 }
                 "Dockerfile" => return Self::Shell,
+                "COMMIT_EDITMSG" | "MERGE_MSG" | "TAG_EDITMSG" => {
+                    return Self::GitCommit;
+                }
+                "git-rebase-todo" => return Self::GitRebase,
                 _ => {}
             }
         }
@@ -76,9 +86,25 @@ impl LanguageId {
             Self::Json => "json",
             Self::Markdown => "markdown",
             Self::Shell => "shell",
+            Self::GitCommit => "git-commit",
+            Self::GitRebase => "git-rebase",
             Self::Plain => "plain",
         }
     }
+
+    /// How new lines are indented in this language.
+    pub fn indent_rules(&self) -> IndentRules {
+        match self {
+            Self::Python => IndentRules {
+                colon_opens: true,
+                ..IndentRules::default()
+            },
+            Self::Markdown | Self::GitCommit | Self::GitRebase | Self::Plain => {
+                IndentRules::copy_only()
+            }
+            _ => IndentRules::default(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -86,6 +112,14 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    #[test]
+    fn indent_rules_follow_language() {
+        assert!(LanguageId::Python.indent_rules().colon_opens);
+        assert!(!LanguageId::Rust.indent_rules().colon_opens);
+        assert!(LanguageId::Rust.indent_rules().openers.contains(&'{'));
+        assert!(LanguageId::Plain.indent_rules().openers.is_empty());
+    }
+
     // ----- from_extension tests -----
 
     #[test]
@@ -222,6 +256,15 @@ mod tests {
         assert_eq!(LanguageId::from_path(&p), LanguageId::Shell);
     }
 
+    #[test]
+    fn path_git_files_are_detected() {
+        let p = PathBuf::from("repo/.git/COMMIT_EDITMSG");
+        assert_eq!(LanguageId::from_path(&p), LanguageId::GitCommit);
+        let p = PathBuf::from("repo/.git/rebase-merge/git-rebase-todo");
+        assert_eq!(LanguageId::from_path(&p), LanguageId::GitRebase);
+        assert_eq!(LanguageId::GitRebase.as_str(), "git-rebase");
+    }
+
     #[test]
     fn path_lowercase_makefile_is_shell() {
         let p = PathBuf::from("makefile");
@@ -0,0 +1,208 @@
+//! Highlighting only what is about to be seen.
+//!
+//! [`LazyHighlighter`] hands lines to a background thread and keeps the
+//! spans that come back, so drawing a frame never waits on highlighting
+//! and a large file is only ever highlighted around the viewport. Spans
+//! are keyed by the line's text: engines highlight each line on its own,
+//! so equal lines share spans and edits need no invalidation.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{mpsc, Mutex};
+
+use crate::highlight::{HighlightEngine, HighlightSpan};
+
+/// Lines highlighted between results sent back, so the first lines of a
+/// request arrive without waiting for the rest.
+const CHUNK_LINES: usize = 64;
+
+type Job = Vec<(u64, String)>;
+type Done = Vec<(u64, Vec<HighlightSpan>)>;
+
+/// Caches spans computed on a worker thread. As a [`HighlightEngine`] it
+/// answers from the cache, leaving lines it has not seen yet plain.
+pub struct LazyHighlighter {
+    spans: HashMap<u64, Vec<HighlightSpan>>,
+    /// Sent to the worker and not back yet.
+    requested: HashSet<u64>,
+    jobs: mpsc::Sender<Job>,
+    /// Behind a lock only so this can be shared as a [`HighlightEngine`];
+    /// it is only read through `&mut self`.
+    results: Mutex<mpsc::Receiver<Done>>,
+}
+
+impl LazyHighlighter {
+    /// Start a worker thread highlighting with `engine`. It stops when
+    /// this is dropped.
+    pub fn spawn<E: HighlightEngine + 'static>(engine: E) -> Self {
+        let (jobs, job_rx) = mpsc::channel::<Job>();
+        let (done_tx, results) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("highlight".into())
+            .spawn(move || run_worker(&engine, &job_rx, &done_tx));
+        if let Err(e) = spawned {
+            tracing::error!("could not start the highlight worker: {}", e);
+        }
+        Self {
+            spans: HashMap::new(),
+            requested: HashSet::new(),
+            jobs,
+            results: Mutex::new(results),
+        }
+    }
+
+    /// Queue lines, in the order they should be highlighted, skipping
+    /// those already known or on their way. A trailing line break is
+    /// ignored. Returns how many were queued.
+    pub fn request<S: AsRef<str>>(&mut self, lines: impl IntoIterator<Item = S>) -> usize {
+        let mut job = Vec::new();
+        for line in lines {
+            let line = trim_line_break(line.as_ref());
+            let key = line_key(line);
+            if !self.spans.contains_key(&key) && self.requested.insert(key) {
+                job.push((key, line.to_string()));
+            }
+        }
+        let queued = job.len();
+        if queued > 0 && self.jobs.send(job).is_err() {
+            tracing::warn!("highlight worker has stopped");
+        }
+        queued
+    }
+
+    /// Take in spans the worker has finished. Returns how many lines
+    /// arrived.
+    pub fn poll(&mut self) -> usize {
+        let mut arrived = 0;
+        let results = self.results.get_mut().unwrap_or_else(|e| e.into_inner());
+        while let Ok(done) = results.try_recv() {
+            arrived += done.len();
+            for (key, spans) in done {
+                self.requested.remove(&key);
+                self.spans.insert(key, spans);
+            }
+        }
+        arrived
+    }
+
+    /// Whether `line` has been highlighted.
+    pub fn is_ready(&self, line: &str) -> bool {
+        self.spans.contains_key(&line_key(trim_line_break(line)))
+    }
+
+    /// Whether lines are still with the worker.
+    pub fn is_busy(&self) -> bool {
+        !self.requested.is_empty()
+    }
+
+    /// Lines whose spans are kept.
+    pub fn cached_lines(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Approximate memory held by the cached spans, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        let entry = std::mem::size_of::<u64>() + std::mem::size_of::<Vec<HighlightSpan>>();
+        let spans: usize = self.spans.values().map(Vec::capacity).sum();
+        self.spans.capacity() * entry
+            + spans * std::mem::size_of::<HighlightSpan>()
+            + self.requested.capacity() * std::mem::size_of::<u64>()
+    }
+
+    /// Forget spans for every line not in `lines`, e.g. once the region
+    /// around the viewport has moved on.
+    pub fn retain<S: AsRef<str>>(&mut self, lines: impl IntoIterator<Item = S>) {
+        let keep: HashSet<u64> = lines
+            .into_iter()
+            .map(|l| line_key(trim_line_break(l.as_ref())))
+            .collect();
+        self.spans.retain(|key, _| keep.contains(key));
+    }
+}
+
+impl HighlightEngine for LazyHighlighter {
+    fn highlight_line(&self, line: &str) -> Vec<HighlightSpan> {
+        self.spans.get(&line_key(line)).cloned().unwrap_or_default()
+    }
+
+    fn engine_name(&self) -> &str {
+        "lazy"
+    }
+}
+
+/// Highlight jobs until the editor side goes away. Newer jobs go first:
+/// while scrolling fast, the lines now in view matter more than those
+/// asked for a few frames ago.
+fn run_worker(engine: &dyn HighlightEngine, jobs: &mpsc::Receiver<Job>, done: &mpsc::Sender<Done>) {
+    while let Ok(first) = jobs.recv() {
+        let mut pending = vec![first];
+        pending.extend(jobs.try_iter());
+        for job in pending.into_iter().rev() {
+            for chunk in job.chunks(CHUNK_LINES) {
+                let spans = chunk
+                    .iter()
+                    .map(|(key, line)| (*key, engine.highlight_line(line)))
+                    .collect();
+                if done.send(spans).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn trim_line_break(line: &str) -> &str {
+    line.trim_end_matches('\n').trim_end_matches('\r')
+}
+
+fn line_key(line: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, RegexHighlighter};
+
+    fn wait(lazy: &mut LazyHighlighter) {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while lazy.is_busy() {
+            assert!(std::time::Instant::now() < deadline, "worker stalled");
+            lazy.poll();
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn lines_are_plain_until_the_worker_answers() {
+        let engine = RegexHighlighter::new(LanguageId::Rust).unwrap();
+        let expected = engine.highlight_line("fn main() {}");
+        let mut lazy = LazyHighlighter::spawn(RegexHighlighter::new(LanguageId::Rust).unwrap());
+        assert!(lazy.highlight_line("fn main() {}").is_empty());
+
+        assert_eq!(lazy.request(["fn main() {}\n", "let x = 1;\r\n"]), 2);
+        wait(&mut lazy);
+        assert!(lazy.is_ready("fn main() {}"));
+        assert_eq!(lazy.highlight_line("fn main() {}"), expected);
+        assert!(!lazy.highlight_line("let x = 1;").is_empty());
+    }
+
+    #[test]
+    fn known_and_pending_lines_are_not_asked_twice() {
+        let mut lazy = LazyHighlighter::spawn(RegexHighlighter::new(LanguageId::Rust).unwrap());
+        assert_eq!(lazy.request(["a", "b", "a"]), 2);
+        assert_eq!(lazy.request(["a", "b"]), 0);
+        wait(&mut lazy);
+        assert_eq!(lazy.request(["a", "c"]), 1);
+        wait(&mut lazy);
+        assert_eq!(lazy.cached_lines(), 3);
+
+        lazy.retain(["c"]);
+        assert_eq!(lazy.cached_lines(), 1);
+        assert!(lazy.is_ready("c"));
+        assert!(!lazy.is_ready("a"));
+    }
+}
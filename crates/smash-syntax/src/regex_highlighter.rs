@@ -6,6 +6,7 @@ use crate::language::LanguageId;
 use crate::scope::ScopeId;
 
 /// A single highlighting rule: a regex pattern + scope.
+#[derive(Clone)]
 struct HighlightRule {
     regex: Regex,
     scope: ScopeId,
@@ -16,6 +17,7 @@ struct HighlightRule {
 /// Rules are evaluated in order; earlier rules have higher
 /// priority. Overlapping matches from later rules are
 /// discarded.
+#[derive(Clone)]
 pub struct RegexHighlighter {
     language: LanguageId,
     rules: Vec<HighlightRule>,
@@ -74,6 +76,8 @@ fn build_rules(lang: LanguageId) -> Result<Vec<HighlightRule>, SyntaxError> {
         LanguageId::Toml => toml_rules(),
         LanguageId::Json => json_rules(),
         LanguageId::Shell => shell_rules(),
+        LanguageId::GitCommit => git_commit_rules(),
+        LanguageId::GitRebase => git_rebase_rules(),
         // Plain / Markdown = no highlighting rules
         _ => Ok(Vec::new()),
     }
@@ -315,6 +319,32 @@ fn shell_rules() -> Result<Vec<HighlightRule>, SyntaxError> {
     ])
 }
 
+fn git_commit_rules() -> Result<Vec<HighlightRule>, SyntaxError> {
+    let lang = "git-commit";
+    Ok(vec![
+        // Git's own help text; it is dropped from the message.
+        make_rule(r"^#.*", ScopeId::Comment, lang)?,
+        // Trailers such as `Signed-off-by:`.
+        make_rule(r"^[A-Z][\w-]*-by:", ScopeId::Attribute, lang)?,
+    ])
+}
+
+fn git_rebase_rules() -> Result<Vec<HighlightRule>, SyntaxError> {
+    let lang = "git-rebase";
+    Ok(vec![
+        make_rule(r"^\s*#.*", ScopeId::Comment, lang)?,
+        make_rule(
+            concat!(
+                r"^\s*(?:pick|reword|edit|squash|fixup|drop|exec|break",
+                r"|label|reset|merge|update-ref|[presfdxbltmu])\b",
+            ),
+            ScopeId::Keyword,
+            lang,
+        )?,
+        make_rule(r"\b[0-9a-f]{7,40}\b", ScopeId::Constant, lang)?,
+    ])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -547,6 +577,32 @@ mod tests {
         assert!(c.is_some());
     }
 
+    // ----- git highlighting -----
+
+    #[test]
+    fn git_rebase_action_hash_and_comment() {
+        let h = RegexHighlighter::new(LanguageId::GitRebase).unwrap();
+        let spans = h.highlight_line("pick 1a2b3c4 Fix the parser");
+        assert_eq!(spans[0].scope, ScopeId::Keyword);
+        assert_eq!((spans[0].start, spans[0].end), (0, 4));
+        assert_eq!(spans[1].scope, ScopeId::Constant);
+        assert_eq!((spans[1].start, spans[1].end), (5, 12));
+        // Words in the subject are not actions.
+        assert_eq!(spans.len(), 2);
+        let spans = h.highlight_line("# Commands:");
+        assert_eq!(spans[0].scope, ScopeId::Comment);
+    }
+
+    #[test]
+    fn git_commit_comments_and_trailers() {
+        let h = RegexHighlighter::new(LanguageId::GitCommit).unwrap();
+        assert!(h.highlight_line("Fix the # parser").is_empty());
+        let spans = h.highlight_line("# Please enter the commit message");
+        assert_eq!(spans[0].scope, ScopeId::Comment);
+        let spans = h.highlight_line("Signed-off-by: A <a@b.c>");
+        assert_eq!(spans[0].scope, ScopeId::Attribute);
+    }
+
     // ----- JSON highlighting -----
 
     #[test]
@@ -688,6 +744,8 @@ mod tests {
             LanguageId::Toml,
             LanguageId::Json,
             LanguageId::Shell,
+            LanguageId::GitCommit,
+            LanguageId::GitRebase,
             LanguageId::Markdown,
             LanguageId::Plain,
         ];
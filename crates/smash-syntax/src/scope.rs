@@ -22,6 +22,30 @@ pub enum ScopeId {
 }
 
 impl ScopeId {
+    /// Every scope, in declaration order.
+    pub const ALL: [ScopeId; 15] = [
+        Self::Keyword,
+        Self::Type,
+        Self::Function,
+        Self::String,
+        Self::Number,
+        Self::Comment,
+        Self::Operator,
+        Self::Punctuation,
+        Self::Variable,
+        Self::Constant,
+        Self::Attribute,
+        Self::Macro,
+        Self::Namespace,
+        Self::Label,
+        Self::Plain,
+    ];
+
+    /// The scope whose [`as_str`](Self::as_str) name is `name`.
+    pub fn from_name(name: &str) -> Option<ScopeId> {
+        Self::ALL.into_iter().find(|scope| scope.as_str() == name)
+    }
+
     /// Return a readable name for this scope
     /// (used in theme mapping).
     pub fn as_str(&self) -> &'static str {
@@ -124,6 +148,14 @@ mod tests {
         assert_eq!(ScopeId::Plain.as_str(), "plain");
     }
 
+    #[test]
+    fn scope_from_name_round_trips() {
+        for scope in ScopeId::ALL {
+            assert_eq!(ScopeId::from_name(scope.as_str()), Some(scope));
+        }
+        assert_eq!(ScopeId::from_name("Keyword"), None);
+    }
+
     #[test]
     fn scope_clone_and_eq() {
         let a = ScopeId::Keyword;
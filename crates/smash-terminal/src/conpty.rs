@@ -0,0 +1,485 @@
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle};
+use std::path::Path;
+
+use windows_sys::Win32::Foundation::{HANDLE, S_OK, WAIT_OBJECT_0};
+use windows_sys::Win32::System::Console::{
+    ClosePseudoConsole, CreatePseudoConsole, ResizePseudoConsole, COORD, HPCON,
+};
+use windows_sys::Win32::System::Pipes::{CreatePipe, PeekNamedPipe};
+use windows_sys::Win32::System::Threading::{
+    CreateProcessW, DeleteProcThreadAttributeList, GetExitCodeProcess,
+    InitializeProcThreadAttributeList, TerminateProcess, UpdateProcThreadAttribute,
+    WaitForSingleObject, EXTENDED_STARTUPINFO_PRESENT, PROCESS_INFORMATION,
+    PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE, STARTUPINFOEXW,
+};
+
+use crate::error::{TerminalError, TerminalResult};
+use crate::grid::TerminalSize;
+use crate::log_target;
+use crate::process::Signal;
+use crate::pty::Pty;
+
+/// A Windows pseudo console (ConPTY) running a child process.
+///
+/// Output is read through an anonymous pipe; [`Pty::read`] only takes
+/// what the pipe already holds, so it never blocks.
+#[derive(Debug)]
+pub struct ConPty {
+    console: HPCON,
+    /// Our end of the console's input pipe; `None` once closed.
+    input: Option<File>,
+    /// Our end of the console's output pipe; `None` once closed.
+    output: Option<File>,
+    process: OwnedHandle,
+    pid: u32,
+    exit: Cell<Option<i32>>,
+}
+
+// SAFETY: the pseudo console and process handles are process-wide kernel
+// handles, usable from any thread; `ConPty` is only ever used by its owner.
+unsafe impl Send for ConPty {}
+
+impl ConPty {
+    /// Spawn `program` with `args` attached to a new pseudo console of
+    /// `size`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TerminalError::PtyFailed` if the pseudo console cannot be
+    /// created and `TerminalError::ShellSpawnFailed` if the process
+    /// cannot start.
+    pub fn spawn(program: &Path, args: &[&str], size: TerminalSize) -> TerminalResult<Self> {
+        // The console reads `pty_in` and writes `pty_out`; we hold the
+        // other ends.
+        let (pty_in, input) = pipe()?;
+        let (output, pty_out) = pipe()?;
+        // SAFETY: a handle value; zero means none yet.
+        let mut console: HPCON = unsafe { std::mem::zeroed() };
+        // SAFETY: both handles are valid pipe ends; the console duplicates
+        // them, so ours can be dropped afterwards.
+        let hr = unsafe {
+            CreatePseudoConsole(
+                coord(size),
+                pty_in.as_raw_handle() as HANDLE,
+                pty_out.as_raw_handle() as HANDLE,
+                0,
+                &mut console,
+            )
+        };
+        drop((pty_in, pty_out));
+        if hr != S_OK {
+            return Err(TerminalError::PtyFailed(format!(
+                "CreatePseudoConsole failed: {:#x}",
+                hr
+            )));
+        }
+
+        let info = match start_process(console, program, args) {
+            Ok(info) => info,
+            Err(e) => {
+                // SAFETY: the console was created above and is not used again.
+                unsafe { ClosePseudoConsole(console) };
+                return Err(e);
+            }
+        };
+        // SAFETY: CreateProcessW returned these handles to us to own.
+        let process = unsafe { OwnedHandle::from_raw_handle(info.hProcess as _) };
+        drop(unsafe { OwnedHandle::from_raw_handle(info.hThread as _) });
+        tracing::info!(
+            target: log_target::PTY,
+            "spawned {} (pid {}) on a pseudo console at {}x{}",
+            program.display(),
+            info.dwProcessId,
+            size.cols,
+            size.rows
+        );
+
+        Ok(Self {
+            console,
+            input: Some(File::from(input)),
+            output: Some(File::from(output)),
+            process,
+            pid: info.dwProcessId,
+            exit: Cell::new(None),
+        })
+    }
+
+    /// Process id of the child.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn poll_exit(&self) -> Option<i32> {
+        if self.exit.get().is_none() {
+            let handle = self.process.as_raw_handle() as HANDLE;
+            let mut code = 0u32;
+            // SAFETY: `handle` is our open process handle.
+            let exited = unsafe {
+                WaitForSingleObject(handle, 0) == WAIT_OBJECT_0
+                    && GetExitCodeProcess(handle, &mut code) != 0
+            };
+            if exited {
+                self.exit.set(Some(code as i32));
+                tracing::info!(target: log_target::PTY, "child exited with {}", code);
+            }
+        }
+        self.exit.get()
+    }
+
+    /// Bytes waiting in the output pipe.
+    fn available(&self) -> Option<u32> {
+        let output = self.output.as_ref()?;
+        let mut avail = 0u32;
+        // SAFETY: peeking with no buffer only reports the byte count.
+        let ok = unsafe {
+            PeekNamedPipe(
+                output.as_raw_handle() as HANDLE,
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+                &mut avail,
+                std::ptr::null_mut(),
+            )
+        };
+        (ok != 0).then_some(avail)
+    }
+
+    fn kill(&self, code: i32) -> TerminalResult<()> {
+        // SAFETY: plain call on our own process handle.
+        if unsafe { TerminateProcess(self.process.as_raw_handle() as HANDLE, code as u32) } == 0 {
+            return Err(TerminalError::Io(
+                std::io::Error::last_os_error().to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Pty for ConPty {
+    fn write(&mut self, data: &[u8]) -> TerminalResult<()> {
+        if self.poll_exit().is_some() {
+            return Err(TerminalError::Io("PTY is closed".to_string()));
+        }
+        tracing::trace!(target: log_target::PTY, "write {} bytes", data.len());
+        let input = self
+            .input
+            .as_mut()
+            .ok_or_else(|| TerminalError::Io("PTY is closed".to_string()))?;
+        input
+            .write_all(data)
+            .map_err(|e| TerminalError::Io(e.to_string()))
+    }
+
+    fn read(&mut self, max: usize) -> TerminalResult<Vec<u8>> {
+        let avail = match self.available() {
+            Some(n) => n as usize,
+            // The pipe breaks once the console has gone away.
+            None if self.poll_exit().is_some() => {
+                return Err(TerminalError::Io("PTY is closed".to_string()));
+            }
+            None => 0,
+        };
+        let want = avail.min(max);
+        let mut out = vec![0u8; want];
+        let mut filled = 0;
+        if let Some(output) = self.output.as_mut() {
+            while filled < want {
+                match output.read(&mut out[filled..]) {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(_) => break,
+                }
+            }
+        }
+        out.truncate(filled);
+        if !out.is_empty() {
+            tracing::trace!(target: log_target::PTY, "read {} bytes", out.len());
+        }
+        Ok(out)
+    }
+
+    fn resize(&mut self, size: TerminalSize) -> TerminalResult<()> {
+        tracing::debug!(
+            target: log_target::PTY,
+            "resize to {}x{}",
+            size.cols,
+            size.rows
+        );
+        // SAFETY: the console stays open until `close`.
+        let hr = unsafe { ResizePseudoConsole(self.console, coord(size)) };
+        if hr != S_OK {
+            return Err(TerminalError::ResizeFailed(format!(
+                "ResizePseudoConsole failed: {:#x}",
+                hr
+            )));
+        }
+        Ok(())
+    }
+
+    fn is_alive(&self) -> bool {
+        self.poll_exit().is_none()
+    }
+
+    fn exit_code(&self) -> Option<i32> {
+        self.poll_exit()
+    }
+
+    /// Windows has no signals. `Interrupt` types Ctrl+C into the console,
+    /// which delivers it to the foreground program; the others end the
+    /// child with exit code `128 + signal`, as a shell would report it.
+    fn signal(&mut self, signal: Signal) -> TerminalResult<()> {
+        if let Some(code) = self.poll_exit() {
+            return Err(TerminalError::Exited(code));
+        }
+        tracing::debug!(
+            target: log_target::PTY,
+            "sending {} to pid {}",
+            signal.name(),
+            self.pid
+        );
+        match signal {
+            Signal::Interrupt => self.write(b"\x03"),
+            Signal::Terminate | Signal::Kill => self.kill(128 + signal.number()),
+        }
+    }
+
+    fn close(&mut self) -> TerminalResult<()> {
+        if self.output.is_none() {
+            return Ok(());
+        }
+        tracing::debug!(target: log_target::PTY, "closing pid {}", self.pid);
+        // Drop our pipe ends first: closing the console can otherwise
+        // wait for its unread output to drain.
+        self.input = None;
+        self.output = None;
+        // SAFETY: the console is open and not used after this.
+        unsafe { ClosePseudoConsole(self.console) };
+        if self.poll_exit().is_none() {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            if self.poll_exit().is_none() {
+                self.kill(1)?;
+                // SAFETY: waits on our own process handle.
+                unsafe { WaitForSingleObject(self.process.as_raw_handle() as HANDLE, 1000) };
+            }
+        }
+        if self.poll_exit().is_none() {
+            self.exit.set(Some(-1));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ConPty {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+fn coord(size: TerminalSize) -> COORD {
+    COORD {
+        X: size.cols.min(i16::MAX as u16) as i16,
+        Y: size.rows.min(i16::MAX as u16) as i16,
+    }
+}
+
+/// An anonymous pipe as (read end, write end).
+fn pipe() -> TerminalResult<(OwnedHandle, OwnedHandle)> {
+    let mut read: HANDLE = std::ptr::null_mut();
+    let mut write: HANDLE = std::ptr::null_mut();
+    // SAFETY: out-pointers to locals; no security attributes.
+    if unsafe { CreatePipe(&mut read, &mut write, std::ptr::null(), 0) } == 0 {
+        return Err(TerminalError::PtyFailed(format!(
+            "CreatePipe failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    // SAFETY: CreatePipe returned two fresh handles for us to own.
+    unsafe {
+        Ok((
+            OwnedHandle::from_raw_handle(read as _),
+            OwnedHandle::from_raw_handle(write as _),
+        ))
+    }
+}
+
+/// Start `program` attached to `console`.
+fn start_process(
+    console: HPCON,
+    program: &Path,
+    args: &[&str],
+) -> TerminalResult<PROCESS_INFORMATION> {
+    let mut size = 0usize;
+    // SAFETY: the first call only reports the size the list needs.
+    unsafe { InitializeProcThreadAttributeList(std::ptr::null_mut(), 1, 0, &mut size) };
+    let mut list = vec![0u8; size];
+    let attrs = list.as_mut_ptr().cast();
+    let last_err = || TerminalError::PtyFailed(std::io::Error::last_os_error().to_string());
+    // SAFETY: `list` is `size` bytes, as asked for above, and outlives
+    // every use of `attrs` below.
+    unsafe {
+        if InitializeProcThreadAttributeList(attrs, 1, 0, &mut size) == 0 {
+            return Err(last_err());
+        }
+        let ok = UpdateProcThreadAttribute(
+            attrs,
+            0,
+            PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE as usize,
+            console as _,
+            std::mem::size_of::<HPCON>(),
+            std::ptr::null_mut(),
+            std::ptr::null(),
+        );
+        if ok == 0 {
+            DeleteProcThreadAttributeList(attrs);
+            return Err(last_err());
+        }
+    }
+
+    // SAFETY: plain C structs, for which all zeroes is the empty value.
+    let mut startup: STARTUPINFOEXW = unsafe { std::mem::zeroed() };
+    startup.StartupInfo.cb = std::mem::size_of::<STARTUPINFOEXW>() as u32;
+    startup.lpAttributeList = attrs;
+    let mut cmdline = command_line(program, args);
+    let mut info: PROCESS_INFORMATION = unsafe { std::mem::zeroed() };
+    // SAFETY: every pointer refers to a live local; `cmdline` is a
+    // mutable, NUL-terminated buffer as CreateProcessW requires.
+    let ok = unsafe {
+        CreateProcessW(
+            std::ptr::null(),
+            cmdline.as_mut_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            0,
+            EXTENDED_STARTUPINFO_PRESENT,
+            std::ptr::null(),
+            std::ptr::null(),
+            &startup.StartupInfo,
+            &mut info,
+        )
+    };
+    let err = std::io::Error::last_os_error();
+    // SAFETY: initialized above and no longer needed.
+    unsafe { DeleteProcThreadAttributeList(attrs) };
+    if ok == 0 {
+        return Err(TerminalError::ShellSpawnFailed(err));
+    }
+    Ok(info)
+}
+
+/// NUL-terminated UTF-16 command line, quoting each part the way the
+/// C runtime splits it back into arguments.
+fn command_line(program: &Path, args: &[&str]) -> Vec<u16> {
+    let mut line = String::new();
+    let program = program.to_string_lossy();
+    for (i, arg) in std::iter::once(program.as_ref())
+        .chain(args.iter().copied())
+        .enumerate()
+    {
+        if i > 0 {
+            line.push(' ');
+        }
+        quote_arg(arg, &mut line);
+    }
+    OsStr::new(&line)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect()
+}
+
+fn quote_arg(arg: &str, out: &mut String) {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        out.push_str(arg);
+        return;
+    }
+    out.push('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Escape the backslashes before a quote, then the quote.
+                out.push_str(&"\\".repeat(backslashes * 2 + 1));
+                out.push('"');
+                backslashes = 0;
+                continue;
+            }
+            _ => {}
+        }
+        if c != '\\' {
+            out.push_str(&"\\".repeat(backslashes));
+            backslashes = 0;
+            out.push(c);
+        }
+    }
+    // Backslashes before the closing quote are doubled too.
+    out.push_str(&"\\".repeat(backslashes * 2));
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn read_until(pty: &mut ConPty, needle: &str) -> String {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut out = String::new();
+        while !out.contains(needle) {
+            assert!(Instant::now() < deadline, "timed out; got {:?}", out);
+            if let Ok(data) = pty.read(usize::MAX) {
+                out.push_str(&String::from_utf8_lossy(&data));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        out
+    }
+
+    #[test]
+    fn quotes_arguments_like_the_c_runtime() {
+        let line = |args: &[&str]| {
+            let wide = command_line(Path::new(r"C:\Program Files\x.exe"), args);
+            String::from_utf16(&wide[..wide.len() - 1]).unwrap()
+        };
+        assert_eq!(line(&[]), r#""C:\Program Files\x.exe""#);
+        assert_eq!(
+            line(&["/c", "echo hi", "", r#"a"b"#, r"dir\"]),
+            r#""C:\Program Files\x.exe" /c "echo hi" "" "a\"b" dir\"#
+        );
+        assert_eq!(line(&[r"x y\"]), r#""C:\Program Files\x.exe" "x y\\""#);
+    }
+
+    #[test]
+    fn runs_command_and_reports_exit() {
+        let size = TerminalSize::new(80, 24);
+        let mut pty = ConPty::spawn(Path::new("cmd.exe"), &["/c", "echo hello& exit 3"], size)
+            .expect("spawn");
+        read_until(&mut pty, "hello");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while pty.is_alive() {
+            assert!(Instant::now() < deadline);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(pty.exit_code(), Some(3));
+    }
+
+    #[test]
+    fn kill_ends_child_and_resize_works() {
+        let size = TerminalSize::new(80, 24);
+        let mut pty = ConPty::spawn(Path::new("cmd.exe"), &[], size).expect("spawn");
+        pty.resize(TerminalSize::new(100, 30)).expect("resize");
+        pty.signal(Signal::Kill).expect("kill");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while pty.is_alive() {
+            assert!(Instant::now() < deadline, "child survived kill");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(pty.exit_code(), Some(137));
+        assert!(pty.write(b"x").is_err());
+    }
+}
@@ -96,6 +96,9 @@ pub struct TerminalGrid {
     pub current_bg: Color,
     /// Title set by OSC sequences.
     pub title: Option<String>,
+    /// Rows of the visible screen changed since the last
+    /// [`TerminalGrid::clear_dirty`].
+    dirty: Vec<bool>,
 }
 
 impl TerminalGrid {
@@ -119,6 +122,7 @@ impl TerminalGrid {
             current_fg: Color::Default,
             current_bg: Color::Default,
             title: None,
+            dirty: vec![true; rows as usize],
         }
     }
 
@@ -168,6 +172,38 @@ impl TerminalGrid {
         if row < rows && col < cols {
             let buf = self.active_buffer_mut();
             buf[row as usize][col as usize] = cell;
+            self.mark_rows_dirty(row as usize..row as usize + 1);
+        }
+    }
+
+    /// Whether `row` changed since the last [`TerminalGrid::clear_dirty`].
+    pub fn is_row_dirty(&self, row: u16) -> bool {
+        self.dirty.get(row as usize).copied().unwrap_or(false)
+    }
+
+    /// Rows changed since the last [`TerminalGrid::clear_dirty`], in order.
+    pub fn dirty_rows(&self) -> impl Iterator<Item = u16> + '_ {
+        self.dirty
+            .iter()
+            .enumerate()
+            .filter(|(_, dirty)| **dirty)
+            .map(|(row, _)| row as u16)
+    }
+
+    /// Mark every row as changed, e.g. when the screen must be repainted.
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.fill(true);
+    }
+
+    /// Forget changes once they have been painted.
+    pub fn clear_dirty(&mut self) {
+        self.dirty.fill(false);
+    }
+
+    fn mark_rows_dirty(&mut self, rows: std::ops::Range<usize>) {
+        let end = rows.end.min(self.dirty.len());
+        for dirty in &mut self.dirty[rows.start.min(end)..end] {
+            *dirty = true;
         }
     }
 
@@ -270,6 +306,9 @@ impl TerminalGrid {
                 buf.insert(bottom, blank_row);
             }
         }
+        if n > 0 {
+            self.mark_rows_dirty(top..bottom + 1);
+        }
     }
 
     /// Scroll the scroll region down by n lines.
@@ -292,6 +331,9 @@ impl TerminalGrid {
                 buf.insert(top, blank_row);
             }
         }
+        if n > 0 {
+            self.mark_rows_dirty(top..bottom + 1);
+        }
     }
 
     /// Set the scroll region. Values are 0-based and inclusive.
@@ -309,6 +351,7 @@ impl TerminalGrid {
         let cols = self.size.cols;
         let rows = self.size.rows;
         *self.active_buffer_mut() = Self::create_buffer(cols, rows);
+        self.mark_all_dirty();
     }
 
     /// Erase display variants — 0: below cursor, 1: above cursor, 2: entire display.
@@ -333,6 +376,7 @@ impl TerminalGrid {
                         *cell = TerminalCell::default();
                     }
                 }
+                self.mark_rows_dirty(row..rows);
             }
             1 => {
                 // Erase from start to cursor
@@ -353,6 +397,7 @@ impl TerminalGrid {
                 {
                     *cell = TerminalCell::default();
                 }
+                self.mark_rows_dirty(0..row + 1);
             }
             2 | 3 => {
                 self.erase_display();
@@ -396,8 +441,9 @@ impl TerminalGrid {
                     *cell = TerminalCell::default();
                 }
             }
-            _ => {}
+            _ => return,
         }
+        self.mark_rows_dirty(row..row + 1);
     }
 
     /// Switch to the alternate screen buffer.
@@ -415,6 +461,7 @@ impl TerminalGrid {
         if self.using_alternate {
             self.using_alternate = false;
             self.cursor = self.saved_cursor;
+            self.mark_all_dirty();
         }
     }
 
@@ -439,12 +486,21 @@ impl TerminalGrid {
         self.scroll_region_top = 0;
         self.scroll_region_bottom = new_rows.saturating_sub(1);
 
-        // For simplicity, just create new buffers — real implementation would reflow.
-        self.primary = Self::create_buffer(new_cols, new_rows);
-        self.alternate = Self::create_buffer(new_cols, new_rows);
+        // Keep existing content (no reflow). When shrinking, drop rows from
+        // the top so the cursor line stays on screen.
+        let drop = (self.cursor.row + 1).saturating_sub(new_rows);
+        for buf in [&mut self.primary, &mut self.alternate] {
+            buf.drain(..(drop as usize).min(buf.len()));
+            buf.resize_with(new_rows as usize, Vec::new);
+            for row in buf.iter_mut() {
+                row.resize(new_cols as usize, TerminalCell::default());
+            }
+        }
+
+        self.dirty = vec![true; new_rows as usize];
 
         // Clamp cursor
-        self.cursor.row = self.cursor.row.min(new_rows.saturating_sub(1));
+        self.cursor.row = (self.cursor.row - drop).min(new_rows.saturating_sub(1));
         self.cursor.col = self.cursor.col.min(new_cols.saturating_sub(1));
     }
 
@@ -704,4 +760,55 @@ mod tests {
         assert_eq!(grid.get_cell(0, 2).unwrap().character, ' ');
         assert_eq!(grid.get_cell(0, 3).unwrap().character, ' ');
     }
+
+    #[test]
+    fn grid_resize_keeps_content_and_cursor_line() {
+        let mut grid = TerminalGrid::new(10, 4);
+        for (row, ch) in ['a', 'b', 'c', 'd'].into_iter().enumerate() {
+            grid.cursor_set_position(row as u16, 0);
+            grid.write_char(ch);
+        }
+        grid.cursor_set_position(3, 1);
+
+        grid.resize(20, 6);
+        assert_eq!(grid.row_text(0).trim_end(), "a");
+        assert_eq!(grid.row_text(5).len(), 20);
+        assert_eq!(grid.cursor, CursorPosition { row: 3, col: 1 });
+
+        grid.resize(5, 2);
+        assert_eq!(grid.row_text(0).trim_end(), "c");
+        assert_eq!(grid.row_text(1).trim_end(), "d");
+        assert_eq!(grid.cursor, CursorPosition { row: 1, col: 1 });
+    }
+
+    #[test]
+    fn grid_tracks_dirty_rows() {
+        let mut grid = TerminalGrid::new(4, 4);
+        assert_eq!(grid.dirty_rows().count(), 4);
+        grid.clear_dirty();
+        assert_eq!(grid.dirty_rows().count(), 0);
+
+        grid.cursor_set_position(1, 0);
+        grid.write_char('a');
+        assert_eq!(grid.dirty_rows().collect::<Vec<_>>(), vec![1]);
+        // Cursor movement alone changes no cells.
+        grid.clear_dirty();
+        grid.cursor_set_position(3, 2);
+        assert!(!grid.is_row_dirty(3));
+
+        grid.erase_line_variant(0);
+        assert_eq!(grid.dirty_rows().collect::<Vec<_>>(), vec![3]);
+        grid.clear_dirty();
+
+        grid.set_scroll_region(1, 2);
+        grid.scroll_up(1);
+        assert_eq!(grid.dirty_rows().collect::<Vec<_>>(), vec![1, 2]);
+        grid.clear_dirty();
+
+        grid.enter_alternate_screen();
+        assert_eq!(grid.dirty_rows().count(), 4);
+        grid.clear_dirty();
+        grid.resize(4, 2);
+        assert_eq!(grid.dirty_rows().collect::<Vec<_>>(), vec![0, 1]);
+    }
 }
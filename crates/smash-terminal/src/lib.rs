@@ -1,15 +1,28 @@
+#[cfg(windows)]
+pub mod conpty;
 pub mod error;
 pub mod grid;
 pub mod hyperlink;
 pub mod input;
+pub mod log_target;
+pub mod output_buffer;
 pub mod pane;
 pub mod parser;
+pub mod process;
 pub mod pty;
+#[cfg(unix)]
+pub mod unix_pty;
 
+#[cfg(windows)]
+pub use conpty::ConPty;
 pub use error::{TerminalError, TerminalResult};
 pub use grid::{CellAttributes, Color, CursorPosition, TerminalCell, TerminalGrid, TerminalSize};
 pub use hyperlink::{DetectedLink, HyperlinkDetector};
 pub use input::key_to_escape_sequence;
+pub use output_buffer::OutputBuffer;
 pub use pane::TerminalPane;
 pub use parser::{TerminalEvent, VtParser};
-pub use pty::{MockPty, Pty};
+pub use process::{ProcessStatus, Signal};
+pub use pty::{spawn_native, MockPty, Pty};
+#[cfg(unix)]
+pub use unix_pty::UnixPty;
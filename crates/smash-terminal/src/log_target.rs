@@ -0,0 +1,9 @@
+//! `tracing` targets for the terminal subsystems.
+//!
+//! Each target can be filtered on its own, e.g. `smash_terminal::pty=trace`.
+
+/// Shell process spawn, resize, I/O, and exit.
+pub const PTY: &str = "smash_terminal::pty";
+
+/// Escape-sequence parsing.
+pub const VT: &str = "smash_terminal::vt";
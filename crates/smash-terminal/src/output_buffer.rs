@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+/// Bounded FIFO of PTY output waiting to be parsed.
+///
+/// The reader only takes as much from the PTY as there is [`room`] for, so
+/// a full buffer pauses reading and the child blocks on its writes. When
+/// the parser falls a whole buffer behind, [`skip_to_tail`] drops the
+/// backlog so the screen catches up with the latest output.
+///
+/// [`room`]: OutputBuffer::room
+/// [`skip_to_tail`]: OutputBuffer::skip_to_tail
+#[derive(Debug, Clone)]
+pub struct OutputBuffer {
+    data: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl OutputBuffer {
+    /// An empty buffer holding at most `capacity` bytes.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            data: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Bytes that can be added before the buffer is full.
+    pub fn room(&self) -> usize {
+        self.capacity - self.data.len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.room() == 0
+    }
+
+    /// Append as much of `bytes` as fits. Returns the number of bytes
+    /// that did not.
+    pub fn push(&mut self, bytes: &[u8]) -> usize {
+        let fits = bytes.len().min(self.room());
+        self.data.extend(&bytes[..fits]);
+        bytes.len() - fits
+    }
+
+    /// Remove and return up to `max` bytes from the front.
+    pub fn take(&mut self, max: usize) -> Vec<u8> {
+        let n = max.min(self.data.len());
+        self.data.drain(..n).collect()
+    }
+
+    /// Drop everything but roughly the last `keep` bytes, starting the
+    /// kept part at a line boundary when there is one so the parser
+    /// resumes on a fresh line. Returns the number of bytes dropped.
+    pub fn skip_to_tail(&mut self, keep: usize) -> usize {
+        let mut start = self.data.len().saturating_sub(keep);
+        if let Some(newline) = self.data.range(start..).position(|&b| b == b'\n') {
+            start += newline + 1;
+        }
+        self.data.drain(..start);
+        start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_stops_at_capacity() {
+        let mut buf = OutputBuffer::new(4);
+        assert_eq!(buf.push(b"abc"), 0);
+        assert_eq!(buf.room(), 1);
+        assert_eq!(buf.push(b"def"), 2);
+        assert!(buf.is_full());
+        assert_eq!(buf.take(2), b"ab");
+        assert_eq!(buf.take(10), b"cd");
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn skip_to_tail_resumes_after_a_newline() {
+        let mut buf = OutputBuffer::new(64);
+        buf.push(b"line one\nline two\nline three\n");
+        let dropped = buf.skip_to_tail(15);
+        assert_eq!(dropped, 18);
+        assert_eq!(buf.take(64), b"line three\n");
+
+        // No newline in the tail: keep exactly `keep` bytes.
+        buf.push(b"yyyyyyyy");
+        assert_eq!(buf.skip_to_tail(3), 5);
+        assert_eq!(buf.len(), 3);
+    }
+}
@@ -2,10 +2,23 @@ use crate::error::TerminalResult;
 use crate::grid::{TerminalGrid, TerminalSize};
 use crate::hyperlink::{DetectedLink, HyperlinkDetector};
 use crate::input::key_to_escape_sequence;
+use crate::log_target;
+use crate::output_buffer::OutputBuffer;
 use crate::parser::{TerminalEvent, VtParser};
+use crate::process::{ProcessStatus, Signal};
 use crate::pty::Pty;
 use smash_input::KeyEvent;
 
+/// Most PTY output held waiting for the parser.
+const OUTPUT_BUFFER_BYTES: usize = 1 << 20;
+
+/// Most output parsed per [`TerminalPane::process_output`] call, so one
+/// burst cannot hold up a frame.
+const PARSE_BUDGET: usize = 256 * 1024;
+
+/// Output kept when a full backlog is skipped.
+const SKIP_KEEP_BYTES: usize = 16 * 1024;
+
 /// A terminal pane that combines a PTY, VT parser, grid, and hyperlink detector.
 #[derive(Debug)]
 pub struct TerminalPane {
@@ -17,6 +30,14 @@ pub struct TerminalPane {
     parser: VtParser,
     /// Hyperlink detector.
     link_detector: HyperlinkDetector,
+    /// Whether the grid changed since the last [`TerminalPane::take_dirty`].
+    dirty: bool,
+    /// Output read from the PTY but not parsed yet.
+    pending: OutputBuffer,
+    /// Bytes dropped since the last [`TerminalPane::take_skipped`].
+    skipped: usize,
+    /// Whether [`TerminalPane::take_exit`] has reported the exit.
+    exit_reported: bool,
 }
 
 impl TerminalPane {
@@ -27,6 +48,10 @@ impl TerminalPane {
             grid: TerminalGrid::new(size.cols, size.rows),
             parser: VtParser::new(),
             link_detector: HyperlinkDetector::new(),
+            dirty: false,
+            pending: OutputBuffer::new(OUTPUT_BUFFER_BYTES),
+            skipped: 0,
+            exit_reported: false,
         }
     }
 
@@ -46,15 +71,56 @@ impl TerminalPane {
 
     /// Read output from the PTY, parse it, and update the grid.
     /// Returns any terminal events generated during parsing.
+    ///
+    /// Reading stops while the output buffer is full, which blocks the
+    /// child until the parser catches up, and each call parses a bounded
+    /// amount. If the buffer fills anyway, the backlog is skipped (see
+    /// [`TerminalPane::take_skipped`]) so the screen shows recent output.
     pub fn process_output(&mut self) -> TerminalResult<Vec<TerminalEvent>> {
-        let data = self.pty.read()?;
-        if data.is_empty() {
+        // A closed PTY is reported by the read below.
+        let _ = self.pty.flush_input();
+        if !self.pending.is_full() {
+            match self.pty.read(self.pending.room()) {
+                Ok(data) => {
+                    self.pending.push(&data);
+                }
+                // Parse what is left before reporting the closed PTY.
+                Err(e) if self.pending.is_empty() => return Err(e),
+                Err(_) => {}
+            }
+        }
+        if self.pending.is_full() {
+            let skipped = self.pending.skip_to_tail(SKIP_KEEP_BYTES);
+            self.skipped += skipped;
+            // The cut may fall inside an escape sequence.
+            self.parser = VtParser::new();
+            tracing::debug!(target: log_target::PTY, "output suppressed, {} bytes skipped", skipped);
+        }
+        if self.pending.is_empty() {
             return Ok(Vec::new());
         }
+        let data = self.pending.take(PARSE_BUDGET);
         let events = self.parser.process(&data, &mut self.grid);
+        self.dirty = true;
         Ok(events)
     }
 
+    /// Whether output is still waiting to be parsed, so the caller should
+    /// call [`TerminalPane::process_output`] again soon.
+    pub fn has_pending_output(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Bytes of output dropped since the last call, and reset.
+    pub fn take_skipped(&mut self) -> usize {
+        std::mem::take(&mut self.skipped)
+    }
+
+    /// Return whether the grid changed since the last call, and reset.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
     /// Get a reference to the terminal grid.
     pub fn grid(&self) -> &TerminalGrid {
         &self.grid
@@ -69,6 +135,7 @@ impl TerminalPane {
     pub fn resize(&mut self, size: TerminalSize) -> TerminalResult<()> {
         self.pty.resize(size)?;
         self.grid.resize(size.cols, size.rows);
+        self.dirty = true;
         Ok(())
     }
 
@@ -87,11 +154,57 @@ impl TerminalPane {
         self.pty.exit_code()
     }
 
+    /// Whether the process is running, or how it exited.
+    pub fn status(&self) -> ProcessStatus {
+        match self.pty.exit_code() {
+            Some(code) => ProcessStatus::Exited(code),
+            None if self.pty.is_alive() => ProcessStatus::Running,
+            None => ProcessStatus::Exited(-1),
+        }
+    }
+
+    /// The exit code the first time the process is seen to have exited,
+    /// then `None`.
+    pub fn take_exit(&mut self) -> Option<i32> {
+        if self.exit_reported {
+            return None;
+        }
+        match self.status() {
+            ProcessStatus::Exited(code) => {
+                self.exit_reported = true;
+                Some(code)
+            }
+            ProcessStatus::Running => None,
+        }
+    }
+
+    /// Send `signal` to the process.
+    pub fn signal(&mut self, signal: Signal) -> TerminalResult<()> {
+        self.pty.signal(signal)
+    }
+
     /// Close the terminal (terminate the PTY process).
     pub fn close(&mut self) -> TerminalResult<()> {
         self.pty.close()
     }
 
+    /// Replace the process with a fresh one on `pty`, closing the old one
+    /// if it is still running. The screen starts blank at the current size.
+    pub fn respawn(&mut self, pty: Box<dyn Pty>) -> TerminalResult<()> {
+        let size = self.grid.size();
+        let mut old = std::mem::replace(&mut self.pty, pty);
+        old.close()?;
+        self.pty.resize(size)?;
+        self.grid = TerminalGrid::new(size.cols, size.rows);
+        self.parser = VtParser::new();
+        self.pending = OutputBuffer::new(OUTPUT_BUFFER_BYTES);
+        self.skipped = 0;
+        self.exit_reported = false;
+        self.dirty = true;
+        tracing::info!(target: log_target::PTY, "terminal respawned");
+        Ok(())
+    }
+
     /// Get the current title, if set by an OSC sequence.
     pub fn title(&self) -> Option<&str> {
         self.grid.title.as_deref()
@@ -146,6 +259,22 @@ mod tests {
         assert_eq!(pane.grid().get_cell(0, 4).unwrap().character, 'o');
     }
 
+    #[test]
+    fn pane_output_marks_dirty() {
+        let size = TerminalSize::new(80, 24);
+        let mut mock = MockPty::new(size);
+        mock.set_read_data(b"Hello");
+        let mut pane = TerminalPane::new(Box::new(mock), size);
+        assert!(!pane.take_dirty());
+
+        pane.process_output().unwrap();
+        assert!(pane.take_dirty());
+        assert!(!pane.take_dirty());
+
+        pane.process_output().unwrap();
+        assert!(!pane.take_dirty());
+    }
+
     #[test]
     fn pane_process_output_with_title() {
         let size = TerminalSize::new(80, 24);
@@ -175,6 +304,44 @@ mod tests {
         assert_eq!(pane.exit_code(), Some(0));
     }
 
+    #[test]
+    fn pane_signal_reports_exit_once() {
+        let mut pane = create_test_pane(80, 24);
+        assert_eq!(pane.status(), ProcessStatus::Running);
+        assert_eq!(pane.take_exit(), None);
+
+        pane.signal(Signal::Interrupt).unwrap();
+        assert_eq!(pane.status(), ProcessStatus::Running);
+        pane.signal(Signal::Kill).unwrap();
+        assert_eq!(pane.status(), ProcessStatus::Exited(137));
+        assert_eq!(pane.take_exit(), Some(137));
+        assert_eq!(pane.take_exit(), None);
+        assert!(pane.signal(Signal::Terminate).is_err());
+    }
+
+    #[test]
+    fn pane_respawn_starts_blank() {
+        let size = TerminalSize::new(20, 4);
+        let mut mock = MockPty::new(size);
+        mock.set_read_data(b"old output");
+        mock.simulate_exit(1);
+        let mut pane = TerminalPane::new(Box::new(mock), size);
+        pane.process_output().unwrap();
+        assert_eq!(pane.take_exit(), Some(1));
+
+        let mut fresh = MockPty::new(TerminalSize::new(80, 24));
+        fresh.set_read_data(b"$ ");
+        pane.respawn(Box::new(fresh)).unwrap();
+        assert_eq!(pane.status(), ProcessStatus::Running);
+        assert_eq!(pane.grid().size(), size);
+        assert_eq!(pane.grid().row_text(0).trim_end(), "");
+        pane.process_output().unwrap();
+        assert_eq!(pane.grid().row_text(0).trim_end(), "$");
+
+        pane.signal(Signal::Terminate).unwrap();
+        assert_eq!(pane.take_exit(), Some(143));
+    }
+
     #[test]
     fn pane_detect_links() {
         let size = TerminalSize::new(80, 24);
@@ -188,6 +355,43 @@ mod tests {
         assert_eq!(links[0].uri, "https://example.com");
     }
 
+    #[test]
+    fn pane_output_is_parsed_in_bounded_chunks() {
+        let size = TerminalSize::new(80, 24);
+        let mut mock = MockPty::new(size);
+        mock.set_read_data(&vec![b'x'; PARSE_BUDGET + 10]);
+        let mut pane = TerminalPane::new(Box::new(mock), size);
+
+        pane.process_output().unwrap();
+        assert!(pane.has_pending_output());
+        pane.process_output().unwrap();
+        assert!(!pane.has_pending_output());
+        assert_eq!(pane.take_skipped(), 0);
+    }
+
+    #[test]
+    fn pane_skips_output_when_the_buffer_fills() {
+        let size = TerminalSize::new(20, 4);
+        let mut mock = MockPty::new(size);
+        let mut flood = b"noise\n".repeat(OUTPUT_BUFFER_BYTES / 6 + 100);
+        flood.extend_from_slice(b"\x1b[H\x1b[2Jdone");
+        mock.set_read_data(&flood);
+        let mut pane = TerminalPane::new(Box::new(mock), size);
+
+        // The first call fills the buffer and drops all but its tail;
+        // reading then continues with the rest of the flood.
+        pane.process_output().unwrap();
+        let skipped = pane.take_skipped();
+        assert!(skipped > OUTPUT_BUFFER_BYTES - SKIP_KEEP_BYTES - 6);
+        assert_eq!(pane.take_skipped(), 0);
+        while pane.has_pending_output() {
+            pane.process_output().unwrap();
+        }
+        pane.process_output().unwrap();
+        assert!(!pane.has_pending_output());
+        assert_eq!(pane.grid().row_text(0).trim_end(), "done");
+    }
+
     #[test]
     fn pane_empty_read() {
         let mut pane = create_test_pane(80, 24);
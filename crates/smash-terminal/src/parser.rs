@@ -1,4 +1,5 @@
 use crate::grid::TerminalGrid;
+use crate::log_target;
 
 /// Events generated during VT parsing.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -263,7 +264,7 @@ impl VtParser {
                     // Close hyperlink — handled at grid level
                 } else {
                     // Store for subsequent characters (simplified)
-                    tracing::debug!("OSC 8 hyperlink: {}", uri);
+                    tracing::debug!(target: log_target::VT, "OSC 8 hyperlink: {}", uri);
                 }
             }
         }
@@ -408,7 +409,11 @@ impl VtParser {
                 let _n = params.first().copied().unwrap_or(1).max(1);
             }
             _ => {
-                tracing::trace!("unhandled CSI final byte: {:?}", final_byte as char);
+                tracing::trace!(
+                    target: log_target::VT,
+                    "unhandled CSI final byte: {:?}",
+                    final_byte as char
+                );
             }
         }
     }
@@ -428,25 +433,21 @@ impl VtParser {
                     // Set foreground color
                     if i + 1 < params.len() {
                         match params[i + 1] {
-                            5 => {
-                                // 256 color: 38;5;n
-                                if i + 2 < params.len() {
-                                    let idx = params[i + 2] as u8;
-                                    grid.current_fg = crate::grid::Color::Indexed(idx);
-                                    i += 3;
-                                    continue;
-                                }
+                            // 256 color: 38;5;n
+                            5 if i + 2 < params.len() => {
+                                let idx = params[i + 2] as u8;
+                                grid.current_fg = crate::grid::Color::Indexed(idx);
+                                i += 3;
+                                continue;
                             }
-                            2 => {
-                                // RGB: 38;2;r;g;b
-                                if i + 4 < params.len() {
-                                    let r = params[i + 2] as u8;
-                                    let g = params[i + 3] as u8;
-                                    let b = params[i + 4] as u8;
-                                    grid.current_fg = crate::grid::Color::Rgb(r, g, b);
-                                    i += 5;
-                                    continue;
-                                }
+                            // RGB: 38;2;r;g;b
+                            2 if i + 4 < params.len() => {
+                                let r = params[i + 2] as u8;
+                                let g = params[i + 3] as u8;
+                                let b = params[i + 4] as u8;
+                                grid.current_fg = crate::grid::Color::Rgb(r, g, b);
+                                i += 5;
+                                continue;
                             }
                             _ => {}
                         }
@@ -457,23 +458,19 @@ impl VtParser {
                     // Set background color
                     if i + 1 < params.len() {
                         match params[i + 1] {
-                            5 => {
-                                if i + 2 < params.len() {
-                                    let idx = params[i + 2] as u8;
-                                    grid.current_bg = crate::grid::Color::Indexed(idx);
-                                    i += 3;
-                                    continue;
-                                }
+                            5 if i + 2 < params.len() => {
+                                let idx = params[i + 2] as u8;
+                                grid.current_bg = crate::grid::Color::Indexed(idx);
+                                i += 3;
+                                continue;
                             }
-                            2 => {
-                                if i + 4 < params.len() {
-                                    let r = params[i + 2] as u8;
-                                    let g = params[i + 3] as u8;
-                                    let b = params[i + 4] as u8;
-                                    grid.current_bg = crate::grid::Color::Rgb(r, g, b);
-                                    i += 5;
-                                    continue;
-                                }
+                            2 if i + 4 < params.len() => {
+                                let r = params[i + 2] as u8;
+                                let g = params[i + 3] as u8;
+                                let b = params[i + 4] as u8;
+                                grid.current_bg = crate::grid::Color::Rgb(r, g, b);
+                                i += 5;
+                                continue;
                             }
                             _ => {}
                         }
@@ -531,7 +528,7 @@ impl VtParser {
                 grid.current_bg = crate::grid::Color::Indexed(code as u8 - 100 + 8);
             }
             _ => {
-                tracing::trace!("unhandled SGR code: {}", code);
+                tracing::trace!(target: log_target::VT, "unhandled SGR code: {}", code);
             }
         }
     }
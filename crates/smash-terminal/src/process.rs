@@ -0,0 +1,78 @@
+//! Typed control of the process running in a terminal pane.
+
+use std::fmt;
+
+/// A signal that can be sent to the terminal's child process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// `SIGINT`, as sent by Ctrl+C.
+    Interrupt,
+    /// `SIGTERM`: ask the process to exit.
+    Terminate,
+    /// `SIGKILL`: end the process; it cannot be caught.
+    Kill,
+}
+
+impl Signal {
+    pub const ALL: [Signal; 3] = [Signal::Interrupt, Signal::Terminate, Signal::Kill];
+
+    /// Conventional name, e.g. `SIGINT`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Interrupt => "SIGINT",
+            Self::Terminate => "SIGTERM",
+            Self::Kill => "SIGKILL",
+        }
+    }
+
+    /// POSIX signal number.
+    pub fn number(self) -> i32 {
+        match self {
+            Self::Interrupt => 2,
+            Self::Terminate => 15,
+            Self::Kill => 9,
+        }
+    }
+
+    /// The signal behind a shell-style exit code (`128 + signal`).
+    pub fn from_exit_code(code: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| code == 128 + s.number())
+    }
+}
+
+/// Whether the terminal's child process is still running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Running,
+    /// Exited with this code; death by signal `n` is reported as `128 + n`.
+    Exited(i32),
+}
+
+impl fmt::Display for ProcessStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Running => write!(f, "running"),
+            Self::Exited(code) => match Signal::from_exit_code(*code) {
+                Some(signal) => write!(f, "exited with code {} ({})", code, signal.name()),
+                None => write!(f, "exited with code {}", code),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_display_names_the_signal() {
+        assert_eq!(ProcessStatus::Running.to_string(), "running");
+        assert_eq!(ProcessStatus::Exited(3).to_string(), "exited with code 3");
+        assert_eq!(
+            ProcessStatus::Exited(137).to_string(),
+            "exited with code 137 (SIGKILL)"
+        );
+        assert_eq!(Signal::from_exit_code(130), Some(Signal::Interrupt));
+        assert_eq!(Signal::from_exit_code(1), None);
+    }
+}
@@ -1,14 +1,26 @@
+use std::path::Path;
+
+use smash_platform::OsKind;
+
 use crate::error::{TerminalError, TerminalResult};
 use crate::grid::TerminalSize;
+use crate::process::Signal;
 
 /// Trait abstracting a PTY (pseudo-terminal) for testability.
 pub trait Pty: std::fmt::Debug + Send {
     /// Write data to the PTY input (stdin of the child process).
     fn write(&mut self, data: &[u8]) -> TerminalResult<()>;
 
-    /// Read available data from the PTY output (stdout of the child process).
-    /// Returns an empty vec if no data is available.
-    fn read(&mut self) -> TerminalResult<Vec<u8>>;
+    /// Send input that [`Pty::write`] could not hand over yet because the
+    /// child was not reading. Called on every output poll.
+    fn flush_input(&mut self) -> TerminalResult<()> {
+        Ok(())
+    }
+
+    /// Read up to `max` bytes of available PTY output (stdout of the
+    /// child process). Returns an empty vec if no data is available.
+    /// Anything beyond `max` stays queued in the PTY.
+    fn read(&mut self, max: usize) -> TerminalResult<Vec<u8>>;
 
     /// Resize the PTY to the given dimensions.
     fn resize(&mut self, size: TerminalSize) -> TerminalResult<()>;
@@ -19,17 +31,69 @@ pub trait Pty: std::fmt::Debug + Send {
     /// Get the exit code if the process has exited.
     fn exit_code(&self) -> Option<i32>;
 
+    /// Send `signal` to the child process.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TerminalError::Exited` if the process has already exited.
+    fn signal(&mut self, signal: Signal) -> TerminalResult<()>;
+
     /// Terminate the PTY and child process.
     fn close(&mut self) -> TerminalResult<()>;
 }
 
+/// Spawn `program` with `args` on the PTY backend for `os`: ConPTY on
+/// Windows, a Unix pseudo-terminal elsewhere.
+///
+/// # Errors
+///
+/// Returns `TerminalError::PtyFailed` if the backend for `os` is not
+/// built into this binary, or whatever error the backend reports.
+pub fn spawn_native(
+    os: &OsKind,
+    program: &Path,
+    args: &[&str],
+    size: TerminalSize,
+) -> TerminalResult<Box<dyn Pty>> {
+    match os {
+        OsKind::Windows => spawn_conpty(program, args, size),
+        OsKind::Linux | OsKind::MacOs | OsKind::Unknown => spawn_unix(program, args, size),
+    }
+}
+
+#[cfg(windows)]
+fn spawn_conpty(program: &Path, args: &[&str], size: TerminalSize) -> TerminalResult<Box<dyn Pty>> {
+    Ok(Box::new(crate::conpty::ConPty::spawn(program, args, size)?))
+}
+
+#[cfg(not(windows))]
+fn spawn_conpty(_: &Path, _: &[&str], _: TerminalSize) -> TerminalResult<Box<dyn Pty>> {
+    Err(TerminalError::PtyFailed(
+        "ConPTY is only available on Windows".to_string(),
+    ))
+}
+
+#[cfg(unix)]
+fn spawn_unix(program: &Path, args: &[&str], size: TerminalSize) -> TerminalResult<Box<dyn Pty>> {
+    Ok(Box::new(crate::unix_pty::UnixPty::spawn(
+        program, args, size,
+    )?))
+}
+
+#[cfg(not(unix))]
+fn spawn_unix(_: &Path, _: &[&str], _: TerminalSize) -> TerminalResult<Box<dyn Pty>> {
+    Err(TerminalError::PtyFailed(
+        "Unix pseudo-terminals are not available on this platform".to_string(),
+    ))
+}
+
 /// A mock PTY for testing purposes.
 /// Stores written data and allows setting read data.
 #[derive(Debug)]
 pub struct MockPty {
     /// Data that has been written to the PTY.
     pub written: Vec<u8>,
-    /// Data to be returned on the next read.
+    /// Data to be returned by the next reads.
     pub read_buffer: Vec<u8>,
     /// Current size.
     pub size: TerminalSize,
@@ -37,6 +101,8 @@ pub struct MockPty {
     pub alive: bool,
     /// Exit code (set when not alive).
     pub exit: Option<i32>,
+    /// Signals sent to the mock process, in order.
+    pub signals: Vec<Signal>,
 }
 
 impl MockPty {
@@ -48,6 +114,7 @@ impl MockPty {
             size,
             alive: true,
             exit: None,
+            signals: Vec::new(),
         }
     }
 
@@ -72,12 +139,12 @@ impl Pty for MockPty {
         Ok(())
     }
 
-    fn read(&mut self) -> TerminalResult<Vec<u8>> {
+    fn read(&mut self, max: usize) -> TerminalResult<Vec<u8>> {
         if !self.alive && self.read_buffer.is_empty() {
             return Err(TerminalError::Io("PTY is closed".to_string()));
         }
-        let data = std::mem::take(&mut self.read_buffer);
-        Ok(data)
+        let n = max.min(self.read_buffer.len());
+        Ok(self.read_buffer.drain(..n).collect())
     }
 
     fn resize(&mut self, size: TerminalSize) -> TerminalResult<()> {
@@ -93,6 +160,19 @@ impl Pty for MockPty {
         self.exit
     }
 
+    /// Records the signal. `Interrupt` leaves the process running; the
+    /// others end it as a real process would (`128 + signal`).
+    fn signal(&mut self, signal: Signal) -> TerminalResult<()> {
+        if let Some(code) = self.exit.filter(|_| !self.alive) {
+            return Err(TerminalError::Exited(code));
+        }
+        self.signals.push(signal);
+        if signal != Signal::Interrupt {
+            self.simulate_exit(128 + signal.number());
+        }
+        Ok(())
+    }
+
     fn close(&mut self) -> TerminalResult<()> {
         self.alive = false;
         self.exit = Some(0);
@@ -115,12 +195,17 @@ mod tests {
 
         // Set read data and read it back
         pty.set_read_data(b"world");
-        let data = pty.read().unwrap();
+        let data = pty.read(usize::MAX).unwrap();
         assert_eq!(data, b"world");
 
         // Second read returns empty (data was consumed)
-        let data = pty.read().unwrap();
+        let data = pty.read(usize::MAX).unwrap();
         assert!(data.is_empty());
+
+        // Reads stop at `max`, leaving the rest queued.
+        pty.set_read_data(b"abcdef");
+        assert_eq!(pty.read(4).unwrap(), b"abcd");
+        assert_eq!(pty.read(4).unwrap(), b"ef");
     }
 
     #[test]
@@ -157,6 +242,21 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn mock_pty_signals() {
+        let size = TerminalSize::new(80, 24);
+        let mut pty = MockPty::new(size);
+        pty.signal(Signal::Interrupt).unwrap();
+        assert!(pty.is_alive());
+        pty.signal(Signal::Terminate).unwrap();
+        assert_eq!(pty.exit_code(), Some(143));
+        assert!(matches!(
+            pty.signal(Signal::Kill),
+            Err(TerminalError::Exited(143))
+        ));
+        assert_eq!(pty.signals, vec![Signal::Interrupt, Signal::Terminate]);
+    }
+
     #[test]
     fn mock_pty_simulate_exit() {
         let size = TerminalSize::new(80, 24);
@@ -166,4 +266,28 @@ mod tests {
         assert!(!pty.is_alive());
         assert_eq!(pty.exit_code(), Some(42));
     }
+
+    #[test]
+    fn spawn_native_picks_the_backend_for_the_os() {
+        let size = TerminalSize::new(80, 24);
+        let other = if cfg!(windows) {
+            OsKind::Linux
+        } else {
+            OsKind::Windows
+        };
+        assert!(matches!(
+            spawn_native(&other, Path::new("sh"), &[], size),
+            Err(TerminalError::PtyFailed(_))
+        ));
+
+        let (shell, args): (&str, &[&str]) = if cfg!(windows) {
+            ("cmd.exe", &["/c", "exit 0"])
+        } else {
+            ("sh", &["-c", "exit 0"])
+        };
+        let os = smash_platform::SystemInfo::detect().os().clone();
+        let mut pty = spawn_native(&os, Path::new(shell), args, size).expect("spawn");
+        pty.close().expect("close");
+        assert!(!pty.is_alive());
+    }
 }
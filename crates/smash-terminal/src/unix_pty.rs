@@ -0,0 +1,409 @@
+use std::cell::{Cell, RefCell};
+use std::ffi::CStr;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+use crate::error::{TerminalError, TerminalResult};
+use crate::grid::TerminalSize;
+use crate::log_target;
+use crate::process::Signal;
+use crate::pty::Pty;
+
+/// A real pseudo-terminal running a child process (Unix only).
+///
+/// The master side is non-blocking, so [`Pty::read`] returns whatever
+/// output is available without waiting, and [`Pty::write`] keeps what the
+/// child is not ready to read for [`Pty::flush_input`].
+#[derive(Debug)]
+pub struct UnixPty {
+    master: File,
+    /// Input written but not yet accepted by the PTY.
+    pending_input: Vec<u8>,
+    child: RefCell<Child>,
+    exit: Cell<Option<i32>>,
+}
+
+impl UnixPty {
+    /// Spawn `program` with `args` attached to a new PTY of `size`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TerminalError::PtyFailed` if the PTY cannot be allocated
+    /// and `TerminalError::ShellSpawnFailed` if the process cannot start.
+    pub fn spawn(program: &Path, args: &[&str], size: TerminalSize) -> TerminalResult<Self> {
+        let (master, slave_path) = open_master()?;
+        set_winsize(&master, size)?;
+
+        let slave = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&slave_path)
+            .map_err(|e| TerminalError::PtyFailed(format!("open {}: {}", slave_path, e)))?;
+
+        let mut cmd = Command::new(program);
+        cmd.args(args)
+            .env("TERM", "xterm-256color")
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+        // SAFETY: only async-signal-safe calls between fork and exec.
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                if libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = cmd.spawn()?;
+        tracing::info!(
+            target: log_target::PTY,
+            "spawned {} (pid {}) on {} at {}x{}",
+            program.display(),
+            child.id(),
+            slave_path,
+            size.cols,
+            size.rows
+        );
+
+        Ok(Self {
+            master,
+            pending_input: Vec::new(),
+            child: RefCell::new(child),
+            exit: Cell::new(None),
+        })
+    }
+
+    /// Process id of the child.
+    pub fn pid(&self) -> u32 {
+        self.child.borrow().id()
+    }
+
+    fn poll_exit(&self) -> Option<i32> {
+        if self.exit.get().is_none() {
+            if let Ok(Some(status)) = self.child.borrow_mut().try_wait() {
+                let code = status
+                    .code()
+                    .or_else(|| status.signal().map(|s| 128 + s))
+                    .unwrap_or(-1);
+                self.exit.set(Some(code));
+                tracing::info!(target: log_target::PTY, "child exited with {}", code);
+            }
+        }
+        self.exit.get()
+    }
+}
+
+impl Pty for UnixPty {
+    fn write(&mut self, data: &[u8]) -> TerminalResult<()> {
+        if self.poll_exit().is_some() {
+            return Err(TerminalError::Io("PTY is closed".to_string()));
+        }
+        tracing::trace!(target: log_target::PTY, "write {} bytes", data.len());
+        self.pending_input.extend_from_slice(data);
+        self.flush_input()
+    }
+
+    fn flush_input(&mut self) -> TerminalResult<()> {
+        while !self.pending_input.is_empty() {
+            match self.master.write(&self.pending_input) {
+                Ok(0) => break,
+                Ok(n) => {
+                    self.pending_input.drain(..n);
+                }
+                // The PTY's input queue is full until the child reads.
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(TerminalError::Io(e.to_string())),
+            }
+        }
+        if !self.pending_input.is_empty() {
+            tracing::trace!(
+                target: log_target::PTY,
+                "{} bytes of input waiting",
+                self.pending_input.len()
+            );
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, max: usize) -> TerminalResult<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut buf = [0u8; 4096];
+        while out.len() < max {
+            let want = buf.len().min(max - out.len());
+            match self.master.read(&mut buf[..want]) {
+                Ok(0) => break,
+                Ok(n) => out.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                // Linux reports EIO once the slave side has been closed.
+                Err(_) if out.is_empty() && self.poll_exit().is_some() => {
+                    return Err(TerminalError::Io("PTY is closed".to_string()));
+                }
+                Err(_) => break,
+            }
+        }
+        if !out.is_empty() {
+            tracing::trace!(target: log_target::PTY, "read {} bytes", out.len());
+        }
+        Ok(out)
+    }
+
+    fn resize(&mut self, size: TerminalSize) -> TerminalResult<()> {
+        tracing::debug!(
+            target: log_target::PTY,
+            "resize to {}x{}",
+            size.cols,
+            size.rows
+        );
+        set_winsize(&self.master, size)
+    }
+
+    fn is_alive(&self) -> bool {
+        self.poll_exit().is_none()
+    }
+
+    fn exit_code(&self) -> Option<i32> {
+        self.poll_exit()
+    }
+
+    /// `Interrupt` goes to the foreground process group, as Ctrl+C
+    /// would; the others go to the child itself. Note that interactive
+    /// shells ignore `SIGTERM`.
+    fn signal(&mut self, signal: Signal) -> TerminalResult<()> {
+        if let Some(code) = self.poll_exit() {
+            return Err(TerminalError::Exited(code));
+        }
+        let pid = self.pid() as libc::pid_t;
+        let (target, signo) = match signal {
+            Signal::Interrupt => {
+                // SAFETY: tcgetpgrp only reads the PTY's foreground group.
+                let group = unsafe { libc::tcgetpgrp(self.master.as_raw_fd()) };
+                (if group > 0 { -group } else { pid }, libc::SIGINT)
+            }
+            Signal::Terminate => (pid, libc::SIGTERM),
+            Signal::Kill => (pid, libc::SIGKILL),
+        };
+        tracing::debug!(
+            target: log_target::PTY,
+            "sending {} to {}",
+            signal.name(),
+            target
+        );
+        // SAFETY: plain syscall on our own child or its process group.
+        if unsafe { libc::kill(target, signo) } < 0 {
+            return Err(TerminalError::Io(
+                std::io::Error::last_os_error().to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) -> TerminalResult<()> {
+        if self.poll_exit().is_none() {
+            let mut child = self.child.borrow_mut();
+            tracing::debug!(target: log_target::PTY, "closing pid {}", child.id());
+            // SIGHUP is what a closing terminal sends; shells exit on it.
+            // SAFETY: plain syscall on our own child's pid.
+            unsafe {
+                libc::kill(child.id() as libc::pid_t, libc::SIGHUP);
+            }
+            // Waiting for it to act on SIGHUP would stall the caller, so
+            // anything still running is killed, which cannot be ignored
+            // and is reaped at once.
+            let status = match child.try_wait()? {
+                Some(status) => status,
+                None => {
+                    child.kill()?;
+                    child.wait()?
+                }
+            };
+            drop(child);
+            self.exit.set(Some(
+                status
+                    .code()
+                    .or_else(|| status.signal().map(|s| 128 + s))
+                    .unwrap_or(-1),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for UnixPty {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+/// Allocate a PTY master and return it with the slave device path.
+fn open_master() -> TerminalResult<(File, String)> {
+    let last_err = || TerminalError::PtyFailed(std::io::Error::last_os_error().to_string());
+    // SAFETY: standard POSIX PTY allocation; the fd is owned by the File.
+    unsafe {
+        let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | OPENPT_CLOEXEC);
+        if fd < 0 {
+            return Err(last_err());
+        }
+        let master = File::from_raw_fd(fd);
+        if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+            return Err(last_err());
+        }
+        let name = slave_name(fd)?;
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags < 0 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) < 0 {
+            return Err(last_err());
+        }
+        if OPENPT_CLOEXEC == 0 && libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) < 0 {
+            return Err(last_err());
+        }
+        Ok((master, name))
+    }
+}
+
+/// Opens the master close-on-exec, so a child spawned by another thread
+/// at the same time cannot inherit it. macOS's `posix_openpt` rejects the
+/// flag, so there it is set just after.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+const OPENPT_CLOEXEC: libc::c_int = libc::O_CLOEXEC;
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+const OPENPT_CLOEXEC: libc::c_int = 0;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+unsafe fn slave_name(fd: libc::c_int) -> TerminalResult<String> {
+    let mut buf = [0 as libc::c_char; 128];
+    if libc::ptsname_r(fd, buf.as_mut_ptr(), buf.len()) != 0 {
+        return Err(TerminalError::PtyFailed(
+            std::io::Error::last_os_error().to_string(),
+        ));
+    }
+    Ok(CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+unsafe fn slave_name(fd: libc::c_int) -> TerminalResult<String> {
+    let ptr = libc::ptsname(fd);
+    if ptr.is_null() {
+        return Err(TerminalError::PtyFailed(
+            std::io::Error::last_os_error().to_string(),
+        ));
+    }
+    Ok(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
+
+fn set_winsize(master: &File, size: TerminalSize) -> TerminalResult<()> {
+    let ws = libc::winsize {
+        ws_row: size.rows,
+        ws_col: size.cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCSWINSZ reads a winsize struct we own.
+    let rc = unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ as _, &ws) };
+    if rc < 0 {
+        return Err(TerminalError::ResizeFailed(
+            std::io::Error::last_os_error().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn read_until(pty: &mut UnixPty, needle: &str) -> String {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut out = String::new();
+        while !out.contains(needle) {
+            assert!(Instant::now() < deadline, "timed out; got {:?}", out);
+            if let Ok(data) = pty.read(usize::MAX) {
+                out.push_str(&String::from_utf8_lossy(&data));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        out
+    }
+
+    #[test]
+    fn runs_command_and_reports_exit() {
+        let size = TerminalSize::new(80, 24);
+        let mut pty =
+            UnixPty::spawn(Path::new("sh"), &["-c", "echo hello; exit 3"], size).expect("spawn");
+        read_until(&mut pty, "hello");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while pty.is_alive() {
+            assert!(Instant::now() < deadline);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(pty.exit_code(), Some(3));
+    }
+
+    #[test]
+    fn input_reaches_child_and_size_is_applied() {
+        let size = TerminalSize::new(100, 30);
+        let mut pty = UnixPty::spawn(Path::new("sh"), &[], size).expect("spawn");
+        pty.write(b"stty size; exit\n").expect("write");
+        read_until(&mut pty, "30 100");
+    }
+
+    #[test]
+    fn input_the_child_is_not_reading_is_kept_for_later() {
+        let size = TerminalSize::new(80, 24);
+        let mut pty =
+            UnixPty::spawn(Path::new("sh"), &["-c", "sleep 1; wc -c"], size).expect("spawn");
+        // Far more than the PTY's input queue holds.
+        let mut data = vec![b'x'; 64 * 1024];
+        data.extend_from_slice(b"\n\x04");
+        let start = Instant::now();
+        pty.write(&data).expect("write");
+        assert!(
+            start.elapsed() < Duration::from_millis(500),
+            "write blocked"
+        );
+        assert!(!pty.pending_input.is_empty());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !pty.pending_input.is_empty() {
+            assert!(Instant::now() < deadline, "input never drained");
+            pty.flush_input().expect("flush");
+            let _ = pty.read(usize::MAX);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn signals_reach_child() {
+        let size = TerminalSize::new(80, 24);
+        let mut pty = UnixPty::spawn(Path::new("sh"), &["-c", "sleep 30"], size).expect("spawn");
+        pty.signal(Signal::Kill).expect("kill");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while pty.is_alive() {
+            assert!(Instant::now() < deadline, "child survived SIGKILL");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(pty.exit_code(), Some(137));
+        assert!(matches!(
+            pty.signal(Signal::Interrupt),
+            Err(TerminalError::Exited(137))
+        ));
+    }
+
+    #[test]
+    fn close_terminates_child() {
+        let size = TerminalSize::new(80, 24);
+        let mut pty = UnixPty::spawn(Path::new("sh"), &["-c", "sleep 30"], size).expect("spawn");
+        assert!(pty.is_alive());
+        pty.close().expect("close");
+        assert!(!pty.is_alive());
+        assert!(pty.write(b"x").is_err());
+    }
+}
@@ -9,6 +9,17 @@ pub trait TerminalBackend {
     fn hide_cursor(&mut self) -> Result<(), TuiError>;
     fn clear(&mut self) -> Result<(), TuiError>;
     fn write_cell(&mut self, col: u16, row: u16, cell: &Cell) -> Result<(), TuiError>;
+    /// Write adjacent cells of one row, all in the same style, starting
+    /// at `col`. Backends that buffer output should move the cursor and
+    /// set the style once for the whole run.
+    fn write_run(&mut self, col: u16, row: u16, cells: &[Cell]) -> Result<(), TuiError> {
+        for (offset, cell) in (0..).zip(cells) {
+            self.write_cell(col.saturating_add(offset), row, cell)?;
+        }
+        Ok(())
+    }
+    /// Send everything written so far to the terminal. Called once per
+    /// frame.
     fn flush(&mut self) -> Result<(), TuiError>;
     fn enter_alternate_screen(&mut self) -> Result<(), TuiError>;
     fn leave_alternate_screen(&mut self) -> Result<(), TuiError>;
@@ -27,6 +38,8 @@ pub struct MockBackend {
     raw_mode: bool,
     alternate_screen: bool,
     pub flush_count: usize,
+    /// Calls to [`TerminalBackend::write_run`].
+    pub run_count: usize,
 }
 
 impl MockBackend {
@@ -43,6 +56,7 @@ impl MockBackend {
             raw_mode: false,
             alternate_screen: false,
             flush_count: 0,
+            run_count: 0,
         }
     }
 
@@ -114,6 +128,14 @@ impl TerminalBackend for MockBackend {
         Ok(())
     }
 
+    fn write_run(&mut self, col: u16, row: u16, cells: &[Cell]) -> Result<(), TuiError> {
+        self.run_count += 1;
+        for (offset, cell) in (0..).zip(cells) {
+            self.write_cell(col.saturating_add(offset), row, cell)?;
+        }
+        Ok(())
+    }
+
     fn flush(&mut self) -> Result<(), TuiError> {
         self.flush_count += 1;
         Ok(())
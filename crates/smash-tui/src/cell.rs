@@ -1,28 +1,62 @@
 use crate::style::Style;
 
+/// Marks the right half of a wide character drawn in the cell before.
+const CONTINUATION: char = '\0';
+
 /// A single terminal cell with a character and style.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Cell {
     pub ch: char,
+    /// The rest of a grapheme cluster drawn in this cell after `ch`, such
+    /// as combining accents or an emoji's skin tone.
+    pub combining: Option<Box<str>>,
     pub style: Style,
 }
 
 impl Cell {
     pub fn new(ch: char, style: Style) -> Self {
-        Self { ch, style }
+        Self {
+            ch,
+            combining: None,
+            style,
+        }
     }
 
     pub fn blank() -> Self {
-        Self {
-            ch: ' ',
-            style: Style::default(),
-        }
+        Self::new(' ', Style::default())
     }
 
     pub fn with_char(ch: char) -> Self {
+        Self::new(ch, Style::default())
+    }
+
+    /// A cell showing grapheme cluster `g`.
+    pub fn grapheme(g: &str, style: Style) -> Self {
+        let mut chars = g.chars();
+        let ch = chars.next().unwrap_or(' ');
+        let rest = chars.as_str();
         Self {
             ch,
-            style: Style::default(),
+            combining: (!rest.is_empty()).then(|| rest.into()),
+            style,
+        }
+    }
+
+    /// The cell covered by the right half of a wide character. Backends
+    /// draw nothing for it.
+    pub fn continuation(style: Style) -> Self {
+        Self::new(CONTINUATION, style)
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.ch == CONTINUATION
+    }
+
+    /// The same text in another style.
+    pub fn restyled(&self, style: Style) -> Self {
+        Self {
+            style,
+            ..self.clone()
         }
     }
 }
@@ -83,6 +117,15 @@ mod tests {
         assert_ne!(a, b);
     }
 
+    #[test]
+    fn grapheme_cell_keeps_combining_characters() {
+        let cell = Cell::grapheme("e\u{301}", Style::default());
+        assert_eq!(cell.ch, 'e');
+        assert_eq!(cell.combining.as_deref(), Some("\u{301}"));
+        assert_eq!(Cell::grapheme("a", Style::default()), Cell::with_char('a'));
+        assert!(Cell::continuation(Style::default()).is_continuation());
+    }
+
     #[test]
     fn cell_debug_format() {
         let cell = Cell::blank();
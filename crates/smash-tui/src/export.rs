@@ -0,0 +1,408 @@
+//! Export a buffer with syntax highlighting as HTML or ANSI text.
+//!
+//! Uses the same [`HighlightEngine`] spans and [`Theme`] styles as the
+//! on-screen renderer, so snippets look the way they do in the editor.
+use std::fmt::Write as _;
+use std::path::Path;
+
+use smash_core::buffer::Buffer;
+use smash_core::position::{Position, Range};
+use smash_syntax::{HighlightEngine, HighlightSpan};
+
+use crate::style::{Color, Style};
+use crate::theme::Theme;
+
+/// Output format of a highlighted export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Standalone HTML document with inline styles.
+    Html,
+    /// Plain text with ANSI SGR escape sequences.
+    Ansi,
+}
+
+impl ExportFormat {
+    /// Pick a format from the output file extension: `.html` / `.htm`
+    /// produce HTML, anything else ANSI text.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm") => {
+                Self::Html
+            }
+            _ => Self::Ansi,
+        }
+    }
+}
+
+/// Options controlling a highlighted export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Output format.
+    pub format: ExportFormat,
+    /// Prefix each line with its 1-based line number.
+    pub line_numbers: bool,
+    /// Export only this range of the buffer (e.g. the selection).
+    pub range: Option<Range>,
+}
+
+impl ExportOptions {
+    /// Export the whole buffer in `format` without line numbers.
+    pub fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            line_numbers: false,
+            range: None,
+        }
+    }
+}
+
+/// Render `buffer` (or `options.range` of it) with highlighting.
+pub fn export_highlighted(
+    buffer: &Buffer,
+    highlighter: Option<&dyn HighlightEngine>,
+    theme: &Theme,
+    options: &ExportOptions,
+) -> String {
+    let mut last_line = buffer.line_count().saturating_sub(1);
+    // The empty line after a trailing newline is not real content.
+    if last_line > 0 && buffer.line(last_line).is_some_and(|l| l.len_chars() == 0) {
+        last_line -= 1;
+    }
+    let range = options
+        .range
+        .unwrap_or_else(|| Range::new(Position::new(0, 0), Position::new(last_line, usize::MAX)));
+    let start = range.start;
+    let mut end = range.end;
+    // A range ending at the start of a line does not include that line.
+    if end.col == 0 && end.line > start.line {
+        end = Position::new(end.line - 1, usize::MAX);
+    }
+    let end_line = end.line.min(last_line);
+    let number_width = (end_line + 1).to_string().len();
+
+    let mut out = String::new();
+    if options.format == ExportFormat::Html {
+        let default = theme.default_style();
+        out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        out.push_str("<title>smash export</title>\n</head>\n<body>\n");
+        let _ = write!(out, "<pre style=\"{}\">", css_for(default, None));
+    }
+
+    for line_idx in start.line..=end_line {
+        let text = buffer
+            .line(line_idx)
+            .map(|l| l.to_string())
+            .unwrap_or_default();
+        let display = text.trim_end_matches('\n').trim_end_matches('\r');
+        let from = if line_idx == start.line { start.col } else { 0 };
+        let to = if line_idx == end.line {
+            end.col
+        } else {
+            usize::MAX
+        };
+        let spans: Vec<HighlightSpan> = highlighter
+            .map(|h| h.highlight_line(display))
+            .unwrap_or_default();
+
+        if options.line_numbers {
+            let number = format!("{:>width$} ", line_idx + 1, width = number_width);
+            push_run(
+                &mut out,
+                options.format,
+                &number,
+                theme.line_number_style(),
+                theme,
+            );
+        }
+        for (run, style) in styled_runs(display, from, to, &spans, theme) {
+            push_run(&mut out, options.format, &run, style, theme);
+        }
+        out.push('\n');
+    }
+
+    if options.format == ExportFormat::Html {
+        out.push_str("</pre>\n</body>\n</html>\n");
+    }
+    out
+}
+
+/// Split the chars `from..to` of `line` into runs of identical style.
+fn styled_runs(
+    line: &str,
+    from: usize,
+    to: usize,
+    spans: &[HighlightSpan],
+    theme: &Theme,
+) -> Vec<(String, Style)> {
+    let mut runs: Vec<(String, Style)> = Vec::new();
+    for (byte, ch) in line
+        .char_indices()
+        .enumerate()
+        .filter(|(i, _)| *i >= from && *i < to)
+        .map(|(_, c)| c)
+    {
+        let style = spans
+            .iter()
+            .find(|s| byte >= s.start && byte < s.end)
+            .map(|s| theme.scope_style(s.scope))
+            .unwrap_or_else(|| theme.default_style());
+        match runs.last_mut() {
+            Some((text, last)) if *last == style => text.push(ch),
+            _ => runs.push((ch.to_string(), style)),
+        }
+    }
+    runs
+}
+
+fn push_run(out: &mut String, format: ExportFormat, text: &str, style: Style, theme: &Theme) {
+    match format {
+        ExportFormat::Html => {
+            let _ = write!(
+                out,
+                "<span style=\"{}\">{}</span>",
+                css_for(style, Some(theme.default_style())),
+                escape_html(text)
+            );
+        }
+        ExportFormat::Ansi => {
+            let codes = sgr_codes(style, theme.default_style());
+            if codes.is_empty() {
+                out.push_str(text);
+            } else {
+                let _ = write!(out, "\x1b[{}m{}\x1b[0m", codes, text);
+            }
+        }
+    }
+}
+
+/// Inline CSS for `style`. The background is only emitted when it
+/// differs from `base` (the enclosing `<pre>` already paints it).
+fn css_for(style: Style, base: Option<Style>) -> String {
+    let mut css = Vec::new();
+    if let Some(fg) = color_to_rgb(style.fg) {
+        css.push(format!("color:{}", hex(fg)));
+    }
+    if !matches!(base, Some(b) if b.bg == style.bg) {
+        if let Some(bg) = color_to_rgb(style.bg) {
+            css.push(format!("background-color:{}", hex(bg)));
+        }
+    }
+    if style.attrs.bold() {
+        css.push("font-weight:bold".to_string());
+    }
+    if style.attrs.italic() {
+        css.push("font-style:italic".to_string());
+    }
+    if style.attrs.underline() {
+        css.push("text-decoration:underline".to_string());
+    }
+    css.join(";")
+}
+
+/// SGR parameters for `style`. The theme background is left to the
+/// terminal so exported text blends into wherever it is pasted.
+fn sgr_codes(style: Style, base: Style) -> String {
+    let mut codes = Vec::new();
+    if style.attrs.bold() {
+        codes.push("1".to_string());
+    }
+    if style.attrs.italic() {
+        codes.push("3".to_string());
+    }
+    if style.attrs.underline() {
+        codes.push("4".to_string());
+    }
+    if let Some(fg) = ansi_color(style.fg, 30) {
+        codes.push(fg);
+    }
+    if style.bg != base.bg {
+        if let Some(bg) = ansi_color(style.bg, 40) {
+            codes.push(bg);
+        }
+    }
+    codes.join(";")
+}
+
+/// SGR color parameter; `base` is 30 for foreground, 40 for background.
+fn ansi_color(color: Color, base: u8) -> Option<String> {
+    let named = |n: u8| Some((base + n).to_string());
+    match color {
+        Color::Reset => None,
+        Color::Black => named(0),
+        Color::Red => named(1),
+        Color::Green => named(2),
+        Color::Yellow => named(3),
+        Color::Blue => named(4),
+        Color::Magenta => named(5),
+        Color::Cyan => named(6),
+        Color::White => named(7),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", base + 8, r, g, b)),
+        Color::Indexed(i) => Some(format!("{};5;{}", base + 8, i)),
+    }
+}
+
+/// Resolve a terminal color to RGB using the standard xterm palette.
+fn color_to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    match color {
+        Color::Reset => None,
+        Color::Black => Some(BASIC[0]),
+        Color::Red => Some(BASIC[1]),
+        Color::Green => Some(BASIC[2]),
+        Color::Yellow => Some(BASIC[3]),
+        Color::Blue => Some(BASIC[4]),
+        Color::Magenta => Some(BASIC[5]),
+        Color::Cyan => Some(BASIC[6]),
+        Color::White => Some(BASIC[7]),
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(i @ 0..=15) => Some(BASIC[i as usize]),
+        Color::Indexed(i @ 16..=231) => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = i - 16;
+            Some((level(i / 36), level((i / 6) % 6), level(i % 6)))
+        }
+        Color::Indexed(i) => {
+            let v = 8 + (i - 232) * 10;
+            Some((v, v, v))
+        }
+    }
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::default_dark_theme;
+    use smash_core::buffer::BufferId;
+    use smash_syntax::{LanguageId, RegexHighlighter, ScopeId};
+
+    fn make_buffer(text: &str) -> Buffer {
+        Buffer::from_text(BufferId::next(), text)
+    }
+
+    #[test]
+    fn format_from_path_uses_extension() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.html")),
+            ExportFormat::Html
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.HTM")),
+            ExportFormat::Html
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.ans")),
+            ExportFormat::Ansi
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("a")), ExportFormat::Ansi);
+    }
+
+    #[test]
+    fn ansi_export_colors_keywords() {
+        let buf = make_buffer("fn main() {}\n");
+        let hl = RegexHighlighter::new(LanguageId::Rust).unwrap();
+        let theme = default_dark_theme();
+        let out = export_highlighted(
+            &buf,
+            Some(&hl),
+            &theme,
+            &ExportOptions::new(ExportFormat::Ansi),
+        );
+        // Keyword style is bold magenta in the dark theme.
+        assert!(out.starts_with("\x1b[1;35mfn\x1b[0m"));
+        assert!(out.ends_with('\n'));
+    }
+
+    #[test]
+    fn html_export_escapes_and_wraps() {
+        let buf = make_buffer("a < b && c\n");
+        let theme = default_dark_theme();
+        let out = export_highlighted(&buf, None, &theme, &ExportOptions::new(ExportFormat::Html));
+        assert!(out.starts_with("<!DOCTYPE html>"));
+        assert!(out.contains("<pre style=\"color:#e5e5e5;background-color:#1e1e2e\">"));
+        assert!(out.contains("a &lt; b &amp;&amp; c"));
+        assert!(out.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn export_with_line_numbers_pads_to_width() {
+        let text: String = (1..=10).map(|i| format!("l{}\n", i)).collect();
+        let buf = make_buffer(&text);
+        let theme = default_dark_theme();
+        let mut options = ExportOptions::new(ExportFormat::Ansi);
+        options.line_numbers = true;
+        let out = export_highlighted(&buf, None, &theme, &options);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 10);
+        assert!(lines[0].contains(" 1 \x1b[0m"));
+        assert!(lines[0].ends_with("l1\x1b[0m"));
+        assert!(lines[9].contains("10 \x1b[0m"));
+    }
+
+    #[test]
+    fn export_range_clips_columns_and_lines() {
+        let buf = make_buffer("hello\nworld\nagain\n");
+        let theme = default_dark_theme();
+        let mut options = ExportOptions::new(ExportFormat::Ansi);
+        options.range = Some(Range::new(Position::new(0, 3), Position::new(2, 0)));
+        let out = export_highlighted(&buf, None, &theme, &options);
+        let plain: Vec<String> = out
+            .lines()
+            .map(|l| l.replace("\x1b[37m", "").replace("\x1b[0m", ""))
+            .collect();
+        assert_eq!(plain, vec!["lo", "world"]);
+    }
+
+    #[test]
+    fn styled_runs_use_byte_offsets() {
+        let theme = default_dark_theme();
+        // "é" is two bytes; the span covers only "x".
+        let spans = vec![HighlightSpan::new(2, 3, ScopeId::Keyword)];
+        let runs = styled_runs("éx", 0, usize::MAX, &spans, &theme);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[1].0, "x");
+        assert_eq!(runs[1].1, theme.scope_style(ScopeId::Keyword));
+    }
+
+    #[test]
+    fn indexed_colors_map_to_xterm_palette() {
+        assert_eq!(color_to_rgb(Color::Indexed(16)), Some((0, 0, 0)));
+        assert_eq!(color_to_rgb(Color::Indexed(231)), Some((255, 255, 255)));
+        assert_eq!(color_to_rgb(Color::Indexed(243)), Some((118, 118, 118)));
+        assert_eq!(color_to_rgb(Color::Reset), None);
+    }
+}
@@ -0,0 +1,294 @@
+//! Project explorer: a collapsible tree of the workspace directory.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use smash_core::fuzzy_finder::is_ignored;
+use smash_input::Key;
+
+use crate::list::ListState;
+
+/// One visible row of the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    pub path: PathBuf,
+    pub name: String,
+    /// Nesting level below the root (0 for top-level entries).
+    pub depth: usize,
+    pub is_dir: bool,
+    pub expanded: bool,
+}
+
+/// The workspace directory as a flat list of visible rows, rebuilt from
+/// disk on [`FileTree::refresh`]. Hidden entries and build/VCS
+/// directories are left out, as in the fuzzy finder.
+#[derive(Debug, Clone)]
+pub struct FileTree {
+    root: PathBuf,
+    expanded: BTreeSet<PathBuf>,
+    entries: Vec<TreeEntry>,
+    list: ListState,
+}
+
+impl FileTree {
+    /// List `root` with every directory collapsed.
+    pub fn new(root: PathBuf) -> Self {
+        let mut tree = Self {
+            root,
+            expanded: BTreeSet::new(),
+            entries: Vec::new(),
+            list: ListState::default(),
+        };
+        tree.refresh();
+        tree
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn entries(&self) -> &[TreeEntry] {
+        &self.entries
+    }
+
+    /// Index of the selected row.
+    pub fn selected_index(&self) -> usize {
+        self.list.selected()
+    }
+
+    pub fn selected(&self) -> Option<&TreeEntry> {
+        self.entries.get(self.list.selected())
+    }
+
+    /// First row shown (see [`FileTree::scroll_to_selected`]).
+    pub fn scroll(&self) -> usize {
+        self.list.scroll()
+    }
+
+    /// Re-read the directory, keeping expanded directories and the
+    /// selection where they still exist.
+    pub fn refresh(&mut self) {
+        let selected = self.selected().map(|e| e.path.clone());
+        self.expanded.retain(|dir| dir.is_dir());
+        let mut entries = Vec::new();
+        self.list_dir(&self.root, 0, &mut entries);
+        self.entries = entries;
+        match selected {
+            Some(path) if self.select_existing(&path) => {}
+            _ => self.list.select(self.list.selected(), self.entries.len()),
+        }
+    }
+
+    fn list_dir(&self, dir: &Path, depth: usize, out: &mut Vec<TreeEntry>) {
+        let Ok(read) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut children: Vec<(bool, String, PathBuf)> = read
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let is_dir = path.is_dir();
+                (!is_ignored(&name, is_dir)).then_some((is_dir, name, path))
+            })
+            .collect();
+        // Directories first, then files, each by name.
+        children.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        for (is_dir, name, path) in children {
+            let expanded = is_dir && self.expanded.contains(&path);
+            out.push(TreeEntry {
+                path: path.clone(),
+                name,
+                depth,
+                is_dir,
+                expanded,
+            });
+            if expanded {
+                self.list_dir(&path, depth + 1, out);
+            }
+        }
+    }
+
+    /// Move the selection by `delta` rows, stopping at either end.
+    pub fn move_selection(&mut self, delta: isize) {
+        self.list.move_selection(delta, self.entries.len());
+    }
+
+    /// Move the selection for a list key (see [`ListState::handle_key`]).
+    /// Returns `false` for any other key.
+    pub fn handle_key(&mut self, key: &Key) -> bool {
+        self.list.handle_key(key, self.entries.len())
+    }
+
+    /// Select the row at `index`, if there is one.
+    pub fn select_index(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.list.select(index, self.entries.len());
+        }
+    }
+
+    /// Open the selected directory.
+    pub fn expand(&mut self) {
+        if let Some(entry) = self.selected().filter(|e| e.is_dir && !e.expanded) {
+            self.expanded.insert(entry.path.clone());
+            self.refresh();
+        }
+    }
+
+    /// Close the selected directory, or if it is not an open directory,
+    /// move to its parent.
+    pub fn collapse(&mut self) {
+        let Some(entry) = self.selected().cloned() else {
+            return;
+        };
+        if entry.expanded {
+            self.expanded.remove(&entry.path);
+            self.refresh();
+        } else if let Some(parent) = entry.path.parent() {
+            self.select_existing(parent);
+        }
+    }
+
+    /// Expand or collapse the selected directory.
+    pub fn toggle(&mut self) {
+        match self.selected() {
+            Some(e) if e.expanded => self.collapse(),
+            Some(e) if e.is_dir => self.expand(),
+            _ => {}
+        }
+    }
+
+    /// Select `path`, expanding the directories above it. Returns `false`
+    /// if it is not in the tree.
+    pub fn select_path(&mut self, path: &Path) -> bool {
+        let Ok(rel) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let mut dir = self.root.clone();
+        let mut components = rel.components().peekable();
+        while let Some(part) = components.next() {
+            if components.peek().is_some() {
+                dir.push(part);
+                self.expanded.insert(dir.clone());
+            }
+        }
+        self.refresh();
+        self.select_existing(path)
+    }
+
+    fn select_existing(&mut self, path: &Path) -> bool {
+        match self.entries.iter().position(|e| e.path == path) {
+            Some(index) => {
+                self.list.select(index, self.entries.len());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Directory new files go into: the selected directory, or the one
+    /// holding the selected file.
+    pub fn target_dir(&self) -> PathBuf {
+        match self.selected() {
+            Some(e) if e.is_dir => e.path.clone(),
+            Some(e) => e.path.parent().unwrap_or(&self.root).to_path_buf(),
+            None => self.root.clone(),
+        }
+    }
+
+    /// Scroll so the selection is within `height` rows.
+    pub fn scroll_to_selected(&mut self, height: usize) {
+        self.list.scroll_to_selected(height, self.entries.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/app")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::write(root.join("src/app/mod.rs"), "").unwrap();
+        std::fs::write(root.join("src/main.rs"), "").unwrap();
+        std::fs::write(root.join("Cargo.toml"), "").unwrap();
+        std::fs::write(root.join(".env"), "").unwrap();
+        dir
+    }
+
+    fn names(tree: &FileTree) -> Vec<String> {
+        tree.entries()
+            .iter()
+            .map(|e| format!("{}{}", "  ".repeat(e.depth), e.name))
+            .collect()
+    }
+
+    #[test]
+    fn lists_directories_first_without_ignored_entries() {
+        let dir = workspace();
+        let tree = FileTree::new(dir.path().to_path_buf());
+        assert_eq!(names(&tree), vec!["src", "Cargo.toml"]);
+        assert!(tree.entries()[0].is_dir);
+    }
+
+    #[test]
+    fn expand_collapse_and_select_path() {
+        let dir = workspace();
+        let mut tree = FileTree::new(dir.path().to_path_buf());
+        tree.expand();
+        assert_eq!(
+            names(&tree),
+            vec!["src", "  app", "  main.rs", "Cargo.toml"]
+        );
+
+        assert!(tree.select_path(&dir.path().join("src/app/mod.rs")));
+        assert_eq!(tree.selected().unwrap().name, "mod.rs");
+        assert_eq!(tree.target_dir(), dir.path().join("src/app"));
+
+        // Left on a file goes to its directory, then closes it.
+        tree.collapse();
+        assert_eq!(tree.selected().unwrap().name, "app");
+        tree.collapse();
+        assert_eq!(
+            names(&tree),
+            vec!["src", "  app", "  main.rs", "Cargo.toml"]
+        );
+
+        tree.move_selection(10);
+        assert_eq!(tree.selected().unwrap().name, "Cargo.toml");
+        tree.move_selection(-10);
+        assert_eq!(tree.selected_index(), 0);
+    }
+
+    #[test]
+    fn refresh_keeps_selection_and_follows_disk() {
+        let dir = workspace();
+        let mut tree = FileTree::new(dir.path().to_path_buf());
+        tree.move_selection(1);
+        std::fs::write(dir.path().join("build.rs"), "").unwrap();
+        tree.refresh();
+        assert_eq!(names(&tree), vec!["src", "Cargo.toml", "build.rs"]);
+        assert_eq!(tree.selected().unwrap().name, "Cargo.toml");
+
+        std::fs::remove_file(dir.path().join("build.rs")).unwrap();
+        tree.move_selection(1);
+        tree.refresh();
+        assert_eq!(tree.selected().unwrap().name, "Cargo.toml");
+    }
+
+    #[test]
+    fn scrolls_to_keep_selection_visible() {
+        let dir = workspace();
+        let mut tree = FileTree::new(dir.path().to_path_buf());
+        tree.expand();
+        tree.move_selection(3);
+        tree.scroll_to_selected(2);
+        assert_eq!(tree.scroll(), 2);
+        tree.move_selection(-3);
+        tree.scroll_to_selected(2);
+        assert_eq!(tree.scroll(), 0);
+    }
+}
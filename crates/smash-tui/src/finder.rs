@@ -0,0 +1,126 @@
+//! The file finder overlay: the matching files on the left, with the
+//! characters that match the query emphasised, and the first lines of the
+//! selected file on the right.
+
+use crate::pane::Rect;
+
+/// Narrowest overlay that has room for a preview beside the list.
+pub const MIN_PREVIEW_WIDTH: u16 = 60;
+
+/// One file in the list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinderRow {
+    /// Path relative to the workspace.
+    pub label: String,
+    /// Character indices of `label` that match the query.
+    pub matched: Vec<usize>,
+}
+
+impl FinderRow {
+    pub fn new(label: impl Into<String>, matched: Vec<usize>) -> Self {
+        Self {
+            label: label.into(),
+            matched,
+        }
+    }
+
+    /// The label fitted into `width` columns, keeping its end (the file
+    /// name) and marking a cut with `…`, each character with whether it
+    /// matches the query.
+    pub fn fitted(&self, width: usize) -> Vec<(char, bool)> {
+        let chars: Vec<char> = self.label.chars().collect();
+        if chars.len() <= width {
+            return chars
+                .into_iter()
+                .enumerate()
+                .map(|(i, ch)| (ch, self.matched.contains(&i)))
+                .collect();
+        }
+        if width == 0 {
+            return Vec::new();
+        }
+        let skip = chars.len() + 1 - width;
+        std::iter::once(('\u{2026}', false))
+            .chain(
+                chars
+                    .into_iter()
+                    .enumerate()
+                    .skip(skip)
+                    .map(|(i, ch)| (ch, self.matched.contains(&i))),
+            )
+            .collect()
+    }
+}
+
+/// What the overlay shows in one frame.
+#[derive(Debug, Clone, Copy)]
+pub struct FinderView<'a> {
+    pub query: &'a str,
+    /// Shown at the right of the query line, e.g. the match count.
+    pub status: &'a str,
+    /// The rows in view, scrolled by the caller (see [`finder_list_rows`]).
+    pub rows: &'a [FinderRow],
+    /// Index into `rows` of the selection, if it is in view.
+    pub selected: Option<usize>,
+    /// First lines of the selected file, or why there are none.
+    pub preview: &'a [String],
+}
+
+/// Where to draw the overlay in `bounds`: most of it, centred, leaving
+/// the top line of the editor visible.
+pub fn finder_placement(bounds: Rect) -> Rect {
+    let width = (bounds.width.saturating_mul(9) / 10).clamp(40.min(bounds.width), bounds.width);
+    let height = (bounds.height.saturating_mul(4) / 5)
+        .max(8)
+        .min(bounds.height.saturating_sub(1))
+        .max(1.min(bounds.height));
+    let x = bounds.x + (bounds.width - width) / 2;
+    let y = bounds.y + (bounds.height - height).min(1);
+    Rect::new(x, y, width, height)
+}
+
+/// Number of list rows an overlay drawn in `area` has: below the border,
+/// query line and separator, above the bottom border.
+pub fn finder_list_rows(area: Rect) -> usize {
+    area.height.saturating_sub(4) as usize
+}
+
+/// Width of the list column in an overlay drawn in `area`, and whether
+/// there is a preview beside it.
+pub fn finder_list_width(area: Rect) -> (usize, bool) {
+    let inner = area.width.saturating_sub(2) as usize;
+    if area.width < MIN_PREVIEW_WIDTH {
+        (inner, false)
+    } else {
+        (inner * 2 / 5, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_labels_keep_the_file_name() {
+        let row = FinderRow::new("src/app/main.rs", vec![8, 13]);
+        let text = |cells: Vec<(char, bool)>| cells.iter().map(|c| c.0).collect::<String>();
+        assert_eq!(text(row.fitted(20)), "src/app/main.rs");
+        assert_eq!(text(row.fitted(8)), "…main.rs");
+        let fitted = row.fitted(8);
+        assert!(fitted[1].1, "the `m` of main still matches");
+        assert!(fitted[6].1);
+        assert_eq!(row.fitted(0), vec![]);
+    }
+
+    #[test]
+    fn placement_fills_most_of_the_screen() {
+        let area = finder_placement(Rect::new(0, 0, 100, 30));
+        assert_eq!((area.x, area.y, area.width, area.height), (5, 1, 90, 24));
+        assert_eq!(finder_list_rows(area), 20);
+        assert_eq!(finder_list_width(area), (35, true));
+
+        let small = finder_placement(Rect::new(0, 0, 30, 6));
+        assert_eq!((small.x, small.y, small.width, small.height), (0, 1, 30, 5));
+        assert_eq!(finder_list_width(small), (28, false));
+    }
+}
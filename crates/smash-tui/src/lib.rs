@@ -1,19 +1,41 @@
 pub mod backend;
 pub mod cell;
 pub mod error;
+pub mod export;
+pub mod file_tree;
+pub mod finder;
+pub mod list;
+pub mod minimap;
 pub mod pane;
+pub mod picker;
+pub mod popup;
 pub mod renderer;
 pub mod screen;
+pub mod scrollbar;
 pub mod style;
 pub mod theme;
+pub mod theme_file;
 pub mod viewport;
 
 pub use backend::{MockBackend, TerminalBackend};
 pub use cell::Cell;
 pub use error::TuiError;
+pub use export::{export_highlighted, ExportFormat, ExportOptions};
+pub use file_tree::{FileTree, TreeEntry};
+pub use finder::{finder_list_rows, finder_list_width, finder_placement, FinderRow, FinderView};
+pub use list::ListState;
+pub use minimap::{minimap_lines_per_row, Minimap, MinimapCell, MinimapRow, MINIMAP_WIDTH};
 pub use pane::{PaneId, PaneTree, Rect, SplitDirection};
-pub use renderer::{GutterDiagnostic, Renderer};
+pub use picker::{Picker, PickerItem};
+pub use popup::Popup;
+pub use renderer::{
+    gutter_width, DiagnosticSigns, GutterDiagnostic, LineNumbers, Renderer, LINE_NUMBER_WIDTH,
+};
 pub use screen::Screen;
+pub use scrollbar::{
+    scrollbar_line, scrollbar_row, scrollbar_thumb, ScrollbarMark, SCROLLBAR_WIDTH,
+};
 pub use style::{Attributes, Color, Style};
-pub use theme::{default_dark_theme, Theme};
+pub use theme::{accessible_dark_theme, builtin_theme, default_dark_theme, Theme, BUILTIN_THEMES};
+pub use theme_file::{find_theme, parse_theme, theme_names, themes_dir};
 pub use viewport::Viewport;
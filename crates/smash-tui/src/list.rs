@@ -0,0 +1,124 @@
+//! Selection and scrolling for a list of rows drawn in a pane or overlay.
+
+use smash_input::{Command, Key};
+
+/// Rows moved by PageUp / PageDown.
+const PAGE_ROWS: isize = 10;
+
+/// Which row of a list is selected and which is drawn first. The rows
+/// themselves belong to the caller, which passes their count in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListState {
+    selected: usize,
+    scroll: usize,
+}
+
+impl ListState {
+    /// Index of the selected row.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// First row shown (see [`ListState::scroll_to_selected`]).
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Select row `index` of `len`, or the last row if it is past the end.
+    pub fn select(&mut self, index: usize, len: usize) {
+        self.selected = index.min(len.saturating_sub(1));
+    }
+
+    /// Move the selection by `delta` rows of `len`, stopping at either end.
+    pub fn move_selection(&mut self, delta: isize, len: usize) {
+        self.select(self.selected.saturating_add_signed(delta), len);
+    }
+
+    /// Move the selection for Up/k, Down/j, PageUp, PageDown, Home or End.
+    /// Returns `false` for any other key.
+    pub fn handle_key(&mut self, key: &Key, len: usize) -> bool {
+        let delta = match key {
+            Key::Up | Key::Char('k') => -1,
+            Key::Down | Key::Char('j') => 1,
+            Key::PageUp => -PAGE_ROWS,
+            Key::PageDown => PAGE_ROWS,
+            Key::Home => isize::MIN,
+            Key::End => isize::MAX,
+            _ => return false,
+        };
+        self.move_selection(delta, len);
+        true
+    }
+
+    /// Move the selection for an Up, Down, PageUp or PageDown command.
+    /// Returns `false` for any other command.
+    pub fn handle_command(&mut self, cmd: &Command, len: usize) -> bool {
+        let delta = match cmd {
+            Command::MoveUp => -1,
+            Command::MoveDown => 1,
+            Command::PageUp => -PAGE_ROWS,
+            Command::PageDown => PAGE_ROWS,
+            _ => return false,
+        };
+        self.move_selection(delta, len);
+        true
+    }
+
+    /// Scroll so the selection is within `height` rows, without leaving
+    /// blank rows below the last of `len`.
+    pub fn scroll_to_selected(&mut self, height: usize, len: usize) {
+        let height = height.max(1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + height {
+            self.scroll = self.selected + 1 - height;
+        }
+        self.scroll = self.scroll.min(len.saturating_sub(height));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_move_the_selection_within_the_list() {
+        let mut list = ListState::default();
+        assert!(list.handle_key(&Key::Down, 5));
+        assert!(list.handle_key(&Key::Char('j'), 5));
+        assert_eq!(list.selected(), 2);
+        assert!(list.handle_key(&Key::PageDown, 5));
+        assert_eq!(list.selected(), 4);
+        assert!(list.handle_key(&Key::Home, 5));
+        assert_eq!(list.selected(), 0);
+        assert!(list.handle_key(&Key::End, 5));
+        assert_eq!(list.selected(), 4);
+        assert!(list.handle_key(&Key::Char('k'), 5));
+        assert_eq!(list.selected(), 3);
+        assert!(!list.handle_key(&Key::Enter, 5));
+        assert!(list.handle_command(&Command::PageUp, 5));
+        assert_eq!(list.selected(), 0);
+        assert!(!list.handle_command(&Command::MoveLeft, 5));
+    }
+
+    #[test]
+    fn empty_list_keeps_the_first_row_selected() {
+        let mut list = ListState::default();
+        list.move_selection(3, 0);
+        assert_eq!(list.selected(), 0);
+    }
+
+    #[test]
+    fn scrolls_to_the_selection_and_not_past_the_end() {
+        let mut list = ListState::default();
+        list.select(4, 5);
+        list.scroll_to_selected(2, 5);
+        assert_eq!(list.scroll(), 3);
+        list.select(0, 5);
+        list.scroll_to_selected(2, 5);
+        assert_eq!(list.scroll(), 0);
+        list.select(4, 5);
+        list.scroll_to_selected(10, 5);
+        assert_eq!(list.scroll(), 0);
+    }
+}
@@ -0,0 +1,229 @@
+//! A compressed overview of a buffer, drawn in a narrow column beside
+//! the text: each row stands for a run of lines and each cell for a few
+//! columns of them, shaded by how much text is there.
+
+use std::ops::Range;
+
+use smash_core::buffer::Buffer;
+use smash_syntax::{HighlightEngine, ScopeId};
+
+use crate::renderer::GutterDiagnostic;
+
+/// Columns taken by the minimap.
+pub const MINIMAP_WIDTH: u16 = 12;
+
+/// Text columns summed up in one minimap cell.
+const COLS_PER_CELL: usize = 4;
+
+/// Lines read per minimap row when it stands for more than this many, so
+/// a frame costs the same however long the buffer is.
+const SAMPLED_LINES: usize = 4;
+
+/// Shades for a cell by the share of it taken up by text.
+const SHADES: [char; 4] = [' ', '\u{2591}', '\u{2592}', '\u{2593}']; // ░ ▒ ▓
+
+/// Buffer lines each of `rows` minimap rows stands for, so that all
+/// `line_count` lines fit.
+pub fn minimap_lines_per_row(line_count: usize, rows: usize) -> usize {
+    line_count.max(1).div_ceil(rows.max(1))
+}
+
+/// One cell of the minimap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinimapCell {
+    pub ch: char,
+    /// The scope most of the cell's text is in, when highlighted.
+    pub scope: Option<ScopeId>,
+}
+
+/// One row of the minimap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinimapRow {
+    /// The buffer lines this row stands for.
+    pub lines: Range<usize>,
+    pub cells: Vec<MinimapCell>,
+    /// The most severe diagnostic on any of `lines`.
+    pub diagnostic: Option<GutterDiagnostic>,
+}
+
+/// The overview of a whole buffer, one row per screen row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Minimap {
+    rows: Vec<MinimapRow>,
+}
+
+impl Minimap {
+    /// Summarise `buffer` in at most `height` rows of `width` cells.
+    /// `diagnostics` holds the line and severity of each diagnostic.
+    pub fn build(
+        buffer: &Buffer,
+        highlighter: Option<&dyn HighlightEngine>,
+        width: usize,
+        height: usize,
+        diagnostics: &[(usize, GutterDiagnostic)],
+    ) -> Self {
+        let line_count = buffer.line_count();
+        let per_row = minimap_lines_per_row(line_count, height);
+        let step = per_row.div_ceil(SAMPLED_LINES);
+        let rows = (0..line_count)
+            .step_by(per_row)
+            .map(|start| {
+                let lines = start..(start + per_row).min(line_count);
+                let sampled: Vec<(String, Vec<Option<ScopeId>>)> = lines
+                    .clone()
+                    .step_by(step)
+                    .map(|line| sample_line(buffer, line, width * COLS_PER_CELL, highlighter))
+                    .collect();
+                let cells = (0..width)
+                    .map(|cell| summarise_cell(&sampled, cell * COLS_PER_CELL))
+                    .collect();
+                let diagnostic = diagnostics
+                    .iter()
+                    .filter(|(line, _)| lines.contains(line))
+                    .map(|&(_, severity)| severity)
+                    .min_by_key(|&severity| severity_rank(severity));
+                MinimapRow {
+                    lines,
+                    cells,
+                    diagnostic,
+                }
+            })
+            .collect();
+        Self { rows }
+    }
+
+    pub fn rows(&self) -> &[MinimapRow] {
+        &self.rows
+    }
+}
+
+/// The first `cols` characters of `line` and the scope of each.
+fn sample_line(
+    buffer: &Buffer,
+    line: usize,
+    cols: usize,
+    highlighter: Option<&dyn HighlightEngine>,
+) -> (String, Vec<Option<ScopeId>>) {
+    let text = buffer.line(line).map(|s| s.to_string()).unwrap_or_default();
+    let text = text.trim_end_matches(['\n', '\r']);
+    let spans = highlighter
+        .map(|h| h.highlight_line(text))
+        .unwrap_or_default();
+    let mut chars = String::new();
+    let mut scopes = Vec::new();
+    for (offset, ch) in text.char_indices().take(cols) {
+        chars.push(ch);
+        scopes.push(
+            spans
+                .iter()
+                .find(|s| offset >= s.start && offset < s.end)
+                .map(|s| s.scope),
+        );
+    }
+    (chars, scopes)
+}
+
+/// The shade and main scope of the cell starting at text column `col`.
+fn summarise_cell(sampled: &[(String, Vec<Option<ScopeId>>)], col: usize) -> MinimapCell {
+    let mut filled = 0;
+    let mut scopes: Vec<(ScopeId, usize)> = Vec::new();
+    for (text, line_scopes) in sampled {
+        let chars = text.chars().zip(line_scopes).skip(col).take(COLS_PER_CELL);
+        for (ch, scope) in chars {
+            if ch.is_whitespace() {
+                continue;
+            }
+            filled += 1;
+            if let Some(scope) = *scope {
+                match scopes.iter_mut().find(|(s, _)| *s == scope) {
+                    Some((_, count)) => *count += 1,
+                    None => scopes.push((scope, 1)),
+                }
+            }
+        }
+    }
+    let capacity = (sampled.len() * COLS_PER_CELL).max(1);
+    // Any text at all gets at least the lightest shade.
+    let shade = (filled * (SHADES.len() - 1)).div_ceil(capacity);
+    MinimapCell {
+        ch: SHADES[shade.min(SHADES.len() - 1)],
+        scope: scopes
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .map(|(scope, _)| *scope),
+    }
+}
+
+/// Lower is more severe.
+fn severity_rank(severity: GutterDiagnostic) -> u8 {
+    match severity {
+        GutterDiagnostic::Error => 0,
+        GutterDiagnostic::Warning => 1,
+        GutterDiagnostic::Information => 2,
+        GutterDiagnostic::Hint => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smash_core::buffer::BufferId;
+    use smash_syntax::HighlightSpan;
+
+    /// Marks every `fn` as a keyword.
+    struct FnKeywords;
+
+    impl HighlightEngine for FnKeywords {
+        fn highlight_line(&self, line: &str) -> Vec<HighlightSpan> {
+            line.match_indices("fn")
+                .map(|(i, m)| HighlightSpan::new(i, i + m.len(), ScopeId::Keyword))
+                .collect()
+        }
+
+        fn engine_name(&self) -> &str {
+            "fn-keywords"
+        }
+    }
+
+    #[test]
+    fn lines_per_row_fits_the_whole_buffer() {
+        assert_eq!(minimap_lines_per_row(10, 20), 1);
+        assert_eq!(minimap_lines_per_row(20, 20), 1);
+        assert_eq!(minimap_lines_per_row(21, 20), 2);
+        assert_eq!(minimap_lines_per_row(0, 0), 1);
+    }
+
+    #[test]
+    fn rows_shade_text_and_keep_its_scope() {
+        let buf = Buffer::from_text(BufferId::next(), "fnfn    x\n\nabcdabcd\n");
+        let map = Minimap::build(&buf, Some(&FnKeywords), 3, 10, &[]);
+        let rows = map.rows();
+        assert_eq!(rows.len(), 4);
+        let shades: Vec<char> = rows[0].cells.iter().map(|c| c.ch).collect();
+        assert_eq!(shades, ['\u{2593}', ' ', '\u{2591}']);
+        assert_eq!(rows[0].cells[0].scope, Some(ScopeId::Keyword));
+        assert_eq!(rows[0].cells[2].scope, None);
+        assert!(rows[1].cells.iter().all(|c| c.ch == ' '));
+        assert_eq!(rows[2].cells[1].ch, '\u{2593}');
+    }
+
+    #[test]
+    fn long_buffers_fold_lines_into_rows_with_their_worst_diagnostic() {
+        let text = "x\n".repeat(100);
+        let buf = Buffer::from_text(BufferId::next(), &text);
+        let diagnostics = [
+            (12, GutterDiagnostic::Hint),
+            (15, GutterDiagnostic::Error),
+            (40, GutterDiagnostic::Warning),
+        ];
+        let map = Minimap::build(&buf, None, 2, 10, &diagnostics);
+        let rows = map.rows();
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows[1].lines, 11..22);
+        assert_eq!(rows[1].diagnostic, Some(GutterDiagnostic::Error));
+        assert_eq!(rows[3].diagnostic, Some(GutterDiagnostic::Warning));
+        assert_eq!(rows[0].diagnostic, None);
+        assert_eq!(rows[9].lines, 99..101);
+    }
+}
@@ -1,3 +1,5 @@
+use smash_core::session::PaneLayout;
+
 use crate::error::TuiError;
 
 /// Rectangle in terminal coordinates.
@@ -57,6 +59,8 @@ pub struct PaneTree {
     root: PaneNode,
     next_id: PaneId,
     active: PaneId,
+    /// Pairs of panes that scroll together.
+    scroll_locks: Vec<(PaneId, PaneId)>,
 }
 
 impl PaneTree {
@@ -65,6 +69,7 @@ impl PaneTree {
             root: PaneNode::Leaf { id: 0 },
             next_id: 1,
             active: 0,
+            scroll_locks: Vec::new(),
         }
     }
 
@@ -122,6 +127,7 @@ impl PaneTree {
         }
         let closed = Self::close_node(&mut self.root, id);
         if closed {
+            self.scroll_locks.retain(|&(a, b)| a != id && b != id);
             // If active pane was closed, find a new active
             if self.active == id {
                 self.active = Self::first_leaf(&self.root);
@@ -222,6 +228,100 @@ impl PaneTree {
             self.active = ids[(pos + ids.len() - 1) % ids.len()];
         }
     }
+
+    /// Lock or unlock scrolling between two panes. A pane scrolls with at
+    /// most one other, so locking replaces any lock either pane had.
+    /// Returns whether the pair is now locked.
+    pub fn toggle_scroll_lock(&mut self, a: PaneId, b: PaneId) -> Result<bool, TuiError> {
+        let ids = self.pane_ids();
+        if a == b || !ids.contains(&a) || !ids.contains(&b) {
+            return Err(TuiError::Layout(format!(
+                "cannot lock scrolling of panes {} and {}",
+                a, b
+            )));
+        }
+        if self.scroll_partner(a) == Some(b) {
+            self.scroll_locks.retain(|&(x, y)| x != a && y != a);
+            return Ok(false);
+        }
+        self.scroll_locks
+            .retain(|&(x, y)| x != a && y != a && x != b && y != b);
+        self.scroll_locks.push((a, b));
+        Ok(true)
+    }
+
+    /// The pane that scrolls together with `id`, if any.
+    pub fn scroll_partner(&self, id: PaneId) -> Option<PaneId> {
+        self.scroll_locks.iter().find_map(|&(a, b)| {
+            if a == id {
+                Some(b)
+            } else if b == id {
+                Some(a)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Snapshot the layout for a saved session.
+    pub fn to_layout(&self) -> PaneLayout {
+        fn convert(node: &PaneNode) -> PaneLayout {
+            match node {
+                PaneNode::Leaf { id } => PaneLayout::Leaf { id: *id },
+                PaneNode::Split {
+                    direction,
+                    ratio,
+                    first,
+                    second,
+                } => PaneLayout::Split {
+                    vertical: *direction == SplitDirection::Vertical,
+                    ratio: *ratio,
+                    first: Box::new(convert(first)),
+                    second: Box::new(convert(second)),
+                },
+            }
+        }
+        convert(&self.root)
+    }
+
+    /// Rebuild a tree from a saved layout. Focus goes to `active`, or to
+    /// the first pane if there is no such pane.
+    pub fn from_layout(layout: &PaneLayout, active: PaneId) -> Self {
+        fn convert(layout: &PaneLayout) -> PaneNode {
+            match layout {
+                PaneLayout::Leaf { id } => PaneNode::Leaf { id: *id },
+                PaneLayout::Split {
+                    vertical,
+                    ratio,
+                    first,
+                    second,
+                } => PaneNode::Split {
+                    direction: if *vertical {
+                        SplitDirection::Vertical
+                    } else {
+                        SplitDirection::Horizontal
+                    },
+                    ratio: ratio.clamp(0.0, 1.0),
+                    first: Box::new(convert(first)),
+                    second: Box::new(convert(second)),
+                },
+            }
+        }
+        let mut tree = Self {
+            root: convert(layout),
+            next_id: 0,
+            active: 0,
+            scroll_locks: Vec::new(),
+        };
+        let ids = tree.pane_ids();
+        tree.next_id = ids.iter().max().map_or(0, |m| m + 1);
+        tree.active = if ids.contains(&active) {
+            active
+        } else {
+            Self::first_leaf(&tree.root)
+        };
+        tree
+    }
 }
 
 impl Default for PaneTree {
@@ -287,6 +387,25 @@ mod tests {
         assert!(!r.contains(10, 20));
     }
 
+    #[test]
+    fn pane_tree_layout_round_trip() {
+        let mut tree = PaneTree::new();
+        tree.split(SplitDirection::Vertical).unwrap();
+        tree.focus_next();
+        tree.split(SplitDirection::Horizontal).unwrap();
+
+        let restored = PaneTree::from_layout(&tree.to_layout(), tree.active_pane());
+        assert_eq!(restored.to_layout(), tree.to_layout());
+        assert_eq!(restored.active_pane(), 1);
+        assert_eq!(restored.pane_ids(), vec![0, 1, 2]);
+        let area = Rect::new(0, 0, 80, 24);
+        assert_eq!(restored.layout(area), tree.layout(area));
+
+        let mut restored = PaneTree::from_layout(&tree.to_layout(), 9);
+        assert_eq!(restored.active_pane(), 0);
+        assert_eq!(restored.split(SplitDirection::Vertical).unwrap(), 3);
+    }
+
     #[test]
     fn pane_tree_new_single_pane() {
         let tree = PaneTree::new();
@@ -445,4 +564,28 @@ mod tests {
         let result = tree.close(99);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn pane_tree_scroll_locks_pair_panes() {
+        let mut tree = PaneTree::new();
+        let right = tree.split(SplitDirection::Vertical).unwrap();
+        assert!(tree.toggle_scroll_lock(0, 0).is_err());
+        assert!(tree.toggle_scroll_lock(0, 99).is_err());
+
+        assert!(tree.toggle_scroll_lock(0, right).unwrap());
+        assert_eq!(tree.scroll_partner(0), Some(right));
+        assert_eq!(tree.scroll_partner(right), Some(0));
+        assert!(!tree.toggle_scroll_lock(right, 0).unwrap());
+        assert_eq!(tree.scroll_partner(0), None);
+
+        // Locking with a third pane replaces the old pair, and closing a
+        // pane drops its lock.
+        tree.toggle_scroll_lock(0, right).unwrap();
+        tree.set_active(right);
+        let third = tree.split(SplitDirection::Horizontal).unwrap();
+        assert!(tree.toggle_scroll_lock(third, 0).unwrap());
+        assert_eq!(tree.scroll_partner(right), None);
+        tree.close(third).unwrap();
+        assert_eq!(tree.scroll_partner(0), None);
+    }
 }
@@ -0,0 +1,229 @@
+//! A fuzzy-filterable list drawn over the editor, for choosing one item
+//! out of many (symbols, files, ...) by typing part of its name.
+
+use smash_core::fuzzy_finder::fuzzy_score;
+use smash_input::Command;
+
+use crate::list::ListState;
+use crate::pane::Rect;
+
+/// One choice in a [`Picker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickerItem {
+    /// Text the query is matched against, shown first.
+    pub label: String,
+    /// Extra context shown after the label (kind, location, ...).
+    pub detail: String,
+}
+
+impl PickerItem {
+    pub fn new(label: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Items filtered by a query, best match first, with a selection.
+#[derive(Debug, Clone)]
+pub struct Picker {
+    title: String,
+    items: Vec<PickerItem>,
+    query: String,
+    /// Indices into `items` that match `query`, best first.
+    filtered: Vec<usize>,
+    /// Selection within `filtered`.
+    list: ListState,
+}
+
+impl Picker {
+    /// A picker showing every item in its original order.
+    pub fn new(title: impl Into<String>, items: Vec<PickerItem>) -> Self {
+        let mut picker = Self {
+            title: title.into(),
+            items,
+            query: String::new(),
+            filtered: Vec::new(),
+            list: ListState::default(),
+        };
+        picker.refilter();
+        picker
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Number of items matching the query.
+    pub fn match_count(&self) -> usize {
+        self.filtered.len()
+    }
+
+    /// Total number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Filter by `query` and select the best match. An empty query keeps
+    /// the original order.
+    pub fn set_query(&mut self, query: &str) {
+        if query != self.query {
+            self.query = query.to_string();
+            self.refilter();
+        }
+    }
+
+    /// Replace the items (e.g. with fresh results from a server), keeping
+    /// the query.
+    pub fn set_items(&mut self, items: Vec<PickerItem>) {
+        self.items = items;
+        self.refilter();
+    }
+
+    fn refilter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| Some((fuzzy_score(&self.query, &item.label)?, i)))
+            .collect();
+        // Stable, so ties keep the original order.
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        self.filtered = scored.into_iter().map(|(_, i)| i).collect();
+        self.list = ListState::default();
+    }
+
+    /// Move the selection by `delta` rows, stopping at either end.
+    pub fn move_selection(&mut self, delta: isize) {
+        self.list.move_selection(delta, self.filtered.len());
+    }
+
+    /// Move the selection for an Up, Down, PageUp or PageDown command.
+    /// Returns `false` for any other command.
+    pub fn handle_motion(&mut self, cmd: &Command) -> bool {
+        self.list.handle_command(cmd, self.filtered.len())
+    }
+
+    /// Index (into the items passed in) of the selected item.
+    pub fn selected(&self) -> Option<usize> {
+        self.filtered.get(self.list.selected()).copied()
+    }
+
+    /// The matching items to draw in `rows` rows, scrolled so the
+    /// selection is visible, each with whether it is selected.
+    pub fn visible(&mut self, rows: usize) -> Vec<(&PickerItem, bool)> {
+        self.list.scroll_to_selected(rows, self.filtered.len());
+        let selected = self.list.selected();
+        self.filtered
+            .iter()
+            .enumerate()
+            .skip(self.list.scroll())
+            .take(rows.max(1))
+            .map(|(pos, &i)| (&self.items[i], pos == selected))
+            .collect()
+    }
+
+    /// Where to draw the picker in `bounds`: centred near the top, wide
+    /// enough for most entries and tall enough for up to `max_rows` items
+    /// below the query line.
+    pub fn placement(&self, bounds: Rect, max_rows: u16) -> Rect {
+        let content_w = self
+            .items
+            .iter()
+            .map(|item| item.label.chars().count() + item.detail.chars().count() + 2)
+            .max()
+            .unwrap_or(0)
+            .max(self.title.chars().count() + 2) as u16;
+        let width = content_w
+            .saturating_add(2)
+            .clamp(40.min(bounds.width), bounds.width);
+        // Border, query line, separator, then the items.
+        let rows = (self.items.len() as u16).clamp(1, max_rows);
+        let height = rows.saturating_add(4).min(bounds.height);
+        let x = bounds.x + (bounds.width - width) / 2;
+        let y = bounds.y + (bounds.height - height).min(1);
+        Rect::new(x, y, width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker() -> Picker {
+        Picker::new(
+            "Symbols",
+            vec![
+                PickerItem::new("parse_args", "function"),
+                PickerItem::new("Config", "struct"),
+                PickerItem::new("run", "function"),
+                PickerItem::new("render_pane", "method"),
+            ],
+        )
+    }
+
+    #[test]
+    fn filters_by_fuzzy_query_best_first() {
+        let mut p = picker();
+        assert_eq!(p.match_count(), 4);
+        assert_eq!(p.selected(), Some(0));
+
+        p.set_query("r");
+        assert_eq!(p.match_count(), 3);
+        assert_eq!(p.selected(), Some(2), "`run` is the shortest match");
+
+        p.set_query("rp");
+        assert_eq!(p.selected(), Some(3));
+        p.set_query("zzz");
+        assert_eq!(p.selected(), None);
+    }
+
+    #[test]
+    fn selection_scrolls_and_stops_at_the_ends() {
+        let mut p = picker();
+        p.move_selection(10);
+        assert_eq!(p.selected(), Some(3));
+        let rows = p.visible(2);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0.label, "run");
+        assert!(rows[1].1);
+        p.move_selection(-10);
+        assert_eq!(p.selected(), Some(0));
+        assert_eq!(p.visible(2)[0].0.label, "parse_args");
+    }
+
+    #[test]
+    fn motion_commands_move_the_selection() {
+        let mut p = picker();
+        assert!(p.handle_motion(&Command::MoveDown));
+        assert_eq!(p.selected(), Some(1));
+        assert!(p.handle_motion(&Command::PageDown));
+        assert_eq!(p.selected(), Some(3));
+        assert!(p.handle_motion(&Command::MoveUp));
+        assert_eq!(p.selected(), Some(2));
+        assert!(!p.handle_motion(&Command::InsertNewline));
+        assert_eq!(p.selected(), Some(2));
+    }
+
+    #[test]
+    fn placement_is_centred_and_clamped() {
+        let p = picker();
+        let area = p.placement(Rect::new(0, 0, 80, 20), 10);
+        assert_eq!(area.width, 40);
+        assert_eq!(area.x, 20);
+        assert_eq!(area.height, 8);
+        assert_eq!(area.y, 1);
+
+        let small = p.placement(Rect::new(0, 0, 30, 5), 10);
+        assert_eq!((small.x, small.y, small.width, small.height), (0, 0, 30, 5));
+    }
+}
@@ -0,0 +1,238 @@
+use std::ops::Range;
+
+use crate::pane::Rect;
+
+/// Smallest popup worth drawing: a border around one cell of text.
+const MIN_SIZE: u16 = 3;
+
+/// A bordered box of text drawn over the editor, anchored to a cell
+/// (usually the cursor). Used for diagnostics and similar detail views.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Popup {
+    pub title: String,
+    pub lines: Vec<String>,
+    /// Characters of one line drawn emphasised: (line, char range).
+    pub highlight: Option<(usize, Range<usize>)>,
+    /// Open above the anchor when there is room, rather than below.
+    pub prefer_above: bool,
+}
+
+impl Popup {
+    pub fn new(title: impl Into<String>, lines: Vec<String>) -> Self {
+        Self {
+            title: title.into(),
+            lines,
+            highlight: None,
+            prefer_above: false,
+        }
+    }
+
+    /// Emphasise the characters `range` of line `line`.
+    pub fn with_highlight(mut self, line: usize, range: Range<usize>) -> Self {
+        self.highlight = Some((line, range));
+        self
+    }
+
+    /// Prefer opening above the anchor, e.g. to keep the line below it
+    /// visible while typing.
+    pub fn above(mut self) -> Self {
+        self.prefer_above = true;
+        self
+    }
+
+    /// Lines word-wrapped to `width` columns.
+    pub fn wrapped_lines(&self, width: u16) -> Vec<String> {
+        self.lines
+            .iter()
+            .flat_map(|line| wrap_line(line, width.max(1) as usize))
+            .collect()
+    }
+
+    /// The highlight mapped onto [`Popup::wrapped_lines`]: a column range
+    /// for each wrapped row it touches.
+    pub fn highlight_spans(&self, width: u16) -> Vec<(usize, Range<usize>)> {
+        let Some((line, range)) = &self.highlight else {
+            return Vec::new();
+        };
+        let Some(text) = self.lines.get(*line) else {
+            return Vec::new();
+        };
+        let width = width.max(1) as usize;
+        let first_row: usize = self.lines[..*line]
+            .iter()
+            .map(|l| wrap_line(l, width).len())
+            .sum();
+        let chars: Vec<char> = text.chars().collect();
+        let mut spans = Vec::new();
+        // Rows after the first start past the spaces the wrap dropped.
+        let mut start = 0;
+        for (i, row) in wrap_line(text, width).iter().enumerate() {
+            if i > 0 {
+                while chars.get(start) == Some(&' ') {
+                    start += 1;
+                }
+            }
+            let len = row.chars().count();
+            let from = range.start.max(start);
+            let to = range.end.min(start + len);
+            if from < to {
+                spans.push((first_row + i, from - start..to - start));
+            }
+            start += len;
+        }
+        spans
+    }
+
+    /// Where to draw the popup for an anchor cell inside `bounds`.
+    ///
+    /// The popup opens below the anchor row when it fits, above it when
+    /// only that fits, and otherwise on whichever side has more room,
+    /// shrunk to that side. It is as wide as its content allows, at most
+    /// as wide as `bounds`, and shifted left to stay inside them. Anchors
+    /// outside `bounds` (e.g. after a resize) are clamped onto the edge.
+    ///
+    /// Returns an empty rect when `bounds` cannot hold a border around at
+    /// least one line of text.
+    pub fn placement(&self, anchor_col: u16, anchor_row: u16, bounds: Rect) -> Rect {
+        if bounds.width < MIN_SIZE || bounds.height < MIN_SIZE {
+            return Rect::new(bounds.x, bounds.y, 0, 0);
+        }
+        let content_w = self
+            .lines
+            .iter()
+            .map(|l| l.chars().count())
+            .chain(std::iter::once(self.title.chars().count() + 2))
+            .max()
+            .unwrap_or(0) as u16;
+        let width = content_w.saturating_add(2).clamp(MIN_SIZE, bounds.width);
+        let height = (self.wrapped_lines(width - 2).len() as u16).saturating_add(2);
+
+        let bottom = bounds.y + bounds.height;
+        let anchor_row = anchor_row.clamp(bounds.y, bottom - 1);
+        let below = bottom - (anchor_row + 1);
+        let above = anchor_row - bounds.y;
+        let use_below = if self.prefer_above {
+            height > above && (height <= below || below > above)
+        } else {
+            height <= below || (height > above && below >= above)
+        };
+        let (y, height) = if use_below {
+            (anchor_row + 1, height.min(below))
+        } else {
+            let h = height.min(above);
+            (anchor_row - h, h)
+        };
+        if height < MIN_SIZE {
+            return Rect::new(bounds.x, bounds.y, 0, 0);
+        }
+
+        let right = bounds.x + bounds.width;
+        let x = anchor_col.clamp(bounds.x, right - width);
+        Rect::new(x, y, width, height)
+    }
+}
+
+/// One line word-wrapped to `width` columns.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    for word in line.split(' ') {
+        let word_len = word.chars().count();
+        let cur_len = current.chars().count();
+        if cur_len > 0 && cur_len + 1 + word_len > width {
+            out.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+        // Hard-break words longer than the popup.
+        while current.chars().count() > width {
+            let head: String = current.chars().take(width).collect();
+            current = current.chars().skip(width).collect();
+            out.push(head);
+        }
+    }
+    out.push(current);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn popup(lines: &[&str]) -> Popup {
+        Popup::new("t", lines.iter().map(|l| l.to_string()).collect())
+    }
+
+    #[test]
+    fn wraps_on_word_boundaries_and_breaks_long_words() {
+        let p = popup(&["one two three", "abcdefghij"]);
+        assert_eq!(
+            p.wrapped_lines(7),
+            vec!["one two", "three", "abcdefg", "hij"]
+        );
+    }
+
+    #[test]
+    fn opens_below_when_it_fits() {
+        let p = popup(&["hello"]);
+        let area = p.placement(4, 2, Rect::new(0, 0, 40, 20));
+        assert_eq!(area, Rect::new(4, 3, 7, 3));
+    }
+
+    #[test]
+    fn opens_above_near_bottom_and_stays_in_bounds() {
+        let p = popup(&["hello", "world"]);
+        let area = p.placement(38, 18, Rect::new(0, 0, 40, 20));
+        assert_eq!(area, Rect::new(33, 14, 7, 4));
+    }
+
+    #[test]
+    fn prefers_above_when_asked() {
+        let p = popup(&["hello"]).above();
+        let area = p.placement(4, 5, Rect::new(0, 0, 40, 20));
+        assert_eq!(area, Rect::new(4, 2, 7, 3));
+        // No room above: falls back to below.
+        let area = p.placement(4, 1, Rect::new(0, 0, 40, 20));
+        assert_eq!(area, Rect::new(4, 2, 7, 3));
+    }
+
+    #[test]
+    fn highlight_follows_wrapped_rows() {
+        let p = popup(&["title", "fn f(first: u8, second: u8)"]).with_highlight(1, 16..26);
+        assert_eq!(p.highlight_spans(40), vec![(1, 16..26)]);
+        // Wrapped at 12: "fn f(first:" / "u8, second:" / "u8)".
+        assert_eq!(
+            p.wrapped_lines(12)[1..],
+            ["fn f(first:", "u8, second:", "u8)"]
+        );
+        assert_eq!(p.highlight_spans(12), vec![(2, 4..11), (3, 0..2)]);
+    }
+
+    #[test]
+    fn shrinks_to_the_larger_side_when_neither_fits() {
+        let p = popup(&["1", "2", "3", "4", "5", "6"]);
+        // 3 rows above the anchor, 5 below: the popup takes the bottom.
+        let area = p.placement(0, 3, Rect::new(0, 0, 40, 9));
+        assert_eq!(area, Rect::new(0, 4, 5, 5));
+        // Flipped: more room above.
+        let area = p.placement(0, 5, Rect::new(0, 0, 40, 9));
+        assert_eq!(area, Rect::new(0, 0, 5, 5));
+    }
+
+    #[test]
+    fn clamps_to_small_and_resized_bounds() {
+        let p = popup(&["a long line of popup text"]);
+        // Narrower than the content: as wide as the screen, text wraps.
+        let area = p.placement(30, 0, Rect::new(0, 0, 10, 8));
+        assert_eq!(area, Rect::new(0, 1, 10, 6));
+        // An anchor left outside the bounds by a resize is pulled back in.
+        let area = p.placement(70, 30, Rect::new(0, 0, 40, 10));
+        assert!(area.x + area.width <= 40 && area.y + area.height <= 10);
+        assert!(area.height >= MIN_SIZE);
+        // Too small for even a one-line popup.
+        assert_eq!(p.placement(0, 0, Rect::new(0, 0, 2, 20)).width, 0);
+        assert_eq!(p.placement(0, 1, Rect::new(0, 0, 40, 3)).width, 0);
+    }
+}
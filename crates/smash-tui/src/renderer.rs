@@ -1,17 +1,71 @@
 use smash_core::buffer::Buffer;
+use smash_core::diff::DiffKind;
+use smash_core::grapheme;
+use smash_core::links::find_links;
+use smash_core::position::{Position, Range};
 use smash_syntax::{HighlightEngine, HighlightSpan};
+use smash_terminal::{TerminalCell, TerminalGrid};
 
 use crate::backend::TerminalBackend;
 use crate::cell::Cell;
 use crate::error::TuiError;
+use crate::file_tree::FileTree;
+use crate::finder::{finder_list_rows, finder_list_width, FinderView};
+use crate::minimap::Minimap;
 use crate::pane::Rect;
+use crate::picker::Picker;
+use crate::popup::Popup;
 use crate::screen::Screen;
-use crate::style::Style;
+use crate::scrollbar::{scrollbar_row, scrollbar_thumb, ScrollbarMark};
+use crate::style::{Attributes, Color, Style};
 use crate::theme::Theme;
 use crate::viewport::Viewport;
 
-/// Number of columns reserved for diagnostic icon + line numbers + separator.
-const LINE_NUMBER_WIDTH: u16 = 7; // "● 1234 " = icon(1) + space(1) + digits(4) + space(1)
+/// Minimum number of columns reserved for diagnostic icon + line numbers +
+/// separator: `"● 1234 "` = icon(1) + space(1) + digits(4) + space(1).
+/// Buffers with more than 9999 lines get a wider gutter; see [`gutter_width`].
+pub const LINE_NUMBER_WIDTH: u16 = 7;
+
+/// Columns taken by the diagnostic icon and the space after it.
+const DIAGNOSTIC_COLUMN_WIDTH: u16 = 2;
+
+/// Blank columns between the end of a line and its annotation.
+const ANNOTATION_GAP: usize = 3;
+
+/// How line numbers are drawn in the gutter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineNumbers {
+    /// No gutter at all.
+    Off,
+    /// The 1-based number of every line.
+    #[default]
+    Absolute,
+    /// Distance from the cursor line, which shows 0.
+    Relative,
+    /// Distance from the cursor line, with the cursor line's absolute number.
+    Hybrid,
+}
+
+impl LineNumbers {
+    /// The number to draw for `line` when the cursor is on `cursor_line`.
+    fn label(self, line: usize, cursor_line: usize) -> usize {
+        match self {
+            LineNumbers::Relative => line.abs_diff(cursor_line),
+            LineNumbers::Hybrid if line != cursor_line => line.abs_diff(cursor_line),
+            _ => line + 1,
+        }
+    }
+}
+
+/// Width of the gutter for a buffer of `line_count` lines: wide enough for
+/// the largest line number, and never narrower than [`LINE_NUMBER_WIDTH`].
+pub fn gutter_width(mode: LineNumbers, line_count: usize) -> u16 {
+    if mode == LineNumbers::Off {
+        return 0;
+    }
+    let digits = line_count.max(1).ilog10() as u16 + 1;
+    (DIAGNOSTIC_COLUMN_WIDTH + digits + 1).max(LINE_NUMBER_WIDTH)
+}
 
 /// The severity of a diagnostic on a single line, used for gutter rendering.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,9 +76,48 @@ pub enum GutterDiagnostic {
     Hint,
 }
 
+/// The character drawn for each diagnostic severity, so severities can
+/// be told apart by shape and not only by colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiagnosticSigns {
+    pub error: char,
+    pub warning: char,
+    pub info: char,
+    pub hint: char,
+}
+
+impl DiagnosticSigns {
+    pub fn sign(&self, severity: GutterDiagnostic) -> char {
+        match severity {
+            GutterDiagnostic::Error => self.error,
+            GutterDiagnostic::Warning => self.warning,
+            GutterDiagnostic::Information => self.info,
+            GutterDiagnostic::Hint => self.hint,
+        }
+    }
+}
+
+impl Default for DiagnosticSigns {
+    fn default() -> Self {
+        Self {
+            error: '\u{25cf}',   // ●
+            warning: '\u{25b2}', // ▲
+            info: '\u{25c6}',    // ◆
+            hint: '\u{25cb}',    // ○
+        }
+    }
+}
+
 pub struct Renderer {
     screen: Screen,
     prev_screen: Screen,
+    diagnostic_signs: DiagnosticSigns,
+    /// Text columns marked with a ruler.
+    rulers: Vec<usize>,
+    /// Give the primary cursor's line the current-line background.
+    highlight_current_line: bool,
+    /// Columns per indent level when indent guides are drawn.
+    indent_guides: Option<usize>,
 }
 
 impl Renderer {
@@ -32,9 +125,35 @@ impl Renderer {
         Self {
             screen: Screen::new(width, height),
             prev_screen: Screen::new(width, height),
+            diagnostic_signs: DiagnosticSigns::default(),
+            rulers: Vec::new(),
+            highlight_current_line: false,
+            indent_guides: None,
         }
     }
 
+    /// Use `signs` for diagnostics in the gutter.
+    pub fn set_diagnostic_signs(&mut self, signs: DiagnosticSigns) {
+        self.diagnostic_signs = signs;
+    }
+
+    /// Mark the text columns in `rulers` (0-based), such as git's 50 and
+    /// 72 character limits, on every buffer line.
+    pub fn set_rulers(&mut self, rulers: Vec<usize>) {
+        self.rulers = rulers;
+    }
+
+    /// Give the primary cursor's line a background of its own.
+    pub fn set_highlight_current_line(&mut self, on: bool) {
+        self.highlight_current_line = on;
+    }
+
+    /// Draw a guide at each indent level of leading whitespace, every
+    /// `indent_width` spaces or at each tab; `None` draws none.
+    pub fn set_indent_guides(&mut self, indent_width: Option<usize>) {
+        self.indent_guides = indent_width.filter(|&w| w > 0);
+    }
+
     pub fn resize(&mut self, width: u16, height: u16) {
         self.screen.resize(width, height);
         self.prev_screen.resize(width, height);
@@ -52,17 +171,39 @@ impl Renderer {
         area: Rect,
         theme: &Theme,
         highlighter: Option<&dyn HighlightEngine>,
-        show_line_numbers: bool,
+        line_numbers: LineNumbers,
         line_diagnostics: &[Option<GutterDiagnostic>],
     ) {
-        let gutter_w = if show_line_numbers {
-            LINE_NUMBER_WIDTH
-        } else {
-            0
-        };
+        let show_line_numbers = line_numbers != LineNumbers::Off;
+        let line_count = buffer.line_count();
+        let gutter_w = gutter_width(line_numbers, line_count);
+        let digits_w = (gutter_w.saturating_sub(DIAGNOSTIC_COLUMN_WIDTH + 1)) as usize;
+        let cursor_line = buffer.cursors().primary().position().line;
         let text_area_start = area.x + gutter_w;
         let text_area_width = area.width.saturating_sub(gutter_w);
-        let line_count = buffer.line_count();
+        let selections: Vec<Range> = buffer
+            .cursors()
+            .iter()
+            .filter_map(|c| c.selection_range())
+            .filter(|r| !r.is_empty())
+            .collect();
+        let selection_bg = theme.selection_style().bg;
+        let bottom_line = viewport.top_line() + area.height as usize;
+        let search_matches: Vec<Range> = buffer
+            .search()
+            .matches()
+            .iter()
+            .map(|m| m.range)
+            .filter(|r| r.end.line >= viewport.top_line() && r.start.line < bottom_line)
+            .collect();
+        let search_bg = theme.search_match_style().bg;
+        // The terminal cursor marks the primary; the others are drawn.
+        let secondary_carets: Vec<Position> = buffer
+            .cursors()
+            .iter()
+            .skip(1)
+            .map(|c| c.position())
+            .collect();
 
         for screen_row in 0..area.height {
             let buf_line = viewport.top_line() + screen_row as usize;
@@ -74,21 +215,9 @@ impl Renderer {
                     // Diagnostic icon column (columns 0-1: icon + space)
                     let diag = line_diagnostics.get(screen_row as usize).and_then(|d| *d);
                     let (icon_ch, icon_style) = match diag {
-                        Some(GutterDiagnostic::Error) => (
-                            '\u{25cf}', // ●
-                            theme.diagnostic_error_style(),
-                        ),
-                        Some(GutterDiagnostic::Warning) => (
-                            '\u{25b2}', // ▲
-                            theme.diagnostic_warning_style(),
-                        ),
-                        Some(GutterDiagnostic::Information) => (
-                            '\u{25c6}', // ◆
-                            theme.diagnostic_info_style(),
-                        ),
-                        Some(GutterDiagnostic::Hint) => (
-                            '\u{25cb}', // ○
-                            theme.diagnostic_hint_style(),
+                        Some(severity) => (
+                            self.diagnostic_signs.sign(severity),
+                            theme.diagnostic_style(severity),
                         ),
                         None => (' ', theme.line_number_style()),
                     };
@@ -96,8 +225,9 @@ impl Renderer {
                     self.screen
                         .set(area.x + 1, y, Cell::new(' ', theme.line_number_style()));
 
-                    // Line number (columns 2-6: 4 digits + space)
-                    let num_str = format!("{:>4} ", buf_line + 1);
+                    // Line number, right-aligned, then a space
+                    let num_str =
+                        format!("{:>digits_w$} ", line_numbers.label(buf_line, cursor_line));
                     let style = theme.line_number_style();
                     for (i, ch) in num_str.chars().enumerate() {
                         let x = area.x + 2 + i as u16;
@@ -119,29 +249,133 @@ impl Renderer {
                 let spans: Vec<HighlightSpan> = highlighter
                     .map(|h| h.highlight_line(display))
                     .unwrap_or_default();
+                let links = find_links(display);
 
-                // Render each character
+                // Render each grapheme; `left_col` and screen positions
+                // count cells, and wide characters take two.
                 let left_col = viewport.left_col();
-                for (i, ch) in display.chars().enumerate() {
-                    if i < left_col {
+                let mut byte = 0;
+                let mut line_width = 0;
+                for (i, g) in grapheme::graphemes(display) {
+                    let offset = byte;
+                    byte += g.len();
+                    let start = line_width;
+                    let width = grapheme::grapheme_width(g);
+                    line_width += width;
+                    if line_width <= left_col {
                         continue;
                     }
-                    let col_on_screen = (i - left_col) as u16;
-                    if col_on_screen >= text_area_width {
+                    let col_on_screen = start.saturating_sub(left_col);
+                    if col_on_screen >= text_area_width as usize {
                         break;
                     }
-                    let x = text_area_start + col_on_screen;
+                    let x = text_area_start + col_on_screen as u16;
 
-                    let style = find_style_for_offset(i, &spans, theme);
-                    self.screen.set(x, y, Cell::new(ch, style));
+                    let mut style = find_style_for_offset(offset, &spans, theme);
+                    if links.iter().any(|l| l.contains(i)) {
+                        style = style.underline();
+                    }
+                    let here = Position::new(buf_line, i);
+                    if is_selected(here, &selections) {
+                        style = style.bg(selection_bg);
+                    } else if is_selected(here, &search_matches) {
+                        style = style.bg(search_bg);
+                    }
+                    // Half a wide character at either edge shows as a blank.
+                    if start < left_col || col_on_screen + width > text_area_width as usize {
+                        self.screen.set(x, y, Cell::new(' ', style));
+                    } else {
+                        self.screen.put_grapheme(x, y, g, style);
+                    }
                 }
 
                 // Clear rest of line
-                let chars_written = display.len().saturating_sub(left_col);
-                let start = (chars_written as u16).min(text_area_width);
+                let display_len = display.chars().count();
+                let cells_written = line_width.saturating_sub(left_col);
+                let start = (cells_written.min(text_area_width as usize)) as u16;
                 for col in start..text_area_width {
                     let x = text_area_start + col;
-                    self.screen.set(x, y, Cell::new(' ', theme.default_style()));
+                    let mut style = theme.default_style();
+                    // Mark a selected line break with one highlighted cell.
+                    if col == start
+                        && line_width >= left_col
+                        && buf_line + 1 < line_count
+                        && is_selected(Position::new(buf_line, display_len), &selections)
+                    {
+                        style = style.bg(selection_bg);
+                    }
+                    self.screen.set(x, y, Cell::new(' ', style));
+                }
+
+                if let Some(indent_width) = self.indent_guides {
+                    let guides = match indent_guide_cols(display, indent_width) {
+                        // Blank lines continue the guides around them.
+                        None => blank_line_guides(buffer, buf_line, indent_width),
+                        Some(cols) => cols,
+                    };
+                    let guide_fg = theme.indent_guide_style().fg;
+                    for guide in guides {
+                        let Some(col) = guide.checked_sub(left_col) else {
+                            continue;
+                        };
+                        if col >= text_area_width as usize {
+                            continue;
+                        }
+                        let x = text_area_start + col as u16;
+                        let Some(cell) = self.screen.get(x, y) else {
+                            continue;
+                        };
+                        if cell.ch == ' ' || cell.ch == '\t' {
+                            let style = cell.style.fg(guide_fg);
+                            self.screen.set(x, y, Cell::new('\u{2502}', style));
+                            // │
+                        }
+                    }
+                }
+
+                for &ruler in &self.rulers {
+                    let Some(col) = ruler.checked_sub(left_col) else {
+                        continue;
+                    };
+                    if col >= text_area_width as usize {
+                        continue;
+                    }
+                    let x = text_area_start + col as u16;
+                    let Some(cell) = self.screen.get(x, y).cloned() else {
+                        continue;
+                    };
+                    // Selections and matches stay visible over a ruler.
+                    if cell.style.bg == theme.default_style().bg {
+                        let style = cell.style.bg(theme.ruler_style().bg);
+                        self.screen.set(x, y, cell.restyled(style));
+                    }
+                }
+
+                if self.highlight_current_line && buf_line == cursor_line {
+                    let line_bg = theme.current_line_style().bg;
+                    for col in 0..text_area_width {
+                        let x = text_area_start + col;
+                        let Some(cell) = self.screen.get(x, y).cloned() else {
+                            continue;
+                        };
+                        // Selections, matches and rulers show through.
+                        if cell.style.bg == theme.default_style().bg {
+                            self.screen.set(x, y, cell.restyled(cell.style.bg(line_bg)));
+                        }
+                    }
+                }
+
+                for caret in secondary_carets.iter().filter(|p| p.line == buf_line) {
+                    let caret_x = grapheme::display_col(display, caret.col);
+                    let Some(col) = caret_x.checked_sub(left_col) else {
+                        continue;
+                    };
+                    if col >= text_area_width as usize {
+                        continue;
+                    }
+                    let x = text_area_start + col as u16;
+                    let cell = self.screen.get(x, y).cloned().unwrap_or_default();
+                    self.screen.set(x, y, cell.restyled(theme.cursor_style()));
                 }
             } else {
                 // Past end of buffer — tilde lines
@@ -151,7 +385,7 @@ impl Renderer {
                         .set(area.x, y, Cell::new(' ', theme.line_number_style()));
                     self.screen
                         .set(area.x + 1, y, Cell::new(' ', theme.line_number_style()));
-                    let tilde = format!("{:>4} ", "~");
+                    let tilde = format!("{:>digits_w$} ", "~");
                     let style = theme.line_number_style();
                     for (i, ch) in tilde.chars().enumerate() {
                         let x = area.x + 2 + i as u16;
@@ -207,17 +441,629 @@ impl Renderer {
         }
     }
 
-    /// Flush diff to backend.
-    pub fn flush_to_backend(&mut self, backend: &mut dyn TerminalBackend) -> Result<(), TuiError> {
-        let diff = self.prev_screen.diff(&self.screen);
-        for (col, row, cell) in &diff {
-            backend.write_cell(*col, *row, cell)?;
+    /// Draw `len` characters of the status bar text, starting `start`
+    /// characters into it, in `style` over the status bar background.
+    pub fn emphasize_status(&mut self, area: Rect, start: usize, len: usize, style: Style) {
+        let y = area.y + area.height.saturating_sub(1);
+        // The text follows a one-column margin.
+        for i in start..start + len {
+            let x = area.x + 1 + i as u16;
+            if x >= area.x + area.width {
+                break;
+            }
+            let Some(cell) = self.screen.get(x, y).cloned() else {
+                break;
+            };
+            let style = if style.bg == Color::Reset {
+                style.bg(cell.style.bg)
+            } else {
+                style
+            };
+            self.screen.set(x, y, cell.restyled(style));
+        }
+    }
+
+    /// Draw `text` as dimmed virtual text after the end of buffer line
+    /// `buf_line`, as laid out by [`Renderer::render_buffer`]. Nothing is
+    /// drawn if the line is off screen or there is no room after it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_line_annotation(
+        &mut self,
+        buffer: &Buffer,
+        viewport: &Viewport,
+        area: Rect,
+        line_numbers: LineNumbers,
+        buf_line: usize,
+        text: &str,
+        theme: &Theme,
+    ) {
+        let Some(row) = buf_line.checked_sub(viewport.top_line()) else {
+            return;
+        };
+        if row >= area.height as usize {
+            return;
+        }
+        let line_width = buffer.line(buf_line).map_or(0, |slice| {
+            let line = slice.to_string();
+            grapheme::display_width(line.trim_end_matches('\n').trim_end_matches('\r'))
+        });
+        let gutter_w = gutter_width(line_numbers, buffer.line_count()) as usize;
+        // Leave a gap after the text, and start in view if it is
+        // scrolled away.
+        let col = gutter_w + line_width.saturating_sub(viewport.left_col()) + ANNOTATION_GAP;
+        let style = theme
+            .default_style()
+            .fg(theme.line_number_style().fg)
+            .italic()
+            .dim();
+        let y = area.y + row as u16;
+        for (i, ch) in text.chars().enumerate() {
+            let x = col + i;
+            if x >= area.width as usize {
+                break;
+            }
+            self.screen.set(area.x + x as u16, y, Cell::new(ch, style));
+        }
+    }
+
+    /// Render a read-only text panel: a title row in the status-bar style
+    /// followed by the last lines of `lines` that fit in `area`.
+    pub fn render_text_panel(&mut self, area: Rect, title: &str, lines: &[String], theme: &Theme) {
+        if area.height == 0 {
+            return;
+        }
+        let title_style = theme.status_bar_style();
+        let body_style = theme.default_style();
+        for row in 0..area.height {
+            let style = if row == 0 { title_style } else { body_style };
+            for col in 0..area.width {
+                self.screen
+                    .set(area.x + col, area.y + row, Cell::new(' ', style));
+            }
+        }
+        let title = format!(" {}", title);
+        for (i, ch) in title.chars().take(area.width as usize).enumerate() {
+            self.screen
+                .set(area.x + i as u16, area.y, Cell::new(ch, title_style));
+        }
+
+        let body_rows = (area.height - 1) as usize;
+        let start = lines.len().saturating_sub(body_rows);
+        for (row, line) in lines[start..].iter().enumerate() {
+            let y = area.y + 1 + row as u16;
+            for (i, ch) in line.chars().take(area.width as usize).enumerate() {
+                let ch = if ch == '\t' { ' ' } else { ch };
+                self.screen
+                    .set(area.x + i as u16, y, Cell::new(ch, body_style));
+            }
+        }
+    }
+
+    /// Render a list panel: a title row, then `lines` from `scroll` on.
+    /// Row `selected` is highlighted; `None` highlights nothing.
+    pub fn render_list_panel(
+        &mut self,
+        area: Rect,
+        title: &str,
+        lines: &[String],
+        selected: Option<usize>,
+        scroll: usize,
+        theme: &Theme,
+    ) {
+        let body_style = theme.default_style();
+        let style_of = |index: usize| {
+            if selected == Some(index) {
+                theme.selection_style()
+            } else {
+                body_style
+            }
+        };
+        self.render_panel_rows(area, title, lines, scroll, style_of, theme);
+    }
+
+    /// Render a diff as a list panel: hunk headers (`None`) dimmed, added
+    /// and removed lines in the theme's diff colours. Row `selected` gets
+    /// the selection background.
+    pub fn render_diff_panel(
+        &mut self,
+        area: Rect,
+        title: &str,
+        rows: &[(Option<DiffKind>, String)],
+        selected: Option<usize>,
+        scroll: usize,
+        theme: &Theme,
+    ) {
+        let body_style = theme.default_style();
+        let lines: Vec<String> = rows.iter().map(|(_, text)| text.clone()).collect();
+        let style_of = |index: usize| {
+            let style = match rows.get(index).and_then(|(kind, _)| *kind) {
+                None => body_style.fg(theme.line_number_style().fg),
+                Some(DiffKind::Context) => body_style,
+                Some(DiffKind::Added) => body_style.fg(theme.diff_added_style().fg),
+                Some(DiffKind::Removed) => body_style.fg(theme.diff_removed_style().fg),
+            };
+            if selected == Some(index) {
+                style.bg(theme.selection_style().bg)
+            } else {
+                style
+            }
+        };
+        self.render_panel_rows(area, title, &lines, scroll, style_of, theme);
+    }
+
+    /// A title row, then `lines` from `scroll` on, each row filled in the
+    /// style `style_of` gives for its line index.
+    fn render_panel_rows(
+        &mut self,
+        area: Rect,
+        title: &str,
+        lines: &[String],
+        scroll: usize,
+        style_of: impl Fn(usize) -> Style,
+        theme: &Theme,
+    ) {
+        if area.height == 0 {
+            return;
+        }
+        let title_style = theme.status_bar_style();
+        let body_style = theme.default_style();
+        for row in 0..area.height {
+            let style = if row == 0 { title_style } else { body_style };
+            for col in 0..area.width {
+                self.screen
+                    .set(area.x + col, area.y + row, Cell::new(' ', style));
+            }
+        }
+        let title = format!(" {}", title);
+        for (i, ch) in title.chars().take(area.width as usize).enumerate() {
+            self.screen
+                .set(area.x + i as u16, area.y, Cell::new(ch, title_style));
+        }
+
+        let body_rows = (area.height - 1) as usize;
+        let visible = lines.iter().enumerate().skip(scroll).take(body_rows);
+        for (row, (index, line)) in visible.enumerate() {
+            let y = area.y + 1 + row as u16;
+            let style = style_of(index);
+            for col in 0..area.width {
+                self.screen.set(area.x + col, y, Cell::new(' ', style));
+            }
+            for (i, ch) in line.chars().take(area.width as usize).enumerate() {
+                let ch = if ch == '\t' { ' ' } else { ch };
+                self.screen.set(area.x + i as u16, y, Cell::new(ch, style));
+            }
+        }
+    }
+
+    /// Draw `minimap` into `area`, one row per screen row, with the rows
+    /// for the lines in `viewport` highlighted. Text takes the colour of
+    /// its scope, or of the row's diagnostic if it has one.
+    pub fn render_minimap(
+        &mut self,
+        area: Rect,
+        minimap: &Minimap,
+        viewport: &Viewport,
+        theme: &Theme,
+    ) {
+        let in_view = viewport.top_line()..viewport.bottom_line().max(viewport.top_line() + 1);
+        for row in 0..area.height {
+            let y = area.y + row;
+            let minimap_row = minimap.rows().get(row as usize);
+            let mut base = theme.default_style();
+            if minimap_row
+                .is_some_and(|r| r.lines.start < in_view.end && in_view.start < r.lines.end)
+            {
+                base = base.bg(theme.minimap_viewport_style().bg);
+            }
+            for col in 0..area.width {
+                let x = area.x + col;
+                let cell = minimap_row.and_then(|r| r.cells.get(col as usize));
+                let Some((cell, minimap_row)) = cell.zip(minimap_row) else {
+                    self.screen.set(x, y, Cell::new(' ', base));
+                    continue;
+                };
+                let fg = match (minimap_row.diagnostic, cell.scope) {
+                    (Some(severity), _) => theme.diagnostic_style(severity).fg,
+                    (None, Some(scope)) => theme.scope_style(scope).fg,
+                    (None, None) => theme.default_style().fg,
+                };
+                self.screen.set(x, y, Cell::new(cell.ch, base.fg(fg)));
+            }
+        }
+    }
+
+    /// Draw a scrollbar for a buffer of `line_count` lines into `area`:
+    /// the thumb covers the rows for the lines in `viewport`, and each
+    /// row shows the most pressing of the `marks` on its lines.
+    pub fn render_scrollbar(
+        &mut self,
+        area: Rect,
+        line_count: usize,
+        viewport: &Viewport,
+        marks: &[(usize, ScrollbarMark)],
+        theme: &Theme,
+    ) {
+        let height = area.height as usize;
+        let thumb = scrollbar_thumb(
+            viewport.top_line(),
+            viewport.visible_lines(),
+            line_count,
+            height,
+        );
+        let mut rows: Vec<Option<ScrollbarMark>> = vec![None; height];
+        for &(line, mark) in marks {
+            let Some(row) = rows.get_mut(scrollbar_row(line, line_count, height)) else {
+                continue;
+            };
+            if !row.is_some_and(|shown| shown.priority() <= mark.priority()) {
+                *row = Some(mark);
+            }
+        }
+        for (row, mark) in rows.into_iter().enumerate() {
+            let base = if thumb.contains(&row) {
+                theme.scrollbar_thumb_style()
+            } else {
+                theme.scrollbar_track_style()
+            };
+            let (ch, fg) = match mark {
+                None => (' ', base.fg),
+                Some(ScrollbarMark::Diagnostic(severity)) => {
+                    ('\u{2501}', theme.diagnostic_style(severity).fg) // ━
+                }
+                Some(ScrollbarMark::SearchMatch) => ('\u{2501}', theme.search_match_style().bg),
+                Some(ScrollbarMark::Added) => ('\u{2590}', theme.diff_added_style().fg), // ▐
+                Some(ScrollbarMark::Modified) => ('\u{2590}', theme.diff_modified_style().fg),
+                Some(ScrollbarMark::Removed) => ('\u{2590}', theme.diff_removed_style().fg),
+            };
+            for col in 0..area.width {
+                self.screen.set(
+                    area.x + col,
+                    area.y + row as u16,
+                    Cell::new(ch, base.fg(fg)),
+                );
+            }
+        }
+    }
+
+    /// Draw the file tree sidebar into `area`: a title row with the root
+    /// directory's name, then one row per entry, and a border on the right.
+    /// The selection is highlighted while the tree has focus.
+    pub fn render_file_tree(&mut self, area: Rect, tree: &FileTree, focused: bool, theme: &Theme) {
+        if area.width < 2 || area.height == 0 {
+            return;
+        }
+        let title_style = theme.status_bar_style();
+        let body_style = theme.default_style();
+        let border_x = area.x + area.width - 1;
+        for row in 0..area.height {
+            let style = if row == 0 { title_style } else { body_style };
+            for x in area.x..border_x {
+                self.screen.set(x, area.y + row, Cell::new(' ', style));
+            }
+            self.screen.set(
+                border_x,
+                area.y + row,
+                Cell::new('│', theme.line_number_style()),
+            );
+        }
+        let inner_w = (area.width - 1) as usize;
+        let root = tree
+            .root()
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| tree.root().display().to_string());
+        for (i, ch) in format!(" {}", root).chars().take(inner_w).enumerate() {
+            self.screen
+                .set(area.x + i as u16, area.y, Cell::new(ch, title_style));
+        }
+
+        let rows = (area.height - 1) as usize;
+        let visible = tree
+            .entries()
+            .iter()
+            .enumerate()
+            .skip(tree.scroll())
+            .take(rows);
+        for (row, (index, entry)) in visible.enumerate() {
+            let y = area.y + 1 + row as u16;
+            let style = if focused && index == tree.selected_index() {
+                theme.selection_style()
+            } else {
+                body_style
+            };
+            let marker = match (entry.is_dir, entry.expanded) {
+                (true, true) => "\u{25be} ",  // ▾
+                (true, false) => "\u{25b8} ", // ▸
+                (false, _) => "  ",
+            };
+            let suffix = if entry.is_dir { "/" } else { "" };
+            let text = format!(
+                "{}{}{}{}",
+                "  ".repeat(entry.depth),
+                marker,
+                entry.name,
+                suffix
+            );
+            for x in area.x..border_x {
+                self.screen.set(x, y, Cell::new(' ', style));
+            }
+            for (i, ch) in text.chars().take(inner_w).enumerate() {
+                self.screen.set(area.x + i as u16, y, Cell::new(ch, style));
+            }
+        }
+    }
+
+    /// Draw `popup` with a border inside `area` (see [`Popup::placement`]).
+    /// Lines that do not fit are cut off, with `…` on the last row.
+    pub fn render_popup(&mut self, popup: &Popup, area: Rect, theme: &Theme) {
+        if area.width < 3 || area.height < 3 {
+            return;
+        }
+        let style = theme.status_bar_style();
+        let (right, bottom) = (area.x + area.width - 1, area.y + area.height - 1);
+        for y in area.y..=bottom {
+            for x in area.x..=right {
+                let ch = match (x == area.x, x == right, y == area.y, y == bottom) {
+                    (true, _, true, _) => '┌',
+                    (_, true, true, _) => '┐',
+                    (true, _, _, true) => '└',
+                    (_, true, _, true) => '┘',
+                    (_, _, true, _) | (_, _, _, true) => '─',
+                    (true, _, _, _) | (_, true, _, _) => '│',
+                    _ => ' ',
+                };
+                self.screen.set(x, y, Cell::new(ch, style));
+            }
+        }
+        let inner_w = area.width - 2;
+        let title = format!(" {} ", popup.title);
+        for (i, ch) in title.chars().take(inner_w as usize).enumerate() {
+            self.screen
+                .set(area.x + 1 + i as u16, area.y, Cell::new(ch, style));
+        }
+        let mut lines = popup.wrapped_lines(inner_w);
+        let rows = (area.height - 2) as usize;
+        if lines.len() > rows {
+            // Shrunk to fit the screen: show that there is more.
+            lines.truncate(rows);
+            lines[rows - 1] = "…".to_string();
+        }
+        let spans = popup.highlight_spans(inner_w);
+        let highlight = style.bold().underline();
+        for (row, line) in lines.iter().enumerate() {
+            let y = area.y + 1 + row as u16;
+            for (i, ch) in line.chars().take(inner_w as usize).enumerate() {
+                let ch = if ch == '\t' { ' ' } else { ch };
+                let emphasised = spans.iter().any(|(r, cols)| *r == row && cols.contains(&i));
+                let style = if emphasised { highlight } else { style };
+                self.screen
+                    .set(area.x + 1 + i as u16, y, Cell::new(ch, style));
+            }
+        }
+    }
+
+    /// Draw a picker into `area`: a border with the title, the query
+    /// line, a separator, then the matching items with the selection
+    /// highlighted. The match count is shown at the right of the query.
+    pub fn render_picker(&mut self, picker: &mut Picker, area: Rect, theme: &Theme) {
+        if area.width < 3 || area.height < 5 {
+            return;
+        }
+        let style = theme.status_bar_style();
+        let (right, bottom) = (area.x + area.width - 1, area.y + area.height - 1);
+        let separator = area.y + 2;
+        for y in area.y..=bottom {
+            for x in area.x..=right {
+                let ch = match (x == area.x, x == right, y == area.y, y == bottom) {
+                    (true, _, true, _) => '┌',
+                    (_, true, true, _) => '┐',
+                    (true, _, _, true) => '└',
+                    (_, true, _, true) => '┘',
+                    (true, _, _, _) if y == separator => '├',
+                    (_, true, _, _) if y == separator => '┤',
+                    (_, _, true, _) | (_, _, _, true) => '─',
+                    (true, _, _, _) | (_, true, _, _) => '│',
+                    _ if y == separator => '─',
+                    _ => ' ',
+                };
+                self.screen.set(x, y, Cell::new(ch, style));
+            }
+        }
+        let inner_w = (area.width - 2) as usize;
+        let title = format!(" {} ", picker.title());
+        for (i, ch) in title.chars().take(inner_w).enumerate() {
+            self.screen
+                .set(area.x + 1 + i as u16, area.y, Cell::new(ch, style));
+        }
+        let count = format!("{}/{}", picker.match_count(), picker.len());
+        let query = format!("> {}", picker.query());
+        for (i, ch) in query.chars().take(inner_w).enumerate() {
+            self.screen
+                .set(area.x + 1 + i as u16, area.y + 1, Cell::new(ch, style));
+        }
+        let count_len = count.chars().count();
+        if query.chars().count() + 1 + count_len <= inner_w {
+            let start = area.x + 1 + (inner_w - count_len) as u16;
+            for (i, ch) in count.chars().enumerate() {
+                self.screen
+                    .set(start + i as u16, area.y + 1, Cell::new(ch, style));
+            }
+        }
+
+        let rows = (area.height - 4) as usize;
+        for (row, (item, selected)) in picker.visible(rows).into_iter().enumerate() {
+            let y = separator + 1 + row as u16;
+            let row_style = if selected {
+                theme.selection_style()
+            } else {
+                style
+            };
+            for x in area.x + 1..right {
+                self.screen.set(x, y, Cell::new(' ', row_style));
+            }
+            let text = if item.detail.is_empty() {
+                item.label.clone()
+            } else {
+                format!("{}  {}", item.label, item.detail)
+            };
+            for (i, ch) in text.chars().take(inner_w).enumerate() {
+                let ch = if ch == '\t' { ' ' } else { ch };
+                self.screen
+                    .set(area.x + 1 + i as u16, y, Cell::new(ch, row_style));
+            }
+        }
+    }
+
+    /// Draw the file finder overlay into `area` (see
+    /// [`finder_placement`](crate::finder::finder_placement)): the query
+    /// line and status, the list with matched characters emphasised and
+    /// the selection highlighted, and, when wide enough, the preview
+    /// coloured by `highlighter`.
+    pub fn render_finder(
+        &mut self,
+        view: &FinderView,
+        area: Rect,
+        theme: &Theme,
+        highlighter: Option<&dyn HighlightEngine>,
+    ) {
+        if area.width < 3 || area.height < 5 {
+            return;
+        }
+        let style = theme.status_bar_style();
+        let (list_w, has_preview) = finder_list_width(area);
+        let divider = area.x + 1 + list_w as u16;
+        let (right, bottom) = (area.x + area.width - 1, area.y + area.height - 1);
+        let separator = area.y + 2;
+        for y in area.y..=bottom {
+            for x in area.x..=right {
+                let on_divider = has_preview && x == divider;
+                let ch = match (x == area.x, x == right, y == area.y, y == bottom) {
+                    (true, _, true, _) => '┌',
+                    (_, true, true, _) => '┐',
+                    (true, _, _, true) => '└',
+                    (_, true, _, true) => '┘',
+                    (true, _, _, _) if y == separator => '├',
+                    (_, true, _, _) if y == separator => '┤',
+                    _ if on_divider && y == separator => '┬',
+                    _ if on_divider && y == bottom => '┴',
+                    (_, _, true, _) | (_, _, _, true) => '─',
+                    (true, _, _, _) | (_, true, _, _) => '│',
+                    _ if y == separator => '─',
+                    _ if on_divider && y > separator => '│',
+                    _ => ' ',
+                };
+                self.screen.set(x, y, Cell::new(ch, style));
+            }
+        }
+        let inner_w = (area.width - 2) as usize;
+        for (i, ch) in " Find file ".chars().take(inner_w).enumerate() {
+            self.screen
+                .set(area.x + 1 + i as u16, area.y, Cell::new(ch, style));
+        }
+        let query = format!("> {}", view.query);
+        for (i, ch) in query.chars().take(inner_w).enumerate() {
+            self.screen
+                .set(area.x + 1 + i as u16, area.y + 1, Cell::new(ch, style));
+        }
+        let status_len = view.status.chars().count();
+        if query.chars().count() + 1 + status_len <= inner_w {
+            let start = area.x + 1 + (inner_w - status_len) as u16;
+            for (i, ch) in view.status.chars().enumerate() {
+                self.screen
+                    .set(start + i as u16, area.y + 1, Cell::new(ch, style));
+            }
+        }
+
+        let rows = finder_list_rows(area);
+        let list_end = if has_preview { divider } else { right };
+        for (row, item) in view.rows.iter().take(rows).enumerate() {
+            let y = separator + 1 + row as u16;
+            let row_style = if view.selected == Some(row) {
+                theme.selection_style()
+            } else {
+                style
+            };
+            for x in area.x + 1..list_end {
+                self.screen.set(x, y, Cell::new(' ', row_style));
+            }
+            for (i, (ch, matched)) in item.fitted(list_w).into_iter().enumerate() {
+                let ch_style = if matched {
+                    row_style.bold().underline()
+                } else {
+                    row_style
+                };
+                self.screen
+                    .set(area.x + 1 + i as u16, y, Cell::new(ch, ch_style));
+            }
+        }
+
+        if !has_preview {
+            return;
+        }
+        let preview_x = divider + 1;
+        let preview_w = right.saturating_sub(preview_x) as usize;
+        for (row, line) in view.preview.iter().take(rows).enumerate() {
+            let y = separator + 1 + row as u16;
+            let spans: Vec<HighlightSpan> = highlighter
+                .map(|h| h.highlight_line(line))
+                .unwrap_or_default();
+            for (col, (offset, ch)) in line.char_indices().take(preview_w).enumerate() {
+                let ch = if ch == '\t' { ' ' } else { ch };
+                let cell_style = find_style_for_offset(offset, &spans, theme);
+                self.screen
+                    .set(preview_x + col as u16, y, Cell::new(ch, cell_style));
+            }
+        }
+    }
+
+    /// Render the visible screen of a terminal emulator grid into `area`.
+    ///
+    /// Default colors fall back to the theme's default style.
+    pub fn render_terminal(&mut self, area: Rect, grid: &TerminalGrid, theme: &Theme) {
+        for row in 0..area.height {
+            self.render_terminal_row(area, grid, row, theme);
         }
-        backend.flush()?;
-        self.prev_screen = self.screen.clone();
+    }
+
+    /// Like [`Renderer::render_terminal`], but only repaint the grid rows
+    /// marked dirty. The rest of `area` must still hold the previous paint.
+    pub fn render_terminal_damage(&mut self, area: Rect, grid: &TerminalGrid, theme: &Theme) {
+        for row in grid.dirty_rows().filter(|&row| row < area.height) {
+            self.render_terminal_row(area, grid, row, theme);
+        }
+    }
+
+    fn render_terminal_row(&mut self, area: Rect, grid: &TerminalGrid, row: u16, theme: &Theme) {
+        let base = theme.default_style();
+        let size = grid.size();
+        for col in 0..area.width {
+            let cell = if row < size.rows && col < size.cols {
+                grid.get_cell(row, col)
+                    .map(|c| terminal_cell(c, base))
+                    .unwrap_or_else(|| Cell::new(' ', base))
+            } else {
+                Cell::new(' ', base)
+            };
+            self.screen.set(area.x + col, area.y + row, cell);
+        }
+    }
+
+    /// Write the cells that changed since the last frame to `backend`,
+    /// in runs of one style, without flushing it.
+    pub fn draw_to_backend(&mut self, backend: &mut dyn TerminalBackend) -> Result<(), TuiError> {
+        for (col, row, cells) in self.prev_screen.diff_runs(&self.screen) {
+            backend.write_run(col, row, &cells)?;
+        }
+        self.prev_screen.copy_from(&self.screen);
         Ok(())
     }
 
+    /// Draw the changes and flush them.
+    pub fn flush_to_backend(&mut self, backend: &mut dyn TerminalBackend) -> Result<(), TuiError> {
+        self.draw_to_backend(backend)?;
+        backend.flush()
+    }
+
     pub fn screen(&self) -> &Screen {
         &self.screen
     }
@@ -227,6 +1073,57 @@ impl Renderer {
     }
 }
 
+/// Lines searched each way for the indent of a blank line.
+const GUIDE_SCAN_LINES: usize = 100;
+
+/// Cells of `line`'s leading whitespace that get an indent guide: each
+/// tab, and every `indent_width`th space. `None` for a blank line.
+fn indent_guide_cols(line: &str, indent_width: usize) -> Option<Vec<usize>> {
+    let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+    if indent == line.len() {
+        return None;
+    }
+    let mut cols = Vec::new();
+    let mut spaces = 0;
+    for (col, ch) in line[..indent].chars().enumerate() {
+        if ch == '\t' {
+            cols.push(col);
+            spaces = 0;
+        } else {
+            if spaces % indent_width == 0 {
+                cols.push(col);
+            }
+            spaces += 1;
+        }
+    }
+    Some(cols)
+}
+
+/// Guides for blank line `line`: those of the less indented of the
+/// nearest non-blank lines above and below it, so a block's guides run
+/// on through the blank lines inside it.
+fn blank_line_guides(buffer: &Buffer, line: usize, indent_width: usize) -> Vec<usize> {
+    let guides_at = |l: usize| {
+        let text = buffer.line(l)?.to_string();
+        indent_guide_cols(text.trim_end_matches(['\n', '\r']), indent_width)
+    };
+    let above = (line.saturating_sub(GUIDE_SCAN_LINES)..line)
+        .rev()
+        .find_map(guides_at);
+    let below =
+        (line + 1..buffer.line_count().min(line + 1 + GUIDE_SCAN_LINES)).find_map(guides_at);
+    match (above, below) {
+        (Some(above), Some(below)) if above.len() <= below.len() => above,
+        (Some(_), Some(below)) => below,
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `pos` falls inside any of the (non-empty) selection ranges.
+fn is_selected(pos: Position, selections: &[Range]) -> bool {
+    selections.iter().any(|r| r.start <= pos && pos < r.end)
+}
+
 fn find_style_for_offset(byte_offset: usize, spans: &[HighlightSpan], theme: &Theme) -> Style {
     for span in spans {
         if byte_offset >= span.start && byte_offset < span.end {
@@ -236,10 +1133,39 @@ fn find_style_for_offset(byte_offset: usize, spans: &[HighlightSpan], theme: &Th
     theme.default_style()
 }
 
+/// Convert a terminal emulator cell into a screen cell.
+fn terminal_cell(cell: &TerminalCell, base: Style) -> Cell {
+    let color = |c: smash_terminal::Color, default: Color| match c {
+        smash_terminal::Color::Default => default,
+        smash_terminal::Color::Indexed(i) => Color::Indexed(i),
+        smash_terminal::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    };
+    let a = cell.attrs;
+    let mut attrs = Attributes::NONE;
+    for (on, flag) in [
+        (a.bold, Attributes::BOLD),
+        (a.dim, Attributes::DIM),
+        (a.italic, Attributes::ITALIC),
+        (a.underline, Attributes::UNDERLINE),
+        (a.reverse, Attributes::REVERSE),
+        (a.strikethrough, Attributes::STRIKETHROUGH),
+    ] {
+        if on {
+            attrs = attrs | flag;
+        }
+    }
+    let ch = if a.hidden { ' ' } else { cell.character };
+    Cell::new(
+        ch,
+        Style::new(color(cell.fg, base.fg), color(cell.bg, base.bg), attrs),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::backend::MockBackend;
+    use crate::picker::PickerItem;
     use crate::theme::default_dark_theme;
     use crate::viewport::Viewport;
     use smash_core::buffer::BufferId;
@@ -279,7 +1205,7 @@ mod tests {
         let vp = Viewport::new(24, 73);
         let area = Rect::new(0, 0, 80, 24);
         let theme = default_dark_theme();
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &[]);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
         // Gutter layout: "  1234 " = icon(1)+space(1)+digits(4)+space(1) = 7
         // No diagnostics, so col 0 = ' ', col 1 = ' ', cols 2-5 = "   1", col 6 = ' '
         let c0 = r.screen().get(0, 0).unwrap();
@@ -305,7 +1231,7 @@ mod tests {
         let vp = Viewport::new(24, 73);
         let area = Rect::new(0, 0, 80, 24);
         let theme = default_dark_theme();
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &[]);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
         // Text starts at col 7 (after 7-wide gutter)
         assert_eq!(r.screen().get(7, 0).unwrap().ch, 'H');
         assert_eq!(r.screen().get(8, 0).unwrap().ch, 'e');
@@ -321,7 +1247,7 @@ mod tests {
         let vp = Viewport::new(24, 80);
         let area = Rect::new(0, 0, 80, 24);
         let theme = default_dark_theme();
-        r.render_buffer(&buf, &vp, area, &theme, None, false, &[]);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Off, &[]);
         // Text starts at col 0
         assert_eq!(r.screen().get(0, 0).unwrap().ch, 'H');
         assert_eq!(r.screen().get(1, 0).unwrap().ch, 'i');
@@ -334,7 +1260,7 @@ mod tests {
         let vp = Viewport::new(5, 73);
         let area = Rect::new(0, 0, 80, 5);
         let theme = default_dark_theme();
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &[]);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
         // Row 2 (beyond buffer) should show tilde
         // Gutter: "     ~ " — tilde in digit area col 5
         assert_eq!(r.screen().get(5, 2).unwrap().ch, '~');
@@ -369,6 +1295,25 @@ mod tests {
         assert!(row_text.contains("[+]"));
     }
 
+    #[test]
+    fn emphasize_status_keeps_text_and_background() {
+        let mut r = Renderer::new(20, 2);
+        let area = Rect::new(0, 0, 20, 2);
+        let theme = default_dark_theme();
+        r.render_status_bar(area, "a.rs E:1", 0, 0, false, &theme);
+        let emphasis = Style::default().fg(Color::Red).bold();
+        r.emphasize_status(area, 5, 3, emphasis);
+        let cell = r.screen().get(6, 1).unwrap();
+        assert_eq!(cell.ch, 'E');
+        assert_eq!(cell.style.fg, Color::Red);
+        assert_eq!(cell.style.bg, theme.status_bar_style().bg);
+        assert!(cell.style.attrs.bold());
+        assert_eq!(
+            r.screen().get(5, 1).unwrap().style,
+            theme.status_bar_style()
+        );
+    }
+
     #[test]
     fn flush_to_backend_writes_diff() {
         let mut r = Renderer::new(10, 5);
@@ -381,6 +1326,27 @@ mod tests {
         assert_eq!(backend.flush_count, 1);
     }
 
+    #[test]
+    fn draw_to_backend_writes_runs_without_flushing() {
+        let mut r = Renderer::new(10, 5);
+        let mut backend = MockBackend::new(10, 5);
+        r.screen
+            .put_str(0, 0, "hello", Style::default().fg(Color::Red));
+        r.screen
+            .put_str(5, 0, "!", Style::default().fg(Color::Green));
+        r.draw_to_backend(&mut backend).unwrap();
+        assert_eq!(backend.read_row(0), "hello!");
+        assert_eq!(backend.run_count, 2);
+        assert_eq!(backend.flush_count, 0);
+
+        // Only what changed is written again.
+        r.screen
+            .put_char(1, 0, 'a', Style::default().fg(Color::Red));
+        r.draw_to_backend(&mut backend).unwrap();
+        assert_eq!(backend.read_row(0), "hallo!");
+        assert_eq!(backend.run_count, 3);
+    }
+
     #[test]
     fn flush_to_backend_no_diff_still_flushes() {
         let mut r = Renderer::new(10, 5);
@@ -416,6 +1382,218 @@ mod tests {
         assert_eq!(style, theme.default_style());
     }
 
+    #[test]
+    fn render_buffer_highlights_selection() {
+        use smash_core::cursor::Cursor;
+
+        let mut buf = make_buffer("Hello world\n");
+        *buf.cursors_mut().primary_mut() =
+            Cursor::with_selection(Position::new(0, 5), Position::new(0, 0));
+        let mut r = Renderer::new(80, 5);
+        let vp = Viewport::new(5, 73);
+        let area = Rect::new(0, 0, 80, 5);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        let selection_bg = theme.selection_style().bg;
+        // "Hello" (cols 7..12) is selected, the space after it is not
+        for x in 7..12 {
+            assert_eq!(r.screen().get(x, 0).unwrap().style.bg, selection_bg);
+        }
+        assert_eq!(
+            r.screen().get(12, 0).unwrap().style.bg,
+            theme.default_style().bg
+        );
+    }
+
+    #[test]
+    fn render_buffer_gives_wide_characters_two_cells() {
+        let buf = make_buffer("字e\u{301}x\n");
+        let mut r = Renderer::new(10, 1);
+        let area = Rect::new(0, 0, 10, 1);
+        let theme = default_dark_theme();
+        r.render_buffer(
+            &buf,
+            &Viewport::new(1, 10),
+            area,
+            &theme,
+            None,
+            LineNumbers::Off,
+            &[],
+        );
+        let cell = |x| r.screen().get(x, 0).unwrap().clone();
+        assert_eq!(cell(0).ch, '字');
+        assert!(cell(1).is_continuation());
+        assert_eq!(cell(2).ch, 'e');
+        assert_eq!(cell(2).combining.as_deref(), Some("\u{301}"));
+        assert_eq!(cell(3).ch, 'x');
+        assert_eq!(cell(4).ch, ' ');
+
+        // Scrolled one cell right, the cut-off wide character is blank.
+        let mut vp = Viewport::new(1, 10);
+        vp.set_left_col(1);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Off, &[]);
+        assert_eq!(r.screen().get(0, 0).unwrap().ch, ' ');
+        assert_eq!(r.screen().get(1, 0).unwrap().ch, 'e');
+    }
+
+    #[test]
+    fn render_buffer_highlights_search_matches() {
+        use smash_core::search::SearchQuery;
+
+        let mut buf = make_buffer("one two one\n");
+        let query = SearchQuery::Plain {
+            pattern: "one".to_string(),
+            case_sensitive: false,
+        };
+        buf.search_mut().set_query(query, "one two one\n");
+        let mut r = Renderer::new(80, 5);
+        let vp = Viewport::new(5, 73);
+        let area = Rect::new(0, 0, 80, 5);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        let search_bg = theme.search_match_style().bg;
+        // Text starts at col 7: "one" at 7..10 and 15..18, "two" between.
+        for x in (7..10).chain(15..18) {
+            assert_eq!(r.screen().get(x, 0).unwrap().style.bg, search_bg);
+        }
+        assert_ne!(r.screen().get(11, 0).unwrap().style.bg, search_bg);
+    }
+
+    #[test]
+    fn render_buffer_underlines_links() {
+        let buf = make_buffer("go to https://a.io or x.rs:3\n");
+        let mut r = Renderer::new(80, 5);
+        let vp = Viewport::new(5, 73);
+        let area = Rect::new(0, 0, 80, 5);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        // Text starts at col 7: the URL is at 13..25, the location 29..35.
+        let underlined = |x: u16| r.screen().get(x, 0).unwrap().style.attrs.underline();
+        assert!((13..25).chain(29..35).all(underlined));
+        assert!(!underlined(12) && !underlined(25) && !underlined(28));
+    }
+
+    #[test]
+    fn render_buffer_draws_rulers() {
+        let buf = make_buffer("short\n");
+        let mut r = Renderer::new(80, 5);
+        r.set_rulers(vec![2, 10]);
+        let vp = Viewport::new(5, 73);
+        let area = Rect::new(0, 0, 80, 5);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        let ruler_bg = theme.ruler_style().bg;
+        // Text starts at col 7; the ruler shows over text and past it.
+        assert_eq!(r.screen().get(9, 0).unwrap().ch, 'o');
+        assert_eq!(r.screen().get(9, 0).unwrap().style.bg, ruler_bg);
+        assert_eq!(r.screen().get(17, 0).unwrap().style.bg, ruler_bg);
+        assert_ne!(r.screen().get(10, 0).unwrap().style.bg, ruler_bg);
+    }
+
+    #[test]
+    fn render_buffer_highlights_the_cursor_line() {
+        let mut buf = make_buffer("ab\ncd\n");
+        buf.cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(1, 1));
+        let mut r = Renderer::new(80, 5);
+        r.set_rulers(vec![5]);
+        let vp = Viewport::new(5, 73);
+        let area = Rect::new(0, 0, 80, 5);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        let line_bg = theme.current_line_style().bg;
+        assert_ne!(r.screen().get(7, 1).unwrap().style.bg, line_bg);
+
+        r.set_highlight_current_line(true);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        assert_ne!(r.screen().get(7, 0).unwrap().style.bg, line_bg);
+        assert_eq!(r.screen().get(7, 1).unwrap().ch, 'c');
+        assert_eq!(r.screen().get(7, 1).unwrap().style.bg, line_bg);
+        assert_eq!(r.screen().get(79, 1).unwrap().style.bg, line_bg);
+        // The ruler still shows on the cursor line.
+        assert_eq!(
+            r.screen().get(12, 1).unwrap().style.bg,
+            theme.ruler_style().bg
+        );
+    }
+
+    #[test]
+    fn render_buffer_draws_indent_guides() {
+        let buf = make_buffer("fn f() {\n    if x {\n        y();\n\n    }\n\t\tz\n}\n");
+        let mut r = Renderer::new(80, 8);
+        r.set_indent_guides(Some(4));
+        let vp = Viewport::new(8, 73);
+        let area = Rect::new(0, 0, 80, 8);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        let guides = |row: u16| -> Vec<u16> {
+            (7..80)
+                .filter(|&x| r.screen().get(x, row).unwrap().ch == '\u{2502}')
+                .map(|x| x - 7)
+                .collect()
+        };
+        assert_eq!(guides(0), Vec::<u16>::new());
+        assert_eq!(guides(1), vec![0]);
+        assert_eq!(guides(2), vec![0, 4]);
+        // The blank line takes the guides of the less indented `}` below.
+        assert_eq!(guides(3), vec![0]);
+        assert_eq!(guides(5), vec![0, 1]);
+        assert_eq!(
+            r.screen().get(7, 1).unwrap().style.fg,
+            theme.indent_guide_style().fg
+        );
+        assert_eq!(r.screen().get(11, 1).unwrap().ch, 'i');
+    }
+
+    #[test]
+    fn render_buffer_highlights_selected_line_break() {
+        use smash_core::cursor::Cursor;
+
+        let mut buf = make_buffer("ab\ncd\n");
+        *buf.cursors_mut().primary_mut() =
+            Cursor::with_selection(Position::new(1, 1), Position::new(0, 1));
+        let mut r = Renderer::new(80, 5);
+        let vp = Viewport::new(5, 73);
+        let area = Rect::new(0, 0, 80, 5);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        let selection_bg = theme.selection_style().bg;
+        // 'b' and the line break after it are selected, 'a' is not
+        assert_ne!(r.screen().get(7, 0).unwrap().style.bg, selection_bg);
+        assert_eq!(r.screen().get(8, 0).unwrap().style.bg, selection_bg);
+        assert_eq!(r.screen().get(9, 0).unwrap().style.bg, selection_bg);
+        assert_ne!(r.screen().get(10, 0).unwrap().style.bg, selection_bg);
+        // 'c' on the next line is selected, 'd' is not
+        assert_eq!(r.screen().get(7, 1).unwrap().style.bg, selection_bg);
+        assert_ne!(r.screen().get(8, 1).unwrap().style.bg, selection_bg);
+    }
+
+    #[test]
+    fn render_buffer_draws_secondary_carets() {
+        use smash_core::cursor::Cursor;
+
+        let mut buf = make_buffer("ab\ncd\n");
+        buf.cursors_mut().add(Cursor::new(Position::new(1, 2)));
+        buf.cursors_mut().add(Cursor::with_selection(
+            Position::new(0, 1),
+            Position::new(0, 0),
+        ));
+        let mut r = Renderer::new(80, 5);
+        let vp = Viewport::new(5, 73);
+        let area = Rect::new(0, 0, 80, 5);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        // The primary (0,0) is left to the terminal cursor.
+        assert_ne!(r.screen().get(7, 0).unwrap().style, theme.cursor_style());
+        // A selecting cursor has its caret drawn where it moved to.
+        let caret = r.screen().get(8, 0).unwrap();
+        assert_eq!((caret.ch, caret.style), ('b', theme.cursor_style()));
+        // A caret past the end of a line gets a cell of its own.
+        let caret = r.screen().get(9, 1).unwrap();
+        assert_eq!((caret.ch, caret.style), (' ', theme.cursor_style()));
+    }
+
     #[test]
     fn render_buffer_with_viewport_offset() {
         let buf = make_buffer("Line0\nLine1\nLine2\nLine3\nLine4\n");
@@ -424,7 +1602,7 @@ mod tests {
         vp.set_top_line(2);
         let area = Rect::new(0, 0, 80, 3);
         let theme = default_dark_theme();
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &[]);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
         // Row 0 should show line 3 (buf_line=2)
         // Line number at cols 2-5: "   3"
         assert_eq!(r.screen().get(5, 0).unwrap().ch, '3');
@@ -432,8 +1610,73 @@ mod tests {
         assert_eq!(r.screen().get(7, 0).unwrap().ch, 'L');
     }
 
+    #[test]
+    fn gutter_width_grows_with_line_count() {
+        assert_eq!(gutter_width(LineNumbers::Off, 100_000), 0);
+        assert_eq!(gutter_width(LineNumbers::Absolute, 1), LINE_NUMBER_WIDTH);
+        assert_eq!(
+            gutter_width(LineNumbers::Absolute, 9_999),
+            LINE_NUMBER_WIDTH
+        );
+        assert_eq!(gutter_width(LineNumbers::Relative, 10_000), 8);
+        assert_eq!(gutter_width(LineNumbers::Hybrid, 1_234_567), 10);
+    }
+
+    #[test]
+    fn render_buffer_relative_and_hybrid_numbers() {
+        let mut buf = make_buffer("a\nb\nc\nd\n");
+        buf.cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(1, 0));
+        let mut r = Renderer::new(80, 4);
+        let vp = Viewport::new(4, 73);
+        let area = Rect::new(0, 0, 80, 4);
+        let theme = default_dark_theme();
+        let digits = |r: &Renderer| -> Vec<char> {
+            (0..4).map(|y| r.screen().get(5, y).unwrap().ch).collect()
+        };
+
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Relative, &[]);
+        assert_eq!(digits(&r), vec!['1', '0', '1', '2']);
+
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Hybrid, &[]);
+        assert_eq!(digits(&r), vec!['1', '2', '1', '2']);
+    }
+
+    #[test]
+    fn render_buffer_widens_gutter_for_long_files() {
+        let text: String = (0..10_000).map(|_| "x\n").collect();
+        let buf = make_buffer(&text);
+        let mut r = Renderer::new(80, 2);
+        let mut vp = Viewport::new(2, 72);
+        vp.set_top_line(9_999);
+        let area = Rect::new(0, 0, 80, 2);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
+        let number: String = (2..7).map(|x| r.screen().get(x, 0).unwrap().ch).collect();
+        assert_eq!(number, "10000");
+        assert_eq!(r.screen().get(8, 0).unwrap().ch, 'x');
+    }
+
     // ── Diagnostic gutter icon tests ────────────────────────────
 
+    #[test]
+    fn render_buffer_uses_configured_signs() {
+        let buf = make_buffer("line one\nline two\n");
+        let mut r = Renderer::new(80, 5);
+        r.set_diagnostic_signs(DiagnosticSigns {
+            error: 'E',
+            ..DiagnosticSigns::default()
+        });
+        let vp = Viewport::new(5, 73);
+        let area = Rect::new(0, 0, 80, 5);
+        let theme = default_dark_theme();
+        let diags = vec![Some(GutterDiagnostic::Error), Some(GutterDiagnostic::Hint)];
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &diags);
+        assert_eq!(r.screen().get(0, 0).unwrap().ch, 'E');
+        assert_eq!(r.screen().get(0, 1).unwrap().ch, '\u{25cb}');
+    }
+
     #[test]
     fn render_buffer_error_icon_in_gutter() {
         let buf = make_buffer("line one\nline two\n");
@@ -443,7 +1686,7 @@ mod tests {
         let theme = default_dark_theme();
         // Error on screen row 0 (first visible line)
         let diags = vec![Some(GutterDiagnostic::Error), None];
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &diags);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &diags);
         // Col 0 should be the error icon '●'
         assert_eq!(r.screen().get(0, 0).unwrap().ch, '\u{25cf}');
         assert_eq!(
@@ -462,7 +1705,7 @@ mod tests {
         let area = Rect::new(0, 0, 80, 5);
         let theme = default_dark_theme();
         let diags = vec![None, Some(GutterDiagnostic::Warning)];
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &diags);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &diags);
         // Row 0: no icon
         assert_eq!(r.screen().get(0, 0).unwrap().ch, ' ');
         // Row 1: warning icon '▲'
@@ -481,7 +1724,7 @@ mod tests {
         let area = Rect::new(0, 0, 80, 5);
         let theme = default_dark_theme();
         let diags = vec![Some(GutterDiagnostic::Information)];
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &diags);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &diags);
         assert_eq!(r.screen().get(0, 0).unwrap().ch, '\u{25c6}');
         assert_eq!(
             r.screen().get(0, 0).unwrap().style,
@@ -497,7 +1740,7 @@ mod tests {
         let area = Rect::new(0, 0, 80, 5);
         let theme = default_dark_theme();
         let diags = vec![Some(GutterDiagnostic::Hint)];
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &diags);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &diags);
         assert_eq!(r.screen().get(0, 0).unwrap().ch, '\u{25cb}');
         assert_eq!(
             r.screen().get(0, 0).unwrap().style,
@@ -512,7 +1755,7 @@ mod tests {
         let vp = Viewport::new(5, 73);
         let area = Rect::new(0, 0, 80, 5);
         let theme = default_dark_theme();
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &[]);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &[]);
         // All icon columns should be space
         for row in 0..2 {
             assert_eq!(r.screen().get(0, row).unwrap().ch, ' ');
@@ -527,10 +1770,332 @@ mod tests {
         let area = Rect::new(0, 0, 80, 5);
         let theme = default_dark_theme();
         let diags = vec![Some(GutterDiagnostic::Error)];
-        r.render_buffer(&buf, &vp, area, &theme, None, true, &diags);
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Absolute, &diags);
         // Text still starts at col 7 regardless of diagnostic icon
         assert_eq!(r.screen().get(7, 0).unwrap().ch, 'A');
         assert_eq!(r.screen().get(8, 0).unwrap().ch, 'B');
         assert_eq!(r.screen().get(9, 0).unwrap().ch, 'C');
     }
+
+    #[test]
+    fn line_annotation_follows_the_line_dimmed() {
+        let buf = make_buffer("ab\ncdef\n");
+        let mut r = Renderer::new(20, 4);
+        let vp = Viewport::new(4, 20);
+        let area = Rect::new(0, 0, 20, 4);
+        let theme = default_dark_theme();
+        r.render_buffer(&buf, &vp, area, &theme, None, LineNumbers::Off, &[]);
+        r.render_line_annotation(&buf, &vp, area, LineNumbers::Off, 1, "Ada, 2024", &theme);
+        let row: String = (0..20).map(|x| r.screen().get(x, 1).unwrap().ch).collect();
+        assert_eq!(row, "cdef   Ada, 2024    ");
+        assert!(r.screen().get(7, 1).unwrap().style.attrs.dim());
+        // Clipped at the right edge; nothing for lines off screen.
+        r.render_line_annotation(
+            &buf,
+            &vp,
+            area,
+            LineNumbers::Off,
+            0,
+            "a long annotation",
+            &theme,
+        );
+        let row: String = (0..20).map(|x| r.screen().get(x, 0).unwrap().ch).collect();
+        assert_eq!(row, "ab   a long annotati");
+        r.render_line_annotation(&buf, &vp, area, LineNumbers::Off, 9, "x", &theme);
+    }
+
+    #[test]
+    fn render_diff_panel_colours_added_and_removed_lines() {
+        let mut r = Renderer::new(20, 4);
+        let theme = default_dark_theme();
+        let rows = vec![
+            (None, "@@ -1,1 +1,1 @@".to_string()),
+            (Some(DiffKind::Removed), "-old".to_string()),
+            (Some(DiffKind::Added), "+new".to_string()),
+        ];
+        r.render_diff_panel(Rect::new(0, 0, 20, 4), "Diff", &rows, Some(2), 0, &theme);
+        let removed = r.screen().get(0, 2).unwrap();
+        assert_eq!(removed.ch, '-');
+        assert_eq!(removed.style.fg, theme.diff_removed_style().fg);
+        let added = r.screen().get(0, 3).unwrap();
+        assert_eq!(added.style.fg, theme.diff_added_style().fg);
+        assert_eq!(added.style.bg, theme.selection_style().bg);
+    }
+
+    #[test]
+    fn render_text_panel_shows_title_and_tail() {
+        let mut r = Renderer::new(20, 10);
+        let theme = default_dark_theme();
+        let lines: Vec<String> = (1..=5).map(|i| format!("line {}", i)).collect();
+        r.render_text_panel(Rect::new(0, 6, 20, 3), "output", &lines, &theme);
+
+        let row = |y: u16| -> String {
+            (0..20)
+                .map(|x| r.screen().get(x, y).unwrap().ch)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+        assert_eq!(row(6), " output");
+        assert_eq!(row(7), "line 4");
+        assert_eq!(row(8), "line 5");
+    }
+
+    #[test]
+    fn render_popup_draws_border_title_and_wrapped_text() {
+        let mut r = Renderer::new(20, 6);
+        let theme = default_dark_theme();
+        let popup = Popup::new("diag", vec!["bad thing here".to_string()]);
+        r.render_popup(&popup, Rect::new(1, 1, 12, 4), &theme);
+
+        let row = |y: u16| -> String {
+            (0..20)
+                .map(|x| r.screen().get(x, y).unwrap().ch)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+        assert_eq!(row(1), " ┌ diag ────┐");
+        assert_eq!(row(2), " │bad thing │");
+        assert_eq!(row(3), " │here      │");
+        assert_eq!(row(4), " └──────────┘");
+
+        // Shrunk below its content: the last row marks the cut.
+        let mut r = Renderer::new(20, 6);
+        r.render_popup(&popup, Rect::new(0, 0, 12, 3), &theme);
+        let row: String = (0..12).map(|x| r.screen().get(x, 1).unwrap().ch).collect();
+        assert_eq!(row, "│…         │");
+
+        // The highlighted characters are drawn bold.
+        let mut r = Renderer::new(20, 6);
+        let popup = popup.with_highlight(0, 4..9);
+        r.render_popup(&popup, Rect::new(1, 1, 12, 4), &theme);
+        let bold: String = (0..20)
+            .filter(|&x| r.screen().get(x, 2).unwrap().style.attrs.bold())
+            .map(|x| r.screen().get(x, 2).unwrap().ch)
+            .collect();
+        assert_eq!(bold, "thing");
+    }
+
+    #[test]
+    fn render_terminal_maps_colors_and_pads() {
+        let mut grid = TerminalGrid::new(4, 2);
+        grid.current_fg = smash_terminal::Color::Indexed(1);
+        grid.current_attrs.bold = true;
+        grid.write_char('h');
+        grid.current_fg = smash_terminal::Color::Default;
+        grid.current_attrs.bold = false;
+        grid.write_char('i');
+
+        let theme = default_dark_theme();
+        let mut r = Renderer::new(10, 5);
+        r.render_terminal(Rect::new(1, 1, 6, 3), &grid, &theme);
+
+        let h = r.screen().get(1, 1).unwrap();
+        assert_eq!(h.ch, 'h');
+        assert_eq!(h.style.fg, Color::Indexed(1));
+        assert!(h.style.attrs.bold());
+        let i = r.screen().get(2, 1).unwrap();
+        assert_eq!(i.ch, 'i');
+        assert_eq!(i.style.fg, theme.default_style().fg);
+        // Outside the grid but inside the area: padded with blanks.
+        assert_eq!(r.screen().get(6, 3).unwrap().ch, ' ');
+        assert_eq!(r.screen().get(6, 3).unwrap().style, theme.default_style());
+    }
+
+    #[test]
+    fn render_terminal_damage_repaints_only_dirty_rows() {
+        let theme = default_dark_theme();
+        let mut grid = TerminalGrid::new(4, 3);
+        let mut r = Renderer::new(4, 3);
+        r.render_terminal(Rect::new(0, 0, 4, 3), &grid, &theme);
+        grid.clear_dirty();
+
+        grid.cursor_set_position(1, 0);
+        grid.write_char('x');
+        // Scribble on a clean row to show it is left alone.
+        r.screen.set(0, 2, Cell::new('?', theme.default_style()));
+        r.render_terminal_damage(Rect::new(0, 0, 4, 3), &grid, &theme);
+        assert_eq!(r.screen().get(0, 1).unwrap().ch, 'x');
+        assert_eq!(r.screen().get(0, 2).unwrap().ch, '?');
+    }
+
+    #[test]
+    fn render_minimap_highlights_the_lines_in_view() {
+        let buf = make_buffer(&"text\n".repeat(40));
+        let map = Minimap::build(&buf, None, 2, 10, &[(0, GutterDiagnostic::Error)]);
+        let theme = default_dark_theme();
+        let mut r = Renderer::new(4, 12);
+        let mut vp = Viewport::new(8, 70);
+        vp.set_top_line(10);
+        r.render_minimap(Rect::new(2, 0, 2, 12), &map, &vp, &theme);
+
+        let view_bg = theme.minimap_viewport_style().bg;
+        let bgs: Vec<bool> = (0..12)
+            .map(|y| r.screen().get(2, y).unwrap().style.bg == view_bg)
+            .collect();
+        // Five lines a row: rows 2 and 3 cover lines 10 to 19.
+        assert_eq!(
+            bgs,
+            [false, false, true, true, false, false, false, false, false, false, false, false]
+        );
+        let first = r.screen().get(2, 0).unwrap();
+        assert_eq!(first.ch, '\u{2593}');
+        assert_eq!(
+            first.style.fg,
+            theme.diagnostic_style(GutterDiagnostic::Error).fg
+        );
+        assert_eq!(r.screen().get(3, 1).unwrap().ch, ' ');
+        assert_eq!(r.screen().get(2, 11).unwrap().ch, ' ');
+    }
+
+    #[test]
+    fn render_scrollbar_draws_thumb_and_marks() {
+        let theme = default_dark_theme();
+        let mut r = Renderer::new(3, 10);
+        let mut vp = Viewport::new(20, 70);
+        vp.set_top_line(50);
+        let marks = [
+            (5, ScrollbarMark::SearchMatch),
+            (7, ScrollbarMark::Diagnostic(GutterDiagnostic::Error)),
+            (30, ScrollbarMark::Added),
+            (55, ScrollbarMark::Modified),
+        ];
+        r.render_scrollbar(Rect::new(2, 0, 1, 10), 100, &vp, &marks, &theme);
+
+        let cell = |y: u16| r.screen().get(2, y).unwrap().clone();
+        let thumb_bg = theme.scrollbar_thumb_style().bg;
+        let thumb: Vec<u16> = (0..10).filter(|&y| cell(y).style.bg == thumb_bg).collect();
+        assert_eq!(thumb, [5, 6]);
+        // The error outranks the search match on the same row.
+        assert_eq!(cell(0).ch, '\u{2501}');
+        assert_eq!(
+            cell(0).style.fg,
+            theme.diagnostic_style(GutterDiagnostic::Error).fg
+        );
+        assert_eq!(cell(3).ch, '\u{2590}');
+        assert_eq!(cell(3).style.fg, theme.diff_added_style().fg);
+        assert_eq!(cell(5).style.fg, theme.diff_modified_style().fg);
+        assert_eq!(cell(5).style.bg, thumb_bg);
+        assert_eq!(cell(8).ch, ' ');
+        assert_eq!(cell(8).style.bg, theme.scrollbar_track_style().bg);
+    }
+
+    #[test]
+    fn render_file_tree_draws_entries_and_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "").unwrap();
+        let tree = FileTree::new(dir.path().to_path_buf());
+        let theme = default_dark_theme();
+        let mut r = Renderer::new(12, 4);
+        r.render_file_tree(Rect::new(0, 0, 12, 4), &tree, true, &theme);
+
+        let row =
+            |y: u16| -> String { (0..12).map(|x| r.screen().get(x, y).unwrap().ch).collect() };
+        assert_eq!(row(1), "\u{25b8} src/     │");
+        assert_eq!(row(2), "  a.txt    │");
+        assert_eq!(r.screen().get(0, 1).unwrap().style, theme.selection_style());
+        assert_eq!(r.screen().get(0, 2).unwrap().style, theme.default_style());
+    }
+
+    #[test]
+    fn render_list_panel_scrolls_and_highlights() {
+        let theme = default_dark_theme();
+        let mut r = Renderer::new(10, 3);
+        let lines: Vec<String> = ["a.rs:1", "b.rs:2", "c.rs:3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        r.render_list_panel(Rect::new(0, 0, 10, 3), "2 hits", &lines, Some(2), 1, &theme);
+
+        let row = |y: u16| -> String {
+            (0..10)
+                .map(|x| r.screen().get(x, y).unwrap().ch)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+        assert_eq!(row(0), " 2 hits");
+        assert_eq!(row(1), "b.rs:2");
+        assert_eq!(row(2), "c.rs:3");
+        assert_eq!(r.screen().get(9, 2).unwrap().style, theme.selection_style());
+        assert_eq!(r.screen().get(0, 1).unwrap().style, theme.default_style());
+    }
+
+    #[test]
+    fn render_finder_draws_list_matches_and_preview() {
+        use crate::finder::FinderRow;
+        use smash_syntax::{LanguageId, RegexHighlighter};
+
+        let theme = default_dark_theme();
+        let mut r = Renderer::new(62, 7);
+        let rows = [
+            FinderRow::new("src/main.rs", vec![4, 9]),
+            FinderRow::new("README.md", vec![]),
+        ];
+        let preview = ["fn main() {}".to_string()];
+        let view = FinderView {
+            query: "mr",
+            status: "2/9",
+            rows: &rows,
+            selected: Some(0),
+            preview: &preview,
+        };
+        let rust = RegexHighlighter::new(LanguageId::Rust).unwrap();
+        r.render_finder(&view, Rect::new(0, 0, 62, 6), &theme, Some(&rust));
+
+        let row =
+            |y: u16| -> String { (0..62).map(|x| r.screen().get(x, y).unwrap().ch).collect() };
+        assert!(row(0).starts_with("┌ Find file ─"));
+        assert!(row(1).starts_with("│> mr "));
+        assert!(row(1).ends_with(" 2/9│"));
+        assert!(row(2).starts_with("├─"));
+        assert_eq!(row(2).chars().nth(25), Some('┬'));
+        assert!(row(3).starts_with("│src/main.rs "));
+        assert_eq!(
+            &row(3)[row(3).char_indices().nth(26).unwrap().0..],
+            "fn main() {}                       │"
+        );
+        assert_eq!(row(5).chars().nth(25), Some('┴'));
+
+        let selected = theme.selection_style();
+        assert_eq!(r.screen().get(1, 3).unwrap().style, selected);
+        assert_eq!(
+            r.screen().get(5, 3).unwrap().style,
+            selected.bold().underline()
+        );
+        assert_eq!(
+            r.screen().get(1, 4).unwrap().style,
+            theme.status_bar_style()
+        );
+        assert_eq!(
+            r.screen().get(26, 3).unwrap().style,
+            theme.scope_style(smash_syntax::ScopeId::Keyword)
+        );
+    }
+
+    #[test]
+    fn render_picker_draws_query_count_and_selection() {
+        let theme = default_dark_theme();
+        let mut r = Renderer::new(24, 7);
+        let mut picker = Picker::new(
+            "Symbols",
+            vec![
+                PickerItem::new("main", "function"),
+                PickerItem::new("App", "struct"),
+            ],
+        );
+        picker.set_query("ap");
+        r.render_picker(&mut picker, Rect::new(0, 0, 24, 6), &theme);
+
+        let row =
+            |y: u16| -> String { (0..24).map(|x| r.screen().get(x, y).unwrap().ch).collect() };
+        assert_eq!(row(0), "┌ Symbols ─────────────┐");
+        assert_eq!(row(1), "│> ap               1/2│");
+        assert_eq!(row(2), "├──────────────────────┤");
+        assert_eq!(row(3), "│App  struct           │");
+        assert_eq!(row(5), "└──────────────────────┘");
+        assert_eq!(r.screen().get(1, 3).unwrap().style, theme.selection_style());
+    }
 }
@@ -1,3 +1,6 @@
+use smash_core::grapheme::grapheme_width;
+use unicode_width::UnicodeWidthChar;
+
 use crate::cell::Cell;
 use crate::style::Style;
 
@@ -35,6 +38,15 @@ impl Screen {
         }
     }
 
+    fn cell_mut(&mut self, col: u16, row: u16) -> Option<&mut Cell> {
+        if col < self.width && row < self.height {
+            let idx = self.index(col, row);
+            Some(&mut self.cells[idx])
+        } else {
+            None
+        }
+    }
+
     pub fn set(&mut self, col: u16, row: u16, cell: Cell) {
         if col < self.width && row < self.height {
             let idx = self.index(col, row);
@@ -46,17 +58,44 @@ impl Screen {
         self.set(col, row, Cell::new(ch, style));
     }
 
+    /// Write `s` from `col`, cut off at the screen edge. Wide characters
+    /// take two cells; zero-width ones join the character before.
     pub fn put_str(&mut self, col: u16, row: u16, s: &str, style: Style) {
-        let mut c = col;
+        let mut x = col;
+        let mut last = None;
         for ch in s.chars() {
-            if c >= self.width {
+            let width = ch.width().unwrap_or(1) as u16;
+            if width == 0 {
+                if let Some(cell) = last.and_then(|at| self.cell_mut(at, row)) {
+                    let mut rest = cell.combining.take().map(String::from).unwrap_or_default();
+                    rest.push(ch);
+                    cell.combining = Some(rest.into());
+                }
+                continue;
+            }
+            if x.saturating_add(width) > self.width {
                 break;
             }
-            self.set(c, row, Cell::new(ch, style));
-            c += 1;
+            self.put_char(x, row, ch, style);
+            if width == 2 {
+                self.set(x + 1, row, Cell::continuation(style));
+            }
+            last = Some(x);
+            x += width;
         }
     }
 
+    /// Draw grapheme cluster `g` at `col`, a wide one across two cells.
+    /// Returns the cells it takes.
+    pub fn put_grapheme(&mut self, col: u16, row: u16, g: &str, style: Style) -> u16 {
+        let width = grapheme_width(g) as u16;
+        self.set(col, row, Cell::grapheme(g, style));
+        if width == 2 {
+            self.set(col.saturating_add(1), row, Cell::continuation(style));
+        }
+        width
+    }
+
     pub fn clear(&mut self) {
         for cell in &mut self.cells {
             *cell = Cell::blank();
@@ -96,6 +135,30 @@ impl Screen {
         changes
     }
 
+    /// Like [`Screen::diff`], but with adjacent changed cells of one row
+    /// and one style grouped into runs: `(col, row, cells)`.
+    pub fn diff_runs(&self, other: &Screen) -> Vec<(u16, u16, Vec<Cell>)> {
+        let mut runs: Vec<(u16, u16, Vec<Cell>)> = Vec::new();
+        for (col, row, cell) in self.diff(other) {
+            if let Some((start, run_row, cells)) = runs.last_mut() {
+                let next = usize::from(*start) + cells.len();
+                if *run_row == row && next == usize::from(col) && cells[0].style == cell.style {
+                    cells.push(cell);
+                    continue;
+                }
+            }
+            runs.push((col, row, vec![cell]));
+        }
+        runs
+    }
+
+    /// Make this screen a copy of `other`, reusing its allocation.
+    pub fn copy_from(&mut self, other: &Screen) {
+        self.width = other.width;
+        self.height = other.height;
+        self.cells.clone_from(&other.cells);
+    }
+
     fn index(&self, col: u16, row: u16) -> usize {
         (row as usize) * (self.width as usize) + (col as usize)
     }
@@ -179,6 +242,51 @@ mod tests {
         // "llo" did not fit
     }
 
+    #[test]
+    fn screen_put_str_lays_out_wide_and_combining_characters() {
+        let mut s = Screen::new(5, 1);
+        s.put_str(0, 0, "字e\u{301}字", Style::default());
+        assert_eq!(s.get(0, 0).unwrap().ch, '字');
+        assert!(s.get(1, 0).unwrap().is_continuation());
+        assert_eq!(s.get(2, 0).unwrap().combining.as_deref(), Some("\u{301}"));
+        assert_eq!(s.get(3, 0).unwrap().ch, '字');
+        // The second wide character needs one more cell than is left.
+        let mut s = Screen::new(4, 1);
+        s.put_str(0, 0, "字e字", Style::default());
+        assert_eq!(s.get(3, 0).unwrap(), &Cell::blank());
+    }
+
+    #[test]
+    fn diff_runs_group_adjacent_cells_of_one_style() {
+        let before = Screen::new(10, 2);
+        let mut after = Screen::new(10, 2);
+        let red = Style::default().fg(Color::Red);
+        after.put_str(0, 0, "ab", Style::default());
+        after.put_str(2, 0, "cd", red);
+        after.put_str(5, 0, "e", red);
+        after.put_str(9, 0, "f", red);
+        after.put_str(0, 1, "g", red);
+        let runs: Vec<(u16, u16, String)> = before
+            .diff_runs(&after)
+            .into_iter()
+            .map(|(col, row, cells)| (col, row, cells.iter().map(|c| c.ch).collect()))
+            .collect();
+        assert_eq!(
+            runs,
+            vec![
+                (0, 0, "ab".to_string()),
+                (2, 0, "cd".to_string()),
+                (5, 0, "e".to_string()),
+                (9, 0, "f".to_string()),
+                (0, 1, "g".to_string()),
+            ]
+        );
+
+        let mut copy = Screen::new(1, 1);
+        copy.copy_from(&after);
+        assert!(after.diff_runs(&copy).is_empty());
+    }
+
     #[test]
     fn screen_clear_resets_all_cells() {
         let mut s = Screen::new(10, 5);
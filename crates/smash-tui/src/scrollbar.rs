@@ -0,0 +1,109 @@
+//! A one-column scrollbar at the right of the editor: the thumb shows
+//! where the view is in the buffer, and marks show where diagnostics,
+//! search matches and changes since the last commit are.
+
+use std::ops::Range;
+
+use crate::renderer::GutterDiagnostic;
+
+/// Columns taken by the scrollbar.
+pub const SCROLLBAR_WIDTH: u16 = 1;
+
+/// Something of note on a line, marked on the scrollbar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollbarMark {
+    Diagnostic(GutterDiagnostic),
+    SearchMatch,
+    /// Lines added since the last commit.
+    Added,
+    /// Lines changed since the last commit.
+    Modified,
+    /// Lines removed since the last commit, just before this one.
+    Removed,
+}
+
+impl ScrollbarMark {
+    /// Which mark a row shows when it has several; lower wins. Errors
+    /// and warnings come first, hints and notes last.
+    pub fn priority(self) -> u8 {
+        match self {
+            ScrollbarMark::Diagnostic(GutterDiagnostic::Error) => 0,
+            ScrollbarMark::Diagnostic(GutterDiagnostic::Warning) => 1,
+            ScrollbarMark::SearchMatch => 2,
+            ScrollbarMark::Modified => 3,
+            ScrollbarMark::Added => 4,
+            ScrollbarMark::Removed => 5,
+            ScrollbarMark::Diagnostic(GutterDiagnostic::Information) => 6,
+            ScrollbarMark::Diagnostic(GutterDiagnostic::Hint) => 7,
+        }
+    }
+}
+
+/// The scrollbar row, of `height`, that buffer `line` falls on.
+pub fn scrollbar_row(line: usize, line_count: usize, height: usize) -> usize {
+    let height = height.max(1);
+    (line * height / line_count.max(1)).min(height - 1)
+}
+
+/// The buffer line in the middle of scrollbar `row`, of `height`.
+pub fn scrollbar_line(row: usize, line_count: usize, height: usize) -> usize {
+    let height = height.max(1);
+    let line = (2 * row + 1) * line_count / (2 * height);
+    line.min(line_count.saturating_sub(1))
+}
+
+/// Rows of the thumb for a view of `visible` lines from `top`, at least
+/// one row long.
+pub fn scrollbar_thumb(
+    top: usize,
+    visible: usize,
+    line_count: usize,
+    height: usize,
+) -> Range<usize> {
+    let height = height.max(1);
+    let line_count = line_count.max(1);
+    let start = scrollbar_row(top, line_count, height);
+    let len = (visible * height).div_ceil(line_count).clamp(1, height);
+    let end = (start + len).min(height);
+    end - len..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_and_lines_map_both_ways() {
+        assert_eq!(scrollbar_row(0, 100, 10), 0);
+        assert_eq!(scrollbar_row(55, 100, 10), 5);
+        assert_eq!(scrollbar_row(99, 100, 10), 9);
+        assert_eq!(scrollbar_row(3, 5, 10), 6);
+        assert_eq!(scrollbar_line(0, 100, 10), 5);
+        assert_eq!(scrollbar_line(9, 100, 10), 95);
+        assert_eq!(scrollbar_line(9, 5, 10), 4);
+    }
+
+    #[test]
+    fn thumb_spans_the_view() {
+        assert_eq!(scrollbar_thumb(0, 20, 100, 10), 0..2);
+        assert_eq!(scrollbar_thumb(50, 20, 100, 10), 5..7);
+        assert_eq!(scrollbar_thumb(95, 20, 100, 10), 8..10);
+        // Short buffers fill the track; huge ones keep one row.
+        assert_eq!(scrollbar_thumb(0, 20, 5, 10), 0..10);
+        assert_eq!(scrollbar_thumb(500_000, 20, 1_000_000, 10), 5..6);
+    }
+
+    #[test]
+    fn errors_outrank_search_matches_and_changes() {
+        let mut marks = [
+            ScrollbarMark::Added,
+            ScrollbarMark::SearchMatch,
+            ScrollbarMark::Diagnostic(GutterDiagnostic::Hint),
+            ScrollbarMark::Diagnostic(GutterDiagnostic::Error),
+        ];
+        marks.sort_by_key(|m| m.priority());
+        assert_eq!(marks[0], ScrollbarMark::Diagnostic(GutterDiagnostic::Error));
+        assert_eq!(marks[1], ScrollbarMark::SearchMatch);
+        assert_eq!(marks[3], ScrollbarMark::Diagnostic(GutterDiagnostic::Hint));
+    }
+}
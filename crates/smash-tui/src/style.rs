@@ -82,6 +82,10 @@ impl Style {
         self.attrs = self.attrs | Attributes::BOLD;
         self
     }
+    pub fn dim(mut self) -> Self {
+        self.attrs = self.attrs | Attributes::DIM;
+        self
+    }
     pub fn italic(mut self) -> Self {
         self.attrs = self.attrs | Attributes::ITALIC;
         self
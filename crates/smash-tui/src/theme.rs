@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use smash_syntax::ScopeId;
 
+use crate::renderer::GutterDiagnostic;
 use crate::style::{Color, Style};
 
 /// Maps syntax scopes to terminal styles.
@@ -15,7 +16,19 @@ pub struct Theme {
     status_bar: Style,
     line_number: Style,
     selection: Style,
+    search_match: Style,
+    ruler: Style,
+    current_line: Style,
+    indent_guide: Style,
+    minimap_viewport: Style,
+    scrollbar_track: Style,
+    scrollbar_thumb: Style,
     cursor: Style,
+    /// Added and removed lines in the diff view.
+    diff_added: Style,
+    diff_removed: Style,
+    /// Lines changed since the last commit, on the scrollbar.
+    diff_modified: Style,
     /// Diagnostic gutter icon styles.
     diagnostic_error: Style,
     diagnostic_warning: Style,
@@ -32,7 +45,17 @@ impl Theme {
             status_bar: Style::default().fg(Color::Black).bg(Color::White),
             line_number: Style::default().fg(Color::Indexed(243)),
             selection: Style::default().bg(Color::Indexed(238)),
+            search_match: Style::default().bg(Color::Indexed(58)),
+            ruler: Style::default().bg(Color::Indexed(236)),
+            current_line: Style::default().bg(Color::Indexed(235)),
+            indent_guide: Style::default().fg(Color::Indexed(239)),
+            minimap_viewport: Style::default().bg(Color::Indexed(237)),
+            scrollbar_track: Style::default().bg(Color::Indexed(235)),
+            scrollbar_thumb: Style::default().bg(Color::Indexed(240)),
             cursor: Style::default().fg(Color::Black).bg(Color::White),
+            diff_added: Style::default().fg(Color::Green),
+            diff_removed: Style::default().fg(Color::Red),
+            diff_modified: Style::default().fg(Color::Blue),
             diagnostic_error: Style::default().fg(Color::Red).bold(),
             diagnostic_warning: Style::default().fg(Color::Yellow).bold(),
             diagnostic_info: Style::default().fg(Color::Blue),
@@ -44,6 +67,10 @@ impl Theme {
         &self.name
     }
 
+    pub(crate) fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
     pub fn set_scope_style(&mut self, scope: ScopeId, style: Style) {
         self.styles.insert(scope, style);
     }
@@ -83,6 +110,62 @@ impl Theme {
         self.selection = style;
     }
 
+    /// Background for search matches in view.
+    pub fn search_match_style(&self) -> Style {
+        self.search_match
+    }
+    pub fn set_search_match_style(&mut self, style: Style) {
+        self.search_match = style;
+    }
+
+    /// Background of a column ruler.
+    pub fn ruler_style(&self) -> Style {
+        self.ruler
+    }
+    pub fn set_ruler_style(&mut self, style: Style) {
+        self.ruler = style;
+    }
+
+    /// Background of the cursor line, when highlighted.
+    pub fn current_line_style(&self) -> Style {
+        self.current_line
+    }
+    pub fn set_current_line_style(&mut self, style: Style) {
+        self.current_line = style;
+    }
+
+    /// Foreground of the indent guides.
+    pub fn indent_guide_style(&self) -> Style {
+        self.indent_guide
+    }
+    pub fn set_indent_guide_style(&mut self, style: Style) {
+        self.indent_guide = style;
+    }
+
+    /// Background of the minimap rows for the lines in view.
+    pub fn minimap_viewport_style(&self) -> Style {
+        self.minimap_viewport
+    }
+    pub fn set_minimap_viewport_style(&mut self, style: Style) {
+        self.minimap_viewport = style;
+    }
+
+    /// Background of the scrollbar.
+    pub fn scrollbar_track_style(&self) -> Style {
+        self.scrollbar_track
+    }
+    pub fn set_scrollbar_track_style(&mut self, style: Style) {
+        self.scrollbar_track = style;
+    }
+
+    /// Background of the scrollbar rows for the lines in view.
+    pub fn scrollbar_thumb_style(&self) -> Style {
+        self.scrollbar_thumb
+    }
+    pub fn set_scrollbar_thumb_style(&mut self, style: Style) {
+        self.scrollbar_thumb = style;
+    }
+
     pub fn cursor_style(&self) -> Style {
         self.cursor
     }
@@ -90,6 +173,40 @@ impl Theme {
         self.cursor = style;
     }
 
+    /// Added lines in the diff view.
+    pub fn diff_added_style(&self) -> Style {
+        self.diff_added
+    }
+    pub fn set_diff_added_style(&mut self, style: Style) {
+        self.diff_added = style;
+    }
+
+    /// Removed lines in the diff view.
+    pub fn diff_removed_style(&self) -> Style {
+        self.diff_removed
+    }
+    pub fn set_diff_removed_style(&mut self, style: Style) {
+        self.diff_removed = style;
+    }
+
+    /// Lines changed since the last commit.
+    pub fn diff_modified_style(&self) -> Style {
+        self.diff_modified
+    }
+    pub fn set_diff_modified_style(&mut self, style: Style) {
+        self.diff_modified = style;
+    }
+
+    /// Style of the sign for a diagnostic of `severity`.
+    pub fn diagnostic_style(&self, severity: GutterDiagnostic) -> Style {
+        match severity {
+            GutterDiagnostic::Error => self.diagnostic_error,
+            GutterDiagnostic::Warning => self.diagnostic_warning,
+            GutterDiagnostic::Information => self.diagnostic_info,
+            GutterDiagnostic::Hint => self.diagnostic_hint,
+        }
+    }
+
     pub fn diagnostic_error_style(&self) -> Style {
         self.diagnostic_error
     }
@@ -159,10 +276,62 @@ pub fn default_dark_theme() -> Theme {
     t.set_status_bar_style(Style::default().fg(Color::White).bg(Color::Indexed(238)));
     t.set_line_number_style(Style::default().fg(Color::Indexed(243)));
     t.set_selection_style(Style::default().bg(Color::Indexed(238)));
+    t.set_search_match_style(Style::default().bg(Color::Indexed(58)));
+    t.set_ruler_style(Style::default().bg(Color::Rgb(49, 50, 68)));
+    t.set_current_line_style(Style::default().bg(Color::Rgb(42, 43, 60)));
+    t.set_indent_guide_style(Style::default().fg(Color::Rgb(69, 71, 90)));
+    t.set_minimap_viewport_style(Style::default().bg(Color::Rgb(49, 50, 68)));
+    t.set_scrollbar_track_style(Style::default().bg(Color::Rgb(36, 37, 52)));
+    t.set_scrollbar_thumb_style(Style::default().bg(Color::Rgb(69, 71, 90)));
     t.set_cursor_style(Style::default().fg(Color::Black).bg(Color::White));
     t
 }
 
+/// Built-in dark theme for colour-blind users. Diagnostic severities use
+/// colours from the Okabe–Ito palette that stay apart in brightness and
+/// hue under protanopia, deuteranopia and tritanopia, and errors and
+/// warnings are bold on top of that; syntax colours avoid red/green pairs.
+pub fn accessible_dark_theme() -> Theme {
+    let mut t = default_dark_theme();
+    t.name = "accessible".to_string();
+    let vermillion = Color::Rgb(213, 94, 0);
+    let yellow = Color::Rgb(240, 228, 66);
+    let sky_blue = Color::Rgb(86, 180, 233);
+    let blue = Color::Rgb(0, 114, 178);
+    let orange = Color::Rgb(230, 159, 0);
+    let purple = Color::Rgb(204, 121, 167);
+    t.set_scope_style(ScopeId::Keyword, Style::default().fg(sky_blue).bold());
+    t.set_scope_style(ScopeId::Type, Style::default().fg(orange));
+    t.set_scope_style(ScopeId::Function, Style::default().fg(sky_blue));
+    t.set_scope_style(ScopeId::String, Style::default().fg(yellow));
+    t.set_scope_style(ScopeId::Number, Style::default().fg(purple));
+    t.set_scope_style(ScopeId::Constant, Style::default().fg(purple));
+    t.set_scope_style(ScopeId::Macro, Style::default().fg(orange).italic());
+    t.set_scope_style(ScopeId::Attribute, Style::default().fg(orange).italic());
+    t.set_scope_style(ScopeId::Namespace, Style::default().fg(sky_blue));
+    t.set_scope_style(ScopeId::Label, Style::default().fg(orange));
+    t.set_diagnostic_error_style(Style::default().fg(vermillion).bold());
+    t.set_diagnostic_warning_style(Style::default().fg(yellow).bold());
+    t.set_diagnostic_info_style(Style::default().fg(blue));
+    t.set_diagnostic_hint_style(Style::default().fg(Color::Rgb(187, 187, 187)));
+    t.set_diff_added_style(Style::default().fg(sky_blue));
+    t.set_diff_removed_style(Style::default().fg(vermillion));
+    t.set_diff_modified_style(Style::default().fg(orange));
+    t
+}
+
+/// Config names of the built-in themes.
+pub const BUILTIN_THEMES: &[&str] = &["accessible", "dark"];
+
+/// The built-in theme called `name` in the config (`display.theme`).
+pub fn builtin_theme(name: &str) -> Option<Theme> {
+    match name {
+        "dark" => Some(default_dark_theme()),
+        "accessible" => Some(accessible_dark_theme()),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -332,4 +501,104 @@ mod tests {
         assert_eq!(t.diagnostic_info_style().fg, Color::Blue);
         assert_eq!(t.diagnostic_hint_style().fg, Color::Cyan);
     }
+
+    /// CIELAB coordinates of `color` as seen with a colour-vision
+    /// deficiency simulated by `m` (Machado et al. 2009, full severity).
+    fn simulated_lab(color: Color, m: &[[f64; 3]; 3]) -> [f64; 3] {
+        let Color::Rgb(r, g, b) = color else {
+            panic!("{:?} is not an RGB colour", color);
+        };
+        let linear = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let rgb = [linear(r), linear(g), linear(b)];
+        let [r, g, b] = m.map(|row| (0..3).map(|j| row[j] * rgb[j]).sum::<f64>().clamp(0.0, 1.0));
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+        let f = |t: f64| {
+            if t > 0.008856 {
+                t.cbrt()
+            } else {
+                7.787 * t + 16.0 / 116.0
+            }
+        };
+        [
+            116.0 * f(y) - 16.0,
+            500.0 * (f(x) - f(y)),
+            200.0 * (f(y) - f(z)),
+        ]
+    }
+
+    #[test]
+    fn accessible_theme_severities_stay_distinct_under_colour_blindness() {
+        let t = accessible_dark_theme();
+        let severities = [
+            GutterDiagnostic::Error,
+            GutterDiagnostic::Warning,
+            GutterDiagnostic::Information,
+            GutterDiagnostic::Hint,
+        ];
+        let deficiencies = [
+            (
+                "normal",
+                [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            ),
+            (
+                "protanopia",
+                [
+                    [0.152286, 1.052583, -0.204868],
+                    [0.114503, 0.786281, 0.099216],
+                    [-0.003882, -0.048116, 1.051998],
+                ],
+            ),
+            (
+                "deuteranopia",
+                [
+                    [0.367322, 0.860646, -0.227968],
+                    [0.280085, 0.672501, 0.047413],
+                    [-0.011820, 0.042940, 0.968881],
+                ],
+            ),
+            (
+                "tritanopia",
+                [
+                    [1.255528, -0.076749, -0.178779],
+                    [-0.078411, 0.930809, 0.147602],
+                    [0.004733, 0.691367, 0.303900],
+                ],
+            ),
+        ];
+        for (name, m) in &deficiencies {
+            for (i, a) in severities.iter().enumerate() {
+                for b in &severities[i + 1..] {
+                    let la = simulated_lab(t.diagnostic_style(*a).fg, m);
+                    let lb = simulated_lab(t.diagnostic_style(*b).fg, m);
+                    let delta_e = (0..3).map(|k| (la[k] - lb[k]).powi(2)).sum::<f64>().sqrt();
+                    assert!(
+                        delta_e > 20.0,
+                        "{:?} and {:?} too close under {} (ΔE {:.1})",
+                        a,
+                        b,
+                        name,
+                        delta_e
+                    );
+                }
+            }
+        }
+        assert!(t.diagnostic_error_style().attrs.bold());
+        assert!(t.diagnostic_warning_style().attrs.bold());
+    }
+
+    #[test]
+    fn builtin_themes_by_name() {
+        assert_eq!(builtin_theme("dark").unwrap().name(), "default-dark");
+        assert_eq!(builtin_theme("accessible").unwrap().name(), "accessible");
+        assert!(builtin_theme("solarized").is_none());
+    }
 }
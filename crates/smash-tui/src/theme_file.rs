@@ -0,0 +1,327 @@
+//! Themes defined in TOML files.
+//!
+//! A theme file lives at `<config dir>/themes/<name>.toml` and is chosen
+//! with `display.theme = "<name>"`. It starts from a built-in theme and
+//! overrides UI and syntax scope styles, optionally naming its colours
+//! in a palette:
+//!
+//! ```toml
+//! inherits = "dark"
+//!
+//! [palette]
+//! base = "#1e1e2e"
+//! mauve = "#cba6f7"
+//!
+//! [ui]
+//! default = { fg = "#cdd6f4", bg = "base" }
+//! selection = { bg = 238 }
+//!
+//! [scopes]
+//! keyword = { fg = "mauve", bold = true }
+//! comment = "#6c7086"
+//! ```
+//!
+//! A colour is `#rrggbb`, a palette name, a terminal colour name (`red`,
+//! `reset`, ...) or a 256-colour index. A style is a colour (used as the
+//! foreground) or a table of `fg`, `bg`, `bold`, `italic` and `underline`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use smash_syntax::ScopeId;
+use toml::{Table, Value};
+
+use crate::error::TuiError;
+use crate::style::{Color, Style};
+use crate::theme::{builtin_theme, default_dark_theme, Theme, BUILTIN_THEMES};
+
+/// Directory holding the theme files for `config_dir`.
+pub fn themes_dir(config_dir: &Path) -> PathBuf {
+    config_dir.join("themes")
+}
+
+/// Names of every theme that can be chosen: the built-in ones and the
+/// files in `themes_dir`, sorted.
+pub fn theme_names(themes_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|n| n.to_string()).collect();
+    if let Ok(entries) = std::fs::read_dir(themes_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "toml") {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// The theme called `name`: `themes_dir/<name>.toml` if there is one,
+/// otherwise the built-in theme of that name.
+///
+/// # Errors
+///
+/// Returns `TuiError::Theme` if the file cannot be read or parsed, or if
+/// there is no theme called `name`.
+pub fn find_theme(name: &str, themes_dir: &Path) -> Result<Theme, TuiError> {
+    let path = themes_dir.join(format!("{}.toml", name));
+    if path.is_file() {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| TuiError::Theme(format!("{}: {}", path.display(), e)))?;
+        return parse(name, &text)
+            .map_err(|e| TuiError::Theme(format!("{}: {}", path.display(), e)));
+    }
+    builtin_theme(name).ok_or_else(|| TuiError::Theme(format!("unknown theme '{}'", name)))
+}
+
+/// Parse the theme file `text` as the theme called `name`.
+///
+/// # Errors
+///
+/// Returns `TuiError::Theme` naming the offending key for invalid TOML,
+/// unknown keys, scopes or colours, and values of the wrong type.
+pub fn parse_theme(name: &str, text: &str) -> Result<Theme, TuiError> {
+    parse(name, text).map_err(TuiError::Theme)
+}
+
+fn parse(name: &str, text: &str) -> Result<Theme, String> {
+    let table: Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| e.message().to_string())?;
+    for key in table.keys() {
+        if !matches!(key.as_str(), "inherits" | "palette" | "ui" | "scopes") {
+            return Err(field_error(key, "unknown key"));
+        }
+    }
+
+    let mut theme = match table.get("inherits") {
+        None => default_dark_theme(),
+        Some(Value::String(base)) => builtin_theme(base)
+            .ok_or_else(|| field_error("inherits", format!("no built-in theme '{}'", base)))?,
+        Some(_) => return Err(field_error("inherits", "expected a theme name")),
+    };
+    theme.set_name(name);
+
+    let mut palette = HashMap::new();
+    for (key, value) in section(&table, "palette")?.into_iter().flatten() {
+        let color = parse_color(value, &HashMap::new())
+            .map_err(|e| field_error(&format!("palette.{}", key), e))?;
+        palette.insert(key.clone(), color);
+    }
+
+    for (key, value) in section(&table, "ui")?.into_iter().flatten() {
+        let field = format!("ui.{}", key);
+        let style = parse_style(value, &palette).map_err(|e| field_error(&field, e))?;
+        match key.as_str() {
+            "default" => theme.set_default_style(style),
+            "status_bar" => theme.set_status_bar_style(style),
+            "line_number" => theme.set_line_number_style(style),
+            "selection" => theme.set_selection_style(style),
+            "search_match" => theme.set_search_match_style(style),
+            "ruler" => theme.set_ruler_style(style),
+            "current_line" => theme.set_current_line_style(style),
+            "indent_guide" => theme.set_indent_guide_style(style),
+            "minimap_viewport" => theme.set_minimap_viewport_style(style),
+            "scrollbar_track" => theme.set_scrollbar_track_style(style),
+            "scrollbar_thumb" => theme.set_scrollbar_thumb_style(style),
+            "cursor" => theme.set_cursor_style(style),
+            "diff_added" => theme.set_diff_added_style(style),
+            "diff_removed" => theme.set_diff_removed_style(style),
+            "diff_modified" => theme.set_diff_modified_style(style),
+            "diagnostic_error" => theme.set_diagnostic_error_style(style),
+            "diagnostic_warning" => theme.set_diagnostic_warning_style(style),
+            "diagnostic_info" => theme.set_diagnostic_info_style(style),
+            "diagnostic_hint" => theme.set_diagnostic_hint_style(style),
+            _ => return Err(field_error(&field, "unknown UI element")),
+        }
+    }
+
+    for (key, value) in section(&table, "scopes")?.into_iter().flatten() {
+        let field = format!("scopes.{}", key);
+        let scope = ScopeId::from_name(key).ok_or_else(|| field_error(&field, "unknown scope"))?;
+        let style = parse_style(value, &palette).map_err(|e| field_error(&field, e))?;
+        theme.set_scope_style(scope, style);
+    }
+    Ok(theme)
+}
+
+/// The table `key` of `table`, if there is one.
+fn section<'a>(table: &'a Table, key: &str) -> Result<Option<&'a Table>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::Table(t)) => Ok(Some(t)),
+        Some(_) => Err(field_error(key, "expected a table")),
+    }
+}
+
+fn field_error(field: &str, msg: impl std::fmt::Display) -> String {
+    format!("{}: {}", field, msg)
+}
+
+/// Parse a style: a colour for the foreground, or a table of `fg`, `bg`
+/// and attribute flags. Colours not given are left as `Reset`.
+fn parse_style(value: &Value, palette: &HashMap<String, Color>) -> Result<Style, String> {
+    let table = match value {
+        Value::Table(t) => t,
+        other => return Ok(Style::default().fg(parse_color(other, palette)?)),
+    };
+    let mut style = Style::default();
+    for (key, value) in table {
+        let flag = || {
+            value
+                .as_bool()
+                .ok_or(format!("{} must be true or false", key))
+        };
+        match key.as_str() {
+            "fg" => style = style.fg(parse_color(value, palette)?),
+            "bg" => style = style.bg(parse_color(value, palette)?),
+            "bold" if flag()? => style = style.bold(),
+            "italic" if flag()? => style = style.italic(),
+            "underline" if flag()? => style = style.underline(),
+            "bold" | "italic" | "underline" => {}
+            _ => return Err(format!("unknown style key '{}'", key)),
+        }
+    }
+    Ok(style)
+}
+
+/// Parse a colour: `#rrggbb`, a palette entry, a terminal colour name or
+/// a 256-colour index.
+fn parse_color(value: &Value, palette: &HashMap<String, Color>) -> Result<Color, String> {
+    let text = match value {
+        Value::Integer(i) => {
+            return u8::try_from(*i)
+                .map(Color::Indexed)
+                .map_err(|_| format!("colour index {} is not in 0-255", i))
+        }
+        Value::String(s) => s.as_str(),
+        _ => return Err("expected a colour".to_string()),
+    };
+    if let Some(hex) = text.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+        };
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => Err(format!("'{}' is not a #rrggbb colour", text)),
+        };
+    }
+    if let Some(color) = palette.get(text) {
+        return Ok(*color);
+    }
+    match text {
+        "reset" | "default" => Ok(Color::Reset),
+        "black" => Ok(Color::Black),
+        "red" => Ok(Color::Red),
+        "green" => Ok(Color::Green),
+        "yellow" => Ok(Color::Yellow),
+        "blue" => Ok(Color::Blue),
+        "magenta" => Ok(Color::Magenta),
+        "cyan" => Ok(Color::Cyan),
+        "white" => Ok(Color::White),
+        _ => Err(format!("unknown colour '{}'", text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THEME: &str = r##"
+inherits = "accessible"
+
+[palette]
+base = "#1e1e2e"
+mauve = "#cba6f7"
+
+[ui]
+default = { fg = "#cdd6f4", bg = "base" }
+selection = { bg = 238 }
+
+[scopes]
+keyword = { fg = "mauve", bold = true, italic = false }
+comment = "cyan"
+"##;
+
+    #[test]
+    fn theme_file_overrides_its_base() {
+        let t = parse_theme("mocha", THEME).unwrap();
+        assert_eq!(t.name(), "mocha");
+        assert_eq!(
+            t.default_style(),
+            Style::default()
+                .fg(Color::Rgb(0xcd, 0xd6, 0xf4))
+                .bg(Color::Rgb(0x1e, 0x1e, 0x2e))
+        );
+        assert_eq!(
+            t.selection_style(),
+            Style::default().bg(Color::Indexed(238))
+        );
+        let keyword = t.scope_style(ScopeId::Keyword);
+        assert_eq!(keyword.fg, Color::Rgb(0xcb, 0xa6, 0xf7));
+        assert!(keyword.attrs.bold() && !keyword.attrs.italic());
+        assert_eq!(
+            t.scope_style(ScopeId::Comment),
+            Style::default().fg(Color::Cyan)
+        );
+        // Untouched entries come from the base theme.
+        let base = builtin_theme("accessible").unwrap();
+        assert_eq!(
+            t.scope_style(ScopeId::String),
+            base.scope_style(ScopeId::String)
+        );
+        assert_eq!(t.diagnostic_error_style(), base.diagnostic_error_style());
+    }
+
+    #[test]
+    fn theme_file_errors_name_the_key() {
+        let err = |text: &str| parse_theme("bad", text).unwrap_err().to_string();
+        assert!(err("[scopes]\nkeywrd = \"red\"").contains("scopes.keywrd: unknown scope"));
+        assert!(err("[ui]\nstatus = \"red\"").contains("ui.status: unknown UI element"));
+        assert!(err("[scopes]\ntype = \"#12345\"").contains("not a #rrggbb colour"));
+        assert!(err("[scopes]\ntype = \"teal\"").contains("unknown colour 'teal'"));
+        assert!(err("[scopes]\ntype = 300").contains("not in 0-255"));
+        assert!(err("[scopes]\ntype = { fg = \"red\", blink = true }").contains("blink"));
+        assert!(err("inherits = \"solarized\"").contains("no built-in theme"));
+        assert!(err("colors = {}").contains("colors: unknown key"));
+        assert!(err("[ui").starts_with("theme error:"));
+    }
+
+    #[test]
+    fn themes_are_found_in_files_then_built_in() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("mocha.toml"), THEME).unwrap();
+        std::fs::write(
+            dir.path().join("dark.toml"),
+            "[scopes]\nkeyword = \"red\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.toml"), "[scopes]\nnope = 1\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+        assert_eq!(find_theme("mocha", dir.path()).unwrap().name(), "mocha");
+        // A file shadows the built-in theme of the same name.
+        let dark = find_theme("dark", dir.path()).unwrap();
+        assert_eq!(dark.scope_style(ScopeId::Keyword).fg, Color::Red);
+        assert_eq!(
+            find_theme("accessible", dir.path()).unwrap().name(),
+            "accessible"
+        );
+        let err = find_theme("broken", dir.path()).unwrap_err().to_string();
+        assert!(
+            err.contains("broken.toml: scopes.nope: unknown scope"),
+            "{}",
+            err
+        );
+        assert!(find_theme("missing", dir.path()).is_err());
+
+        assert_eq!(
+            theme_names(dir.path()),
+            vec!["accessible", "broken", "dark", "mocha"]
+        );
+    }
+}
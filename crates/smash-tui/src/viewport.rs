@@ -58,8 +58,31 @@ impl Viewport {
     }
 
     pub fn scroll_down(&mut self, lines: usize, total_lines: usize) {
-        let max = total_lines.saturating_sub(self.visible_lines);
-        self.top_line = (self.top_line + lines).min(max);
+        self.top_line = (self.top_line + lines).min(self.max_top_line(total_lines));
+    }
+
+    /// Scroll so `line` is in the middle of the view, as far as the
+    /// start and end of the buffer allow.
+    pub fn center_on(&mut self, line: usize, total_lines: usize) {
+        let top = line.saturating_sub(self.visible_lines / 2);
+        self.top_line = top.min(self.max_top_line(total_lines));
+    }
+
+    /// Scroll so `line` is the first visible line, or as near the top as
+    /// the end of the buffer allows.
+    pub fn align_top(&mut self, line: usize, total_lines: usize) {
+        self.top_line = line.min(self.max_top_line(total_lines));
+    }
+
+    /// Scroll so `line` is the last visible line, or as near the bottom
+    /// as the start of the buffer allows.
+    pub fn align_bottom(&mut self, line: usize) {
+        self.top_line = line.saturating_sub(self.visible_lines.saturating_sub(1));
+    }
+
+    /// The furthest down the view scrolls: the last line at the bottom.
+    fn max_top_line(&self, total_lines: usize) -> usize {
+        total_lines.saturating_sub(self.visible_lines)
     }
 
     pub fn resize(&mut self, lines: usize, cols: usize) {
@@ -73,6 +96,13 @@ impl Viewport {
     pub fn set_left_col(&mut self, col: usize) {
         self.left_col = col;
     }
+
+    /// Follow a scroll-locked `leader`: take its column and the top line
+    /// `map` gives for its top line, so aligned lines stay side by side.
+    pub fn follow(&mut self, leader: &Viewport, map: impl Fn(usize) -> usize) {
+        self.top_line = map(leader.top_line);
+        self.left_col = leader.left_col;
+    }
 }
 
 #[cfg(test)]
@@ -171,6 +201,31 @@ mod tests {
         assert_eq!(vp.top_line(), 0);
     }
 
+    #[test]
+    fn center_on_puts_the_line_mid_view() {
+        let mut vp = Viewport::new(10, 80);
+        vp.center_on(50, 100);
+        assert_eq!(vp.top_line(), 45);
+        // Clamped at both ends of the buffer.
+        vp.center_on(2, 100);
+        assert_eq!(vp.top_line(), 0);
+        vp.center_on(98, 100);
+        assert_eq!(vp.top_line(), 90);
+    }
+
+    #[test]
+    fn align_top_and_bottom() {
+        let mut vp = Viewport::new(10, 80);
+        vp.align_top(40, 100);
+        assert_eq!(vp.top_line(), 40);
+        vp.align_top(95, 100);
+        assert_eq!(vp.top_line(), 90);
+        vp.align_bottom(40);
+        assert_eq!(vp.top_line(), 31); // 40 - (10-1) = 31
+        vp.align_bottom(3);
+        assert_eq!(vp.top_line(), 0);
+    }
+
     #[test]
     fn resize_updates_dimensions() {
         let mut vp = Viewport::new(10, 80);
@@ -187,4 +242,15 @@ mod tests {
         assert_eq!(vp.top_line(), 42);
         assert_eq!(vp.left_col(), 7);
     }
+
+    #[test]
+    fn follow_maps_the_leaders_top_line() {
+        let mut leader = Viewport::new(10, 80);
+        leader.set_top_line(20);
+        leader.set_left_col(4);
+        let mut follower = Viewport::new(10, 80);
+        follower.follow(&leader, |line| line + 3);
+        assert_eq!(follower.top_line(), 23);
+        assert_eq!(follower.left_col(), 4);
+    }
 }
@@ -0,0 +1,130 @@
+use smash_core::position::{Position, Range};
+use smash_syntax::LanguageId;
+
+use super::completion::is_word_char;
+use super::App;
+
+/// An abbreviation just expanded, kept so Backspace right after it can
+/// put the trigger back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Expansion {
+    /// The word that was typed.
+    trigger: String,
+    /// What replaced it.
+    text: String,
+    /// Where the expansion starts and ends; the boundary typed after it
+    /// follows `end`.
+    start: Position,
+    end: Position,
+    /// Where the cursor was left once the boundary was typed.
+    cursor: Position,
+}
+
+// =========================================================================
+// Abbreviations
+// =========================================================================
+
+impl App {
+    /// The expansion of `trigger` for the current buffer's language: its
+    /// `[languages.<id>.abbreviations]` entry, else the global one.
+    fn abbreviation(&self, trigger: &str) -> Option<String> {
+        let language = self
+            .buffer
+            .path()
+            .map(LanguageId::from_path)
+            .unwrap_or(LanguageId::Plain);
+        self.language_configs
+            .get(language.as_str())
+            .and_then(|l| l.abbreviations.get(trigger))
+            .or_else(|| self.abbreviations.get(trigger))
+            .cloned()
+    }
+
+    /// Type `boundary` with `insert`, first expanding the abbreviation
+    /// the word before the cursor is, if any. The expansion and the
+    /// boundary undo as one step.
+    pub(crate) fn insert_with_abbreviation(
+        &mut self,
+        boundary: char,
+        insert: impl FnOnce(&mut Self),
+    ) {
+        let cursor = self.buffer.cursors().primary();
+        let pos = cursor.position();
+        let single = self.buffer.cursors().len() == 1
+            && cursor.selection_range().filter(|r| !r.is_empty()).is_none();
+        if is_word_char(boundary) || !single || self.buffer.is_read_only() {
+            return insert(self);
+        }
+        let before: Vec<char> = self
+            .buffer
+            .line(pos.line)
+            .map(|l| l.chars().take(pos.col).collect())
+            .unwrap_or_default();
+        let start_col = before
+            .iter()
+            .rposition(|&c| !is_word_char(c))
+            .map_or(0, |i| i + 1);
+        let trigger: String = before[start_col..].iter().collect();
+        let Some(expansion) = (!trigger.is_empty())
+            .then(|| self.abbreviation(&trigger))
+            .flatten()
+        else {
+            return insert(self);
+        };
+
+        let start = Position::new(pos.line, start_col);
+        self.buffer.begin_undo_group();
+        self.buffer.cursors_mut().primary_mut().set_anchor(start);
+        self.insert_text(&expansion);
+        let end = self.buffer.cursors().primary().position();
+        insert(self);
+        self.buffer.end_undo_group();
+        self.last_expansion = Some(Expansion {
+            trigger,
+            text: self.buffer.line_ending().normalize(&expansion).into_owned(),
+            start,
+            end,
+            cursor: self.buffer.cursors().primary().position(),
+        });
+    }
+
+    /// Put back the trigger of `expansion` if the cursor has not moved
+    /// since it was expanded. Returns `false` when there is nothing to
+    /// undo, for Backspace to delete as usual.
+    pub(crate) fn revert_expansion(&mut self, expansion: Option<Expansion>) -> bool {
+        let Some(expansion) = expansion else {
+            return false;
+        };
+        let cursor = self.buffer.cursors().primary();
+        if self.buffer.cursors().len() > 1
+            || cursor.selection_range().is_some_and(|r| !r.is_empty())
+            || cursor.position() != expansion.cursor
+            || self
+                .buffer
+                .text_in_range(Range::new(expansion.start, expansion.end))
+                .ok()
+                != Some(expansion.text.clone())
+        {
+            return false;
+        }
+        let Expansion {
+            trigger,
+            start,
+            end,
+            cursor,
+            ..
+        } = expansion;
+        self.buffer.cursors_mut().primary_mut().set_anchor(start);
+        self.buffer.cursors_mut().primary_mut().set_position(end);
+        self.insert_text(&trigger);
+        // Whatever the boundary inserted still follows the trigger.
+        let trigger_end = Position::new(start.line, start.col + trigger.chars().count());
+        let restored = if cursor.line == end.line {
+            Position::new(trigger_end.line, trigger_end.col + cursor.col - end.col)
+        } else {
+            Position::new(cursor.line - (end.line - start.line), cursor.col)
+        };
+        self.set_cursor(restored);
+        true
+    }
+}
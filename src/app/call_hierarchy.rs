@@ -0,0 +1,276 @@
+use std::path::PathBuf;
+
+use smash_input::{Key, KeyEvent};
+use smash_lsp::CallHierarchyItem;
+use smash_tui::ListState;
+
+use super::App;
+use crate::lsp_types::{CallDirection, LspCommand};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeState {
+    Collapsed,
+    /// Its calls have been asked for.
+    Loading,
+    Expanded,
+    /// Expanded, and it has no calls.
+    Leaf,
+}
+
+/// A function in the call hierarchy pane.
+struct CallNode {
+    /// Matches answers to the request for this node's calls.
+    id: u64,
+    item: CallHierarchyItem,
+    depth: usize,
+    state: NodeState,
+}
+
+/// Callers or callees of the function at the cursor, as a tree that is
+/// expanded a level at a time. Shown in the results pane.
+pub(crate) struct CallHierarchy {
+    direction: CallDirection,
+    /// The document the hierarchy started from; its server is asked.
+    uri: String,
+    /// Paths are shown relative to this.
+    root: PathBuf,
+    /// Depth first: each node's calls follow it, one level deeper.
+    nodes: Vec<CallNode>,
+    next_id: u64,
+    pub(crate) list: ListState,
+}
+
+impl CallHierarchy {
+    fn new(direction: CallDirection, uri: String, items: Vec<CallHierarchyItem>) -> Self {
+        let mut hierarchy = Self {
+            direction,
+            uri,
+            root: std::env::current_dir().unwrap_or_default(),
+            nodes: Vec::new(),
+            next_id: 0,
+            list: ListState::default(),
+        };
+        hierarchy.insert(0, 0, items);
+        hierarchy
+    }
+
+    /// Insert `items` as nodes at `depth`, starting at `index`.
+    fn insert(&mut self, index: usize, depth: usize, items: Vec<CallHierarchyItem>) {
+        let nodes: Vec<CallNode> = items
+            .into_iter()
+            .map(|item| {
+                self.next_id += 1;
+                CallNode {
+                    id: self.next_id,
+                    item,
+                    depth,
+                    state: NodeState::Collapsed,
+                }
+            })
+            .collect();
+        self.nodes.splice(index..index, nodes);
+    }
+
+    pub(crate) fn title(&self) -> String {
+        let name = self.nodes.first().map_or("", |n| n.item.name.as_str());
+        match self.direction {
+            CallDirection::Incoming => format!("Callers of {}", name),
+            CallDirection::Outgoing => format!("Calls from {}", name),
+        }
+    }
+
+    /// One row per node: indented by depth, marked by whether it is
+    /// expanded, with its kind and `path:line`.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        self.nodes
+            .iter()
+            .map(|node| {
+                let marker = match node.state {
+                    NodeState::Collapsed => '▸',
+                    NodeState::Loading => '…',
+                    NodeState::Expanded => '▾',
+                    NodeState::Leaf => '·',
+                };
+                let path = App::uri_to_path(&node.item.uri);
+                let path = path.strip_prefix(&self.root).unwrap_or(&path);
+                format!(
+                    "{}{} {} ({}) {}:{}",
+                    "  ".repeat(node.depth),
+                    marker,
+                    node.item.name,
+                    node.item.kind.label(),
+                    path.display(),
+                    node.item.selection_range.start.line + 1
+                )
+            })
+            .collect()
+    }
+
+    /// Mark the selected node as loading and return the request for its
+    /// calls, unless they are already shown.
+    fn expand(&mut self) -> Option<(u64, CallHierarchyItem)> {
+        let node = self.nodes.get_mut(self.list.selected())?;
+        if node.state != NodeState::Collapsed {
+            return None;
+        }
+        node.state = NodeState::Loading;
+        Some((node.id, node.item.clone()))
+    }
+
+    /// Fold the selected node away, or if it is folded, select its
+    /// parent.
+    fn collapse(&mut self) {
+        let selected = self.list.selected();
+        let Some(node) = self.nodes.get(selected) else {
+            return;
+        };
+        let depth = node.depth;
+        if node.state == NodeState::Collapsed {
+            if let Some(parent) = self.nodes[..selected].iter().rposition(|n| n.depth < depth) {
+                self.list.select(parent, self.nodes.len());
+            }
+            return;
+        }
+        let end = self.subtree_end(selected);
+        self.nodes.drain(selected + 1..end);
+        self.nodes[selected].state = NodeState::Collapsed;
+    }
+
+    /// Index just past the descendants of the node at `index`.
+    fn subtree_end(&self, index: usize) -> usize {
+        let depth = self.nodes[index].depth;
+        self.nodes[index + 1..]
+            .iter()
+            .position(|n| n.depth <= depth)
+            .map_or(self.nodes.len(), |p| index + 1 + p)
+    }
+
+    /// Show `items` as the calls of node `id`, if it is still waiting
+    /// for them.
+    fn fill(&mut self, id: u64, items: Vec<CallHierarchyItem>) -> bool {
+        let Some(index) = self.nodes.iter().position(|n| n.id == id) else {
+            return false;
+        };
+        let node = &mut self.nodes[index];
+        if node.state != NodeState::Loading {
+            return false;
+        }
+        node.state = if items.is_empty() {
+            NodeState::Leaf
+        } else {
+            NodeState::Expanded
+        };
+        let depth = node.depth + 1;
+        self.insert(index + 1, depth, items);
+        true
+    }
+}
+
+// =========================================================================
+// Call hierarchy
+// =========================================================================
+
+impl App {
+    /// Ask for the function at the cursor, to show its callers or
+    /// callees.
+    pub(crate) fn lsp_call_hierarchy(&mut self, direction: CallDirection) {
+        if !self.lsp_server_started {
+            self.messages.warn("No LSP server running");
+            return;
+        }
+        if let Some(uri) = self.current_uri() {
+            let pos = self.buffer.cursors().primary().position();
+            let _ = self.lsp_cmd_tx.try_send(LspCommand::PrepareCallHierarchy {
+                uri,
+                position: self.lsp_position(pos),
+                direction,
+            });
+        }
+    }
+
+    /// Open the pane on the functions found at the cursor, with the
+    /// first expanded.
+    pub(crate) fn handle_call_hierarchy_prepared(
+        &mut self,
+        direction: CallDirection,
+        items: Vec<CallHierarchyItem>,
+    ) {
+        let Some(uri) = self.current_uri().filter(|_| !items.is_empty()) else {
+            self.messages.info("No function at the cursor");
+            return;
+        };
+        self.call_hierarchy = Some(CallHierarchy::new(direction, uri, items));
+        self.diff_view = None;
+        self.shell_output = None;
+        self.search_results_focused = true;
+        self.file_tree_focused = false;
+        self.expand_call_node();
+    }
+
+    /// Show calls that arrived for node `id`. Returns `true` if the pane
+    /// needs a redraw.
+    pub(crate) fn handle_call_hierarchy_calls(
+        &mut self,
+        id: u64,
+        items: Vec<CallHierarchyItem>,
+    ) -> bool {
+        self.call_hierarchy
+            .as_mut()
+            .is_some_and(|h| h.fill(id, items))
+    }
+
+    /// Ask for the calls of the selected node.
+    fn expand_call_node(&mut self) {
+        let Some(hierarchy) = self.call_hierarchy.as_mut() else {
+            return;
+        };
+        if let Some((node, item)) = hierarchy.expand() {
+            let _ = self.lsp_cmd_tx.try_send(LspCommand::CallHierarchyCalls {
+                uri: hierarchy.uri.clone(),
+                item,
+                direction: hierarchy.direction,
+                node,
+            });
+        }
+    }
+
+    /// Handle a key while the pane shows the call hierarchy and has
+    /// focus.
+    pub(crate) fn handle_call_hierarchy_key(&mut self, key: &KeyEvent) -> bool {
+        let Some(hierarchy) = self.call_hierarchy.as_mut() else {
+            return false;
+        };
+        if hierarchy.list.handle_key(&key.key, hierarchy.nodes.len()) {
+            return true;
+        }
+        match key.key {
+            Key::Right | Key::Char('l') => self.expand_call_node(),
+            Key::Left | Key::Char('h') => hierarchy.collapse(),
+            Key::Enter => self.open_call_node(),
+            _ => {}
+        }
+        true
+    }
+
+    /// Jump to the selected function. The pane stays open but gives
+    /// focus back to the editor.
+    fn open_call_node(&mut self) {
+        let Some(hierarchy) = self.call_hierarchy.as_ref() else {
+            return;
+        };
+        let Some(node) = hierarchy.nodes.get(hierarchy.list.selected()) else {
+            return;
+        };
+        let uri = node.item.uri.clone();
+        let target = self.location_position(&uri, node.item.selection_range.start);
+        self.push_jump();
+        if self.is_current_uri(&uri) {
+            let cursor = self.buffer.cursors_mut().primary_mut();
+            cursor.clear_selection();
+            cursor.set_position(target);
+        } else {
+            self.open_path(Self::uri_to_path(&uri), Some(target), false);
+        }
+        self.search_results_focused = false;
+    }
+}
@@ -0,0 +1,311 @@
+use std::path::Path;
+
+use smash_core::buffer::{Buffer, BufferId};
+use smash_core::edit::EditCommand;
+use smash_core::safe_write::WriteMode;
+use smash_lsp::{
+    CodeAction, DocumentChange, PositionEncoding, ResourceOperation, ServerCommand, ServerRequest,
+    TextDocumentEdit, WorkspaceEdit,
+};
+use smash_tui::{Picker, PickerItem};
+use tracing::{info, warn};
+
+use super::lsp::edits_last_first;
+use super::{App, InputMode};
+use crate::lsp_types::LspCommand;
+
+/// The code action picker overlay and the actions behind its items.
+pub(crate) struct CodeActionPicker {
+    pub(crate) picker: Picker,
+    /// Same order as the picker items.
+    actions: Vec<CodeAction>,
+}
+
+/// Picker row for `action`: its title, then its kind and whether it is
+/// the server's preferred choice.
+fn action_item(action: &CodeAction) -> PickerItem {
+    let mut detail = action.kind.clone().unwrap_or_default();
+    if action.is_preferred {
+        if !detail.is_empty() {
+            detail.push_str(" · ");
+        }
+        detail.push_str("preferred");
+    }
+    PickerItem::new(action.title.clone(), detail)
+}
+
+// =========================================================================
+// Code action picker
+// =========================================================================
+
+impl App {
+    pub(super) fn handle_code_action_result(&mut self, mut actions: Vec<CodeAction>) {
+        // The user moved on to something else while waiting.
+        if self.input_mode != InputMode::Normal {
+            return;
+        }
+        if actions.is_empty() {
+            self.messages.info("No code actions available");
+            return;
+        }
+        actions.sort_by_key(|a| !a.is_preferred);
+        let items = actions.iter().map(action_item).collect();
+        self.code_action_picker = Some(CodeActionPicker {
+            picker: Picker::new("Code actions", items),
+            actions,
+        });
+        self.input_mode = InputMode::CodeActionPicker;
+        self.prompt_input.clear();
+    }
+
+    /// Apply the selected code action.
+    pub(crate) fn confirm_code_action_picker(&mut self) {
+        let Some(mut state) = self.code_action_picker.take() else {
+            return;
+        };
+        let Some(index) = state.picker.selected().filter(|&i| i < state.actions.len()) else {
+            self.messages.info("No matching code action");
+            return;
+        };
+        let action = state.actions.swap_remove(index);
+        self.apply_code_action(action);
+    }
+
+    /// Apply `action`'s edit, then ask the server to run its command.
+    pub(crate) fn apply_code_action(&mut self, action: CodeAction) {
+        if action.edit.is_none() && action.command.is_none() {
+            self.messages
+                .warn(format!("'{}' has nothing to apply", action.title));
+            return;
+        }
+        if let Some(edit) = &action.edit {
+            match self.apply_workspace_edit(edit) {
+                Ok(files) if files > 1 => self
+                    .messages
+                    .info(format!("Applied '{}' across {} files", action.title, files)),
+                Ok(_) => self.messages.info(format!("Applied '{}'", action.title)),
+                Err(e) => {
+                    self.messages
+                        .error(format!("Could not apply '{}': {}", action.title, e));
+                    warn!(title = %action.title, "code action failed: {}", e);
+                    return;
+                }
+            }
+        }
+        if let Some(command) = action.command {
+            info!(command = %command.command, "executing code action command");
+            if action.edit.is_none() {
+                self.messages.info(format!("Running '{}'", action.title));
+            }
+            let title = command.title.clone();
+            if self
+                .lsp_cmd_tx
+                .try_send(LspCommand::ExecuteCommand {
+                    command,
+                    language_id: self.language_id.clone(),
+                })
+                .is_ok()
+            {
+                self.running_commands.push(title);
+            } else {
+                self.messages
+                    .error(format!("Could not run '{}': LSP is busy", action.title));
+            }
+        }
+        info!(title = %action.title, "code action applied");
+    }
+
+    /// Report a server command as done.
+    pub(crate) fn handle_command_finished(
+        &mut self,
+        command: ServerCommand,
+        result: Result<(), String>,
+    ) {
+        if let Some(i) = self
+            .running_commands
+            .iter()
+            .position(|t| *t == command.title)
+        {
+            self.running_commands.remove(i);
+        }
+        match result {
+            Ok(()) => {
+                info!(command = %command.command, "server command finished");
+                self.messages.info(format!("Finished '{}'", command.title));
+            }
+            Err(e) => {
+                warn!(command = %command.command, "server command failed: {}", e);
+                self.messages
+                    .error(format!("'{}' failed: {}", command.title, e));
+            }
+        }
+    }
+
+    /// Apply an edit the server asked for (`workspace/applyEdit`) and tell
+    /// it whether the edit was made.
+    pub(crate) fn handle_server_apply_edit(
+        &mut self,
+        label: Option<String>,
+        edit: WorkspaceEdit,
+        request: ServerRequest,
+    ) {
+        let label = label.unwrap_or_else(|| "server edit".to_string());
+        let reply = match self.apply_workspace_edit(&edit) {
+            Ok(files) => {
+                if files > 1 {
+                    self.messages
+                        .info(format!("Applied '{}' across {} files", label, files));
+                } else {
+                    self.messages.info(format!("Applied '{}'", label));
+                }
+                serde_json::json!({ "applied": true })
+            }
+            Err(e) => {
+                self.messages
+                    .error(format!("Could not apply '{}': {}", label, e));
+                warn!(label = %label, "server edit failed: {}", e);
+                serde_json::json!({ "applied": false, "failureReason": e })
+            }
+        };
+        if let Err(e) = request.reply(reply) {
+            warn!("could not answer workspace/applyEdit: {}", e);
+        }
+    }
+
+    /// Apply a workspace edit: text edits to the current buffer go through
+    /// the buffer (and so undo and document sync); other files are edited
+    /// on disk. Stops at the first change that fails, leaving the earlier
+    /// ones applied. Returns how many files were touched.
+    pub(crate) fn apply_workspace_edit(&mut self, edit: &WorkspaceEdit) -> Result<usize, String> {
+        // Versions in the edit refer to the buffer before any of it.
+        let version = self.document_version;
+        let mut files = 0;
+        for op in edit.operations() {
+            match op {
+                DocumentChange::Edit(doc) if self.is_current_uri(&doc.text_document.uri) => {
+                    if doc.text_document.version.is_some_and(|v| v != version) {
+                        return Err(format!(
+                            "{} changed since the action was computed",
+                            self.filename.as_deref().unwrap_or("the buffer")
+                        ));
+                    }
+                    if self.buffer.is_read_only() {
+                        return Err("the buffer is read-only".to_string());
+                    }
+                    if !doc.edits.is_empty() {
+                        self.apply_text_edits(doc.edits);
+                        self.lsp_did_change();
+                        files += 1;
+                    }
+                }
+                DocumentChange::Edit(doc) => {
+                    if !doc.edits.is_empty() {
+                        edit_file_on_disk(&doc, self.lsp_position_encoding, self.write_mode())
+                            .map_err(|e| {
+                                format!(
+                                    "{}: {}",
+                                    Self::uri_to_path(&doc.text_document.uri).display(),
+                                    e
+                                )
+                            })?;
+                        files += 1;
+                    }
+                }
+                DocumentChange::Resource(op) => {
+                    self.apply_resource_operation(&op)?;
+                    files += 1;
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Create, rename or delete a file. Renaming the current buffer's file
+    /// moves the buffer along with it.
+    fn apply_resource_operation(&mut self, op: &ResourceOperation) -> Result<(), String> {
+        let fail = |path: &Path, e: std::io::Error| format!("{}: {}", path.display(), e);
+        match op {
+            ResourceOperation::Create { uri, options } => {
+                let path = Self::uri_to_path(uri);
+                if path.exists() && !options.overwrite {
+                    if options.ignore_if_exists {
+                        return Ok(());
+                    }
+                    return Err(format!("{} already exists", path.display()));
+                }
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| fail(parent, e))?;
+                }
+                std::fs::write(&path, "").map_err(|e| fail(&path, e))
+            }
+            ResourceOperation::Rename {
+                old_uri,
+                new_uri,
+                options,
+            } => {
+                let from = Self::uri_to_path(old_uri);
+                let to = Self::uri_to_path(new_uri);
+                if to.exists() && !options.overwrite {
+                    if options.ignore_if_exists {
+                        return Ok(());
+                    }
+                    return Err(format!("{} already exists", to.display()));
+                }
+                if let Some(parent) = to.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| fail(parent, e))?;
+                }
+                std::fs::rename(&from, &to).map_err(|e| fail(&from, e))?;
+                if self.is_current_uri(old_uri) {
+                    let uri = self.current_uri();
+                    self.filename = to.file_name().map(|n| n.to_string_lossy().into_owned());
+                    self.buffer.set_path(to);
+                    self.reattach_language(uri);
+                }
+                Ok(())
+            }
+            ResourceOperation::Delete { uri, options } => {
+                let path = Self::uri_to_path(uri);
+                if !path.exists() {
+                    if options.ignore_if_not_exists {
+                        return Ok(());
+                    }
+                    return Err(format!("{} does not exist", path.display()));
+                }
+                let removed = if path.is_dir() && options.recursive {
+                    std::fs::remove_dir_all(&path)
+                } else if path.is_dir() {
+                    std::fs::remove_dir(&path)
+                } else {
+                    std::fs::remove_file(&path)
+                };
+                removed.map_err(|e| fail(&path, e))?;
+                if self.is_current_uri(uri) {
+                    self.messages.warn(format!(
+                        "{} was deleted; saving will write it again",
+                        path.display()
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Apply `doc`'s edits to a file that is not open, saving the result.
+fn edit_file_on_disk(
+    doc: &TextDocumentEdit,
+    encoding: PositionEncoding,
+    mode: WriteMode,
+) -> Result<(), smash_core::error::EditError> {
+    let path = App::uri_to_path(&doc.text_document.uri);
+    let mut buffer = Buffer::from_file(BufferId::next(), &path)?;
+    let original = buffer.text().clone();
+    for (range, text) in edits_last_first(doc.edits.clone(), &original, encoding) {
+        buffer.apply_edit(EditCommand::Delete { range })?;
+        buffer.apply_edit(EditCommand::Insert {
+            pos: range.start,
+            text,
+        })?;
+    }
+    buffer.save_with(mode)
+}
@@ -0,0 +1,403 @@
+use smash_core::edit::EditCommand;
+use smash_core::position::{Position, Range};
+use smash_core::search::{SearchOptions, SearchQuery};
+use smash_input::vim::Operator;
+use smash_syntax::LanguageId;
+
+use super::App;
+
+/// First and last line of a range, 0-based and inclusive.
+type LineRange = (usize, usize);
+
+/// A parsed `:s/pattern/replacement/flags`.
+#[derive(Debug, PartialEq, Eq)]
+struct Substitute {
+    pattern: String,
+    replacement: String,
+    global: bool,
+    ignore_case: bool,
+}
+
+// =========================================================================
+// Ranged ex commands (`:10,20d`, `:%s/a/b/g`)
+// =========================================================================
+
+impl App {
+    /// Run `input` if it is a line command: a range with an optional
+    /// `d`, `y`, `>`, `<`, `!cmd` or `s/pat/rep/flags` after it, or `s` on the
+    /// cursor line. Returns `false` for anything else (`:w`, `:e`, ...).
+    pub(crate) fn run_line_command(&mut self, input: &str) -> bool {
+        let current = self.buffer.cursors().primary().position().line;
+        let (range, rest) = match parse_range(input, current, self.last_line()) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.messages.error(e);
+                return true;
+            }
+        };
+        let rest = rest.trim();
+        let (first, last) = match range {
+            Some(range) => range,
+            None if is_substitute(rest) => (current, current),
+            None => return false,
+        };
+        let lines = last - first + 1;
+        match rest {
+            // A bare address goes to its line.
+            "" => self.confirm_goto_line(&(last + 1).to_string()),
+            "d" => {
+                self.operate_lines(Operator::Delete, first, last);
+                self.messages.info(format!("{} line(s) deleted", lines));
+            }
+            "y" => self.operate_lines(Operator::Yank, first, last),
+            shift if shift.chars().all(|c| c == '>') => {
+                self.shift_lines(first, last, shift.len() as isize)
+            }
+            shift if shift.chars().all(|c| c == '<') => {
+                self.shift_lines(first, last, -(shift.len() as isize))
+            }
+            filter if filter.starts_with('!') => self.filter_lines(first, last, &filter[1..]),
+            sub if is_substitute(sub) => match parse_substitute(sub) {
+                Ok(sub) => self.substitute_lines(first, last, &sub),
+                Err(e) => self.messages.error(e),
+            },
+            _ => self
+                .messages
+                .error(format!("Not an editor command: {}", input)),
+        }
+        true
+    }
+
+    /// Indent lines `first..=last` by `levels` (outdent when negative).
+    /// Blank lines are not indented.
+    fn shift_lines(&mut self, first: usize, last: usize, levels: isize) {
+        let language = self
+            .buffer
+            .path()
+            .map(LanguageId::from_path)
+            .unwrap_or(LanguageId::Plain);
+        let editor = self
+            .editor_config
+            .with_overrides(self.language_configs.get(language.as_str()));
+        let tab_size = (editor.tab_size as usize).max(1);
+        let unit = if editor.insert_spaces {
+            " ".repeat(tab_size)
+        } else {
+            "\t".to_string()
+        };
+
+        let mut changed = 0;
+        self.buffer.begin_undo_group();
+        for line in first..=last {
+            let text: String = match self.buffer.line(line) {
+                Some(slice) => slice.chars().collect(),
+                None => continue,
+            };
+            let edit = if levels > 0 {
+                if text.trim().is_empty() {
+                    continue;
+                }
+                EditCommand::Insert {
+                    pos: Position::new(line, 0),
+                    text: unit.repeat(levels as usize),
+                }
+            } else {
+                let width = outdent_width(&text, tab_size, levels.unsigned_abs());
+                if width == 0 {
+                    continue;
+                }
+                EditCommand::Delete {
+                    range: Range::new(Position::new(line, 0), Position::new(line, width)),
+                }
+            };
+            if self.apply_edit(edit).is_err() {
+                break;
+            }
+            changed += 1;
+        }
+        self.buffer.end_undo_group();
+        if changed > 0 {
+            self.lsp_did_change();
+        }
+        self.set_cursor(Position::new(last, self.first_non_blank(last)));
+        let verb = if levels > 0 { "indented" } else { "outdented" };
+        self.messages.info(format!("{} line(s) {}", changed, verb));
+    }
+
+    /// Replace `sub.pattern` on lines `first..=last`, the first match on
+    /// each line or every match with the `g` flag, as one undo step.
+    fn substitute_lines(&mut self, first: usize, last: usize, sub: &Substitute) {
+        let options = SearchOptions {
+            regex: true,
+            case_sensitive: !sub.ignore_case,
+            whole_word: false,
+        };
+        let re = match options.query(&sub.pattern) {
+            Ok(SearchQuery::Regex(re)) => re,
+            Ok(SearchQuery::Plain { .. }) => return,
+            Err(e) => {
+                self.messages.error(format!("Invalid regex: {}", e));
+                return;
+            }
+        };
+        let range = Range::new(
+            Position::new(first, 0),
+            Position::new(last, self.line_len(last)),
+        );
+        let text = self.buffer.text_in_range(range).unwrap_or_default();
+
+        let mut out = String::with_capacity(text.len());
+        let (mut count, mut lines) = (0, 0);
+        // Line of the last substitution, counted in the new text.
+        let mut cursor_line = first;
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            // Match without the `\r` of a CRLF break, so `$` still anchors
+            // at the end of the line.
+            let (line, cr) = match line.strip_suffix('\r') {
+                Some(line) => (line, "\r"),
+                None => (line, ""),
+            };
+            let found = if sub.global {
+                re.find_iter(line).count()
+            } else {
+                usize::from(re.is_match(line))
+            };
+            if found == 0 {
+                out.push_str(line);
+                out.push_str(cr);
+                continue;
+            }
+            cursor_line = first + out.matches('\n').count();
+            let limit = if sub.global { 0 } else { 1 };
+            out.push_str(&re.replacen(line, limit, sub.replacement.as_str()));
+            out.push_str(cr);
+            count += found;
+            lines += 1;
+        }
+        if count == 0 {
+            self.messages
+                .error(format!("Pattern not found: {}", sub.pattern));
+            return;
+        }
+
+        self.buffer.begin_undo_group();
+        let replaced = self
+            .apply_edit(EditCommand::Delete { range })
+            .and_then(|_| {
+                self.apply_edit(EditCommand::Insert {
+                    pos: range.start,
+                    text: out,
+                })
+            });
+        self.buffer.end_undo_group();
+        if replaced.is_err() {
+            return;
+        }
+        self.lsp_did_change();
+        self.set_cursor(Position::new(
+            cursor_line,
+            self.first_non_blank(cursor_line),
+        ));
+        self.messages
+            .info(format!("{} substitution(s) on {} line(s)", count, lines));
+    }
+}
+
+/// Split a leading line range off `input`, as 0-based inclusive lines.
+///
+/// A range is `%` (every line) or one or two addresses separated by `,`.
+/// An address is a line number, `.` (the cursor line) or `$` (the last
+/// line), optionally followed by `+N`/`-N` offsets; a bare offset is
+/// relative to the cursor line. Reversed ranges are swapped.
+fn parse_range(
+    input: &str,
+    current: usize,
+    last: usize,
+) -> Result<(Option<LineRange>, &str), String> {
+    if let Some(rest) = input.strip_prefix('%') {
+        return Ok((Some((0, last)), rest));
+    }
+    let (Some(start), rest) = parse_address(input, current, last)? else {
+        return Ok((None, input));
+    };
+    let (end, rest) = match rest.strip_prefix(',') {
+        Some(after) => match parse_address(after, current, last)? {
+            (Some(end), rest) => (end, rest),
+            (None, _) => return Err(format!("Invalid range: {}", input)),
+        },
+        None => (start, rest),
+    };
+    if start > last || end > last {
+        return Err(format!("Invalid range: {}", input));
+    }
+    Ok((Some((start.min(end), start.max(end))), rest))
+}
+
+/// Parse one address at the start of `s`, returning the 0-based line
+/// and the text after it, or `None` if `s` does not start with one.
+fn parse_address(s: &str, current: usize, last: usize) -> Result<(Option<usize>, &str), String> {
+    let invalid = || format!("Invalid range: {}", s);
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (mut line, mut rest) = match s.chars().next() {
+        Some('0'..='9') => {
+            let len = digits(s);
+            let n: usize = s[..len].parse().map_err(|_| invalid())?;
+            // Line numbers are 1-based.
+            (n.checked_sub(1).ok_or_else(invalid)?, &s[len..])
+        }
+        Some('.') => (current, &s[1..]),
+        Some('$') => (last, &s[1..]),
+        Some('+' | '-') => (current, s),
+        _ => return Ok((None, s)),
+    };
+    while let Some(sign) = rest.chars().next().filter(|c| matches!(c, '+' | '-')) {
+        let after = &rest[1..];
+        let len = digits(after);
+        let n = if len == 0 {
+            1
+        } else {
+            after[..len].parse().map_err(|_| invalid())?
+        };
+        line = if sign == '+' {
+            line.checked_add(n)
+        } else {
+            line.checked_sub(n)
+        }
+        .ok_or_else(invalid)?;
+        rest = &after[len..];
+    }
+    Ok((Some(line), rest))
+}
+
+/// Whether `cmd` is `s` followed by a delimiter, as in `s/a/b/`.
+fn is_substitute(cmd: &str) -> bool {
+    let mut chars = cmd.chars();
+    chars.next() == Some('s')
+        && chars
+            .next()
+            .is_some_and(|c| !c.is_alphanumeric() && !c.is_whitespace() && c != '\\')
+}
+
+/// Parse `s/pattern/replacement/flags`, with any delimiter after the
+/// `s`. A backslash before the delimiter makes it literal; other escapes
+/// are left for the regex.
+fn parse_substitute(cmd: &str) -> Result<Substitute, String> {
+    let mut chars = cmd.chars().skip(1);
+    let delim = chars.next().ok_or("Missing pattern")?;
+    // Finished parts, and the one being read.
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == delim => part.push(next),
+                Some(next) => {
+                    part.push('\\');
+                    part.push(next);
+                }
+                None => part.push('\\'),
+            },
+            c if c == delim && parts.len() < 2 => parts.push(std::mem::take(&mut part)),
+            c => part.push(c),
+        }
+    }
+    parts.push(part);
+    let mut parts = parts.into_iter();
+    let pattern = parts.next().unwrap_or_default();
+    let replacement = parts.next().unwrap_or_default();
+    let flags = parts.next().unwrap_or_default();
+    if pattern.is_empty() {
+        return Err("Empty search pattern".to_string());
+    }
+    if let Some(bad) = flags.chars().find(|c| !matches!(c, 'g' | 'i')) {
+        return Err(format!("Unknown substitute flag: {}", bad));
+    }
+    Ok(Substitute {
+        pattern,
+        replacement,
+        global: flags.contains('g'),
+        ignore_case: flags.contains('i'),
+    })
+}
+
+/// Characters to remove from the start of `line` to outdent it by
+/// `levels`: a tab or up to `tab_size` spaces per level.
+fn outdent_width(line: &str, tab_size: usize, levels: usize) -> usize {
+    let mut width = 0;
+    for _ in 0..levels {
+        let rest = &line[width..];
+        if rest.starts_with('\t') {
+            width += 1;
+            continue;
+        }
+        let spaces = rest
+            .chars()
+            .take(tab_size)
+            .take_while(|c| *c == ' ')
+            .count();
+        if spaces == 0 {
+            break;
+        }
+        width += spaces;
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_parse_numbers_marks_and_offsets() {
+        // Cursor on line 5 (0-based 4) of 20.
+        let parse = |s| parse_range(s, 4, 19);
+        assert_eq!(parse("10,20d"), Ok((Some((9, 19)), "d")));
+        assert_eq!(parse("%s/a/b/"), Ok((Some((0, 19)), "s/a/b/")));
+        assert_eq!(parse(".,$y"), Ok((Some((4, 19)), "y")));
+        assert_eq!(parse(".,+2>"), Ok((Some((4, 6)), ">")));
+        assert_eq!(parse("-1"), Ok((Some((3, 3)), "")));
+        assert_eq!(parse("$-2,$"), Ok((Some((17, 19)), "")));
+        assert_eq!(parse("7,3d"), Ok((Some((2, 6)), "d")));
+        assert_eq!(parse("w"), Ok((None, "w")));
+        assert!(parse("0d").is_err());
+        assert!(parse("5,25d").is_err());
+        assert!(parse("5,d").is_err());
+        assert!(parse("1-3").is_err());
+    }
+
+    #[test]
+    fn substitutions_parse_delimiters_and_flags() {
+        assert_eq!(
+            parse_substitute("s/a\\/b/$1 c/gi"),
+            Ok(Substitute {
+                pattern: "a/b".to_string(),
+                replacement: "$1 c".to_string(),
+                global: true,
+                ignore_case: true,
+            })
+        );
+        let sub = parse_substitute("s#\\d+#n").unwrap();
+        assert_eq!(
+            (sub.pattern.as_str(), sub.replacement.as_str()),
+            ("\\d+", "n")
+        );
+        assert!(!sub.global);
+        assert!(parse_substitute("s//x/").is_err());
+        assert!(parse_substitute("s/a/b/x").is_err());
+        assert!(is_substitute("s/a/b"));
+        assert!(!is_substitute("set"));
+        assert!(!is_substitute("s"));
+    }
+
+    #[test]
+    fn outdent_removes_a_tab_or_a_level_of_spaces() {
+        assert_eq!(outdent_width("        x", 4, 1), 4);
+        assert_eq!(outdent_width("  x", 4, 1), 2);
+        assert_eq!(outdent_width("\t\tx", 4, 1), 1);
+        assert_eq!(outdent_width("\t  x", 4, 2), 3);
+        assert_eq!(outdent_width("x", 4, 1), 0);
+    }
+}
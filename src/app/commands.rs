@@ -1,53 +1,211 @@
 use smash_core::buffer::BufferId;
-use smash_core::edit::EditCommand;
-use smash_core::position::Position;
+use smash_core::edit::{EditCommand, EditEvent};
+use smash_core::encoding::LineEnding;
+use smash_core::error::EditError;
+use smash_core::fuzzy_finder::fuzzy_score;
+use smash_core::indent::{indent_for_closer, newline_indent, IndentRules, IndentStyle};
+use smash_core::position::{Position, Range};
 use smash_core::search::SearchQuery;
-use smash_input::Command;
-use smash_syntax::{LanguageId, RegexHighlighter};
+use smash_core::undo::UndoBudget;
+use smash_input::command::Direction;
+use smash_input::{Command, Key, KeyEvent, Modifiers, PALETTE_COMMANDS};
+use smash_lsp::GotoTarget;
+use smash_syntax::LanguageId;
+use smash_terminal::Signal;
 use tracing::{error, info};
 
+use smash_tui::{export_highlighted, ExportFormat, ExportOptions};
+
+use super::abbreviations::Expansion;
+use super::diff_view::DiffBase;
+use super::paths::PathFormat;
+use super::transform::TextCase;
 use super::{line_content_len, App, InputMode, JumpLocation};
+use crate::job::JobStatus;
+use crate::lsp_types::CallDirection;
+use crate::watch::WatchJob;
+
+/// Parse a `START-END` range of 1-based line numbers.
+fn parse_line_range(token: &str) -> Option<(usize, usize)> {
+    let (start, end) = token.split_once('-')?;
+    let start: usize = start.parse().ok()?;
+    let end: usize = end.parse().ok()?;
+    (start >= 1 && start <= end).then_some((start, end))
+}
+
+/// Best palette command for `query`, fuzzy-matched against command names
+/// and descriptions. Earlier palette entries win ties.
+pub(crate) fn palette_match(query: &str) -> Option<Command> {
+    let query = query.trim();
+    if query.is_empty() {
+        return None;
+    }
+    PALETTE_COMMANDS
+        .iter()
+        .rev()
+        .filter_map(|cmd| {
+            let name = format!("{:?}", cmd);
+            let score = [
+                fuzzy_score(query, &name),
+                fuzzy_score(query, cmd.info().description),
+            ]
+            .into_iter()
+            .flatten()
+            .max()?;
+            Some((score, cmd))
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, cmd)| cmd.clone())
+}
 
 // =========================================================================
 // Command dispatch
 // =========================================================================
 
 impl App {
+    /// Close the popup on any key. Returns `true` if the key was consumed:
+    /// Esc, or a digit choosing one of the listed quick fixes.
+    pub(crate) fn dismiss_popup(&mut self, key: &KeyEvent) -> bool {
+        if self.popup.take().is_none() {
+            return false;
+        }
+        self.quick_fix_diagnostics = None;
+        if key.modifiers == Modifiers::NONE {
+            if let Key::Char(c @ '1'..='9') = key.key {
+                let index = c as usize - '1' as usize;
+                if index < self.quick_fixes.len() {
+                    self.apply_quick_fix(index);
+                    return true;
+                }
+            }
+        }
+        self.quick_fixes.clear();
+        key.key == Key::Esc
+    }
+
     /// Top-level command handler (Normal mode).
     pub(crate) fn handle_command(&mut self, cmd: Command) {
+        // Only Backspace right after an expansion takes it back.
+        let expansion = self.last_expansion.take();
         // When in a prompt mode, route input differently
         if self.input_mode != InputMode::Normal {
             self.handle_prompt_command(cmd);
             return;
         }
 
+        if self.handle_command_while_loading(&cmd) {
+            return;
+        }
+
+        let handled = cmd.clone();
+        // Only a paste right before may be replaced by a yank-pop.
+        if cmd != Command::YankPop {
+            self.last_yank = None;
+        }
         match cmd {
-            Command::Quit | Command::ForceQuit => {
+            Command::Quit | Command::Close => self.cmd_quit(),
+            Command::ForceQuit => {
                 self.running = false;
             }
-            Command::InsertChar(c) => self.cmd_insert_char(c),
-            Command::InsertNewline => {
-                self.handle_command(Command::InsertChar('\n'));
+            Command::AbortEdit => self.cmd_abort_edit(),
+            Command::RebasePick => self.cmd_rebase_action("pick"),
+            Command::RebaseReword => self.cmd_rebase_action("reword"),
+            Command::RebaseSquash => self.cmd_rebase_action("squash"),
+            Command::RebaseFixup => self.cmd_rebase_action("fixup"),
+            Command::InsertChar(c) => {
+                self.insert_with_abbreviation(c, |app| app.cmd_insert_char(c))
             }
-            Command::DeleteBackward => self.cmd_delete_backward(),
+            Command::InsertNewline => self.insert_with_abbreviation('\n', Self::cmd_insert_newline),
+            Command::DeleteBackward => self.cmd_delete_backward(expansion),
             Command::DeleteForward => self.cmd_delete_forward(),
-            Command::MoveLeft => self.cmd_move_left(),
-            Command::MoveRight => self.cmd_move_right(),
-            Command::MoveUp => self.cmd_move_up(),
-            Command::MoveDown => self.cmd_move_down(),
-            Command::MoveLineStart => self.cmd_move_line_start(),
-            Command::MoveLineEnd => self.cmd_move_line_end(),
-            Command::MoveBufferStart => self.cmd_move_buffer_start(),
-            Command::MoveBufferEnd => self.cmd_move_buffer_end(),
-            Command::PageUp => self.cmd_page_up(),
-            Command::PageDown => self.cmd_page_down(),
+            Command::MoveLeft
+            | Command::MoveRight
+            | Command::MoveUp
+            | Command::MoveDown
+            | Command::MoveWordLeft
+            | Command::MoveWordRight
+            | Command::MoveLineStart
+            | Command::SmartHome
+            | Command::MoveLineEnd
+            | Command::MoveBufferStart
+            | Command::MoveBufferEnd
+            | Command::PageUp
+            | Command::PageDown => {
+                // Paging scrolls the view, so it leaves one cursor.
+                if matches!(cmd, Command::PageUp | Command::PageDown) {
+                    self.collapse_cursors();
+                }
+                let step = |app: &mut Self| {
+                    app.buffer.cursors_mut().primary_mut().clear_selection();
+                    app.apply_motion(&cmd);
+                };
+                if !self.for_each_cursor(step) {
+                    step(self);
+                }
+            }
+            Command::CenterCursor => {
+                let line = self.buffer.cursors().primary().position().line;
+                self.viewport.center_on(line, self.buffer.line_count());
+            }
+            Command::CursorToTop => {
+                let line = self.buffer.cursors().primary().position().line;
+                self.viewport.align_top(line, self.buffer.line_count());
+            }
+            Command::CursorToBottom => {
+                let line = self.buffer.cursors().primary().position().line;
+                self.viewport.align_bottom(line);
+            }
+            Command::ScrollLineUp => self.scroll_view_lines(true, 1),
+            Command::ScrollLineDown => self.scroll_view_lines(false, 1),
+            Command::ExtendSelection(dir) => {
+                let motion = match dir {
+                    Direction::Left => Command::MoveLeft,
+                    Direction::Right => Command::MoveRight,
+                    Direction::Up => Command::MoveUp,
+                    Direction::Down => Command::MoveDown,
+                };
+                self.cmd_extend_selection(&motion);
+            }
+            Command::SelectWordLeft => self.cmd_extend_selection(&Command::MoveWordLeft),
+            Command::SelectWordRight => self.cmd_extend_selection(&Command::MoveWordRight),
+            Command::SelectLineStart => self.cmd_extend_selection(&Command::MoveLineStart),
+            Command::SelectSmartHome => self.cmd_extend_selection(&Command::SmartHome),
+            Command::SelectLineEnd => self.cmd_extend_selection(&Command::MoveLineEnd),
+            Command::SelectBufferStart => self.cmd_extend_selection(&Command::MoveBufferStart),
+            Command::SelectBufferEnd => self.cmd_extend_selection(&Command::MoveBufferEnd),
+            Command::Copy => self.cmd_copy(),
+            Command::Cut => self.cmd_cut(),
+            Command::Paste => self.cmd_paste(),
+            Command::PasteFromHistory => self.cmd_paste_from_history(),
+            Command::PasteFromRegister(name) => self.cmd_paste_from_register(name),
+            Command::ShowRegisters => self.cmd_show_registers(),
+            Command::YankPop => self.cmd_yank_pop(),
+            Command::CopyPath => self.cmd_copy_path(PathFormat::Absolute),
+            Command::CopyRelativePath => self.cmd_copy_path(PathFormat::Relative),
+            Command::CopyFileUri => self.cmd_copy_path(PathFormat::Uri),
+            Command::PasteMarkdownLink => self.cmd_paste_markdown_link(),
             Command::Undo => {
-                let _ = self.buffer.undo();
+                self.buffer.cursors_mut().primary_mut().clear_selection();
+                if let Ok(Some(events)) = self.buffer.undo() {
+                    self.record_edit_events(&events);
+                    self.lsp_did_change();
+                }
+            }
+            Command::ClearUndoHistory => {
+                self.buffer.clear_undo_history();
+                self.messages.info("Undo history cleared");
             }
             Command::Redo => {
-                let _ = self.buffer.redo();
+                self.buffer.cursors_mut().primary_mut().clear_selection();
+                if let Ok(Some(events)) = self.buffer.redo() {
+                    self.record_edit_events(&events);
+                    self.lsp_did_change();
+                }
             }
             Command::Save => self.cmd_save(),
+            Command::SaveAll => self.cmd_save_all(),
+            Command::SudoSave => self.cmd_sudo_save(),
+            Command::ToggleReadOnly => self.cmd_toggle_read_only(),
             Command::Open => {
                 self.input_mode = InputMode::PromptOpen;
                 self.prompt_input.clear();
@@ -55,46 +213,103 @@ impl App {
             Command::Find => {
                 self.input_mode = InputMode::PromptFind;
                 self.prompt_input.clear();
+                self.search_history_index = None;
             }
+            Command::SearchPresets => self.cmd_search_presets(),
+            Command::SaveSearchPreset => self.cmd_save_search_preset(),
             Command::FindNext => self.find_next(),
             Command::FindPrev => self.find_prev(),
+            Command::ToggleSearchRegex
+            | Command::ToggleSearchCase
+            | Command::ToggleSearchWholeWord => {
+                self.toggle_search_option(&cmd);
+                let on = self.search_options.describe();
+                self.messages.info(if on.is_empty() {
+                    "Search options: none".to_string()
+                } else {
+                    format!("Search options: {}", on)
+                });
+            }
             Command::GoToLine => {
                 self.input_mode = InputMode::PromptGoToLine;
                 self.prompt_input.clear();
             }
-            Command::FindReplace => {
+            Command::OpenCommandLine => self.open_command_line(),
+            Command::FindReplace | Command::FindReplaceInteractive => {
                 self.input_mode = InputMode::PromptFindReplace;
                 self.prompt_input.clear();
                 self.replace_input.clear();
                 self.replace_focused = false;
+                self.replace_interactive = cmd == Command::FindReplaceInteractive;
+                self.search_history_index = None;
             }
             Command::SaveAs => {
                 self.input_mode = InputMode::PromptSaveAs;
                 self.prompt_input.clear();
             }
-            Command::Close => {
-                self.running = false;
+            Command::ReopenWithEncoding => {
+                self.input_mode = InputMode::PromptReopenEncoding;
+                self.prompt_input.clear();
+            }
+            Command::SetLineEnding => {
+                self.input_mode = InputMode::PromptLineEnding;
+                self.prompt_input.clear();
+            }
+            Command::NewBuffer => self.cmd_new_buffer(),
+            Command::ExportHighlighted => {
+                self.input_mode = InputMode::PromptExport;
+                self.prompt_input.clear();
+            }
+            Command::WatchCommand => {
+                self.input_mode = InputMode::PromptWatch;
+                self.prompt_input.clear();
+            }
+            Command::RunShellCommand => {
+                self.input_mode = InputMode::PromptShellCommand;
+                self.prompt_input.clear();
             }
+            Command::RunTask => self.cmd_run_task(),
+            Command::QuickfixNext => self.cmd_quickfix_step(true),
+            Command::QuickfixPrev => self.cmd_quickfix_step(false),
+            Command::ShowDiagnostics => self.cmd_show_diagnostics(),
             Command::OpenFileFinder => self.cmd_open_file_finder(),
+            Command::OpenRecent => self.cmd_open_recent(),
             Command::OpenCommandPalette => {
-                self.messages.info("Command palette not yet implemented");
+                self.input_mode = InputMode::CommandPalette;
+                self.prompt_input.clear();
             }
-            Command::MoveWordLeft => self.move_word_left(),
-            Command::MoveWordRight => self.move_word_right(),
             Command::DeleteLine => self.delete_current_line(),
-            Command::SelectAll => {
-                self.buffer
-                    .cursors_mut()
-                    .primary_mut()
-                    .set_position(Position::new(0, 0));
-                self.messages.info("SelectAll: cursor moved to start");
-            }
+            Command::DuplicateLine => self.cmd_duplicate_line(),
+            Command::MoveLineUp => self.cmd_move_line_up(),
+            Command::MoveLineDown => self.cmd_move_line_down(),
+            Command::JoinLines => self.cmd_join_lines(),
+            Command::UppercaseSelection => self.cmd_change_case(TextCase::Upper),
+            Command::LowercaseSelection => self.cmd_change_case(TextCase::Lower),
+            Command::TitleCaseSelection => self.cmd_change_case(TextCase::Title),
+            Command::TransposeChars => self.cmd_transpose_chars(),
+            Command::TransposeWords => self.cmd_transpose_words(),
+            Command::SelectAll => self.cmd_select_all(),
+            Command::AddCursorAbove => self.cmd_add_cursor_vertical(false),
+            Command::AddCursorBelow => self.cmd_add_cursor_vertical(true),
+            Command::AddCursorAtNextMatch => self.cmd_add_cursor_at_next_match(),
             // --- LSP commands ---
             Command::LspHover => self.lsp_hover(),
-            Command::LspGotoDefinition => self.lsp_goto_definition(),
+            Command::LspGotoDefinition => self.lsp_goto_definition(GotoTarget::Definition),
+            Command::LspGotoTypeDefinition => {
+                self.lsp_goto_definition(GotoTarget::TypeDefinition);
+            }
+            Command::LspGotoImplementation => {
+                self.lsp_goto_definition(GotoTarget::Implementation);
+            }
+            Command::LspGotoDeclaration => self.lsp_goto_definition(GotoTarget::Declaration),
             Command::LspFindReferences => self.lsp_find_references(),
+            Command::LspDocumentSymbols => self.lsp_document_symbols(),
+            Command::LspIncomingCalls => self.lsp_call_hierarchy(CallDirection::Incoming),
+            Command::LspOutgoingCalls => self.lsp_call_hierarchy(CallDirection::Outgoing),
+            Command::LspWorkspaceSymbols => self.lsp_workspace_symbols(),
             Command::LspCompletion => self.lsp_completion(),
             Command::LspFormat => self.lsp_format(),
+            Command::LspFormatSelection => self.lsp_format_selection(),
             Command::LspRename => {
                 if self.lsp_server_started {
                     self.input_mode = InputMode::PromptLspRename;
@@ -106,14 +321,77 @@ impl App {
             Command::LspCodeAction => self.lsp_code_action(),
             Command::LspDiagnosticNext => self.lsp_diagnostic_next(),
             Command::LspDiagnosticPrev => self.lsp_diagnostic_prev(),
+            Command::ShowDiagnosticAtCursor => self.show_diagnostic_at_cursor(),
             Command::LspRestart => self.start_lsp_for_current_file(),
             // --- Jump navigation ---
             Command::JumpBack => self.cmd_jump_back(),
+            Command::JumpToLastEdit => self.cmd_jump_to_last_edit(),
+            Command::SetMark(name) => self.cmd_set_mark(name),
+            Command::GotoMark(name) => self.cmd_goto_mark(name),
+            Command::ListMarks => self.cmd_list_marks(),
             Command::JumpForward => self.cmd_jump_forward(),
+            Command::AlternateBuffer => self.cmd_alternate_buffer(),
+            Command::GoToCompanionFile => self.cmd_go_to_companion_file(),
+            Command::OpenLinkUnderCursor => self.cmd_open_link_under_cursor(),
+            Command::FileInfo => self.cmd_file_info(),
+            Command::ShowHealth => self.cmd_show_health(),
+            Command::ToggleRenderStats => {
+                self.render_stats.visible = !self.render_stats.visible;
+            }
+            Command::ShowHelp => self.cmd_show_help(),
+            Command::OpenUserConfig => self.cmd_open_user_config(),
+            Command::OpenProjectConfig => self.cmd_open_project_config(),
+            Command::OpenLogFile => self.cmd_open_log_file(),
+            Command::ReloadConfig => self.reload_config(),
+            Command::SelectTheme => self.cmd_select_theme(),
+            Command::SetLogLevel => {
+                self.input_mode = InputMode::PromptLogLevel;
+                self.prompt_input = self
+                    .log_filter
+                    .as_ref()
+                    .map(|f| f.current().to_string())
+                    .unwrap_or_default();
+            }
+            Command::ToggleTerminal => self.cmd_toggle_terminal(),
+            Command::ToggleFileTree => self.cmd_toggle_file_tree(),
+            Command::ToggleLineNumbers => {
+                self.show_line_numbers = !self.show_line_numbers;
+            }
+            Command::ToggleRulers => {
+                self.show_rulers = !self.show_rulers;
+                if self.rulers().is_empty() && self.show_rulers {
+                    self.messages
+                        .info("No rulers; set them with display.rulers in the config");
+                }
+            }
+            Command::ToggleCurrentLineHighlight => {
+                self.highlight_current_line = !self.highlight_current_line;
+            }
+            Command::ToggleIndentGuides => {
+                self.indent_guides = !self.indent_guides;
+            }
+            Command::ToggleMinimap => {
+                self.show_minimap = !self.show_minimap;
+            }
+            Command::ToggleScrollbar => {
+                self.show_scrollbar = !self.show_scrollbar;
+            }
+            Command::ToggleGitBlame => self.cmd_toggle_git_blame(),
+            Command::DiffAgainstDisk => self.cmd_diff(DiffBase::Disk),
+            Command::DiffAgainstHead => self.cmd_diff(DiffBase::Head),
+            Command::WorkspaceSearch => self.cmd_workspace_search(),
+            Command::NewTerminal => self.cmd_new_terminal(),
+            Command::TerminalInterrupt => self.cmd_terminal_signal(Signal::Interrupt),
+            Command::TerminalTerminate => self.cmd_terminal_signal(Signal::Terminate),
+            Command::TerminalKill => self.cmd_terminal_signal(Signal::Kill),
+            Command::TerminalRespawn => self.cmd_terminal_respawn(),
             _ => {
                 // Commands not yet implemented in prototype
             }
         }
+        self.update_signature_help(&handled);
+        self.update_auto_completion(&handled);
+        self.queue_compaction_if_due();
     }
 
     /// Handle input while a prompt is active.
@@ -121,6 +399,31 @@ impl App {
         match cmd {
             Command::InsertChar(c) => {
                 match self.input_mode {
+                    InputMode::PromptTreeDelete => {
+                        // Answered with a single key.
+                        self.input_mode = InputMode::Normal;
+                        self.confirm_tree_delete(matches!(c, 'y' | 'Y'));
+                        return;
+                    }
+                    InputMode::PromptReload => {
+                        self.input_mode = InputMode::Normal;
+                        self.confirm_reload(matches!(c, 'y' | 'Y'));
+                        return;
+                    }
+                    InputMode::PromptQuit => {
+                        self.input_mode = InputMode::Normal;
+                        self.confirm_quit(c);
+                        return;
+                    }
+                    InputMode::PromptCreateCompanion => {
+                        self.input_mode = InputMode::Normal;
+                        self.confirm_create_companion(matches!(c, 'y' | 'Y'));
+                        return;
+                    }
+                    InputMode::PromptReplaceStep => {
+                        self.answer_replace(c);
+                        return;
+                    }
                     InputMode::PromptFindReplace if self.replace_focused => {
                         self.replace_input.push(c);
                     }
@@ -128,6 +431,17 @@ impl App {
                         self.prompt_input.push(c);
                         self.update_finder_results();
                     }
+                    InputMode::SymbolPicker
+                    | InputMode::CodeActionPicker
+                    | InputMode::ThemePicker
+                    | InputMode::SearchPicker
+                    | InputMode::KillRingPicker
+                    | InputMode::RecentPicker
+                    | InputMode::MarkPicker
+                    | InputMode::RegisterPicker => {
+                        self.prompt_input.push(c);
+                        self.update_picker();
+                    }
                     _ => {
                         self.prompt_input.push(c);
                     }
@@ -145,7 +459,51 @@ impl App {
                     InputMode::PromptFind => self.confirm_find(&input),
                     InputMode::PromptGoToLine => self.confirm_goto_line(&input),
                     InputMode::PromptSaveAs => self.confirm_save_as(&input),
+                    InputMode::PromptQuit => self.confirm_quit('\n'),
+                    InputMode::PromptSaveAsAndQuit => self.confirm_save_as_and_quit(&input),
+                    InputMode::PromptExport => self.confirm_export(&input),
+                    InputMode::PromptWatch => self.confirm_watch(&input),
+                    InputMode::PromptShellCommand => self.confirm_shell_command(&input),
+                    InputMode::PromptTask => self.confirm_task(&input),
+                    InputMode::PromptLogLevel => self.confirm_log_level(&input),
+                    InputMode::PromptReopenEncoding => self.confirm_reopen_with_encoding(&input),
+                    InputMode::PromptLineEnding => self.confirm_line_ending(&input),
+                    InputMode::PromptVimCommand => {
+                        // `:w` on an unnamed buffer opens the Save-As prompt.
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_input.clear();
+                        self.confirm_vim_command(&input);
+                        return;
+                    }
+                    InputMode::CommandPalette => {
+                        // The chosen command may open a prompt of its own,
+                        // so leave palette mode before running it.
+                        self.input_mode = InputMode::Normal;
+                        self.prompt_input.clear();
+                        self.confirm_palette(&input);
+                        return;
+                    }
                     InputMode::PromptLspRename => self.confirm_lsp_rename(&input),
+                    InputMode::PromptTreeCreate => self.confirm_tree_create(&input),
+                    InputMode::PromptTreeRename => self.confirm_tree_rename(&input),
+                    InputMode::PromptTreeDelete => self.confirm_tree_delete(false),
+                    InputMode::PromptReload => self.confirm_reload(false),
+                    InputMode::PromptCreateCompanion => self.confirm_create_companion(false),
+                    InputMode::PromptReplaceStep => return,
+                    InputMode::PromptWorkspaceSearch => self.confirm_workspace_search(&input),
+                    InputMode::SymbolPicker => self.confirm_symbol_picker(),
+                    InputMode::CodeActionPicker => self.confirm_code_action_picker(),
+                    InputMode::ThemePicker => self.confirm_theme_picker(),
+                    InputMode::KillRingPicker => self.confirm_kill_ring_picker(),
+                    InputMode::RecentPicker => self.confirm_recent_picker(),
+                    InputMode::MarkPicker => self.confirm_mark_picker(),
+                    InputMode::RegisterPicker => self.confirm_register_picker(),
+                    InputMode::SearchPicker => {
+                        // Leaves a Find prompt open, filled in.
+                        self.confirm_search_picker();
+                        return;
+                    }
+                    InputMode::PromptSearchPresetName => self.confirm_save_search_preset(&input),
                     InputMode::PromptFindReplace => {
                         if !self.replace_focused {
                             // Tab to replacement field
@@ -153,6 +511,10 @@ impl App {
                             return;
                         }
                         let replacement = self.replace_input.clone();
+                        if self.replace_interactive {
+                            self.start_interactive_replace(&input, &replacement);
+                            return;
+                        }
                         self.confirm_find_replace(&input, &replacement);
                     }
                     InputMode::FileFinder => {
@@ -175,6 +537,17 @@ impl App {
                         self.prompt_input.pop();
                         self.update_finder_results();
                     }
+                    InputMode::SymbolPicker
+                    | InputMode::CodeActionPicker
+                    | InputMode::ThemePicker
+                    | InputMode::SearchPicker
+                    | InputMode::KillRingPicker
+                    | InputMode::RecentPicker
+                    | InputMode::MarkPicker
+                    | InputMode::RegisterPicker => {
+                        self.prompt_input.pop();
+                        self.update_picker();
+                    }
                     _ => {
                         self.prompt_input.pop();
                     }
@@ -186,13 +559,57 @@ impl App {
             }
             Command::Quit | Command::ForceQuit => {
                 // Treat as cancel
+                self.finish_replace();
                 self.input_mode = InputMode::Normal;
                 self.prompt_input.clear();
                 self.replace_input.clear();
                 self.finder_results.clear();
+                self.finder_preview = None;
             }
-            _ => {
+            Command::ToggleSearchRegex
+            | Command::ToggleSearchCase
+            | Command::ToggleSearchWholeWord
+                if matches!(
+                    self.input_mode,
+                    InputMode::PromptFind | InputMode::PromptFindReplace
+                ) =>
+            {
+                self.toggle_search_option(&cmd);
+                if self.input_mode == InputMode::PromptFind {
+                    self.incremental_search();
+                }
+            }
+            Command::MoveUp | Command::MoveDown
+                if matches!(
+                    self.input_mode,
+                    InputMode::PromptFind | InputMode::PromptFindReplace
+                ) =>
+            {
+                self.recall_search(cmd == Command::MoveUp);
+            }
+            Command::SearchPresets
+                if matches!(
+                    self.input_mode,
+                    InputMode::PromptFind | InputMode::PromptFindReplace
+                ) =>
+            {
+                self.cmd_search_presets();
+            }
+            Command::CopyPath if self.input_mode == InputMode::FileFinder => {
+                self.cmd_copy_path(PathFormat::Absolute);
+            }
+            Command::CopyRelativePath if self.input_mode == InputMode::FileFinder => {
+                self.cmd_copy_path(PathFormat::Relative);
+            }
+            Command::CopyFileUri if self.input_mode == InputMode::FileFinder => {
+                self.cmd_copy_path(PathFormat::Uri);
+            }
+            cmd if self.input_mode == InputMode::FileFinder => {
+                self.move_finder_selection(&cmd);
+            }
+            cmd => {
                 // Ignore other commands while in prompt mode
+                self.move_picker_selection(&cmd);
             }
         }
     }
@@ -204,121 +621,356 @@ impl App {
 
 impl App {
     fn cmd_insert_char(&mut self, c: char) {
-        let pos = self.buffer.cursors().primary().position();
-        let edit = EditCommand::Insert {
-            pos,
-            text: c.to_string(),
+        let cursor = self.buffer.cursors().primary();
+        let pos = cursor.position();
+        let selecting = cursor.selection_range().is_some_and(|r| !r.is_empty());
+        let dedent = match self.indent_settings() {
+            Some((rules, style)) if self.buffer.cursors().len() == 1 && !selecting => {
+                indent_for_closer(&self.line_before(pos), c, &rules, &style)
+            }
+            _ => None,
         };
-        if self.buffer.apply_edit(edit).is_ok() {
-            let new_pos = if c == '\n' {
-                Position::new(pos.line + 1, 0)
-            } else {
-                Position::new(pos.line, pos.col + 1)
-            };
+        if let Some(indent) = dedent {
+            // Replace the blank indent so the closer lines up with its opener.
             self.buffer
                 .cursors_mut()
                 .primary_mut()
-                .set_position(new_pos);
+                .set_anchor(Position::new(pos.line, 0));
+            self.insert_text(&format!("{}{}", indent, c));
+            return;
+        }
+        self.insert_text(&c.to_string());
+    }
+
+    /// Split the line at the cursor, indenting the new line for the
+    /// buffer's language when `auto_indent` is on.
+    fn cmd_insert_newline(&mut self) {
+        let settings = self.indent_settings();
+        let (rules, style) = match settings {
+            Some(settings) if self.buffer.cursors().len() == 1 => settings,
+            _ => return self.insert_text("\n"),
+        };
+        let cursor = self.buffer.cursors().primary();
+        let range = cursor
+            .selection_range()
+            .filter(|r| !r.is_empty())
+            .unwrap_or_else(|| Range::new(cursor.position(), cursor.position()));
+        let before = self.line_before(range.start);
+        let rest = self
+            .buffer
+            .line(range.end.line)
+            .map(|l| l.chars().skip(range.end.col).collect::<String>())
+            .unwrap_or_default();
+        let rest = rest.trim_end_matches(['\n', '\r']);
+        let after = rest.trim_start_matches([' ', '\t']);
+        let layout = newline_indent(&before, after, &rules, &style);
+
+        // Whitespace left at the end of `before` and the start of `after`
+        // would only dangle on either side of the break.
+        let start = Position::new(
+            range.start.line,
+            before.trim_end_matches([' ', '\t']).chars().count(),
+        );
+        let end = Position::new(
+            range.end.line,
+            range.end.col + (rest.chars().count() - after.chars().count()),
+        );
+        let mut text = format!("\n{}", layout.indent);
+        if let Some(closer_indent) = &layout.closer_indent {
+            text.push_str(&format!("\n{}", closer_indent));
+        }
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        cursor.set_anchor(start);
+        cursor.set_position(end);
+        if start == end {
+            cursor.clear_selection();
+        }
+        self.insert_text(&text);
+        if layout.closer_indent.is_some() {
+            let line = start.line + 1;
+            let col = layout.indent.chars().count();
+            self.buffer
+                .cursors_mut()
+                .primary_mut()
+                .set_position(Position::new(line, col));
+        }
+    }
+
+    /// Indentation rules and style for the current buffer's language, or
+    /// `None` when `auto_indent` is off for it.
+    pub(crate) fn indent_settings(&self) -> Option<(IndentRules, IndentStyle)> {
+        let language = self
+            .buffer
+            .path()
+            .map(LanguageId::from_path)
+            .unwrap_or(LanguageId::Plain);
+        let editor = self
+            .editor_config
+            .with_overrides(self.language_configs.get(language.as_str()));
+        editor.auto_indent.then(|| {
+            (
+                language.indent_rules(),
+                IndentStyle {
+                    tab_size: editor.tab_size as usize,
+                    insert_spaces: editor.insert_spaces,
+                },
+            )
+        })
+    }
+
+    /// Text of line `pos.line` before `pos`.
+    fn line_before(&self, pos: Position) -> String {
+        self.buffer
+            .line(pos.line)
+            .map(|l| l.chars().take(pos.col).collect())
+            .unwrap_or_default()
+    }
+
+    /// Insert `text` at the primary cursor, replacing the active selection
+    /// if there is one, and move the cursor to the end of the new text.
+    /// Line breaks in `text` are written in the buffer's line ending.
+    pub(crate) fn insert_text(&mut self, text: &str) {
+        let line_ending = self.buffer.line_ending();
+        let text = &*line_ending.normalize(text);
+        if self.buffer.cursors().len() > 1 {
+            let result = self.buffer.apply_multi_cursor_edit(text);
+            self.finish_cursor_edit(result);
+            return;
+        }
+        let cursor = self.buffer.cursors().primary();
+        let (pos, edit) = match cursor.selection_range().filter(|r| !r.is_empty()) {
+            Some(range) => (
+                range.start,
+                EditCommand::Replace {
+                    range,
+                    text: text.to_string(),
+                },
+            ),
+            None => (
+                cursor.position(),
+                EditCommand::Insert {
+                    pos: cursor.position(),
+                    text: text.to_string(),
+                },
+            ),
+        };
+        if self.apply_edit(edit).is_ok() {
+            let mut lines = text.split(line_ending.as_str());
+            let first_len = lines.next().map_or(0, |l| l.chars().count());
+            let new_pos = match lines.enumerate().last() {
+                Some((i, last)) => Position::new(pos.line + i + 1, last.chars().count()),
+                None => Position::new(pos.line, pos.col + first_len),
+            };
+            let cursor = self.buffer.cursors_mut().primary_mut();
+            cursor.clear_selection();
+            cursor.set_position(new_pos);
             self.lsp_did_change();
         }
     }
 
-    fn cmd_delete_backward(&mut self) {
-        let pos = self.buffer.cursors().primary().position();
-        if pos.col > 0 {
-            let start = Position::new(pos.line, pos.col - 1);
-            let range = smash_core::position::Range::new(start, pos);
-            let edit = EditCommand::Delete { range };
-            if self.buffer.apply_edit(edit).is_ok() {
-                self.buffer.cursors_mut().primary_mut().set_position(start);
-                self.lsp_did_change();
+    /// Apply an edit to the buffer, recording it for LSP document sync.
+    pub(crate) fn apply_edit(&mut self, cmd: EditCommand) -> Result<Vec<EditEvent>, EditError> {
+        let events = match self.buffer.apply_edit(cmd) {
+            Ok(events) => events,
+            Err(EditError::ReadOnly) => {
+                self.warn_read_only();
+                return Err(EditError::ReadOnly);
             }
-        } else if pos.line > 0 {
-            let prev_line = pos.line - 1;
-            let prev_len = self
-                .buffer
-                .line(prev_line)
-                .map(line_content_len)
-                .unwrap_or(0);
-            let start = Position::new(prev_line, prev_len);
-            let range = smash_core::position::Range::new(start, pos);
-            let edit = EditCommand::Delete { range };
-            if self.buffer.apply_edit(edit).is_ok() {
-                self.buffer.cursors_mut().primary_mut().set_position(start);
+            Err(e) => return Err(e),
+        };
+        self.record_edit_events(&events);
+        Ok(events)
+    }
+
+    /// Record an edit the buffer made at its cursors for LSP document sync.
+    fn finish_cursor_edit(&mut self, result: Result<Vec<EditEvent>, EditError>) {
+        match result {
+            Ok(events) if !events.is_empty() => {
+                self.record_edit_events(&events);
                 self.lsp_did_change();
             }
+            Ok(_) => {}
+            Err(EditError::ReadOnly) => self.warn_read_only(),
+            Err(e) => error!("edit at cursors failed: {}", e),
         }
     }
 
-    fn cmd_delete_forward(&mut self) {
-        let pos = self.buffer.cursors().primary().position();
-        let line_len = self
-            .buffer
-            .line(pos.line)
-            .map(line_content_len)
-            .unwrap_or(0);
-        let end = if pos.col < line_len {
-            Position::new(pos.line, pos.col + 1)
-        } else if pos.line + 1 < self.buffer.line_count() {
-            Position::new(pos.line + 1, 0)
+    /// Delete the text under the primary cursor's selection.
+    ///
+    /// Returns `false` (and does nothing) when no non-empty selection is
+    /// active, so callers can fall back to their single-character behavior.
+    pub(crate) fn delete_selection(&mut self) -> bool {
+        let range = match self.buffer.cursors().primary().selection_range() {
+            Some(range) if !range.is_empty() => range,
+            _ => {
+                self.buffer.cursors_mut().primary_mut().clear_selection();
+                return false;
+            }
+        };
+        if self.apply_edit(EditCommand::Delete { range }).is_ok() {
+            let cursor = self.buffer.cursors_mut().primary_mut();
+            cursor.clear_selection();
+            cursor.set_position(range.start);
+            self.lsp_did_change();
+        }
+        true
+    }
+
+    /// Text covered by the primary cursor's selection, if any.
+    pub(crate) fn selected_text(&self) -> Option<String> {
+        let range = self.buffer.cursors().primary().selection_range()?;
+        if range.is_empty() {
+            return None;
+        }
+        self.buffer.text_in_range(range).ok()
+    }
+
+    fn cmd_select_all(&mut self) {
+        let last = self.buffer.line_count().saturating_sub(1);
+        let last_len = self.buffer.line(last).map(line_content_len).unwrap_or(0);
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        cursor.set_anchor(Position::new(0, 0));
+        cursor.set_position(Position::new(last, last_len));
+    }
+
+    /// Run a cursor motion while keeping (or starting) a selection anchored
+    /// at the position the cursor had before the first extending motion.
+    /// Every cursor extends its own selection.
+    fn cmd_extend_selection(&mut self, motion: &Command) {
+        if !self.for_each_cursor(|app| app.extend_primary_selection(motion)) {
+            self.extend_primary_selection(motion);
+        }
+    }
+
+    fn extend_primary_selection(&mut self, motion: &Command) {
+        let cursor = self.buffer.cursors().primary();
+        let anchor = cursor.anchor().unwrap_or(cursor.position());
+        self.apply_motion(motion);
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        if cursor.position() == anchor {
+            cursor.clear_selection();
         } else {
+            cursor.set_anchor(anchor);
+        }
+    }
+
+    /// Move the primary cursor according to a movement command without
+    /// touching its selection anchor.
+    pub(crate) fn apply_motion(&mut self, motion: &Command) {
+        match motion {
+            Command::MoveLeft => self.cmd_move_left(),
+            Command::MoveRight => self.cmd_move_right(),
+            Command::MoveUp => self.cmd_move_up(),
+            Command::MoveDown => self.cmd_move_down(),
+            Command::MoveWordLeft => self.move_word_left(),
+            Command::MoveWordRight => self.move_word_right(),
+            Command::MoveLineStart => self.cmd_move_line_start(),
+            Command::SmartHome => self.cmd_smart_home(),
+            Command::MoveLineEnd => self.cmd_move_line_end(),
+            Command::MoveBufferStart => self.cmd_move_buffer_start(),
+            Command::MoveBufferEnd => self.cmd_move_buffer_end(),
+            Command::PageUp => self.cmd_page_up(),
+            Command::PageDown => self.cmd_page_down(),
+            _ => {}
+        }
+    }
+
+    fn cmd_copy(&mut self) {
+        let Some(text) = self.selected_text() else {
+            self.messages.info("Nothing selected");
             return;
         };
-        let range = smash_core::position::Range::new(pos, end);
-        let edit = EditCommand::Delete { range };
-        if self.buffer.apply_edit(edit).is_ok() {
-            self.lsp_did_change();
+        self.record_kill(&text);
+        match self.clipboard.set(&text) {
+            Ok(()) => self
+                .messages
+                .info(format!("Copied {} character(s)", text.chars().count())),
+            Err(e) => {
+                self.messages.error(format!("Copy failed: {}", e));
+                error!("clipboard set failed: {}", e);
+            }
+        }
+    }
+
+    fn cmd_cut(&mut self) {
+        let Some(text) = self.selected_text() else {
+            self.messages.info("Nothing selected");
+            return;
+        };
+        self.record_kill(&text);
+        match self.clipboard.set(&text) {
+            Ok(()) => {
+                self.delete_selection();
+            }
+            Err(e) => {
+                self.messages.error(format!("Cut failed: {}", e));
+                error!("clipboard set failed: {}", e);
+            }
+        }
+    }
+
+    fn cmd_paste(&mut self) {
+        match self.clipboard.get() {
+            Ok(text) if !text.is_empty() => {
+                // Text copied in another program joins the history too.
+                if self.kill_ring.newest() != Some(text.as_str()) {
+                    self.kill_ring.push(&text);
+                }
+                self.yank(&text, 0);
+            }
+            Ok(_) => self.messages.info("Clipboard is empty"),
+            Err(e) => {
+                self.messages.error(format!("Paste failed: {}", e));
+                error!("clipboard get failed: {}", e);
+            }
         }
     }
 
+    /// Delete backward, or take back `expansion` if it was the last edit.
+    fn cmd_delete_backward(&mut self, expansion: Option<Expansion>) {
+        if !self.revert_expansion(expansion) {
+            self.delete_at_cursors(false);
+        }
+    }
+
+    fn cmd_delete_forward(&mut self) {
+        self.delete_at_cursors(true);
+    }
+
+    /// Delete each cursor's selection, or the character next to it, as a
+    /// single undoable edit.
+    fn delete_at_cursors(&mut self, forward: bool) {
+        let result = self.buffer.delete_at_cursors(forward);
+        self.finish_cursor_edit(result);
+    }
+
     fn cmd_move_left(&mut self) {
         let pos = self.buffer.cursors().primary().position();
         if pos.col > 0 {
-            self.buffer
-                .cursors_mut()
-                .primary_mut()
-                .set_position(Position::new(pos.line, pos.col - 1));
+            let left = self.buffer.prev_grapheme(pos);
+            self.buffer.cursors_mut().primary_mut().set_position(left);
         }
     }
 
     fn cmd_move_right(&mut self) {
         let pos = self.buffer.cursors().primary().position();
-        let line_len = self
-            .buffer
-            .line(pos.line)
-            .map(line_content_len)
-            .unwrap_or(0);
-        if pos.col < line_len {
-            self.buffer
-                .cursors_mut()
-                .primary_mut()
-                .set_position(Position::new(pos.line, pos.col + 1));
+        let right = self.buffer.next_grapheme(pos);
+        if right != pos {
+            self.buffer.cursors_mut().primary_mut().set_position(right);
         }
     }
 
     fn cmd_move_up(&mut self) {
         let pos = self.buffer.cursors().primary().position();
         if pos.line > 0 {
-            let new_pos = self
-                .buffer
-                .clamp_position(Position::new(pos.line - 1, pos.col));
-            self.buffer
-                .cursors_mut()
-                .primary_mut()
-                .set_position(new_pos);
+            self.buffer.move_primary_to_line(pos.line - 1);
         }
     }
 
     fn cmd_move_down(&mut self) {
         let pos = self.buffer.cursors().primary().position();
         if pos.line + 1 < self.buffer.line_count() {
-            let new_pos = self
-                .buffer
-                .clamp_position(Position::new(pos.line + 1, pos.col));
-            self.buffer
-                .cursors_mut()
-                .primary_mut()
-                .set_position(new_pos);
+            self.buffer.move_primary_to_line(pos.line + 1);
         }
     }
 
@@ -362,12 +1014,8 @@ impl App {
         let lines = self.viewport.visible_lines();
         self.viewport.scroll_up(lines);
         let pos = self.buffer.cursors().primary().position();
-        let new_line = pos.line.saturating_sub(lines);
-        let new_pos = self.buffer.clamp_position(Position::new(new_line, pos.col));
         self.buffer
-            .cursors_mut()
-            .primary_mut()
-            .set_position(new_pos);
+            .move_primary_to_line(pos.line.saturating_sub(lines));
     }
 
     fn cmd_page_down(&mut self) {
@@ -375,44 +1023,57 @@ impl App {
         let total = self.buffer.line_count();
         self.viewport.scroll_down(lines, total);
         let pos = self.buffer.cursors().primary().position();
-        let new_line = (pos.line + lines).min(total.saturating_sub(1));
-        let new_pos = self.buffer.clamp_position(Position::new(new_line, pos.col));
         self.buffer
-            .cursors_mut()
-            .primary_mut()
-            .set_position(new_pos);
+            .move_primary_to_line((pos.line + lines).min(total.saturating_sub(1)));
     }
 
-    fn cmd_save(&mut self) {
-        if self.buffer.path().is_some() {
-            match self.buffer.save() {
-                Ok(()) => {
-                    self.messages.info("File saved");
-                    info!("file saved");
-                    self.lsp_did_save();
-                }
-                Err(e) => {
-                    self.messages.error(format!("Save failed: {}", e));
-                    error!("save failed: {}", e);
-                }
-            }
+    /// Scroll the view `lines` up or down, taking the cursor along only
+    /// when it would otherwise leave the view.
+    pub(crate) fn scroll_view_lines(&mut self, up: bool, lines: usize) {
+        if up {
+            self.viewport.scroll_up(lines);
         } else {
-            self.messages.warn("No file path set — use Save As");
+            self.viewport.scroll_down(lines, self.buffer.line_count());
         }
+        self.keep_cursor_in_view();
     }
 
-    fn cmd_open_file_finder(&mut self) {
-        self.input_mode = InputMode::FileFinder;
-        self.prompt_input.clear();
-        self.finder_results.clear();
-        if self.file_finder.is_none() {
-            if let Ok(cwd) = std::env::current_dir() {
-                let mut finder = smash_core::fuzzy_finder::FileFinder::new(cwd);
-                finder.index();
-                self.file_finder = Some(finder);
+    pub(crate) fn cmd_save(&mut self) {
+        if self.buffer.path().is_none() {
+            self.input_mode = InputMode::PromptSaveAs;
+            self.prompt_input.clear();
+        } else if !self.request_will_save_edits() {
+            self.write_buffer();
+        }
+    }
+
+    /// Write the buffer to its file and run the on-save hooks.
+    pub(crate) fn write_buffer(&mut self) {
+        match self.buffer.save_with(self.write_mode()) {
+            Ok(()) => {
+                self.messages.info("File saved");
+                info!("file saved");
+                self.after_write();
+            }
+            Err(EditError::Io(e)) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                self.messages
+                    .error(format!("Save failed: {} — try SudoSave", e));
+                error!("save failed: {}", e);
+            }
+            Err(e) => {
+                self.messages.error(format!("Save failed: {}", e));
+                error!("save failed: {}", e);
             }
         }
     }
+
+    /// Run the on-save hooks once the buffer is on disk.
+    pub(crate) fn after_write(&mut self) {
+        self.forget_own_write();
+        self.lsp_did_save();
+        self.run_watch_for_saved_file();
+        self.reload_config_if_saved();
+    }
 }
 
 // =========================================================================
@@ -420,56 +1081,70 @@ impl App {
 // =========================================================================
 
 impl App {
-    /// Run incremental search on the current prompt input.
+    /// Run incremental search on the current prompt input. A regex that
+    /// does not parse yet (mid-typing) just shows no matches.
     pub(crate) fn incremental_search(&mut self) {
         let query_str = self.prompt_input.trim().to_string();
-        if query_str.is_empty() {
-            self.buffer.search_mut().clear();
-            return;
-        }
-        let text = self.buffer.text().to_string();
-        let search_query = SearchQuery::Plain {
-            pattern: query_str,
-            case_sensitive: false,
+        let query = match self.search_options.query(&query_str) {
+            Ok(query) if !query_str.is_empty() => query,
+            _ => {
+                self.buffer.search_mut().clear();
+                return;
+            }
         };
-        self.buffer.search_mut().set_query(search_query, &text);
+        let text = self.buffer.text().to_string();
+        self.buffer.search_mut().set_query(query, &text);
+    }
+
+    /// Flip the search option `cmd` toggles.
+    fn toggle_search_option(&mut self, cmd: &Command) {
+        let options = &mut self.search_options;
+        match cmd {
+            Command::ToggleSearchRegex => options.regex = !options.regex,
+            Command::ToggleSearchCase => options.case_sensitive = !options.case_sensitive,
+            Command::ToggleSearchWholeWord => options.whole_word = !options.whole_word,
+            _ => {}
+        }
     }
 
-    /// Open a file (or create it) from the prompt.
+    /// Open a file (or create it) from the prompt, leaving a jump back to
+    /// the current location. The file is read in the background; see
+    /// [`App::open_path`].
     pub(crate) fn confirm_open(&mut self, filename: &str) {
         let filename = filename.trim();
         if filename.is_empty() {
             self.messages.warn("Open cancelled — no filename entered");
             return;
         }
-        let path = std::path::PathBuf::from(filename);
-        let id = BufferId::next();
-        match smash_core::buffer::Buffer::open_or_create(id, &path) {
-            Ok(buf) => {
-                let name = path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("unnamed")
-                    .to_string();
-                let lang = LanguageId::from_path(&path);
-                self.highlighter = RegexHighlighter::new(lang).ok();
-                self.buffer = buf;
-                self.filename = Some(name.clone());
-                if path.exists() {
-                    self.messages.info(format!("Opened: {}", filename));
-                } else {
-                    self.messages.info(format!("New file: {}", filename));
-                }
-                info!("opened file: {}", filename);
-            }
+        if let Some(path) = self.expand_prompt_path(filename) {
+            self.push_jump();
+            self.open_path(path, None, false);
+        }
+    }
+
+    /// Expand `~` and environment variables in a path typed into a
+    /// prompt, reporting a variable that is not set.
+    pub(crate) fn expand_prompt_path(&mut self, input: &str) -> Option<std::path::PathBuf> {
+        match smash_platform::expand_path(input) {
+            Ok(path) => Some(path),
             Err(e) => {
                 self.messages
-                    .error(format!("Failed to open '{}': {}", filename, e));
-                error!("open failed: {}", e);
+                    .error(format!("Cannot expand '{}': {}", input, e));
+                None
             }
         }
     }
 
+    /// Open a file forwarded by another `smash` invocation and jump to
+    /// the requested line, if any.
+    pub(crate) fn open_forwarded(&mut self, req: smash_platform::OpenRequest) {
+        self.push_jump();
+        let target = req
+            .line
+            .map(|line| Position::new(line.saturating_sub(1), 0));
+        self.open_path(req.path, target, false);
+    }
+
     /// Start a search from the prompt.
     pub(crate) fn confirm_find(&mut self, query: &str) {
         let query_str = query.trim();
@@ -478,11 +1153,16 @@ impl App {
             self.messages.info("Search cleared");
             return;
         }
-        let text = self.buffer.text().to_string();
-        let search_query = SearchQuery::Plain {
-            pattern: query_str.to_string(),
-            case_sensitive: false,
+        let search_query = match self.search_options.query(query_str) {
+            Ok(query) => query,
+            Err(e) => {
+                self.buffer.search_mut().clear();
+                self.messages.error(format!("Invalid regex: {}", e));
+                return;
+            }
         };
+        self.record_search(query_str, None);
+        let text = self.buffer.text().to_string();
         self.buffer.search_mut().set_query(search_query, &text);
         let count = self.buffer.search().match_count();
         if count > 0 {
@@ -499,7 +1179,7 @@ impl App {
     pub(crate) fn find_next(&mut self) {
         if let Some(m) = self.buffer.search_mut().next_match() {
             let pos = m.range.start;
-            self.buffer.cursors_mut().primary_mut().set_position(pos);
+            self.jump_cursor_to(pos);
         } else {
             self.messages.info("No search results");
         }
@@ -509,7 +1189,7 @@ impl App {
     pub(crate) fn find_prev(&mut self) {
         if let Some(m) = self.buffer.search_mut().prev_match() {
             let pos = m.range.start;
-            self.buffer.cursors_mut().primary_mut().set_position(pos);
+            self.jump_cursor_to(pos);
         } else {
             self.messages.info("No search results");
         }
@@ -527,10 +1207,7 @@ impl App {
             }
             Ok(n) => {
                 let target = (n - 1).min(self.buffer.line_count().saturating_sub(1));
-                self.buffer
-                    .cursors_mut()
-                    .primary_mut()
-                    .set_position(Position::new(target, 0));
+                self.jump_cursor_to(Position::new(target, 0));
                 self.messages.info(format!("Jumped to line {}", target + 1));
             }
             Err(_) => {
@@ -547,8 +1224,11 @@ impl App {
             self.messages.warn("Save cancelled — no filename entered");
             return;
         }
-        let path = std::path::PathBuf::from(input);
-        match self.buffer.save_as(&path) {
+        let Some(path) = self.expand_prompt_path(input) else {
+            return;
+        };
+        let old_uri = self.current_uri();
+        match self.buffer.save_as_with(&path, self.write_mode()) {
             Ok(()) => {
                 let name = path
                     .file_name()
@@ -556,8 +1236,14 @@ impl App {
                     .unwrap_or("unnamed")
                     .to_string();
                 self.filename = Some(name);
-                self.messages.info(format!("Saved as: {}", input));
-                info!("saved as: {}", input);
+                self.messages.info(format!("Saved as: {}", path.display()));
+                info!("saved as: {}", path.display());
+                self.forget_own_write();
+                if self.current_uri() != old_uri {
+                    self.reattach_language(old_uri);
+                }
+                self.run_watch_for_saved_file();
+                self.reload_config_if_saved();
             }
             Err(e) => {
                 self.messages.error(format!("Save failed: {}", e));
@@ -566,6 +1252,167 @@ impl App {
         }
     }
 
+    /// Convert the buffer to the line ending named by `input`.
+    pub(crate) fn confirm_line_ending(&mut self, input: &str) {
+        let Some(ending) = LineEnding::from_name(input) else {
+            self.messages
+                .error(format!("Unknown line ending: {}", input.trim()));
+            return;
+        };
+        match self.buffer.convert_line_endings(ending) {
+            Ok(events) => {
+                if !events.is_empty() {
+                    self.record_edit_events(&events);
+                    self.lsp_did_change();
+                }
+                self.messages
+                    .info(format!("Line endings: {}", ending.name()));
+                info!("line endings set to {}", ending.name());
+            }
+            Err(EditError::ReadOnly) => self.warn_read_only(),
+            Err(e) => {
+                self.messages
+                    .error(format!("Could not convert line endings: {}", e));
+                error!("line ending conversion failed: {}", e);
+            }
+        }
+    }
+
+    /// Run the palette command that best matches `input`.
+    pub(crate) fn confirm_palette(&mut self, input: &str) {
+        match palette_match(input) {
+            Some(cmd) => self.handle_command(cmd),
+            None => self
+                .messages
+                .warn(format!("No command matches '{}'", input.trim())),
+        }
+    }
+
+    /// Confirm the watch prompt.
+    ///
+    /// Input is `GLOB COMMAND...`; the command runs through the shell on
+    /// every save of a file matching the glob. Empty input stops watching.
+    pub(crate) fn confirm_watch(&mut self, input: &str) {
+        let input = input.trim();
+        if input.is_empty() {
+            if self.watch.take().is_some() {
+                self.messages.info("Watch stopped");
+            }
+            return;
+        }
+        match input.split_once(char::is_whitespace) {
+            Some((glob, command)) if !command.trim().is_empty() => {
+                let command = command.trim();
+                self.messages
+                    .info(format!("Watching {}: {}", glob, command));
+                info!("watch registered: {} -> {}", glob, command);
+                self.watch = Some(WatchJob::new(glob, command));
+            }
+            _ => {
+                self.messages.warn("Usage: GLOB COMMAND");
+            }
+        }
+    }
+
+    /// Drain watch output. Returns `true` if the watch pane needs a redraw.
+    pub(crate) fn poll_watch(&mut self) -> bool {
+        let Some(watch) = self.watch.as_mut() else {
+            return false;
+        };
+        let was_running = watch.status() == JobStatus::Running;
+        let changed = watch.poll();
+        match watch.status() {
+            JobStatus::Succeeded if was_running => {
+                self.messages.info("Watch command succeeded");
+            }
+            JobStatus::Failed(Some(code)) if was_running => {
+                self.messages
+                    .error(format!("Watch command failed (exit {})", code));
+            }
+            JobStatus::Failed(None) if was_running => {
+                self.messages.error("Watch command failed");
+            }
+            _ => {}
+        }
+        changed
+    }
+
+    /// Trigger the watch command if the just-saved file matches its glob.
+    pub(crate) fn run_watch_for_saved_file(&mut self) {
+        let Some(path) = self.buffer.path().map(|p| p.to_path_buf()) else {
+            return;
+        };
+        if let Some(watch) = self.watch.as_mut() {
+            if watch.matches(&path) {
+                watch.start();
+            }
+        }
+    }
+
+    /// Confirm highlighted export.
+    ///
+    /// Input is `PATH [-n] [START-END]`: `-n` adds line numbers and
+    /// `START-END` limits the export to those 1-based lines. Without a
+    /// line range the active selection (or the whole buffer) is exported.
+    pub(crate) fn confirm_export(&mut self, input: &str) {
+        let mut path = None;
+        let mut line_numbers = false;
+        let mut lines = None;
+        for token in input.split_whitespace() {
+            if token == "-n" {
+                line_numbers = true;
+            } else if let Some(range) = parse_line_range(token) {
+                lines = Some(range);
+            } else if path.is_none() {
+                path = Some(token);
+            }
+        }
+        let Some(path) = path else {
+            self.messages.warn("Export cancelled — no filename entered");
+            return;
+        };
+        let Some(path) = self.expand_prompt_path(path) else {
+            return;
+        };
+
+        let range = match lines {
+            Some((first, last)) => {
+                let last = last.min(self.buffer.line_count());
+                Some(smash_core::position::Range::new(
+                    Position::new(first - 1, 0),
+                    Position::new(last - 1, usize::MAX),
+                ))
+            }
+            None => self
+                .buffer
+                .cursors()
+                .primary()
+                .selection_range()
+                .filter(|r| !r.is_empty()),
+        };
+        let options = ExportOptions {
+            format: ExportFormat::from_path(&path),
+            line_numbers,
+            range,
+        };
+        let highlighter = self
+            .highlighter
+            .as_ref()
+            .map(|h| h as &dyn smash_syntax::HighlightEngine);
+        let output = export_highlighted(&self.buffer, highlighter, &self.theme, &options);
+        match std::fs::write(&path, output) {
+            Ok(()) => {
+                self.messages
+                    .info(format!("Exported to {}", path.display()));
+                info!("exported highlighted buffer to {}", path.display());
+            }
+            Err(e) => {
+                self.messages.error(format!("Export failed: {}", e));
+                error!("export failed: {}", e);
+            }
+        }
+    }
+
     /// Confirm find-replace: replace all occurrences.
     pub(crate) fn confirm_find_replace(&mut self, pattern: &str, replacement: &str) {
         let pattern = pattern.trim();
@@ -576,13 +1423,48 @@ impl App {
             self.replace_input.clear();
             return;
         }
+        let query = match self.search_options.query(pattern) {
+            Ok(query) => query,
+            Err(e) => {
+                self.messages.error(format!("Invalid regex: {}", e));
+                return;
+            }
+        };
+        self.record_search(pattern, Some(replacement));
         let text = self.buffer.text().to_string();
-        let new_text = text.replace(pattern, replacement);
-        if new_text == text {
+        let matches = query.find_all(&text);
+        if matches.is_empty() {
             self.messages
                 .info(format!("No occurrences of '{}' found", pattern));
         } else {
-            let count = text.matches(pattern).count();
+            let (new_text, count) = match &query {
+                // Regex replacements may refer to groups, as in `$1`.
+                SearchQuery::Regex(re) => (
+                    re.replace_all(&text, replacement).into_owned(),
+                    matches.len(),
+                ),
+                SearchQuery::Plain { .. } => {
+                    let mut out = String::with_capacity(text.len());
+                    let mut last = 0;
+                    let mut count = 0;
+                    for m in &matches {
+                        let (start, end) = (m.byte_range.start, m.byte_range.end);
+                        // Plain matches may overlap; replace left to right.
+                        if start < last
+                            || !text.is_char_boundary(start)
+                            || !text.is_char_boundary(end)
+                        {
+                            continue;
+                        }
+                        out.push_str(&text[last..start]);
+                        out.push_str(replacement);
+                        last = end;
+                        count += 1;
+                    }
+                    out.push_str(&text[last..]);
+                    (out, count)
+                }
+            };
             // Replace entire buffer content
             let full_range = smash_core::position::Range::new(
                 Position::new(0, 0),
@@ -594,13 +1476,16 @@ impl App {
                         .unwrap_or(0),
                 ),
             );
+            self.buffer.begin_undo_group();
             let edit = EditCommand::Delete { range: full_range };
-            let _ = self.buffer.apply_edit(edit);
+            let _ = self.apply_edit(edit);
             let edit = EditCommand::Insert {
                 pos: Position::new(0, 0),
                 text: new_text,
             };
-            let _ = self.buffer.apply_edit(edit);
+            let _ = self.apply_edit(edit);
+            self.buffer.end_undo_group();
+            self.lsp_did_change();
             self.messages
                 .info(format!("Replaced {} occurrence(s)", count));
         }
@@ -705,12 +1590,13 @@ impl App {
             );
             let range = smash_core::position::Range::new(actual_start, actual_end);
             let edit = EditCommand::Delete { range };
-            if self.buffer.apply_edit(edit).is_ok() {
+            if self.apply_edit(edit).is_ok() {
                 let new_line = pos.line.saturating_sub(1);
                 self.buffer
                     .cursors_mut()
                     .primary_mut()
                     .set_position(Position::new(new_line, 0));
+                self.lsp_did_change();
             }
             return;
         } else {
@@ -719,34 +1605,13 @@ impl App {
         };
         let range = smash_core::position::Range::new(start, end);
         let edit = EditCommand::Delete { range };
-        if self.buffer.apply_edit(edit).is_ok() {
+        if self.apply_edit(edit).is_ok() {
             let new_line = pos.line.min(self.buffer.line_count().saturating_sub(1));
             self.buffer
                 .cursors_mut()
                 .primary_mut()
                 .set_position(Position::new(new_line, 0));
-        }
-    }
-
-    /// Update the fuzzy finder results from prompt input.
-    pub(crate) fn update_finder_results(&mut self) {
-        if let Some(ref finder) = self.file_finder {
-            self.finder_results = finder.search(&self.prompt_input, 20);
-        }
-    }
-
-    /// Confirm file finder selection: open the first result.
-    pub(crate) fn confirm_file_finder(&mut self) {
-        if let Some(first) = self.finder_results.first() {
-            let path = first.path().to_path_buf();
-            self.input_mode = InputMode::Normal;
-            self.prompt_input.clear();
-            self.finder_results.clear();
-            self.confirm_open(&path.to_string_lossy());
-        } else {
-            self.messages.info("No matching files");
-            self.input_mode = InputMode::Normal;
-            self.prompt_input.clear();
+            self.lsp_did_change();
         }
     }
 }
@@ -769,18 +1634,79 @@ impl App {
         self.jump_stack.push(loc);
     }
 
+    /// Move the cursor to `pos` in this file. Leaving the cursor line is
+    /// a jump, remembered for jump-back.
+    pub(crate) fn jump_cursor_to(&mut self, pos: Position) {
+        if pos.line != self.buffer.cursors().primary().position().line {
+            self.push_jump();
+        }
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        cursor.clear_selection();
+        cursor.set_position(pos);
+    }
+
     /// Navigate to a `JumpLocation`, opening the file if necessary.
     fn navigate_to_location(&mut self, loc: &JumpLocation) {
-        // If the target is in a different file, open it
+        // If the target is in a different file, open it there
         if loc.path != self.buffer.path().map(|p| p.to_path_buf()) {
             if let Some(ref path) = loc.path {
-                self.confirm_open(&path.to_string_lossy());
+                self.open_path(path.clone(), Some(loc.position), false);
+                return;
             }
         }
-        self.buffer
-            .cursors_mut()
-            .primary_mut()
-            .set_position(loc.position);
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        cursor.clear_selection();
+        cursor.set_position(loc.position);
+    }
+
+    /// Set the undo budget for this and every later-opened buffer.
+    pub(crate) fn set_undo_budget(&mut self, budget: UndoBudget) {
+        self.undo_budget = budget;
+        self.buffer.set_undo_budget(budget);
+    }
+
+    /// Pop up which clipboard provider, language server and paths the
+    /// editor is using, and how long startup took.
+    fn cmd_show_health(&mut self) {
+        let lsp = match (&self.language_id, self.lsp_server_started) {
+            _ if !self.lsp_enabled => "disabled".to_string(),
+            (Some(lang), true) => format!("running for {}", lang),
+            (Some(lang), false) => format!("not running for {}", lang),
+            (None, _) => "none for this file".to_string(),
+        };
+        let mut lines = vec![
+            format!("Clipboard: {}", self.clipboard.describe()),
+            format!("Language server: {}", lsp),
+            format!("Shell: {}", self.terminal_shell_path().display()),
+        ];
+        if let Some(paths) = &self.config_paths {
+            lines.push(format!("Config dir: {}", paths.config_dir.display()));
+            lines.push(format!("Log file: {}", paths.log_file.display()));
+        }
+        lines.push("Startup:".to_string());
+        lines.extend(
+            self.startup_profile
+                .lines()
+                .iter()
+                .map(|l| format!("  {}", l)),
+        );
+        self.show_cursor_popup(smash_tui::Popup::new("Health", lines));
+    }
+
+    /// Replace the buffer with a read-only help page for the active keymap.
+    fn cmd_show_help(&mut self) {
+        if self.buffer.is_dirty() {
+            self.messages.warn("Save changes before opening help");
+            return;
+        }
+        let text = smash_input::keymap_help(self.resolver.keymap(), &self.keymap_preset);
+        self.push_jump();
+        self.buffer = smash_core::buffer::Buffer::from_text(BufferId::next(), &text);
+        self.buffer.set_read_only(true);
+        self.filename = Some("[help]".to_string());
+        self.highlighter = None;
+        self.messages
+            .info("Keymap help \u{2014} Jump back to return to your file");
     }
 
     /// Jump back to the previous location in the jump stack.
@@ -806,4 +1732,47 @@ impl App {
             self.messages.info("No next location");
         }
     }
+
+    /// Flip back to the file open before this one, where its cursor was.
+    fn cmd_alternate_buffer(&mut self) {
+        let Some(alternate) = self.alternate_file.clone() else {
+            self.messages.info("No alternate file");
+            return;
+        };
+        if self.buffer.is_dirty() {
+            self.messages
+                .warn("Save changes before switching to the alternate file");
+            return;
+        }
+        self.push_jump();
+        self.navigate_to_location(&alternate);
+    }
+
+    /// Go back to where the last edit was made, in this file or the one
+    /// last edited before it.
+    fn cmd_jump_to_last_edit(&mut self) {
+        let here = self.buffer.last_edit().map(|pos| {
+            let path = self.buffer.path().map(|p| p.to_path_buf());
+            JumpLocation::new(path, pos)
+        });
+        let Some(target) = here.or_else(|| self.last_edit.clone()) else {
+            self.messages.info("No edits yet");
+            return;
+        };
+        self.jump_to_location(target);
+    }
+
+    /// Jump to `target`, leaving a jump back. Another file is not opened
+    /// over unsaved changes.
+    pub(crate) fn jump_to_location(&mut self, mut target: JumpLocation) {
+        if target.path.as_deref() == self.buffer.path() {
+            target.position = self.buffer.clamp_position(target.position);
+        } else if self.buffer.is_dirty() {
+            self.messages
+                .warn("Save changes before jumping to another file");
+            return;
+        }
+        self.push_jump();
+        self.navigate_to_location(&target);
+    }
 }
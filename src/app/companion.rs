@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+
+use smash_config::config::CompanionConfig;
+use smash_core::companion::{capture, fill, map_path};
+use smash_syntax::LanguageId;
+use tracing::{info, warn};
+
+use super::loading::file_name;
+use super::{App, InputMode};
+
+/// A companion file that does not exist yet, offered for creation.
+pub(crate) struct MissingCompanion {
+    path: PathBuf,
+    /// Initial contents, already filled in.
+    contents: String,
+}
+
+// =========================================================================
+// Source ↔ test companion files
+// =========================================================================
+
+impl App {
+    /// Companion patterns for `language`: the configured ones, otherwise
+    /// the built-in ones.
+    fn companion_patterns(&self, language: LanguageId) -> Vec<CompanionConfig> {
+        self.language_configs
+            .get(language.as_str())
+            .and_then(|l| l.companions.clone())
+            .unwrap_or_else(|| CompanionConfig::defaults(language.as_str()))
+    }
+
+    /// Candidates for the companion of `relative`, with the contents a
+    /// new one would start with. A test maps to its source first, so a
+    /// test file is never taken for a source.
+    fn companion_candidates(&self, relative: &str, language: LanguageId) -> Vec<(String, String)> {
+        let patterns = self.companion_patterns(language);
+        let to_source = patterns
+            .iter()
+            .filter_map(|p| map_path(&p.test, &p.source, relative).map(|s| (s, String::new())));
+        let to_test = patterns.iter().filter_map(|p| {
+            let (dir, name) = capture(&p.source, relative)?;
+            let contents = p
+                .template
+                .as_deref()
+                .map_or_else(String::new, |t| fill(t, &dir, &name));
+            Some((fill(&p.test, &dir, &name), contents))
+        });
+        to_source.chain(to_test).collect()
+    }
+
+    /// Jump to the current file's test, or from a test to its source.
+    /// A missing companion is offered for creation.
+    pub(crate) fn cmd_go_to_companion_file(&mut self) {
+        let Some(path) = self.buffer.path().map(Path::to_path_buf) else {
+            self.messages
+                .warn("Save the file before looking for its companion");
+            return;
+        };
+        let root = self
+            .config_paths
+            .as_ref()
+            .and_then(|paths| paths.project_dir.clone())
+            .filter(|dir| path.starts_with(dir))
+            .or_else(|| path.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+        let relative = path
+            .strip_prefix(&root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let candidates = self.companion_candidates(&relative, LanguageId::from_path(&path));
+        let existing = candidates.iter().find(|(c, _)| root.join(c).is_file());
+        let Some((companion, contents)) = existing.or(candidates.first()).cloned() else {
+            self.messages
+                .info(format!("No companion pattern matches {}", relative));
+            return;
+        };
+        if self.buffer.is_dirty() {
+            self.messages.warn("Save changes before switching files");
+            return;
+        }
+        let target = root.join(&companion);
+        if target.is_file() {
+            self.push_jump();
+            self.open_path(target, None, false);
+            return;
+        }
+        self.missing_companion = Some(MissingCompanion {
+            path: target,
+            contents,
+        });
+        self.input_mode = InputMode::PromptCreateCompanion;
+        self.prompt_input.clear();
+    }
+
+    /// Create the offered companion file if `yes`, then open it.
+    pub(crate) fn confirm_create_companion(&mut self, yes: bool) {
+        let Some(missing) = self.missing_companion.take() else {
+            return;
+        };
+        if !yes {
+            return;
+        }
+        let name = file_name(&missing.path);
+        let result = missing
+            .path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| {
+                use std::io::Write;
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&missing.path)?
+                    .write_all(missing.contents.as_bytes())
+            });
+        match result {
+            Ok(()) => {
+                info!("created companion file {}", missing.path.display());
+                self.messages.info(format!("Created {}", name));
+                self.push_jump();
+                self.open_path(missing.path, None, false);
+            }
+            Err(e) => {
+                self.messages
+                    .error(format!("Cannot create {}: {}", name, e));
+                warn!("companion create failed: {}", e);
+            }
+        }
+    }
+
+    /// Prompt text while offering to create the companion.
+    pub(crate) fn create_companion_prompt(&self) -> String {
+        let path = self
+            .missing_companion
+            .as_ref()
+            .map(|m| m.path.display().to_string())
+            .unwrap_or_default();
+        format!("{} does not exist. Create it? (y/n)", path)
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+use smash_core::position::Position;
+use smash_input::Command;
+
+use super::{App, InputMode};
+use crate::lsp_types::LspCommand;
+
+/// Pause in typing after which completions are asked for.
+pub(crate) const COMPLETION_DELAY: Duration = Duration::from_millis(150);
+
+// =========================================================================
+// Completion while typing
+// =========================================================================
+
+impl App {
+    /// Schedule completion after a command just handled in Normal mode:
+    /// typing part of an identifier or one of the server's trigger
+    /// characters (re)starts the wait, anything else cancels it.
+    pub(crate) fn update_auto_completion(&mut self, cmd: &Command) {
+        if !self.lsp_server_started {
+            return;
+        }
+        match cmd {
+            Command::InsertChar(c) if self.wants_completion(*c) => {
+                self.completion_debounce.schedule(Instant::now());
+            }
+            _ => self.completion_debounce.cancel(),
+        }
+    }
+
+    /// Whether typing `c`, now just before the cursor, should ask for
+    /// completions: a trigger character, or part of an identifier that
+    /// does not start with a digit.
+    fn wants_completion(&self, c: char) -> bool {
+        let mut buf = [0; 4];
+        if self
+            .completion_triggers
+            .iter()
+            .any(|t| t == c.encode_utf8(&mut buf))
+        {
+            return true;
+        }
+        if !is_word_char(c) {
+            return false;
+        }
+        let pos = self.buffer.cursors().primary().position();
+        let Some(line) = self.buffer.line(pos.line) else {
+            return false;
+        };
+        let before: Vec<char> = line.chars().take(pos.col).collect();
+        let start = before
+            .iter()
+            .rposition(|&c| !is_word_char(c))
+            .map_or(0, |i| i + 1);
+        before.get(start).is_some_and(|c| !c.is_ascii_digit())
+    }
+
+    /// Send the completion request once typing has paused.
+    pub(crate) fn poll_completion(&mut self, now: Instant) {
+        if !self.completion_debounce.fire(now) || self.input_mode != InputMode::Normal {
+            return;
+        }
+        let Some(uri) = self.current_uri() else {
+            return;
+        };
+        let pos = self.buffer.cursors().primary().position();
+        self.completion_request = Some(pos);
+        let _ = self.lsp_cmd_tx.try_send(LspCommand::Completion {
+            uri,
+            position: self.lsp_position(pos),
+        });
+    }
+
+    /// How long the event loop may wait for input before
+    /// [`App::poll_completion`], [`App::poll_pending_save`],
+    /// [`App::poll_input_idle`] or the next frame is due.
+    pub(crate) fn next_timer(&self, now: Instant) -> Option<Duration> {
+        [
+            self.completion_debounce.remaining(now),
+            self.pending_save_remaining(now),
+            self.input_idle.remaining(now),
+            self.render_scheduler.remaining(now),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Whether a result for the automatic request is still wanted: the
+    /// cursor has stayed put and no newer request is waiting.
+    pub(super) fn auto_completion_current(&self, requested: Position) -> bool {
+        self.input_mode == InputMode::Normal
+            && !self.completion_debounce.is_pending()
+            && self.buffer.cursors().primary().position() == requested
+    }
+}
+
+pub(super) fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use smash_config::config::LineNumberMode;
+use smash_config::Config;
+use smash_core::undo::UndoBudget;
+use smash_input::{KeyResolver, KeymapLayer};
+use smash_platform::{Clipboard, SystemClipboard};
+use smash_tui::{default_dark_theme, DiagnosticSigns, LineNumbers};
+use tracing::{error, info, warn};
+
+use super::{keymap_for_preset, App};
+use crate::debounce::Debounce;
+use crate::log_filter::config_log_filter;
+
+// =========================================================================
+// Config editing and reload
+// =========================================================================
+
+impl App {
+    /// Apply the settings that can change while the editor is running.
+    pub(crate) fn apply_config(&mut self, config: &Config) {
+        if config.keymap.preset != self.keymap_preset || config.keys != self.user_keys {
+            self.rebuild_keymap(&config.keymap.preset, &config.keys);
+        }
+        self.option_as_alt = config.editor.option_as_alt;
+        self.editor_config = config.editor.clone();
+        self.language_configs = config.languages.clone();
+        self.abbreviations = config.abbreviations.clone();
+        self.tasks = config.tasks.clone();
+        self.lsp_enabled = config.lsp.enabled;
+        self.lsp_server_configs = config.lsp.servers.clone();
+        self.lsp_disable = config.lsp.disable.clone();
+        self.format_on_save = config.lsp.format_on_save;
+        self.organize_imports_on_save = config.lsp.organize_imports_on_save;
+        self.hover_on_idle = config.lsp.hover_on_idle;
+        self.input_idle = Debounce::new(Duration::from_millis(config.editor.idle_delay_ms));
+        self.render_scheduler.set_max_fps(config.display.max_fps);
+        self.set_undo_budget(UndoBudget {
+            max_entries: config.editor.undo_max_entries,
+            max_bytes: config.editor.undo_max_memory_mb.saturating_mul(1024 * 1024),
+        });
+        self.terminal_shell = config.terminal_shell.clone();
+        match self.find_theme(&config.display.theme) {
+            Ok(theme) => {
+                self.theme = theme;
+                self.theme_name = config.display.theme.clone();
+            }
+            Err(e) => {
+                warn!("{}; using dark", e);
+                self.messages.error(format!("{}; using the dark theme", e));
+                self.theme = default_dark_theme();
+                self.theme_name = "dark".to_string();
+            }
+        }
+        let signs = &config.display.diagnostic_signs;
+        self.diagnostic_signs = DiagnosticSigns {
+            error: signs.error,
+            warning: signs.warning,
+            info: signs.info,
+            hint: signs.hint,
+        };
+        self.renderer.set_diagnostic_signs(self.diagnostic_signs);
+        self.highlight_margin = config.display.highlight_margin;
+        self.line_numbers = match config.display.line_numbers {
+            LineNumberMode::Absolute => LineNumbers::Absolute,
+            LineNumberMode::Relative => LineNumbers::Relative,
+            LineNumberMode::Hybrid => LineNumbers::Hybrid,
+            LineNumberMode::None => LineNumbers::Off,
+        };
+        self.show_line_numbers = self.line_numbers != LineNumbers::Off;
+        self.ruler_columns = config.display.rulers.clone();
+        self.highlight_current_line = config.display.highlight_current_line;
+        self.indent_guides = config.display.indent_guides;
+        self.show_minimap = config.display.show_minimap;
+        self.show_scrollbar = config.display.scrollbar;
+        if self
+            .clipboard_setting
+            .as_ref()
+            .is_some_and(|s| *s != config.editor.clipboard)
+        {
+            self.use_system_clipboard(&config.editor.clipboard);
+        }
+        if let Some(filter) = self.log_filter.as_mut() {
+            let spec = config_log_filter(config);
+            if spec != filter.current() {
+                if let Err(e) = filter.set(&spec) {
+                    warn!("ignoring log filter '{}': {}", spec, e);
+                }
+            }
+        }
+    }
+
+    /// Switch to the system clipboard as configured by `editor.clipboard`,
+    /// detecting providers when the setting is not recognised.
+    pub(crate) fn use_system_clipboard(&mut self, setting: &str) {
+        let clipboard = SystemClipboard::from_setting(setting).unwrap_or_else(|e| {
+            warn!("editor.clipboard: {}; detecting a provider", e);
+            SystemClipboard::detect()
+        });
+        info!("clipboard provider: {}", clipboard.describe());
+        self.clipboard = Box::new(clipboard);
+        self.clipboard_setting = Some(setting.to_string());
+    }
+
+    /// Rebuild the key resolver from a preset plus the `[keys]` table.
+    /// Invalid user bindings are skipped with a warning.
+    fn rebuild_keymap(&mut self, preset: &str, keys: &BTreeMap<String, String>) {
+        let mut keymap = keymap_for_preset(preset);
+        if !keys.is_empty() {
+            let (layer, errors) = KeymapLayer::from_bindings(
+                "user",
+                keys.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+            );
+            for e in &errors {
+                warn!("ignoring [keys] entry: {}", e);
+            }
+            if let Some(e) = errors.first() {
+                self.messages
+                    .warn(format!("Ignored {} [keys] binding(s): {}", errors.len(), e));
+            }
+            keymap.push_layer(layer);
+        }
+        self.resolver = KeyResolver::new(keymap);
+        if preset != self.keymap_preset {
+            self.vim = (preset == "vim").then(smash_input::Vim::new);
+        }
+        self.keymap_preset = preset.to_string();
+        self.user_keys = keys.clone();
+    }
+
+    /// Confirm the log-level prompt: install a new `tracing` filter.
+    pub(crate) fn confirm_log_level(&mut self, input: &str) {
+        let Some(filter) = self.log_filter.as_mut() else {
+            self.messages.warn("Logging is not initialised");
+            return;
+        };
+        match filter.set(input) {
+            Ok(()) => {
+                info!("log filter changed to {}", filter.current());
+                self.messages
+                    .info(format!("Log filter: {}", filter.current()));
+            }
+            Err(e) => self.messages.error(format!("Invalid log filter: {}", e)),
+        }
+    }
+
+    /// Re-read the user and project config files and apply them.
+    pub(crate) fn reload_config(&mut self) {
+        let Some(paths) = self.config_paths.clone() else {
+            self.messages.warn("Config location unknown");
+            return;
+        };
+        match smash_config::load_config(&paths.config_dir, paths.project_dir.as_deref()) {
+            Ok(config) => {
+                self.apply_config(&config);
+                self.messages.info("Config reloaded");
+                info!("config reloaded");
+            }
+            Err(e) => {
+                self.messages.error(format!("Config reload failed: {}", e));
+                error!("config reload failed: {}", e);
+            }
+        }
+    }
+
+    /// Reload the config if the file just saved is one of the config files.
+    pub(crate) fn reload_config_if_saved(&mut self) {
+        let Some(saved) = self.buffer.path() else {
+            return;
+        };
+        let is_config = self.config_file_paths().iter().any(|p| same_file(p, saved));
+        if is_config {
+            self.reload_config();
+        }
+    }
+
+    fn config_file_paths(&self) -> Vec<PathBuf> {
+        let Some(paths) = &self.config_paths else {
+            return Vec::new();
+        };
+        let mut files = vec![smash_config::user_config_path(&paths.config_dir)];
+        if let Some(project) = &paths.project_dir {
+            files.push(smash_config::project_config_path(project));
+        }
+        files
+    }
+
+    pub(crate) fn cmd_open_user_config(&mut self) {
+        let Some(paths) = &self.config_paths else {
+            self.messages.warn("Config location unknown");
+            return;
+        };
+        let path = smash_config::user_config_path(&paths.config_dir);
+        self.open_config_file(&path);
+    }
+
+    pub(crate) fn cmd_open_project_config(&mut self) {
+        let Some(project) = self
+            .config_paths
+            .as_ref()
+            .and_then(|p| p.project_dir.clone())
+        else {
+            self.messages.warn("No project directory");
+            return;
+        };
+        let path = smash_config::project_config_path(&project);
+        self.open_config_file(&path);
+    }
+
+    pub(crate) fn cmd_open_log_file(&mut self) {
+        let Some(log_file) = self.config_paths.as_ref().map(|p| p.log_file.clone()) else {
+            self.messages.warn("Log location unknown");
+            return;
+        };
+        if !log_file.exists() {
+            self.messages
+                .warn(format!("No log file at {}", log_file.display()));
+            return;
+        }
+        self.push_jump();
+        // The log is still being appended to; keep it read-only.
+        self.open_path(log_file, None, true);
+    }
+
+    /// Open a config file, creating it from the template if missing.
+    fn open_config_file(&mut self, path: &Path) {
+        if let Err(e) = smash_config::ensure_config_file(path) {
+            self.messages
+                .error(format!("Cannot create {}: {}", path.display(), e));
+            return;
+        }
+        self.confirm_open(&path.to_string_lossy());
+    }
+}
+
+/// Compare paths, resolving symlinks and relative components when possible.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
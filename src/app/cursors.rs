@@ -0,0 +1,127 @@
+use smash_core::cursor::Cursor;
+use smash_core::position::{Position, Range};
+
+use super::completion::is_word_char;
+use super::App;
+
+// =========================================================================
+// Multiple cursors
+// =========================================================================
+
+impl App {
+    /// Add a cursor on the line below the bottom cursor (or above the top
+    /// one), at the column a vertical move from it would aim for.
+    pub(crate) fn cmd_add_cursor_vertical(&mut self, below: bool) {
+        let cursors = self.buffer.cursors().iter();
+        let edge = if below {
+            cursors.max_by_key(|c| c.position())
+        } else {
+            cursors.min_by_key(|c| c.position())
+        };
+        let Some(edge) = edge.cloned() else {
+            return;
+        };
+        let line = edge.position().line;
+        let target = if below {
+            line + 1
+        } else {
+            match line.checked_sub(1) {
+                Some(line) => line,
+                None => return,
+            }
+        };
+        if target >= self.buffer.line_count() {
+            return;
+        }
+        let mut cursor = Cursor::new(edge.position());
+        cursor.set_vertical_position(edge.position(), edge.goal_col());
+        self.buffer.move_cursor_to_line(&mut cursor, target);
+        self.buffer.cursors_mut().add(cursor);
+        self.report_cursor_count();
+    }
+
+    /// Select the word under the cursor; with a selection, add a cursor
+    /// selecting its next occurrence, wrapping around the buffer.
+    pub(crate) fn cmd_add_cursor_at_next_match(&mut self) {
+        let Some(pattern) = self.selected_text() else {
+            self.select_word_at_cursor();
+            return;
+        };
+        let text = self.buffer.text().clone();
+        if self
+            .buffer
+            .cursors_mut()
+            .add_cursor_at_next_match(&text, &pattern)
+        {
+            self.report_cursor_count();
+        } else {
+            self.messages
+                .info(format!("No more matches for '{}'", pattern));
+        }
+    }
+
+    /// The word `pos` is on or just after, if any.
+    pub(crate) fn word_range_at(&self, pos: Position) -> Option<Range> {
+        let line: Vec<char> = self
+            .buffer
+            .line(pos.line)
+            .map(|l| l.chars().collect())
+            .unwrap_or_default();
+        let mut start = pos.col.min(line.len());
+        while start > 0 && is_word_char(line[start - 1]) {
+            start -= 1;
+        }
+        let mut end = pos.col.min(line.len());
+        while end < line.len() && is_word_char(line[end]) {
+            end += 1;
+        }
+        (start < end)
+            .then(|| Range::new(Position::new(pos.line, start), Position::new(pos.line, end)))
+    }
+
+    /// Select the word the primary cursor is on or just after.
+    fn select_word_at_cursor(&mut self) {
+        let pos = self.buffer.cursors().primary().position();
+        let Some(word) = self.word_range_at(pos) else {
+            self.messages.info("No word under the cursor");
+            return;
+        };
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        cursor.set_anchor(word.start);
+        cursor.set_position(word.end);
+    }
+
+    /// Drop every cursor but the primary. Returns `false` if there was
+    /// only one.
+    pub(crate) fn collapse_cursors(&mut self) -> bool {
+        if self.buffer.cursors().len() < 2 {
+            return false;
+        }
+        self.buffer.cursors_mut().clear_secondary();
+        true
+    }
+
+    /// Run `f` once per cursor, with that cursor as the primary, then
+    /// merge cursors that met. Returns `false`, doing nothing, when there
+    /// is only one cursor.
+    pub(crate) fn for_each_cursor(&mut self, mut f: impl FnMut(&mut Self)) -> bool {
+        if self.buffer.cursors().len() < 2 {
+            return false;
+        }
+        let cursors: Vec<Cursor> = self.buffer.cursors().iter().cloned().collect();
+        let mut moved = Vec::with_capacity(cursors.len());
+        for cursor in cursors {
+            *self.buffer.cursors_mut().primary_mut() = cursor;
+            f(self);
+            moved.push(self.buffer.cursors().primary().clone());
+        }
+        *self.buffer.cursors_mut() = moved.into_iter().collect();
+        true
+    }
+
+    fn report_cursor_count(&mut self) {
+        let count = self.buffer.cursors().len();
+        self.messages
+            .info(format!("{} cursors (Esc for one)", count));
+    }
+}
@@ -0,0 +1,309 @@
+use std::path::Path;
+use std::process::Command as Process;
+
+use smash_core::diff::{diff_lines, DiffKind, Hunk};
+use smash_core::edit::EditCommand;
+use smash_core::position::{Position, Range};
+use smash_input::{Key, KeyEvent};
+use smash_tui::ListState;
+use tracing::info;
+
+use super::loading::file_name;
+use super::{line_content_len, App};
+
+/// Unchanged lines shown around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// What the buffer is compared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiffBase {
+    /// The file as last saved.
+    Disk,
+    /// The file in git's `HEAD` commit.
+    Head,
+}
+
+impl DiffBase {
+    fn label(self) -> &'static str {
+        match self {
+            DiffBase::Disk => "disk",
+            DiffBase::Head => "HEAD",
+        }
+    }
+}
+
+/// A unified diff of the buffer against its saved or committed version,
+/// shown in the results pane.
+pub(crate) struct DiffView {
+    base: DiffBase,
+    name: String,
+    /// The text the buffer is compared with.
+    base_text: String,
+    /// The buffer text `hunks` were worked out for.
+    diffed: String,
+    hunks: Vec<Hunk>,
+    /// Per row: its hunk, and its line in the hunk or `None` for the
+    /// hunk's header.
+    rows: Vec<(usize, Option<usize>)>,
+    pub(crate) list: ListState,
+}
+
+impl DiffView {
+    fn new(base: DiffBase, name: String, base_text: String, text: String) -> Self {
+        let mut view = Self {
+            base,
+            name,
+            base_text,
+            diffed: String::new(),
+            hunks: Vec::new(),
+            rows: Vec::new(),
+            list: ListState::default(),
+        };
+        view.update(text);
+        view
+    }
+
+    /// Diff `text` against the base again, keeping the selection on the
+    /// same hunk where there still is one.
+    fn update(&mut self, text: String) {
+        let hunk = self.rows.get(self.list.selected()).map_or(0, |(h, _)| *h);
+        self.hunks = diff_lines(&self.base_text, &text, CONTEXT_LINES);
+        self.diffed = text;
+        self.rows = self
+            .hunks
+            .iter()
+            .enumerate()
+            .flat_map(|(h, hunk)| {
+                std::iter::once((h, None)).chain((0..hunk.lines.len()).map(move |l| (h, Some(l))))
+            })
+            .collect();
+        let hunk = hunk.min(self.hunks.len().saturating_sub(1));
+        self.list.select(self.header_row(hunk), self.rows.len());
+    }
+
+    fn header_row(&self, hunk: usize) -> usize {
+        self.rows
+            .iter()
+            .position(|&(h, line)| h == hunk && line.is_none())
+            .unwrap_or(0)
+    }
+
+    /// Select the header of the next (`forward`) or previous hunk.
+    fn jump_hunk(&mut self, forward: bool) {
+        let Some(&(hunk, line)) = self.rows.get(self.list.selected()) else {
+            return;
+        };
+        let target = if forward {
+            hunk + 1
+        } else if line.is_some() {
+            hunk
+        } else {
+            hunk.saturating_sub(1)
+        };
+        if target < self.hunks.len() {
+            self.list.select(self.header_row(target), self.rows.len());
+        }
+    }
+
+    pub(crate) fn title(&self) -> String {
+        let hunks = match self.hunks.len() {
+            1 => "1 hunk".to_string(),
+            n => format!("{} hunks", n),
+        };
+        format!(
+            "Diff of {} against {} ({}) — n/p: next/previous hunk, r: revert hunk",
+            self.name,
+            self.base.label(),
+            hunks
+        )
+    }
+
+    /// Hunk headers, then their lines marked ` `, `-` or `+`.
+    pub(crate) fn rows(&self) -> Vec<(Option<DiffKind>, String)> {
+        self.rows
+            .iter()
+            .map(|&(h, line)| {
+                let hunk = &self.hunks[h];
+                match line {
+                    None => (None, hunk.header()),
+                    Some(l) => {
+                        let line = &hunk.lines[l];
+                        let marker = match line.kind {
+                            DiffKind::Context => ' ',
+                            DiffKind::Removed => '-',
+                            DiffKind::Added => '+',
+                        };
+                        let text = line.text.trim_end_matches('\n').trim_end_matches('\r');
+                        (Some(line.kind), format!("{}{}", marker, text))
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// The buffer line the selected row stands for.
+    fn selected_line(&self) -> Option<usize> {
+        let &(h, line) = self.rows.get(self.list.selected())?;
+        let hunk = &self.hunks[h];
+        let before = line.map_or(0, |l| {
+            hunk.lines[..l]
+                .iter()
+                .filter(|line| line.kind != DiffKind::Removed)
+                .count()
+        });
+        Some(hunk.new_start + before)
+    }
+}
+
+/// The committed text of `path` in `HEAD`.
+pub(crate) fn head_text(path: &Path) -> Result<String, String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().ok_or("not a file")?.to_string_lossy();
+    let output = Process::new("git")
+        .arg("show")
+        .arg(format!("HEAD:./{}", name))
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("cannot run git: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().next().unwrap_or("git show failed").trim();
+        return Err(reason.trim_start_matches("fatal: ").to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// =========================================================================
+// Diff view
+// =========================================================================
+
+impl App {
+    /// Show how the buffer differs from the saved file or from `HEAD`.
+    pub(crate) fn cmd_diff(&mut self, base: DiffBase) {
+        let Some(path) = self.buffer.path().map(Path::to_path_buf) else {
+            self.messages.warn("Save the file before diffing it");
+            return;
+        };
+        let base_text = match base {
+            DiffBase::Disk => self
+                .buffer
+                .encoding()
+                .read(&path)
+                .map_err(|e| e.to_string()),
+            DiffBase::Head => head_text(&path),
+        };
+        let base_text = match base_text {
+            Ok(text) => text,
+            Err(e) => {
+                self.messages
+                    .error(format!("Cannot diff against {}: {}", base.label(), e));
+                return;
+            }
+        };
+        let view = DiffView::new(
+            base,
+            file_name(&path),
+            base_text,
+            self.buffer.text().to_string(),
+        );
+        if view.hunks.is_empty() {
+            self.messages
+                .info(format!("No changes against {}", base.label()));
+            return;
+        }
+        info!(hunks = view.hunks.len(), "diff against {}", base.label());
+        self.search_results = None;
+        self.call_hierarchy = None;
+        self.shell_output = None;
+        self.diff_view = Some(view);
+        self.search_results_focused = true;
+        self.file_tree_focused = false;
+    }
+
+    /// Handle a key while the pane shows a diff and has focus.
+    pub(crate) fn handle_diff_view_key(&mut self, key: &KeyEvent) -> bool {
+        let Some(view) = self.diff_view.as_mut() else {
+            return false;
+        };
+        // The buffer may have been edited since the pane was last used.
+        if *self.buffer.text() != view.diffed.as_str() {
+            view.update(self.buffer.text().to_string());
+        }
+        if view.list.handle_key(&key.key, view.rows.len()) {
+            return true;
+        }
+        match key.key {
+            Key::Char('n') | Key::Char(']') => view.jump_hunk(true),
+            Key::Char('p') | Key::Char('[') => view.jump_hunk(false),
+            Key::Char('r') => self.revert_selected_hunk(),
+            Key::Enter => self.open_diff_line(),
+            _ => {}
+        }
+        true
+    }
+
+    /// Put the selected hunk back the way it is in the base text.
+    fn revert_selected_hunk(&mut self) {
+        let Some(view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let Some(hunk) = view
+            .rows
+            .get(view.list.selected())
+            .map(|&(h, _)| &view.hunks[h])
+        else {
+            return;
+        };
+        let start = Position::new(hunk.new_start, 0);
+        let end_line = hunk.new_start + hunk.new_len;
+        // The buffer's last line has no line break to end the range at.
+        let end = if end_line < self.buffer.line_count() {
+            Position::new(end_line, 0)
+        } else {
+            let last = self.buffer.line_count().saturating_sub(1);
+            let len = self.buffer.line(last).map_or(0, line_content_len);
+            Position::new(last, len)
+        };
+        let edit = EditCommand::Replace {
+            range: Range::new(start, end),
+            text: hunk.old_text(),
+        };
+        let base = view.base;
+        if self.apply_edit(edit).is_err() {
+            return;
+        }
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        cursor.clear_selection();
+        cursor.set_position(start);
+        self.lsp_did_change();
+        let text = self.buffer.text().to_string();
+        let Some(view) = self.diff_view.as_mut() else {
+            return;
+        };
+        view.update(text);
+        if view.hunks.is_empty() {
+            self.diff_view = None;
+            self.search_results_focused = false;
+            self.messages.info(format!(
+                "Reverted; no changes left against {}",
+                base.label()
+            ));
+        } else {
+            self.messages.info("Reverted hunk");
+        }
+    }
+
+    /// Move the cursor to the selected line. The pane stays open but
+    /// gives focus back to the editor.
+    fn open_diff_line(&mut self) {
+        let Some(line) = self.diff_view.as_ref().and_then(DiffView::selected_line) else {
+            return;
+        };
+        let line = line.min(self.buffer.line_count().saturating_sub(1));
+        self.push_jump();
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        cursor.clear_selection();
+        cursor.set_position(Position::new(line, 0));
+        self.search_results_focused = false;
+    }
+}
@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use smash_core::encoding::TextEncoding;
+use smash_platform::{FileChange, FileWatcher};
+use tracing::{error, info, warn};
+
+use super::loading::file_name;
+use super::{App, InputMode};
+
+/// How often the open file is checked for changes made by other programs.
+pub(crate) const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the open file for changes made outside the editor.
+pub(crate) struct DiskWatch {
+    watcher: FileWatcher,
+    /// The watched file, which follows the buffer's path.
+    path: Option<PathBuf>,
+    next_check: Instant,
+    /// A change to a modified buffer, waiting to ask about until no
+    /// other prompt is open.
+    unasked: Option<PathBuf>,
+}
+
+impl DiskWatch {
+    pub(crate) fn new() -> Self {
+        Self {
+            watcher: FileWatcher::new(),
+            path: None,
+            next_check: Instant::now(),
+            unasked: None,
+        }
+    }
+}
+
+// =========================================================================
+// External file changes
+// =========================================================================
+
+impl App {
+    /// Check the open file for changes made by other programs, at most
+    /// every [`DISK_CHECK_INTERVAL`]. A clean buffer is reloaded; for a
+    /// modified one the user is asked. Returns `true` if the screen needs
+    /// a redraw.
+    pub(crate) fn poll_disk_changes(&mut self, now: Instant) -> bool {
+        if self.disk_watch.unasked.is_some() && self.input_mode == InputMode::Normal {
+            self.disk_watch.unasked = None;
+            self.input_mode = InputMode::PromptReload;
+            self.prompt_input.clear();
+            return true;
+        }
+        if now < self.disk_watch.next_check || self.pending_open.is_some() {
+            return false;
+        }
+        self.disk_watch.next_check = now + DISK_CHECK_INTERVAL;
+        let path = self.buffer.path().map(Path::to_path_buf);
+        if path != self.disk_watch.path {
+            // Another file was opened or this one saved under a new name.
+            if let Some(old) = self.disk_watch.path.take() {
+                self.disk_watch.watcher.unwatch(&old);
+            }
+            if let Some(path) = &path {
+                self.disk_watch.watcher.watch(path);
+            }
+            self.disk_watch.unasked = None;
+            self.disk_watch.path = path;
+            return false;
+        }
+        let mut redraw = false;
+        for change in self.disk_watch.watcher.poll() {
+            redraw |= match change {
+                FileChange::Modified(path) => self.handle_disk_modified(&path),
+                FileChange::Removed(path) => {
+                    info!("{} was removed on disk", path.display());
+                    self.messages.warn(format!(
+                        "{} was deleted on disk; saving will write it again",
+                        file_name(&path)
+                    ));
+                    true
+                }
+            };
+        }
+        redraw
+    }
+
+    /// Note that the editor itself just wrote the open file, so the write
+    /// is not mistaken for someone else's.
+    pub(crate) fn forget_own_write(&mut self) {
+        if let Some(path) = &self.disk_watch.path {
+            self.disk_watch.watcher.watch(path);
+        }
+    }
+
+    fn handle_disk_modified(&mut self, path: &Path) -> bool {
+        let text = match self.buffer.encoding().read(path) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("could not read changed file {}: {}", path.display(), e);
+                return false;
+            }
+        };
+        // Touched, or rewritten with what we already have.
+        if self.buffer.text() == text.as_str() {
+            return false;
+        }
+        info!("{} changed on disk", path.display());
+        if self.buffer.is_dirty() {
+            self.disk_watch.unasked = Some(path.to_path_buf());
+            return false;
+        }
+        self.reload_from_disk(text);
+        self.messages
+            .info(format!("Reloaded {}: changed on disk", file_name(path)));
+        true
+    }
+
+    /// Answer the reload prompt: take the file as it is on disk, or keep
+    /// the buffer's edits (and overwrite the file on the next save).
+    pub(crate) fn confirm_reload(&mut self, yes: bool) {
+        let Some(path) = self.buffer.path().map(Path::to_path_buf) else {
+            return;
+        };
+        if !yes {
+            self.messages.info(format!(
+                "Kept your edits; saving will overwrite {}",
+                file_name(&path)
+            ));
+            return;
+        }
+        match self.buffer.encoding().read(&path) {
+            Ok(text) => {
+                self.reload_from_disk(text);
+                self.messages.info(format!("Reloaded {}", file_name(&path)));
+            }
+            Err(e) => {
+                self.messages
+                    .error(format!("Could not reload {}: {}", file_name(&path), e));
+                error!("reload failed: {}", e);
+            }
+        }
+    }
+
+    /// Prompt text while asking whether to reload.
+    pub(crate) fn reload_prompt(&self) -> String {
+        format!(
+            "{} changed on disk. Reload and discard your edits? (y/n)",
+            self.filename.as_deref().unwrap_or("The file")
+        )
+    }
+
+    fn reload_from_disk(&mut self, text: String) {
+        match self.buffer.reload(&text) {
+            Ok(events) if !events.is_empty() => {
+                self.record_edit_events(&events);
+                self.lsp_did_change();
+            }
+            Ok(_) => {}
+            Err(e) => {
+                self.messages.error(format!("Reload failed: {}", e));
+                error!("reload failed: {}", e);
+            }
+        }
+    }
+
+    /// Read the open file again in the encoding named by `label`, for a
+    /// file whose encoding was guessed wrong. Later saves use it too.
+    pub(crate) fn confirm_reopen_with_encoding(&mut self, label: &str) {
+        let Some(path) = self.buffer.path().map(Path::to_path_buf) else {
+            self.messages.warn("Save the file before reopening it");
+            return;
+        };
+        let Some(encoding) = TextEncoding::for_label(label) else {
+            self.messages
+                .error(format!("Unknown encoding: {}", label.trim()));
+            return;
+        };
+        if self.buffer.is_dirty() {
+            self.messages.warn(format!(
+                "Save or undo your edits before reopening {}",
+                file_name(&path)
+            ));
+            return;
+        }
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.messages
+                    .error(format!("Could not reopen {}: {}", file_name(&path), e));
+                error!("reopen failed: {}", e);
+                return;
+            }
+        };
+        let (text, had_errors) = encoding.decode(&bytes);
+        self.reload_from_disk(text);
+        self.buffer.set_encoding(encoding);
+        info!("reopened {} as {}", path.display(), encoding);
+        if had_errors {
+            self.messages.warn(format!(
+                "Reopened {} as {}; some bytes were not valid {}",
+                file_name(&path),
+                encoding,
+                encoding
+            ));
+        } else {
+            self.messages
+                .info(format!("Reopened {} as {}", file_name(&path), encoding));
+        }
+    }
+}
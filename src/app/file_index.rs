@@ -0,0 +1,74 @@
+use smash_core::file_index::FileIndexer;
+use smash_core::fuzzy_finder::FileFinder;
+use tracing::info;
+
+use super::{App, InputMode};
+
+/// A background walk filling or refreshing the file finder's index.
+pub(crate) struct FileIndexJob {
+    indexer: FileIndexer,
+    /// Whether files join the index as they are found, for the first
+    /// index. A refresh swaps its listing in once complete, so files
+    /// deleted since drop out without the index ever going empty.
+    streaming: bool,
+}
+
+// =========================================================================
+// File finder index
+// =========================================================================
+
+impl App {
+    /// Walk the workspace for the file finder in the background. An
+    /// existing index stays searchable until the new one is complete.
+    pub(crate) fn start_file_index(&mut self) {
+        if self.file_index.is_some() {
+            return;
+        }
+        let finder = match self.file_finder.as_mut() {
+            Some(finder) => finder,
+            None => {
+                let Ok(cwd) = std::env::current_dir() else {
+                    return;
+                };
+                self.file_finder.insert(FileFinder::new(cwd))
+            }
+        };
+        let root = finder.root().to_path_buf();
+        self.file_index = Some(FileIndexJob {
+            indexer: FileIndexer::spawn(root, finder.max_files()),
+            streaming: finder.file_count() == 0,
+        });
+    }
+
+    /// Take in files found by the background walk. Returns `true` if the
+    /// finder needs a redraw.
+    pub(crate) fn poll_file_index(&mut self) -> bool {
+        let (Some(job), Some(finder)) = (self.file_index.as_mut(), self.file_finder.as_mut())
+        else {
+            return false;
+        };
+        let found = job.indexer.poll();
+        if job.streaming && !found.is_empty() {
+            finder.add_files(found);
+        } else if !job.indexer.is_done() {
+            return false;
+        }
+        if job.indexer.is_done() {
+            if let Some(job) = self.file_index.take() {
+                let files = job.indexer.into_files();
+                info!("file index: {} files", files.len());
+                finder.set_files(files);
+            }
+        }
+        if self.input_mode != InputMode::FileFinder {
+            return false;
+        }
+        self.search_files(true);
+        true
+    }
+
+    /// Files found so far while the index is being built.
+    pub(crate) fn file_index_progress(&self) -> Option<usize> {
+        self.file_index.as_ref().map(|job| job.indexer.file_count())
+    }
+}
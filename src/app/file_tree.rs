@@ -0,0 +1,308 @@
+use std::path::{Path, PathBuf};
+
+use smash_input::{Key, KeyEvent};
+use smash_tui::FileTree;
+use tracing::{info, warn};
+
+use super::loading::file_name;
+use super::paths::PathFormat;
+use super::{App, InputMode};
+
+/// Narrowest terminal that still gets a sidebar next to the editor.
+const MIN_TOTAL_WIDTH: u16 = 40;
+
+// =========================================================================
+// File tree sidebar
+// =========================================================================
+
+impl App {
+    /// Show the file tree and focus it. If it already has focus, hide it.
+    pub(crate) fn cmd_toggle_file_tree(&mut self) {
+        if self.file_tree_visible && self.file_tree_focused {
+            self.file_tree_visible = false;
+            self.file_tree_focused = false;
+            return;
+        }
+        match self.file_tree.as_mut() {
+            Some(tree) => tree.refresh(),
+            None => match std::env::current_dir() {
+                Ok(cwd) => self.file_tree = Some(FileTree::new(cwd)),
+                Err(e) => {
+                    self.messages
+                        .error(format!("Cannot list the workspace: {}", e));
+                    return;
+                }
+            },
+        }
+        // Start on the file being edited.
+        if let (Some(tree), Some(path)) = (self.file_tree.as_mut(), self.buffer.path()) {
+            tree.select_path(&absolute(path));
+        }
+        self.file_tree_visible = true;
+        self.file_tree_focused = true;
+        self.search_results_focused = false;
+    }
+
+    /// Columns taken by the sidebar on a screen `total` columns wide.
+    pub(crate) fn file_tree_width(&self, total: u16) -> u16 {
+        if !self.file_tree_visible || self.file_tree.is_none() || total < MIN_TOTAL_WIDTH {
+            return 0;
+        }
+        (total / 4).clamp(20, 32)
+    }
+
+    /// Whether keystrokes currently go to the file tree.
+    pub(crate) fn file_tree_focused(&self) -> bool {
+        self.file_tree_visible
+            && self.file_tree_focused
+            && self.file_tree.is_some()
+            && self.input_mode == InputMode::Normal
+    }
+
+    /// Handle a key while the file tree has focus. Keys with Ctrl or Alt
+    /// fall through to the keymap, so saving, quitting and the toggle key
+    /// keep working; everything else is used by the tree.
+    pub(crate) fn handle_file_tree_key(&mut self, key: &KeyEvent) -> bool {
+        if !self.file_tree_focused() || key.modifiers.ctrl() || key.modifiers.alt() {
+            return false;
+        }
+        let Some(tree) = self.file_tree.as_mut() else {
+            return false;
+        };
+        if tree.handle_key(&key.key) {
+            return true;
+        }
+        match key.key {
+            Key::Right | Key::Char('l') => tree.expand(),
+            Key::Left | Key::Char('h') => tree.collapse(),
+            Key::Enter => self.activate_file_tree_entry(),
+            Key::Char('a') => self.start_tree_prompt(InputMode::PromptTreeCreate),
+            Key::Char('r') => self.start_tree_prompt(InputMode::PromptTreeRename),
+            Key::Char('d') | Key::Delete => self.start_tree_prompt(InputMode::PromptTreeDelete),
+            Key::Char('R') => tree.refresh(),
+            Key::Char('y') => self.cmd_copy_path(PathFormat::Relative),
+            Key::Char('Y') => self.cmd_copy_path(PathFormat::Absolute),
+            Key::Esc => self.file_tree_focused = false,
+            _ => {}
+        }
+        true
+    }
+
+    /// Open the selected file in the editor, or expand/collapse the
+    /// selected directory.
+    pub(crate) fn activate_file_tree_entry(&mut self) {
+        let Some(tree) = self.file_tree.as_mut() else {
+            return;
+        };
+        let Some(entry) = tree.selected().cloned() else {
+            return;
+        };
+        if entry.is_dir {
+            tree.toggle();
+            return;
+        }
+        self.push_jump();
+        self.open_path(entry.path, None, false);
+        self.file_tree_focused = false;
+    }
+
+    fn start_tree_prompt(&mut self, mode: InputMode) {
+        let selected = self.file_tree.as_ref().and_then(|t| t.selected().cloned());
+        self.prompt_input = match (&mode, selected) {
+            (InputMode::PromptTreeRename, Some(entry)) => entry.name,
+            (InputMode::PromptTreeCreate, _) => String::new(),
+            // Rename and delete need something selected.
+            (_, None) => return,
+            (_, Some(_)) => String::new(),
+        };
+        self.input_mode = mode;
+    }
+
+    /// Prompt text for the file tree prompts.
+    pub(crate) fn file_tree_prompt(&self) -> String {
+        let tree = self.file_tree.as_ref();
+        let selected = tree
+            .and_then(|t| t.selected())
+            .map(|e| e.name.as_str())
+            .unwrap_or("");
+        match self.input_mode {
+            InputMode::PromptTreeCreate => {
+                let dir = tree.map(|t| tree_relative(t, &t.target_dir()));
+                format!(
+                    "New file in {}/ (end with / for a folder): {}",
+                    dir.unwrap_or_default(),
+                    self.prompt_input
+                )
+            }
+            InputMode::PromptTreeRename => {
+                format!("Rename {} to: {}", selected, self.prompt_input)
+            }
+            InputMode::PromptTreeDelete => format!("Delete {}? (y/n)", selected),
+            _ => String::new(),
+        }
+    }
+
+    /// Create the file or folder named in the prompt inside the selected
+    /// directory, then open the new file.
+    pub(crate) fn confirm_tree_create(&mut self, input: &str) {
+        let Some(tree) = self.file_tree.as_mut() else {
+            return;
+        };
+        let name = input.trim();
+        if name.is_empty() {
+            self.messages.warn("Nothing created — no name entered");
+            return;
+        }
+        let path = tree.target_dir().join(name);
+        let result = if name.ends_with('/') {
+            std::fs::create_dir_all(&path)
+        } else {
+            path.parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| {
+                    std::fs::OpenOptions::new()
+                        .write(true)
+                        .create_new(true)
+                        .open(&path)
+                        .map(|_| ())
+                })
+        };
+        match result {
+            Ok(()) => {
+                info!("file tree: created {}", path.display());
+                let path = PathBuf::from(path.to_string_lossy().trim_end_matches('/'));
+                tree.select_path(&path);
+                self.messages.info(format!("Created {}", name));
+                if !name.ends_with('/') {
+                    self.push_jump();
+                    self.open_path(path, None, false);
+                    self.file_tree_focused = false;
+                }
+            }
+            Err(e) => {
+                self.messages
+                    .error(format!("Cannot create {}: {}", name, e));
+                warn!("file tree create failed: {}", e);
+            }
+        }
+    }
+
+    /// Rename the selected entry within its directory.
+    pub(crate) fn confirm_tree_rename(&mut self, input: &str) {
+        let Some(tree) = self.file_tree.as_mut() else {
+            return;
+        };
+        let Some(entry) = tree.selected().cloned() else {
+            return;
+        };
+        let name = input.trim();
+        if name.is_empty() || name == entry.name {
+            return;
+        }
+        let dir = entry.path.parent().unwrap_or(tree.root());
+        let target = dir.join(name);
+        if target.exists() {
+            self.messages
+                .error(format!("Cannot rename: {} already exists", name));
+            return;
+        }
+        if let Err(e) = std::fs::rename(&entry.path, &target) {
+            self.messages
+                .error(format!("Cannot rename {}: {}", entry.name, e));
+            warn!("file tree rename failed: {}", e);
+            return;
+        }
+        info!(
+            "file tree: renamed {} to {}",
+            entry.path.display(),
+            target.display()
+        );
+        tree.select_path(&target);
+        self.messages
+            .info(format!("Renamed {} to {}", entry.name, name));
+        // Keep editing the file under its new name.
+        let open = self.buffer.path().map(absolute);
+        if let Some(rest) = open
+            .as_deref()
+            .and_then(|p| p.strip_prefix(&entry.path).ok())
+        {
+            let moved = target.join(rest);
+            let old_uri = self.current_uri();
+            self.filename = Some(file_name(&moved));
+            self.buffer.set_path(moved);
+            self.reattach_language(old_uri);
+        }
+    }
+
+    /// Delete the selected entry if `yes`; a directory goes with
+    /// everything in it.
+    pub(crate) fn confirm_tree_delete(&mut self, yes: bool) {
+        let Some(tree) = self.file_tree.as_mut() else {
+            return;
+        };
+        let Some(entry) = tree.selected().cloned() else {
+            return;
+        };
+        if !yes {
+            self.messages.info("Delete cancelled");
+            return;
+        }
+        let result = if entry.is_dir {
+            std::fs::remove_dir_all(&entry.path)
+        } else {
+            std::fs::remove_file(&entry.path)
+        };
+        match result {
+            Ok(()) => {
+                info!("file tree: deleted {}", entry.path.display());
+                tree.refresh();
+                self.messages.info(format!("Deleted {}", entry.name));
+            }
+            Err(e) => {
+                self.messages
+                    .error(format!("Cannot delete {}: {}", entry.name, e));
+                warn!("file tree delete failed: {}", e);
+            }
+        }
+    }
+
+    /// A click on row `row` of the sidebar: select that entry, focus the
+    /// tree and open it as Enter would.
+    pub(crate) fn click_file_tree(&mut self, row: u16) {
+        let Some(tree) = self.file_tree.as_mut() else {
+            return;
+        };
+        // Row 0 is the title.
+        if row == 0 {
+            self.file_tree_focused = true;
+            return;
+        }
+        let index = tree.scroll() + row as usize - 1;
+        if index >= tree.entries().len() {
+            self.file_tree_focused = true;
+            return;
+        }
+        tree.select_index(index);
+        self.file_tree_focused = true;
+        self.activate_file_tree_entry();
+    }
+}
+
+/// `path` relative to the tree root, `.` for the root itself.
+fn tree_relative(tree: &FileTree, path: &Path) -> String {
+    let rel = path.strip_prefix(tree.root()).unwrap_or(path);
+    if rel.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        rel.display().to_string()
+    }
+}
+
+/// `path` made absolute against the working directory, which is where the
+/// tree is rooted.
+pub(super) fn absolute(path: &Path) -> PathBuf {
+    match std::env::current_dir() {
+        Ok(cwd) if path.is_relative() => cwd.join(path),
+        _ => path.to_path_buf(),
+    }
+}
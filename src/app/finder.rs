@@ -0,0 +1,175 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use smash_core::fuzzy_finder::{fuzzy_positions, FileMatch};
+use smash_input::Command;
+use smash_syntax::{LanguageId, RegexHighlighter};
+use smash_tui::FinderRow;
+
+use super::{App, InputMode};
+
+/// Matches listed in the finder.
+const MAX_RESULTS: usize = 200;
+
+/// Lines of the selected file shown in the preview.
+const PREVIEW_LINES: usize = 100;
+
+/// Bytes read for the preview; enough for [`PREVIEW_LINES`] of most code.
+const PREVIEW_BYTES: u64 = 64 * 1024;
+
+/// The first lines of the file selected in the finder.
+pub(crate) struct FinderPreview {
+    path: PathBuf,
+    pub(crate) lines: Vec<String>,
+    /// Colours the preview like the file would be in the editor.
+    pub(crate) highlighter: Option<RegexHighlighter>,
+}
+
+/// The start of `path` as preview lines, tabs expanded; or a line saying
+/// why it cannot be shown.
+fn read_preview(path: &Path) -> Vec<String> {
+    let mut bytes = Vec::new();
+    if let Err(e) = File::open(path).and_then(|f| f.take(PREVIEW_BYTES).read_to_end(&mut bytes)) {
+        return vec![format!("Cannot read file: {}", e)];
+    }
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        return vec!["Binary file".to_string()];
+    }
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .take(PREVIEW_LINES)
+        .map(|line| line.replace('\t', "    "))
+        .collect()
+}
+
+// =========================================================================
+// File finder
+// =========================================================================
+
+impl App {
+    pub(crate) fn cmd_open_file_finder(&mut self) {
+        self.input_mode = InputMode::FileFinder;
+        self.prompt_input.clear();
+        // Reuse the index, refreshing it for files added or deleted since.
+        self.start_file_index();
+        self.update_finder_results();
+    }
+
+    /// Search again for the query just typed, selecting the best match.
+    pub(crate) fn update_finder_results(&mut self) {
+        self.search_files(false);
+    }
+
+    /// Search the index for the query, ranking recently opened files
+    /// higher. With `keep_selection`, e.g. as more files are indexed, the
+    /// selected file stays selected.
+    pub(crate) fn search_files(&mut self, keep_selection: bool) {
+        let Some(finder) = self.file_finder.as_ref() else {
+            return;
+        };
+        let recent: Vec<PathBuf> = self
+            .recent_files
+            .files
+            .iter()
+            .filter_map(|p| p.strip_prefix(finder.root()).ok())
+            .map(Path::to_path_buf)
+            .collect();
+        let selected = self.selected_finder_match().map(|m| m.path().to_path_buf());
+        self.finder_results = finder.search_with_recent(&self.prompt_input, MAX_RESULTS, &recent);
+        let index = selected
+            .filter(|_| keep_selection)
+            .and_then(|path| self.finder_results.iter().position(|m| m.path() == path))
+            .unwrap_or(0);
+        self.finder_list.select(index, self.finder_results.len());
+        self.update_finder_preview();
+    }
+
+    /// Move the selection for Up, Down, PageUp and PageDown. Returns
+    /// `true` if `cmd` was one of them.
+    pub(crate) fn move_finder_selection(&mut self, cmd: &Command) -> bool {
+        if !self
+            .finder_list
+            .handle_command(cmd, self.finder_results.len())
+        {
+            return false;
+        }
+        self.update_finder_preview();
+        true
+    }
+
+    pub(crate) fn selected_finder_match(&self) -> Option<&FileMatch> {
+        self.finder_results.get(self.finder_list.selected())
+    }
+
+    /// Read the selected file for the preview, unless it is already shown.
+    fn update_finder_preview(&mut self) {
+        let Some(path) = self.selected_finder_match().map(|m| m.path().to_path_buf()) else {
+            self.finder_preview = None;
+            return;
+        };
+        if self.finder_preview.as_ref().is_some_and(|p| p.path == path) {
+            return;
+        }
+        let language = LanguageId::from_path(&path);
+        // Building a highlighter compiles its patterns; keep the last one
+        // while browsing files of one language.
+        let highlighter = match self.finder_preview.take().and_then(|p| p.highlighter) {
+            Some(h) if h.language() == language => Some(h),
+            _ => RegexHighlighter::new(language).ok(),
+        };
+        self.finder_preview = Some(FinderPreview {
+            lines: read_preview(&path),
+            path,
+            highlighter,
+        });
+    }
+
+    /// The list rows fitting in `rows`, scrolled to keep the selection in
+    /// view, and the selection's index among them.
+    pub(crate) fn finder_rows(&mut self, rows: usize) -> (Vec<FinderRow>, Option<usize>) {
+        let rows = rows.max(1);
+        self.finder_list
+            .scroll_to_selected(rows, self.finder_results.len());
+        let visible = self
+            .finder_results
+            .iter()
+            .skip(self.finder_list.scroll())
+            .take(rows)
+            .map(|m| {
+                let label = m.relative_path();
+                let matched = fuzzy_positions(&self.prompt_input, label).unwrap_or_default();
+                FinderRow::new(label, matched)
+            })
+            .collect();
+        let selected = self
+            .finder_list
+            .selected()
+            .checked_sub(self.finder_list.scroll());
+        (visible, selected.filter(|&s| s < rows))
+    }
+
+    /// Shown at the right of the query: matches out of the files indexed,
+    /// and progress while indexing.
+    pub(crate) fn finder_status(&self) -> String {
+        let total = self.file_finder.as_ref().map_or(0, |f| f.file_count());
+        let count = format!("{}/{}", self.finder_results.len(), total);
+        match self.file_index_progress() {
+            Some(found) => format!("indexing\u{2026} {} files  {}", found, count),
+            None => count,
+        }
+    }
+
+    /// Open the selected file.
+    pub(crate) fn confirm_file_finder(&mut self) {
+        let selected = self.selected_finder_match().map(|m| m.path().to_path_buf());
+        self.input_mode = InputMode::Normal;
+        self.prompt_input.clear();
+        self.finder_results.clear();
+        self.finder_preview = None;
+        match selected {
+            Some(path) => self.confirm_open(&path.to_string_lossy()),
+            None => self.messages.info("No matching files"),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use super::App;
+use crate::blame::{BlameJob, BlameLine};
+
+/// How often the buffer is checked for edits that need a fresh blame.
+const BLAME_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Blame annotations for the open file, while they are switched on.
+pub(crate) struct GitBlame {
+    job: Option<BlameJob>,
+    /// The file `lines` are for.
+    path: Option<PathBuf>,
+    lines: Vec<BlameLine>,
+    /// Buffer text last sent to `git blame`.
+    blamed: Option<String>,
+    next_check: Instant,
+}
+
+impl GitBlame {
+    fn new() -> Self {
+        Self {
+            job: None,
+            path: None,
+            lines: Vec::new(),
+            blamed: None,
+            next_check: Instant::now(),
+        }
+    }
+}
+
+// =========================================================================
+// Git blame
+// =========================================================================
+
+impl App {
+    /// Show or hide who last changed the cursor line.
+    pub(crate) fn cmd_toggle_git_blame(&mut self) {
+        if self.git_blame.take().is_some() {
+            self.messages.info("Git blame off");
+            return;
+        }
+        if self.buffer.path().is_none() {
+            self.messages.warn("Save the file before blaming it");
+            return;
+        }
+        self.git_blame = Some(GitBlame::new());
+        self.messages.info("Git blame on");
+        self.poll_git_blame(Instant::now());
+    }
+
+    /// Collect a finished blame, and start another when the buffer has
+    /// changed since the last, at most every [`BLAME_CHECK_INTERVAL`].
+    /// Returns `true` if the screen needs a redraw.
+    pub(crate) fn poll_git_blame(&mut self, now: Instant) -> bool {
+        let Some(blame) = self.git_blame.as_mut() else {
+            return false;
+        };
+        if let Some(job) = &blame.job {
+            let Some(result) = job.poll() else {
+                return false;
+            };
+            let path = job.path().to_path_buf();
+            blame.job = None;
+            match result {
+                Ok(lines) => {
+                    blame.path = Some(path);
+                    blame.lines = lines;
+                }
+                Err(e) => {
+                    self.git_blame = None;
+                    self.messages.error(format!("Git blame: {}", e));
+                }
+            }
+            return true;
+        }
+        if now < blame.next_check {
+            return false;
+        }
+        blame.next_check = now + BLAME_CHECK_INTERVAL;
+        let Some(path) = self.buffer.path() else {
+            return false;
+        };
+        let text = self.buffer.text();
+        let unchanged = blame.blamed.as_deref().is_some_and(|b| *text == b);
+        if blame.path.as_deref() == Some(path) && unchanged {
+            return false;
+        }
+        let text = text.to_string();
+        blame.blamed = Some(text.clone());
+        blame.job = Some(BlameJob::spawn(path, text));
+        false
+    }
+
+    /// Who last changed the cursor line, if blame is on and has run for
+    /// this file.
+    pub(crate) fn blame_annotation(&self) -> Option<String> {
+        let blame = self.git_blame.as_ref()?;
+        if blame.path.as_deref() != self.buffer.path() {
+            return None;
+        }
+        let line = self.buffer.cursors().primary().position().line;
+        blame.lines.get(line).map(BlameLine::annotation)
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+use smash_core::diff::{diff_lines, DiffKind};
+use smash_tui::ScrollbarMark;
+
+use super::diff_view::head_text;
+use super::App;
+
+/// How often the buffer is checked for edits that change the marks.
+const CHANGES_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the committed text is read again, to catch new commits.
+const HEAD_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Lines of the open file changed since the last commit, for the
+/// scrollbar.
+pub(crate) struct GitChanges {
+    path: PathBuf,
+    /// The committed text being read by `git show`.
+    loading: Option<Receiver<Option<String>>>,
+    loaded_at: Instant,
+    /// The committed text; `None` when the file is not in git.
+    head: Option<String>,
+    /// Buffer text `marks` were worked out from.
+    diffed: Option<String>,
+    marks: Vec<(usize, ScrollbarMark)>,
+    next_check: Instant,
+}
+
+impl GitChanges {
+    fn new(path: &Path, now: Instant) -> Self {
+        let mut changes = Self {
+            path: path.to_path_buf(),
+            loading: None,
+            loaded_at: now,
+            head: None,
+            diffed: None,
+            marks: Vec::new(),
+            next_check: now,
+        };
+        changes.load_head(now);
+        changes
+    }
+
+    fn load_head(&mut self, now: Instant) {
+        let (tx, rx) = mpsc::channel();
+        let path = self.path.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(head_text(&path).ok());
+        });
+        self.loading = Some(rx);
+        self.loaded_at = now;
+    }
+}
+
+/// A mark for each line of `text` added or changed since `head`, and
+/// for each place lines were removed from.
+fn change_marks(head: &str, text: &str) -> Vec<(usize, ScrollbarMark)> {
+    let mut marks = Vec::new();
+    for hunk in diff_lines(head, text, 0) {
+        let removed = hunk.lines.iter().any(|l| l.kind == DiffKind::Removed);
+        let kind = match (hunk.new_len, removed) {
+            (0, _) => {
+                marks.push((hunk.new_start, ScrollbarMark::Removed));
+                continue;
+            }
+            (_, true) => ScrollbarMark::Modified,
+            (_, false) => ScrollbarMark::Added,
+        };
+        marks.extend((hunk.new_start..hunk.new_start + hunk.new_len).map(|line| (line, kind)));
+    }
+    marks
+}
+
+// =========================================================================
+// Git changes
+// =========================================================================
+
+impl App {
+    /// Keep the marks of lines changed since the last commit up to date
+    /// while the scrollbar is shown: the committed text is read in the
+    /// background and diffed with the buffer when it changes, at most
+    /// every [`CHANGES_CHECK_INTERVAL`]. Returns `true` if the marks
+    /// changed.
+    pub(crate) fn poll_git_changes(&mut self, now: Instant) -> bool {
+        let Some(path) = self.buffer.path().filter(|_| self.show_scrollbar) else {
+            return self.git_changes.take().is_some_and(|c| !c.marks.is_empty());
+        };
+        let changes = match self.git_changes.as_mut() {
+            Some(changes) if changes.path == path => changes,
+            _ => {
+                let had_marks = self
+                    .git_changes
+                    .replace(GitChanges::new(path, now))
+                    .is_some_and(|c| !c.marks.is_empty());
+                return had_marks;
+            }
+        };
+        if let Some(rx) = &changes.loading {
+            match rx.try_recv() {
+                Ok(head) => {
+                    changes.head = head;
+                    changes.diffed = None;
+                    changes.next_check = now;
+                }
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => {}
+            }
+            changes.loading = None;
+        } else if now >= changes.loaded_at + HEAD_RELOAD_INTERVAL {
+            changes.load_head(now);
+            return false;
+        }
+        if now < changes.next_check {
+            return false;
+        }
+        changes.next_check = now + CHANGES_CHECK_INTERVAL;
+        let text = self.buffer.text();
+        if changes.diffed.as_deref().is_some_and(|d| *text == d) {
+            return false;
+        }
+        let text = text.to_string();
+        let marks = match &changes.head {
+            Some(head) => change_marks(head, &text),
+            None => Vec::new(),
+        };
+        changes.diffed = Some(text);
+        if marks == changes.marks {
+            return false;
+        }
+        changes.marks = marks;
+        true
+    }
+
+    /// Lines of the buffer changed since the last commit, as far as
+    /// they are known.
+    pub(crate) fn git_change_marks(&self) -> &[(usize, ScrollbarMark)] {
+        match &self.git_changes {
+            Some(changes) if Some(changes.path.as_path()) == self.buffer.path() => &changes.marks,
+            _ => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_added_changed_and_removed_lines() {
+        let head = "a\nb\nc\nd\ne\n";
+        let text = "a\nB\nc\nnew\nd\n";
+        assert_eq!(
+            change_marks(head, text),
+            vec![
+                (1, ScrollbarMark::Modified),
+                (3, ScrollbarMark::Added),
+                (5, ScrollbarMark::Removed),
+            ]
+        );
+        assert!(change_marks(text, text).is_empty());
+    }
+}
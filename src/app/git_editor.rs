@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use smash_core::edit::EditCommand;
+use smash_core::position::{Position, Range};
+use smash_syntax::LanguageId;
+
+use super::App;
+
+/// Rebase todo actions that name a commit, long and short forms.
+const COMMIT_ACTIONS: &[&str] = &[
+    "pick", "reword", "edit", "squash", "fixup", "drop", "p", "r", "e", "s", "f", "d",
+];
+
+/// Whether `path` is a file git opens the editor for: a commit, merge
+/// or tag message, or a rebase todo list.
+pub(crate) fn is_git_file(path: &Path) -> bool {
+    matches!(
+        LanguageId::from_path(path),
+        LanguageId::GitCommit | LanguageId::GitRebase
+    )
+}
+
+// =========================================================================
+// Editing for git (commit messages and rebase todo lists)
+// =========================================================================
+
+impl App {
+    /// Columns to mark for the buffer: those from `display.rulers`, and
+    /// git's 50 character subject and 72 character body limits in commit
+    /// messages. None while `ToggleRulers` has them hidden.
+    pub(crate) fn rulers(&self) -> Vec<usize> {
+        if !self.show_rulers {
+            return Vec::new();
+        }
+        let mut rulers = self.ruler_columns.clone();
+        if self.buffer.path().map(LanguageId::from_path) == Some(LanguageId::GitCommit) {
+            rulers.extend([50, 72]);
+        }
+        rulers
+    }
+
+    /// Set the action of every rebase todo line a cursor or selection is
+    /// on, e.g. `pick abc123 msg` to `fixup abc123 msg`.
+    pub(crate) fn cmd_rebase_action(&mut self, action: &str) {
+        let rebase = self.buffer.path().map(LanguageId::from_path) == Some(LanguageId::GitRebase);
+        if !rebase {
+            self.messages
+                .warn("Not a rebase todo list (git-rebase-todo)");
+            return;
+        }
+        let mut lines: Vec<usize> = self
+            .buffer
+            .cursors()
+            .iter()
+            .flat_map(|c| match c.selection_range() {
+                Some(r) if !r.is_empty() => {
+                    // A selection ending at a line start leaves that line.
+                    let last = if r.end.col == 0 && r.end.line > r.start.line {
+                        r.end.line - 1
+                    } else {
+                        r.end.line
+                    };
+                    (r.start.line..=last).collect::<Vec<_>>()
+                }
+                _ => vec![c.position().line],
+            })
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        let mut changed = 0;
+        self.buffer.begin_undo_group();
+        for line in lines {
+            let text: String = match self.buffer.line(line) {
+                Some(slice) => slice.chars().collect(),
+                None => continue,
+            };
+            let indent = text.chars().take_while(|c| c.is_whitespace()).count();
+            let word: String = text
+                .chars()
+                .skip(indent)
+                .take_while(|c| !c.is_whitespace())
+                .collect();
+            if !COMMIT_ACTIONS.contains(&word.as_str()) || word == action {
+                continue;
+            }
+            let range = Range::new(
+                Position::new(line, indent),
+                Position::new(line, indent + word.chars().count()),
+            );
+            if self.apply_edit(EditCommand::Delete { range }).is_err() {
+                break;
+            }
+            let _ = self.apply_edit(EditCommand::Insert {
+                pos: range.start,
+                text: action.to_string(),
+            });
+            changed += 1;
+        }
+        self.buffer.end_undo_group();
+        if changed == 0 {
+            self.messages.info("No commit lines to change");
+        } else {
+            self.lsp_did_change();
+        }
+    }
+
+    /// Quit without saving and exit with status 1, which makes git abort
+    /// the commit or rebase it started the editor for.
+    pub(crate) fn cmd_abort_edit(&mut self) {
+        self.exit_code = 1;
+        self.running = false;
+    }
+}
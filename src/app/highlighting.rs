@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+use smash_syntax::{HighlightEngine, HighlightSpan, LanguageId, LazyHighlighter, RegexHighlighter};
+
+use super::App;
+
+/// Lines a frame highlights itself when the worker has not reached them
+/// yet: enough for the line being typed on, too few to stall a jump
+/// across a large file.
+const FRAME_HIGHLIGHT_BUDGET: usize = 8;
+
+/// Background highlighting for the current buffer.
+pub(crate) struct LazyHighlights {
+    language: LanguageId,
+    pub(crate) highlighter: LazyHighlighter,
+    /// Top line of the view last frame, to tell which way it scrolls.
+    last_top: usize,
+}
+
+impl LazyHighlights {
+    /// The highlighter to draw a frame with, if `engine` is still the
+    /// one this was started from.
+    pub(crate) fn for_frame<'a>(
+        &'a self,
+        engine: &'a RegexHighlighter,
+    ) -> Option<FrameHighlighter<'a>> {
+        (self.language == engine.language()).then(|| FrameHighlighter {
+            lazy: &self.highlighter,
+            engine,
+            budget: AtomicUsize::new(FRAME_HIGHLIGHT_BUDGET),
+        })
+    }
+}
+
+/// What a frame is drawn with: spans from the worker, falling back to
+/// highlighting a few missing lines on the spot.
+pub(crate) struct FrameHighlighter<'a> {
+    lazy: &'a LazyHighlighter,
+    engine: &'a RegexHighlighter,
+    budget: AtomicUsize,
+}
+
+impl HighlightEngine for FrameHighlighter<'_> {
+    fn highlight_line(&self, line: &str) -> Vec<HighlightSpan> {
+        if self.lazy.is_ready(line) {
+            return self.lazy.highlight_line(line);
+        }
+        let left = self.budget.load(AtomicOrdering::Relaxed);
+        if left == 0 {
+            return Vec::new();
+        }
+        self.budget.store(left - 1, AtomicOrdering::Relaxed);
+        self.engine.highlight_line(line)
+    }
+
+    fn engine_name(&self) -> &str {
+        "lazy"
+    }
+}
+
+// =========================================================================
+// Lazy highlighting
+// =========================================================================
+
+impl App {
+    /// Ask the worker for the lines around the view: the visible ones
+    /// first, then `highlight_margin` lines on each side, twice as many
+    /// ahead of the scroll direction. Spans far from the view are
+    /// dropped.
+    pub(crate) fn prefetch_highlights(&mut self) {
+        let Some(engine) = self.highlighter.as_ref() else {
+            self.lazy_highlights = None;
+            return;
+        };
+        let top = self.viewport.top_line();
+        if self.lazy_highlights.as_ref().map(|l| l.language) != Some(engine.language()) {
+            self.lazy_highlights = Some(LazyHighlights {
+                language: engine.language(),
+                highlighter: LazyHighlighter::spawn(engine.clone()),
+                last_top: top,
+            });
+        }
+        let Some(lazy) = self.lazy_highlights.as_mut() else {
+            return;
+        };
+
+        let total = self.buffer.line_count();
+        let bottom = (top + self.viewport.visible_lines()).min(total);
+        let margin = self.highlight_margin;
+        let direction = top.cmp(&lazy.last_top);
+        lazy.last_top = top;
+        let (above, below) = match direction {
+            Ordering::Greater => (margin / 2, margin * 2),
+            Ordering::Less => (margin * 2, margin / 2),
+            Ordering::Equal => (margin, margin),
+        };
+        let start = top.saturating_sub(above);
+        let end = (bottom + below).min(total);
+
+        let text = self.buffer.text();
+        let line = |i: usize| Cow::<str>::from(text.line(i));
+        let visible = (top..bottom).map(line);
+        let after = (bottom..end).map(line);
+        let before = (start..top).rev().map(line);
+        if direction == Ordering::Less {
+            lazy.highlighter.request(visible.chain(before).chain(after));
+        } else {
+            lazy.highlighter.request(visible.chain(after).chain(before));
+        }
+        if lazy.highlighter.cached_lines() > 2 * (end - start) {
+            lazy.highlighter.retain((start..end).map(line));
+        }
+    }
+
+    /// Take in lines the worker has highlighted. Returns `true` if any
+    /// arrived and the screen needs a redraw.
+    pub(crate) fn poll_highlights(&mut self) -> bool {
+        self.lazy_highlights
+            .as_mut()
+            .is_some_and(|l| l.highlighter.poll() > 0)
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+use smash_syntax::{LanguageId, RegexHighlighter};
+use tracing::info;
+
+use super::{App, StartupPhase};
+
+/// Pause in input after which idle callbacks run, until the config sets
+/// `editor.idle_delay_ms`.
+pub(crate) const IDLE_DELAY: Duration = Duration::from_millis(500);
+
+/// Work left until the editor is idle: startup tasks deferred past the
+/// first frame, and upkeep. Run one task at a time while no input is
+/// waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IdleTask {
+    /// Compile the syntax highlighter for the current file.
+    Highlighter,
+    /// Start the language server for the current file.
+    StartLsp,
+    /// Index the workspace for the file finder.
+    IndexFiles,
+    /// Repack the buffer's text after many edits and check the memory
+    /// budget.
+    Compact,
+}
+
+// =========================================================================
+// Idle tasks
+// =========================================================================
+
+impl App {
+    pub(crate) fn queue_idle(&mut self, task: IdleTask) {
+        if !self.idle_tasks.contains(&task) {
+            self.idle_tasks.push_back(task);
+        }
+    }
+
+    pub(crate) fn has_idle_tasks(&self) -> bool {
+        !self.idle_tasks.is_empty()
+    }
+
+    /// Run the next idle task. Returns `true` if the screen needs a redraw.
+    pub(crate) fn run_idle_task(&mut self) -> bool {
+        let Some(task) = self.idle_tasks.pop_front() else {
+            return false;
+        };
+        let started = std::time::Instant::now();
+        let redraw = match task {
+            IdleTask::Highlighter => {
+                let path = self.buffer.path().map(|p| p.to_path_buf());
+                // A file opened since has brought its own highlighter.
+                if self.highlighter.is_none() {
+                    self.highlighter =
+                        path.and_then(|p| RegexHighlighter::new(LanguageId::from_path(&p)).ok());
+                }
+                self.highlighter.is_some()
+            }
+            IdleTask::StartLsp => {
+                self.start_lsp_for_current_file();
+                false
+            }
+            IdleTask::IndexFiles => {
+                if self.file_finder.is_none() {
+                    self.start_file_index();
+                }
+                false
+            }
+            IdleTask::Compact => {
+                self.compact_buffer();
+                true
+            }
+        };
+        if task == IdleTask::Highlighter && redraw {
+            self.startup_profile
+                .record(StartupPhase::Highlighter, started.elapsed());
+        }
+        info!("idle task {:?} took {:?}", task, started.elapsed());
+        redraw
+    }
+}
+
+// =========================================================================
+// Input idle callbacks
+// =========================================================================
+
+impl App {
+    /// Restart the wait for input to pause.
+    pub(crate) fn note_input(&mut self, now: Instant) {
+        if self.editor_config.idle_delay_ms > 0 {
+            self.input_idle.schedule(now);
+        }
+    }
+
+    /// Run the idle callbacks once no input has arrived for
+    /// `editor.idle_delay_ms`. Their results arrive later, as server
+    /// events.
+    pub(crate) fn poll_input_idle(&mut self, now: Instant) {
+        if !self.input_idle.fire(now) {
+            return;
+        }
+        if self.hover_on_idle {
+            self.lsp_hover_on_idle();
+        }
+    }
+}
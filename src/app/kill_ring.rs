@@ -0,0 +1,134 @@
+use smash_core::position::Position;
+use smash_tui::{Picker, PickerItem};
+use tracing::warn;
+
+use super::{App, InputMode};
+
+/// Characters of an entry shown in the picker.
+const PREVIEW_CHARS: usize = 80;
+
+/// The clipboard history picker and the entries behind its items.
+pub(crate) struct KillRingPicker {
+    pub(crate) picker: Picker,
+    /// Same order as the picker items.
+    entries: Vec<String>,
+}
+
+/// Text just pasted, which yank-pop may swap for an older entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LastYank {
+    start: Position,
+    end: Position,
+    /// Kill ring entry that was pasted.
+    index: usize,
+}
+
+/// Picker row for `text`: its first line, shortened, then how many lines
+/// it has.
+pub(super) fn entry_item(text: &str) -> PickerItem {
+    let first = text.lines().next().unwrap_or_default();
+    let mut label: String = first.chars().take(PREVIEW_CHARS).collect();
+    if first.chars().count() > PREVIEW_CHARS {
+        label.push('…');
+    }
+    let lines = text.lines().count();
+    let detail = if lines > 1 {
+        format!("{} lines", lines)
+    } else {
+        String::new()
+    };
+    PickerItem::new(label, detail)
+}
+
+// =========================================================================
+// Kill ring (clipboard history)
+// =========================================================================
+
+impl App {
+    /// Remember copied or cut `text` in the kill ring.
+    pub(crate) fn record_kill(&mut self, text: &str) {
+        self.kill_ring.push(text);
+    }
+
+    /// Paste kill ring entry `index`, remembering where it went so a
+    /// yank-pop can replace it.
+    pub(crate) fn yank(&mut self, text: &str, index: usize) {
+        let single = self.buffer.cursors().len() == 1;
+        let cursor = self.buffer.cursors().primary();
+        let start = cursor
+            .selection_range()
+            .map_or(cursor.position(), |r| r.start);
+        self.insert_text(text);
+        if single && !self.buffer.is_read_only() {
+            self.last_yank = Some(LastYank {
+                start,
+                end: self.buffer.cursors().primary().position(),
+                index,
+            });
+        }
+    }
+
+    /// Swap the text just pasted for the next older kill ring entry,
+    /// wrapping around to the newest.
+    pub(crate) fn cmd_yank_pop(&mut self) {
+        let last = self.last_yank.take().filter(|y| {
+            self.buffer.cursors().len() == 1 && self.buffer.cursors().primary().position() == y.end
+        });
+        let Some(last) = last else {
+            self.messages
+                .info("Paste something before cycling the clipboard history");
+            return;
+        };
+        if self.kill_ring.len() < 2 {
+            self.messages.info("No older clipboard entries");
+            self.last_yank = Some(last);
+            return;
+        }
+        let index = (last.index + 1) % self.kill_ring.len();
+        let Some(text) = self.kill_ring.get(index).map(str::to_string) else {
+            return;
+        };
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        cursor.set_anchor(last.start);
+        cursor.set_position(last.end);
+        self.yank(&text, index);
+        self.messages.info(format!(
+            "Clipboard history {}/{}",
+            index + 1,
+            self.kill_ring.len()
+        ));
+    }
+
+    /// Open a picker of the kill ring, newest first.
+    pub(crate) fn cmd_paste_from_history(&mut self) {
+        if self.kill_ring.is_empty() {
+            self.messages.info("Clipboard history is empty");
+            return;
+        }
+        let entries: Vec<String> = self.kill_ring.iter().map(str::to_string).collect();
+        let items = entries.iter().map(|e| entry_item(e)).collect();
+        self.kill_ring_picker = Some(KillRingPicker {
+            picker: Picker::new("Clipboard history", items),
+            entries,
+        });
+        self.input_mode = InputMode::KillRingPicker;
+        self.prompt_input.clear();
+    }
+
+    /// Paste the selected entry. It becomes the newest, and goes back on
+    /// the clipboard.
+    pub(crate) fn confirm_kill_ring_picker(&mut self) {
+        let Some(state) = self.kill_ring_picker.take() else {
+            return;
+        };
+        let Some(text) = state.picker.selected().and_then(|i| state.entries.get(i)) else {
+            self.messages.info("No matching clipboard entry");
+            return;
+        };
+        if let Err(e) = self.clipboard.set(text) {
+            warn!("clipboard set failed: {}", e);
+        }
+        self.kill_ring.push(text);
+        self.yank(text, 0);
+    }
+}
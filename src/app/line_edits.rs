@@ -0,0 +1,169 @@
+use smash_core::cursor::Cursor;
+use smash_core::edit::EditCommand;
+use smash_core::position::{Position, Range};
+
+use super::{line_content_len, App};
+
+// =========================================================================
+// Line editing (smart Home, duplicate, move and join lines)
+// =========================================================================
+
+impl App {
+    /// Move to the first non-blank character of the line, or to column 0
+    /// when already there.
+    pub(crate) fn cmd_smart_home(&mut self) {
+        let pos = self.buffer.cursors().primary().position();
+        let indent = self.first_non_blank(pos.line);
+        let col = if pos.col == indent { 0 } else { indent };
+        self.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(pos.line, col));
+    }
+
+    /// First and last line the primary cursor or its selection covers. A
+    /// selection ending at the start of a line leaves that line out.
+    fn selected_lines(&self) -> (usize, usize) {
+        let cursor = self.buffer.cursors().primary();
+        match cursor.selection_range() {
+            Some(range) if range.end.line > range.start.line && range.end.col == 0 => {
+                (range.start.line, range.end.line - 1)
+            }
+            Some(range) => (range.start.line, range.end.line),
+            None => (cursor.position().line, cursor.position().line),
+        }
+    }
+
+    /// Text of `line` without its line break.
+    fn line_text(&self, line: usize) -> String {
+        self.buffer
+            .line(line)
+            .map(|l| l.chars().take(line_content_len(l)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Lines `first..=last` joined by the buffer's line ending.
+    fn lines_text(&self, first: usize, last: usize) -> String {
+        let eol = self.buffer.line_ending().as_str();
+        (first..=last)
+            .map(|line| self.line_text(line))
+            .collect::<Vec<_>>()
+            .join(eol)
+    }
+
+    /// Replace lines `first..=last`, without the final line break, with
+    /// `text` as one undo step. Returns `false` if the edit was refused.
+    fn replace_lines(&mut self, first: usize, last: usize, text: String) -> bool {
+        let range = Range::new(
+            Position::new(first, 0),
+            Position::new(last, self.line_len(last)),
+        );
+        if self
+            .apply_edit(EditCommand::Replace { range, text })
+            .is_err()
+        {
+            return false;
+        }
+        self.lsp_did_change();
+        true
+    }
+
+    /// Put the primary cursor, and its selection, `delta` lines away from
+    /// where `before` had them.
+    fn shift_cursor(&mut self, before: Cursor, delta: isize) {
+        let shift = |pos: Position| {
+            let line = pos.line.saturating_add_signed(delta);
+            Position::new(line, pos.col.min(self.line_len(line)))
+        };
+        let cursor = match before.anchor() {
+            Some(anchor) => Cursor::with_selection(shift(before.position()), shift(anchor)),
+            None => Cursor::new(shift(before.position())),
+        };
+        *self.buffer.cursors_mut().primary_mut() = cursor;
+    }
+
+    /// Copy the cursor's line, or the selected lines, below themselves and
+    /// move the cursor onto the copy.
+    pub(crate) fn cmd_duplicate_line(&mut self) {
+        self.collapse_cursors();
+        let before = self.buffer.cursors().primary().clone();
+        let (first, last) = self.selected_lines();
+        let block = self.lines_text(first, last);
+        let eol = self.buffer.line_ending().as_str();
+        let text = format!("{}{}{}", block, eol, block);
+        if self.replace_lines(first, last, text) {
+            self.shift_cursor(before, (last - first + 1) as isize);
+        }
+    }
+
+    /// Swap the cursor's line, or the selected lines, with the line above,
+    /// keeping the cursor on them.
+    pub(crate) fn cmd_move_line_up(&mut self) {
+        self.collapse_cursors();
+        let before = self.buffer.cursors().primary().clone();
+        let (first, last) = self.selected_lines();
+        if first == 0 {
+            return;
+        }
+        let eol = self.buffer.line_ending().as_str();
+        let text = format!(
+            "{}{}{}",
+            self.lines_text(first, last),
+            eol,
+            self.line_text(first - 1)
+        );
+        if self.replace_lines(first - 1, last, text) {
+            self.shift_cursor(before, -1);
+        }
+    }
+
+    /// Swap the cursor's line, or the selected lines, with the line below,
+    /// keeping the cursor on them.
+    pub(crate) fn cmd_move_line_down(&mut self) {
+        self.collapse_cursors();
+        let before = self.buffer.cursors().primary().clone();
+        let (first, last) = self.selected_lines();
+        if last >= self.last_line() {
+            return;
+        }
+        let eol = self.buffer.line_ending().as_str();
+        let text = format!(
+            "{}{}{}",
+            self.line_text(last + 1),
+            eol,
+            self.lines_text(first, last)
+        );
+        if self.replace_lines(first, last + 1, text) {
+            self.shift_cursor(before, 1);
+        }
+    }
+
+    /// Join the selected lines, or the cursor's line and the next, into
+    /// one: each following line loses its indentation and is separated by
+    /// a space. The cursor goes to the last join.
+    pub(crate) fn cmd_join_lines(&mut self) {
+        self.collapse_cursors();
+        let (first, last) = self.selected_lines();
+        let last = if last == first { first + 1 } else { last };
+        if last > self.last_line() {
+            return;
+        }
+        let mut joined = self.line_text(first);
+        let mut col = 0;
+        for line in first + 1..=last {
+            let text = self.line_text(line);
+            let rest = text.trim_start();
+            col = joined.chars().count();
+            if rest.is_empty() {
+                continue;
+            }
+            if !joined.is_empty() && !joined.ends_with([' ', '\t']) {
+                joined.push(' ');
+            }
+            joined.push_str(rest);
+        }
+        if self.replace_lines(first, last, joined) {
+            *self.buffer.cursors_mut().primary_mut() = Cursor::new(Position::new(first, col));
+        }
+    }
+}
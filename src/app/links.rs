@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+
+use smash_core::links::{link_at, LinkTarget};
+use smash_core::position::Position;
+use smash_platform::{uri_to_path, PlatformError};
+use tracing::error;
+
+use super::{App, JumpLocation};
+
+/// Opens a URL outside the editor, such as in the web browser.
+pub(crate) type UrlOpener = Box<dyn Fn(&str) -> Result<(), PlatformError>>;
+
+// =========================================================================
+// Links
+// =========================================================================
+
+impl App {
+    /// Open the URL under the cursor in the browser, or jump to the
+    /// `file:line:col` location under it.
+    pub(crate) fn cmd_open_link_under_cursor(&mut self) {
+        let pos = self.buffer.cursors().primary().position();
+        let line: String = self
+            .buffer
+            .line(pos.line)
+            .map(|l| l.chars().take(self.line_len(pos.line)).collect())
+            .unwrap_or_default();
+        let Some(link) = link_at(&line, pos.col) else {
+            self.messages.info("No link under the cursor");
+            return;
+        };
+        self.follow_link(link.target);
+    }
+
+    /// Open a URL in the browser, or jump to a file location.
+    pub(crate) fn follow_link(&mut self, target: LinkTarget) {
+        match target {
+            LinkTarget::Url(url) if url.starts_with("file://") => match uri_to_path(&url) {
+                Some(path) => self.open_link_file(path, Position::new(0, 0)),
+                None => self.messages.warn(format!("Cannot open {}", url)),
+            },
+            LinkTarget::Url(url) => match (self.url_opener)(&url) {
+                Ok(()) => self.messages.info(format!("Opening {}", url)),
+                Err(e) => {
+                    self.messages.error(format!("Cannot open {}: {}", url, e));
+                    error!("opening {} failed: {}", url, e);
+                }
+            },
+            LinkTarget::File { path, line, col } => {
+                let target = Position::new(
+                    line.saturating_sub(1),
+                    col.map_or(0, |c| c.saturating_sub(1)),
+                );
+                match self.resolve_link_path(&path) {
+                    Some(path) => self.open_link_file(path, target),
+                    None => self.messages.warn(format!("No such file: {}", path)),
+                }
+            }
+        }
+    }
+
+    /// The file a link names: as is when absolute, otherwise next to the
+    /// current file or else under the working directory.
+    fn resolve_link_path(&self, path: &str) -> Option<PathBuf> {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+        let beside = self
+            .buffer
+            .path()
+            .and_then(Path::parent)
+            .map(|d| d.join(path));
+        let cwd = std::env::current_dir().ok().map(|d| d.join(path));
+        beside.into_iter().chain(cwd).find(|p| p.is_file())
+    }
+
+    /// Jump to `position` in `path`, staying in this buffer when `path`
+    /// is another way of naming its file.
+    fn open_link_file(&mut self, path: PathBuf, position: Position) {
+        let same_file = |a: &Path, b: &Path| match (a.canonicalize(), b.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        };
+        let path = match self.buffer.path() {
+            Some(current) if same_file(current, &path) => current.to_path_buf(),
+            _ => path,
+        };
+        self.jump_to_location(JumpLocation::new(Some(path), position));
+    }
+}
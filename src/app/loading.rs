@@ -0,0 +1,220 @@
+use std::path::{Path, PathBuf};
+
+use smash_core::buffer::{Buffer, BufferId};
+use smash_core::encoding::TextEncoding;
+use smash_core::error::EditError;
+use smash_core::loader::{FileLoader, LoadStatus, LoadedFile};
+use smash_core::position::Position;
+use smash_core::safe_write::is_writable;
+use smash_input::Command;
+use smash_syntax::{LanguageId, RegexHighlighter};
+use tracing::{error, info};
+
+use super::{App, JumpLocation};
+
+/// A file being read in the background. The editor shows an empty,
+/// read-only placeholder until it arrives.
+pub(crate) struct PendingOpen {
+    loader: FileLoader,
+    /// Cursor position to jump to once loaded.
+    target: Option<Position>,
+    read_only: bool,
+    /// What was open before, restored if the load fails or is cancelled.
+    previous: (Buffer, Option<String>, Option<RegexHighlighter>),
+}
+
+impl PendingOpen {
+    pub(crate) fn path(&self) -> &Path {
+        self.loader.path()
+    }
+}
+
+// =========================================================================
+// Background file loading
+// =========================================================================
+
+impl App {
+    /// Start loading `path`, showing a placeholder until it is read.
+    /// `target` moves the cursor once the file is open; `read_only`
+    /// marks the loaded buffer read-only.
+    pub(crate) fn open_path(&mut self, path: PathBuf, target: Option<Position>, read_only: bool) {
+        self.cancel_pending_open();
+        let name = file_name(&path);
+        let mut placeholder = Buffer::new(BufferId::next());
+        placeholder.set_read_only(true);
+        let previous = (
+            std::mem::replace(&mut self.buffer, placeholder),
+            self.filename.replace(name),
+            self.highlighter.take(),
+        );
+        info!("loading file: {}", path.display());
+        self.pending_open = Some(PendingOpen {
+            loader: FileLoader::spawn(&path),
+            target,
+            read_only,
+            previous,
+        });
+    }
+
+    /// Check on the background load. Returns `true` if the screen needs
+    /// a redraw (progress changed or the file arrived).
+    pub(crate) fn poll_pending_open(&mut self) -> bool {
+        let Some(pending) = self.pending_open.as_mut() else {
+            return false;
+        };
+        let before = pending.loader.progress();
+        let result = match pending.loader.poll() {
+            LoadStatus::Loading { .. } => return pending.loader.progress() != before,
+            LoadStatus::Done(result) => result,
+        };
+        let Some(pending) = self.pending_open.take() else {
+            return false;
+        };
+        let path = pending.path().to_path_buf();
+        let shown = path.display().to_string();
+        let (text, encoding) = match result {
+            Ok(LoadedFile::Text { text, encoding }) => {
+                self.messages.info(format!("Opened: {}", shown));
+                self.note_recent_file(&path);
+                (text, encoding)
+            }
+            Ok(LoadedFile::Binary { dump, len }) => {
+                self.remember_alternate(&pending.previous.0, &path);
+                self.remember_last_edit(&pending.previous.0);
+                self.show_hex_dump(&path, &dump, len);
+                return true;
+            }
+            Err(EditError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.messages.info(format!("New file: {}", shown));
+                (String::new(), TextEncoding::default())
+            }
+            Err(e) => {
+                self.messages
+                    .error(format!("Failed to open '{}': {}", shown, e));
+                error!("open failed: {}", e);
+                self.restore_previous(pending.previous);
+                return true;
+            }
+        };
+
+        let mut buffer = Buffer::from_loaded(BufferId::next(), &path, &text);
+        buffer.set_encoding(encoding);
+        buffer.set_undo_budget(self.undo_budget);
+        buffer.set_read_only(pending.read_only);
+        if !pending.read_only && !is_writable(&path) {
+            buffer.set_read_only(true);
+            self.messages
+                .info(format!("Opened read-only: {} is not writable", shown));
+        }
+        if let Some(target) = pending.target {
+            let pos = buffer.clamp_position(target);
+            buffer.cursors_mut().primary_mut().set_position(pos);
+        }
+        self.remember_alternate(&pending.previous.0, &path);
+        self.remember_last_edit(&pending.previous.0);
+        self.buffer = buffer;
+        self.highlighter = RegexHighlighter::new(LanguageId::from_path(&path)).ok();
+        info!("opened file: {}", shown);
+        true
+    }
+
+    /// Make the file `previous` was showing the alternate file, unless it
+    /// is the one being opened.
+    fn remember_alternate(&mut self, previous: &Buffer, path: &Path) {
+        if let Some(previous_path) = previous.path().filter(|p| *p != path) {
+            let position = previous.cursors().primary().position();
+            self.alternate_file = Some(JumpLocation::new(
+                Some(previous_path.to_path_buf()),
+                position,
+            ));
+        }
+    }
+
+    /// Keep where the last edit in `previous` was, now that another file
+    /// is open in its place.
+    fn remember_last_edit(&mut self, previous: &Buffer) {
+        if let (Some(path), Some(position)) = (previous.path(), previous.last_edit()) {
+            self.last_edit = Some(JumpLocation::new(Some(path.to_path_buf()), position));
+        }
+    }
+
+    /// Show a binary file as a read-only hex dump. The buffer has no path,
+    /// so nothing can be saved over the file.
+    fn show_hex_dump(&mut self, path: &Path, dump: &str, len: usize) {
+        self.buffer = hex_dump_buffer(dump);
+        self.filename = Some(hex_dump_name(path));
+        self.highlighter = None;
+        self.messages.info(format!(
+            "{} is a binary file ({} bytes); showing a read-only hex dump",
+            file_name(path),
+            len
+        ));
+        info!("opened binary file as hex: {}", path.display());
+    }
+
+    /// Abandon a load in progress and go back to the previous buffer.
+    pub(crate) fn cancel_pending_open(&mut self) {
+        if let Some(pending) = self.pending_open.take() {
+            pending.loader.cancel();
+            info!("cancelled loading {}", pending.path().display());
+            self.messages
+                .info(format!("Cancelled opening {}", pending.path().display()));
+            self.restore_previous(pending.previous);
+        }
+    }
+
+    /// Commands that behave differently while the placeholder is shown.
+    /// Returns `true` if `cmd` was handled.
+    pub(crate) fn handle_command_while_loading(&mut self, cmd: &Command) -> bool {
+        let Some(pending) = self.pending_open.as_ref() else {
+            return false;
+        };
+        match cmd {
+            Command::Close => {
+                self.cancel_pending_open();
+                true
+            }
+            Command::Save | Command::SaveAs | Command::SaveAll | Command::SudoSave => {
+                let msg = format!("Still loading {}", pending.path().display());
+                self.messages.warn(msg);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Status bar note for a load in progress, e.g. ` [loading 42%]`.
+    pub(crate) fn loading_status(&self) -> String {
+        match self.pending_open.as_ref().map(|p| p.loader.progress()) {
+            None => String::new(),
+            Some(Some(fraction)) => format!(" [loading {:.0}%]", fraction * 100.0),
+            Some(None) => " [loading]".to_string(),
+        }
+    }
+
+    fn restore_previous(&mut self, previous: (Buffer, Option<String>, Option<RegexHighlighter>)) {
+        let (buffer, filename, highlighter) = previous;
+        self.buffer = buffer;
+        self.filename = filename;
+        self.highlighter = highlighter;
+    }
+}
+
+/// A read-only buffer, with no path, showing a binary file's hex dump.
+pub(crate) fn hex_dump_buffer(dump: &str) -> Buffer {
+    let mut buffer = Buffer::from_text(BufferId::next(), dump);
+    buffer.set_read_only(true);
+    buffer
+}
+
+/// The name shown for a binary file's hex dump.
+pub(crate) fn hex_dump_name(path: &Path) -> String {
+    format!("{} [hex]", file_name(path))
+}
+
+pub(crate) fn file_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unnamed")
+        .to_string()
+}
@@ -1,11 +1,30 @@
-use smash_core::edit::EditCommand;
+use std::path::PathBuf;
+
+use smash_core::buffer::Rope;
+use smash_core::edit::{EditCommand, EditEvent};
 use smash_core::position::Position;
-use smash_lsp::{DiagnosticSeverity, LspPosition, LspRange, LspServerConfig};
-use tracing::info;
+use smash_lsp::{
+    lsp_position_for, position_from_lsp, DiagnosticSeverity, GotoTarget, LspFeature, LspPosition,
+    LspRange, LspServerConfig, MessageType, PositionEncoding, SaveNotification,
+    TextDocumentContentChangeEvent, TextDocumentSyncKind,
+};
+use smash_syntax::{LanguageId, RegexHighlighter};
+use tracing::{info, warn};
 
 use super::{App, InputMode};
 use crate::lsp_types::{LspCommand, LspEvent};
 
+/// Completion items listed in the popup; the rest are counted in its title.
+const MAX_COMPLETION_ROWS: usize = 10;
+
+/// `text` with its first letter in upper case.
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+}
+
 // =========================================================================
 // LSP integration helpers
 // =========================================================================
@@ -22,7 +41,13 @@ impl App {
         } else {
             std::env::current_dir().unwrap_or_default().join(path)
         };
-        format!("file://{}", abs.to_string_lossy())
+        smash_platform::path_to_uri(&abs)
+    }
+
+    /// Filesystem path of a URI from the server. Anything that is not a
+    /// `file://` URI is taken as a path as-is.
+    pub(crate) fn uri_to_path(uri: &str) -> PathBuf {
+        smash_platform::uri_to_path(uri).unwrap_or_else(|| PathBuf::from(uri))
     }
 
     /// Get the file URI for the current buffer.
@@ -30,6 +55,18 @@ impl App {
         self.buffer.path().map(Self::path_to_uri)
     }
 
+    /// Whether `uri` names the current buffer's file. Servers may spell a
+    /// URI differently from us (e.g. `%3A` for a drive colon), so the
+    /// paths are compared.
+    pub(crate) fn is_current_uri(&self, uri: &str) -> bool {
+        match self.current_uri() {
+            Some(current) => {
+                current == uri || Self::uri_to_path(&current) == Self::uri_to_path(uri)
+            }
+            None => false,
+        }
+    }
+
     /// Start an LSP server for the current file's language, if configured.
     pub(crate) fn start_lsp_for_current_file(&mut self) {
         if !self.lsp_enabled {
@@ -40,21 +77,98 @@ impl App {
             None => return,
         };
 
-        if let Some(entry) = self.lsp_server_configs.get(&lang_id) {
+        if let Some(entry) = self.lsp_server_configs.get(&lang_id).cloned() {
             let root_uri = std::env::current_dir().ok().map(|p| Self::path_to_uri(&p));
             let config = LspServerConfig {
                 command: entry.command.clone(),
                 args: entry.args.clone(),
                 language_id: lang_id.clone(),
                 root_uri,
+                disabled_features: self.disabled_lsp_features(&entry),
             };
             let _ = self.lsp_cmd_tx.try_send(LspCommand::StartServer(config));
+            self.startup_profile
+                .lsp_requested(std::time::Instant::now());
             info!(language = %lang_id, "requesting LSP server start");
         }
     }
 
+    /// Features switched off for the server `entry`: `lsp.disable`
+    /// and its own `disable`. Unknown names are reported and skipped.
+    fn disabled_lsp_features(&mut self, entry: &smash_config::LspServerEntry) -> Vec<LspFeature> {
+        let names: Vec<String> = self
+            .lsp_disable
+            .iter()
+            .chain(&entry.disable)
+            .cloned()
+            .collect();
+        let mut features = Vec::new();
+        for name in names {
+            match LspFeature::from_name(&name) {
+                Some(feature) if !features.contains(&feature) => features.push(feature),
+                Some(_) => {}
+                None => {
+                    warn!("unknown LSP feature '{}' in disable list", name);
+                    self.messages.warn(format!(
+                        "Unknown LSP feature '{}' in lsp disable list",
+                        name
+                    ));
+                }
+            }
+        }
+        features
+    }
+
+    /// Follow the buffer to a new path (Save As, a rename): re-detect its
+    /// language, swap the highlighter, and reopen the document on the
+    /// server under the new URI. A new language gets its own server.
+    pub(crate) fn reattach_language(&mut self, old_uri: Option<String>) {
+        let Some(path) = self.buffer.path().map(|p| p.to_path_buf()) else {
+            return;
+        };
+        let lang = LanguageId::from_path(&path);
+        let lang_id = lang.as_str().to_string();
+        self.highlighter = RegexHighlighter::new(lang).ok();
+        if self.lsp_server_started {
+            if let Some(uri) = old_uri {
+                let _ = self.lsp_cmd_tx.try_send(LspCommand::DidClose { uri });
+            }
+        }
+        self.current_diagnostics.clear();
+        if self.language_id.as_deref() == Some(lang_id.as_str()) {
+            self.lsp_did_open();
+            return;
+        }
+        info!(language = %lang_id, "language changed to {}", lang_id);
+        self.language_id = Some(lang_id);
+        // The running server, if any, speaks the old language.
+        self.lsp_server_started = false;
+        self.start_lsp_for_current_file();
+    }
+
+    /// `pos` in the buffer with its column counted the way the server
+    /// counts.
+    pub(crate) fn lsp_position(&self, pos: Position) -> LspPosition {
+        lsp_position_for(self.buffer.text(), pos, self.lsp_position_encoding)
+    }
+
+    /// The buffer position of a position the server sent.
+    pub(crate) fn buffer_position(&self, pos: LspPosition) -> Position {
+        position_from_lsp(self.buffer.text(), pos, self.lsp_position_encoding)
+    }
+
+    /// Where `pos` in the document at `uri` is. Columns in other files
+    /// are taken as chars, as their text is not at hand.
+    pub(crate) fn location_position(&self, uri: &str, pos: LspPosition) -> Position {
+        if self.is_current_uri(uri) {
+            self.buffer_position(pos)
+        } else {
+            Position::new(pos.line as usize, pos.character as usize)
+        }
+    }
+
     /// Send didOpen notification for the current buffer.
-    pub(crate) fn lsp_did_open(&self) {
+    pub(crate) fn lsp_did_open(&mut self) {
         if !self.lsp_server_started {
             return;
         }
@@ -66,6 +180,8 @@ impl App {
             Some(l) => l.clone(),
             None => return,
         };
+        self.pending_edits.clear();
+        self.lsp_synced_text = Some(self.buffer.text().clone());
         let text = self.buffer.text().to_string();
         let _ = self.lsp_cmd_tx.try_send(LspCommand::DidOpen {
             uri,
@@ -75,6 +191,11 @@ impl App {
     }
 
     /// Send didChange notification after an edit.
+    ///
+    /// Incremental-sync servers receive the ranged edits recorded since the
+    /// last notification. When none were recorded (e.g. the buffer was
+    /// replaced wholesale) they get a line diff against the last text sent
+    /// instead. Everyone else gets the full document text.
     pub(crate) fn lsp_did_change(&mut self) {
         if !self.lsp_server_started {
             return;
@@ -83,22 +204,66 @@ impl App {
             Some(u) => u,
             None => return,
         };
+        let encoding = self.lsp_position_encoding;
+        let changes = match (self.lsp_sync_kind, self.lsp_synced_text.as_ref()) {
+            (TextDocumentSyncKind::None, _) => {
+                self.pending_edits.clear();
+                return;
+            }
+            (TextDocumentSyncKind::Incremental, Some(synced)) if self.pending_edits.is_empty() => {
+                match smash_core::diff::line_change(synced, self.buffer.text()) {
+                    Some(change) => vec![TextDocumentContentChangeEvent::ranged(
+                        LspRange::new(
+                            lsp_position_for(synced, change.range.start, encoding),
+                            lsp_position_for(synced, change.range.end, encoding),
+                        ),
+                        change.text,
+                    )],
+                    None => return,
+                }
+            }
+            (TextDocumentSyncKind::Incremental, Some(synced)) => {
+                ranged_changes(synced, &self.pending_edits, encoding)
+            }
+            _ => self.full_document_change(),
+        };
         self.document_version += 1;
-        let text = self.buffer.text().to_string();
-        let _ = self.lsp_cmd_tx.try_send(LspCommand::DidChange {
+        let sent = self.lsp_cmd_tx.try_send(LspCommand::DidChange {
             uri,
             version: self.document_version,
-            text,
+            changes,
         });
+        // Unsent edits are kept so the server never misses one.
+        if sent.is_ok() {
+            self.pending_edits.clear();
+            self.lsp_synced_text = Some(self.buffer.text().clone());
+        }
+    }
+
+    /// A change replacing the whole document; drops any recorded edits.
+    fn full_document_change(&mut self) -> Vec<TextDocumentContentChangeEvent> {
+        self.pending_edits.clear();
+        vec![TextDocumentContentChangeEvent::full(
+            self.buffer.text().to_string(),
+        )]
     }
 
-    /// Send didSave notification.
+    /// Remember edit events for the next incremental didChange.
+    pub(crate) fn record_edit_events(&mut self, events: &[EditEvent]) {
+        if self.lsp_server_started && self.lsp_sync_kind == TextDocumentSyncKind::Incremental {
+            self.pending_edits.extend_from_slice(events);
+        }
+    }
+
+    /// Send didSave notification, with the text if the server wants it.
     pub(crate) fn lsp_did_save(&self) {
-        if !self.lsp_server_started {
+        if !self.lsp_server_started || self.lsp_save == SaveNotification::None {
             return;
         }
         if let Some(uri) = self.current_uri() {
-            let _ = self.lsp_cmd_tx.try_send(LspCommand::DidSave { uri });
+            let text = (self.lsp_save == SaveNotification::IncludeText)
+                .then(|| self.buffer.text().to_string());
+            let _ = self.lsp_cmd_tx.try_send(LspCommand::DidSave { uri, text });
         }
     }
 
@@ -110,15 +275,40 @@ impl App {
         }
         if let Some(uri) = self.current_uri() {
             let pos = self.buffer.cursors().primary().position();
+            self.hover_request = None;
             let _ = self.lsp_cmd_tx.try_send(LspCommand::Hover {
                 uri,
-                position: LspPosition::from(pos),
+                position: self.lsp_position(pos),
             });
         }
     }
 
-    /// Request go-to-definition at the cursor position.
-    pub(crate) fn lsp_goto_definition(&mut self) {
+    /// Ask for hover information at the cursor without being asked to:
+    /// nothing is said when there is none, and the answer is dropped if
+    /// the cursor has moved on by the time it arrives.
+    pub(crate) fn lsp_hover_on_idle(&mut self) {
+        let pos = self.buffer.cursors().primary().position();
+        if !self.lsp_server_started
+            || self.input_mode != InputMode::Normal
+            || self.popup.is_some()
+            || self.idle_hovered == Some(pos)
+        {
+            return;
+        }
+        let Some(uri) = self.current_uri() else {
+            return;
+        };
+        self.hover_request = Some(pos);
+        self.idle_hovered = Some(pos);
+        let _ = self.lsp_cmd_tx.try_send(LspCommand::Hover {
+            uri,
+            position: self.lsp_position(pos),
+        });
+    }
+
+    /// Request go-to-definition at the cursor position, or `target` in
+    /// general. The jump is recorded so JumpBack returns here.
+    pub(crate) fn lsp_goto_definition(&mut self, target: GotoTarget) {
         if !self.lsp_server_started {
             self.messages.warn("No LSP server running");
             return;
@@ -127,7 +317,8 @@ impl App {
             let pos = self.buffer.cursors().primary().position();
             let _ = self.lsp_cmd_tx.try_send(LspCommand::GotoDefinition {
                 uri,
-                position: LspPosition::from(pos),
+                position: self.lsp_position(pos),
+                target,
             });
         }
     }
@@ -142,7 +333,7 @@ impl App {
             let pos = self.buffer.cursors().primary().position();
             let _ = self.lsp_cmd_tx.try_send(LspCommand::FindReferences {
                 uri,
-                position: LspPosition::from(pos),
+                position: self.lsp_position(pos),
             });
         }
     }
@@ -155,9 +346,10 @@ impl App {
         }
         if let Some(uri) = self.current_uri() {
             let pos = self.buffer.cursors().primary().position();
+            self.completion_request = None;
             let _ = self.lsp_cmd_tx.try_send(LspCommand::Completion {
                 uri,
-                position: LspPosition::from(pos),
+                position: self.lsp_position(pos),
             });
         }
     }
@@ -173,6 +365,39 @@ impl App {
         }
     }
 
+    /// Request formatting of the primary selection only.
+    pub(crate) fn lsp_format_selection(&mut self) {
+        if !self.lsp_server_started {
+            self.messages.warn("No LSP server running");
+            return;
+        }
+        let Some(range) = self
+            .buffer
+            .cursors()
+            .primary()
+            .selection_range()
+            .filter(|r| !r.is_empty())
+        else {
+            self.messages.warn("Select the text to format first");
+            return;
+        };
+        if !self.lsp_range_formatting {
+            if self.lsp_formatting {
+                self.messages
+                    .warn("Server cannot format a selection; use Format document instead");
+            } else {
+                self.messages.warn("Server does not support formatting");
+            }
+            return;
+        }
+        if let Some(uri) = self.current_uri() {
+            let range = LspRange::new(self.lsp_position(range.start), self.lsp_position(range.end));
+            let _ = self
+                .lsp_cmd_tx
+                .try_send(LspCommand::RangeFormat { uri, range });
+        }
+    }
+
     /// Request code actions at the cursor position.
     pub(crate) fn lsp_code_action(&mut self) {
         if !self.lsp_server_started {
@@ -181,10 +406,13 @@ impl App {
         }
         if let Some(uri) = self.current_uri() {
             let pos = self.buffer.cursors().primary().position();
-            let range = LspRange::new(LspPosition::from(pos), LspPosition::from(pos));
-            let _ = self
-                .lsp_cmd_tx
-                .try_send(LspCommand::CodeAction { uri, range });
+            let range = LspRange::new(self.lsp_position(pos), self.lsp_position(pos));
+            let _ = self.lsp_cmd_tx.try_send(LspCommand::CodeAction {
+                uri,
+                range,
+                diagnostics: Vec::new(),
+                only: Vec::new(),
+            });
         }
     }
 
@@ -198,15 +426,8 @@ impl App {
             self.diagnostic_index = 0;
         }
         let diag = &self.current_diagnostics[self.diagnostic_index];
-        let line = diag.range.start.line as usize;
-        let col = diag.range.start.character as usize;
-        let severity = match diag.severity {
-            Some(DiagnosticSeverity::Error) => "error",
-            Some(DiagnosticSeverity::Warning) => "warning",
-            Some(DiagnosticSeverity::Information) => "info",
-            Some(DiagnosticSeverity::Hint) => "hint",
-            None => "diagnostic",
-        };
+        let target = self.buffer_position(diag.range.start);
+        let severity = severity_label(diag.severity);
         let msg = format!(
             "[{}/{}] {}: {}",
             self.diagnostic_index + 1,
@@ -214,10 +435,7 @@ impl App {
             severity,
             diag.message
         );
-        self.buffer
-            .cursors_mut()
-            .primary_mut()
-            .set_position(Position::new(line, col));
+        self.buffer.cursors_mut().primary_mut().set_position(target);
         self.messages.info(msg);
         self.diagnostic_index += 1;
     }
@@ -233,15 +451,8 @@ impl App {
         }
         self.diagnostic_index -= 1;
         let diag = &self.current_diagnostics[self.diagnostic_index];
-        let line = diag.range.start.line as usize;
-        let col = diag.range.start.character as usize;
-        let severity = match diag.severity {
-            Some(DiagnosticSeverity::Error) => "error",
-            Some(DiagnosticSeverity::Warning) => "warning",
-            Some(DiagnosticSeverity::Information) => "info",
-            Some(DiagnosticSeverity::Hint) => "hint",
-            None => "diagnostic",
-        };
+        let target = self.buffer_position(diag.range.start);
+        let severity = severity_label(diag.severity);
         let msg = format!(
             "[{}/{}] {}: {}",
             self.diagnostic_index + 1,
@@ -249,13 +460,156 @@ impl App {
             severity,
             diag.message
         );
-        self.buffer
-            .cursors_mut()
-            .primary_mut()
-            .set_position(Position::new(line, col));
+        self.buffer.cursors_mut().primary_mut().set_position(target);
         self.messages.info(msg);
     }
 
+    /// Show every diagnostic overlapping the cursor line in a popup, with
+    /// source, code, documentation link, and related locations.
+    pub(crate) fn show_diagnostic_at_cursor(&mut self) {
+        let line = self.buffer.cursors().primary().position().line as u32;
+        let diags: Vec<&smash_lsp::Diagnostic> = self
+            .current_diagnostics
+            .iter()
+            .filter(|d| d.range.start.line <= line && d.range.end.line >= line)
+            .collect();
+        if diags.is_empty() {
+            self.messages.info("No diagnostics on this line");
+            return;
+        }
+
+        let mut lines = Vec::new();
+        for (i, diag) in diags.iter().enumerate() {
+            if i > 0 {
+                lines.push(String::new());
+            }
+            let mut message = diag.message.lines();
+            lines.push(format!(
+                "{}: {}",
+                severity_label(diag.severity),
+                message.next().unwrap_or_default()
+            ));
+            lines.extend(message.map(|l| format!("  {}", l)));
+
+            let mut origin = Vec::new();
+            if let Some(source) = &diag.source {
+                origin.push(source.clone());
+            }
+            if let Some(code) = &diag.code {
+                origin.push(format!("[{}]", code));
+            }
+            if !origin.is_empty() {
+                lines.push(format!("  {}", origin.join(" ")));
+            }
+            if let Some(desc) = &diag.code_description {
+                lines.push(format!("  {}", desc.href));
+            }
+            for related in &diag.related_information {
+                let path = Self::uri_to_path(&related.location.uri);
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string());
+                lines.push(format!(
+                    "  {}:{}:{}: {}",
+                    name,
+                    related.location.range.start.line + 1,
+                    related.location.range.start.character + 1,
+                    related.message
+                ));
+            }
+        }
+        let title = if diags.len() == 1 {
+            format!("Line {}", line + 1)
+        } else {
+            format!("Line {} ({} diagnostics)", line + 1, diags.len())
+        };
+        let diags: Vec<smash_lsp::Diagnostic> = diags.into_iter().cloned().collect();
+        self.popup = Some(smash_tui::Popup::new(title, lines));
+        self.request_quick_fixes(diags);
+    }
+
+    /// Ask the server for quick fixes for the diagnostics shown in the
+    /// popup. The result is appended to the popup as a numbered list.
+    fn request_quick_fixes(&mut self, diagnostics: Vec<smash_lsp::Diagnostic>) {
+        self.quick_fixes.clear();
+        self.quick_fix_diagnostics = None;
+        if !self.lsp_server_started {
+            return;
+        }
+        let Some(uri) = self.current_uri() else {
+            return;
+        };
+        let start = diagnostics
+            .iter()
+            .map(|d| d.range.start)
+            .min_by_key(|p| (p.line, p.character));
+        let end = diagnostics
+            .iter()
+            .map(|d| d.range.end)
+            .max_by_key(|p| (p.line, p.character));
+        let (Some(start), Some(end)) = (start, end) else {
+            return;
+        };
+        let sent = self.lsp_cmd_tx.try_send(LspCommand::CodeAction {
+            uri,
+            range: LspRange::new(start, end),
+            diagnostics: diagnostics.clone(),
+            only: vec!["quickfix".to_string()],
+        });
+        if sent.is_ok() {
+            self.quick_fix_diagnostics = Some(diagnostics);
+        }
+    }
+
+    /// List the quick fixes matching the popup's diagnostics in the popup.
+    fn handle_quick_fix_result(&mut self, actions: Vec<smash_lsp::CodeAction>) {
+        let Some(diagnostics) = self.quick_fix_diagnostics.take() else {
+            return;
+        };
+        let Some(popup) = self.popup.as_mut() else {
+            return;
+        };
+        let mut fixes: Vec<smash_lsp::CodeAction> = actions
+            .into_iter()
+            .filter(|a| a.is_quickfix())
+            .filter(|a| {
+                a.diagnostics.is_empty()
+                    || a.diagnostics.iter().any(|d| {
+                        diagnostics
+                            .iter()
+                            .any(|shown| shown.range == d.range && shown.message == d.message)
+                    })
+            })
+            .collect();
+        // Preferred fixes first, so `1` is the server's best guess.
+        fixes.sort_by_key(|a| !a.is_preferred);
+        fixes.truncate(9);
+        if fixes.is_empty() {
+            return;
+        }
+        popup.lines.push(String::new());
+        popup
+            .lines
+            .push("Quick fixes (press a number to apply):".to_string());
+        for (i, fix) in fixes.iter().enumerate() {
+            let star = if fix.is_preferred { " *" } else { "" };
+            popup
+                .lines
+                .push(format!("  {}. {}{}", i + 1, fix.title, star));
+        }
+        self.quick_fixes = fixes;
+    }
+
+    /// Apply the `index`-th quick fix listed in the diagnostic popup.
+    pub(crate) fn apply_quick_fix(&mut self, index: usize) {
+        let Some(fix) = self.quick_fixes.get(index).cloned() else {
+            return;
+        };
+        self.quick_fixes.clear();
+        self.apply_code_action(fix);
+    }
+
     /// Confirm LSP rename from prompt.
     pub(crate) fn confirm_lsp_rename(&mut self, new_name: &str) {
         let new_name = new_name.trim();
@@ -266,7 +620,7 @@ impl App {
         }
         if let Some(_uri) = self.current_uri() {
             let pos = self.buffer.cursors().primary().position();
-            let lsp_pos = LspPosition::from(pos);
+            let lsp_pos = self.lsp_position(pos);
             self.messages.info(format!(
                 "Rename requested: '{}' at {}:{}",
                 new_name,
@@ -290,34 +644,88 @@ impl App {
     /// Handle LSP events received from the async task.
     pub(crate) fn handle_lsp_event(&mut self, event: LspEvent) {
         match event {
-            LspEvent::ServerStarted(lang) => {
+            LspEvent::ServerStarted {
+                language: lang,
+                sync,
+                save,
+                will_save_wait_until,
+                formatting,
+                range_formatting,
+                inlay_hints,
+                completion_triggers,
+                signature_triggers,
+                position_encoding,
+            } => {
+                // A server for another open file's language says nothing
+                // about this buffer.
+                if self.language_id.as_deref() != Some(lang.as_str()) {
+                    info!(language = %lang, "LSP server started");
+                    return;
+                }
                 self.lsp_server_started = true;
+                self.startup_profile.lsp_started(std::time::Instant::now());
+                self.lsp_sync_kind = sync;
+                self.lsp_save = save;
+                self.lsp_will_save_wait_until = will_save_wait_until;
+                self.lsp_formatting = formatting;
+                self.lsp_range_formatting = range_formatting;
+                self.lsp_inlay_hints = inlay_hints;
+                self.lsp_position_encoding = position_encoding;
+                self.completion_triggers = completion_triggers;
+                self.signature_triggers = signature_triggers;
                 self.messages
                     .info(format!("LSP server started for {}", lang));
                 info!(language = %lang, "LSP server started");
                 self.lsp_did_open();
             }
             LspEvent::HoverResult(text) => self.handle_hover_result(text),
-            LspEvent::GotoDefinitionResult(locations) => {
-                self.handle_goto_definition_result(locations);
+            LspEvent::GotoDefinitionResult { target, locations } => {
+                self.handle_goto_definition_result(target, locations);
             }
             LspEvent::ReferencesResult(locations) => self.handle_references_result(locations),
+            LspEvent::DocumentSymbolsResult(symbols) => {
+                self.handle_document_symbols_result(symbols);
+            }
+            LspEvent::CallHierarchyPrepared { direction, items } => {
+                self.handle_call_hierarchy_prepared(direction, items);
+            }
+            LspEvent::CallHierarchyCalls { node, items } => {
+                self.handle_call_hierarchy_calls(node, items);
+            }
+            LspEvent::WorkspaceSymbolsResult { query, symbols } => {
+                self.handle_workspace_symbols_result(query, symbols);
+            }
+            LspEvent::SignatureHelpResult(help) => self.handle_signature_help_result(help),
             LspEvent::CompletionResult(items) => self.handle_completion_result(items),
             LspEvent::FormatResult(edits) => self.handle_format_result(edits),
-            LspEvent::CodeActionResult(actions) => {
-                if actions.is_empty() {
-                    self.messages.info("No code actions available");
-                } else {
-                    let titles: Vec<&str> =
-                        actions.iter().take(5).map(|a| a.title.as_str()).collect();
-                    self.messages
-                        .info(format!("Code actions: {}", titles.join(", ")));
-                    // TODO: Allow selecting and applying code actions
-                }
+            LspEvent::WillSaveEdits { uri, edits } => self.finish_pending_save(&uri, edits),
+            LspEvent::CodeActionResult(actions) if self.quick_fix_diagnostics.is_some() => {
+                self.handle_quick_fix_result(actions);
+            }
+            LspEvent::CodeActionResult(actions) => self.handle_code_action_result(actions),
+            LspEvent::ApplyEdit {
+                label,
+                edit,
+                request,
+            } => self.handle_server_apply_edit(label, edit, request),
+            LspEvent::CommandFinished { command, result } => {
+                self.handle_command_finished(command, result)
             }
             LspEvent::DiagnosticsUpdated { uri, diagnostics } => {
                 self.handle_diagnostics_updated(uri, diagnostics);
             }
+            LspEvent::Progress(progress) => {
+                self.lsp_progress = progress;
+                self.lsp_progress_ticks = self.lsp_progress_ticks.wrapping_add(1);
+            }
+            LspEvent::ServerMessage(message) => {
+                let text = format!("LSP: {}", message.text);
+                match message.kind {
+                    MessageType::Error => self.messages.error(text),
+                    MessageType::Warning => self.messages.warn(text),
+                    MessageType::Info | MessageType::Log => self.messages.info(text),
+                }
+            }
             LspEvent::Error(msg) => {
                 self.messages.error(format!("LSP: {}", msg));
                 tracing::error!(msg = %msg, "LSP error");
@@ -329,6 +737,17 @@ impl App {
     }
 
     fn handle_hover_result(&mut self, text: Option<String>) {
+        if let Some(requested) = self.hover_request.take() {
+            let current = self.input_mode == InputMode::Normal
+                && self.popup.is_none()
+                && self.buffer.cursors().primary().position() == requested;
+            if let (true, Some(text)) = (current, text) {
+                let lines = text.lines().map(str::to_string).collect();
+                self.show_cursor_popup(smash_tui::Popup::new("Hover", lines));
+                self.hover_text = Some(text);
+            }
+            return;
+        }
         if let Some(text) = text {
             let display = if text.len() > 200 {
                 format!("{}...", &text[..200])
@@ -336,6 +755,8 @@ impl App {
                 text.clone()
             };
             let display = display.replace('\n', " | ");
+            let lines = text.lines().map(str::to_string).collect();
+            self.show_cursor_popup(smash_tui::Popup::new("Hover", lines));
             self.hover_text = Some(text);
             self.messages.info(format!("Hover: {}", display));
         } else {
@@ -344,9 +765,19 @@ impl App {
         }
     }
 
-    fn handle_goto_definition_result(&mut self, locations: Vec<smash_lsp::Location>) {
+    fn handle_goto_definition_result(
+        &mut self,
+        target: GotoTarget,
+        locations: Vec<smash_lsp::Location>,
+    ) {
         if locations.is_empty() {
-            self.messages.info("No definition found");
+            self.messages.info(format!("No {} found", target.label()));
+            return;
+        }
+        let label = capitalize(target.label());
+        // Several, e.g. a trait's implementations, are listed to pick from.
+        if locations.len() > 1 {
+            self.show_locations(&format!("{}s", label), &locations);
             return;
         }
 
@@ -354,32 +785,24 @@ impl App {
         self.push_jump();
 
         let loc = &locations[0];
-        let line = loc.range.start.line as usize;
-        let col = loc.range.start.character as usize;
+        let position = self.location_position(&loc.uri, loc.range.start);
 
         // Check if it's a different file
-        let current_uri = self.current_uri().unwrap_or_default();
-        if loc.uri != current_uri {
-            let path = loc.uri.strip_prefix("file://").unwrap_or(&loc.uri);
-            self.confirm_open(path);
-        }
-
-        self.buffer
-            .cursors_mut()
-            .primary_mut()
-            .set_position(Position::new(line, col));
-
-        if locations.len() > 1 {
-            self.messages.info(format!(
-                "Definition: {}:{} ({} locations)",
-                line + 1,
-                col + 1,
-                locations.len()
-            ));
+        if !self.is_current_uri(&loc.uri) {
+            self.open_path(Self::uri_to_path(&loc.uri), Some(position), false);
         } else {
-            self.messages
-                .info(format!("Definition: {}:{}", line + 1, col + 1));
+            self.buffer
+                .cursors_mut()
+                .primary_mut()
+                .set_position(position);
         }
+
+        self.messages.info(format!(
+            "{}: {}:{}",
+            label,
+            position.line + 1,
+            position.col + 1
+        ));
     }
 
     fn handle_references_result(&mut self, locations: Vec<smash_lsp::Location>) {
@@ -387,90 +810,110 @@ impl App {
             self.messages.info("No references found");
             return;
         }
+        let count = locations.len();
+        if count > 1 {
+            self.show_locations("References", &locations);
+            self.messages.info(format!("Found {} references", count));
+            return;
+        }
 
         // Save current position before jumping
         self.push_jump();
 
-        let count = locations.len();
         let loc = &locations[0];
-        let line = loc.range.start.line as usize;
-        let col = loc.range.start.character as usize;
+        let target = self.location_position(&loc.uri, loc.range.start);
 
         // Check if it's a different file
-        let current_uri = self.current_uri().unwrap_or_default();
-        if loc.uri != current_uri {
-            let path = loc.uri.strip_prefix("file://").unwrap_or(&loc.uri);
-            self.confirm_open(path);
+        if !self.is_current_uri(&loc.uri) {
+            self.open_path(Self::uri_to_path(&loc.uri), Some(target), false);
+        } else {
+            self.buffer.cursors_mut().primary_mut().set_position(target);
         }
-
-        self.buffer
-            .cursors_mut()
-            .primary_mut()
-            .set_position(Position::new(line, col));
-        self.messages.info(format!("Found {} reference(s)", count));
+        self.messages.info("Found 1 reference");
     }
 
     fn handle_completion_result(&mut self, items: Vec<smash_lsp::CompletionItem>) {
+        // Answers to requests made while typing are shown quietly, and
+        // only if the cursor is still where they were asked for.
+        let auto = self.completion_request.take();
+        if auto.is_some_and(|pos| !self.auto_completion_current(pos)) {
+            return;
+        }
         if items.is_empty() {
-            self.messages.info("No completions");
+            if auto.is_none() {
+                self.messages.info("No completions");
+            }
             self.completion_items.clear();
             return;
         }
         let count = items.len();
-        let preview: Vec<&str> = items.iter().take(5).map(|i| i.label.as_str()).collect();
-        self.messages.info(format!(
-            "Completions ({}): {}{}",
-            count,
-            preview.join(", "),
-            if count > 5 { ", ..." } else { "" }
+        if auto.is_none() {
+            let preview: Vec<&str> = items.iter().take(5).map(|i| i.label.as_str()).collect();
+            self.messages.info(format!(
+                "Completions ({}): {}{}",
+                count,
+                preview.join(", "),
+                if count > 5 { ", ..." } else { "" }
+            ));
+        }
+        let lines = items
+            .iter()
+            .take(MAX_COMPLETION_ROWS)
+            .map(|i| match &i.detail {
+                Some(detail) => format!("{}  {}", i.label, detail),
+                None => i.label.clone(),
+            })
+            .collect();
+        self.show_cursor_popup(smash_tui::Popup::new(
+            format!("Completions ({})", count),
+            lines,
         ));
         self.completion_items = items;
         self.completion_index = 0;
     }
 
+    /// Open `popup` at the cursor in place of whatever was showing.
+    pub(super) fn show_cursor_popup(&mut self, popup: smash_tui::Popup) {
+        self.quick_fixes.clear();
+        self.quick_fix_diagnostics = None;
+        self.popup = Some(popup);
+    }
+
     fn handle_format_result(&mut self, edits: Vec<smash_lsp::TextEdit>) {
         if edits.is_empty() {
             self.messages.info("No formatting changes");
             return;
         }
-        // Apply text edits in reverse order to preserve positions
-        let mut edits = edits;
-        edits.sort_by(|a, b| {
-            b.range
-                .start
-                .line
-                .cmp(&a.range.start.line)
-                .then(b.range.start.character.cmp(&a.range.start.character))
-        });
+        let applied = self.apply_text_edits(edits);
+        self.messages
+            .info(format!("Applied {} formatting edit(s)", applied));
+        self.lsp_did_change();
+    }
+
+    /// Apply LSP text edits to the current buffer, returning how many
+    /// succeeded. Edits are applied last-first so earlier positions stay
+    /// valid, and undo as one step.
+    pub(super) fn apply_text_edits(&mut self, edits: Vec<smash_lsp::TextEdit>) -> usize {
+        self.buffer.begin_undo_group();
         let mut applied = 0;
-        for edit in &edits {
-            let start = Position::new(
-                edit.range.start.line as usize,
-                edit.range.start.character as usize,
-            );
-            let end = Position::new(
-                edit.range.end.line as usize,
-                edit.range.end.character as usize,
-            );
-            let range = smash_core::position::Range::new(start, end);
+        let encoding = self.lsp_position_encoding;
+        for (range, text) in edits_last_first(edits, &self.buffer.text().clone(), encoding) {
             let delete = EditCommand::Delete { range };
-            if self.buffer.apply_edit(delete).is_ok() {
+            if self.apply_edit(delete).is_ok() {
                 let insert = EditCommand::Insert {
-                    pos: start,
-                    text: edit.new_text.clone(),
+                    pos: range.start,
+                    text,
                 };
-                let _ = self.buffer.apply_edit(insert);
+                let _ = self.apply_edit(insert);
                 applied += 1;
             }
         }
-        self.messages
-            .info(format!("Applied {} formatting edit(s)", applied));
-        self.lsp_did_change();
+        self.buffer.end_undo_group();
+        applied
     }
 
     fn handle_diagnostics_updated(&mut self, uri: String, diagnostics: Vec<smash_lsp::Diagnostic>) {
-        let current_uri = self.current_uri().unwrap_or_default();
-        if uri == current_uri {
+        if self.is_current_uri(&uri) {
             let count = diagnostics.len();
             let errors = diagnostics
                 .iter()
@@ -491,3 +934,65 @@ impl App {
         }
     }
 }
+
+/// Ranged changes for `edits`, made in turn to `synced`, with columns
+/// counted in `encoding`. Each edit's range is worked out against the
+/// text as the edits before it left it.
+fn ranged_changes(
+    synced: &Rope,
+    edits: &[EditEvent],
+    encoding: PositionEncoding,
+) -> Vec<TextDocumentContentChangeEvent> {
+    let mut text = synced.clone();
+    let char_idx = |text: &Rope, pos: Position| {
+        let line_start = text.try_line_to_char(pos.line).unwrap_or(text.len_chars());
+        (line_start + pos.col).min(text.len_chars())
+    };
+    edits
+        .iter()
+        .map(|evt| {
+            let change = TextDocumentContentChangeEvent::from_edit(evt, &text, encoding);
+            let start = char_idx(&text, evt.start_position);
+            let end = char_idx(&text, evt.old_end_position).max(start);
+            text.remove(start..end);
+            text.insert(start, &evt.new_text);
+            change
+        })
+        .collect()
+}
+
+/// `edits` as buffer ranges and replacement text, last in the document
+/// first, so applying them in turn leaves the earlier ranges valid.
+/// Their columns count `encoding` code units of `text`.
+pub(super) fn edits_last_first(
+    mut edits: Vec<smash_lsp::TextEdit>,
+    text: &Rope,
+    encoding: PositionEncoding,
+) -> Vec<(smash_core::position::Range, String)> {
+    edits.sort_by(|a, b| {
+        b.range
+            .start
+            .line
+            .cmp(&a.range.start.line)
+            .then(b.range.start.character.cmp(&a.range.start.character))
+    });
+    edits
+        .into_iter()
+        .map(|edit| {
+            let start = position_from_lsp(text, edit.range.start, encoding);
+            let end = position_from_lsp(text, edit.range.end, encoding);
+            (smash_core::position::Range::new(start, end), edit.new_text)
+        })
+        .collect()
+}
+
+/// Short lower-case name for a diagnostic severity.
+fn severity_label(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::Error) => "error",
+        Some(DiagnosticSeverity::Warning) => "warning",
+        Some(DiagnosticSeverity::Information) => "info",
+        Some(DiagnosticSeverity::Hint) => "hint",
+        None => "diagnostic",
+    }
+}
@@ -0,0 +1,105 @@
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use smash_tui::{Picker, PickerItem};
+
+use super::{App, InputMode, JumpLocation};
+
+/// The marks picker and the marks behind its items.
+pub(crate) struct MarkPicker {
+    pub(crate) picker: Picker,
+    /// Same order as the picker items.
+    marks: Vec<JumpLocation>,
+}
+
+/// Line `line` of the file at `path`, if it can be read.
+fn file_line(path: &Path, line: usize) -> Option<String> {
+    let file = std::fs::File::open(path).ok()?;
+    BufReader::new(file).lines().nth(line)?.ok()
+}
+
+// =========================================================================
+// Marks
+// =========================================================================
+
+impl App {
+    /// Remember the cursor position in this file as mark `name`,
+    /// replacing an earlier mark of that name.
+    pub(crate) fn cmd_set_mark(&mut self, name: char) {
+        if !name.is_ascii_alphanumeric() {
+            self.messages.warn("Marks are named by a letter or digit");
+            return;
+        }
+        let Some(path) = self.buffer.path().map(Path::to_path_buf) else {
+            self.messages.warn("Save the file before setting a mark");
+            return;
+        };
+        let position = self.buffer.cursors().primary().position();
+        self.marks
+            .insert(name, JumpLocation::new(Some(path), position));
+        self.messages
+            .info(format!("Mark '{}' set at line {}", name, position.line + 1));
+    }
+
+    /// Jump to mark `name`, opening its file if needed.
+    pub(crate) fn cmd_goto_mark(&mut self, name: char) {
+        let Some(target) = self.marks.get(&name).cloned() else {
+            self.messages.info(format!("Mark '{}' is not set", name));
+            return;
+        };
+        self.jump_to_location(target);
+    }
+
+    /// The text of the marked line, trimmed, to tell marks apart.
+    fn mark_context(&self, mark: &JumpLocation) -> String {
+        let line = mark.position.line;
+        let text = match mark.path.as_deref() {
+            Some(path) if Some(path) == self.buffer.path() => {
+                self.buffer.line(line).map(|l| l.to_string())
+            }
+            Some(path) => file_line(path, line),
+            None => None,
+        };
+        text.map(|t| t.trim().to_string()).unwrap_or_default()
+    }
+
+    /// Open a picker of the marks in name order, each with its file,
+    /// line and the text there.
+    pub(crate) fn cmd_list_marks(&mut self) {
+        if self.marks.is_empty() {
+            self.messages.info("No marks set");
+            return;
+        }
+        let mut items = Vec::new();
+        let mut marks = Vec::new();
+        for (name, mark) in &self.marks {
+            let file = mark
+                .path
+                .as_deref()
+                .and_then(Path::file_name)
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let label = format!("{}  {}:{}", name, file, mark.position.line + 1);
+            items.push(PickerItem::new(label, self.mark_context(mark)));
+            marks.push(mark.clone());
+        }
+        self.mark_picker = Some(MarkPicker {
+            picker: Picker::new("Marks", items),
+            marks,
+        });
+        self.input_mode = InputMode::MarkPicker;
+        self.prompt_input.clear();
+    }
+
+    /// Jump to the selected mark.
+    pub(crate) fn confirm_mark_picker(&mut self) {
+        let Some(state) = self.mark_picker.take() else {
+            return;
+        };
+        let Some(mark) = state.picker.selected().and_then(|i| state.marks.get(i)) else {
+            self.messages.info("No matching mark");
+            return;
+        };
+        self.jump_to_location(mark.clone());
+    }
+}
@@ -0,0 +1,145 @@
+use super::idle::IdleTask;
+use super::App;
+
+/// Edits after which the buffer is compacted the next time the editor
+/// is idle.
+pub(crate) const COMPACT_AFTER_EDITS: usize = 1000;
+
+/// Format a byte count with a binary unit suffix.
+pub(crate) fn format_bytes(bytes: usize) -> String {
+    const KIB: usize = 1024;
+    const MIB: usize = 1024 * KIB;
+    if bytes >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB as f64)
+    } else if bytes >= KIB {
+        format!("{:.1} KiB", bytes as f64 / KIB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Approximate memory held for the open buffer, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MemoryReport {
+    pub(crate) text: usize,
+    pub(crate) undo: usize,
+    pub(crate) highlights: usize,
+    /// Cursors, search matches and diagnostics.
+    pub(crate) markers: usize,
+}
+
+impl MemoryReport {
+    pub(crate) fn total(&self) -> usize {
+        self.text + self.undo + self.highlights + self.markers
+    }
+}
+
+// =========================================================================
+// Memory use
+// =========================================================================
+
+impl App {
+    /// What the open buffer costs in memory.
+    pub(crate) fn memory_report(&self) -> MemoryReport {
+        let diagnostics: usize = self
+            .current_diagnostics
+            .iter()
+            .map(|d| std::mem::size_of_val(d) + d.message.len())
+            .sum();
+        MemoryReport {
+            text: self.buffer.text_memory_usage(),
+            undo: self.buffer.undo_memory_usage(),
+            highlights: self
+                .lazy_highlights
+                .as_ref()
+                .map_or(0, |l| l.highlighter.memory_usage()),
+            markers: self.buffer.marker_memory_usage() + diagnostics,
+        }
+    }
+
+    /// `editor.memory_budget_mb` in bytes, if the warning is on.
+    fn memory_budget(&self) -> Option<usize> {
+        match self.editor_config.memory_budget_mb {
+            0 => None,
+            mb => Some(mb.saturating_mul(1024 * 1024)),
+        }
+    }
+
+    /// Queue a compaction once enough edits have fragmented the text.
+    pub(crate) fn queue_compaction_if_due(&mut self) {
+        if self.buffer.edits_since_compact() >= COMPACT_AFTER_EDITS {
+            self.queue_idle(IdleTask::Compact);
+        }
+    }
+
+    /// Repack the buffer's text, then warn if memory is still over the
+    /// budget. The warning is given once until usage drops back under it.
+    pub(crate) fn compact_buffer(&mut self) {
+        let freed = self.buffer.compact();
+        tracing::debug!("compacted buffer, freed {}", format_bytes(freed));
+        let total = self.memory_report().total();
+        match self.memory_budget() {
+            Some(budget) if total > budget => {
+                if !self.memory_warned {
+                    self.memory_warned = true;
+                    self.messages.warn(format!(
+                        "Buffer memory {} exceeds the {} budget (editor.memory_budget_mb); see FileInfo",
+                        format_bytes(total),
+                        format_bytes(budget)
+                    ));
+                }
+            }
+            _ => self.memory_warned = false,
+        }
+    }
+
+    /// Pop up file, undo and memory statistics for the current buffer.
+    pub(crate) fn cmd_file_info(&mut self) {
+        let name = self.filename.as_deref().unwrap_or("[scratch]");
+        let dirty = if self.buffer.is_dirty() {
+            " [modified]"
+        } else {
+            ""
+        };
+        let report = self.memory_report();
+        let cached_lines = self
+            .lazy_highlights
+            .as_ref()
+            .map_or(0, |l| l.highlighter.cached_lines());
+        let budget = match self.memory_budget() {
+            Some(budget) => format!(" of {} budget", format_bytes(budget)),
+            None => String::new(),
+        };
+        let lines = vec![
+            format!(
+                "{}{} — {} lines, {}",
+                name,
+                dirty,
+                self.buffer.line_count(),
+                format_bytes(self.buffer.len_bytes())
+            ),
+            format!("Text: {}", format_bytes(report.text)),
+            format!(
+                "Undo: {} entries, {} (limit {} / {})",
+                self.buffer.undo_len(),
+                format_bytes(report.undo),
+                self.undo_budget.max_entries,
+                format_bytes(self.undo_budget.max_bytes),
+            ),
+            format!(
+                "Highlights: {} lines, {}",
+                cached_lines,
+                format_bytes(report.highlights)
+            ),
+            format!(
+                "Markers: {} cursors, {} matches, {} diagnostics, {}",
+                self.buffer.cursors().len(),
+                self.buffer.search().match_count(),
+                self.current_diagnostics.len(),
+                format_bytes(report.markers)
+            ),
+            format!("Total: {}{}", format_bytes(report.total()), budget),
+        ];
+        self.show_cursor_popup(smash_tui::Popup::new("File info", lines));
+    }
+}
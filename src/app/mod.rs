@@ -1,19 +1,87 @@
+mod abbreviations;
+mod call_hierarchy;
+mod code_actions;
+mod command_line;
 mod commands;
+mod companion;
+mod completion;
+mod config;
+mod cursors;
+mod diff_view;
+mod disk_changes;
+mod file_index;
+mod file_tree;
+mod finder;
+mod git_blame;
+mod git_changes;
+mod git_editor;
+mod highlighting;
+mod idle;
+mod kill_ring;
+mod line_edits;
+mod links;
+mod loading;
 mod lsp;
+mod marks;
+mod memory;
+mod mouse;
+mod paths;
+mod pickers;
+mod quit;
+mod read_only;
+mod recent_files;
+mod registers;
 mod render;
+mod render_stats;
+mod replace;
+mod saving;
+mod scratch;
+mod search_history;
+mod session;
+mod shell;
+mod signature_help;
+mod startup_profile;
+mod symbols;
+mod tasks;
+mod terminal;
+mod themes;
+mod transform;
+mod vim;
+mod workspace_search;
 
 use anyhow::{Context, Result};
+use std::collections::{BTreeMap, VecDeque};
 use std::path::PathBuf;
 
 use smash_core::buffer::{Buffer, BufferId};
+use smash_core::edit::EditEvent;
+use smash_core::error::EditError;
+use smash_core::hex_dump::hex_dump;
 use smash_core::message::MessageBuffer;
 use smash_core::position::Position;
+use smash_core::safe_write::is_writable;
+use smash_core::search::SearchOptions;
+use smash_core::search_history::SearchHistory;
+use smash_core::undo::UndoBudget;
 use smash_input::{create_default_keymap, create_emacs_keymap, KeyResolver, Keymap};
-use smash_lsp::{CompletionItem, Diagnostic};
+use smash_lsp::{
+    CompletionItem, Diagnostic, PositionEncoding, SaveNotification, TextDocumentSyncKind,
+};
 use smash_syntax::{LanguageId, RegexHighlighter};
-use smash_tui::{PaneTree, Renderer, Viewport};
+use smash_tui::{
+    default_dark_theme, DiagnosticSigns, LineNumbers, ListState, PaneTree, Rect, Renderer, Theme,
+    Viewport,
+};
 
+use crate::debounce::Debounce;
+use crate::log_filter::LogFilter;
 use crate::lsp_types::{LspCommand, LspEvent};
+use crate::render_schedule::RenderScheduler;
+use crate::watch::WatchJob;
+pub(crate) use git_editor::is_git_file;
+pub(crate) use idle::IdleTask;
+pub(crate) use render_stats::RenderStats;
+pub(crate) use startup_profile::{StartupPhase, StartupProfile};
 
 /// Maximum number of entries in the jump stack.
 const JUMP_STACK_MAX: usize = 100;
@@ -33,6 +101,29 @@ impl JumpLocation {
     }
 }
 
+/// Files operated on by the config-editing commands.
+#[derive(Debug, Clone)]
+pub(crate) struct ConfigPaths {
+    /// Directory holding the user `config.toml`.
+    pub(crate) config_dir: PathBuf,
+    /// Workspace whose `.smash/config.toml` is the project config.
+    pub(crate) project_dir: Option<PathBuf>,
+    /// Active log file.
+    pub(crate) log_file: PathBuf,
+}
+
+/// Build the keymap for a config preset name. The `vim` preset uses the
+/// default bindings in Insert mode; Normal and Visual keys are handled by
+/// [`smash_input::Vim`].
+pub(crate) fn keymap_for_preset(preset: &str) -> Keymap {
+    let base_layer = if preset == "emacs" {
+        create_emacs_keymap()
+    } else {
+        create_default_keymap()
+    };
+    Keymap::new(base_layer)
+}
+
 /// A stack that records jump locations so the user can navigate back/forward.
 #[derive(Debug)]
 pub(crate) struct JumpStack {
@@ -80,6 +171,20 @@ impl JumpStack {
         Some(loc)
     }
 
+    /// The back stack, oldest first.
+    pub(crate) fn locations(&self) -> &[JumpLocation] {
+        &self.entries
+    }
+
+    /// Replace the back stack with `entries`, oldest first, e.g. from a
+    /// saved session.
+    pub(crate) fn restore(&mut self, mut entries: Vec<JumpLocation>) {
+        let excess = entries.len().saturating_sub(JUMP_STACK_MAX);
+        entries.drain(..excess);
+        self.entries = entries;
+        self.forward.clear();
+    }
+
     /// Number of back entries.
     #[cfg(test)]
     pub(crate) fn back_len(&self) -> usize {
@@ -95,17 +200,12 @@ impl JumpStack {
 
 /// Return the "content length" of a rope line slice.
 ///
-/// Ropey includes a trailing `\n` in `len_chars()` for every line except
-/// (potentially) the last one.  This helper subtracts 1 only when the line
-/// actually ends with a newline so that the cursor can reach the true end
-/// of text on the final line.
+/// Ropey includes the line break (`\n`, or `\r\n` in a CRLF file) in
+/// `len_chars()` for every line except the last one.  This helper
+/// subtracts it only when the line actually ends with one so that the
+/// cursor can reach the true end of text on the final line.
 pub(crate) fn line_content_len(line: smash_core::buffer::RopeSlice<'_>) -> usize {
-    let len = line.len_chars();
-    if len > 0 && line.char(len - 1) == '\n' {
-        len - 1
-    } else {
-        len
-    }
+    line.len_chars() - smash_core::buffer::line_break_len(line)
 }
 
 /// The current input mode of the editor.
@@ -123,10 +223,64 @@ pub(crate) enum InputMode {
     PromptFindReplace,
     /// Prompt for Save-As filename.
     PromptSaveAs,
+    /// Quitting with unsaved changes: save, discard or stay?
+    PromptQuit,
+    /// Save-As filename for scratch text, quitting once saved.
+    PromptSaveAsAndQuit,
+    /// Prompt for a highlighted-export target and options.
+    PromptExport,
     /// Fuzzy file finder overlay.
     FileFinder,
     /// Prompt for LSP rename (new symbol name).
     PromptLspRename,
+    /// Prompt for a watch glob and command.
+    PromptWatch,
+    /// Prompt for a shell command to run, filter through or insert.
+    PromptShellCommand,
+    /// Prompt for the name of a task to run.
+    PromptTask,
+    /// Command palette: run a command by name.
+    CommandPalette,
+    /// Prompt for a log filter such as `info,smash_lsp=debug`.
+    PromptLogLevel,
+    /// Vim `:` command line.
+    PromptVimCommand,
+    /// File tree: name of a new file or folder.
+    PromptTreeCreate,
+    /// File tree: new name for the selected entry.
+    PromptTreeRename,
+    /// File tree: confirm deleting the selected entry.
+    PromptTreeDelete,
+    /// Pattern to search the workspace for.
+    PromptWorkspaceSearch,
+    /// LSP symbol picker overlay; the input filters it.
+    SymbolPicker,
+    /// LSP code action picker overlay; the input filters it.
+    CodeActionPicker,
+    /// Theme picker overlay; the input filters it.
+    ThemePicker,
+    /// Clipboard history picker; the input filters it.
+    KillRingPicker,
+    /// Recent files picker; the input filters it.
+    RecentPicker,
+    /// Marks picker; the input filters it.
+    MarkPicker,
+    /// Register viewer; the input filters it.
+    RegisterPicker,
+    /// Search preset and history picker; the input filters it.
+    SearchPicker,
+    /// Prompt for the name to save the last search under.
+    PromptSearchPresetName,
+    /// The open file changed on disk while modified: reload it?
+    PromptReload,
+    /// Encoding to read the open file in again.
+    PromptReopenEncoding,
+    /// Line ending to convert the buffer to.
+    PromptLineEnding,
+    /// The companion file does not exist: create it?
+    PromptCreateCompanion,
+    /// Interactive replace: answer y/n/a/q for the selected match.
+    PromptReplaceStep,
 }
 
 /// Application state
@@ -135,9 +289,42 @@ pub(crate) struct App {
     pub(crate) buffer: Buffer,
     pub(crate) viewport: Viewport,
     pub(crate) renderer: Renderer,
+    /// Frame timings for the render stats overlay.
+    pub(crate) render_stats: RenderStats,
+    /// When the event loop next draws (`display.max_fps`).
+    pub(crate) render_scheduler: RenderScheduler,
+    /// Waits for input to pause for `editor.idle_delay_ms`.
+    pub(crate) input_idle: Debounce,
+    /// How long each phase of startup took.
+    pub(crate) startup_profile: StartupProfile,
+    /// Startup work deferred until after the first frame.
+    pub(crate) idle_tasks: VecDeque<IdleTask>,
+    /// Colour theme from `display.theme`.
+    pub(crate) theme: Theme,
+    /// Config name of `theme`.
+    pub(crate) theme_name: String,
+    /// Signs for diagnostic severities, in the gutter and status bar.
+    pub(crate) diagnostic_signs: DiagnosticSigns,
+    /// Line number mode from `display.line_numbers`.
+    pub(crate) line_numbers: LineNumbers,
+    /// Whether the gutter is shown; flipped by `ToggleLineNumbers`.
+    pub(crate) show_line_numbers: bool,
+    /// Line lengths marked with rulers, from `display.rulers`.
+    pub(crate) ruler_columns: Vec<usize>,
+    /// Whether rulers are drawn; flipped by `ToggleRulers`.
+    pub(crate) show_rulers: bool,
+    /// From `display.highlight_current_line`; flipped by
+    /// `ToggleCurrentLineHighlight`.
+    pub(crate) highlight_current_line: bool,
+    /// From `display.indent_guides`; flipped by `ToggleIndentGuides`.
+    pub(crate) indent_guides: bool,
     pub(crate) panes: PaneTree,
     pub(crate) resolver: KeyResolver,
     pub(crate) highlighter: Option<RegexHighlighter>,
+    /// Background highlighting around the view, with `highlighter`.
+    pub(crate) lazy_highlights: Option<highlighting::LazyHighlights>,
+    /// Lines past the view to highlight ahead of scrolling.
+    pub(crate) highlight_margin: usize,
     pub(crate) filename: Option<String>,
     pub(crate) messages: MessageBuffer,
     pub(crate) input_mode: InputMode,
@@ -146,11 +333,34 @@ pub(crate) struct App {
     pub(crate) replace_input: String,
     /// Whether the replace prompt is focused (vs find prompt).
     pub(crate) replace_focused: bool,
+    /// Regex, case and whole-word toggles for find and replace.
+    pub(crate) search_options: SearchOptions,
+    /// Whether the find-replace prompt confirms each match.
+    pub(crate) replace_interactive: bool,
+    /// Interactive replace in progress.
+    pub(crate) replace_session: Option<replace::ReplaceSession>,
+    /// Recent searches and presets of every project.
+    pub(crate) search_history: SearchHistory,
+    /// Recent search shown in the Find prompt by Up / Down.
+    pub(crate) search_history_index: Option<usize>,
+    /// Open search picker, if any.
+    pub(crate) search_picker: Option<search_history::SearchPicker>,
     /// Fuzzy file finder.
     pub(crate) file_finder: Option<smash_core::fuzzy_finder::FileFinder>,
+    /// Background walk building the file finder's index.
+    pub(crate) file_index: Option<file_index::FileIndexJob>,
     /// Current finder results.
     pub(crate) finder_results: Vec<smash_core::fuzzy_finder::FileMatch>,
+    /// Selected file and scroll position in `finder_results`.
+    pub(crate) finder_list: ListState,
+    /// Start of the selected file, shown beside the list.
+    pub(crate) finder_preview: Option<finder::FinderPreview>,
+    /// Recently opened files; the finder ranks them higher.
+    pub(crate) recent_files: smash_core::recent_files::RecentFiles,
     pub(crate) running: bool,
+    /// Process exit status once the editor stops; non-zero aborts the
+    /// git operation that started it.
+    pub(crate) exit_code: i32,
     // --- LSP integration ---
     /// Channel to send commands to the LSP async task.
     pub(crate) lsp_cmd_tx: tokio::sync::mpsc::Sender<LspCommand>,
@@ -164,23 +374,208 @@ pub(crate) struct App {
     pub(crate) lsp_enabled: bool,
     /// LSP server configs from config file.
     pub(crate) lsp_server_configs: std::collections::HashMap<String, smash_config::LspServerEntry>,
+    /// Features switched off for every server (`lsp.disable`).
+    pub(crate) lsp_disable: Vec<String>,
     /// Whether an LSP server has been started for the current language.
     pub(crate) lsp_server_started: bool,
+    /// Document sync mode negotiated with the running server.
+    pub(crate) lsp_sync_kind: TextDocumentSyncKind,
+    /// Whether and how the running server wants `didSave`.
+    pub(crate) lsp_save: SaveNotification,
+    /// The running server may edit the document before it is saved.
+    pub(crate) lsp_will_save_wait_until: bool,
+    /// The running server formats documents.
+    pub(crate) lsp_formatting: bool,
+    /// The running server formats ranges of a document.
+    pub(crate) lsp_range_formatting: bool,
+    /// The running server offers inlay hints.
+    pub(crate) lsp_inlay_hints: bool,
+    /// How the running server counts columns.
+    pub(crate) lsp_position_encoding: PositionEncoding,
+    /// The newest operation the server reports progress for, if any.
+    pub(crate) lsp_progress: Option<smash_lsp::Progress>,
+    /// Progress updates seen, which turn the status bar spinner.
+    pub(crate) lsp_progress_ticks: usize,
+    /// Format with the server before writing (`lsp.format_on_save`).
+    pub(crate) format_on_save: bool,
+    /// Organize imports before writing (`lsp.organize_imports_on_save`).
+    pub(crate) organize_imports_on_save: bool,
+    /// Ask for hover information once input pauses (`lsp.hover_on_idle`).
+    pub(crate) hover_on_idle: bool,
+    /// Cursor position of the pending automatic hover request.
+    pub(crate) hover_request: Option<Position>,
+    /// Where hover information was last asked for automatically, so
+    /// pausing again in the same place does not ask twice.
+    pub(crate) idle_hovered: Option<Position>,
+    /// Edits not yet sent to an incremental-sync server.
+    pub(crate) pending_edits: Vec<EditEvent>,
+    /// Document text as last sent to the server, for diff-based didChange.
+    pub(crate) lsp_synced_text: Option<smash_core::buffer::Rope>,
     /// Diagnostics for the current file.
     pub(crate) current_diagnostics: Vec<Diagnostic>,
     /// Current diagnostic index for next/prev navigation.
     pub(crate) diagnostic_index: usize,
     /// Last hover text to display.
     pub(crate) hover_text: Option<String>,
+    /// Popup drawn over the editor; closed by the next key.
+    pub(crate) popup: Option<smash_tui::Popup>,
+    /// Signature help shown above the cursor while typing a call.
+    pub(crate) signature_help: Option<smash_lsp::SignatureHelp>,
+    /// Cursor position of the latest signature help request; answers
+    /// arriving after the cursor moved on are dropped.
+    pub(crate) signature_request: Option<Position>,
+    /// Characters that ask the running server for signature help.
+    pub(crate) signature_triggers: Vec<String>,
     /// Completion items from LSP.
     pub(crate) completion_items: Vec<CompletionItem>,
     /// Selected completion index.
     pub(crate) completion_index: usize,
+    /// Characters that ask the running server for completion.
+    pub(crate) completion_triggers: Vec<String>,
+    /// Waits for a pause in typing before asking for completions.
+    pub(crate) completion_debounce: Debounce,
+    /// Cursor position of the pending automatic completion request.
+    pub(crate) completion_request: Option<Position>,
     /// Whether to normalize macOS Option key to Alt.
     pub(crate) option_as_alt: bool,
+    /// Clipboard used by Copy / Cut / Paste.
+    pub(crate) clipboard: Box<dyn smash_platform::Clipboard>,
+    /// The `editor.clipboard` setting the system clipboard was built
+    /// from; `None` while using an in-memory one.
+    pub(crate) clipboard_setting: Option<String>,
+    /// Opens URLs for OpenLinkUnderCursor; the desktop's handler.
+    pub(crate) url_opener: links::UrlOpener,
     // --- Jump navigation ---
     /// Stack for jump-back / jump-forward navigation across files.
     pub(crate) jump_stack: JumpStack,
+    /// The file open before the current one, with its cursor, for
+    /// AlternateBuffer.
+    pub(crate) alternate_file: Option<JumpLocation>,
+    /// Last edit made in a file no longer open, for JumpToLastEdit when
+    /// the open one has none.
+    pub(crate) last_edit: Option<JumpLocation>,
+    /// Named marks set by SetMark.
+    pub(crate) marks: BTreeMap<char, JumpLocation>,
+    pub(crate) mark_picker: Option<marks::MarkPicker>,
+    /// Companion file offered for creation by GoToCompanionFile.
+    pub(crate) missing_companion: Option<companion::MissingCompanion>,
+    /// The memory budget warning has been given and usage has not
+    /// dropped back under the budget since.
+    pub(crate) memory_warned: bool,
+    /// Undo history limits applied to every opened buffer.
+    pub(crate) undo_budget: UndoBudget,
+    /// Command re-run on save of matching files, shown in the watch pane.
+    pub(crate) watch: Option<WatchJob>,
+    /// Tasks from the config `[tasks]` table: name to shell command.
+    pub(crate) tasks: std::collections::BTreeMap<String, String>,
+    /// The task last run, shown in the task pane.
+    pub(crate) task: Option<tasks::TaskRun>,
+    /// Blame annotations for the cursor line, while switched on.
+    pub(crate) git_blame: Option<git_blame::GitBlame>,
+    /// Name of the active keymap preset, shown in the help screen.
+    pub(crate) keymap_preset: String,
+    /// Config and log locations; `None` until the editor resolves them.
+    pub(crate) config_paths: Option<ConfigPaths>,
+    /// Reload handle for the log filter; `None` when logging is not set up.
+    pub(crate) log_filter: Option<LogFilter>,
+    // --- Integrated terminal ---
+    /// Shell session shown in the bottom terminal pane.
+    pub(crate) terminal: Option<smash_terminal::TerminalPane>,
+    /// Whether the terminal pane is shown; a shown terminal has focus.
+    pub(crate) terminal_visible: bool,
+    // --- File tree ---
+    /// Workspace explorer, created the first time it is shown.
+    pub(crate) file_tree: Option<smash_tui::FileTree>,
+    /// Whether the sidebar is shown.
+    pub(crate) file_tree_visible: bool,
+    /// Whether keys go to the sidebar rather than the editor.
+    pub(crate) file_tree_focused: bool,
+    /// Column where the editor area starts, right of the sidebar (as of
+    /// the last render).
+    pub(crate) editor_left: u16,
+    // --- Minimap ---
+    /// From `display.show_minimap`; flipped by `ToggleMinimap`.
+    pub(crate) show_minimap: bool,
+    /// Where the minimap was drawn in the last render, if it was.
+    pub(crate) minimap_area: Option<Rect>,
+    /// From `display.scrollbar`; flipped by `ToggleScrollbar`.
+    pub(crate) show_scrollbar: bool,
+    /// Where the scrollbar was drawn in the last render, if it was.
+    pub(crate) scrollbar_area: Option<Rect>,
+    /// Lines changed since the last commit, marked on the scrollbar.
+    pub(crate) git_changes: Option<git_changes::GitChanges>,
+    /// The minimap or scrollbar the mouse went down on and is dragging.
+    pub(crate) scroll_drag: Option<mouse::ScrollArea>,
+    // --- Workspace search ---
+    /// Results pane of the last workspace search.
+    pub(crate) search_results: Option<workspace_search::SearchResults>,
+    /// Whether keys go to the results pane rather than the editor.
+    pub(crate) search_results_focused: bool,
+    /// Call hierarchy shown in the results pane in place of the search
+    /// results.
+    pub(crate) call_hierarchy: Option<call_hierarchy::CallHierarchy>,
+    /// Diff of the buffer against disk or `HEAD`, shown in the results
+    /// pane in place of search results.
+    pub(crate) diff_view: Option<diff_view::DiffView>,
+    /// Output of the last shell command, shown in the results pane.
+    pub(crate) shell_output: Option<shell::ShellOutputView>,
+    /// Shell command still running, if any.
+    pub(crate) shell_run: Option<shell::ShellRun>,
+    /// Symbols listed by the symbol picker while it is open.
+    pub(crate) symbol_picker: Option<symbols::SymbolPicker>,
+    /// Open code action picker, if any.
+    pub(crate) code_action_picker: Option<code_actions::CodeActionPicker>,
+    pub(crate) theme_picker: Option<themes::ThemePicker>,
+    pub(crate) kill_ring_picker: Option<kill_ring::KillRingPicker>,
+    pub(crate) recent_picker: Option<recent_files::RecentPicker>,
+    /// Recently copied and cut text, newest first.
+    pub(crate) kill_ring: smash_core::kill_ring::KillRing,
+    /// Set by a paste, so a yank-pop right after it can replace it.
+    pub(crate) last_yank: Option<kill_ring::LastYank>,
+    /// Titles of server commands sent and not yet finished.
+    pub(crate) running_commands: Vec<String>,
+    /// Screen area the terminal grid was last painted into, so the next
+    /// frame only needs its dirty rows.
+    pub(crate) terminal_painted: Option<Rect>,
+    /// Shell override from the config (`terminal_shell`).
+    pub(crate) terminal_shell: Option<String>,
+    /// User bindings from the config `[keys]` table, layered over the preset.
+    pub(crate) user_keys: std::collections::BTreeMap<String, String>,
+    /// `[editor]` settings, before per-language overrides.
+    pub(crate) editor_config: smash_config::config::EditorConfig,
+    /// Per-language overrides from the config `[languages]` table.
+    pub(crate) language_configs:
+        std::collections::BTreeMap<String, smash_config::config::LanguageConfig>,
+    /// Abbreviations from the config `[abbreviations]` table, before the
+    /// per-language ones.
+    pub(crate) abbreviations: std::collections::BTreeMap<String, String>,
+    /// The abbreviation the last command expanded, which an immediate
+    /// Backspace turns back into its trigger.
+    pub(crate) last_expansion: Option<abbreviations::Expansion>,
+    /// Quick fixes listed in the diagnostic popup, chosen with `1`-`9`.
+    pub(crate) quick_fixes: Vec<smash_lsp::CodeAction>,
+    /// Diagnostics of an outstanding quick-fix request for the popup.
+    pub(crate) quick_fix_diagnostics: Option<Vec<smash_lsp::Diagnostic>>,
+    /// File being read in the background, if any.
+    pub(crate) pending_open: Option<loading::PendingOpen>,
+    /// Selection start while the mouse button is held.
+    pub(crate) mouse_drag_anchor: Option<Position>,
+    /// A save held until the server's pre-save edits arrive.
+    pub(crate) pending_save: Option<saving::PendingSave>,
+    /// Notices the open file being changed by other programs.
+    pub(crate) disk_watch: disk_changes::DiskWatch,
+    // --- Vim preset ---
+    /// Modal state; `Some` only when the keymap preset is `vim`.
+    pub(crate) vim: Option<smash_input::Vim>,
+    /// Where the Vim Visual selection started.
+    pub(crate) visual_anchor: Position,
+    /// Whether the last yank or delete took whole lines.
+    pub(crate) register_linewise: bool,
+    /// Text yanked and deleted into Vim-style registers.
+    pub(crate) registers: smash_core::registers::Registers,
+    /// Register named with `"x` for the Vim command being run.
+    pub(crate) active_register: Option<char>,
+    pub(crate) register_picker: Option<registers::RegisterPicker>,
 }
 
 impl App {
@@ -198,65 +593,185 @@ impl App {
     ) -> Result<Self> {
         let id = BufferId::next();
         let (buffer, filename, highlighter, lang_id) = match file {
-            Some(ref path) => {
-                let buf = Buffer::open_or_create(id, path)
-                    .with_context(|| format!("failed to open: {}", path.display()))?;
-                let name = path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("unnamed")
-                    .to_string();
-                let lang = LanguageId::from_path(path);
-                let hl = RegexHighlighter::new(lang).ok();
-                let lang_str = lang.as_str().to_string();
-                (buf, Some(name), hl, Some(lang_str))
-            }
+            Some(ref path) => match Buffer::open_or_create(id, path) {
+                Err(EditError::Binary) => {
+                    let bytes = std::fs::read(path)
+                        .with_context(|| format!("failed to open: {}", path.display()))?;
+                    let buf = loading::hex_dump_buffer(&hex_dump(&bytes));
+                    (buf, Some(loading::hex_dump_name(path)), None, None)
+                }
+                result => {
+                    let mut buf =
+                        result.with_context(|| format!("failed to open: {}", path.display()))?;
+                    buf.set_read_only(!is_writable(path));
+                    let name = path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("unnamed")
+                        .to_string();
+                    let lang = LanguageId::from_path(path);
+                    let lang_str = lang.as_str().to_string();
+                    // Compiled once the first frame is up.
+                    (buf, Some(name), None, Some(lang_str))
+                }
+            },
             None => (Buffer::new(id), None, None, None),
         };
 
         // Reserve 1 line for status bar
         let edit_height = height.saturating_sub(1);
 
-        // Select base keymap from config preset
-        let base_layer = if keymap_preset == "emacs" {
-            create_emacs_keymap()
+        let resolver = KeyResolver::new(keymap_for_preset(keymap_preset));
+
+        let idle_tasks = if file.is_some() {
+            VecDeque::from([IdleTask::Highlighter])
         } else {
-            create_default_keymap()
+            VecDeque::new()
         };
-        let keymap = Keymap::new(base_layer);
-
-        let resolver = KeyResolver::new(keymap);
 
         Ok(Self {
             buffer,
             viewport: Viewport::new(edit_height as usize, width as usize),
             renderer: Renderer::new(width, height),
+            theme: default_dark_theme(),
+            theme_name: "dark".to_string(),
+            render_stats: RenderStats::new(std::time::Instant::now()),
+            render_scheduler: RenderScheduler::new(60),
+            input_idle: Debounce::new(idle::IDLE_DELAY),
+            startup_profile: StartupProfile::default(),
+            idle_tasks,
+            diagnostic_signs: DiagnosticSigns::default(),
+            line_numbers: LineNumbers::Absolute,
+            show_line_numbers: true,
+            ruler_columns: Vec::new(),
+            show_rulers: true,
+            highlight_current_line: false,
+            indent_guides: false,
             panes: PaneTree::new(),
             resolver,
             highlighter,
+            lazy_highlights: None,
+            highlight_margin: smash_config::Config::default().display.highlight_margin,
             filename,
             messages: MessageBuffer::new(),
             input_mode: InputMode::Normal,
             prompt_input: String::new(),
             replace_input: String::new(),
             replace_focused: false,
+            search_options: SearchOptions::default(),
+            replace_interactive: false,
+            replace_session: None,
+            search_history: SearchHistory::default(),
+            search_history_index: None,
+            search_picker: None,
             file_finder: None,
+            file_index: None,
             finder_results: Vec::new(),
+            finder_list: ListState::default(),
+            finder_preview: None,
+            recent_files: Default::default(),
             running: true,
+            exit_code: 0,
             lsp_cmd_tx,
             lsp_evt_rx,
             document_version: 1,
             language_id: lang_id,
             lsp_enabled,
             lsp_server_configs,
+            lsp_disable: Vec::new(),
             lsp_server_started: false,
+            lsp_sync_kind: TextDocumentSyncKind::default(),
+            lsp_save: SaveNotification::default(),
+            lsp_will_save_wait_until: false,
+            lsp_formatting: false,
+            lsp_range_formatting: false,
+            lsp_inlay_hints: false,
+            lsp_position_encoding: PositionEncoding::default(),
+            lsp_progress: None,
+            lsp_progress_ticks: 0,
+            format_on_save: false,
+            organize_imports_on_save: false,
+            hover_on_idle: false,
+            hover_request: None,
+            idle_hovered: None,
+            pending_edits: Vec::new(),
+            lsp_synced_text: None,
             current_diagnostics: Vec::new(),
             diagnostic_index: 0,
             hover_text: None,
+            popup: None,
+            signature_help: None,
+            signature_request: None,
+            signature_triggers: Vec::new(),
             completion_items: Vec::new(),
             completion_index: 0,
+            completion_triggers: Vec::new(),
+            completion_debounce: Debounce::new(completion::COMPLETION_DELAY),
+            completion_request: None,
             option_as_alt,
+            clipboard: Box::new(smash_platform::InMemoryClipboard::new()),
+            url_opener: Box::new(smash_platform::open_url),
+            clipboard_setting: None,
             jump_stack: JumpStack::new(),
+            alternate_file: None,
+            last_edit: None,
+            marks: BTreeMap::new(),
+            mark_picker: None,
+            missing_companion: None,
+            memory_warned: false,
+            undo_budget: UndoBudget::default(),
+            watch: None,
+            tasks: std::collections::BTreeMap::new(),
+            task: None,
+            git_blame: None,
+            keymap_preset: keymap_preset.to_string(),
+            config_paths: None,
+            log_filter: None,
+            terminal: None,
+            terminal_visible: false,
+            terminal_painted: None,
+            file_tree: None,
+            file_tree_visible: false,
+            file_tree_focused: false,
+            editor_left: 0,
+            show_minimap: false,
+            minimap_area: None,
+            show_scrollbar: false,
+            scrollbar_area: None,
+            git_changes: None,
+            scroll_drag: None,
+            search_results: None,
+            search_results_focused: false,
+            call_hierarchy: None,
+            diff_view: None,
+            shell_output: None,
+            shell_run: None,
+            symbol_picker: None,
+            code_action_picker: None,
+            theme_picker: None,
+            kill_ring_picker: None,
+            recent_picker: None,
+            kill_ring: smash_core::kill_ring::KillRing::default(),
+            last_yank: None,
+            running_commands: Vec::new(),
+            terminal_shell: None,
+            user_keys: std::collections::BTreeMap::new(),
+            editor_config: smash_config::config::EditorConfig::default(),
+            language_configs: std::collections::BTreeMap::new(),
+            abbreviations: std::collections::BTreeMap::new(),
+            last_expansion: None,
+            quick_fixes: Vec::new(),
+            quick_fix_diagnostics: None,
+            pending_open: None,
+            pending_save: None,
+            disk_watch: disk_changes::DiskWatch::new(),
+            mouse_drag_anchor: None,
+            vim: (keymap_preset == "vim").then(smash_input::Vim::new),
+            visual_anchor: Position::new(0, 0),
+            register_linewise: false,
+            registers: smash_core::registers::Registers::default(),
+            active_register: None,
+            register_picker: None,
         })
     }
 }
@@ -265,24 +780,102 @@ impl App {
 mod tests {
     use super::*;
     use smash_core::buffer::{Buffer, BufferId};
+    use smash_core::undo::UndoBudget;
+    use smash_input::command::Direction;
     use smash_input::Command;
 
+    /// Options for an 80x24 test [`App`]; the defaults give an empty
+    /// buffer, the default keymap and no language servers.
+    #[derive(Default)]
+    struct TestApp {
+        file: Option<PathBuf>,
+        keymap: Option<&'static str>,
+        lsp_servers: Option<std::collections::HashMap<String, smash_config::LspServerEntry>>,
+    }
+
+    impl TestApp {
+        /// Open `path` on startup.
+        fn file(mut self, path: PathBuf) -> Self {
+            self.file = Some(path);
+            self
+        }
+
+        /// Use the named keymap preset.
+        fn keymap(mut self, preset: &'static str) -> Self {
+            self.keymap = Some(preset);
+            self
+        }
+
+        /// Enable LSP support with the given server configs.
+        fn lsp(
+            mut self,
+            servers: std::collections::HashMap<String, smash_config::LspServerEntry>,
+        ) -> Self {
+            self.lsp_servers = Some(servers);
+            self
+        }
+
+        /// Build the App, dropping the LSP command receiver.
+        fn build(self) -> App {
+            self.build_with_lsp().0
+        }
+
+        /// Build the App along with the receiver for its LSP commands.
+        fn build_with_lsp(self) -> (App, tokio::sync::mpsc::Receiver<LspCommand>) {
+            let (lsp_cmd_tx, lsp_cmd_rx) = tokio::sync::mpsc::channel(8);
+            let (_lsp_evt_tx, lsp_evt_rx) = std::sync::mpsc::channel();
+            let app = App::new(
+                80,
+                24,
+                self.file,
+                self.keymap.unwrap_or("default"),
+                lsp_cmd_tx,
+                lsp_evt_rx,
+                self.lsp_servers.is_some(),
+                self.lsp_servers.unwrap_or_default(),
+                false,
+            )
+            .unwrap();
+            (app, lsp_cmd_rx)
+        }
+    }
+
     /// Create a test App instance with dummy LSP channels.
     fn test_app() -> App {
-        let (lsp_cmd_tx, _lsp_cmd_rx) = tokio::sync::mpsc::channel(1);
-        let (_lsp_evt_tx, lsp_evt_rx) = std::sync::mpsc::channel();
-        App::new(
-            80,
-            24,
-            None,
-            "default",
-            lsp_cmd_tx,
-            lsp_evt_rx,
-            false,
-            std::collections::HashMap::new(),
-            false,
-        )
-        .unwrap()
+        TestApp::default().build()
+    }
+
+    /// Write `text` to `doc.txt` in a fresh temporary directory.
+    fn temp_doc(text: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("doc.txt");
+        std::fs::write(&path, text).expect("write");
+        (dir, path)
+    }
+
+    /// Call `poll` until `done` holds, failing after ten seconds.
+    fn wait_until<T>(
+        app: &mut App,
+        what: &str,
+        done: impl Fn(&App) -> bool,
+        mut poll: impl FnMut(&mut App) -> T,
+    ) {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while !done(app) {
+            assert!(std::time::Instant::now() < deadline, "{} timed out", what);
+            poll(app);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    /// Poll until a background file open has finished.
+    fn wait_for_open(app: &mut App) {
+        wait_until(
+            app,
+            "file open",
+            |app| app.pending_open.is_none(),
+            App::poll_pending_open,
+        );
     }
 
     #[test]
@@ -306,6 +899,237 @@ mod tests {
         );
     }
 
+    #[test]
+    fn uris_with_encoded_characters_match_the_open_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("my notes.txt");
+        std::fs::write(&path, "a\nb\n").unwrap();
+        let mut app = test_app();
+        app.open_path(path.clone(), None, false);
+        wait_for_open(&mut app);
+
+        let uri = app.current_uri().unwrap();
+        assert!(uri.ends_with("/my%20notes.txt"), "got {}", uri);
+        assert_eq!(App::uri_to_path(&uri), path);
+        // A server may leave safe characters encoded.
+        let respelled = uri.replace("notes", "%6Eotes");
+        assert!(app.is_current_uri(&respelled));
+
+        app.handle_lsp_event(LspEvent::GotoDefinitionResult {
+            target: smash_lsp::GotoTarget::Definition,
+            locations: vec![smash_lsp::Location {
+                uri: respelled,
+                range: smash_lsp::LspRange {
+                    start: smash_lsp::LspPosition {
+                        line: 1,
+                        character: 0,
+                    },
+                    end: smash_lsp::LspPosition {
+                        line: 1,
+                        character: 1,
+                    },
+                },
+            }],
+        });
+        assert_eq!(app.buffer.path(), Some(path.as_path()));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 0)
+        );
+    }
+
+    #[test]
+    fn several_references_are_listed_in_the_results_pane() {
+        use smash_input::{Key, KeyEvent, Modifiers};
+        let (mut app, _rx, dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let doc = dir.path().join("doc.txt");
+        let other = dir.path().join("other.txt");
+        std::fs::write(&other, "one\n  two\n").unwrap();
+        // The open file's lines come from the buffer, not the disk.
+        std::fs::write(&doc, "changed\n").unwrap();
+        let at = |path: &std::path::Path, line, character| {
+            let pos = smash_lsp::LspPosition { line, character };
+            smash_lsp::Location {
+                uri: App::path_to_uri(path),
+                range: smash_lsp::LspRange::new(pos, pos),
+            }
+        };
+
+        app.handle_lsp_event(LspEvent::ReferencesResult(vec![
+            at(&other, 1, 2),
+            at(&doc, 1, 0),
+            at(&doc, 0, 0),
+        ]));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 0)
+        );
+        assert!(app.search_results_focused());
+        assert!(app.normal_status().0.contains("[locations]"));
+        let results = app.search_results.as_ref().unwrap();
+        assert_eq!(results.title(), "References: 3 locations in 2 files");
+        let lines = results.lines();
+        assert!(lines[0].ends_with("doc.txt:1:1: hello"), "{:?}", lines);
+        assert!(lines[1].ends_with("doc.txt:2:1: world"));
+        assert!(lines[2].ends_with("other.txt:2:3: two"));
+
+        let key = |k| KeyEvent::new(k, Modifiers::NONE);
+        assert!(app.handle_search_results_key(&key(Key::Char('j'))));
+        assert!(app.handle_search_results_key(&key(Key::Char('j'))));
+        assert!(app.handle_search_results_key(&key(Key::Enter)));
+        wait_for_open(&mut app);
+        assert_eq!(app.filename.as_deref(), Some("other.txt"));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 2)
+        );
+
+        // A single implementation is jumped to; several are listed.
+        app.handle_lsp_event(LspEvent::GotoDefinitionResult {
+            target: smash_lsp::GotoTarget::Implementation,
+            locations: vec![at(&doc, 0, 0), at(&other, 0, 0)],
+        });
+        assert_eq!(
+            app.search_results.as_ref().unwrap().title(),
+            "Implementations: 2 locations in 2 files"
+        );
+        // The search prompt does not offer a location list's title.
+        app.handle_command(Command::WorkspaceSearch);
+        assert_eq!(app.prompt_input, "");
+    }
+
+    #[test]
+    fn call_hierarchy_expands_callers_a_level_at_a_time() {
+        use crate::lsp_types::CallDirection;
+        use smash_input::{Key, KeyEvent, Modifiers};
+
+        let (mut app, mut rx, dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let doc = dir.path().join("doc.txt");
+        let item = |name: &str, path: &std::path::Path, line| {
+            let at = smash_lsp::LspPosition { line, character: 2 };
+            smash_lsp::CallHierarchyItem {
+                name: name.to_string(),
+                kind: smash_lsp::SymbolKind::Function,
+                detail: None,
+                uri: App::path_to_uri(path),
+                range: smash_lsp::LspRange::new(at, at),
+                selection_range: smash_lsp::LspRange::new(at, at),
+                data: None,
+            }
+        };
+        let next_calls = |rx: &mut tokio::sync::mpsc::Receiver<LspCommand>| match rx.try_recv() {
+            Ok(LspCommand::CallHierarchyCalls {
+                node,
+                direction: CallDirection::Incoming,
+                ..
+            }) => node,
+            _ => panic!("expected a request for incoming calls"),
+        };
+
+        app.handle_command(Command::LspIncomingCalls);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(LspCommand::PrepareCallHierarchy {
+                direction: CallDirection::Incoming,
+                ..
+            })
+        ));
+        app.handle_lsp_event(LspEvent::CallHierarchyPrepared {
+            direction: CallDirection::Incoming,
+            items: vec![item("run", &doc, 1)],
+        });
+        // The function at the cursor is expanded straight away.
+        let root = next_calls(&mut rx);
+        app.handle_lsp_event(LspEvent::CallHierarchyCalls {
+            node: root,
+            items: vec![item("main", &dir.path().join("main.rs"), 0)],
+        });
+        assert!(app.search_results_focused());
+        assert!(app.normal_status().0.contains("[calls]"));
+        let hierarchy = app.call_hierarchy.as_ref().unwrap();
+        assert_eq!(hierarchy.title(), "Callers of run");
+        let lines = hierarchy.lines();
+        assert!(lines[0].starts_with("▾ run (function) "), "{:?}", lines);
+        assert!(lines[0].ends_with("doc.txt:2"));
+        assert!(lines[1].starts_with("  ▸ main (function) "));
+
+        let key = |k| KeyEvent::new(k, Modifiers::NONE);
+        app.handle_search_results_key(&key(Key::Char('j')));
+        app.handle_search_results_key(&key(Key::Char('l')));
+        let main = next_calls(&mut rx);
+        assert!(app.call_hierarchy.as_ref().unwrap().lines()[1].starts_with("  … main"));
+        app.handle_lsp_event(LspEvent::CallHierarchyCalls {
+            node: main,
+            items: Vec::new(),
+        });
+        assert!(app.call_hierarchy.as_ref().unwrap().lines()[1].starts_with("  · main"));
+
+        // Left folds a node, then goes to its parent, then folds that.
+        for _ in 0..3 {
+            app.handle_search_results_key(&key(Key::Char('h')));
+        }
+        assert_eq!(
+            app.call_hierarchy.as_ref().unwrap().lines(),
+            vec![lines[0].replace('▾', "▸")]
+        );
+
+        app.handle_search_results_key(&key(Key::Enter));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 2)
+        );
+        assert!(!app.search_results_focused());
+        app.search_results_focused = true;
+        app.handle_search_results_key(&key(Key::Esc));
+        assert!(app.call_hierarchy.is_none());
+    }
+
+    #[test]
+    fn goto_type_definition_jumps_and_jump_back_returns() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        app.handle_command(Command::LspGotoTypeDefinition);
+        let uri = match rx.try_recv().expect("type definition requested") {
+            LspCommand::GotoDefinition {
+                uri,
+                target: smash_lsp::GotoTarget::TypeDefinition,
+                ..
+            } => uri,
+            _ => panic!("expected a type definition request"),
+        };
+
+        let at = smash_lsp::LspPosition {
+            line: 1,
+            character: 2,
+        };
+        app.handle_lsp_event(LspEvent::GotoDefinitionResult {
+            target: smash_lsp::GotoTarget::TypeDefinition,
+            locations: vec![smash_lsp::Location {
+                uri,
+                range: smash_lsp::LspRange::new(at, at),
+            }],
+        });
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 2)
+        );
+        assert_eq!(app.messages.last().unwrap().text(), "Type definition: 2:3");
+
+        app.handle_command(Command::JumpBack);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 0)
+        );
+
+        app.handle_lsp_event(LspEvent::GotoDefinitionResult {
+            target: smash_lsp::GotoTarget::Implementation,
+            locations: Vec::new(),
+        });
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "No implementation found"
+        );
+    }
+
     #[test]
     fn current_uri_returns_none_for_empty_buffer() {
         let app = test_app();
@@ -342,6 +1166,12 @@ mod tests {
         assert_eq!(line_content_len(line), 3);
     }
 
+    #[test]
+    fn line_content_len_excludes_crlf() {
+        let buf = Buffer::from_text(BufferId::next(), "hello\r\nworld");
+        assert_eq!(line_content_len(buf.line(0).unwrap()), 5);
+    }
+
     #[test]
     fn line_content_len_empty_buffer() {
         let buf = Buffer::new(BufferId::next());
@@ -403,6 +1233,178 @@ mod tests {
         assert_eq!(app.buffer.text().to_string(), "ab");
     }
 
+    // =====================================================================
+    // Selection tests
+    // =====================================================================
+
+    #[test]
+    fn extend_selection_anchors_at_start() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "hello world");
+        app.handle_command(Command::ExtendSelection(Direction::Right));
+        app.handle_command(Command::ExtendSelection(Direction::Right));
+        let cursor = app.buffer.cursors().primary();
+        assert_eq!(cursor.anchor(), Some(Position::new(0, 0)));
+        assert_eq!(cursor.position(), Position::new(0, 2));
+        assert_eq!(app.selected_text().as_deref(), Some("he"));
+    }
+
+    #[test]
+    fn select_word_right_extends_by_word() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "hello world");
+        app.handle_command(Command::SelectWordRight);
+        assert_eq!(app.selected_text().as_deref(), Some("hello "));
+    }
+
+    #[test]
+    fn plain_motion_clears_selection() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "hello");
+        app.handle_command(Command::SelectLineEnd);
+        assert!(app.buffer.cursors().primary().has_selection());
+        app.handle_command(Command::MoveLeft);
+        assert!(!app.buffer.cursors().primary().has_selection());
+    }
+
+    #[test]
+    fn selection_collapsing_to_anchor_clears_it() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "hello");
+        app.handle_command(Command::ExtendSelection(Direction::Right));
+        app.handle_command(Command::ExtendSelection(Direction::Left));
+        assert!(!app.buffer.cursors().primary().has_selection());
+    }
+
+    #[test]
+    fn insert_char_replaces_selection() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "hello world");
+        app.handle_command(Command::SelectWordRight);
+        app.handle_command(Command::InsertChar('X'));
+        assert_eq!(app.buffer.text().to_string(), "Xworld");
+        let cursor = app.buffer.cursors().primary();
+        assert_eq!(cursor.position(), Position::new(0, 1));
+        assert!(!cursor.has_selection());
+    }
+
+    #[test]
+    fn delete_backward_removes_selection() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "abc\ndef");
+        app.handle_command(Command::MoveRight);
+        app.handle_command(Command::ExtendSelection(Direction::Down));
+        app.handle_command(Command::DeleteBackward);
+        assert_eq!(app.buffer.text().to_string(), "aef");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 1)
+        );
+    }
+
+    #[test]
+    fn delete_forward_removes_selection() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "abcdef");
+        app.handle_command(Command::MoveLineEnd);
+        app.handle_command(Command::ExtendSelection(Direction::Left));
+        app.handle_command(Command::ExtendSelection(Direction::Left));
+        app.handle_command(Command::DeleteForward);
+        assert_eq!(app.buffer.text().to_string(), "abcd");
+    }
+
+    #[test]
+    fn select_all_covers_whole_buffer() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "one\ntwo");
+        app.handle_command(Command::SelectAll);
+        assert_eq!(app.selected_text().as_deref(), Some("one\ntwo"));
+    }
+
+    #[test]
+    fn copy_then_paste_duplicates_selection() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "abc");
+        app.handle_command(Command::SelectLineEnd);
+        app.handle_command(Command::Copy);
+        assert_eq!(app.clipboard.get().unwrap(), "abc");
+        // Copy leaves the text untouched
+        assert_eq!(app.buffer.text().to_string(), "abc");
+        app.handle_command(Command::MoveLineEnd);
+        app.handle_command(Command::Paste);
+        assert_eq!(app.buffer.text().to_string(), "abcabc");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 6)
+        );
+    }
+
+    #[test]
+    fn cut_removes_selection_into_clipboard() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "hello world");
+        app.handle_command(Command::SelectWordRight);
+        app.handle_command(Command::Cut);
+        assert_eq!(app.clipboard.get().unwrap(), "hello ");
+        assert_eq!(app.buffer.text().to_string(), "world");
+    }
+
+    #[test]
+    fn yank_pop_cycles_through_older_copies() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "one two ");
+        app.handle_command(Command::SelectWordRight);
+        app.handle_command(Command::Cut);
+        app.handle_command(Command::SelectWordRight);
+        app.handle_command(Command::Cut);
+        assert_eq!(app.kill_ring.iter().collect::<Vec<_>>(), ["two ", "one "]);
+
+        app.handle_command(Command::Paste);
+        assert_eq!(app.buffer.text().to_string(), "two ");
+        app.handle_command(Command::YankPop);
+        assert_eq!(app.buffer.text().to_string(), "one ");
+        // Wraps around to the newest.
+        app.handle_command(Command::YankPop);
+        assert_eq!(app.buffer.text().to_string(), "two ");
+
+        // Anything else in between ends the cycle.
+        app.handle_command(Command::MoveLeft);
+        app.handle_command(Command::YankPop);
+        assert_eq!(app.buffer.text().to_string(), "two ");
+
+        app.handle_command(Command::PasteFromHistory);
+        assert_eq!(app.input_mode, InputMode::KillRingPicker);
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.buffer.text().to_string(), "twoone  ");
+        assert_eq!(app.clipboard.get().unwrap(), "one ");
+        assert_eq!(app.kill_ring.newest(), Some("one "));
+    }
+
+    #[test]
+    fn paste_multiline_places_cursor_after_text() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "xy");
+        app.clipboard.set("1\n22").unwrap();
+        app.handle_command(Command::MoveRight);
+        app.handle_command(Command::Paste);
+        assert_eq!(app.buffer.text().to_string(), "x1\n22y");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 2)
+        );
+    }
+
+    #[test]
+    fn copy_without_selection_leaves_clipboard_alone() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "abc");
+        app.clipboard.set("keep").unwrap();
+        app.handle_command(Command::Copy);
+        assert_eq!(app.clipboard.get().unwrap(), "keep");
+    }
+
     // =====================================================================
     // JumpStack unit tests
     // =====================================================================
@@ -609,4 +1611,4591 @@ mod tests {
         app.handle_command(Command::JumpForward);
         assert_eq!(app.buffer.cursors().primary().position(), line2_pos);
     }
+
+    #[test]
+    fn goto_line_search_and_opening_files_push_jumps() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let other = dir.path().join("other.txt");
+        std::fs::write(&other, "other\n").expect("write");
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "alpha\nbeta\ngamma\nbeta\n");
+
+        app.confirm_goto_line("3");
+        assert_eq!(app.jump_stack.back_len(), 1);
+        // Staying on the line is not a jump.
+        app.confirm_goto_line("3");
+        assert_eq!(app.jump_stack.back_len(), 1);
+
+        app.confirm_find("beta");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(3, 0)
+        );
+        assert_eq!(app.jump_stack.back_len(), 2);
+        app.handle_command(Command::JumpBack);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(2, 0)
+        );
+
+        app.confirm_open(&other.to_string_lossy());
+        wait_for_open(&mut app);
+        assert_eq!(
+            app.jump_stack.locations().last(),
+            Some(&JumpLocation::new(None, Position::new(2, 0)))
+        );
+    }
+
+    #[test]
+    fn jump_to_last_edit_follows_the_most_recent_edit_across_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        std::fs::write(&first, "one\ntwo\nthree\n").expect("write");
+        std::fs::write(&second, "a\nb\n").expect("write");
+        let mut app = test_app();
+        app.handle_command(Command::JumpToLastEdit);
+        assert_eq!(app.messages.last().unwrap().text(), "No edits yet");
+
+        app.confirm_open(&first.to_string_lossy());
+        wait_for_open(&mut app);
+        app.confirm_goto_line("2");
+        app.handle_command(Command::MoveLineEnd);
+        app.insert_text("!");
+        app.buffer.save().expect("save");
+
+        app.confirm_open(&second.to_string_lossy());
+        wait_for_open(&mut app);
+        app.handle_command(Command::JumpToLastEdit);
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(first.as_path()));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 4)
+        );
+
+        // An edit in the open file is the one to go back to.
+        app.handle_command(Command::MoveBufferStart);
+        app.insert_text("#");
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::JumpToLastEdit);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 1)
+        );
+        app.handle_command(Command::JumpBack);
+        assert_eq!(app.buffer.cursors().primary().position().line, 2);
+    }
+
+    #[test]
+    fn marks_jump_across_files_and_list_with_their_lines() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        std::fs::write(&first, "one\n  two\nthree\n").expect("write");
+        std::fs::write(&second, "a\nb\n").expect("write");
+        let mut app = test_app();
+        app.handle_command(Command::SetMark('a'));
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Save the file before setting a mark"
+        );
+        app.handle_command(Command::ListMarks);
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        app.confirm_open(&first.to_string_lossy());
+        wait_for_open(&mut app);
+        app.confirm_goto_line("2");
+        app.handle_command(Command::SetMark('a'));
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Mark 'a' set at line 2"
+        );
+
+        app.confirm_open(&second.to_string_lossy());
+        wait_for_open(&mut app);
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::SetMark('b'));
+        app.handle_command(Command::GotoMark('z'));
+        assert_eq!(app.messages.last().unwrap().text(), "Mark 'z' is not set");
+
+        app.handle_command(Command::GotoMark('a'));
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(first.as_path()));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 0)
+        );
+
+        // The picker shows each mark's file, line and text, in name order.
+        app.handle_command(Command::ListMarks);
+        assert_eq!(app.input_mode, InputMode::MarkPicker);
+        let picker = &mut app.mark_picker.as_mut().unwrap().picker;
+        let rows: Vec<(String, String)> = picker
+            .visible(10)
+            .into_iter()
+            .map(|(item, _)| (item.label.clone(), item.detail.clone()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("a  first.txt:2".to_string(), "two".to_string()),
+                ("b  second.txt:2".to_string(), "b".to_string()),
+            ]
+        );
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::InsertNewline);
+        wait_for_open(&mut app);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.buffer.path(), Some(second.as_path()));
+        assert_eq!(app.buffer.cursors().primary().position().line, 1);
+    }
+
+    #[test]
+    fn open_forwarded_opens_file_at_line() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("fwd.txt");
+        std::fs::write(&path, "a\nb\nc\n").expect("write");
+
+        let mut app = test_app();
+        app.open_forwarded(smash_platform::OpenRequest::new(path.clone(), Some(3)));
+        wait_for_open(&mut app);
+
+        assert_eq!(app.buffer.path(), Some(path.as_path()));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(2, 0)
+        );
+        assert_eq!(app.jump_stack.back_len(), 1);
+    }
+
+    #[test]
+    fn alternate_buffer_flips_between_the_last_two_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let source = dir.path().join("lib.rs");
+        let test = dir.path().join("lib_test.rs");
+        std::fs::write(&source, "fn a() {}\nfn b() {}\n").expect("write");
+        std::fs::write(&test, "#[test]\n").expect("write");
+
+        let mut app = test_app();
+        app.handle_command(Command::AlternateBuffer);
+        assert_eq!(app.messages.last().unwrap().text(), "No alternate file");
+
+        app.open_path(source.clone(), Some(Position::new(1, 3)), false);
+        wait_for_open(&mut app);
+        assert!(app.alternate_file.is_none());
+        app.open_path(test.clone(), None, false);
+        wait_for_open(&mut app);
+        assert!(app.normal_status().0.contains("[#lib.rs]"));
+
+        app.handle_command(Command::AlternateBuffer);
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(source.as_path()));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 3)
+        );
+        assert!(app.normal_status().0.contains("[#lib_test.rs]"));
+
+        // Unsaved changes keep the file open.
+        app.handle_command(Command::InsertChar('x'));
+        app.handle_command(Command::AlternateBuffer);
+        assert_eq!(app.buffer.path(), Some(source.as_path()));
+        assert_eq!(
+            app.messages.last().unwrap().level(),
+            smash_core::message::MessageLevel::Warning
+        );
+    }
+
+    #[test]
+    fn companion_file_jumps_between_source_and_test() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let project = dir.path().join("project");
+        let source = project.join("src").join("net").join("http.rs");
+        std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+        std::fs::write(&source, "pub fn get() {}\n").unwrap();
+        let mut app = config_test_app(dir.path());
+        app.open_path(source.clone(), None, false);
+        wait_for_open(&mut app);
+
+        // A missing test is offered, and created from the template.
+        app.handle_command(Command::GoToCompanionFile);
+        assert_eq!(app.input_mode, InputMode::PromptCreateCompanion);
+        let test = project.join("tests").join("net").join("http.rs");
+        assert!(app
+            .create_companion_prompt()
+            .starts_with(&test.display().to_string()));
+        app.handle_command(Command::InsertChar('y'));
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(test.as_path()));
+        assert_eq!(
+            std::fs::read_to_string(&test).unwrap(),
+            "#[test]\nfn http() {\n}\n"
+        );
+
+        app.handle_command(Command::GoToCompanionFile);
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(source.as_path()));
+
+        // Configured patterns replace the built-in ones; declining
+        // creates nothing.
+        app.language_configs.insert(
+            "rust".to_string(),
+            smash_config::config::LanguageConfig {
+                companions: Some(vec![smash_config::config::CompanionConfig {
+                    source: "src/{dir}{name}.rs".to_string(),
+                    test: "src/{dir}{name}_tests.rs".to_string(),
+                    template: None,
+                }]),
+                ..Default::default()
+            },
+        );
+        app.handle_command(Command::GoToCompanionFile);
+        assert!(app.create_companion_prompt().contains("http_tests.rs"));
+        app.handle_command(Command::InsertChar('n'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(!source.with_file_name("http_tests.rs").exists());
+
+        let mut app = test_app();
+        app.handle_command(Command::GoToCompanionFile);
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn open_link_under_cursor_follows_locations_and_urls() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, "see b.rs:2:3 or https://x.io/p.\n").unwrap();
+        std::fs::write(dir.path().join("b.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        let mut app = test_app();
+        let opened = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let record = opened.clone();
+        app.url_opener = Box::new(move |url| {
+            record.borrow_mut().push(url.to_string());
+            Ok(())
+        });
+        app.open_path(notes.clone(), None, false);
+        wait_for_open(&mut app);
+
+        app.handle_command(Command::OpenLinkUnderCursor);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "No link under the cursor"
+        );
+        // The URL stops before the full stop ending the sentence.
+        app.set_cursor(Position::new(0, 20));
+        app.handle_command(Command::OpenLinkUnderCursor);
+        assert_eq!(*opened.borrow(), vec!["https://x.io/p".to_string()]);
+
+        // A relative location is found next to the file, and leaves a
+        // jump back.
+        app.set_cursor(Position::new(0, 6));
+        app.handle_command(Command::OpenLinkUnderCursor);
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(dir.path().join("b.rs").as_path()));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 2)
+        );
+        app.handle_command(Command::JumpBack);
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(notes.as_path()));
+    }
+
+    /// Create an App backed by a real file with a live LSP command receiver.
+    fn lsp_test_app(
+        sync: TextDocumentSyncKind,
+    ) -> (
+        App,
+        tokio::sync::mpsc::Receiver<LspCommand>,
+        tempfile::TempDir,
+    ) {
+        let (dir, path) = temp_doc("hello\nworld\n");
+        let (mut app, lsp_cmd_rx) = TestApp::default()
+            .file(path)
+            .lsp(std::collections::HashMap::new())
+            .build_with_lsp();
+        app.lsp_server_started = true;
+        app.lsp_sync_kind = sync;
+        (app, lsp_cmd_rx, dir)
+    }
+
+    /// Open the document on the server, dropping the didOpen.
+    fn open_on_server(app: &mut App, rx: &mut tokio::sync::mpsc::Receiver<LspCommand>) {
+        app.lsp_did_open();
+        assert!(matches!(rx.try_recv(), Ok(LspCommand::DidOpen { .. })));
+    }
+
+    #[test]
+    fn incremental_sync_sends_ranged_changes() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Incremental);
+        open_on_server(&mut app, &mut rx);
+        app.handle_command(Command::InsertChar('X'));
+        match rx.try_recv().expect("didChange sent") {
+            LspCommand::DidChange { changes, .. } => {
+                assert_eq!(changes.len(), 1);
+                assert_eq!(changes[0].text, "X");
+                let range = changes[0].range.expect("ranged change");
+                assert_eq!((range.start.line, range.start.character), (0, 0));
+                assert_eq!(range.start, range.end);
+            }
+            _ => panic!("expected DidChange"),
+        }
+        assert!(app.pending_edits.is_empty());
+    }
+
+    #[test]
+    fn ranged_changes_count_columns_in_the_servers_encoding() {
+        use smash_core::edit::EditCommand;
+        use smash_lsp::LspPosition;
+
+        let (mut app, mut rx, dir) = lsp_test_app(TextDocumentSyncKind::Incremental);
+        app.buffer = Buffer::from_text(BufferId::next(), "a😀b\n");
+        app.buffer.set_path(dir.path().join("doc.txt"));
+        open_on_server(&mut app, &mut rx);
+        let ranges = |rx: &mut tokio::sync::mpsc::Receiver<LspCommand>| match rx.try_recv() {
+            Ok(LspCommand::DidChange { changes, .. }) => changes
+                .iter()
+                .map(|c| c.range.expect("ranged change"))
+                .collect::<Vec<_>>(),
+            _ => panic!("expected DidChange"),
+        };
+
+        // The emoji is two UTF-16 code units.
+        app.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(0, 2));
+        app.handle_command(Command::DeleteBackward);
+        let deleted = ranges(&mut rx);
+        assert_eq!(
+            (deleted[0].start, deleted[0].end),
+            (LspPosition::new(0, 1), LspPosition::new(0, 3))
+        );
+
+        // Each edit is placed in the text the ones before it left.
+        for (col, text) in [(0, "😀"), (2, "x")] {
+            let events = app
+                .buffer
+                .apply_edit(EditCommand::Insert {
+                    pos: Position::new(0, col),
+                    text: text.into(),
+                })
+                .unwrap();
+            app.record_edit_events(&events);
+        }
+        app.lsp_did_change();
+        let inserted = ranges(&mut rx);
+        assert_eq!(inserted[0].start, LspPosition::new(0, 0));
+        assert_eq!(inserted[1].start, LspPosition::new(0, 3));
+
+        app.lsp_position_encoding = PositionEncoding::Utf8;
+        assert_eq!(
+            app.lsp_position(Position::new(0, 1)),
+            LspPosition::new(0, 4)
+        );
+        assert_eq!(
+            app.buffer_position(LspPosition::new(0, 4)),
+            Position::new(0, 1)
+        );
+    }
+
+    #[test]
+    fn server_started_for_another_language_leaves_buffer_alone() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        app.lsp_server_started = false;
+        app.language_id = Some("rust".to_string());
+        let started = |language: &str| LspEvent::ServerStarted {
+            language: language.to_string(),
+            sync: TextDocumentSyncKind::Incremental,
+            save: Default::default(),
+            will_save_wait_until: false,
+            formatting: true,
+            range_formatting: true,
+            inlay_hints: false,
+            completion_triggers: Vec::new(),
+            signature_triggers: Vec::new(),
+            position_encoding: Default::default(),
+        };
+
+        app.handle_lsp_event(started("python"));
+        assert!(!app.lsp_server_started);
+        assert!(!app.lsp_formatting);
+        assert!(rx.try_recv().is_err());
+
+        app.handle_lsp_event(started("rust"));
+        assert!(app.lsp_server_started);
+        assert_eq!(app.lsp_sync_kind, TextDocumentSyncKind::Incremental);
+        match rx.try_recv().expect("didOpen sent") {
+            LspCommand::DidOpen { language_id, .. } => assert_eq!(language_id, "rust"),
+            _ => panic!("expected DidOpen"),
+        }
+    }
+
+    #[test]
+    fn full_sync_sends_whole_document() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        app.handle_command(Command::InsertChar('X'));
+        match rx.try_recv().expect("didChange sent") {
+            LspCommand::DidChange { changes, .. } => {
+                assert_eq!(changes.len(), 1);
+                assert!(changes[0].range.is_none());
+                assert_eq!(changes[0].text, "Xhello\nworld\n");
+            }
+            _ => panic!("expected DidChange"),
+        }
+    }
+
+    #[test]
+    fn undo_is_synced_incrementally() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Incremental);
+        open_on_server(&mut app, &mut rx);
+        app.handle_command(Command::InsertChar('X'));
+        let _ = rx.try_recv();
+        app.handle_command(Command::Undo);
+        match rx.try_recv().expect("didChange sent for undo") {
+            LspCommand::DidChange {
+                changes, version, ..
+            } => {
+                assert_eq!(version, 3);
+                assert_eq!(changes[0].text, "");
+                let range = changes[0].range.expect("ranged change");
+                assert_eq!((range.end.line, range.end.character), (0, 1));
+            }
+            _ => panic!("expected DidChange"),
+        }
+    }
+
+    #[test]
+    fn incremental_sync_diffs_edits_it_did_not_see() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Incremental);
+        app.lsp_did_open();
+        let _ = rx.try_recv();
+        // Edit the buffer behind the App's back, as a reload would.
+        app.buffer
+            .apply_edit(smash_core::edit::EditCommand::Insert {
+                pos: Position::new(1, 0),
+                text: "new ".into(),
+            })
+            .unwrap();
+        app.lsp_did_change();
+        match rx.try_recv().expect("didChange sent") {
+            LspCommand::DidChange { changes, .. } => {
+                assert_eq!(changes.len(), 1);
+                assert_eq!(changes[0].text, "new world\n");
+                let range = changes[0].range.expect("ranged change");
+                assert_eq!((range.start.line, range.start.character), (1, 0));
+                assert_eq!((range.end.line, range.end.character), (2, 0));
+            }
+            _ => panic!("expected DidChange"),
+        }
+        // Nothing changed since: nothing to send.
+        app.lsp_did_change();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn no_sync_server_receives_nothing() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::None);
+        app.handle_command(Command::InsertChar('X'));
+        assert!(rx.try_recv().is_err());
+        assert!(app.pending_edits.is_empty());
+    }
+
+    #[test]
+    fn enter_indents_for_the_language() {
+        let mut app = test_app();
+        let mut buffer = Buffer::from_text(BufferId::next(), "");
+        buffer.set_path("main.rs".into());
+        app.buffer = buffer;
+        for c in "fn main() {}".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::MoveLeft);
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.buffer.text().to_string(), "fn main() {\n    \n}");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 4)
+        );
+
+        for c in "if x {\n".chars() {
+            app.handle_command(match c {
+                '\n' => Command::InsertNewline,
+                c => Command::InsertChar(c),
+            });
+        }
+        app.handle_command(Command::InsertChar('}'));
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "fn main() {\n    if x {\n    }\n}"
+        );
+    }
+
+    #[test]
+    fn enter_follows_language_overrides() {
+        let mut app = test_app();
+        let mut config = smash_config::Config::default();
+        config.languages.insert(
+            "python".to_string(),
+            smash_config::config::LanguageConfig {
+                tab_size: Some(2),
+                ..Default::default()
+            },
+        );
+        config.languages.insert(
+            "go".to_string(),
+            smash_config::config::LanguageConfig {
+                auto_indent: Some(false),
+                ..Default::default()
+            },
+        );
+        app.apply_config(&config);
+
+        let mut buffer = Buffer::from_text(BufferId::next(), "  if x:");
+        buffer.set_path("a.py".into());
+        app.buffer = buffer;
+        app.handle_command(Command::MoveLineEnd);
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.buffer.text().to_string(), "  if x:\n    ");
+
+        let mut buffer = Buffer::from_text(BufferId::next(), "\tfunc() {");
+        buffer.set_path("a.go".into());
+        app.buffer = buffer;
+        app.handle_command(Command::MoveLineEnd);
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.buffer.text().to_string(), "\tfunc() {\n");
+    }
+
+    #[test]
+    fn export_writes_selection_as_html() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let out = dir.path().join("snippet.html");
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "alpha\nbeta\n");
+        app.handle_command(Command::SelectLineEnd);
+
+        app.handle_command(Command::ExportHighlighted);
+        assert_eq!(app.input_mode, InputMode::PromptExport);
+        for c in out.to_string_lossy().chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let html = std::fs::read_to_string(&out).expect("export written");
+        assert!(html.contains(">alpha</span>"));
+        assert!(!html.contains("beta"));
+    }
+
+    #[test]
+    fn export_line_range_with_numbers_as_ansi() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let out = dir.path().join("snippet.txt");
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "one\ntwo\nthree\n");
+        app.confirm_export(&format!("{} -n 2-3", out.display()));
+
+        let text = std::fs::read_to_string(&out).expect("export written");
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("2 ") && lines[0].contains("two"));
+        assert!(lines[1].contains("3 ") && lines[1].contains("three"));
+    }
+
+    #[test]
+    fn clear_undo_history_and_file_info() {
+        let mut app = test_app();
+        app.set_undo_budget(UndoBudget {
+            max_entries: 2,
+            max_bytes: usize::MAX,
+        });
+        // Each word is its own undo step.
+        for c in "a b c".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        assert_eq!(app.buffer.undo_len(), 2);
+
+        app.handle_command(Command::ClearUndoHistory);
+        assert_eq!(app.buffer.undo_len(), 0);
+        app.handle_command(Command::Undo);
+        assert_eq!(app.buffer.text().to_string(), "a b c");
+
+        app.handle_command(Command::FileInfo);
+        let popup = app.popup.as_ref().expect("file info popup");
+        let text = popup.lines.join("\n");
+        assert!(text.contains("Undo: 0 entries"), "{}", text);
+        assert!(text.contains("Total: "), "{}", text);
+    }
+
+    #[test]
+    fn idle_compaction_repacks_text_and_warns_over_budget() {
+        let mut app = test_app();
+        let line = "x".repeat(99);
+        app.buffer = Buffer::from_text(BufferId::next(), &format!("{}\n", line).repeat(12_000));
+        app.editor_config.memory_budget_mb = 1;
+        app.handle_command(Command::MoveRight);
+        for i in 0..memory::COMPACT_AFTER_EDITS {
+            app.buffer
+                .apply_edit(smash_core::edit::EditCommand::Insert {
+                    pos: Position::new(i * 11, 50),
+                    text: "y".to_string(),
+                })
+                .unwrap();
+        }
+        assert!(!app.idle_tasks.contains(&IdleTask::Compact));
+        // Queued after the next command.
+        app.handle_command(Command::MoveRight);
+        assert!(app.idle_tasks.contains(&IdleTask::Compact));
+
+        let before = app.memory_report();
+        while app.run_idle_task() || app.has_idle_tasks() {}
+        let after = app.memory_report();
+        assert!(after.text < before.text);
+        assert_eq!(app.buffer.edits_since_compact(), 0);
+        // 1.2 MB of text outgrows a 1 MiB budget.
+        assert!(after.total() > 1024 * 1024, "{:?}", after);
+        let warning = app.messages.last().unwrap().text().to_string();
+        assert!(
+            warning.contains("exceeds the 1.0 MiB budget"),
+            "{}",
+            warning
+        );
+
+        // Warned once until usage drops back under the budget.
+        app.messages.info("other");
+        app.compact_buffer();
+        assert_eq!(app.messages.last().unwrap().text(), "other");
+        app.editor_config.memory_budget_mb = 0;
+        app.compact_buffer();
+        assert!(!app.memory_warned);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn task_errors_fill_the_quickfix_list() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let file = dir.path().join("main.c");
+        std::fs::write(&file, "int main() {\n  return x;\n}\n").unwrap();
+        let mut app = test_app();
+        let mut config = smash_config::Config::default();
+        config.tasks.insert(
+            "build".into(),
+            format!(
+                "echo '{}:2:10: error: x undeclared'; exit 1",
+                file.display()
+            ),
+        );
+        app.apply_config(&config);
+
+        app.handle_command(Command::RunTask);
+        assert_eq!(app.input_mode, InputMode::PromptTask);
+        assert_eq!(app.prompt_input, "build");
+        app.handle_command(Command::InsertNewline);
+        wait_until(
+            &mut app,
+            "task",
+            |app| app.search_results.is_some(),
+            App::poll_task,
+        );
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Task build failed (1): 1 error"
+        );
+        assert!(app.normal_status().0.contains("[task build: failed (1)]"));
+        let results = app.search_results.as_ref().unwrap();
+        assert_eq!(results.title(), "Task build: 1 problems in 1 files");
+        assert!(results.lines()[0].ends_with("main.c:2:10: error: x undeclared"));
+        // The list does not take keys away from the editor.
+        assert!(!app.search_results_focused());
+
+        app.handle_command(Command::QuickfixNext);
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(file.as_path()));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 9)
+        );
+        app.handle_command(Command::QuickfixNext);
+        assert_eq!(app.messages.last().unwrap().text(), "No more items");
+
+        app.confirm_task("");
+        assert!(app.task.is_none());
+    }
+
+    #[test]
+    fn show_diagnostics_lists_them_in_the_quickfix_pane() {
+        use smash_lsp::{Diagnostic, DiagnosticSeverity, LspPosition, LspRange};
+
+        let mut app = test_app();
+        app.insert_text("one\ntwo\n");
+        app.buffer.set_path(PathBuf::from("/tmp/notes.rs"));
+        app.handle_command(Command::ShowDiagnostics);
+        assert_eq!(app.messages.last().unwrap().text(), "No diagnostics");
+
+        let diagnostic = |line, severity, message: &str| Diagnostic {
+            range: LspRange::new(LspPosition::new(line, 2), LspPosition::new(line, 3)),
+            severity: Some(severity),
+            message: message.to_string(),
+            source: None,
+            code: None,
+            code_description: None,
+            related_information: Vec::new(),
+        };
+        app.current_diagnostics = vec![
+            diagnostic(1, DiagnosticSeverity::Warning, "unused\ndetails"),
+            diagnostic(0, DiagnosticSeverity::Error, "broken"),
+        ];
+        app.handle_command(Command::ShowDiagnostics);
+        let results = app.search_results.as_ref().unwrap();
+        assert_eq!(results.title(), "Diagnostics: 2 problems in 1 files");
+        assert_eq!(
+            results.lines(),
+            [
+                "/tmp/notes.rs:1:3: error: broken",
+                "/tmp/notes.rs:2:3: warning: unused"
+            ]
+        );
+        assert!(app.search_results_focused());
+        assert!(app.normal_status().0.contains("[quickfix]"));
+
+        app.handle_command(Command::QuickfixNext);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 2)
+        );
+        app.handle_command(Command::QuickfixNext);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 2)
+        );
+        app.confirm_vim_command("cp");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 2)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shell_commands_filter_insert_and_show_output() {
+        use smash_input::{Key, KeyEvent, Modifiers};
+        fn finish(app: &mut App) {
+            wait_until(
+                app,
+                "shell command",
+                |app| app.shell_run.is_none(),
+                App::poll_shell,
+            );
+        }
+        let mut app = test_app();
+        app.insert_text("b\nc\na\nend");
+        app.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_anchor(Position::new(0, 0));
+        app.set_cursor(Position::new(3, 0));
+        app.handle_command(Command::RunShellCommand);
+        assert_eq!(app.input_mode, InputMode::PromptShellCommand);
+        for c in "|sort".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.running_commands, ["sort"]);
+        finish(&mut app);
+        assert_eq!(app.buffer.text().to_string(), "a\nb\nc\nend");
+        assert!(app.running_commands.is_empty());
+
+        // Without a selection the cursor line is filtered.
+        app.set_cursor(Position::new(3, 1));
+        app.confirm_shell_command("|tr a-z A-Z");
+        finish(&mut app);
+        assert_eq!(app.buffer.text().to_string(), "a\nb\nc\nEND");
+        app.run_line_command("1,2!tr a-z A-Z");
+        finish(&mut app);
+        assert_eq!(app.buffer.text().to_string(), "A\nB\nc\nEND");
+
+        app.set_cursor(Position::new(2, 1));
+        app.confirm_shell_command("<echo hi");
+        finish(&mut app);
+        assert_eq!(app.buffer.text().to_string(), "A\nB\nchi\nEND");
+
+        // A failing filter leaves the text alone.
+        app.confirm_shell_command("|echo nope >&2; exit 1");
+        finish(&mut app);
+        assert_eq!(app.buffer.text().to_string(), "A\nB\nchi\nEND");
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "echo nope >&2; exit 1 failed: nope"
+        );
+
+        app.confirm_vim_command("!echo out; echo src/main.rs:3 >&2; exit 2");
+        finish(&mut app);
+        let view = app.shell_output.as_ref().expect("output pane");
+        assert_eq!(
+            view.lines(),
+            [
+                "$ echo out; echo src/main.rs:3 >&2; exit 2",
+                "out",
+                "src/main.rs:3"
+            ]
+        );
+        assert!(view.title().contains("(exit 2)"));
+        assert!(app.search_results_focused());
+        app.handle_search_results_key(&KeyEvent::new(Key::Esc, Modifiers::NONE));
+        assert!(app.shell_output.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn watch_runs_on_save_of_matching_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut app = test_app();
+        app.handle_command(Command::WatchCommand);
+        assert_eq!(app.input_mode, InputMode::PromptWatch);
+        for c in "*.txt echo built".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        assert!(app.watch.is_some());
+
+        app.confirm_save_as(&dir.path().join("notes.md").to_string_lossy());
+        assert_eq!(
+            app.watch.as_ref().unwrap().status(),
+            crate::job::JobStatus::Idle
+        );
+
+        app.confirm_save_as(&dir.path().join("notes.txt").to_string_lossy());
+        wait_until(
+            &mut app,
+            "watch",
+            |app| app.watch.as_ref().unwrap().status() != crate::job::JobStatus::Running,
+            App::poll_watch,
+        );
+        let watch = app.watch.as_ref().unwrap();
+        assert_eq!(watch.status(), crate::job::JobStatus::Succeeded);
+        assert!(watch.output().iter().any(|l| l == "built"));
+        assert!(app.normal_status().0.contains("[watch: ok]"));
+
+        app.confirm_watch("");
+        assert!(app.watch.is_none());
+    }
+
+    #[test]
+    fn diff_against_disk_lists_hunks_and_reverts_them() {
+        use smash_core::diff::DiffKind;
+        use smash_input::{Key, KeyEvent, Modifiers};
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("list.txt");
+        let lines: Vec<String> = (1..=12).map(|n| format!("line {}\n", n)).collect();
+        std::fs::write(&path, lines.concat()).unwrap();
+        let mut app = test_app();
+        app.open_path(path, None, false);
+        wait_for_open(&mut app);
+
+        app.handle_command(Command::DiffAgainstDisk);
+        assert!(app.diff_view.is_none());
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "No changes against disk"
+        );
+
+        // Change the first line and the last.
+        app.handle_command(Command::InsertChar('*'));
+        app.handle_command(Command::MoveBufferEnd);
+        app.handle_command(Command::InsertChar('+'));
+        app.handle_command(Command::DiffAgainstDisk);
+        let view = app.diff_view.as_ref().unwrap();
+        assert!(view.title().contains("(2 hunks)"));
+        let rows = view.rows();
+        assert_eq!(rows[0], (None, "@@ -1,4 +1,4 @@".to_string()));
+        assert_eq!(rows[1], (Some(DiffKind::Removed), "-line 1".to_string()));
+        assert_eq!(rows[2], (Some(DiffKind::Added), "+*line 1".to_string()));
+        assert!(app.normal_status().0.contains("[diff]"));
+
+        let key = |c| KeyEvent::new(Key::Char(c), Modifiers::NONE);
+        assert!(app.handle_search_results_key(&key('n')));
+        assert_eq!(
+            app.diff_view.as_ref().unwrap().rows()[app.diff_view.as_ref().unwrap().list.selected()]
+                .1,
+            "@@ -10,3 +10,4 @@"
+        );
+        app.handle_search_results_key(&key('r'));
+        assert!(!app.buffer.text().to_string().ends_with('+'));
+        assert!(app.diff_view.as_ref().unwrap().title().contains("(1 hunk)"));
+        app.handle_search_results_key(&key('r'));
+        assert_eq!(app.buffer.text().to_string(), lines.concat());
+        assert!(app.diff_view.is_none());
+    }
+
+    #[test]
+    fn git_blame_annotates_the_cursor_line() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .env("GIT_AUTHOR_DATE", "2024-03-01T12:00:00Z")
+                .output()
+                .map(|o| o.status.success());
+            status.unwrap_or(false)
+        };
+        if !git(&["init", "-q"]) {
+            return; // No git available.
+        }
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "first\nsecond\n").unwrap();
+        assert!(git(&["add", "notes.txt"]));
+        assert!(git(&["commit", "-q", "-m", "Add notes"]));
+
+        let mut app = test_app();
+        app.open_path(path, None, false);
+        wait_for_open(&mut app);
+        app.handle_command(Command::ToggleGitBlame);
+        wait_until(
+            &mut app,
+            "blame",
+            |app| app.blame_annotation().is_some(),
+            |app| app.poll_git_blame(std::time::Instant::now()),
+        );
+        assert_eq!(
+            app.blame_annotation().as_deref(),
+            Some("Ada, 2024-03-01 • Add notes")
+        );
+
+        // An unsaved line is blamed on the working copy.
+        app.handle_command(Command::InsertNewline);
+        app.handle_command(Command::MoveUp);
+        wait_until(
+            &mut app,
+            "blame refresh",
+            |app| app.blame_annotation().as_deref() == Some("You, not committed yet"),
+            |app| app.poll_git_blame(std::time::Instant::now() + std::time::Duration::from_secs(2)),
+        );
+
+        app.handle_command(Command::ToggleGitBlame);
+        assert!(app.blame_annotation().is_none());
+    }
+
+    #[test]
+    fn show_help_opens_read_only_keymap_page() {
+        let mut app = test_app();
+        app.handle_command(Command::ShowHelp);
+        assert_eq!(app.filename.as_deref(), Some("[help]"));
+        assert!(app.buffer.is_read_only());
+        let text = app.buffer.text().to_string();
+        assert!(text.contains("preset: default"));
+        assert!(text.contains("Save the current file"));
+
+        app.handle_command(Command::InsertChar('x'));
+        assert_eq!(app.buffer.text().to_string(), text);
+        assert_eq!(app.messages.last().unwrap().text(), "Buffer is read-only");
+    }
+
+    #[test]
+    fn show_help_refuses_when_buffer_dirty() {
+        let mut app = test_app();
+        app.handle_command(Command::InsertChar('x'));
+        app.handle_command(Command::ShowHelp);
+        assert!(!app.buffer.is_read_only());
+        assert_eq!(app.buffer.text().to_string(), "x");
+    }
+
+    fn config_test_app(dir: &std::path::Path) -> App {
+        let mut app = test_app();
+        app.config_paths = Some(ConfigPaths {
+            config_dir: dir.join("config"),
+            project_dir: Some(dir.join("project")),
+            log_file: dir.join("smash.log"),
+        });
+        app
+    }
+
+    #[test]
+    fn palette_matches_names_and_descriptions() {
+        assert_eq!(commands::palette_match("save"), Some(Command::Save));
+        assert_eq!(
+            commands::palette_match("reloadconfig"),
+            Some(Command::ReloadConfig)
+        );
+        assert_eq!(
+            commands::palette_match("open log"),
+            Some(Command::OpenLogFile)
+        );
+        assert_eq!(commands::palette_match("   "), None);
+    }
+
+    #[test]
+    fn palette_runs_command_that_opens_prompt() {
+        let mut app = test_app();
+        app.handle_command(Command::OpenCommandPalette);
+        assert_eq!(app.input_mode, InputMode::CommandPalette);
+        for c in "goto".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.input_mode, InputMode::PromptGoToLine);
+    }
+
+    #[test]
+    fn open_project_config_creates_template() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut app = config_test_app(dir.path());
+        app.handle_command(Command::OpenProjectConfig);
+        wait_for_open(&mut app);
+        let path = dir
+            .path()
+            .join("project")
+            .join(".smash")
+            .join("config.toml");
+        assert!(path.exists());
+        assert_eq!(app.buffer.path(), Some(path.as_path()));
+        assert!(app
+            .buffer
+            .text()
+            .to_string()
+            .starts_with("# SMASH configuration"));
+    }
+
+    #[test]
+    fn saving_user_config_reloads_it() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut app = config_test_app(dir.path());
+        app.handle_command(Command::OpenUserConfig);
+        wait_for_open(&mut app);
+        app.handle_command(Command::MoveBufferEnd);
+        for c in "[keymap]\npreset = \"emacs\"\n".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::Save);
+        assert_eq!(app.keymap_preset, "emacs");
+        assert_eq!(app.messages.last().unwrap().text(), "Config reloaded");
+    }
+
+    #[test]
+    fn open_log_file_is_read_only() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut app = config_test_app(dir.path());
+        app.handle_command(Command::OpenLogFile);
+        assert!(app.buffer.path().is_none());
+
+        std::fs::write(dir.path().join("smash.log"), "INFO started\n").unwrap();
+        app.handle_command(Command::OpenLogFile);
+        wait_for_open(&mut app);
+        assert!(app.buffer.is_read_only());
+        assert!(app.buffer.text().to_string().contains("INFO started"));
+    }
+
+    #[test]
+    fn terminal_pane_renders_and_takes_focus() {
+        use smash_input::{Key, KeyEvent, Modifiers};
+        use smash_terminal::{MockPty, TerminalPane, TerminalSize};
+
+        let mut app = test_app();
+        let size = TerminalSize::new(80, 5);
+        let mut pty = MockPty::new(size);
+        pty.set_read_data(b"hello\r\n$ ");
+        app.terminal = Some(TerminalPane::new(Box::new(pty), size));
+        app.terminal_visible = true;
+        assert!(app.poll_terminal());
+
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        // 23 body rows: 14 for the editor, 9 for the terminal (a title
+        // bar and 8 rows of screen).
+        assert!(backend.read_row(14).starts_with(" Terminal: "));
+        assert_eq!(backend.read_row(15), "hello");
+        assert_eq!(backend.read_row(16), "$");
+        assert_eq!(backend.cursor_position(), (2, 16));
+        assert_eq!(app.terminal.as_ref().unwrap().grid().size().rows, 8);
+        assert!(app.normal_status().0.contains("[terminal]"));
+
+        assert!(app.handle_terminal_key(&KeyEvent::char('x')));
+        let toggle = KeyEvent::new(Key::Char('\\'), Modifiers::CTRL);
+        assert!(!app.handle_terminal_key(&toggle));
+        app.handle_command(Command::ToggleTerminal);
+        assert!(!app.handle_terminal_key(&KeyEvent::char('x')));
+        app.handle_command(Command::ToggleTerminal);
+        assert!(app.terminal_focused());
+
+        // After exit the pane stays up with its output and status.
+        app.terminal.as_mut().unwrap().close().expect("close");
+        assert!(app.poll_terminal());
+        assert!(!app.poll_terminal());
+        assert!(app.terminal_visible);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Terminal exited with code 0"
+        );
+        app.render(&mut backend).expect("render");
+        assert!(backend
+            .read_row(14)
+            .ends_with("(exited with code 0, press Enter to restart)"));
+        assert_eq!(backend.read_row(15), "hello");
+        assert!(app.handle_terminal_key(&KeyEvent::char('x')));
+        app.handle_command(Command::ToggleTerminal);
+        app.handle_command(Command::TerminalKill);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "The terminal has exited"
+        );
+    }
+
+    #[test]
+    fn terminal_commands_send_signals() {
+        use smash_terminal::{MockPty, ProcessStatus, TerminalPane, TerminalSize};
+
+        let mut app = test_app();
+        app.handle_command(Command::TerminalInterrupt);
+        assert_eq!(app.messages.last().unwrap().text(), "No terminal running");
+
+        let size = TerminalSize::new(80, 5);
+        app.terminal = Some(TerminalPane::new(Box::new(MockPty::new(size)), size));
+        app.handle_command(Command::TerminalInterrupt);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Sent SIGINT to the terminal"
+        );
+        assert_eq!(
+            app.terminal.as_ref().unwrap().status(),
+            ProcessStatus::Running
+        );
+        app.handle_command(Command::TerminalTerminate);
+        assert_eq!(
+            app.terminal.as_ref().unwrap().status(),
+            ProcessStatus::Exited(143)
+        );
+        assert!(app.poll_terminal());
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Terminal exited with code 143"
+        );
+        assert!(app
+            .terminal_title()
+            .contains("(exited with code 143 (SIGTERM)"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn toggle_terminal_spawns_shell_and_reports_exit() {
+        use smash_input::{Key, KeyEvent, Modifiers};
+
+        let mut app = test_app();
+        app.terminal_shell = Some("sh".to_string());
+        app.handle_command(Command::ToggleTerminal);
+        assert!(app.terminal_focused());
+
+        for c in "exit 4".chars() {
+            assert!(app.handle_terminal_key(&KeyEvent::char(c)));
+        }
+        assert!(app.handle_terminal_key(&KeyEvent::new(Key::Enter, Modifiers::NONE)));
+
+        let wait_for_exit = |app: &mut App| {
+            wait_until(
+                app,
+                "shell exit",
+                |app| !app.terminal.as_ref().unwrap().is_alive(),
+                App::poll_terminal,
+            );
+            app.poll_terminal();
+        };
+        wait_for_exit(&mut app);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Terminal exited with code 4"
+        );
+
+        // Enter restarts the shell in the same pane.
+        assert!(app.handle_terminal_key(&KeyEvent::new(Key::Enter, Modifiers::NONE)));
+        assert!(app.terminal.as_ref().unwrap().is_alive());
+        app.handle_command(Command::TerminalKill);
+        wait_for_exit(&mut app);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Terminal exited with code 137"
+        );
+    }
+
+    #[test]
+    fn set_log_level_prompt_reloads_filter() {
+        use tracing_subscriber::{reload, EnvFilter, Registry};
+
+        let mut app = test_app();
+        app.handle_command(Command::SetLogLevel);
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Logging is not initialised"
+        );
+
+        let (_layer, handle) = reload::Layer::<EnvFilter, Registry>::new(EnvFilter::new("info"));
+        app.log_filter = Some(LogFilter::new(handle, "info".to_string()));
+        app.handle_command(Command::SetLogLevel);
+        assert_eq!(app.input_mode, InputMode::PromptLogLevel);
+        assert_eq!(app.prompt_input, "info");
+        for c in ",smash_lsp::rpc=debug".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(
+            app.log_filter.as_ref().unwrap().current(),
+            "info,smash_lsp::rpc=debug"
+        );
+
+        app.confirm_log_level("smash_lsp=noisy");
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .starts_with("Invalid log filter"));
+
+        app.apply_config(&smash_config::Config::default());
+        assert_eq!(app.log_filter.as_ref().unwrap().current(), "info");
+    }
+
+    #[test]
+    fn show_diagnostic_at_cursor_opens_popup() {
+        use smash_input::event::MouseKind;
+        use smash_input::{Key, KeyEvent, Modifiers, MouseEvent};
+        use smash_lsp::{
+            CodeDescription, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity,
+            Location, LspPosition, LspRange,
+        };
+
+        let mut app = test_app();
+        for c in "let x = y;\nok".chars() {
+            match c {
+                '\n' => app.handle_command(Command::InsertNewline),
+                c => app.handle_command(Command::InsertChar(c)),
+            }
+        }
+        let range = LspRange::new(LspPosition::new(0, 8), LspPosition::new(0, 9));
+        app.current_diagnostics = vec![
+            Diagnostic {
+                range,
+                severity: Some(DiagnosticSeverity::Error),
+                message: "cannot find value `y`\nin this scope".to_string(),
+                source: Some("rustc".to_string()),
+                code: Some("E0425".to_string()),
+                code_description: Some(CodeDescription {
+                    href: "https://doc.rust-lang.org/error_codes/E0425.html".to_string(),
+                }),
+                related_information: vec![DiagnosticRelatedInformation {
+                    location: Location {
+                        uri: "file:///src/lib.rs".to_string(),
+                        range: LspRange::new(LspPosition::new(4, 2), LspPosition::new(4, 3)),
+                    },
+                    message: "a local variable `z` exists".to_string(),
+                }],
+            },
+            Diagnostic {
+                range,
+                severity: Some(DiagnosticSeverity::Hint),
+                message: "consider `z`".to_string(),
+                source: None,
+                code: None,
+                code_description: None,
+                related_information: Vec::new(),
+            },
+        ];
+
+        // Cursor is on line 1, which has no diagnostics.
+        app.handle_command(Command::ShowDiagnosticAtCursor);
+        assert!(app.popup.is_none());
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "No diagnostics on this line"
+        );
+
+        app.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(0, 3));
+        app.handle_command(Command::ShowDiagnosticAtCursor);
+        let popup = app.popup.clone().expect("popup");
+        assert_eq!(popup.title, "Line 1 (2 diagnostics)");
+        assert_eq!(
+            popup.lines,
+            vec![
+                "error: cannot find value `y`",
+                "  in this scope",
+                "  rustc [E0425]",
+                "  https://doc.rust-lang.org/error_codes/E0425.html",
+                "  lib.rs:5:3: a local variable `z` exists",
+                "",
+                "hint: consider `z`",
+            ]
+        );
+
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        assert!(backend.read_row(1).contains("Line 1 (2 diagnostics)"));
+        assert!(backend.read_row(2).contains("error: cannot find value `y`"));
+
+        assert!(app.dismiss_popup(&KeyEvent::new(Key::Esc, Modifiers::NONE)));
+        assert!(app.popup.is_none());
+        assert!(!app.dismiss_popup(&KeyEvent::char('a')));
+
+        // Clicking the gutter sign of line 1 reopens it; other rows only
+        // move the cursor.
+        let click = |row| MouseEvent {
+            kind: MouseKind::Down,
+            col: 0,
+            row,
+            modifiers: Modifiers::NONE,
+        };
+        assert!(app.handle_mouse(&click(1)));
+        assert!(app.popup.is_none());
+        assert!(app.handle_mouse(&click(0)));
+        assert!(app.popup.is_some());
+        assert!(!app.dismiss_popup(&KeyEvent::char('a')));
+        assert!(app.popup.is_none());
+    }
+
+    #[test]
+    fn config_keys_layer_over_preset() {
+        let mut app = test_app();
+        let config = smash_config::Config {
+            keys: [
+                ("ctrl-t", "show_help"),
+                ("ctrl-s", "noop"),
+                ("ctrl-q", "frobnicate"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            ..Default::default()
+        };
+        app.apply_config(&config);
+
+        let keymap = app.resolver.keymap();
+        assert_eq!(
+            keymap.resolve(&[smash_input::KeyEvent::ctrl('t')]),
+            Some(&Command::ShowHelp)
+        );
+        assert_eq!(
+            keymap.resolve(&[smash_input::KeyEvent::ctrl('s')]),
+            Some(&Command::Noop)
+        );
+        assert_eq!(
+            keymap.resolve(&[smash_input::KeyEvent::ctrl('q')]),
+            Some(&Command::Quit)
+        );
+        assert!(app.messages.last().unwrap().text().contains("frobnicate"));
+
+        // Clearing the table restores the preset bindings.
+        app.apply_config(&smash_config::Config::default());
+        assert_eq!(
+            app.resolver
+                .keymap()
+                .resolve(&[smash_input::KeyEvent::ctrl('s')]),
+            Some(&Command::Save)
+        );
+    }
+
+    #[test]
+    fn quick_fix_from_diagnostic_popup() {
+        use smash_input::{Key, KeyEvent, Modifiers};
+        use smash_lsp::{
+            CodeAction, Diagnostic, DiagnosticSeverity, LspPosition, LspRange, TextEdit,
+            WorkspaceEdit,
+        };
+
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let uri = app.current_uri().unwrap();
+        let diag = Diagnostic {
+            range: LspRange::new(LspPosition::new(0, 0), LspPosition::new(0, 5)),
+            severity: Some(DiagnosticSeverity::Error),
+            message: "unknown word".to_string(),
+            source: None,
+            code: None,
+            code_description: None,
+            related_information: Vec::new(),
+        };
+        app.current_diagnostics = vec![diag.clone()];
+        app.handle_command(Command::ShowDiagnosticAtCursor);
+        match rx.try_recv().expect("codeAction sent") {
+            LspCommand::CodeAction {
+                diagnostics, only, ..
+            } => {
+                assert_eq!(diagnostics, vec![diag.clone()]);
+                assert_eq!(only, vec!["quickfix".to_string()]);
+            }
+            _ => panic!("expected CodeAction"),
+        }
+
+        let fix = |title: &str, text: &str, preferred: bool| CodeAction {
+            title: title.to_string(),
+            kind: Some("quickfix".to_string()),
+            edit: Some(WorkspaceEdit {
+                changes: Some(
+                    [(
+                        uri.clone(),
+                        vec![TextEdit {
+                            range: diag.range,
+                            new_text: text.to_string(),
+                        }],
+                    )]
+                    .into(),
+                ),
+                document_changes: None,
+            }),
+            diagnostics: vec![diag.clone()],
+            is_preferred: preferred,
+            command: None,
+        };
+        let refactor = CodeAction {
+            title: "Extract".to_string(),
+            kind: Some("refactor.extract".to_string()),
+            edit: None,
+            diagnostics: Vec::new(),
+            is_preferred: false,
+            command: None,
+        };
+        app.handle_lsp_event(LspEvent::CodeActionResult(vec![
+            fix("Change to 'help'", "help", false),
+            refactor,
+            fix("Change to 'hallo'", "hallo", true),
+        ]));
+        let lines = &app.popup.as_ref().expect("popup").lines;
+        assert_eq!(
+            &lines[lines.len() - 3..],
+            [
+                "Quick fixes (press a number to apply):",
+                "  1. Change to 'hallo' *",
+                "  2. Change to 'help'",
+            ]
+        );
+
+        assert!(app.dismiss_popup(&KeyEvent::new(Key::Char('2'), Modifiers::NONE)));
+        assert!(app.popup.is_none());
+        assert_eq!(app.buffer.text().to_string(), "help\nworld\n");
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Applied 'Change to 'help''"
+        );
+        assert!(matches!(rx.try_recv(), Ok(LspCommand::DidChange { .. })));
+
+        // A digit with no matching fix just closes the popup.
+        app.handle_command(Command::ShowDiagnosticAtCursor);
+        assert!(!app.dismiss_popup(&KeyEvent::new(Key::Char('1'), Modifiers::NONE)));
+        assert!(app.quick_fixes.is_empty());
+    }
+
+    #[test]
+    fn open_loads_in_background_behind_placeholder() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("slow.txt");
+        std::fs::write(&path, "loaded\n").expect("write");
+
+        let mut app = test_app();
+        app.handle_command(Command::InsertChar('x'));
+        app.confirm_open(&path.to_string_lossy());
+        assert!(app.buffer.is_read_only());
+        assert!(app.buffer.is_empty());
+        assert_eq!(app.filename.as_deref(), Some("slow.txt"));
+        assert!(app.normal_status().0.contains("[loading"));
+
+        // Saving the placeholder is refused; closing it cancels the load.
+        app.handle_command(Command::Save);
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .starts_with("Still loading"));
+        app.handle_command(Command::Close);
+        assert!(app.running);
+        assert!(app.pending_open.is_none());
+        assert_eq!(app.buffer.text().to_string(), "x");
+        assert_eq!(app.filename, None);
+
+        app.confirm_open(&path.to_string_lossy());
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.text().to_string(), "loaded\n");
+        assert!(!app.buffer.is_read_only());
+        assert!(app.messages.last().unwrap().text().starts_with("Opened: "));
+
+        // A missing file opens as a new, empty buffer with that path.
+        let new_path = dir.path().join("new.txt");
+        app.confirm_open(&new_path.to_string_lossy());
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(new_path.as_path()));
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .starts_with("New file: "));
+
+        // A failed read puts the previous buffer back.
+        app.confirm_open(&dir.path().to_string_lossy());
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(new_path.as_path()));
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .starts_with("Failed to open"));
+    }
+
+    #[test]
+    fn binary_files_open_as_a_read_only_hex_dump() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let text_path = dir.path().join("notes.txt");
+        std::fs::write(&text_path, "notes\n").expect("write");
+        let path = dir.path().join("app.bin");
+        let mut bytes = b"\x7fELF\x00".to_vec();
+        bytes.resize(1000, 0xab);
+        std::fs::write(&path, &bytes).expect("write");
+
+        let mut app = test_app();
+        app.confirm_open(&text_path.to_string_lossy());
+        wait_for_open(&mut app);
+        app.confirm_open(&path.to_string_lossy());
+        wait_for_open(&mut app);
+        assert!(app.buffer.is_read_only());
+        assert_eq!(app.buffer.path(), None);
+        assert_eq!(app.filename.as_deref(), Some("app.bin [hex]"));
+        assert_eq!(
+            app.buffer.line(0).unwrap().to_string(),
+            "00000000  7f 45 4c 46 00 ab ab ab  ab ab ab ab ab ab ab ab  |.ELF............|\n"
+        );
+        assert_eq!(app.buffer.line_count(), 1000 / 16 + 2);
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .contains("binary file (1000 bytes)"));
+        assert_eq!(
+            app.alternate_file.as_ref().and_then(|l| l.path.as_deref()),
+            Some(text_path.as_path())
+        );
+
+        // It pages like any buffer, but cannot be edited or saved.
+        app.handle_command(Command::PageDown);
+        assert!(app.buffer.cursors().primary().position().line > 0);
+        app.handle_command(Command::InsertChar('x'));
+        app.handle_command(Command::Save);
+        assert!(!app.buffer.is_dirty());
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+
+        // Likewise when given on the command line.
+        let app = TestApp::default().file(path).build();
+        assert!(app.buffer.is_read_only());
+        assert_eq!(app.filename.as_deref(), Some("app.bin [hex]"));
+        assert_eq!(app.language_id, None);
+    }
+
+    #[test]
+    fn unwritable_files_open_read_only_until_toggled() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("hosts");
+        std::fs::write(&path, "127.0.0.1 localhost\n").expect("write");
+        let mut perms = std::fs::metadata(&path).unwrap().permissions();
+        perms.set_readonly(true);
+        std::fs::set_permissions(&path, perms).expect("chmod");
+
+        let mut app = test_app();
+        app.confirm_open(&path.to_string_lossy());
+        wait_for_open(&mut app);
+        assert!(app.buffer.is_read_only());
+        assert!(app.normal_status().0.starts_with("hosts [RO]"));
+        app.handle_command(Command::InsertChar('#'));
+        assert!(!app.buffer.is_dirty());
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .starts_with("File is not writable"));
+
+        app.handle_command(Command::ToggleReadOnly);
+        assert!(!app.buffer.is_read_only());
+        assert!(app.messages.last().unwrap().text().contains("SudoSave"));
+        app.handle_command(Command::InsertChar('#'));
+        assert_eq!(app.buffer.text().to_string(), "#127.0.0.1 localhost\n");
+        app.handle_command(Command::ToggleReadOnly);
+        assert!(app.buffer.is_read_only());
+
+        // Help and other buffers with no file stay read-only.
+        let mut app = test_app();
+        app.handle_command(Command::ShowHelp);
+        app.handle_command(Command::ToggleReadOnly);
+        assert!(app.buffer.is_read_only());
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "This buffer cannot be edited"
+        );
+    }
+
+    #[test]
+    fn scratch_buffers_ask_for_a_name_on_save_and_quit() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mut app = test_app();
+        app.handle_command(Command::NewBuffer);
+        assert_eq!(app.filename, None);
+        assert!(app.normal_status().0.starts_with("[scratch]"));
+
+        // Save drops straight into the Save-As prompt.
+        app.handle_command(Command::InsertChar('x'));
+        app.handle_command(Command::Save);
+        assert_eq!(app.input_mode, InputMode::PromptSaveAs);
+        app.handle_command(Command::Quit);
+
+        // A new buffer is refused while the text is unsaved.
+        app.handle_command(Command::NewBuffer);
+        assert_eq!(app.buffer.text().to_string(), "x");
+
+        // Quitting asks; anything but y/n stays.
+        app.handle_command(Command::Quit);
+        assert_eq!(app.input_mode, InputMode::PromptQuit);
+        assert!(app
+            .quit_prompt()
+            .starts_with("Unsaved changes in [scratch]."));
+        app.handle_command(Command::InsertChar('c'));
+        assert!(app.running);
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        // y asks for a name, saves and quits.
+        app.handle_command(Command::Quit);
+        app.handle_command(Command::InsertChar('y'));
+        assert_eq!(app.input_mode, InputMode::PromptSaveAsAndQuit);
+        let path = dir.path().join("scratch.txt");
+        for c in path.to_string_lossy().chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        assert!(!app.running);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "x");
+
+        // n discards.
+        let mut app = test_app();
+        app.handle_command(Command::InsertChar('x'));
+        app.handle_command(Command::Quit);
+        app.handle_command(Command::InsertChar('n'));
+        assert!(!app.running);
+
+        // An empty scratch buffer quits without asking.
+        let mut app = test_app();
+        app.handle_command(Command::Quit);
+        assert!(!app.running);
+    }
+
+    #[test]
+    fn quitting_with_unsaved_changes_asks_first() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("todo.md");
+        std::fs::write(&path, "- milk\n").expect("write");
+        let open = |path: &std::path::Path| {
+            let mut app = test_app();
+            app.confirm_open(&path.to_string_lossy());
+            wait_for_open(&mut app);
+            app.handle_command(Command::InsertChar('x'));
+            app.handle_command(Command::Quit);
+            assert_eq!(app.input_mode, InputMode::PromptQuit);
+            assert!(app.quit_prompt().starts_with("Unsaved changes in todo.md."));
+            app
+        };
+
+        // s saves, then quits.
+        let mut app = open(&path);
+        app.handle_command(Command::InsertChar('s'));
+        assert!(!app.running);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "x- milk\n");
+
+        // d quits without saving.
+        let mut app = open(&path);
+        app.handle_command(Command::InsertChar('d'));
+        assert!(!app.running);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "x- milk\n");
+
+        // Enter cancels; ForceQuit never asks.
+        let mut app = open(&path);
+        app.handle_command(Command::InsertNewline);
+        assert!(app.running);
+        assert_eq!(app.messages.last().unwrap().text(), "Quit cancelled");
+        app.handle_command(Command::ForceQuit);
+        assert!(!app.running);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sudo_save_pipes_the_buffer_through_the_configured_command() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("motd");
+        std::fs::write(&path, "hello\n").expect("write");
+        let mut app = test_app();
+        app.confirm_open(&path.to_string_lossy());
+        wait_for_open(&mut app);
+        app.handle_command(Command::InsertChar('>'));
+
+        app.editor_config.sudo_command = "false".to_string();
+        app.handle_command(Command::SudoSave);
+        assert!(app.buffer.is_dirty());
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .starts_with("Sudo save failed: false exited"));
+
+        app.editor_config.sudo_command = "tee".to_string();
+        app.handle_command(Command::SudoSave);
+        assert!(!app.buffer.is_dirty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), ">hello\n");
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Saved motd with elevated permissions"
+        );
+    }
+
+    fn vim_test_app(text: &str) -> (App, tempfile::TempDir) {
+        let (dir, path) = temp_doc(text);
+        (TestApp::default().file(path).keymap("vim").build(), dir)
+    }
+
+    /// Type `keys` the way the editor loop delivers them; `\n` is Enter
+    /// and `\x1b` is Esc.
+    fn vim_type(app: &mut App, keys: &str) {
+        use smash_input::{InputEvent, Key, KeyEvent, Modifiers, ResolveResult};
+        for c in keys.chars() {
+            let key = match c {
+                '\n' => KeyEvent::new(Key::Enter, Modifiers::NONE),
+                '\x1b' => KeyEvent::new(Key::Esc, Modifiers::NONE),
+                c => KeyEvent::char(c),
+            };
+            if app.handle_vim_key(&key) {
+                continue;
+            }
+            if let ResolveResult::Command(cmd) = app.resolver.resolve(InputEvent::Key(key)) {
+                app.handle_command(cmd);
+            }
+        }
+    }
+
+    #[test]
+    fn vim_operators_take_counts_and_motions() {
+        let (mut app, _dir) = vim_test_app("one two three\nfour five\nsix\n");
+        vim_type(&mut app, "2d");
+        assert!(app.vim_status().contains("NORMAL 2d"));
+        vim_type(&mut app, "w");
+        assert_eq!(app.buffer.text().to_string(), "three\nfour five\nsix\n");
+
+        vim_type(&mut app, "jwd$");
+        assert_eq!(app.buffer.text().to_string(), "three\nfour \nsix\n");
+
+        vim_type(&mut app, "ggddp");
+        assert_eq!(app.buffer.text().to_string(), "four \nthree\nsix\n");
+        assert_eq!(app.buffer.cursors().primary().position().line, 1);
+
+        vim_type(&mut app, "uu");
+        assert_eq!(app.buffer.text().to_string(), "three\nfour \nsix\n");
+    }
+
+    #[test]
+    fn vim_insert_and_visual_modes() {
+        let (mut app, _dir) = vim_test_app("one two\nthree\nfour\n");
+        vim_type(&mut app, "cwsix\x1b");
+        assert_eq!(app.buffer.text().to_string(), "six two\nthree\nfour\n");
+        assert!(app.vim_status().contains("NORMAL"));
+
+        vim_type(&mut app, "0vlld");
+        assert_eq!(app.buffer.text().to_string(), " two\nthree\nfour\n");
+
+        vim_type(&mut app, "jVjd");
+        assert_eq!(app.buffer.text().to_string(), " two\n");
+        assert!(!app.buffer.cursors().primary().has_selection());
+    }
+
+    #[test]
+    fn vim_registers_hold_named_yanks_and_deletes() {
+        let (mut app, _dir) = vim_test_app("one\ntwo\nthree\n");
+        let text = |app: &App| app.buffer.text().to_string();
+        vim_type(&mut app, "\"a");
+        assert!(app.vim_status().contains("NORMAL \"a"));
+        vim_type(&mut app, "yyj\"Ayy");
+        assert_eq!(
+            app.registers.get('a').map(|r| r.text.as_str()),
+            Some("one\ntwo\n")
+        );
+        vim_type(&mut app, "dd\"ap");
+        assert_eq!(text(&app), "one\nthree\none\ntwo\n");
+        assert_eq!(
+            app.registers.get('1').map(|r| r.text.as_str()),
+            Some("two\n")
+        );
+
+        // The black hole register keeps the clipboard and `1` as they were.
+        vim_type(&mut app, "\"_ddp");
+        assert_eq!(text(&app), "one\nthree\ntwo\ntwo\n");
+        vim_type(&mut app, "\"zp");
+        assert_eq!(app.messages.last().unwrap().text(), "Register 'z' is empty");
+
+        vim_type(&mut app, "gg:reg\n");
+        assert_eq!(app.input_mode, InputMode::RegisterPicker);
+        let picker = &mut app.register_picker.as_mut().unwrap().picker;
+        let labels: Vec<String> = picker
+            .visible(10)
+            .into_iter()
+            .map(|(item, _)| item.label.clone())
+            .collect();
+        assert_eq!(labels, ["\"\"  two", "\"1  two", "\"a  one"]);
+        vim_type(&mut app, "\"a\n");
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(text(&app), "one\ntwo\none\nthree\ntwo\ntwo\n");
+    }
+
+    #[test]
+    fn vim_command_line_writes_quits_and_edits() {
+        let (mut app, dir) = vim_test_app("alpha\n");
+        let path = dir.path().join("doc.txt");
+        vim_type(&mut app, "x:q\n");
+        assert!(app.running);
+        assert!(app.messages.last().unwrap().text().contains("No write"));
+
+        vim_type(&mut app, ":w\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "lpha\n");
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        let other = dir.path().join("other.txt");
+        std::fs::write(&other, "beta\n").unwrap();
+        vim_type(&mut app, &format!(":e {}\n", other.display()));
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.text().to_string(), "beta\n");
+
+        vim_type(&mut app, ":bogus\n");
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .contains("Not an editor command"));
+
+        vim_type(&mut app, ":q\n");
+        assert!(!app.running);
+    }
+
+    #[test]
+    fn vim_z_and_ctrl_e_y_scroll_the_view() {
+        use smash_input::KeyEvent;
+        let text: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        let (mut app, _dir) = vim_test_app(&text);
+        app.set_cursor(Position::new(50, 0));
+        let mut backend = smash_tui::MockBackend::new(40, 11);
+        app.render(&mut backend).expect("render");
+
+        vim_type(&mut app, "zt");
+        assert_eq!(app.viewport.top_line(), 50);
+        vim_type(&mut app, "zb");
+        assert_eq!(app.viewport.top_line(), 41);
+        vim_type(&mut app, "zz");
+        assert_eq!(app.viewport.top_line(), 45);
+        vim_type(&mut app, "3");
+        assert!(app.handle_vim_key(&KeyEvent::ctrl('e')));
+        assert_eq!(app.viewport.top_line(), 48);
+        assert!(app.handle_vim_key(&KeyEvent::ctrl('y')));
+        assert_eq!(app.viewport.top_line(), 47);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(50, 0)
+        );
+    }
+
+    #[test]
+    fn save_all_saves_the_modified_buffer() {
+        let (mut app, dir) = vim_test_app("alpha\n");
+        app.handle_command(Command::SaveAll);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "No modified files to save"
+        );
+
+        app.insert_text("new ");
+        app.handle_command(Command::SaveAll);
+        let path = dir.path().join("doc.txt");
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "new alpha\n");
+        assert!(!app.buffer.is_dirty());
+        assert_eq!(app.messages.last().unwrap().text(), "File saved");
+    }
+
+    #[test]
+    fn line_numbers_follow_config_and_toggle() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "one\ntwo\nthree\n");
+        app.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(1, 0));
+        let mut config = smash_config::Config::default();
+        config.display.line_numbers = smash_config::config::LineNumberMode::Hybrid;
+        app.apply_config(&config);
+        let mut backend = smash_tui::MockBackend::new(40, 10);
+        app.render(&mut backend).expect("render");
+        assert!(backend.read_row(0).starts_with("     1 one"));
+        assert!(backend.read_row(1).starts_with("     2 two"));
+
+        app.handle_command(Command::ToggleLineNumbers);
+        assert_eq!(app.gutter_width(), 0);
+        app.render(&mut backend).expect("render");
+        assert!(backend.read_row(0).starts_with("one"));
+
+        config.display.line_numbers = smash_config::config::LineNumberMode::None;
+        app.apply_config(&config);
+        assert_eq!(app.line_number_mode(), LineNumbers::Off);
+        app.handle_command(Command::ToggleLineNumbers);
+        assert_eq!(app.line_number_mode(), LineNumbers::Absolute);
+    }
+
+    #[test]
+    fn rulers_guides_and_line_highlight_follow_config_and_toggle() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "if x {\n\ty();\n}\n");
+        let mut config = smash_config::Config::default();
+        config.display.rulers = vec![80];
+        config.display.highlight_current_line = true;
+        config.display.indent_guides = true;
+        config.editor.tab_size = 2;
+        app.apply_config(&config);
+        assert_eq!(app.rulers(), vec![80]);
+        assert_eq!(app.indent_guide_width(), Some(2));
+        let mut backend = smash_tui::MockBackend::new(40, 10);
+        app.render(&mut backend).expect("render");
+        assert!(backend.read_row(1).starts_with("     2 \u{2502}y();"));
+
+        app.buffer.set_path(PathBuf::from("COMMIT_EDITMSG"));
+        assert_eq!(app.rulers(), vec![80, 50, 72]);
+        app.handle_command(Command::ToggleRulers);
+        assert!(app.rulers().is_empty());
+        app.handle_command(Command::ToggleIndentGuides);
+        assert_eq!(app.indent_guide_width(), None);
+        app.handle_command(Command::ToggleCurrentLineHighlight);
+        assert!(!app.highlight_current_line);
+        app.render(&mut backend).expect("render");
+        assert!(!backend.read_row(1).contains('\u{2502}'));
+
+        config.display.rulers = Vec::new();
+        app.apply_config(&config);
+        assert!(app.highlight_current_line);
+        assert!(app.indent_guides);
+    }
+
+    #[test]
+    fn minimap_shows_the_buffer_and_scrolls_on_click() {
+        use smash_input::event::MouseKind;
+        use smash_input::{Modifiers, MouseEvent};
+        let mut app = test_app();
+        let text: String = (0..200).map(|i| format!("line {}\n", i)).collect();
+        app.buffer = Buffer::from_text(BufferId::next(), &text);
+        let mut config = smash_config::Config::default();
+        config.display.show_minimap = true;
+        app.apply_config(&config);
+        let mut backend = smash_tui::MockBackend::new(80, 20);
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.minimap_area, Some(Rect::new(68, 0, 12, 19)));
+        // The first row shades "line" fully and the numbers after it lightly.
+        let row = backend.read_row(0);
+        assert_eq!(row.chars().count(), 70);
+        assert!(row.ends_with("\u{2593}\u{2591}"));
+        assert_eq!(
+            app.viewport.visible_cols(),
+            68 - app.gutter_width() as usize
+        );
+
+        let mouse = |kind, row| MouseEvent {
+            kind,
+            col: 70,
+            row,
+            modifiers: Modifiers::NONE,
+        };
+        // 201 lines in 19 rows is 11 lines a row.
+        assert!(app.handle_mouse(&mouse(MouseKind::Down, 18)));
+        assert_eq!(app.viewport.top_line(), 182);
+        assert_eq!(app.buffer.cursors().primary().position().line, 182);
+        assert!(app.handle_mouse(&mouse(MouseKind::Drag, 9)));
+        assert_eq!(app.viewport.top_line(), 95);
+        assert!(app.handle_mouse(&mouse(MouseKind::Up, 9)));
+        assert!(app.scroll_drag.is_none());
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.viewport.top_line(), 95);
+
+        app.handle_command(Command::ToggleMinimap);
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.minimap_area, None);
+        assert!(!app.handle_mouse(&mouse(MouseKind::Drag, 9)));
+    }
+
+    #[test]
+    fn scrollbar_marks_diagnostics_and_matches_and_scrolls_on_click() {
+        use smash_input::event::MouseKind;
+        use smash_input::{Modifiers, MouseEvent};
+        use smash_lsp::{Diagnostic, DiagnosticSeverity, LspPosition, LspRange};
+        use smash_tui::{GutterDiagnostic, ScrollbarMark};
+
+        let mut app = test_app();
+        let text: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        app.insert_text(&text);
+        app.set_cursor(Position::new(0, 0));
+        let mut config = smash_config::Config::default();
+        config.display.scrollbar = true;
+        app.apply_config(&config);
+        app.current_diagnostics = vec![Diagnostic {
+            range: LspRange::new(LspPosition::new(90, 0), LspPosition::new(90, 4)),
+            severity: Some(DiagnosticSeverity::Error),
+            message: "broken".to_string(),
+            source: None,
+            code: None,
+            code_description: None,
+            related_information: Vec::new(),
+        }];
+        app.handle_command(Command::Find);
+        for c in "line 7".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        let marks = app.scrollbar_marks();
+        assert!(marks.contains(&(90, ScrollbarMark::Diagnostic(GutterDiagnostic::Error))));
+        assert!(marks.contains(&(75, ScrollbarMark::SearchMatch)));
+        assert_eq!(marks.len(), 12);
+
+        let mut backend = smash_tui::MockBackend::new(40, 11);
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.scrollbar_area, Some(Rect::new(39, 0, 1, 10)));
+        assert_eq!(
+            app.viewport.visible_cols(),
+            39 - app.gutter_width() as usize
+        );
+        // 101 lines in 10 rows: line 90 is on row 8, and 70 to 79 on row 6.
+        assert!(backend.read_row(8).ends_with('\u{2501}'));
+        assert!(backend.read_row(6).ends_with('\u{2501}'));
+        assert!(!backend.read_row(3).ends_with('\u{2501}'));
+
+        app.input_mode = InputMode::Normal;
+        let mouse = |kind, row| MouseEvent {
+            kind,
+            col: 39,
+            row,
+            modifiers: Modifiers::NONE,
+        };
+        assert!(app.handle_mouse(&mouse(MouseKind::Down, 5)));
+        assert_eq!(app.viewport.top_line(), 50);
+        assert!(app.handle_mouse(&mouse(MouseKind::Drag, 0)));
+        assert_eq!(app.viewport.top_line(), 0);
+        assert!(app.handle_mouse(&mouse(MouseKind::Up, 0)));
+
+        app.handle_command(Command::ToggleScrollbar);
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.scrollbar_area, None);
+    }
+
+    #[test]
+    fn scrollbar_marks_lines_changed_since_the_last_commit() {
+        use smash_tui::ScrollbarMark;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .is_ok_and(|o| o.status.success())
+        };
+        if !git(&["init", "-q"]) {
+            return; // No git available.
+        }
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+        assert!(git(&["add", "notes.txt"]));
+        assert!(git(&["commit", "-q", "-m", "Add notes"]));
+
+        let mut app = test_app();
+        app.open_path(path, None, false);
+        wait_for_open(&mut app);
+        app.show_scrollbar = true;
+        app.set_cursor(Position::new(1, 0));
+        app.insert_text("new\n");
+        let mut now = std::time::Instant::now();
+        wait_until(
+            &mut app,
+            "change marks",
+            |app| !app.git_change_marks().is_empty(),
+            |app| {
+                now += std::time::Duration::from_secs(2);
+                app.poll_git_changes(now);
+            },
+        );
+        assert_eq!(app.git_change_marks(), [(1, ScrollbarMark::Added)]);
+
+        app.handle_command(Command::ToggleScrollbar);
+        assert!(app.poll_git_changes(now));
+        assert!(app.git_change_marks().is_empty());
+    }
+
+    #[test]
+    fn view_scrolls_around_the_cursor_without_moving_it() {
+        let mut app = test_app();
+        let text: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        app.insert_text(&text);
+        app.set_cursor(Position::new(50, 0));
+        let mut backend = smash_tui::MockBackend::new(40, 11);
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.viewport.visible_lines(), 10);
+
+        app.handle_command(Command::CenterCursor);
+        assert_eq!(app.viewport.top_line(), 45);
+        app.handle_command(Command::CursorToTop);
+        assert_eq!(app.viewport.top_line(), 50);
+        app.handle_command(Command::CursorToBottom);
+        assert_eq!(app.viewport.top_line(), 41);
+        app.handle_command(Command::ScrollLineDown);
+        assert_eq!(app.viewport.top_line(), 42);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(50, 0)
+        );
+
+        // The cursor is taken along once its line scrolls out of view.
+        app.handle_command(Command::CursorToTop);
+        app.handle_command(Command::ScrollLineDown);
+        assert_eq!(app.viewport.top_line(), 51);
+        assert_eq!(app.buffer.cursors().primary().position().line, 51);
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.viewport.top_line(), 51);
+        app.handle_command(Command::ScrollLineUp);
+        assert_eq!(app.viewport.top_line(), 50);
+        assert_eq!(app.buffer.cursors().primary().position().line, 51);
+
+        // Centring near the end of the buffer stops at its last line.
+        app.set_cursor(Position::new(98, 0));
+        app.handle_command(Command::CenterCursor);
+        assert_eq!(app.viewport.top_line(), 91);
+    }
+
+    #[test]
+    fn mouse_click_drag_and_scroll() {
+        use smash_input::event::MouseKind;
+        use smash_input::{Modifiers, MouseEvent};
+        let mut app = test_app();
+        let text: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+        app.buffer = Buffer::from_text(BufferId::next(), &text);
+        let mouse = |kind, col, row| MouseEvent {
+            kind,
+            col,
+            row,
+            modifiers: Modifiers::NONE,
+        };
+        let gutter = smash_tui::LINE_NUMBER_WIDTH;
+
+        // Click past the end of line 2 lands on its last column.
+        assert!(app.handle_mouse(&mouse(MouseKind::Down, gutter + 40, 2)));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(2, 6)
+        );
+
+        assert!(app.handle_mouse(&mouse(MouseKind::Down, gutter + 1, 3)));
+        assert!(app.handle_mouse(&mouse(MouseKind::Drag, gutter + 3, 4)));
+        assert!(app.handle_mouse(&mouse(MouseKind::Up, gutter + 3, 4)));
+        assert_eq!(app.selected_text().as_deref(), Some("ine 3\nlin"));
+        assert!(!app.handle_mouse(&mouse(MouseKind::Drag, gutter, 5)));
+
+        assert!(app.handle_mouse(&mouse(MouseKind::ScrollDown, gutter, 0)));
+        assert_eq!(app.viewport.top_line(), 3);
+        assert_eq!(app.buffer.cursors().primary().position().line, 4);
+        for _ in 0..10 {
+            app.handle_mouse(&mouse(MouseKind::ScrollDown, gutter, 0));
+        }
+        let top = app.viewport.top_line();
+        assert_eq!(app.buffer.cursors().primary().position().line, top);
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.viewport.top_line(), top);
+    }
+
+    #[test]
+    fn session_round_trip_restores_cursor_and_scroll() {
+        let text: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        let (mut app, dir) = vim_test_app(&text);
+        app.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(60, 3));
+        app.viewport.set_top_line(50);
+        app.push_jump();
+        app.insert_text("x");
+        app.handle_command(Command::SetMark('q'));
+        let session = app.session(Some(dir.path().to_path_buf()));
+        let saved = session.active().expect("active buffer");
+        assert_eq!(saved.path, dir.path().join("doc.txt"));
+        assert_eq!(saved.top_line, 50);
+        assert_eq!(session.jumps.len(), 1);
+        assert_eq!(session.jumps[0].position, Position::new(60, 3));
+        assert_eq!(
+            session.last_edit.as_ref().map(|e| e.position),
+            Some(Position::new(60, 4))
+        );
+        assert_eq!(session.marks.len(), 1);
+        assert_eq!(session.marks[0].name, 'q');
+
+        let (mut fresh, _other) = vim_test_app(&text);
+        fresh.buffer = Buffer::open_or_create(BufferId::next(), &saved.path).unwrap();
+        fresh.restore_session(&session);
+        assert_eq!(
+            fresh.buffer.cursors().primary().position(),
+            Position::new(60, 4)
+        );
+        assert_eq!(fresh.viewport.top_line(), 50);
+        assert_eq!(fresh.jump_stack.back_len(), 1);
+        assert_eq!(
+            fresh.last_edit.as_ref().map(|e| e.position),
+            Some(Position::new(60, 4))
+        );
+        assert_eq!(
+            fresh.marks.get(&'q').map(|m| m.position),
+            Some(Position::new(60, 4))
+        );
+
+        // A session for some other file leaves the cursor alone.
+        let (mut unrelated, _dir) = vim_test_app(&text);
+        unrelated.restore_session(&session);
+        assert_eq!(
+            unrelated.buffer.cursors().primary().position(),
+            Position::new(0, 0)
+        );
+    }
+    #[test]
+    fn lsp_popups_flip_clamp_and_follow_resize() {
+        let mut app = test_app();
+        let text: String = (0..30).map(|i| format!("line {}\n", i)).collect();
+        app.insert_text(&text);
+        app.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(20, 4));
+        let items = (0..6)
+            .map(|i| smash_lsp::CompletionItem {
+                label: format!("item{}", i),
+                kind: None,
+                detail: None,
+                insert_text: None,
+                documentation: None,
+            })
+            .collect();
+        app.handle_lsp_event(LspEvent::CompletionResult(items));
+        assert_eq!(app.popup.as_ref().unwrap().title, "Completions (6)");
+
+        // Near the bottom of the editor the popup opens above the cursor.
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        let (_, cursor_row) = backend.cursor_position();
+        let title_row = (0..24)
+            .find(|&r| backend.read_row(r).contains("Completions (6)"))
+            .expect("popup drawn");
+        assert!(title_row + 7 < cursor_row);
+        assert!(backend.read_row(title_row + 1).contains("item0"));
+
+        // After shrinking the screen it is redrawn inside the new bounds,
+        // cut down to the room there is.
+        let mut backend = smash_tui::MockBackend::new(16, 6);
+        app.render(&mut backend).expect("render");
+        let (_, cursor_row) = backend.cursor_position();
+        let rows: Vec<String> = (0..5).map(|r| backend.read_row(r)).collect();
+        let top = rows.iter().position(|r| r.contains('┌')).expect("popup");
+        let bottom = rows.iter().position(|r| r.contains('┘')).expect("popup");
+        assert!(bottom < cursor_row as usize || top > cursor_row as usize);
+        assert!(rows.iter().any(|r| r.contains('…')));
+
+        // Too small to hold a popup: nothing is drawn over the text.
+        let mut backend = smash_tui::MockBackend::new(16, 3);
+        app.render(&mut backend).expect("render");
+        assert!(!(0..3).any(|r| backend.read_row(r).contains('┌')));
+
+        // A hover replaces the list.
+        app.handle_lsp_event(LspEvent::HoverResult(Some("fn item()".into())));
+        assert_eq!(app.popup.as_ref().unwrap().lines, vec!["fn item()"]);
+    }
+
+    #[test]
+    fn terminal_repaints_dirty_rows_between_frames() {
+        use smash_terminal::{MockPty, TerminalPane, TerminalSize};
+
+        let mut app = test_app();
+        let size = TerminalSize::new(80, 9);
+        let mut pty = MockPty::new(size);
+        pty.set_read_data(b"one\r\ntwo");
+        app.terminal = Some(TerminalPane::new(Box::new(pty), size));
+        app.terminal_visible = true;
+        assert!(app.poll_terminal());
+
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        let term = app.terminal.as_mut().unwrap();
+        assert_eq!(term.grid().dirty_rows().count(), 0);
+
+        let grid = term.grid_mut();
+        grid.cursor_set_position(1, 0);
+        grid.erase_line();
+        grid.write_char('2');
+        assert_eq!(grid.dirty_rows().collect::<Vec<_>>(), vec![1]);
+        app.render(&mut backend).expect("render");
+        assert_eq!(backend.read_row(15), "one");
+        assert_eq!(backend.read_row(16), "2");
+
+        // Hiding and showing the pane repaints it in full.
+        app.terminal_visible = false;
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.terminal_painted, None);
+        app.terminal_visible = true;
+        app.render(&mut backend).expect("render");
+        assert_eq!(backend.read_row(15), "one");
+    }
+
+    #[test]
+    fn file_tree_opens_creates_renames_and_deletes() {
+        use smash_input::{Key, KeyEvent, Modifiers};
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+        std::fs::create_dir(root.join(".git")).unwrap();
+
+        let mut app = test_app();
+        app.file_tree = Some(smash_tui::FileTree::new(root.clone()));
+        app.handle_command(Command::ToggleFileTree);
+        assert!(app.file_tree_focused());
+
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        assert!(backend.read_row(1).starts_with("\u{25b8} src/"));
+        assert!(backend.read_row(2).starts_with("  notes.txt"));
+        assert!(!(0..23).any(|r| backend.read_row(r).contains(".git")));
+        assert_eq!(app.editor_left, 20);
+        assert!(app.normal_status().0.contains("[files]"));
+
+        let key = |app: &mut App, k: Key| {
+            assert!(app.handle_file_tree_key(&KeyEvent::new(k, Modifiers::NONE)))
+        };
+        let type_text = |app: &mut App, text: &str| {
+            for c in text.chars() {
+                app.handle_command(Command::InsertChar(c));
+            }
+            app.handle_command(Command::InsertNewline);
+        };
+
+        // Enter expands a directory; `a` creates a file inside it and
+        // opens it.
+        key(&mut app, Key::Enter);
+        assert_eq!(app.file_tree.as_ref().unwrap().entries().len(), 3);
+        key(&mut app, Key::Char('a'));
+        assert_eq!(app.input_mode, InputMode::PromptTreeCreate);
+        assert!(app.file_tree_prompt().starts_with("New file in src/"));
+        type_text(&mut app, "lib.rs");
+        assert!(root.join("src/lib.rs").is_file());
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(root.join("src/lib.rs").as_path()));
+        assert!(!app.file_tree_focused());
+
+        // Back in the tree, `r` renames the open file; the buffer follows.
+        app.handle_command(Command::ToggleFileTree);
+        assert!(app.file_tree_focused());
+        app.file_tree
+            .as_mut()
+            .unwrap()
+            .select_path(&root.join("src/lib.rs"));
+        key(&mut app, Key::Char('r'));
+        assert_eq!(app.prompt_input, "lib.rs");
+        app.prompt_input.clear();
+        type_text(&mut app, "util.rs");
+        assert!(!root.join("src/lib.rs").exists());
+        assert!(root.join("src/util.rs").is_file());
+        assert_eq!(app.buffer.path(), Some(root.join("src/util.rs").as_path()));
+        assert_eq!(app.filename.as_deref(), Some("util.rs"));
+
+        // `d` asks first; anything but `y` keeps the file.
+        key(&mut app, Key::Char('d'));
+        app.handle_command(Command::InsertChar('n'));
+        assert!(root.join("src/util.rs").exists());
+        key(&mut app, Key::Char('d'));
+        assert_eq!(app.file_tree_prompt(), "Delete util.rs? (y/n)");
+        app.handle_command(Command::InsertChar('y'));
+        assert!(!root.join("src/util.rs").exists());
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        // Ctrl keys fall through to the keymap; Esc returns to the editor
+        // and the toggle key hides the tree once it has focus.
+        assert!(!app.handle_file_tree_key(&KeyEvent::ctrl('s')));
+        key(&mut app, Key::Esc);
+        assert!(!app.file_tree_focused());
+        app.handle_command(Command::ToggleFileTree);
+        app.handle_command(Command::ToggleFileTree);
+        assert!(!app.file_tree_visible);
+        app.render(&mut backend).expect("render");
+        assert_eq!(app.editor_left, 0);
+    }
+
+    #[test]
+    fn terminal_flood_reports_skipped_output() {
+        use smash_terminal::{MockPty, TerminalPane, TerminalSize};
+
+        let mut app = test_app();
+        let size = TerminalSize::new(80, 9);
+        let mut pty = MockPty::new(size);
+        pty.set_read_data(&b"y\r\n".repeat(1 << 19));
+        app.terminal = Some(TerminalPane::new(Box::new(pty), size));
+        app.terminal_visible = true;
+
+        assert!(app.poll_terminal());
+        let msg = app.messages.last().expect("message").text().to_string();
+        assert!(msg.starts_with("Terminal output suppressed,"), "{}", msg);
+        assert!(msg.ends_with("bytes skipped"));
+        // The rest of the flood drains over the next polls.
+        for _ in 0..4 {
+            app.poll_terminal();
+        }
+        assert!(!app.terminal_backlogged());
+        assert_eq!(
+            app.terminal.as_ref().unwrap().grid().row_text(0).trim_end(),
+            "y"
+        );
+    }
+
+    #[test]
+    fn file_finder_indexes_in_the_background_and_refreshes_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(root.join("main.rs"), "").unwrap();
+        std::fs::write(root.join("debug.log"), "").unwrap();
+        let wait_for_index = |app: &mut App| {
+            wait_until(
+                app,
+                "indexing",
+                |app| app.file_index_progress().is_none(),
+                App::poll_file_index,
+            );
+        };
+        let found = |app: &App| {
+            app.finder_results
+                .iter()
+                .map(|m| m.relative_path().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut app = test_app();
+        app.file_finder = Some(smash_core::fuzzy_finder::FileFinder::new(root.clone()));
+        app.handle_command(Command::OpenFileFinder);
+        assert_eq!(app.input_mode, InputMode::FileFinder);
+        assert!(app.file_index_progress().is_some());
+        app.handle_command(Command::InsertChar('.'));
+        wait_for_index(&mut app);
+        assert_eq!(found(&app), vec!["main.rs"]);
+
+        // Reopening searches the old index while a fresh one is built.
+        app.input_mode = InputMode::Normal;
+        std::fs::remove_file(root.join("main.rs")).unwrap();
+        std::fs::write(root.join("lib.rs"), "").unwrap();
+        app.handle_command(Command::OpenFileFinder);
+        app.handle_command(Command::InsertChar('.'));
+        assert_eq!(found(&app), vec!["main.rs"]);
+        wait_for_index(&mut app);
+        assert_eq!(found(&app), vec!["lib.rs"]);
+    }
+
+    #[test]
+    fn file_finder_overlay_selects_previews_and_ranks_recent_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/alpha.rs"), "fn alpha() {}\n").unwrap();
+        std::fs::write(root.join("src/beta.rs"), "fn beta() {}\n").unwrap();
+        std::fs::write(root.join("notes.txt"), "notes\n").unwrap();
+        let mut app = test_app();
+        let mut finder = smash_core::fuzzy_finder::FileFinder::new(root.clone());
+        finder.index();
+        app.file_finder = Some(finder);
+        let selected = |app: &App| {
+            app.selected_finder_match()
+                .map(|m| m.relative_path().to_string())
+        };
+
+        app.handle_command(Command::OpenFileFinder);
+        assert_eq!(
+            app.finder_results.len(),
+            3,
+            "an empty query lists every file"
+        );
+        for c in "src".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        // The shorter path ranks first.
+        assert_eq!(selected(&app).as_deref(), Some("src/beta.rs"));
+        assert_eq!(app.finder_preview.as_ref().unwrap().lines, ["fn beta() {}"]);
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::MoveDown);
+        assert_eq!(selected(&app).as_deref(), Some("src/alpha.rs"));
+        assert_eq!(
+            app.finder_preview.as_ref().unwrap().lines,
+            ["fn alpha() {}"]
+        );
+
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        let screen: Vec<String> = (0..24).map(|y| backend.read_row(y)).collect();
+        assert!(screen[1].contains(" Find file "));
+        assert!(screen[2].contains("> src"));
+        assert!(screen.iter().any(|row| row.contains("fn alpha() {}")));
+
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        wait_for_open(&mut app);
+        assert!(app.buffer.path().unwrap().ends_with("src/alpha.rs"));
+
+        // The file just opened comes first next time.
+        app.handle_command(Command::OpenFileFinder);
+        assert_eq!(selected(&app).as_deref(), Some("src/alpha.rs"));
+        app.handle_command(Command::InsertChar('s'));
+        assert_eq!(selected(&app).as_deref(), Some("src/alpha.rs"));
+        app.recent_files = Default::default();
+        app.note_recent_file(&root.join("notes.txt"));
+        app.update_finder_results();
+        assert_eq!(selected(&app).as_deref(), Some("notes.txt"));
+    }
+
+    #[test]
+    fn open_recent_lists_recently_opened_files_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            std::fs::write(root.join(name), "").unwrap();
+        }
+        let mut app = test_app();
+        app.handle_command(Command::OpenRecent);
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            app.confirm_open(&root.join(name).to_string_lossy());
+            wait_for_open(&mut app);
+        }
+        std::fs::remove_file(root.join("a.rs")).unwrap();
+        assert_eq!(app.recent_files.files[0], root.join("c.rs"));
+
+        // The open file and deleted ones are left out.
+        app.handle_command(Command::OpenRecent);
+        assert_eq!(app.input_mode, InputMode::RecentPicker);
+        let picker = &app.recent_picker.as_ref().unwrap().picker;
+        assert_eq!(picker.len(), 1);
+        app.handle_command(Command::InsertNewline);
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.path(), Some(root.join("b.rs").as_path()));
+        assert_eq!(app.recent_files.files[0], root.join("b.rs"));
+    }
+
+    #[test]
+    fn workspace_search_fills_results_pane_and_jumps() {
+        use smash_input::{Key, KeyEvent, Modifiers};
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("out")).unwrap();
+        std::fs::write(root.join(".gitignore"), "out/\n").unwrap();
+        std::fs::write(
+            root.join("src/a.rs"),
+            "fn alpha() {}\nfn beta() { alpha() }\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/b.rs"), "// ALPHA\n").unwrap();
+        std::fs::write(root.join("out/gen.rs"), "alpha\n").unwrap();
+
+        let mut app = test_app();
+        app.handle_command(Command::WorkspaceSearch);
+        assert_eq!(app.input_mode, InputMode::PromptWorkspaceSearch);
+        app.input_mode = InputMode::Normal;
+        app.search_workspace(root.clone(), "alpha");
+        assert!(app.search_results_focused());
+        wait_until(
+            &mut app,
+            "search",
+            |app| {
+                !app.search_results
+                    .as_ref()
+                    .unwrap()
+                    .title()
+                    .contains("searching")
+            },
+            App::poll_workspace_search,
+        );
+        let results = app.search_results.as_ref().unwrap();
+        assert_eq!(results.title(), "Search \"alpha\": 3 matches in 2 files");
+        assert_eq!(
+            results.lines(),
+            vec![
+                "src/a.rs:1:4: fn alpha() {}",
+                "src/a.rs:2:13: fn beta() { alpha() }",
+                "src/b.rs:1:4: // ALPHA",
+            ]
+        );
+
+        let mut backend = smash_tui::MockBackend::new(60, 24);
+        app.render(&mut backend).unwrap();
+        assert!(app.normal_status().0.contains("[search]"));
+        let title_row = (0..24)
+            .find(|&y| backend.read_row(y).contains("Search \"alpha\""))
+            .expect("results pane drawn");
+        assert!(backend.read_row(title_row + 2).starts_with("src/a.rs:2:13"));
+
+        let key = |k| KeyEvent::new(k, Modifiers::NONE);
+        assert!(app.handle_search_results_key(&key(Key::Char('j'))));
+        assert!(app.handle_search_results_key(&key(Key::Enter)));
+        wait_for_open(&mut app);
+        assert_eq!(app.filename.as_deref(), Some("a.rs"));
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 12)
+        );
+        assert!(!app.search_results_focused());
+        assert!(app.search_results.is_some());
+
+        // A bad regex leaves the previous results alone.
+        app.search_workspace(root.clone(), "/(unclosed/");
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .starts_with("Invalid regex"));
+        assert_eq!(app.search_results.as_ref().unwrap().lines().len(), 3);
+
+        app.search_results_focused = true;
+        assert!(app.handle_search_results_key(&key(Key::Esc)));
+        assert!(app.search_results.is_none());
+    }
+
+    fn symbol(
+        name: &str,
+        kind: smash_lsp::SymbolKind,
+        uri: &str,
+        line: u32,
+    ) -> smash_lsp::SymbolInformation {
+        smash_lsp::SymbolInformation {
+            name: name.to_string(),
+            kind,
+            location: smash_lsp::Location {
+                uri: uri.to_string(),
+                range: smash_lsp::LspRange {
+                    start: smash_lsp::LspPosition { line, character: 1 },
+                    end: smash_lsp::LspPosition { line, character: 3 },
+                },
+            },
+            container_name: None,
+        }
+    }
+
+    #[test]
+    fn document_symbol_picker_filters_and_jumps() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let uri = app.current_uri().unwrap();
+        app.handle_command(Command::LspDocumentSymbols);
+        let mut requested = false;
+        while let Ok(cmd) = rx.try_recv() {
+            if let LspCommand::DocumentSymbols { uri: sent } = cmd {
+                assert_eq!(sent, uri);
+                requested = true;
+            }
+        }
+        assert!(requested, "documentSymbol request sent");
+
+        app.handle_lsp_event(LspEvent::DocumentSymbolsResult(vec![
+            symbol("hello", smash_lsp::SymbolKind::Function, &uri, 0),
+            symbol("world", smash_lsp::SymbolKind::Variable, &uri, 1),
+        ]));
+        assert_eq!(app.input_mode, InputMode::SymbolPicker);
+
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).unwrap();
+        assert!((0..24).any(|y| backend.read_row(y).contains("world  variable · line 2")));
+
+        for c in "wld".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 1)
+        );
+        assert!(app.symbol_picker.is_none());
+
+        // No match: Enter closes the picker without moving.
+        app.handle_lsp_event(LspEvent::DocumentSymbolsResult(vec![symbol(
+            "hello",
+            smash_lsp::SymbolKind::Function,
+            &uri,
+            0,
+        )]));
+        app.handle_command(Command::InsertChar('z'));
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 1)
+        );
+    }
+
+    #[test]
+    fn workspace_symbol_picker_requeries_and_ignores_stale_results() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let uri = app.current_uri().unwrap();
+        app.handle_command(Command::LspWorkspaceSymbols);
+        assert_eq!(app.input_mode, InputMode::SymbolPicker);
+        app.handle_command(Command::InsertChar('w'));
+        let mut queries = Vec::new();
+        while let Ok(cmd) = rx.try_recv() {
+            if let LspCommand::WorkspaceSymbols { query, .. } = cmd {
+                queries.push(query);
+            }
+        }
+        assert_eq!(queries, vec!["".to_string(), "w".to_string()]);
+
+        // A reply to the empty query arrives late and is dropped.
+        app.handle_lsp_event(LspEvent::WorkspaceSymbolsResult {
+            query: String::new(),
+            symbols: vec![symbol("hello", smash_lsp::SymbolKind::Function, &uri, 0)],
+        });
+        assert_eq!(app.symbol_picker.as_ref().unwrap().picker.len(), 0);
+
+        app.handle_lsp_event(LspEvent::WorkspaceSymbolsResult {
+            query: "w".to_string(),
+            symbols: vec![
+                symbol("walk", smash_lsp::SymbolKind::Struct, &uri, 1),
+                symbol("wake", smash_lsp::SymbolKind::Struct, &uri, 0),
+            ],
+        });
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 1)
+        );
+    }
+
+    #[test]
+    fn signature_help_follows_typing_and_closes_on_motion() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        app.signature_triggers = vec!["(".to_string(), ",".to_string()];
+        app.insert_text("\n\n\n\n\n");
+        while rx.try_recv().is_ok() {}
+
+        // Plain typing does not ask; the trigger does, after its didChange.
+        app.handle_command(Command::InsertChar('f'));
+        assert!(matches!(rx.try_recv(), Ok(LspCommand::DidChange { .. })));
+        assert!(rx.try_recv().is_err());
+        app.handle_command(Command::InsertChar('('));
+        assert!(matches!(rx.try_recv(), Ok(LspCommand::DidChange { .. })));
+        match rx.try_recv().expect("signature help requested") {
+            LspCommand::SignatureHelp { position, .. } => {
+                assert_eq!(position, smash_lsp::LspPosition::new(5, 2));
+            }
+            _ => panic!("expected SignatureHelp"),
+        }
+
+        let param = |label: &str| smash_lsp::ParameterInformation {
+            label: smash_lsp::ParameterLabel::Text(label.to_string()),
+            documentation: None,
+        };
+        let help = smash_lsp::SignatureHelp {
+            signatures: vec![smash_lsp::SignatureInformation {
+                label: "f(a: u8, b: u8)".to_string(),
+                documentation: Some(smash_lsp::Documentation::Text("Adds things.".to_string())),
+                parameters: vec![param("a: u8"), param("b: u8")],
+                active_parameter: None,
+            }],
+            active_signature: None,
+            active_parameter: Some(1),
+        };
+        app.handle_lsp_event(LspEvent::SignatureHelpResult(Some(help.clone())));
+        let popup = app.signature_popup().expect("signature shown");
+        assert_eq!(popup.lines, vec!["f(a: u8, b: u8)", "Adds things."]);
+        assert_eq!(popup.highlight, Some((0, 9..14)));
+
+        // Drawn above the cursor line.
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        let (_, cursor_row) = backend.cursor_position();
+        let label_row = (0..24)
+            .find(|&r| backend.read_row(r).contains("f(a: u8, b: u8)"))
+            .expect("popup drawn");
+        assert!(label_row < cursor_row);
+
+        // While open, every edit asks again; an answer arriving after the
+        // cursor moved elsewhere is ignored.
+        app.handle_command(Command::InsertChar('1'));
+        app.handle_command(Command::DeleteBackward);
+        let asked = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter(|c| matches!(c, LspCommand::SignatureHelp { .. }))
+            .count();
+        assert_eq!(asked, 2);
+        app.close_signature_help();
+        app.signature_request = Some(Position::new(5, 3));
+        app.handle_lsp_event(LspEvent::SignatureHelpResult(Some(help.clone())));
+        assert!(app.signature_help.is_none());
+        app.signature_request = Some(Position::new(5, 2));
+        app.handle_lsp_event(LspEvent::SignatureHelpResult(Some(help)));
+        assert!(app.signature_help.is_some());
+
+        // Moving away closes it; so does a reply saying there is no call.
+        app.handle_command(Command::MoveLeft);
+        assert!(app.signature_help.is_none());
+        assert!(app.signature_request.is_none());
+        app.handle_command(Command::InsertChar(','));
+        while rx.try_recv().is_ok() {}
+        app.handle_lsp_event(LspEvent::SignatureHelpResult(None));
+        assert!(app.signature_request.is_none());
+        app.handle_command(Command::InsertChar('x'));
+        while let Ok(cmd) = rx.try_recv() {
+            assert!(!matches!(cmd, LspCommand::SignatureHelp { .. }));
+        }
+    }
+
+    #[test]
+    fn show_health_reports_clipboard_and_language_server() {
+        let mut app = test_app();
+        app.handle_command(Command::ShowHealth);
+        let popup = app.popup.as_ref().expect("health popup");
+        assert_eq!(popup.title, "Health");
+        assert_eq!(popup.lines[0], "Clipboard: in-memory");
+        assert!(popup.lines[1].starts_with("Language server: "));
+
+        // A config change rebuilds only a system clipboard.
+        let mut config = smash_config::Config::default();
+        config.editor.clipboard = "osc52".to_string();
+        app.apply_config(&config);
+        assert_eq!(app.clipboard.describe(), "in-memory");
+        app.use_system_clipboard("xsel");
+        app.apply_config(&config);
+        assert_eq!(app.clipboard.describe(), "osc52");
+    }
+
+    #[test]
+    fn startup_profile_times_phases_and_lsp_spawn() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let ms = std::time::Duration::from_millis;
+        app.startup_profile.record(StartupPhase::ConfigLoad, ms(3));
+        // Only the first run of a phase counts.
+        app.startup_profile.record(StartupPhase::ConfigLoad, ms(9));
+        assert_eq!(
+            app.startup_profile.get(StartupPhase::ConfigLoad),
+            Some(ms(3))
+        );
+
+        app.lsp_server_started = false;
+        app.language_id = Some("rust".to_string());
+        app.lsp_server_configs.insert(
+            "rust".to_string(),
+            smash_config::LspServerEntry {
+                command: "rust-analyzer".to_string(),
+                args: Vec::new(),
+                extensions: Vec::new(),
+                disable: Vec::new(),
+            },
+        );
+        app.start_lsp_for_current_file();
+        assert!(matches!(rx.try_recv(), Ok(LspCommand::StartServer(_))));
+        app.handle_command(Command::ShowHealth);
+        let lines = &app.popup.as_ref().expect("health popup").lines;
+        assert!(lines.contains(&"  Config load: 3.0 ms".to_string()));
+        assert!(lines.contains(&"  LSP spawn: waiting".to_string()));
+        assert!(lines.contains(&"  First render: not run".to_string()));
+
+        app.handle_lsp_event(LspEvent::ServerStarted {
+            language: "rust".to_string(),
+            sync: TextDocumentSyncKind::Full,
+            save: Default::default(),
+            will_save_wait_until: false,
+            formatting: false,
+            range_formatting: false,
+            inlay_hints: false,
+            completion_triggers: Vec::new(),
+            signature_triggers: Vec::new(),
+            position_encoding: Default::default(),
+        });
+        assert!(app.startup_profile.get(StartupPhase::LspSpawn).is_some());
+        let report = app.startup_profile.report();
+        assert!(report.starts_with("smash startup profile\n  Config load: 3.0 ms\n"));
+    }
+
+    #[test]
+    fn completion_is_requested_after_a_pause_in_typing() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        app.completion_triggers = vec![".".to_string()];
+        app.insert_text("\n\n\n\n\n");
+        while rx.try_recv().is_ok() {}
+        let completions = |rx: &mut tokio::sync::mpsc::Receiver<LspCommand>| {
+            std::iter::from_fn(|| rx.try_recv().ok())
+                .filter_map(|c| match c {
+                    LspCommand::Completion { position, .. } => Some(position),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let later = || std::time::Instant::now() + completion::COMPLETION_DELAY;
+
+        // Each keystroke restarts the wait; one request follows the pause.
+        app.handle_command(Command::InsertChar('f'));
+        app.handle_command(Command::InsertChar('o'));
+        app.poll_completion(std::time::Instant::now());
+        assert!(completions(&mut rx).is_empty());
+        assert!(app.next_timer(std::time::Instant::now()).is_some());
+        app.poll_completion(later());
+        assert_eq!(
+            completions(&mut rx),
+            vec![smash_lsp::LspPosition::new(5, 2)]
+        );
+        assert_eq!(app.next_timer(std::time::Instant::now()), None);
+
+        let item = |label: &str| smash_lsp::CompletionItem {
+            label: label.to_string(),
+            kind: None,
+            detail: None,
+            insert_text: None,
+            documentation: None,
+        };
+        let messages = app.messages.len();
+        app.handle_lsp_event(LspEvent::CompletionResult(vec![item("foo")]));
+        assert_eq!(app.popup.as_ref().unwrap().title, "Completions (1)");
+        assert_eq!(app.messages.len(), messages);
+
+        // A trigger character asks too; digits and motion do not, and a
+        // reply to a request the cursor has moved on from is dropped.
+        app.popup = None;
+        app.handle_command(Command::InsertChar('.'));
+        app.poll_completion(later());
+        assert_eq!(completions(&mut rx).len(), 1);
+        app.handle_command(Command::MoveLeft);
+        app.handle_lsp_event(LspEvent::CompletionResult(vec![item("bar")]));
+        assert!(app.popup.is_none());
+        app.handle_command(Command::MoveLineEnd);
+        app.handle_command(Command::InsertChar(' '));
+        app.handle_command(Command::InsertChar('4'));
+        app.handle_command(Command::InsertChar('2'));
+        app.poll_completion(later());
+        assert!(completions(&mut rx).is_empty());
+    }
+
+    #[test]
+    fn hover_is_shown_quietly_once_input_pauses() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        app.insert_text("one two\n");
+        app.hover_on_idle = true;
+        while rx.try_recv().is_ok() {}
+        let hovers = |rx: &mut tokio::sync::mpsc::Receiver<LspCommand>| {
+            std::iter::from_fn(|| rx.try_recv().ok())
+                .filter(|c| matches!(c, LspCommand::Hover { .. }))
+                .count()
+        };
+        let now = std::time::Instant::now();
+        let later = now + idle::IDLE_DELAY;
+
+        // Nothing is asked until input has paused.
+        app.note_input(now);
+        assert_eq!(app.next_timer(now), Some(idle::IDLE_DELAY));
+        app.poll_input_idle(now);
+        assert_eq!(hovers(&mut rx), 0);
+        app.poll_input_idle(later);
+        assert_eq!(hovers(&mut rx), 1);
+        let messages = app.messages.len();
+        app.handle_lsp_event(LspEvent::HoverResult(Some("fn one()".into())));
+        assert_eq!(app.popup.as_ref().unwrap().lines, vec!["fn one()"]);
+        assert_eq!(app.messages.len(), messages);
+
+        // Pausing again in the same place does not ask again.
+        app.popup = None;
+        app.note_input(now);
+        app.poll_input_idle(later);
+        assert_eq!(hovers(&mut rx), 0);
+
+        // An empty answer says nothing; one for a cursor that has moved
+        // on is dropped.
+        app.handle_command(Command::MoveUp);
+        app.note_input(now);
+        app.poll_input_idle(later);
+        assert_eq!(hovers(&mut rx), 1);
+        app.handle_lsp_event(LspEvent::HoverResult(None));
+        assert_eq!(app.messages.len(), messages);
+        app.handle_command(Command::MoveRight);
+        app.note_input(now);
+        app.poll_input_idle(later);
+        assert_eq!(hovers(&mut rx), 1);
+        app.handle_command(Command::MoveRight);
+        app.handle_lsp_event(LspEvent::HoverResult(Some("fn one()".into())));
+        assert!(app.popup.is_none());
+    }
+
+    #[test]
+    fn diagnostic_counts_use_signs_and_severity_emphasis() {
+        use smash_lsp::{Diagnostic, DiagnosticSeverity, LspPosition, LspRange};
+
+        let mut app = test_app();
+        let mut config = smash_config::Config::default();
+        config.display.theme = "accessible".to_string();
+        config.display.diagnostic_signs.error = 'x';
+        app.apply_config(&config);
+        assert_eq!(app.theme.name(), "accessible");
+
+        app.insert_text("one\ntwo\n");
+        let diagnostic = |line, severity| Diagnostic {
+            range: LspRange::new(LspPosition::new(line, 0), LspPosition::new(line, 1)),
+            severity: Some(severity),
+            message: "m".to_string(),
+            source: None,
+            code: None,
+            code_description: None,
+            related_information: Vec::new(),
+        };
+        app.current_diagnostics = vec![diagnostic(1, DiagnosticSeverity::Error)];
+        let (text, emphasis) = app.normal_status();
+        assert!(text.contains(" xE:1 \u{25b2}W:0"), "{}", text);
+        // Only the non-zero count is emphasised.
+        assert_eq!(emphasis.len(), 1);
+        let (range, severity) = emphasis[0].clone();
+        assert_eq!(severity, smash_tui::GutterDiagnostic::Error);
+        let chars: Vec<char> = text.chars().collect();
+        assert_eq!(chars[range].iter().collect::<String>(), "xE:1");
+
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        let screen = app.renderer.screen();
+        let error_style = app.theme.diagnostic_error_style();
+        assert_eq!(screen.get(0, 1).unwrap().ch, 'x');
+        let status = (0..80).find(|&c| screen.get(c, 23).unwrap().ch == 'x');
+        let cell = screen.get(status.expect("sign in status bar"), 23).unwrap();
+        assert_eq!(cell.style.fg, error_style.fg);
+        assert!(cell.style.attrs.bold());
+    }
+
+    #[test]
+    fn startup_paints_before_compiling_the_highlighter() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, "fn main() {}\n").expect("write");
+        let (mut app, _lsp_cmd_rx) = TestApp::default()
+            .file(path)
+            .lsp(std::collections::HashMap::new())
+            .build_with_lsp();
+        assert!(app.highlighter.is_none());
+        app.queue_idle(IdleTask::Highlighter);
+        assert_eq!(app.idle_tasks.len(), 1);
+
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        assert!(backend.read_row(0).contains("fn main() {}"));
+
+        assert!(app.run_idle_task());
+        assert!(app.highlighter.is_some());
+        assert!(!app.has_idle_tasks());
+        assert!(!app.run_idle_task());
+
+        // The overlay reports the first paint against its budget.
+        app.handle_command(Command::ToggleRenderStats);
+        app.render(&mut backend).expect("render");
+        let rows: Vec<String> = (0..24).map(|r| backend.read_row(r)).collect();
+        assert!(rows[0].contains("Render stats"));
+        assert!(rows
+            .iter()
+            .any(|r| r.contains("First paint: ") && r.contains("budget 50.0 ms")));
+        assert!(rows.iter().any(|r| r.contains("Frames: 1")));
+        app.handle_command(Command::ToggleRenderStats);
+        app.render(&mut backend).expect("render");
+        assert!(!backend.read_row(0).contains("Render stats"));
+    }
+
+    #[test]
+    fn code_action_picker_applies_workspace_edits_and_runs_commands() {
+        use smash_lsp::{
+            CodeAction, DocumentChange, LspPosition, LspRange, ResourceOperation, ResourceOptions,
+            ServerCommand, TextDocumentEdit, TextEdit, VersionedTextDocumentIdentifier,
+            WorkspaceEdit,
+        };
+
+        let (mut app, mut rx, dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let uri = app.current_uri().unwrap();
+        let other = dir.path().join("other.txt");
+        std::fs::write(&other, "one\ntwo\n").unwrap();
+        let other_uri = App::path_to_uri(&other);
+        let moved = dir.path().join("sub/moved.txt");
+
+        app.handle_command(Command::LspCodeAction);
+        assert!(matches!(rx.try_recv(), Ok(LspCommand::CodeAction { .. })));
+
+        let insert = |uri: &str, line: u32, text: &str| {
+            DocumentChange::Edit(TextDocumentEdit {
+                text_document: VersionedTextDocumentIdentifier {
+                    uri: uri.to_string(),
+                    version: None,
+                },
+                edits: vec![TextEdit {
+                    range: LspRange::new(LspPosition::new(line, 0), LspPosition::new(line, 0)),
+                    new_text: text.to_string(),
+                }],
+            })
+        };
+        let rename_all = CodeAction {
+            title: "Rename everywhere".to_string(),
+            kind: Some("refactor.rewrite".to_string()),
+            edit: Some(WorkspaceEdit {
+                changes: None,
+                document_changes: Some(vec![
+                    insert(&uri, 0, ">"),
+                    insert(&other_uri, 1, "+"),
+                    DocumentChange::Resource(ResourceOperation::Rename {
+                        old_uri: other_uri.clone(),
+                        new_uri: App::path_to_uri(&moved),
+                        options: ResourceOptions::default(),
+                    }),
+                ]),
+            }),
+            diagnostics: Vec::new(),
+            is_preferred: false,
+            command: None,
+        };
+        let run = CodeAction {
+            title: "Run test".to_string(),
+            kind: None,
+            edit: None,
+            diagnostics: Vec::new(),
+            is_preferred: false,
+            command: Some(ServerCommand {
+                title: "Run test".to_string(),
+                command: "test.run".to_string(),
+                arguments: vec![],
+            }),
+        };
+        app.handle_lsp_event(LspEvent::CodeActionResult(vec![
+            run.clone(),
+            rename_all.clone(),
+        ]));
+        assert_eq!(app.input_mode, InputMode::CodeActionPicker);
+        assert_eq!(app.code_action_picker.as_ref().unwrap().picker.len(), 2);
+
+        // Filter down to the edit and apply it.
+        for c in "every".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        assert_eq!(
+            app.code_action_picker
+                .as_ref()
+                .unwrap()
+                .picker
+                .match_count(),
+            1
+        );
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.code_action_picker.is_none());
+        assert_eq!(app.buffer.text().to_string(), ">hello\nworld\n");
+        assert!(!other.exists());
+        assert_eq!(std::fs::read_to_string(&moved).unwrap(), "one\n+two\n");
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Applied 'Rename everywhere' across 3 files"
+        );
+        assert!(matches!(rx.try_recv(), Ok(LspCommand::DidChange { .. })));
+
+        // An action with only a command is run on the server.
+        app.handle_lsp_event(LspEvent::CodeActionResult(vec![run]));
+        app.handle_command(Command::InsertNewline);
+        match rx.try_recv() {
+            Ok(LspCommand::ExecuteCommand { command, .. }) => {
+                assert_eq!(command.command, "test.run")
+            }
+            _ => panic!("expected ExecuteCommand"),
+        }
+
+        // Edits computed for an older version of the buffer are refused.
+        let mut stale = rename_all;
+        if let Some(DocumentChange::Edit(doc)) = stale
+            .edit
+            .as_mut()
+            .and_then(|e| e.document_changes.as_mut())
+            .and_then(|c| c.first_mut())
+        {
+            doc.text_document.version = Some(app.document_version - 1);
+        }
+        app.apply_code_action(stale);
+        assert_eq!(app.buffer.text().to_string(), ">hello\nworld\n");
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .starts_with("Could not apply 'Rename everywhere'"));
+    }
+
+    #[test]
+    fn server_commands_report_progress_and_apply_requested_edits() {
+        use smash_lsp::{
+            CodeAction, LspPosition, LspRange, ServerCommand, ServerRequest, TextEdit,
+            WorkspaceEdit,
+        };
+
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let uri = app.current_uri().unwrap();
+        let command = ServerCommand {
+            title: "Organize imports".to_string(),
+            command: "imports.organize".to_string(),
+            arguments: vec![],
+        };
+        app.apply_code_action(CodeAction {
+            title: "Organize imports".to_string(),
+            kind: None,
+            edit: None,
+            diagnostics: Vec::new(),
+            is_preferred: false,
+            command: Some(command.clone()),
+        });
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(LspCommand::ExecuteCommand { .. })
+        ));
+        assert!(app
+            .normal_status()
+            .0
+            .contains("[running 'Organize imports']"));
+
+        // The server edits the buffer while the command runs.
+        let (writer, mut answers) = tokio::sync::mpsc::channel(4);
+        let request = |id| {
+            ServerRequest::new(
+                id,
+                "workspace/applyEdit".to_string(),
+                serde_json::Value::Null,
+                writer.clone(),
+            )
+        };
+        let mut changes = std::collections::HashMap::new();
+        changes.insert(
+            uri.clone(),
+            vec![TextEdit {
+                range: LspRange::new(LspPosition::new(0, 0), LspPosition::new(0, 0)),
+                new_text: "use a;\n".to_string(),
+            }],
+        );
+        let edit = WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+        };
+        app.handle_lsp_event(LspEvent::ApplyEdit {
+            label: Some("Organize imports".to_string()),
+            edit,
+            request: request(5),
+        });
+        assert!(app.buffer.text().to_string().starts_with("use a;\n"));
+        let answer = String::from_utf8(answers.try_recv().unwrap()).unwrap();
+        assert!(answer.contains(r#""id":5"#));
+        assert!(answer.contains(r#""applied":true"#));
+
+        // Edits that cannot be made are refused with a reason.
+        let mut missing = std::collections::HashMap::new();
+        missing.insert(
+            "file:///does/not/exist.txt".to_string(),
+            vec![TextEdit {
+                range: LspRange::new(LspPosition::new(0, 0), LspPosition::new(0, 0)),
+                new_text: "x".to_string(),
+            }],
+        );
+        app.handle_lsp_event(LspEvent::ApplyEdit {
+            label: None,
+            edit: WorkspaceEdit {
+                changes: Some(missing),
+                document_changes: None,
+            },
+            request: request(6),
+        });
+        let answer = String::from_utf8(answers.try_recv().unwrap()).unwrap();
+        assert!(answer.contains(r#""applied":false"#));
+        assert!(answer.contains("failureReason"));
+
+        app.handle_lsp_event(LspEvent::CommandFinished {
+            command: command.clone(),
+            result: Ok(()),
+        });
+        assert!(app.running_commands.is_empty());
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Finished 'Organize imports'"
+        );
+
+        app.running_commands.push(command.title.clone());
+        app.handle_lsp_event(LspEvent::CommandFinished {
+            command,
+            result: Err("request timed out".to_string()),
+        });
+        assert!(!app.normal_status().0.contains("[running"));
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "'Organize imports' failed: request timed out"
+        );
+    }
+
+    #[test]
+    fn multiple_cursors_from_the_keyboard() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "foo bar\nx\nfoo baz\nfoo\n");
+        let positions = |app: &App| -> Vec<Position> {
+            app.buffer.cursors().iter().map(|c| c.position()).collect()
+        };
+
+        // Cursors below keep the column they aim for.
+        app.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(0, 5));
+        app.handle_command(Command::AddCursorBelow);
+        app.handle_command(Command::AddCursorBelow);
+        assert_eq!(
+            positions(&app),
+            vec![
+                Position::new(0, 5),
+                Position::new(1, 1),
+                Position::new(2, 5)
+            ]
+        );
+        app.handle_command(Command::InsertChar('!'));
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "foo b!ar\nx!\nfoo b!az\nfoo\n"
+        );
+        // Motions move every cursor.
+        app.handle_command(Command::MoveLineStart);
+        assert!(positions(&app).iter().all(|p| p.col == 0));
+        assert_eq!(app.buffer.cursors().len(), 3);
+        assert!(app.collapse_cursors());
+        assert!(!app.collapse_cursors());
+        app.handle_command(Command::AddCursorAbove);
+        assert_eq!(app.buffer.cursors().len(), 1, "nothing above line 0");
+
+        // The first press selects the word, later ones add its matches.
+        app.handle_command(Command::AddCursorAtNextMatch);
+        assert_eq!(app.selected_text().as_deref(), Some("foo"));
+        app.handle_command(Command::AddCursorAtNextMatch);
+        app.handle_command(Command::AddCursorAtNextMatch);
+        assert_eq!(app.buffer.cursors().len(), 3);
+        app.handle_command(Command::AddCursorAtNextMatch);
+        assert!(app
+            .messages
+            .last()
+            .unwrap()
+            .text()
+            .starts_with("No more matches"));
+        // Typing replaces every selection.
+        for c in "qux".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "qux b!ar\nx!\nqux b!az\nqux\n"
+        );
+    }
+
+    #[test]
+    fn vertical_moves_return_to_the_goal_column() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(
+            BufferId::next(),
+            "long line
+ab
+
+longer line
+",
+        );
+        app.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(0, 7));
+        let pos = |app: &App| app.buffer.cursors().primary().position();
+
+        app.handle_command(Command::MoveDown);
+        assert_eq!(pos(&app), Position::new(1, 2));
+        app.handle_command(Command::MoveDown);
+        assert_eq!(pos(&app), Position::new(2, 0));
+        app.handle_command(Command::MoveDown);
+        assert_eq!(pos(&app), Position::new(3, 7));
+        app.handle_command(Command::PageUp);
+        assert_eq!(pos(&app), Position::new(0, 7));
+
+        // A horizontal move sets a new goal.
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::MoveLeft);
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::MoveDown);
+        assert_eq!(pos(&app), Position::new(3, 1));
+    }
+
+    #[test]
+    fn cursor_steps_over_graphemes_and_is_drawn_after_wide_characters() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "👍🏽字x\nabcdef\n");
+        let pos = |app: &App| app.buffer.cursors().primary().position();
+
+        app.handle_command(Command::MoveRight);
+        assert_eq!(pos(&app), Position::new(0, 2));
+        app.handle_command(Command::MoveRight);
+        assert_eq!(pos(&app), Position::new(0, 3));
+
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        app.render(&mut backend).expect("render");
+        assert_eq!(backend.cursor_position(), (app.gutter_width() + 4, 0));
+
+        // Straight down lands at the same screen column.
+        app.handle_command(Command::MoveDown);
+        assert_eq!(pos(&app), Position::new(1, 4));
+        app.handle_command(Command::MoveUp);
+        app.handle_command(Command::MoveLeft);
+        assert_eq!(pos(&app), Position::new(0, 2));
+        app.handle_command(Command::DeleteBackward);
+        assert_eq!(app.buffer.text().to_string(), "字x\nabcdef\n");
+    }
+
+    #[test]
+    fn typing_and_deleting_replace_selections_at_every_cursor() {
+        use smash_core::cursor::Cursor;
+
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "let foo = 1;\nlet foo = 2;\n");
+        let select_foo = |app: &mut App| {
+            let cursors = app.buffer.cursors_mut();
+            *cursors.primary_mut() =
+                Cursor::with_selection(Position::new(0, 7), Position::new(0, 4));
+            cursors.add(Cursor::with_selection(
+                Position::new(1, 7),
+                Position::new(1, 4),
+            ));
+        };
+
+        select_foo(&mut app);
+        app.handle_command(Command::InsertChar('x'));
+        assert_eq!(app.buffer.text().to_string(), "let x = 1;\nlet x = 2;\n");
+        app.handle_command(Command::Undo);
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "let foo = 1;\nlet foo = 2;\n"
+        );
+
+        select_foo(&mut app);
+        app.handle_command(Command::DeleteBackward);
+        assert_eq!(app.buffer.text().to_string(), "let  = 1;\nlet  = 2;\n");
+        app.handle_command(Command::DeleteForward);
+        assert_eq!(app.buffer.text().to_string(), "let = 1;\nlet = 2;\n");
+        app.handle_command(Command::Undo);
+        app.handle_command(Command::Undo);
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "let foo = 1;\nlet foo = 2;\n"
+        );
+
+        // A single cursor's selection goes the same way.
+        app.buffer.cursors_mut().clear_secondary();
+        *app.buffer.cursors_mut().primary_mut() =
+            Cursor::with_selection(Position::new(0, 12), Position::new(0, 8));
+        app.handle_command(Command::DeleteBackward);
+        assert_eq!(app.buffer.text().to_string(), "let foo \nlet foo = 2;\n");
+        assert!(!app.buffer.cursors().primary().has_selection());
+    }
+
+    #[test]
+    fn undo_takes_back_typing_a_word_at_a_time() {
+        let mut app = test_app();
+        for c in "hello world".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::DeleteBackward);
+        app.handle_command(Command::DeleteBackward);
+        assert_eq!(app.buffer.text().to_string(), "hello wor");
+
+        app.handle_command(Command::Undo);
+        assert_eq!(app.buffer.text().to_string(), "hello world");
+        app.handle_command(Command::Undo);
+        assert_eq!(app.buffer.text().to_string(), "hello ");
+        app.handle_command(Command::Undo);
+        assert_eq!(app.buffer.text().to_string(), "");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 0)
+        );
+    }
+
+    #[test]
+    fn smart_home_toggles_between_indent_and_line_start() {
+        let mut app = test_app();
+        app.insert_text("    let x = 1;");
+        app.handle_command(Command::SmartHome);
+        assert_eq!(app.buffer.cursors().primary().position().col, 4);
+        app.handle_command(Command::SmartHome);
+        assert_eq!(app.buffer.cursors().primary().position().col, 0);
+        app.handle_command(Command::SelectSmartHome);
+        let cursor = app.buffer.cursors().primary();
+        assert_eq!(cursor.position().col, 4);
+        assert_eq!(cursor.anchor(), Some(Position::new(0, 0)));
+    }
+
+    #[test]
+    fn line_commands_move_duplicate_and_join_as_one_undo_step() {
+        let mut app = test_app();
+        app.insert_text("one\ntwo\n    three\nfour");
+        let set = |app: &mut App, line, col| {
+            app.buffer
+                .cursors_mut()
+                .primary_mut()
+                .set_position(Position::new(line, col));
+        };
+        let text = |app: &App| app.buffer.text().to_string();
+
+        set(&mut app, 1, 2);
+        app.handle_command(Command::MoveLineUp);
+        assert_eq!(text(&app), "two\none\n    three\nfour");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 2)
+        );
+        app.handle_command(Command::MoveLineUp);
+        assert_eq!(text(&app), "two\none\n    three\nfour");
+        app.handle_command(Command::Undo);
+        assert_eq!(text(&app), "one\ntwo\n    three\nfour");
+
+        // Selected lines move together, keeping their selection.
+        set(&mut app, 0, 1);
+        app.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_anchor(Position::new(1, 1));
+        app.handle_command(Command::MoveLineDown);
+        assert_eq!(text(&app), "    three\none\ntwo\nfour");
+        let cursor = app.buffer.cursors().primary();
+        assert_eq!(cursor.position(), Position::new(1, 1));
+        assert_eq!(cursor.anchor(), Some(Position::new(2, 1)));
+        app.handle_command(Command::MoveLineDown);
+        app.handle_command(Command::MoveLineDown);
+        assert_eq!(text(&app), "    three\nfour\none\ntwo");
+        app.handle_command(Command::Undo);
+        app.handle_command(Command::Undo);
+        assert_eq!(text(&app), "one\ntwo\n    three\nfour");
+
+        set(&mut app, 3, 2);
+        app.handle_command(Command::DuplicateLine);
+        assert_eq!(text(&app), "one\ntwo\n    three\nfour\nfour");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(4, 2)
+        );
+        app.handle_command(Command::Undo);
+
+        // Joining drops the next line's indentation.
+        set(&mut app, 1, 0);
+        app.handle_command(Command::JoinLines);
+        assert_eq!(text(&app), "one\ntwo three\nfour");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(1, 3)
+        );
+        app.handle_command(Command::Undo);
+        assert_eq!(text(&app), "one\ntwo\n    three\nfour");
+        set(&mut app, 3, 0);
+        app.handle_command(Command::JoinLines);
+        assert_eq!(text(&app), "one\ntwo\n    three\nfour");
+    }
+
+    #[test]
+    fn case_commands_change_the_selection_or_the_word_at_the_cursor() {
+        let mut app = test_app();
+        app.insert_text("hello big world");
+        let text = |app: &App| app.buffer.text().to_string();
+        app.set_cursor(Position::new(0, 7));
+        app.handle_command(Command::UppercaseSelection);
+        assert_eq!(text(&app), "hello BIG world");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 7)
+        );
+
+        app.handle_command(Command::SelectAll);
+        app.handle_command(Command::TitleCaseSelection);
+        assert_eq!(text(&app), "Hello Big World");
+        let cursor = app.buffer.cursors().primary();
+        assert_eq!(cursor.anchor(), Some(Position::new(0, 0)));
+        assert_eq!(cursor.position(), Position::new(0, 15));
+        app.handle_command(Command::LowercaseSelection);
+        assert_eq!(text(&app), "hello big world");
+        app.handle_command(Command::Undo);
+        assert_eq!(text(&app), "Hello Big World");
+    }
+
+    #[test]
+    fn transpose_swaps_characters_and_words() {
+        let mut app = test_app();
+        app.insert_text("ab cd");
+        let text = |app: &App| app.buffer.text().to_string();
+        app.set_cursor(Position::new(0, 1));
+        app.handle_command(Command::TransposeChars);
+        assert_eq!(text(&app), "ba cd");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 2)
+        );
+        // At the end of the line the last two characters swap.
+        app.set_cursor(Position::new(0, 5));
+        app.handle_command(Command::TransposeChars);
+        assert_eq!(text(&app), "ba dc");
+
+        app.set_cursor(Position::new(0, 0));
+        app.handle_command(Command::TransposeWords);
+        assert_eq!(text(&app), "dc ba");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(0, 5)
+        );
+        app.handle_command(Command::TransposeWords);
+        assert_eq!(app.messages.last().unwrap().text(), "No words to transpose");
+    }
+
+    #[test]
+    fn abbreviations_expand_at_a_boundary_and_backspace_takes_them_back() {
+        let mut app = test_app();
+        app.buffer.set_path("main.rs".into());
+        let mut config = smash_config::Config::default();
+        config.abbreviations.insert("teh".into(), "the".into());
+        config.abbreviations.insert("fnret".into(), "return".into());
+        config.languages.insert(
+            "rust".to_string(),
+            smash_config::config::LanguageConfig {
+                abbreviations: BTreeMap::from([("fnret".into(), "fn f() -> T {\n}".into())]),
+                ..Default::default()
+            },
+        );
+        app.apply_config(&config);
+        let text = |app: &App| app.buffer.text().to_string();
+        let type_text = |app: &mut App, s: &str| {
+            for c in s.chars() {
+                app.handle_command(match c {
+                    '\n' => Command::InsertNewline,
+                    c => Command::InsertChar(c),
+                });
+            }
+        };
+
+        type_text(&mut app, "teh cat, ateh teh.");
+        assert_eq!(text(&app), "the cat, ateh the.");
+        // Backspace right after the expansion restores the trigger,
+        // leaving the boundary; a second one deletes as usual.
+        app.handle_command(Command::DeleteBackward);
+        assert_eq!(text(&app), "the cat, ateh teh.");
+        app.handle_command(Command::DeleteBackward);
+        assert_eq!(text(&app), "the cat, ateh teh");
+
+        // The language table wins, and expansion plus boundary undo as one.
+        type_text(&mut app, "\nfnret ");
+        assert_eq!(text(&app), "the cat, ateh the\nfn f() -> T {\n} ");
+        app.handle_command(Command::Undo);
+        assert_eq!(text(&app), "the cat, ateh the\nfnret");
+        // Only right after: moving the cursor forgets the expansion.
+        type_text(&mut app, " teh ");
+        app.handle_command(Command::MoveLeft);
+        app.handle_command(Command::MoveRight);
+        app.handle_command(Command::DeleteBackward);
+        assert!(text(&app).ends_with("{\n} the"));
+    }
+
+    #[test]
+    fn did_save_follows_the_servers_save_options() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        app.handle_command(Command::Save);
+        assert!(rx.try_recv().is_err(), "server did not ask for didSave");
+
+        app.lsp_save = SaveNotification::Notify;
+        app.handle_command(Command::Save);
+        match rx.try_recv().expect("didSave sent") {
+            LspCommand::DidSave { text, .. } => assert_eq!(text, None),
+            _ => panic!("expected DidSave"),
+        }
+
+        app.lsp_save = SaveNotification::IncludeText;
+        app.handle_command(Command::Save);
+        match rx.try_recv().expect("didSave sent") {
+            LspCommand::DidSave { text, .. } => {
+                assert_eq!(text.as_deref(), Some("hello\nworld\n"));
+            }
+            _ => panic!("expected DidSave"),
+        }
+    }
+
+    #[test]
+    fn save_waits_for_will_save_edits_before_writing() {
+        let (mut app, mut rx, dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let path = dir.path().join("doc.txt");
+        app.lsp_will_save_wait_until = true;
+        app.handle_command(Command::InsertChar('X'));
+        while rx.try_recv().is_ok() {}
+
+        app.handle_command(Command::Save);
+        let uri = match rx.try_recv().expect("willSaveWaitUntil sent") {
+            LspCommand::WillSaveWaitUntil { uri, format: false } => uri,
+            _ => panic!("expected WillSaveWaitUntil"),
+        };
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\nworld\n");
+        assert!(app.next_timer(std::time::Instant::now()).is_some());
+
+        let edit = smash_lsp::TextEdit {
+            range: smash_lsp::LspRange::new(
+                smash_lsp::LspPosition::new(1, 5),
+                smash_lsp::LspPosition::new(1, 5),
+            ),
+            new_text: "!".to_string(),
+        };
+        app.finish_pending_save(&uri, vec![edit]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Xhello\nworld!\n");
+        assert!(!app.buffer.is_dirty());
+        assert!(app.pending_save.is_none());
+
+        // A server that never answers does not hold the save forever.
+        app.handle_command(Command::InsertChar('Y'));
+        app.handle_command(Command::Save);
+        let later = std::time::Instant::now() + saving::WILL_SAVE_TIMEOUT;
+        assert!(app.poll_pending_save(later));
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with("XY"));
+        // Its late answer changes nothing.
+        app.finish_pending_save(&uri, Vec::new());
+        assert!(!app.buffer.is_dirty());
+    }
+
+    #[test]
+    fn format_selection_sends_the_selected_range() {
+        let (mut app, mut rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        app.handle_command(Command::LspFormatSelection);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Select the text to format first"
+        );
+
+        app.handle_command(Command::SelectAll);
+        app.lsp_formatting = true;
+        app.handle_command(Command::LspFormatSelection);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Server cannot format a selection; use Format document instead"
+        );
+        assert!(rx.try_recv().is_err());
+
+        app.lsp_range_formatting = true;
+        app.handle_command(Command::LspFormatSelection);
+        match rx.try_recv().expect("range formatting requested") {
+            LspCommand::RangeFormat { range, .. } => {
+                assert_eq!((range.start.line, range.start.character), (0, 0));
+                assert_eq!((range.end.line, range.end.character), (2, 0));
+            }
+            _ => panic!("expected range formatting"),
+        }
+    }
+
+    #[test]
+    fn status_shows_lsp_facilities_and_formats_on_save() {
+        let (mut app, mut rx, dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let path = dir.path().join("doc.txt");
+        assert_eq!(app.lsp_status(), " [LSP]");
+
+        app.format_on_save = true;
+        assert!(app.lsp_status().contains("fmt-on-save:n/a"));
+        app.lsp_formatting = true;
+        app.lsp_inlay_hints = true;
+        assert_eq!(app.lsp_status(), " [LSP fmt fmt-on-save hints:off]");
+
+        // Saving waits for the server's formatting.
+        app.handle_command(Command::InsertChar('X'));
+        while rx.try_recv().is_ok() {}
+        app.handle_command(Command::Save);
+        let uri = match rx.try_recv().expect("formatting requested") {
+            LspCommand::WillSaveWaitUntil { uri, format: true } => uri,
+            _ => panic!("expected formatting before save"),
+        };
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\nworld\n");
+        app.finish_pending_save(&uri, Vec::new());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Xhello\nworld\n");
+
+        // A configured server that is not running is called out.
+        app.lsp_server_started = false;
+        app.language_id = Some("rust".to_string());
+        assert_eq!(app.lsp_status(), "");
+        app.lsp_server_configs.insert(
+            "rust".to_string(),
+            smash_config::LspServerEntry {
+                command: "rust-analyzer".to_string(),
+                args: Vec::new(),
+                extensions: Vec::new(),
+                disable: Vec::new(),
+            },
+        );
+        assert_eq!(app.lsp_status(), " [LSP off]");
+        app.lsp_enabled = false;
+        assert_eq!(app.lsp_status(), "");
+    }
+
+    #[test]
+    fn organize_imports_runs_before_formatting_on_save() {
+        use smash_lsp::{LspPosition, LspRange, TextEdit};
+
+        let (mut app, mut rx, dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let path = dir.path().join("doc.txt");
+        app.lsp_formatting = true;
+        app.format_on_save = true;
+        app.organize_imports_on_save = true;
+        assert_eq!(app.lsp_status(), " [LSP fmt fmt-on-save imports-on-save]");
+
+        app.handle_command(Command::InsertChar('X'));
+        while rx.try_recv().is_ok() {}
+        app.handle_command(Command::Save);
+        let uri = match rx.try_recv().expect("organize imports requested") {
+            LspCommand::OrganizeImports { uri, range } => {
+                assert_eq!(range.end, LspPosition::new(2, 0));
+                uri
+            }
+            _ => panic!("expected organize imports before save"),
+        };
+        let edit = TextEdit {
+            range: LspRange::new(LspPosition::new(1, 0), LspPosition::new(1, 5)),
+            new_text: "earth".to_string(),
+        };
+        app.finish_pending_save(&uri, vec![edit]);
+        match rx.try_recv().expect("didChange sent") {
+            LspCommand::DidChange { .. } => {}
+            _ => panic!("expected the organized text to be synced"),
+        }
+        match rx.try_recv().expect("formatting requested") {
+            LspCommand::WillSaveWaitUntil { format: true, .. } => {}
+            _ => panic!("expected formatting after organizing imports"),
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\nworld\n");
+        app.finish_pending_save(&uri, Vec::new());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Xhello\nearth\n");
+
+        // The language's overrides win over the [lsp] settings.
+        app.language_configs.insert(
+            "plain".to_string(),
+            smash_config::config::LanguageConfig {
+                format_on_save: Some(false),
+                organize_imports_on_save: Some(false),
+                ..Default::default()
+            },
+        );
+        assert_eq!(app.lsp_status(), " [LSP fmt]");
+        app.handle_command(Command::InsertChar('Y'));
+        while rx.try_recv().is_ok() {}
+        app.handle_command(Command::Save);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "XYhello\nearth\n");
+    }
+
+    #[test]
+    fn save_all_and_quit_format_before_writing() {
+        let (mut app, mut rx, dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let path = dir.path().join("doc.txt");
+        app.lsp_formatting = true;
+        app.format_on_save = true;
+
+        app.handle_command(Command::InsertChar('X'));
+        while rx.try_recv().is_ok() {}
+        app.handle_command(Command::SaveAll);
+        let uri = match rx.try_recv().expect("formatting requested") {
+            LspCommand::WillSaveWaitUntil { uri, format: true } => uri,
+            _ => panic!("expected formatting before Save All"),
+        };
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\nworld\n");
+        app.finish_pending_save(&uri, Vec::new());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Xhello\nworld\n");
+
+        // Saving from the quit prompt quits once the held save is written.
+        app.handle_command(Command::InsertChar('Y'));
+        while rx.try_recv().is_ok() {}
+        app.confirm_quit('s');
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(LspCommand::WillSaveWaitUntil { format: true, .. })
+        ));
+        assert!(app.running);
+        app.finish_pending_save(&uri, Vec::new());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "XYhello\nworld\n");
+        assert!(!app.running);
+    }
+
+    #[test]
+    fn status_shows_server_progress_and_messages() {
+        let (mut app, _rx, _dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let indexing = |percentage, message: &str| smash_lsp::Progress {
+            title: "Indexing".to_string(),
+            message: Some(message.to_string()),
+            percentage: Some(percentage),
+        };
+        app.handle_lsp_event(LspEvent::Progress(Some(indexing(10, "core"))));
+        assert_eq!(app.lsp_status(), " [LSP] [⠙ Indexing 10% core]");
+        // Each update turns the spinner.
+        app.handle_lsp_event(LspEvent::Progress(Some(indexing(42, "serde"))));
+        assert_eq!(app.lsp_status(), " [LSP] [⠹ Indexing 42% serde]");
+        app.handle_lsp_event(LspEvent::Progress(None));
+        assert_eq!(app.lsp_status(), " [LSP]");
+
+        app.handle_lsp_event(LspEvent::ServerMessage(smash_lsp::ServerMessage {
+            kind: smash_lsp::MessageType::Warning,
+            text: "no Cargo.toml found".to_string(),
+        }));
+        let last = app.messages.last().unwrap();
+        assert_eq!(last.text(), "LSP: no Cargo.toml found");
+        assert_eq!(last.level(), smash_core::message::MessageLevel::Warning);
+    }
+
+    #[test]
+    fn save_as_reattaches_language_and_server() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let python = smash_config::LspServerEntry {
+            command: "pylsp".to_string(),
+            args: Vec::new(),
+            extensions: Vec::new(),
+            disable: vec!["formatting".to_string(), "semantic_tokens".to_string()],
+        };
+        let (mut app, mut rx) = TestApp::default()
+            .lsp(std::collections::HashMap::from([(
+                "python".to_string(),
+                python,
+            )]))
+            .build_with_lsp();
+        app.lsp_disable = vec!["hover".to_string(), "formatting".to_string()];
+        app.handle_command(Command::InsertChar('x'));
+
+        app.confirm_save_as(&dir.path().join("main.py").to_string_lossy());
+        assert_eq!(app.language_id.as_deref(), Some("python"));
+        assert!(app.highlighter.is_some());
+        assert!(app.normal_status().0.starts_with("main.py [python]"));
+        match rx.try_recv().expect("server requested") {
+            LspCommand::StartServer(config) => {
+                assert_eq!(config.language_id, "python");
+                // `lsp.disable` and the server's own list, without repeats.
+                assert_eq!(
+                    config.disabled_features,
+                    [
+                        smash_lsp::LspFeature::Hover,
+                        smash_lsp::LspFeature::Formatting
+                    ]
+                );
+            }
+            _ => panic!("expected StartServer"),
+        }
+        assert!(app
+            .messages
+            .iter()
+            .any(|m| m.text().contains("Unknown LSP feature 'semantic_tokens'")));
+
+        // Same language: the document moves to its new URI.
+        app.lsp_server_started = true;
+        let main_uri = app.current_uri().unwrap();
+        app.confirm_save_as(&dir.path().join("app.py").to_string_lossy());
+        match rx.try_recv().expect("didClose sent") {
+            LspCommand::DidClose { uri } => assert_eq!(uri, main_uri),
+            _ => panic!("expected DidClose"),
+        }
+        match rx.try_recv().expect("didOpen sent") {
+            LspCommand::DidOpen {
+                uri, language_id, ..
+            } => {
+                assert!(uri.ends_with("app.py"));
+                assert_eq!(language_id, "python");
+            }
+            _ => panic!("expected DidOpen"),
+        }
+
+        // Another language without a server leaves the old one behind.
+        app.confirm_save_as(&dir.path().join("notes.txt").to_string_lossy());
+        assert!(matches!(rx.try_recv(), Ok(LspCommand::DidClose { .. })));
+        assert!(rx.try_recv().is_err());
+        assert!(!app.lsp_server_started);
+        assert_eq!(app.language_id.as_deref(), Some("plain"));
+        assert!(!app.normal_status().0.contains('['));
+    }
+
+    #[test]
+    fn external_changes_reload_clean_buffers_and_ask_for_modified_ones() {
+        let (mut app, mut rx, dir) = lsp_test_app(TextDocumentSyncKind::Full);
+        let path = dir.path().join("doc.txt");
+        let start = std::time::Instant::now();
+        let tick = |n: u32| start + disk_changes::DISK_CHECK_INTERVAL * n;
+        assert!(!app.poll_disk_changes(tick(0)));
+
+        std::fs::write(&path, "hello\nthere\nworld\n").unwrap();
+        assert!(app.poll_disk_changes(tick(1)));
+        assert_eq!(app.buffer.text().to_string(), "hello\nthere\nworld\n");
+        assert!(!app.buffer.is_dirty());
+        assert!(matches!(rx.try_recv(), Ok(LspCommand::DidChange { .. })));
+
+        // Our own save is not someone else's change.
+        app.handle_command(Command::InsertChar('X'));
+        app.handle_command(Command::Save);
+        assert!(!app.poll_disk_changes(tick(2)));
+
+        app.handle_command(Command::InsertChar('Y'));
+        std::fs::write(&path, "changed\n").unwrap();
+        assert!(!app.poll_disk_changes(tick(3)));
+        assert!(app.poll_disk_changes(tick(3)));
+        assert_eq!(app.input_mode, InputMode::PromptReload);
+        app.handle_prompt_command(Command::InsertChar('n'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.buffer.text().to_string().starts_with("XY"));
+        assert!(app.buffer.is_dirty());
+
+        std::fs::write(&path, "again!\n").unwrap();
+        app.poll_disk_changes(tick(4));
+        app.poll_disk_changes(tick(4));
+        app.handle_prompt_command(Command::InsertChar('y'));
+        assert_eq!(app.buffer.text().to_string(), "again!\n");
+        assert!(!app.buffer.is_dirty());
+        // The reload can be undone.
+        app.handle_command(Command::Undo);
+        assert!(app.buffer.text().to_string().starts_with("XY"));
+    }
+
+    #[test]
+    fn crlf_files_keep_their_line_endings_and_can_be_converted() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("win.txt");
+        std::fs::write(&path, "one\r\ntwo\r\n").expect("write");
+        let mut app = test_app();
+        app.confirm_open(&path.to_string_lossy());
+        wait_for_open(&mut app);
+        assert!(app.normal_status().0.contains("[CRLF]"));
+
+        app.handle_command(Command::MoveLineEnd);
+        app.handle_command(Command::InsertNewline);
+        app.yank("a\nb", 0);
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(2, 1)
+        );
+        app.handle_command(Command::Save);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "one\r\na\r\nb\r\ntwo\r\n"
+        );
+
+        app.handle_command(Command::SetLineEnding);
+        assert_eq!(app.input_mode, InputMode::PromptLineEnding);
+        for c in "lf".chars() {
+            app.handle_prompt_command(Command::InsertChar(c));
+        }
+        app.handle_prompt_command(Command::InsertNewline);
+        assert_eq!(app.buffer.text().to_string(), "one\na\nb\ntwo\n");
+        assert!(!app.normal_status().0.contains("[CRLF]"));
+        app.handle_command(Command::Save);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\na\nb\ntwo\n");
+    }
+
+    #[test]
+    fn files_reopen_in_another_encoding_and_save_in_it() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("ru.txt");
+        // "Привет" in windows-1251, which reads as Latin-1 until told.
+        let bytes = b"\xcf\xf0\xe8\xe2\xe5\xf2\n";
+        std::fs::write(&path, bytes).expect("write");
+        let mut app = TestApp::default().file(path.clone()).build();
+        wait_for_open(&mut app);
+        assert_eq!(app.buffer.text().to_string(), "Ïðèâåò\n");
+        assert!(app.normal_status().0.contains("[Latin-1]"));
+
+        app.handle_command(Command::ReopenWithEncoding);
+        assert_eq!(app.input_mode, InputMode::PromptReopenEncoding);
+        for c in "klingon".chars() {
+            app.handle_prompt_command(Command::InsertChar(c));
+        }
+        app.handle_prompt_command(Command::InsertNewline);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Unknown encoding: klingon"
+        );
+
+        app.handle_command(Command::ReopenWithEncoding);
+        for c in "windows-1251".chars() {
+            app.handle_prompt_command(Command::InsertChar(c));
+        }
+        app.handle_prompt_command(Command::InsertNewline);
+        assert_eq!(app.buffer.text().to_string(), "Привет\n");
+        assert!(!app.buffer.is_dirty());
+        assert!(app.normal_status().0.contains("[windows-1251]"));
+
+        app.handle_command(Command::MoveLineEnd);
+        app.handle_command(Command::InsertChar('!'));
+        app.handle_command(Command::Save);
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            b"\xcf\xf0\xe8\xe2\xe5\xf2!\n"
+        );
+    }
+
+    #[test]
+    fn highlighting_covers_the_view_and_a_margin_ahead_of_scrolling() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("main.rs");
+        let text: String = (0..2000).map(|i| format!("fn f{}() {{}}\n", i)).collect();
+        std::fs::write(&path, text).expect("write");
+        let mut app = TestApp::default().file(path).build();
+        app.highlight_margin = 50;
+        app.run_idle_task();
+        let mut backend = smash_tui::MockBackend::new(80, 24);
+        let settle = |app: &mut App| {
+            wait_until(
+                app,
+                "highlighting",
+                |app| !app.lazy_highlights.as_ref().unwrap().highlighter.is_busy(),
+                App::poll_highlights,
+            );
+        };
+        let ready = |app: &App, line: usize| {
+            let lazy = &app.lazy_highlights.as_ref().unwrap().highlighter;
+            lazy.is_ready(&format!("fn f{}() {{}}", line))
+        };
+
+        app.render(&mut backend).expect("render");
+        settle(&mut app);
+        let rows = app.viewport.visible_lines();
+        assert!(ready(&app, 0) && ready(&app, rows + 49));
+        assert!(!ready(&app, rows + 50));
+
+        // Scrolling down looks twice as far ahead.
+        app.handle_command(Command::PageDown);
+        app.render(&mut backend).expect("render");
+        settle(&mut app);
+        let top = app.viewport.top_line();
+        assert!(top > 0);
+        assert!(ready(&app, top + rows + 99));
+        assert!(!ready(&app, top + rows + 100));
+        assert!(!ready(&app, 1999));
+    }
+
+    #[test]
+    fn copies_file_paths_and_pastes_markdown_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("docs")).unwrap();
+        std::fs::write(root.join("README.md"), "").unwrap();
+        std::fs::write(root.join("docs/a b.md"), "").unwrap();
+
+        let mut app = test_app();
+        app.open_path(root.join("README.md"), None, false);
+        wait_for_open(&mut app);
+        let readme = root.join("README.md");
+
+        // The buffer's own path, in each format.
+        app.handle_command(Command::CopyPath);
+        assert_eq!(app.clipboard.get().unwrap(), readme.display().to_string());
+        app.handle_command(Command::CopyFileUri);
+        assert_eq!(
+            app.clipboard.get().unwrap(),
+            smash_platform::path_to_uri(&readme)
+        );
+
+        // In the finder, the top result is copied instead.
+        let other = root.join("docs/a b.md");
+        app.input_mode = InputMode::FileFinder;
+        app.finder_results = vec![smash_core::fuzzy_finder::FileMatch::new(
+            other.clone(),
+            "docs/a b.md".to_string(),
+            1,
+        )];
+        app.handle_command(Command::CopyPath);
+        assert_eq!(app.clipboard.get().unwrap(), other.display().to_string());
+        app.handle_command(Command::Quit);
+
+        // Pasting it links relative to the README, with the file name or
+        // the selection as the text.
+        app.handle_command(Command::PasteMarkdownLink);
+        assert_eq!(app.buffer.text().to_string(), "[a b.md](docs/a%20b.md)");
+        app.handle_command(Command::SelectAll);
+        app.handle_command(Command::Cut);
+        app.handle_command(Command::InsertChar('x'));
+        app.handle_command(Command::SelectAll);
+        app.clipboard.set("https://example.com/x").unwrap();
+        app.handle_command(Command::PasteMarkdownLink);
+        assert_eq!(app.buffer.text().to_string(), "[x](https://example.com/x)");
+
+        // Other languages are left alone.
+        let mut app = test_app();
+        app.handle_command(Command::PasteMarkdownLink);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Paste as Markdown link needs a Markdown buffer"
+        );
+    }
+
+    #[test]
+    fn search_prompt_toggles_regex_case_and_whole_word() {
+        let mut app = test_app();
+        app.insert_text("Foo foobar foo\n");
+        app.handle_command(Command::Find);
+        for c in "foo".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        assert_eq!(app.buffer.search().match_count(), 3);
+        assert_eq!(app.search_prompt_label("Find"), "Find");
+
+        app.handle_command(Command::ToggleSearchCase);
+        assert_eq!(app.buffer.search().match_count(), 2);
+        app.handle_command(Command::ToggleSearchWholeWord);
+        assert_eq!(app.buffer.search().match_count(), 1);
+        assert_eq!(app.search_prompt_label("Find"), "Find [case, word]");
+
+        // A half-typed regex shows no matches rather than an error.
+        app.handle_command(Command::ToggleSearchWholeWord);
+        app.handle_command(Command::ToggleSearchRegex);
+        app.handle_command(Command::InsertChar('('));
+        assert_eq!(app.buffer.search().match_count(), 0);
+        app.handle_command(Command::InsertChar('.'));
+        app.handle_command(Command::InsertChar(')'));
+        assert_eq!(app.buffer.search().match_count(), 1);
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        // Replace uses the same options; regex replacements see groups.
+        app.handle_command(Command::FindReplace);
+        for c in "f(o+)".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        for c in "b$1".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.buffer.text().to_string(), "Foo boobar boo\n");
+    }
+
+    #[test]
+    fn interactive_replace_all_tracks_lines_added_by_earlier_matches() {
+        let mut app = test_app();
+        app.insert_text("x x\nx\n");
+        app.start_interactive_replace("x", "y\nz");
+        app.handle_command(Command::InsertChar('a'));
+        assert_eq!(app.buffer.text().to_string(), "y\nz y\nz\ny\nz\n");
+        assert_eq!(
+            app.buffer.cursors().primary().position(),
+            Position::new(4, 1)
+        );
+    }
+
+    #[test]
+    fn interactive_replace_confirms_each_match() {
+        let mut app = test_app();
+        app.insert_text("a1 a2 a3 a4\n");
+        app.search_options.regex = true;
+        let type_text = |app: &mut App, text: &str| {
+            for c in text.chars() {
+                app.handle_command(Command::InsertChar(c));
+            }
+            app.handle_command(Command::InsertNewline);
+        };
+        app.handle_command(Command::FindReplaceInteractive);
+        type_text(&mut app, r"a(\d)");
+        type_text(&mut app, "b$1");
+        assert_eq!(app.input_mode, InputMode::PromptReplaceStep);
+        assert_eq!(app.selected_text().as_deref(), Some("a1"));
+        assert!(app.replace_step_prompt().contains("0 replaced"));
+
+        // Yes, no, then all of the rest.
+        app.handle_command(Command::InsertChar('y'));
+        assert_eq!(app.selected_text().as_deref(), Some("a2"));
+        app.handle_command(Command::InsertChar('n'));
+        assert_eq!(app.selected_text().as_deref(), Some("a3"));
+        app.handle_command(Command::InsertChar('a'));
+        assert_eq!(app.buffer.text().to_string(), "b1 a2 b3 b4\n");
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Replaced 3 occurrence(s)"
+        );
+
+        // Each replacement is its own undo step.
+        app.handle_command(Command::Undo);
+        assert_eq!(app.buffer.text().to_string(), "b1 a2 b3 a4\n");
+
+        // `q` stops early; no matches ends at once.
+        app.handle_command(Command::FindReplaceInteractive);
+        type_text(&mut app, "b");
+        type_text(&mut app, "c");
+        app.handle_command(Command::InsertChar('q'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.buffer.text().to_string(), "b1 a2 b3 a4\n");
+        app.handle_command(Command::FindReplaceInteractive);
+        type_text(&mut app, "zzz");
+        type_text(&mut app, "c");
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "No occurrences of 'zzz' found"
+        );
+    }
+
+    #[test]
+    fn rebase_todo_actions_rulers_and_abort() {
+        use smash_core::cursor::Cursor;
+
+        let dir = tempfile::tempdir().unwrap();
+        let todo = dir.path().join("git-rebase-todo");
+        std::fs::write(
+            &todo,
+            "pick 1111111 First\npick 2222222 Second\np 3333333 Third\n# Commands:\n",
+        )
+        .unwrap();
+        let mut app = test_app();
+        app.open_path(todo, None, false);
+        wait_for_open(&mut app);
+        assert!(app.rulers().is_empty());
+
+        // A selection over two lines changes both; comments are left.
+        *app.buffer.cursors_mut().primary_mut() =
+            Cursor::with_selection(Position::new(1, 0), Position::new(3, 4));
+        app.handle_command(Command::RebaseFixup);
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "pick 1111111 First\nfixup 2222222 Second\nfixup 3333333 Third\n# Commands:\n"
+        );
+        app.handle_command(Command::Undo);
+        assert!(app.buffer.text().to_string().contains("pick 2222222"));
+
+        // Commit messages get rulers; elsewhere the rebase commands refuse.
+        let msg = dir.path().join("COMMIT_EDITMSG");
+        std::fs::write(&msg, "\n# Please enter the commit message\n").unwrap();
+        app.open_path(msg, None, false);
+        wait_for_open(&mut app);
+        assert_eq!(app.rulers(), vec![50, 72]);
+        app.handle_command(Command::RebaseReword);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Not a rebase todo list (git-rebase-todo)"
+        );
+
+        app.handle_command(Command::AbortEdit);
+        assert!(!app.running);
+        assert_eq!(app.exit_code, 1);
+    }
+
+    #[test]
+    fn path_prompts_expand_environment_variables() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("SMASH_TEST_SAVE_DIR", dir.path());
+        let mut app = test_app();
+        app.insert_text("hello\n");
+        app.confirm_save_as("${SMASH_TEST_SAVE_DIR}/out.txt");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+            "hello\n"
+        );
+
+        app.confirm_open("$SMASH_TEST_UNSET_VARIABLE/x.txt");
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Cannot expand '$SMASH_TEST_UNSET_VARIABLE/x.txt': \
+             environment variable SMASH_TEST_UNSET_VARIABLE is not set"
+        );
+        assert!(app.pending_open.is_none());
+    }
+
+    #[test]
+    fn command_line_runs_ranged_line_commands() {
+        let mut app = test_app();
+        app.insert_text("one\ntwo\nthree\nfour\nfive\n");
+        let run = |app: &mut App, line: &str| {
+            app.handle_command(Command::OpenCommandLine);
+            assert_eq!(app.input_mode, InputMode::PromptVimCommand);
+            for c in line.chars() {
+                app.handle_command(Command::InsertChar(c));
+            }
+            app.handle_command(Command::InsertNewline);
+            assert_eq!(app.input_mode, InputMode::Normal);
+        };
+
+        run(&mut app, "2,3>");
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "one\n    two\n    three\nfour\nfive\n"
+        );
+        run(&mut app, "3<");
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "one\n    two\nthree\nfour\nfive\n"
+        );
+
+        // Without `g` only the first match on each line is replaced.
+        run(&mut app, "%s/(o|e)/[$1]/");
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "[o]ne\n    tw[o]\nthr[e]e\nf[o]ur\nfiv[e]\n"
+        );
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "5 substitution(s) on 5 line(s)"
+        );
+        app.handle_command(Command::Undo);
+        assert_eq!(
+            app.buffer.text().to_string(),
+            "one\n    two\nthree\nfour\nfive\n"
+        );
+
+        run(&mut app, "1,$-3y");
+        assert_eq!(app.clipboard.get().unwrap(), "one\n    two\nthree\n");
+        run(&mut app, "4,5d");
+        assert_eq!(app.buffer.text().to_string(), "one\n    two\nthree\n");
+        assert_eq!(app.buffer.cursors().primary().position().line, 3);
+
+        // `s` alone works on the cursor line.
+        run(&mut app, "1");
+        run(&mut app, "s/O/0/gi");
+        assert_eq!(app.buffer.text().to_string(), "0ne\n    two\nthree\n");
+
+        run(&mut app, "9d");
+        assert_eq!(app.messages.last().unwrap().text(), "Invalid range: 9d");
+        run(&mut app, "%s/xyz/a/");
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Pattern not found: xyz"
+        );
+    }
+
+    #[test]
+    fn command_line_substitute_anchors_before_crlf() {
+        let mut app = test_app();
+        app.buffer = Buffer::from_text(BufferId::next(), "ab\r\ncb\r\n");
+        app.handle_command(Command::OpenCommandLine);
+        for c in "%s/b$/X/".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.buffer.text().to_string(), "aX\r\ncX\r\n");
+    }
+
+    #[test]
+    fn themes_load_from_files_and_switch_from_the_picker() {
+        use smash_syntax::ScopeId;
+        use smash_tui::Color;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = config_test_app(dir.path());
+        let themes = dir.path().join("config").join("themes");
+        std::fs::create_dir_all(&themes).unwrap();
+        std::fs::write(
+            themes.join("mocha.toml"),
+            "[palette]\nmauve = \"#cba6f7\"\n[scopes]\nkeyword = { fg = \"mauve\", bold = true }\n",
+        )
+        .unwrap();
+        std::fs::write(themes.join("broken.toml"), "[ui]\nstatus = \"red\"\n").unwrap();
+
+        let mut config = smash_config::Config::default();
+        config.display.theme = "mocha".to_string();
+        app.apply_config(&config);
+        assert_eq!(app.theme.name(), "mocha");
+        assert_eq!(
+            app.theme.scope_style(ScopeId::Keyword).fg,
+            Color::Rgb(0xcb, 0xa6, 0xf7)
+        );
+
+        // A broken file falls back to the dark theme and says why.
+        config.display.theme = "broken".to_string();
+        app.apply_config(&config);
+        assert_eq!(app.theme.name(), "default-dark");
+        let msg = app.messages.last().unwrap().text().to_string();
+        assert!(msg.contains("broken.toml: ui.status"), "{}", msg);
+
+        app.handle_command(Command::SelectTheme);
+        assert_eq!(app.input_mode, InputMode::ThemePicker);
+        assert_eq!(app.theme_picker.as_ref().unwrap().picker.len(), 4);
+        for c in "moch".chars() {
+            app.handle_command(Command::InsertChar(c));
+        }
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.theme.name(), "mocha");
+        assert_eq!(app.theme_name, "mocha");
+        assert_eq!(app.messages.last().unwrap().text(), "Theme: mocha");
+    }
+
+    #[test]
+    fn searches_are_remembered_per_project_and_saved_as_presets() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = config_test_app(dir.path());
+        let type_text = |app: &mut App, text: &str| {
+            for c in text.chars() {
+                app.handle_command(Command::InsertChar(c));
+            }
+        };
+        type_text(&mut app, "TODO one\nFIXME two\n");
+
+        app.handle_command(Command::SaveSearchPreset);
+        assert_eq!(app.messages.last().unwrap().text(), "No search to save yet");
+
+        // A regex search, then a plain one.
+        app.handle_command(Command::Find);
+        app.handle_command(Command::ToggleSearchRegex);
+        type_text(&mut app, "TODO|FIXME");
+        app.handle_command(Command::InsertNewline);
+        app.handle_command(Command::Find);
+        app.handle_command(Command::ToggleSearchRegex);
+        type_text(&mut app, "two");
+        app.handle_command(Command::InsertNewline);
+
+        // Up walks back through them, restoring their options.
+        app.handle_command(Command::Find);
+        app.handle_command(Command::MoveUp);
+        assert_eq!(app.prompt_input, "two");
+        app.handle_command(Command::MoveUp);
+        assert_eq!(app.prompt_input, "TODO|FIXME");
+        assert!(app.search_options.regex);
+        assert_eq!(app.buffer.search().match_count(), 2);
+        app.handle_command(Command::MoveDown);
+        app.handle_command(Command::MoveDown);
+        assert_eq!(app.prompt_input, "");
+        app.handle_command(Command::MoveUp);
+        app.handle_command(Command::MoveUp);
+        app.handle_command(Command::InsertNewline);
+
+        app.handle_command(Command::SaveSearchPreset);
+        assert_eq!(app.input_mode, InputMode::PromptSearchPresetName);
+        type_text(&mut app, "TODO scan");
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(
+            app.messages.last().unwrap().text(),
+            "Saved search preset 'TODO scan': TODO|FIXME"
+        );
+
+        // The picker lists the preset first and fills the Find prompt.
+        app.search_options = Default::default();
+        app.handle_command(Command::Find);
+        app.handle_command(Command::SearchPresets);
+        assert_eq!(app.input_mode, InputMode::SearchPicker);
+        let picker = &app.search_picker.as_ref().unwrap().picker;
+        assert_eq!(picker.len(), 3);
+        type_text(&mut app, "scan");
+        app.handle_command(Command::InsertNewline);
+        assert_eq!(app.input_mode, InputMode::PromptFind);
+        assert_eq!(app.prompt_input, "TODO|FIXME");
+        assert!(app.search_options.regex);
+        app.handle_command(Command::Quit);
+
+        // The history is saved with the project it belongs to.
+        let path = dir
+            .path()
+            .join(smash_core::search_history::SEARCH_HISTORY_FILE);
+        app.search_history.save(&path).unwrap();
+        let history = SearchHistory::load(&path).unwrap();
+        let project = dir.path().join("project");
+        let searches = history.project(Some(&project)).unwrap();
+        assert_eq!(searches.presets[0].name, "TODO scan");
+        assert_eq!(searches.recent.len(), 2);
+        assert!(history.project(None).is_none());
+    }
 }
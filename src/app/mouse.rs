@@ -0,0 +1,207 @@
+use smash_core::position::Position;
+use smash_input::event::MouseKind;
+use smash_input::MouseEvent;
+use smash_tui::{minimap_lines_per_row, scrollbar_line, Rect};
+
+use super::App;
+
+/// Lines scrolled per mouse wheel notch.
+const SCROLL_LINES: usize = 3;
+
+/// An overview of the buffer that scrolls the view when clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScrollArea {
+    Minimap,
+    Scrollbar,
+}
+
+// =========================================================================
+// Mouse handling
+// =========================================================================
+
+impl App {
+    /// Handle a mouse event. A click on a gutter diagnostic sign shows the
+    /// diagnostics for that line; a click in the text moves the cursor,
+    /// dragging selects, and the wheel scrolls. Clicks in the file tree
+    /// open the entry under the mouse, and clicking or dragging on the
+    /// minimap or scrollbar scrolls to that part of the buffer. Returns
+    /// `true` if the event was used.
+    pub(crate) fn handle_mouse(&mut self, event: &MouseEvent) -> bool {
+        if self.handle_scroll_area_mouse(event) {
+            return true;
+        }
+        let in_editor = (event.row as usize) < self.viewport.visible_lines();
+        if event.col < self.editor_left && in_editor {
+            match event.kind {
+                MouseKind::Down => self.click_file_tree(event.row),
+                MouseKind::ScrollUp | MouseKind::ScrollDown => {
+                    let delta = if event.kind == MouseKind::ScrollUp {
+                        -(SCROLL_LINES as isize)
+                    } else {
+                        SCROLL_LINES as isize
+                    };
+                    if let Some(tree) = self.file_tree.as_mut() {
+                        tree.move_selection(delta);
+                    }
+                }
+                _ => return self.mouse_drag_anchor.is_some() && self.drag_in_editor(event),
+            }
+            return true;
+        }
+        self.handle_editor_mouse(event, in_editor)
+    }
+
+    fn scroll_area(&self, which: ScrollArea) -> Option<Rect> {
+        match which {
+            ScrollArea::Minimap => self.minimap_area,
+            ScrollArea::Scrollbar => self.scrollbar_area,
+        }
+    }
+
+    /// Scroll to the lines of the minimap or scrollbar row under a
+    /// click, and keep following the mouse while it drags.
+    fn handle_scroll_area_mouse(&mut self, event: &MouseEvent) -> bool {
+        let which = match event.kind {
+            MouseKind::Down => [ScrollArea::Minimap, ScrollArea::Scrollbar]
+                .into_iter()
+                .find(|&which| {
+                    self.scroll_area(which)
+                        .is_some_and(|area| area.contains(event.col, event.row))
+                }),
+            MouseKind::Drag => self.scroll_drag,
+            MouseKind::Up => return self.scroll_drag.take().is_some(),
+            _ => None,
+        };
+        let Some((which, area)) = which.and_then(|w| Some((w, self.scroll_area(w)?))) else {
+            return false;
+        };
+        self.scroll_drag = Some(which);
+        self.mouse_drag_anchor = None;
+        let row = event
+            .row
+            .saturating_sub(area.y)
+            .min(area.height.saturating_sub(1)) as usize;
+        let line_count = self.buffer.line_count();
+        let height = area.height as usize;
+        // Centre the middle of the row's lines in the view.
+        let line = match which {
+            ScrollArea::Minimap => {
+                let per_row = minimap_lines_per_row(line_count, height);
+                row * per_row + per_row / 2
+            }
+            ScrollArea::Scrollbar => scrollbar_line(row, line_count, height),
+        };
+        self.viewport.center_on(line, line_count);
+        self.keep_cursor_in_view();
+        true
+    }
+
+    /// A drag that strayed over the file tree keeps selecting from the
+    /// editor's left edge.
+    fn drag_in_editor(&mut self, event: &MouseEvent) -> bool {
+        let clamped = MouseEvent {
+            col: self.editor_left,
+            ..event.clone()
+        };
+        self.handle_editor_mouse(&clamped, true)
+    }
+
+    fn handle_editor_mouse(&mut self, event: &MouseEvent, in_editor: bool) -> bool {
+        let event = &MouseEvent {
+            col: event.col - self.editor_left,
+            ..event.clone()
+        };
+        match event.kind {
+            MouseKind::Down if in_editor => {
+                if event.col < self.gutter_width() && self.click_gutter_diagnostic(event.row) {
+                    return true;
+                }
+                let pos = self.mouse_position(event);
+                let cursor = self.buffer.cursors_mut().primary_mut();
+                if event.modifiers.shift() {
+                    // Shift-click extends from wherever the cursor was.
+                    if !cursor.has_selection() {
+                        let from = cursor.position();
+                        cursor.set_anchor(from);
+                    }
+                } else {
+                    cursor.clear_selection();
+                }
+                cursor.set_position(pos);
+                self.buffer.cursors_mut().clear_secondary();
+                self.popup = None;
+                self.file_tree_focused = false;
+                self.search_results_focused = false;
+                self.mouse_drag_anchor =
+                    Some(self.buffer.cursors().primary().anchor().unwrap_or(pos));
+                true
+            }
+            MouseKind::Drag => {
+                let Some(anchor) = self.mouse_drag_anchor else {
+                    return false;
+                };
+                // Dragging past the top or bottom edge scrolls.
+                if event.row == 0 {
+                    self.viewport.scroll_up(1);
+                } else if !in_editor {
+                    self.viewport.scroll_down(1, self.buffer.line_count());
+                }
+                let pos = self.mouse_position(event);
+                let cursor = self.buffer.cursors_mut().primary_mut();
+                cursor.set_anchor(anchor);
+                cursor.set_position(pos);
+                true
+            }
+            MouseKind::Up => self.mouse_drag_anchor.take().is_some(),
+            MouseKind::ScrollUp if in_editor => {
+                self.scroll_view_lines(true, SCROLL_LINES);
+                true
+            }
+            MouseKind::ScrollDown if in_editor => {
+                self.scroll_view_lines(false, SCROLL_LINES);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Show the diagnostics for a gutter click on `row`, if that line
+    /// has any.
+    fn click_gutter_diagnostic(&mut self, row: u16) -> bool {
+        let line = self.viewport.top_line() + row as usize;
+        if self.highest_diagnostic_severity(line).is_none() {
+            return false;
+        }
+        self.popup = None;
+        self.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(Position::new(line, 0));
+        self.show_diagnostic_at_cursor();
+        true
+    }
+
+    /// Buffer position under the mouse, clamped to the text. Rows outside
+    /// the editor area map to its first or last visible line.
+    fn mouse_position(&self, event: &MouseEvent) -> Position {
+        let visible = self.viewport.visible_lines().max(1);
+        let row = (event.row as usize).min(visible - 1);
+        let last = self.buffer.line_count().saturating_sub(1);
+        let line = (self.viewport.top_line() + row).min(last);
+        let x = self.viewport.left_col() + event.col.saturating_sub(self.gutter_width()) as usize;
+        Position::new(line, self.buffer.col_at_display(line, x))
+    }
+
+    /// After scrolling the view, move the cursor onto the visible lines so
+    /// the next render does not scroll straight back to it.
+    pub(crate) fn keep_cursor_in_view(&mut self) {
+        let pos = self.buffer.cursors().primary().position();
+        let top = self.viewport.top_line();
+        let bottom = self.viewport.bottom_line().saturating_sub(1).max(top);
+        let line = pos.line.clamp(top, bottom);
+        if line != pos.line {
+            self.buffer.cursors_mut().primary_mut().clear_selection();
+            self.buffer.move_primary_to_line(line);
+        }
+    }
+}
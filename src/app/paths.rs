@@ -0,0 +1,179 @@
+use std::path::{Component, Path, PathBuf};
+
+use smash_syntax::LanguageId;
+use tracing::error;
+
+use super::file_tree::absolute;
+use super::{App, InputMode};
+
+/// How a copied path is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PathFormat {
+    /// Absolute filesystem path.
+    Absolute,
+    /// Relative to the working directory.
+    Relative,
+    /// A `file://` URI.
+    Uri,
+}
+
+// =========================================================================
+// Copying paths and pasting links
+// =========================================================================
+
+impl App {
+    /// Copy the path of the top finder result, the selected file tree
+    /// entry, or the current buffer, in that order of preference.
+    pub(crate) fn cmd_copy_path(&mut self, format: PathFormat) {
+        let Some(path) = self.path_to_copy() else {
+            self.messages.info("No file to copy the path of");
+            return;
+        };
+        let text = match format {
+            PathFormat::Absolute => path.display().to_string(),
+            PathFormat::Relative => {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                relative_path(&path, &cwd).display().to_string()
+            }
+            PathFormat::Uri => smash_platform::path_to_uri(&path),
+        };
+        match self.clipboard.set(&text) {
+            Ok(()) => self.messages.info(format!("Copied {}", text)),
+            Err(e) => {
+                self.messages.error(format!("Copy failed: {}", e));
+                error!("clipboard set failed: {}", e);
+            }
+        }
+    }
+
+    /// The absolute path the copy commands act on.
+    fn path_to_copy(&self) -> Option<PathBuf> {
+        if self.input_mode == InputMode::FileFinder {
+            return self.selected_finder_match().map(|m| absolute(m.path()));
+        }
+        if self.file_tree_focused() {
+            return self
+                .file_tree
+                .as_ref()
+                .and_then(|t| t.selected())
+                .map(|e| absolute(&e.path));
+        }
+        self.buffer.path().map(absolute)
+    }
+
+    /// Paste the clipboard's path or URL as a Markdown link, using the
+    /// selection (or the file name) as its text. Local paths are made
+    /// relative to the buffer's directory so the link works from there.
+    pub(crate) fn cmd_paste_markdown_link(&mut self) {
+        let markdown = match self.buffer.path() {
+            Some(path) => LanguageId::from_path(path) == LanguageId::Markdown,
+            None => self.language_id.as_deref() == Some(LanguageId::Markdown.as_str()),
+        };
+        if !markdown {
+            self.messages
+                .warn("Paste as Markdown link needs a Markdown buffer");
+            return;
+        }
+        let clip = match self.clipboard.get() {
+            Ok(text) => text.trim().to_string(),
+            Err(e) => {
+                self.messages.error(format!("Paste failed: {}", e));
+                error!("clipboard get failed: {}", e);
+                return;
+            }
+        };
+        if clip.is_empty() || clip.contains('\n') {
+            self.messages
+                .info("The clipboard does not hold a path or URL");
+            return;
+        }
+        let local = if clip.starts_with("file://") {
+            smash_platform::uri_to_path(&clip)
+        } else if clip.contains("://") {
+            None
+        } else {
+            Some(PathBuf::from(&clip))
+        };
+        let (target, name) = match local {
+            Some(path) => {
+                let path = absolute(&path);
+                let base = self
+                    .buffer
+                    .path()
+                    .and_then(|p| absolute(p).parent().map(Path::to_path_buf))
+                    .or_else(|| std::env::current_dir().ok())
+                    .unwrap_or_default();
+                let target = relative_path(&path, &base);
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| clip.clone());
+                (markdown_target(&target), name)
+            }
+            None => (clip.clone(), clip.clone()),
+        };
+        let name = self.selected_text().unwrap_or(name);
+        self.insert_text(&format!("[{}]({})", name, target));
+    }
+}
+
+/// `path` relative to the directory `base`, climbing out with `..` where
+/// needed. Paths on another root are returned unchanged.
+pub(crate) fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path_parts: Vec<Component> = path.components().collect();
+    let base_parts: Vec<Component> = base.components().collect();
+    let common = path_parts
+        .iter()
+        .zip(&base_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    // Nothing shared, not even the root.
+    if common == 0 {
+        return path.to_path_buf();
+    }
+    let mut rel = PathBuf::new();
+    for _ in common..base_parts.len() {
+        rel.push("..");
+    }
+    for part in &path_parts[common..] {
+        rel.push(part);
+    }
+    if rel.as_os_str().is_empty() {
+        rel.push(".");
+    }
+    rel
+}
+
+/// A relative path as a Markdown link target: forward slashes, with
+/// spaces and parentheses escaped so the link does not end early.
+fn markdown_target(path: &Path) -> String {
+    let parts: Vec<String> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    parts
+        .join("/")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_paths_climb_out_of_the_base() {
+        let rel = |p: &str, b: &str| relative_path(Path::new(p), Path::new(b));
+        assert_eq!(rel("/w/src/main.rs", "/w"), PathBuf::from("src/main.rs"));
+        assert_eq!(rel("/w/docs/a.md", "/w/src"), PathBuf::from("../docs/a.md"));
+        assert_eq!(rel("/w", "/w"), PathBuf::from("."));
+        assert_eq!(rel("rel/x", "/w"), PathBuf::from("rel/x"));
+    }
+
+    #[test]
+    fn markdown_targets_use_slashes_and_escape() {
+        let target = markdown_target(Path::new("../my docs/a (1).md"));
+        assert_eq!(target, "../my%20docs/a%20%281%29.md");
+    }
+}
@@ -0,0 +1,65 @@
+use smash_input::Command;
+use smash_tui::Picker;
+
+use super::{App, InputMode};
+
+// =========================================================================
+// Picker overlays
+// =========================================================================
+
+impl App {
+    /// The picker shown in the current input mode, if any.
+    fn active_picker_mut(&mut self) -> Option<&mut Picker> {
+        match self.input_mode {
+            InputMode::SymbolPicker => self.symbol_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::CodeActionPicker => self.code_action_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::ThemePicker => self.theme_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::SearchPicker => self.search_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::KillRingPicker => self.kill_ring_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::RecentPicker => self.recent_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::MarkPicker => self.mark_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::RegisterPicker => self.register_picker.as_mut().map(|s| &mut s.picker),
+            _ => None,
+        }
+    }
+
+    /// Filter the open picker by the query typed so far.
+    pub(super) fn update_picker(&mut self) {
+        let query = self.prompt_input.clone();
+        if let Some(picker) = self.active_picker_mut() {
+            picker.set_query(&query);
+        }
+        if self.input_mode == InputMode::SymbolPicker {
+            self.requery_workspace_symbols();
+        }
+    }
+
+    /// Handle a movement command in the open picker. Returns `false` for
+    /// anything else, or when no picker is open.
+    pub(super) fn move_picker_selection(&mut self, cmd: &Command) -> bool {
+        self.active_picker_mut()
+            .is_some_and(|picker| picker.handle_motion(cmd))
+    }
+
+    /// Status bar text while a picker is open.
+    pub(super) fn picker_prompt(&self) -> String {
+        let (what, action) = match self.input_mode {
+            InputMode::SymbolPicker => match &self.symbol_picker {
+                Some(state) if state.workspace => ("Go to symbol in workspace", "jump"),
+                _ => ("Go to symbol in file", "jump"),
+            },
+            InputMode::CodeActionPicker => ("Code action", "apply"),
+            InputMode::ThemePicker => ("Theme", "switch"),
+            InputMode::SearchPicker => ("Search", "fill the prompt"),
+            InputMode::KillRingPicker => ("Clipboard history", "paste"),
+            InputMode::RecentPicker => ("Recent files", "open"),
+            InputMode::MarkPicker => ("Marks", "jump"),
+            InputMode::RegisterPicker => ("Registers", "paste"),
+            _ => return self.prompt_input.clone(),
+        };
+        format!(
+            "{} (Up/Down to choose, Enter to {}): {}",
+            what, action, self.prompt_input
+        )
+    }
+}
@@ -0,0 +1,82 @@
+use super::loading::file_name;
+use super::{App, InputMode};
+
+// =========================================================================
+// Quitting with unsaved changes
+// =========================================================================
+
+impl App {
+    /// Names of the buffers with unsaved changes.
+    fn dirty_buffer_names(&self) -> Vec<String> {
+        self.open_buffers()
+            .filter(|b| b.is_dirty())
+            .map(|b| match b.path() {
+                Some(path) => file_name(path),
+                None => "[scratch]".to_string(),
+            })
+            .collect()
+    }
+
+    /// Quit, first asking what to do with unsaved changes. `ForceQuit`
+    /// skips the question.
+    pub(crate) fn cmd_quit(&mut self) {
+        if self.dirty_buffer_names().is_empty() {
+            self.running = false;
+        } else {
+            self.input_mode = InputMode::PromptQuit;
+            self.prompt_input.clear();
+        }
+    }
+
+    /// Answer the quit prompt: `s` saves everything and quits, asking
+    /// for a file name for scratch text; `d` quits without saving;
+    /// anything else stays.
+    pub(crate) fn confirm_quit(&mut self, answer: char) {
+        match answer {
+            's' | 'S' | 'y' | 'Y' => self.save_all_and_quit(),
+            'd' | 'D' | 'n' | 'N' => self.running = false,
+            _ => self.messages.info("Quit cancelled"),
+        }
+    }
+
+    fn save_all_and_quit(&mut self) {
+        if self.buffer.is_dirty() {
+            if self.buffer.path().is_none() {
+                self.input_mode = InputMode::PromptSaveAsAndQuit;
+                self.prompt_input.clear();
+                return;
+            }
+            // The held save quits once it is written.
+            if self.request_will_save_edits() {
+                self.quit_after_pending_save();
+                return;
+            }
+            self.write_buffer();
+        }
+        self.quit_if_all_saved();
+    }
+
+    /// Quit unless something is still unsaved. A failed save leaves the
+    /// buffer dirty, and the editor open.
+    pub(crate) fn quit_if_all_saved(&mut self) {
+        if self.dirty_buffer_names().is_empty() {
+            self.running = false;
+        }
+    }
+
+    /// Save the scratch buffer as `input`, then quit if that worked.
+    pub(crate) fn confirm_save_as_and_quit(&mut self, input: &str) {
+        self.confirm_save_as(input);
+        if self.buffer.path().is_some() && self.dirty_buffer_names().is_empty() {
+            self.running = false;
+        }
+    }
+
+    /// Prompt text while asking about unsaved changes on quit.
+    pub(crate) fn quit_prompt(&self) -> String {
+        format!(
+            "Unsaved changes in {}. Quit anyway? (s: save all, d: discard, Esc: cancel)",
+            self.dirty_buffer_names().join(", ")
+        )
+    }
+}
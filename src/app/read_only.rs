@@ -0,0 +1,109 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command as Process, Stdio};
+
+use smash_core::safe_write::is_writable;
+use tracing::{error, info};
+
+use super::loading::file_name;
+use super::App;
+
+/// Pipe `bytes` into `command`, with `path` appended as its last
+/// argument, the way `sudo tee FILE` writes a file as root.
+fn write_through(command: &str, path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or("editor.sudo_command is empty")?;
+    let mut child = Process::new(program)
+        .args(words)
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run {}: {}", program, e))?;
+    // Dropping stdin closes it, so the command sees the end of the text.
+    let written = child.stdin.take().map(|mut stdin| stdin.write_all(bytes));
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("{} failed: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.lines().next().map(str::trim) {
+            Some(reason) if !reason.is_empty() => reason.to_string(),
+            _ => format!("{} exited with {}", program, output.status),
+        });
+    }
+    match written {
+        Some(Ok(())) => Ok(()),
+        _ => Err(format!("{} did not read the whole file", program)),
+    }
+}
+
+// =========================================================================
+// Read-only buffers
+// =========================================================================
+
+impl App {
+    /// Allow or block editing the buffer. Buffers with no file behind
+    /// them, such as help and hex dumps, stay read-only.
+    pub(crate) fn cmd_toggle_read_only(&mut self) {
+        if self.buffer.is_read_only() && self.buffer.path().is_none() {
+            self.messages.warn("This buffer cannot be edited");
+            return;
+        }
+        let read_only = !self.buffer.is_read_only();
+        self.buffer.set_read_only(read_only);
+        if read_only {
+            self.messages.info("Read-only");
+        } else if self.buffer.path().is_some_and(|p| !is_writable(p)) {
+            self.messages
+                .info("Editable — the file is not writable, save it with SudoSave");
+        } else {
+            self.messages.info("Editable");
+        }
+    }
+
+    /// Explain why an edit was refused, and how to get past it.
+    pub(crate) fn warn_read_only(&mut self) {
+        let msg = match self.buffer.path() {
+            None => "Buffer is read-only",
+            Some(path) if !is_writable(path) => {
+                "File is not writable — ToggleReadOnly to edit it anyway, SudoSave to save it"
+            }
+            Some(_) => "Buffer is read-only — ToggleReadOnly to edit it",
+        };
+        self.messages.warn(msg);
+    }
+
+    /// Write the buffer through `editor.sudo_command`, for files the
+    /// editor itself is not allowed to write.
+    pub(crate) fn cmd_sudo_save(&mut self) {
+        let Some(path) = self.buffer.path().map(Path::to_path_buf) else {
+            self.messages.warn("No file path set — use Save As");
+            return;
+        };
+        let text = self.buffer.text().clone();
+        let bytes = match self.buffer.encoding().encode(&text.to_string()) {
+            Ok(bytes) => bytes.into_owned(),
+            Err(e) => {
+                self.messages.error(format!("Save failed: {}", e));
+                return;
+            }
+        };
+        match write_through(&self.editor_config.sudo_command, &path, &bytes) {
+            Ok(()) => {
+                self.buffer.mark_saved(&text);
+                self.messages.info(format!(
+                    "Saved {} with elevated permissions",
+                    file_name(&path)
+                ));
+                info!("saved {} through sudo command", path.display());
+                self.after_write();
+            }
+            Err(e) => {
+                self.messages.error(format!("Sudo save failed: {}", e));
+                error!("sudo save of {} failed: {}", path.display(), e);
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+use smash_tui::{Picker, PickerItem};
+
+use super::{App, InputMode};
+
+/// The recent files picker and the files behind its items.
+pub(crate) struct RecentPicker {
+    pub(crate) picker: Picker,
+    /// Same order as the picker items.
+    paths: Vec<PathBuf>,
+}
+
+/// Picker row for `path`: its file name, then where it is, relative to
+/// `cwd` when inside it.
+fn recent_item(path: &Path, cwd: &Path) -> PickerItem {
+    let name = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    );
+    let dir = path.parent().unwrap_or(path);
+    let detail = match dir.strip_prefix(cwd) {
+        Ok(rel) if rel.as_os_str().is_empty() => String::new(),
+        Ok(rel) => rel.display().to_string(),
+        Err(_) => dir.display().to_string(),
+    };
+    PickerItem::new(name, detail)
+}
+
+// =========================================================================
+// Recent files
+// =========================================================================
+
+impl App {
+    /// Remember `path` as the most recently opened file.
+    pub(crate) fn note_recent_file(&mut self, path: &Path) {
+        let path = std::env::current_dir().unwrap_or_default().join(path);
+        self.recent_files.record(path);
+    }
+
+    /// Open a picker of the recently opened files, most recent first,
+    /// leaving out the open file and files that no longer exist.
+    pub(crate) fn cmd_open_recent(&mut self) {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let current = self.buffer.path().map(|p| cwd.join(p));
+        let paths: Vec<PathBuf> = self
+            .recent_files
+            .files
+            .iter()
+            .filter(|p| Some(*p) != current.as_ref() && p.is_file())
+            .cloned()
+            .collect();
+        if paths.is_empty() {
+            self.messages.info("No recent files");
+            return;
+        }
+        let items = paths.iter().map(|p| recent_item(p, &cwd)).collect();
+        self.recent_picker = Some(RecentPicker {
+            picker: Picker::new("Recent files", items),
+            paths,
+        });
+        self.input_mode = InputMode::RecentPicker;
+        self.prompt_input.clear();
+    }
+
+    /// Open the selected file.
+    pub(crate) fn confirm_recent_picker(&mut self) {
+        let Some(state) = self.recent_picker.take() else {
+            return;
+        };
+        let Some(path) = state.picker.selected().and_then(|i| state.paths.get(i)) else {
+            self.messages.info("No matching recent file");
+            return;
+        };
+        self.confirm_open(&path.to_string_lossy());
+    }
+}
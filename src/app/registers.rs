@@ -0,0 +1,138 @@
+use smash_core::position::Position;
+use smash_core::registers::{is_clipboard_register, RegisterContent};
+use smash_input::Command;
+use smash_tui::{Picker, PickerItem};
+use tracing::error;
+
+use super::kill_ring::entry_item;
+use super::{App, InputMode};
+
+/// The register viewer and the register behind each of its items.
+pub(crate) struct RegisterPicker {
+    pub(crate) picker: Picker,
+    /// Same order as the picker items.
+    names: Vec<char>,
+}
+
+/// Viewer row for register `name`: its name and first line, then how
+/// many lines it has.
+fn register_item(name: char, text: &str) -> PickerItem {
+    let item = entry_item(text);
+    PickerItem::new(format!("\"{}  {}", name, item.label), item.detail)
+}
+
+// =========================================================================
+// Registers
+// =========================================================================
+
+impl App {
+    /// Store text a Vim yank or delete took, in the register named with
+    /// `"x` or else the numbered ones and the clipboard.
+    pub(crate) fn set_register(&mut self, text: &str, linewise: bool, deleted: bool) {
+        let content = RegisterContent::new(text, linewise);
+        match self.active_register {
+            Some(name) if !is_clipboard_register(name) => {
+                self.registers.record(Some(name), content, deleted);
+                return;
+            }
+            _ => self.registers.record(None, content, deleted),
+        }
+        self.register_linewise = linewise;
+        if let Err(e) = self.clipboard.set(text) {
+            self.messages.error(format!("Copy failed: {}", e));
+            error!("clipboard set failed: {}", e);
+        }
+    }
+
+    /// The register named with `"x` for a Vim paste, or the clipboard.
+    /// `None`, with a message, when there is nothing to paste.
+    pub(crate) fn register_for_paste(&mut self) -> Option<RegisterContent> {
+        match self.active_register {
+            Some('_') => None,
+            Some(name) if !is_clipboard_register(name) => {
+                let content = self.registers.get(name).cloned();
+                if content.is_none() {
+                    self.messages.info(format!("Register '{}' is empty", name));
+                }
+                content
+            }
+            _ => match self.clipboard.get() {
+                Ok(text) if !text.is_empty() => {
+                    Some(RegisterContent::new(text, self.register_linewise))
+                }
+                Ok(_) => {
+                    self.messages.info("Nothing to paste");
+                    None
+                }
+                Err(e) => {
+                    self.messages.error(format!("Paste failed: {}", e));
+                    error!("clipboard get failed: {}", e);
+                    None
+                }
+            },
+        }
+    }
+
+    /// Paste register `name`: whole lines above the cursor's line, other
+    /// text at the cursor. In Vim mode this is `"xP`.
+    pub(crate) fn cmd_paste_from_register(&mut self, name: char) {
+        if is_clipboard_register(name) {
+            self.handle_command(Command::Paste);
+            return;
+        }
+        self.active_register = Some(name);
+        let content = self.register_for_paste();
+        self.active_register = None;
+        let Some(content) = content else {
+            return;
+        };
+        if content.linewise && content.text.ends_with('\n') {
+            let line = self.buffer.cursors().primary().position().line;
+            self.buffer.cursors_mut().primary_mut().clear_selection();
+            self.set_cursor(Position::new(line, 0));
+            self.insert_text(&content.text);
+            self.set_cursor(Position::new(line, self.first_non_blank(line)));
+        } else {
+            self.insert_text(&content.text);
+        }
+    }
+
+    /// Open the register viewer: the clipboard, then every register
+    /// holding text. Choosing one pastes it.
+    pub(crate) fn cmd_show_registers(&mut self) {
+        let mut items = Vec::new();
+        let mut names = Vec::new();
+        if let Ok(text) = self.clipboard.get() {
+            if !text.is_empty() {
+                items.push(register_item('"', &text));
+                names.push('"');
+            }
+        }
+        for (name, content) in self.registers.iter() {
+            items.push(register_item(name, &content.text));
+            names.push(name);
+        }
+        if items.is_empty() {
+            self.messages.info("Registers are empty");
+            return;
+        }
+        self.register_picker = Some(RegisterPicker {
+            picker: Picker::new("Registers", items),
+            names,
+        });
+        self.input_mode = InputMode::RegisterPicker;
+        self.prompt_input.clear();
+    }
+
+    /// Paste the selected register.
+    pub(crate) fn confirm_register_picker(&mut self) {
+        let Some(state) = self.register_picker.take() else {
+            return;
+        };
+        let Some(&name) = state.picker.selected().and_then(|i| state.names.get(i)) else {
+            self.messages.info("No matching register");
+            return;
+        };
+        self.cmd_paste_from_register(name);
+    }
+}
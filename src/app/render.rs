@@ -1,15 +1,73 @@
+use std::time::Instant;
+
 use anyhow::Result;
 
+use smash_core::encoding::LineEnding;
 use smash_lsp::DiagnosticSeverity;
-use smash_tui::{default_dark_theme, Rect, TerminalBackend};
+use smash_syntax::LanguageId;
+use smash_tui::{
+    gutter_width, GutterDiagnostic, LineNumbers, Minimap, Rect, ScrollbarMark, TerminalBackend,
+    MINIMAP_WIDTH, SCROLLBAR_WIDTH,
+};
 
 use super::{App, InputMode};
 
+/// Narrowest the editor may get before the minimap is left out.
+const MIN_EDIT_WIDTH_WITH_MINIMAP: u16 = 40;
+
+/// Narrowest the editor may get before the scrollbar is left out.
+const MIN_EDIT_WIDTH_WITH_SCROLLBAR: u16 = 20;
+
+/// The gutter sign for a diagnostic of `severity`.
+fn gutter_severity(severity: DiagnosticSeverity) -> GutterDiagnostic {
+    match severity {
+        DiagnosticSeverity::Error => GutterDiagnostic::Error,
+        DiagnosticSeverity::Warning => GutterDiagnostic::Warning,
+        DiagnosticSeverity::Information => GutterDiagnostic::Information,
+        DiagnosticSeverity::Hint => GutterDiagnostic::Hint,
+    }
+}
+
+/// Frames of the spinner shown while a language server reports progress.
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
 // =========================================================================
 // Rendering
 // =========================================================================
 
 impl App {
+    /// The line number mode in effect, after `ToggleLineNumbers`. Showing
+    /// the gutter when `display.line_numbers` is "none" uses absolute numbers.
+    pub(crate) fn line_number_mode(&self) -> LineNumbers {
+        match (self.show_line_numbers, self.line_numbers) {
+            (false, _) => LineNumbers::Off,
+            (true, LineNumbers::Off) => LineNumbers::Absolute,
+            (true, mode) => mode,
+        }
+    }
+
+    /// Columns per indent level for the indent guides, from the buffer's
+    /// `tab_size`, or `None` while they are off.
+    pub(crate) fn indent_guide_width(&self) -> Option<usize> {
+        if !self.indent_guides {
+            return None;
+        }
+        let language = self
+            .buffer
+            .path()
+            .map(LanguageId::from_path)
+            .unwrap_or(LanguageId::Plain);
+        let editor = self
+            .editor_config
+            .with_overrides(self.language_configs.get(language.as_str()));
+        Some(editor.tab_size as usize)
+    }
+
+    /// Columns taken by the gutter for the current buffer.
+    pub(crate) fn gutter_width(&self) -> u16 {
+        gutter_width(self.line_number_mode(), self.buffer.line_count())
+    }
+
     /// Return the highest-priority diagnostic severity for a buffer line.
     pub(crate) fn highest_diagnostic_severity(
         &self,
@@ -30,25 +88,143 @@ impl App {
                 });
             }
         }
-        worst.map(|s| match s {
-            DiagnosticSeverity::Error => smash_tui::GutterDiagnostic::Error,
-            DiagnosticSeverity::Warning => smash_tui::GutterDiagnostic::Warning,
-            DiagnosticSeverity::Information => smash_tui::GutterDiagnostic::Information,
-            DiagnosticSeverity::Hint => smash_tui::GutterDiagnostic::Hint,
-        })
+        worst.map(gutter_severity)
+    }
+
+    /// Lines with diagnostics, search matches and changes since the last
+    /// commit, for the scrollbar.
+    pub(crate) fn scrollbar_marks(&self) -> Vec<(usize, ScrollbarMark)> {
+        let diagnostics = self.current_diagnostics.iter().map(|d| {
+            let severity = d.severity.unwrap_or(DiagnosticSeverity::Error);
+            (
+                d.range.start.line as usize,
+                ScrollbarMark::Diagnostic(gutter_severity(severity)),
+            )
+        });
+        let mut match_lines: Vec<usize> = self
+            .buffer
+            .search()
+            .matches()
+            .iter()
+            .map(|m| m.range.start.line)
+            .collect();
+        match_lines.dedup();
+        let matches = match_lines
+            .into_iter()
+            .map(|line| (line, ScrollbarMark::SearchMatch));
+        diagnostics
+            .chain(matches)
+            .chain(self.git_change_marks().iter().copied())
+            .collect()
+    }
+
+    /// The overview of the buffer for a minimap `height` rows tall.
+    fn minimap(&self, height: u16) -> Minimap {
+        let diagnostics: Vec<(usize, GutterDiagnostic)> = self
+            .current_diagnostics
+            .iter()
+            .map(|d| {
+                let severity = d.severity.unwrap_or(DiagnosticSeverity::Error);
+                (d.range.start.line as usize, gutter_severity(severity))
+            })
+            .collect();
+        // Lines far from the view are not in the lazy highlights, so the
+        // engine highlights the few lines sampled itself.
+        Minimap::build(
+            &self.buffer,
+            self.highlighter
+                .as_ref()
+                .map(|h| h as &dyn smash_syntax::HighlightEngine),
+            MINIMAP_WIDTH as usize,
+            height as usize,
+            &diagnostics,
+        )
     }
 
     pub(crate) fn render(&mut self, backend: &mut dyn TerminalBackend) -> Result<()> {
-        let (w, h) = backend.size()?;
+        let start = Instant::now();
+        // The frame and the cursor placed after it go out in one write.
+        let result = self
+            .render_frame(backend)
+            .and_then(|()| Ok(backend.flush()?));
+        self.render_stats.record_frame(start, start.elapsed());
+        result
+    }
 
-        let pos = self.buffer.cursors().primary().position();
-        self.viewport.scroll_to_cursor(pos.line, pos.col);
+    fn render_frame(&mut self, backend: &mut dyn TerminalBackend) -> Result<()> {
+        let (w, h) = backend.size()?;
+        let screen = self.renderer.screen();
+        if (screen.width(), screen.height()) != (w, h) {
+            self.renderer.resize(w, h);
+            self.terminal_painted = None;
+        }
 
         let status_h = 1u16;
-        let edit_area = Rect::new(0, 0, w, h.saturating_sub(status_h));
+        let body_h = h.saturating_sub(status_h);
+        // The watch and task panes share a row, side by side.
+        let watch_h = if (self.watch.is_some() || self.task.is_some()) && body_h >= 6 {
+            (body_h / 3).clamp(3, 12)
+        } else {
+            0
+        };
+        let results_open = self.search_results.is_some()
+            || self.call_hierarchy.is_some()
+            || self.diff_view.is_some()
+            || self.shell_output.is_some();
+        let results_h = if results_open && body_h - watch_h >= 6 {
+            ((body_h - watch_h) / 3).clamp(3, 12)
+        } else {
+            0
+        };
+        let term_h = if self.terminal.is_some() && self.terminal_visible && body_h >= 8 {
+            ((body_h - watch_h - results_h) * 2 / 5).max(3)
+        } else {
+            0
+        };
+        let edit_h = body_h - watch_h - results_h - term_h;
+        let tree_w = self.file_tree_width(w);
+        let tree_area = Rect::new(0, 0, tree_w, edit_h);
+        // The scrollbar takes the right edge, with the minimap inside it.
+        let scrollbar_w = if self.show_scrollbar
+            && w - tree_w >= MIN_EDIT_WIDTH_WITH_SCROLLBAR + SCROLLBAR_WIDTH
+        {
+            SCROLLBAR_WIDTH
+        } else {
+            0
+        };
+        let minimap_w = if self.show_minimap
+            && w - tree_w - scrollbar_w >= MIN_EDIT_WIDTH_WITH_MINIMAP + MINIMAP_WIDTH
+        {
+            MINIMAP_WIDTH
+        } else {
+            0
+        };
+        let edit_area = Rect::new(tree_w, 0, w - tree_w - minimap_w - scrollbar_w, edit_h);
+        let minimap_area = Rect::new(edit_area.x + edit_area.width, 0, minimap_w, edit_h);
+        let scrollbar_area = Rect::new(w - scrollbar_w, 0, scrollbar_w, edit_h);
+        self.editor_left = tree_w;
+        self.minimap_area = (minimap_w > 0 && edit_h > 0).then_some(minimap_area);
+        self.scrollbar_area = (scrollbar_w > 0 && edit_h > 0).then_some(scrollbar_area);
+        let results_area = Rect::new(0, edit_h, w, results_h);
+        let term_area = Rect::new(0, edit_h + results_h, w, term_h);
+        // Title bar on top, then the shell's screen.
+        let grid_area = Rect::new(0, term_area.y + 1, w, term_h.saturating_sub(1));
+        let watch_area = Rect::new(0, edit_h + results_h + term_h, w, watch_h);
         let status_area = Rect::new(0, h.saturating_sub(status_h), w, status_h);
 
-        let theme = default_dark_theme();
+        let pos = self.buffer.cursors().primary().position();
+        // Wide characters before the cursor push it further right.
+        let cursor_x = self.buffer.display_col(pos);
+        // Follow the backend's current size so a resize takes effect on
+        // the next frame.
+        let gutter_w = self.gutter_width();
+        self.viewport.resize(
+            edit_area.height as usize,
+            edit_area.width.saturating_sub(gutter_w) as usize,
+        );
+        self.viewport.scroll_to_cursor(pos.line, cursor_x);
+
+        let theme = self.theme.clone();
 
         // Build per-screen-row diagnostic severity map for the gutter.
         let line_diagnostics: Vec<Option<smash_tui::GutterDiagnostic>> = (0..edit_area.height)
@@ -58,27 +234,291 @@ impl App {
             })
             .collect();
 
+        self.prefetch_highlights();
+        let highlighter = self
+            .lazy_highlights
+            .as_ref()
+            .zip(self.highlighter.as_ref())
+            .and_then(|(lazy, engine)| lazy.for_frame(engine));
+        self.renderer.set_rulers(self.rulers());
+        self.renderer
+            .set_highlight_current_line(self.highlight_current_line);
+        self.renderer.set_indent_guides(self.indent_guide_width());
         self.renderer.render_buffer(
             &self.buffer,
             &self.viewport,
             edit_area,
             &theme,
-            self.highlighter
+            highlighter
                 .as_ref()
                 .map(|h| h as &dyn smash_syntax::HighlightEngine),
-            true,
+            self.line_number_mode(),
             &line_diagnostics,
         );
+        if let Some(annotation) = self.blame_annotation() {
+            self.renderer.render_line_annotation(
+                &self.buffer,
+                &self.viewport,
+                edit_area,
+                self.line_number_mode(),
+                pos.line,
+                &annotation,
+                &theme,
+            );
+        }
+
+        if self.minimap_area.is_some() {
+            let minimap = self.minimap(edit_h);
+            self.renderer
+                .render_minimap(minimap_area, &minimap, &self.viewport, &theme);
+        }
+        if self.scrollbar_area.is_some() {
+            let marks = self.scrollbar_marks();
+            self.renderer.render_scrollbar(
+                scrollbar_area,
+                self.buffer.line_count(),
+                &self.viewport,
+                &marks,
+                &theme,
+            );
+        }
+
+        if tree_w > 0 {
+            let focused = self.file_tree_focused();
+            if let Some(tree) = self.file_tree.as_mut() {
+                tree.scroll_to_selected(edit_h.saturating_sub(1) as usize);
+                self.renderer
+                    .render_file_tree(tree_area, tree, focused, &theme);
+            }
+        }
+
+        if results_h > 0 {
+            let focused = self.search_results_focused();
+            if let Some(hierarchy) = self.call_hierarchy.as_mut() {
+                let lines = hierarchy.lines();
+                hierarchy
+                    .list
+                    .scroll_to_selected(results_h as usize - 1, lines.len());
+                self.renderer.render_list_panel(
+                    results_area,
+                    &hierarchy.title(),
+                    &lines,
+                    focused.then_some(hierarchy.list.selected()),
+                    hierarchy.list.scroll(),
+                    &theme,
+                );
+            } else if let Some(view) = self.diff_view.as_mut() {
+                let rows = view.rows();
+                view.list
+                    .scroll_to_selected(results_h as usize - 1, rows.len());
+                self.renderer.render_diff_panel(
+                    results_area,
+                    &view.title(),
+                    &rows,
+                    focused.then_some(view.list.selected()),
+                    view.list.scroll(),
+                    &theme,
+                );
+            } else if let Some(results) = self.search_results.as_mut() {
+                let lines = results.lines();
+                results
+                    .list
+                    .scroll_to_selected(results_h as usize - 1, lines.len());
+                self.renderer.render_list_panel(
+                    results_area,
+                    &results.title(),
+                    &lines,
+                    focused.then_some(results.list.selected()),
+                    results.list.scroll(),
+                    &theme,
+                );
+            } else if let Some(view) = self.shell_output.as_mut() {
+                let len = view.lines().len();
+                view.list.scroll_to_selected(results_h as usize - 1, len);
+                self.renderer.render_list_panel(
+                    results_area,
+                    &view.title(),
+                    view.lines(),
+                    focused.then_some(view.list.selected()),
+                    view.list.scroll(),
+                    &theme,
+                );
+            }
+        }
+
+        let (task_area, watch_area) = if self.task.is_some() && self.watch.is_some() {
+            let half = w / 2;
+            (
+                Rect::new(0, watch_area.y, half, watch_h),
+                Rect::new(half, watch_area.y, w - half, watch_h),
+            )
+        } else {
+            (watch_area, watch_area)
+        };
+        if let Some(task) = &self.task {
+            self.renderer
+                .render_text_panel(task_area, &task.status_label(), task.output(), &theme);
+        }
+        if let Some(watch) = &self.watch {
+            let title = format!(
+                "{} [{}] {}",
+                watch.status_label(),
+                watch.glob(),
+                watch.command()
+            );
+            self.renderer
+                .render_text_panel(watch_area, &title, watch.output(), &theme);
+        }
+
+        if term_h > 0 {
+            let title = self.terminal_title();
+            self.renderer.render_list_panel(
+                Rect::new(term_area.x, term_area.y, term_area.width, 1),
+                &title,
+                &[],
+                None,
+                0,
+                &theme,
+            );
+            self.resize_terminal(grid_area);
+            if let Some(term) = &mut self.terminal {
+                // A busy shell usually changes a few rows per frame; repaint
+                // just those unless the pane moved or the screen was reset.
+                if self.terminal_painted == Some(grid_area) {
+                    self.renderer
+                        .render_terminal_damage(grid_area, term.grid(), &theme);
+                } else {
+                    self.renderer
+                        .render_terminal(grid_area, term.grid(), &theme);
+                }
+                term.grid_mut().clear_dirty();
+                self.terminal_painted = Some(grid_area);
+            }
+        } else {
+            self.terminal_painted = None;
+        }
 
         // Render status bar based on current input mode
         self.render_status_bar(status_area, pos, &theme);
 
-        self.renderer.flush_to_backend(backend)?;
-
-        let gutter_w = 7u16;
-        let screen_col = gutter_w + (pos.col.saturating_sub(self.viewport.left_col())) as u16;
+        let screen_col =
+            edit_area.x + gutter_w + (cursor_x.saturating_sub(self.viewport.left_col())) as u16;
         let screen_row = (pos.line.saturating_sub(self.viewport.top_line())) as u16;
-        backend.move_cursor(screen_col, screen_row)?;
+
+        if let Some(popup) = &self.popup {
+            let area = popup.placement(screen_col, screen_row, edit_area);
+            self.renderer.render_popup(popup, area, &theme);
+        } else if let Some(popup) = self.signature_popup() {
+            let area = popup.placement(screen_col, screen_row, edit_area);
+            self.renderer.render_popup(&popup, area, &theme);
+        }
+
+        let mut picker_cursor = None;
+        let picker = match self.input_mode {
+            InputMode::SymbolPicker => self.symbol_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::CodeActionPicker => self.code_action_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::ThemePicker => self.theme_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::KillRingPicker => self.kill_ring_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::RecentPicker => self.recent_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::MarkPicker => self.mark_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::RegisterPicker => self.register_picker.as_mut().map(|s| &mut s.picker),
+            InputMode::SearchPicker => self.search_picker.as_mut().map(|s| &mut s.picker),
+            _ => None,
+        };
+        if let Some(picker) = picker {
+            let area = picker.placement(edit_area, 12);
+            self.renderer.render_picker(picker, area, &theme);
+            let query_w = picker.query().chars().count() as u16;
+            picker_cursor = Some((
+                (area.x + 3 + query_w).min(area.x + area.width.saturating_sub(2)),
+                area.y + 1,
+            ));
+        }
+        if self.input_mode == InputMode::FileFinder {
+            let area = smash_tui::finder_placement(edit_area);
+            let (rows, selected) = self.finder_rows(smash_tui::finder_list_rows(area));
+            let status = self.finder_status();
+            let preview = self.finder_preview.as_ref();
+            let view = smash_tui::FinderView {
+                query: &self.prompt_input,
+                status: &status,
+                rows: &rows,
+                selected,
+                preview: preview.map_or(&[], |p| p.lines.as_slice()),
+            };
+            self.renderer.render_finder(
+                &view,
+                area,
+                &theme,
+                preview
+                    .and_then(|p| p.highlighter.as_ref())
+                    .map(|h| h as &dyn smash_syntax::HighlightEngine),
+            );
+            let query_w = self.prompt_input.chars().count() as u16;
+            picker_cursor = Some((
+                (area.x + 3 + query_w).min(area.x + area.width.saturating_sub(2)),
+                area.y + 1,
+            ));
+        }
+
+        if self.render_stats.visible {
+            let popup = self.render_stats.popup(self.idle_tasks.len());
+            let mut area = popup.placement(w, 0, Rect::new(0, 0, w, edit_h));
+            // Pinned to the top-right corner.
+            area.y = 0;
+            self.renderer.render_popup(&popup, area, &theme);
+        }
+
+        self.renderer.draw_to_backend(backend)?;
+
+        if term_h > 0 && self.terminal_focused() {
+            if let Some(term) = &self.terminal {
+                let cursor = term.grid().cursor;
+                backend.move_cursor(
+                    cursor.col.min(w.saturating_sub(1)),
+                    grid_area.y + cursor.row.min(grid_area.height - 1),
+                )?;
+                if term.is_alive() {
+                    backend.show_cursor()?;
+                } else {
+                    backend.hide_cursor()?;
+                }
+                return Ok(());
+            }
+        }
+
+        if self.file_tree_focused() && tree_w > 0 {
+            if let Some(tree) = &self.file_tree {
+                let row = tree.selected_index().saturating_sub(tree.scroll()) + 1;
+                backend.move_cursor(0, (row as u16).min(edit_h.saturating_sub(1)))?;
+                backend.hide_cursor()?;
+                return Ok(());
+            }
+        }
+
+        if self.search_results_focused() && results_h > 0 {
+            let list = match (
+                &self.call_hierarchy,
+                &self.diff_view,
+                &self.search_results,
+                &self.shell_output,
+            ) {
+                (Some(hierarchy), _, _, _) => Some(hierarchy.list),
+                (None, Some(view), _, _) => Some(view.list),
+                (None, None, Some(results), _) => Some(results.list),
+                (None, None, None, Some(view)) => Some(view.list),
+                (None, None, None, None) => None,
+            };
+            if let Some(list) = list {
+                let row = list.selected().saturating_sub(list.scroll()) + 1;
+                backend.move_cursor(0, results_area.y + (row as u16).min(results_h - 1))?;
+                backend.hide_cursor()?;
+                return Ok(());
+            }
+        }
+
+        let (col, row) = picker_cursor.unwrap_or((screen_col, screen_row));
+        backend.move_cursor(col, row)?;
         backend.show_cursor()?;
 
         Ok(())
@@ -105,10 +545,11 @@ impl App {
             }
             InputMode::PromptFind => {
                 let match_count = self.buffer.search().match_count();
+                let label = self.search_prompt_label("Find");
                 let prompt_text = if match_count > 0 {
-                    format!("Find: {} ({} matches)", self.prompt_input, match_count)
+                    format!("{}: {} ({} matches)", label, self.prompt_input, match_count)
                 } else {
-                    format!("Find: {}", self.prompt_input)
+                    format!("{}: {}", label, self.prompt_input)
                 };
                 self.renderer.render_status_bar(
                     status_area,
@@ -130,7 +571,7 @@ impl App {
                     theme,
                 );
             }
-            InputMode::PromptSaveAs => {
+            InputMode::PromptSaveAs | InputMode::PromptSaveAsAndQuit => {
                 let prompt_text = format!("Save as: {}", self.prompt_input);
                 self.renderer.render_status_bar(
                     status_area,
@@ -141,6 +582,20 @@ impl App {
                     theme,
                 );
             }
+            InputMode::PromptExport => {
+                let prompt_text = format!(
+                    "Export to (.html or ANSI; -n numbers, START-END lines): {}",
+                    self.prompt_input
+                );
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
             InputMode::PromptFindReplace => {
                 let prompt_text = if self.replace_focused {
                     format!(
@@ -148,7 +603,11 @@ impl App {
                         self.prompt_input, self.replace_input
                     )
                 } else {
-                    format!("Find (for replace): {}", self.prompt_input)
+                    format!(
+                        "{}: {}",
+                        self.search_prompt_label("Find (for replace)"),
+                        self.prompt_input
+                    )
                 };
                 self.renderer.render_status_bar(
                     status_area,
@@ -160,19 +619,9 @@ impl App {
                 );
             }
             InputMode::FileFinder => {
-                let result_count = self.finder_results.len();
-                let prompt_text = if result_count > 0 {
-                    let first = self.finder_results[0].relative_path();
-                    format!(
-                        "Find file: {} ({} results, top: {})",
-                        self.prompt_input, result_count, first
-                    )
-                } else {
-                    format!("Find file: {}", self.prompt_input)
-                };
                 self.renderer.render_status_bar(
                     status_area,
-                    &prompt_text,
+                    "Find file \u{2014} \u{2191}/\u{2193} select, Enter open, Esc cancel",
                     pos.line,
                     pos.col,
                     false,
@@ -180,7 +629,7 @@ impl App {
                 );
             }
             InputMode::Normal => {
-                let status_text = self.build_normal_status_text();
+                let (status_text, emphasis) = self.normal_status();
                 self.renderer.render_status_bar(
                     status_area,
                     &status_text,
@@ -189,6 +638,135 @@ impl App {
                     self.buffer.is_dirty(),
                     theme,
                 );
+                // Counts in their severity's colour, bold so they stand out
+                // without it.
+                for (range, severity) in emphasis {
+                    let style = theme.diagnostic_style(severity).bold();
+                    self.renderer
+                        .emphasize_status(status_area, range.start, range.len(), style);
+                }
+            }
+            InputMode::CommandPalette => {
+                let prompt_text = match super::commands::palette_match(&self.prompt_input) {
+                    Some(cmd) => format!(
+                        "Command: {} \u{2192} {}",
+                        self.prompt_input,
+                        cmd.info().description
+                    ),
+                    None => format!("Command: {}", self.prompt_input),
+                };
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptVimCommand => {
+                let prompt_text = format!(":{}", self.prompt_input);
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptSearchPresetName => {
+                let prompt_text = format!("Save last search as preset: {}", self.prompt_input);
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptLineEnding => {
+                let prompt_text = format!(
+                    "Line ending (lf, crlf or cr; now {}): {}",
+                    self.buffer.line_ending().name().to_ascii_lowercase(),
+                    self.prompt_input
+                );
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptReopenEncoding => {
+                let prompt_text = format!(
+                    "Reopen with encoding (e.g. utf-8, shift_jis, latin1): {}",
+                    self.prompt_input
+                );
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptLogLevel => {
+                let prompt_text = format!("Log filter: {}", self.prompt_input);
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptWatch => {
+                let prompt_text =
+                    format!("Watch (GLOB COMMAND, empty to stop): {}", self.prompt_input);
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptTask => {
+                let names: Vec<&str> = self.tasks.keys().map(String::as_str).collect();
+                let prompt_text = format!(
+                    "Run task ({}; empty closes the pane): {}",
+                    names.join(", "),
+                    self.prompt_input
+                );
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptShellCommand => {
+                let prompt_text = format!(
+                    "Shell command (|cmd filters the selection, <cmd inserts): {}",
+                    self.prompt_input
+                );
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
             }
             InputMode::PromptLspRename => {
                 let prompt_text = format!("Rename to: {}", self.prompt_input);
@@ -201,48 +779,263 @@ impl App {
                     theme,
                 );
             }
+            InputMode::PromptWorkspaceSearch => {
+                let prompt_text = format!(
+                    "Search workspace (/regex/ for a regex): {}",
+                    self.prompt_input
+                );
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::SymbolPicker
+            | InputMode::CodeActionPicker
+            | InputMode::ThemePicker
+            | InputMode::KillRingPicker
+            | InputMode::RecentPicker
+            | InputMode::MarkPicker
+            | InputMode::RegisterPicker
+            | InputMode::SearchPicker => {
+                let prompt_text = self.picker_prompt();
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptReplaceStep => {
+                let prompt_text = self.replace_step_prompt();
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptCreateCompanion => {
+                let prompt_text = self.create_companion_prompt();
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptQuit => {
+                let prompt_text = self.quit_prompt();
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptReload => {
+                let prompt_text = self.reload_prompt();
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+            InputMode::PromptTreeCreate
+            | InputMode::PromptTreeRename
+            | InputMode::PromptTreeDelete => {
+                let prompt_text = self.file_tree_prompt();
+                self.renderer.render_status_bar(
+                    status_area,
+                    &prompt_text,
+                    pos.line,
+                    pos.col,
+                    false,
+                    theme,
+                );
+            }
+        }
+    }
+
+    /// A search prompt's label with its active toggles, e.g.
+    /// `Find [regex, case]`.
+    pub(crate) fn search_prompt_label(&self, base: &str) -> String {
+        let on = self.search_options.describe();
+        if on.is_empty() {
+            base.to_string()
+        } else {
+            format!("{} [{}]", base, on)
+        }
+    }
+
+    /// Status bar tags for what the language server does for this buffer,
+    /// so a feature that is not working can be traced to its cause.
+    pub(crate) fn lsp_status(&self) -> String {
+        if !self.lsp_server_started {
+            let configured = self.lsp_enabled
+                && self
+                    .language_id
+                    .as_ref()
+                    .is_some_and(|lang| self.lsp_server_configs.contains_key(lang));
+            return if configured {
+                " [LSP off]".to_string()
+            } else {
+                String::new()
+            };
+        }
+        let mut tags = vec!["LSP"];
+        if self.lsp_formatting {
+            tags.push("fmt");
+        }
+        let hooks = self.save_hooks();
+        match (hooks.format, self.lsp_formatting) {
+            (true, true) => tags.push("fmt-on-save"),
+            (true, false) => tags.push("fmt-on-save:n/a"),
+            (false, _) => {}
+        }
+        if hooks.organize_imports {
+            tags.push("imports-on-save");
+        }
+        // Inlay hints are not drawn yet, so a server offering them shows
+        // them as off.
+        if self.lsp_inlay_hints {
+            tags.push("hints:off");
         }
+        let mut status = format!(" [{}]", tags.join(" "));
+        if let Some(progress) = &self.lsp_progress {
+            let frame = SPINNER[self.lsp_progress_ticks % SPINNER.len()];
+            status.push_str(&format!(" [{} {}]", frame, progress));
+        }
+        status
     }
 
-    /// Build the status text for Normal mode (includes LSP info, diagnostics).
-    fn build_normal_status_text(&self) -> String {
-        let diag_info = if !self.current_diagnostics.is_empty() {
-            let errors = self
-                .current_diagnostics
-                .iter()
-                .filter(|d| d.severity == Some(DiagnosticSeverity::Error))
-                .count();
-            let warnings = self
-                .current_diagnostics
-                .iter()
-                .filter(|d| d.severity == Some(DiagnosticSeverity::Warning))
-                .count();
-            format!(" E:{} W:{}", errors, warnings)
+    /// Build the status text for Normal mode (includes LSP info,
+    /// diagnostics), plus the character ranges of its non-zero diagnostic
+    /// counts and their severities.
+    pub(crate) fn normal_status(
+        &self,
+    ) -> (String, Vec<(std::ops::Range<usize>, GutterDiagnostic)>) {
+        let mut diag_info = String::new();
+        let mut emphasis = Vec::new();
+        if !self.current_diagnostics.is_empty() {
+            for (severity, gutter, letter) in [
+                (DiagnosticSeverity::Error, GutterDiagnostic::Error, 'E'),
+                (DiagnosticSeverity::Warning, GutterDiagnostic::Warning, 'W'),
+            ] {
+                let count = self
+                    .current_diagnostics
+                    .iter()
+                    .filter(|d| d.severity == Some(severity))
+                    .count();
+                let text = format!("{}{}:{}", self.diagnostic_signs.sign(gutter), letter, count);
+                let start = diag_info.chars().count() + 1;
+                if count > 0 {
+                    emphasis.push((start..start + text.chars().count(), gutter));
+                }
+                diag_info.push(' ');
+                diag_info.push_str(&text);
+            }
+        }
+
+        let read_only_info = if self.buffer.is_read_only() && self.buffer.path().is_some() {
+            " [RO]"
         } else {
-            String::new()
+            ""
+        };
+        let encoding_info = match self.buffer.encoding() {
+            encoding if encoding.is_utf8() => String::new(),
+            encoding => format!(" [{}]", encoding),
+        };
+        let line_ending_info = match self.buffer.line_ending() {
+            LineEnding::Lf => String::new(),
+            ending => format!(" [{}]", ending.name()),
+        };
+        let language_info = match self.language_id.as_deref() {
+            Some(lang) if lang != LanguageId::Plain.as_str() => format!(" [{}]", lang),
+            _ => String::new(),
         };
+        let lsp_indicator = self.lsp_status();
 
-        let lsp_indicator = if self.lsp_server_started {
-            " [LSP]"
+        let watch_info: String = self
+            .task
+            .as_ref()
+            .map(|t| t.status_label())
+            .into_iter()
+            .chain(self.watch.as_ref().map(|w| w.status_label()))
+            .map(|label| format!(" [{}]", label))
+            .collect();
+        let terminal_info = if self.terminal_focused() {
+            " [terminal]"
+        } else if self.file_tree_focused() {
+            " [files]"
+        } else if self.search_results_focused() {
+            match (
+                &self.call_hierarchy,
+                &self.diff_view,
+                &self.search_results,
+                &self.shell_output,
+            ) {
+                (Some(_), _, _, _) => " [calls]",
+                (None, Some(_), _, _) => " [diff]",
+                (None, None, Some(results), _) => results.status_tag(),
+                (None, None, None, Some(_)) => " [output]",
+                (None, None, None, None) => "",
+            }
         } else {
             ""
         };
+        let command_info = match self.running_commands.as_slice() {
+            [] => String::new(),
+            [title] => format!(" [running '{}']", title),
+            titles => format!(" [running {} commands]", titles.len()),
+        };
+        let alternate_info = self
+            .alternate_file
+            .as_ref()
+            .and_then(|loc| loc.path.as_deref())
+            .map(|path| format!(" [#{}]", super::loading::file_name(path)))
+            .unwrap_or_default();
+        let loading_info = self.loading_status();
+        let vim_info = self.vim_status();
 
+        let prefix = format!(
+            "{}{}{}{}{}{}{}{}",
+            self.filename.as_deref().unwrap_or("[scratch]"),
+            read_only_info,
+            vim_info,
+            loading_info,
+            encoding_info,
+            line_ending_info,
+            language_info,
+            lsp_indicator,
+        );
+        let offset = prefix.chars().count();
+        let emphasis = emphasis
+            .into_iter()
+            .map(|(range, severity)| (range.start + offset..range.end + offset, severity))
+            .collect();
+        let mut text = format!(
+            "{}{}{}{}{}{}",
+            prefix, diag_info, alternate_info, watch_info, command_info, terminal_info
+        );
         if let Some(msg) = self.messages.last() {
-            format!(
-                "{}{}{} | {}",
-                self.filename.as_deref().unwrap_or("[scratch]"),
-                lsp_indicator,
-                diag_info,
-                msg.text()
-            )
-        } else {
-            format!(
-                "{}{}{}",
-                self.filename.as_deref().unwrap_or("[scratch]"),
-                lsp_indicator,
-                diag_info,
-            )
+            text.push_str(" | ");
+            text.push_str(msg.text());
         }
+        (text, emphasis)
     }
 }
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use smash_tui::Popup;
+use tracing::{info, warn};
+
+/// Time from startup to the first frame on screen that startup aims for.
+pub(crate) const FIRST_PAINT_BUDGET: Duration = Duration::from_millis(50);
+
+/// Frame timings, shown by `ToggleRenderStats`.
+#[derive(Debug, Clone)]
+pub(crate) struct RenderStats {
+    /// When the editor started.
+    started: Instant,
+    /// Time from `started` until the first frame was drawn.
+    first_paint: Option<Duration>,
+    /// How long the latest frame took to draw.
+    last_frame: Duration,
+    /// The slowest frame since startup.
+    slowest_frame: Duration,
+    frames: u64,
+    /// Draw the overlay.
+    pub(crate) visible: bool,
+}
+
+impl RenderStats {
+    pub(crate) fn new(started: Instant) -> Self {
+        Self {
+            started,
+            first_paint: None,
+            last_frame: Duration::ZERO,
+            slowest_frame: Duration::ZERO,
+            frames: 0,
+            visible: false,
+        }
+    }
+
+    /// Note a frame that began at `start` and took `took` to draw.
+    pub(crate) fn record_frame(&mut self, start: Instant, took: Duration) {
+        self.frames += 1;
+        self.last_frame = took;
+        self.slowest_frame = self.slowest_frame.max(took);
+        if self.first_paint.is_none() {
+            let first_paint = (start + took).saturating_duration_since(self.started);
+            self.first_paint = Some(first_paint);
+            if first_paint > FIRST_PAINT_BUDGET {
+                warn!(
+                    "first paint took {:?}, over the {:?} budget",
+                    first_paint, FIRST_PAINT_BUDGET
+                );
+            } else {
+                info!("first paint after {:?}", first_paint);
+            }
+        }
+    }
+
+    /// The overlay, with `idle_tasks` startup tasks still to run.
+    pub(crate) fn popup(&self, idle_tasks: usize) -> Popup {
+        let first_paint = match self.first_paint {
+            Some(t) => format!(
+                "First paint: {} ({} budget {})",
+                millis(t),
+                if t > FIRST_PAINT_BUDGET {
+                    "over"
+                } else {
+                    "within"
+                },
+                millis(FIRST_PAINT_BUDGET)
+            ),
+            None => "First paint: pending".to_string(),
+        };
+        let mut lines = vec![
+            first_paint,
+            format!("Last frame: {}", millis(self.last_frame)),
+            format!("Slowest frame: {}", millis(self.slowest_frame)),
+            format!("Frames: {}", self.frames),
+        ];
+        if idle_tasks > 0 {
+            lines.push(format!("Startup tasks left: {}", idle_tasks));
+        }
+        Popup::new("Render stats", lines)
+    }
+}
+
+pub(super) fn millis(d: Duration) -> String {
+    format!("{:.1} ms", d.as_secs_f64() * 1000.0)
+}
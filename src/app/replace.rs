@@ -0,0 +1,288 @@
+use smash_core::edit::EditCommand;
+use smash_core::position::{Position, Range};
+use smash_core::search::{SearchMatch, SearchQuery};
+
+use super::{App, InputMode};
+
+/// An interactive replace in progress: each match is shown and answered
+/// with y/n/a/q before moving on.
+pub(crate) struct ReplaceSession {
+    pattern: String,
+    query: SearchQuery,
+    replacement: String,
+    /// The match waiting for an answer.
+    current: Option<SearchMatch>,
+    /// Matches starting before this have been answered.
+    from: Position,
+    /// Matches shown so far.
+    shown: usize,
+    replaced: usize,
+}
+
+// =========================================================================
+// Interactive find-and-replace
+// =========================================================================
+
+impl App {
+    /// Step through the matches of `pattern`, asking before replacing
+    /// each with `replacement`.
+    pub(crate) fn start_interactive_replace(&mut self, pattern: &str, replacement: &str) {
+        self.prompt_input.clear();
+        self.replace_input.clear();
+        self.input_mode = InputMode::Normal;
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            self.messages.warn("Empty search pattern");
+            return;
+        }
+        let query = match self.search_options.query(pattern) {
+            Ok(query) => query,
+            Err(e) => {
+                self.messages.error(format!("Invalid regex: {}", e));
+                return;
+            }
+        };
+        self.record_search(pattern, Some(replacement));
+        self.replace_session = Some(ReplaceSession {
+            pattern: pattern.to_string(),
+            query,
+            replacement: replacement.to_string(),
+            current: None,
+            from: Position::new(0, 0),
+            shown: 0,
+            replaced: 0,
+        });
+        self.input_mode = InputMode::PromptReplaceStep;
+        self.next_replace_match();
+    }
+
+    /// Answer for the current match: `y` replaces it, `n` skips it, `a`
+    /// replaces it and every later one, `q` stops.
+    pub(crate) fn answer_replace(&mut self, answer: char) {
+        match answer {
+            'y' | 'Y' if self.replace_current() => self.next_replace_match(),
+            'n' | 'N' => {
+                if let Some(session) = self.replace_session.as_mut() {
+                    if let Some(m) = session.current.take() {
+                        session.from = m.range.end;
+                    }
+                }
+                self.next_replace_match();
+            }
+            'a' | 'A' => {
+                self.replace_remaining();
+                self.finish_replace();
+            }
+            'q' | 'Q' => self.finish_replace(),
+            _ => {}
+        }
+    }
+
+    /// Status bar text while stepping through matches.
+    pub(crate) fn replace_step_prompt(&self) -> String {
+        let Some(session) = self.replace_session.as_ref() else {
+            return String::new();
+        };
+        format!(
+            "Replace with '{}'? (y)es (n)o (a)ll (q)uit — {} replaced",
+            session.replacement, session.replaced
+        )
+    }
+
+    /// Find the first match at or after the answered part and select it,
+    /// or finish when there are none left.
+    fn next_replace_match(&mut self) {
+        let Some(session) = self.replace_session.as_mut() else {
+            return;
+        };
+        // Highlight every match, and pick the current one from those rather
+        // than searching the text a second time.
+        let text = self.buffer.text().to_string();
+        self.buffer
+            .search_mut()
+            .set_query(session.query.clone(), &text);
+        let next = self
+            .buffer
+            .search()
+            .matches()
+            .iter()
+            .find(|m| m.range.start >= session.from && m.range.start != m.range.end)
+            .cloned();
+        session.current = next.clone();
+        let Some(m) = next else {
+            self.finish_replace();
+            return;
+        };
+        session.shown += 1;
+        let cursor = self.buffer.cursors_mut().primary_mut();
+        cursor.set_anchor(m.range.start);
+        cursor.set_position(m.range.end);
+    }
+
+    /// Replace the current match as its own undo step. Returns `false`,
+    /// ending the session, if the buffer could not be edited.
+    fn replace_current(&mut self) -> bool {
+        let Some(session) = self.replace_session.as_ref() else {
+            return false;
+        };
+        let Some(m) = session.current.clone() else {
+            return false;
+        };
+        let text = self.buffer.text().to_string();
+        let replacement = expand_replacement(&session.query, &session.replacement, &text, &m);
+        self.buffer.cursors_mut().primary_mut().clear_selection();
+        self.buffer.begin_undo_group();
+        let removed = self.apply_edit(EditCommand::Delete { range: m.range });
+        let inserted = removed.and_then(|_| {
+            self.apply_edit(EditCommand::Insert {
+                pos: m.range.start,
+                text: replacement.clone(),
+            })
+        });
+        self.buffer.end_undo_group();
+        if inserted.is_err() {
+            self.finish_replace();
+            return false;
+        }
+        self.lsp_did_change();
+        let end = end_of_insert(m.range.start, &replacement);
+        if let Some(session) = self.replace_session.as_mut() {
+            session.replaced += 1;
+            session.from = end;
+            session.current = None;
+        }
+        self.buffer.cursors_mut().primary_mut().set_position(end);
+        true
+    }
+
+    /// Replace the current match and every later one, each as its own undo
+    /// step. The matches are found once up front, so this stays linear in
+    /// the size of the buffer however many there are.
+    fn replace_remaining(&mut self) {
+        let Some(session) = self.replace_session.as_ref() else {
+            return;
+        };
+        let Some(from) = session.current.as_ref().map(|m| m.range.start) else {
+            return;
+        };
+        let text = self.buffer.text().to_string();
+        // Skip overlapping matches, as answering `y` to each would.
+        let mut last_end = 0;
+        let edits: Vec<(SearchMatch, String)> = session
+            .query
+            .find_all(&text)
+            .into_iter()
+            .filter(|m| m.range.start >= from && m.range.start != m.range.end)
+            .filter(|m| {
+                let keep = m.byte_range.start >= last_end;
+                if keep {
+                    last_end = m.byte_range.end;
+                }
+                keep
+            })
+            .map(|m| {
+                let replacement =
+                    expand_replacement(&session.query, &session.replacement, &text, &m);
+                (m, replacement)
+            })
+            .collect();
+
+        self.buffer.cursors_mut().primary_mut().clear_selection();
+        // Where the previous replacement ended before and after the edit;
+        // later matches move by the difference.
+        let mut old_end = Position::new(0, 0);
+        let mut new_end = Position::new(0, 0);
+        let mut replaced = 0;
+        for (m, replacement) in edits {
+            let range = Range::new(
+                shifted(m.range.start, old_end, new_end),
+                shifted(m.range.end, old_end, new_end),
+            );
+            self.buffer.begin_undo_group();
+            let removed = self.apply_edit(EditCommand::Delete { range });
+            let inserted = removed.and_then(|_| {
+                self.apply_edit(EditCommand::Insert {
+                    pos: range.start,
+                    text: replacement.clone(),
+                })
+            });
+            self.buffer.end_undo_group();
+            if inserted.is_err() {
+                break;
+            }
+            old_end = m.range.end;
+            new_end = end_of_insert(range.start, &replacement);
+            replaced += 1;
+        }
+        if replaced == 0 {
+            return;
+        }
+        self.lsp_did_change();
+        if let Some(session) = self.replace_session.as_mut() {
+            session.replaced += replaced;
+            session.current = None;
+        }
+        self.buffer
+            .cursors_mut()
+            .primary_mut()
+            .set_position(new_end);
+    }
+
+    /// Leave the replace, reporting how many matches were replaced.
+    pub(crate) fn finish_replace(&mut self) {
+        let Some(session) = self.replace_session.take() else {
+            return;
+        };
+        self.input_mode = InputMode::Normal;
+        self.buffer.search_mut().clear();
+        self.buffer.cursors_mut().primary_mut().clear_selection();
+        if session.shown == 0 {
+            self.messages
+                .info(format!("No occurrences of '{}' found", session.pattern));
+        } else {
+            self.messages
+                .info(format!("Replaced {} occurrence(s)", session.replaced));
+        }
+    }
+}
+
+/// The text `replacement` stands for at `m`, with regex groups such as `$1`
+/// filled in.
+fn expand_replacement(
+    query: &SearchQuery,
+    replacement: &str,
+    text: &str,
+    m: &SearchMatch,
+) -> String {
+    match query {
+        SearchQuery::Regex(re) => match re.captures_at(text, m.byte_range.start) {
+            Some(caps) => {
+                let mut out = String::new();
+                caps.expand(replacement, &mut out);
+                out
+            }
+            None => replacement.to_string(),
+        },
+        SearchQuery::Plain { .. } => replacement.to_string(),
+    }
+}
+
+/// Where `pos`, after an edit that moved `old_end` to `new_end`, is now.
+fn shifted(pos: Position, old_end: Position, new_end: Position) -> Position {
+    if pos.line == old_end.line {
+        Position::new(new_end.line, new_end.col + pos.col - old_end.col)
+    } else {
+        Position::new(pos.line - old_end.line + new_end.line, pos.col)
+    }
+}
+
+/// Where the cursor ends up after inserting `text` at `start`.
+fn end_of_insert(start: Position, text: &str) -> Position {
+    match text.rfind('\n') {
+        Some(i) => Position::new(
+            start.line + text.matches('\n').count(),
+            text[i + 1..].chars().count(),
+        ),
+        None => Position::new(start.line, start.col + text.chars().count()),
+    }
+}
@@ -0,0 +1,228 @@
+use std::time::{Duration, Instant};
+
+use smash_core::buffer::Buffer;
+use smash_core::safe_write::WriteMode;
+use tracing::warn;
+
+use smash_core::position::Position;
+use smash_lsp::{LspPosition, LspRange};
+use smash_syntax::LanguageId;
+
+use super::App;
+use crate::lsp_types::LspCommand;
+
+/// How long a save waits for each round of the server's pre-save edits
+/// before going on without them.
+pub(crate) const WILL_SAVE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A save waiting on `willSaveWaitUntil`, formatting or organize imports.
+pub(crate) struct PendingSave {
+    uri: String,
+    /// Document version the server computed its edits against.
+    version: i32,
+    /// Write without the edits after this.
+    deadline: Instant,
+    /// After organizing imports, ask for formatting (`true`) or the
+    /// server's own pre-save edits (`false`) before writing.
+    then_format: Option<bool>,
+    /// Quit once the file is written (Save All from the quit prompt).
+    then_quit: bool,
+}
+
+/// What to run before writing the current buffer, after its language's
+/// overrides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SaveHooks {
+    pub(crate) format: bool,
+    pub(crate) organize_imports: bool,
+}
+
+// =========================================================================
+// Save All
+// =========================================================================
+
+impl App {
+    /// How files are written, from `editor.atomic_save`.
+    pub(crate) fn write_mode(&self) -> WriteMode {
+        if self.editor_config.atomic_save {
+            WriteMode::Atomic
+        } else {
+            WriteMode::InPlace
+        }
+    }
+
+    /// Every buffer the editor holds, active one first.
+    pub(crate) fn open_buffers(&self) -> impl Iterator<Item = &Buffer> {
+        std::iter::once(&self.buffer)
+    }
+
+    /// Write every modified buffer. The editor holds one buffer, so this
+    /// is a Save of the active buffer when it has changes, with the
+    /// server's pre-save edits and a Save As prompt for unnamed text.
+    pub(crate) fn cmd_save_all(&mut self) {
+        if self.buffer.is_dirty() {
+            self.cmd_save();
+        } else {
+            self.messages.info("No modified files to save");
+        }
+    }
+}
+
+// =========================================================================
+// Pre-save edits from the language server
+// =========================================================================
+
+impl App {
+    /// `lsp.format_on_save` and `lsp.organize_imports_on_save` with the
+    /// current language's `[languages]` overrides applied.
+    pub(crate) fn save_hooks(&self) -> SaveHooks {
+        let language = self
+            .buffer
+            .path()
+            .map(LanguageId::from_path)
+            .unwrap_or(LanguageId::Plain);
+        let overrides = self.language_configs.get(language.as_str());
+        SaveHooks {
+            format: overrides
+                .and_then(|l| l.format_on_save)
+                .unwrap_or(self.format_on_save),
+            organize_imports: overrides
+                .and_then(|l| l.organize_imports_on_save)
+                .unwrap_or(self.organize_imports_on_save),
+        }
+    }
+
+    /// Ask the server for edits to make before saving: organized imports
+    /// if configured, then its formatting when formatting on save,
+    /// otherwise any edits it wants to be asked for. Returns `true` if
+    /// the save is now held until they arrive (see
+    /// [`App::finish_pending_save`]).
+    pub(crate) fn request_will_save_edits(&mut self) -> bool {
+        if self.pending_save.is_some() {
+            self.messages
+                .info("Waiting for the language server to save");
+            return true;
+        }
+        let hooks = self.save_hooks();
+        let format = hooks.format && self.lsp_formatting;
+        let will_save = format || self.lsp_will_save_wait_until;
+        if !self.lsp_server_started
+            || !(will_save || hooks.organize_imports)
+            || self.buffer.is_read_only()
+        {
+            return false;
+        }
+        let Some(uri) = self.current_uri() else {
+            return false;
+        };
+        if hooks.organize_imports {
+            let end = self
+                .buffer
+                .clamp_position(Position::new(usize::MAX, usize::MAX));
+            let range = LspRange::new(LspPosition::new(0, 0), self.lsp_position(end));
+            let command = LspCommand::OrganizeImports {
+                uri: uri.clone(),
+                range,
+            };
+            self.hold_save(uri, command, will_save.then_some(format))
+        } else {
+            let command = LspCommand::WillSaveWaitUntil {
+                uri: uri.clone(),
+                format,
+            };
+            self.hold_save(uri, command, None)
+        }
+    }
+
+    /// Send a pre-save request and hold the save until it is answered
+    /// or times out. Returns whether the request was sent.
+    fn hold_save(&mut self, uri: String, command: LspCommand, then_format: Option<bool>) -> bool {
+        if self.lsp_cmd_tx.try_send(command).is_err() {
+            return false;
+        }
+        self.pending_save = Some(PendingSave {
+            uri,
+            version: self.document_version,
+            deadline: Instant::now() + WILL_SAVE_TIMEOUT,
+            then_format,
+            then_quit: false,
+        });
+        true
+    }
+
+    /// Quit once the held save has been written.
+    pub(crate) fn quit_after_pending_save(&mut self) {
+        if let Some(pending) = self.pending_save.as_mut() {
+            pending.then_quit = true;
+        }
+    }
+
+    /// Write a held save, then quit if that was asked for and it worked.
+    fn write_held_save(&mut self, then_quit: bool) {
+        self.write_buffer();
+        if then_quit {
+            self.quit_if_all_saved();
+        }
+    }
+
+    /// Apply the server's pre-save `edits` for `uri` and write the held
+    /// save. Edits computed before the buffer changed again are dropped.
+    pub(crate) fn finish_pending_save(&mut self, uri: &str, edits: Vec<smash_lsp::TextEdit>) {
+        match &self.pending_save {
+            Some(p) if p.uri == uri => {}
+            // A late answer to a save that already timed out.
+            _ => return,
+        }
+        let Some(pending) = self.pending_save.take() else {
+            return;
+        };
+        // Another file was opened while waiting.
+        if !self.is_current_uri(uri) {
+            return;
+        }
+        if pending.version != self.document_version {
+            warn!("dropping pre-save edits: the buffer changed while waiting");
+        } else if !edits.is_empty() {
+            self.apply_text_edits(edits);
+            self.lsp_did_change();
+        }
+        if let Some(format) = pending.then_format {
+            let command = LspCommand::WillSaveWaitUntil {
+                uri: uri.to_string(),
+                format,
+            };
+            if self.hold_save(uri.to_string(), command, None) {
+                if pending.then_quit {
+                    self.quit_after_pending_save();
+                }
+                return;
+            }
+        }
+        self.write_held_save(pending.then_quit);
+    }
+
+    /// Time left before a held save is written anyway.
+    pub(crate) fn pending_save_remaining(&self, now: Instant) -> Option<Duration> {
+        self.pending_save
+            .as_ref()
+            .map(|p| p.deadline.saturating_duration_since(now))
+    }
+
+    /// Write a held save without edits once the server has taken too
+    /// long. Returns `true` if it was written and the screen needs a
+    /// redraw.
+    pub(crate) fn poll_pending_save(&mut self, now: Instant) -> bool {
+        match &self.pending_save {
+            Some(p) if p.deadline <= now => {}
+            _ => return false,
+        }
+        let Some(pending) = self.pending_save.take() else {
+            return false;
+        };
+        warn!("willSaveWaitUntil timed out after {:?}", WILL_SAVE_TIMEOUT);
+        if self.is_current_uri(&pending.uri) {
+            self.write_held_save(pending.then_quit);
+        }
+        true
+    }
+}
@@ -0,0 +1,28 @@
+use smash_core::buffer::{Buffer, BufferId};
+use tracing::info;
+
+use super::App;
+
+// =========================================================================
+// Scratch buffers
+// =========================================================================
+
+impl App {
+    /// Replace the buffer with an empty, unnamed one. Jump back returns
+    /// to the file.
+    pub(crate) fn cmd_new_buffer(&mut self) {
+        if self.buffer.is_dirty() {
+            self.messages
+                .warn("Save changes before opening a new buffer");
+            return;
+        }
+        self.push_jump();
+        self.buffer = Buffer::new(BufferId::next());
+        self.buffer.set_undo_budget(self.undo_budget);
+        self.filename = None;
+        self.highlighter = None;
+        self.messages
+            .info("New scratch buffer \u{2014} Save asks for a file name");
+        info!("new scratch buffer");
+    }
+}
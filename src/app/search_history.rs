@@ -0,0 +1,187 @@
+use std::path::PathBuf;
+
+use smash_core::search_history::{ProjectSearches, SavedSearch};
+use smash_tui::{Picker, PickerItem};
+
+use super::{App, InputMode};
+
+/// The saved-search picker overlay and the searches behind its items.
+pub(crate) struct SearchPicker {
+    pub(crate) picker: Picker,
+    /// Same order as the picker items.
+    searches: Vec<SavedSearch>,
+    /// Opened from the find-and-replace prompt.
+    from_replace: bool,
+}
+
+/// `[regex, case] → replacement`, leaving out the parts that are unset.
+fn describe(search: &SavedSearch) -> String {
+    let mut parts = Vec::new();
+    let options = search.options.describe();
+    if !options.is_empty() {
+        parts.push(format!("[{}]", options));
+    }
+    if let Some(replacement) = &search.replacement {
+        parts.push(format!("→ {}", replacement));
+    }
+    parts.join(" ")
+}
+
+// =========================================================================
+// Search history and presets
+// =========================================================================
+
+impl App {
+    /// The workspace whose searches are remembered together.
+    fn search_project(&self) -> Option<PathBuf> {
+        self.config_paths
+            .as_ref()
+            .and_then(|paths| paths.project_dir.clone())
+    }
+
+    /// Searches recorded for the current project.
+    pub(crate) fn project_searches(&self) -> Option<&ProjectSearches> {
+        self.search_history
+            .project(self.search_project().as_deref())
+    }
+
+    /// Remember `pattern` with the current search options, and the
+    /// replacement of a find-and-replace.
+    pub(crate) fn record_search(&mut self, pattern: &str, replacement: Option<&str>) {
+        let project = self.search_project();
+        self.search_history
+            .project_mut(project.as_deref())
+            .record(SavedSearch {
+                pattern: pattern.to_string(),
+                options: self.search_options,
+                replacement: replacement.map(str::to_string),
+            });
+        self.search_history_index = None;
+    }
+
+    /// Fill the Find prompt with an older (Up) or newer (Down) recent
+    /// search. Going newer than the newest clears the prompt.
+    pub(crate) fn recall_search(&mut self, older: bool) {
+        let count = self.project_searches().map_or(0, |s| s.recent.len());
+        let index = match (self.search_history_index, older) {
+            (None, true) if count > 0 => Some(0),
+            (Some(i), true) if i + 1 < count => Some(i + 1),
+            (Some(i), false) if i > 0 => Some(i - 1),
+            (Some(_), false) => None,
+            _ => return,
+        };
+        self.search_history_index = index;
+        let search = index.and_then(|i| {
+            self.project_searches()
+                .and_then(|s| s.recent.get(i))
+                .cloned()
+        });
+        match search {
+            Some(search) => self.fill_search_prompt(&search),
+            None => {
+                self.prompt_input.clear();
+                self.replace_input.clear();
+            }
+        }
+        if self.input_mode == InputMode::PromptFind {
+            self.incremental_search();
+        }
+    }
+
+    /// Put `search` into the prompt fields and search options.
+    fn fill_search_prompt(&mut self, search: &SavedSearch) {
+        self.prompt_input = search.pattern.clone();
+        self.search_options = search.options;
+        self.replace_input = search.replacement.clone().unwrap_or_default();
+    }
+
+    /// Open a picker of the project's search presets and recent searches.
+    pub(crate) fn cmd_search_presets(&mut self) {
+        let (presets, recent) = match self.project_searches() {
+            Some(searches) => (searches.presets.clone(), searches.recent.clone()),
+            None => Default::default(),
+        };
+        if presets.is_empty() && recent.is_empty() {
+            self.messages.info("No saved or recent searches");
+            return;
+        }
+        let mut items = Vec::new();
+        let mut searches = Vec::new();
+        for preset in presets {
+            let detail = format!("{} {}", preset.search.pattern, describe(&preset.search));
+            items.push(PickerItem::new(preset.name, detail.trim_end()));
+            searches.push(preset.search);
+        }
+        for search in recent {
+            items.push(PickerItem::new(search.pattern.clone(), describe(&search)));
+            searches.push(search);
+        }
+        let from_replace = self.input_mode == InputMode::PromptFindReplace;
+        self.search_picker = Some(SearchPicker {
+            picker: Picker::new("Searches", items),
+            searches,
+            from_replace,
+        });
+        self.input_mode = InputMode::SearchPicker;
+        self.prompt_input.clear();
+    }
+
+    /// Fill the Find prompt with the selected search. A search with a
+    /// replacement, or one picked from the replace prompt, opens the
+    /// find-and-replace prompt ready to run.
+    pub(crate) fn confirm_search_picker(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.prompt_input.clear();
+        let Some(state) = self.search_picker.take() else {
+            return;
+        };
+        let Some(search) = state.picker.selected().and_then(|i| state.searches.get(i)) else {
+            self.messages.info("No matching search");
+            return;
+        };
+        if !state.from_replace {
+            self.replace_interactive = false;
+        }
+        self.fill_search_prompt(search);
+        self.search_history_index = None;
+        if state.from_replace || search.replacement.is_some() {
+            self.input_mode = InputMode::PromptFindReplace;
+            self.replace_focused = search.replacement.is_some();
+        } else {
+            self.input_mode = InputMode::PromptFind;
+            self.incremental_search();
+        }
+    }
+
+    /// Ask for a name to save the most recent search under.
+    pub(crate) fn cmd_save_search_preset(&mut self) {
+        let has_search = self
+            .project_searches()
+            .is_some_and(|s| !s.recent.is_empty());
+        if !has_search {
+            self.messages.warn("No search to save yet");
+            return;
+        }
+        self.input_mode = InputMode::PromptSearchPresetName;
+        self.prompt_input.clear();
+    }
+
+    /// Save the most recent search as the preset `name`, replacing any
+    /// preset of that name.
+    pub(crate) fn confirm_save_search_preset(&mut self, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            self.messages.warn("Preset not saved — no name entered");
+            return;
+        }
+        let project = self.search_project();
+        let searches = self.search_history.project_mut(project.as_deref());
+        let Some(search) = searches.recent.first().cloned() else {
+            return;
+        };
+        let pattern = search.pattern.clone();
+        searches.save_preset(name, search);
+        self.messages
+            .info(format!("Saved search preset '{}': {}", name, pattern));
+    }
+}
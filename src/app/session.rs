@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use smash_core::session::{Session, SessionBuffer, SessionJump, SessionMark};
+use smash_tui::PaneTree;
+
+use super::{App, JumpLocation};
+
+/// `loc` as saved in a session; locations in unnamed buffers are not.
+fn session_jump(loc: &JumpLocation, workspace: Option<&Path>) -> Option<SessionJump> {
+    let path = loc.path.as_ref()?;
+    let path = match workspace {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.clone(),
+    };
+    Some(SessionJump {
+        path,
+        position: loc.position,
+    })
+}
+
+// =========================================================================
+// Session save / restore
+// =========================================================================
+
+impl App {
+    /// Snapshot the open file, cursor, scroll position, pane layout, jump
+    /// list, last edit and marks.
+    pub(crate) fn session(&self, workspace: Option<PathBuf>) -> Session {
+        let mut session = Session::new(workspace.clone());
+        // While a file is still loading, remember it rather than the
+        // placeholder.
+        let (path, cursor) = match &self.pending_open {
+            Some(pending) => (Some(pending.path()), Default::default()),
+            None => (
+                self.buffer.path(),
+                self.buffer.cursors().primary().position(),
+            ),
+        };
+        if let Some(path) = path {
+            let path = match &workspace {
+                Some(dir) if path.is_relative() => dir.join(path),
+                _ => path.to_path_buf(),
+            };
+            session.buffers.push(SessionBuffer {
+                path,
+                cursor,
+                top_line: self.viewport.top_line(),
+                left_col: self.viewport.left_col(),
+            });
+        }
+        session.panes = self.panes.to_layout();
+        session.active_pane = self.panes.active_pane();
+        session.jumps = self
+            .jump_stack
+            .locations()
+            .iter()
+            .filter_map(|loc| session_jump(loc, workspace.as_deref()))
+            .collect();
+        let last_edit = match self.buffer.last_edit() {
+            Some(pos) => Some(JumpLocation::new(
+                self.buffer.path().map(Path::to_path_buf),
+                pos,
+            )),
+            None => self.last_edit.clone(),
+        };
+        session.last_edit = last_edit.and_then(|loc| session_jump(&loc, workspace.as_deref()));
+        session.marks = self
+            .marks
+            .iter()
+            .filter_map(|(&name, loc)| {
+                let location = session_jump(loc, workspace.as_deref())?;
+                Some(SessionMark { name, location })
+            })
+            .collect();
+        session
+    }
+
+    /// Put the cursor, scroll position, panes, jump list and marks back as
+    /// they were. The session's active file must already be open.
+    pub(crate) fn restore_session(&mut self, session: &Session) {
+        self.panes = PaneTree::from_layout(&session.panes, session.active_pane);
+        let location =
+            |jump: &SessionJump| JumpLocation::new(Some(jump.path.clone()), jump.position);
+        self.jump_stack
+            .restore(session.jumps.iter().map(location).collect());
+        self.last_edit = session.last_edit.as_ref().map(location);
+        self.marks = session
+            .marks
+            .iter()
+            .map(|mark| (mark.name, location(&mark.location)))
+            .collect();
+        let Some(saved) = session.active() else {
+            return;
+        };
+        if self.buffer.path() != Some(saved.path.as_path()) {
+            return;
+        }
+        let pos = self.buffer.clamp_position(saved.cursor);
+        self.buffer.cursors_mut().primary_mut().set_position(pos);
+        let last = self.buffer.line_count().saturating_sub(1);
+        self.viewport
+            .set_top_line(saved.top_line.min(pos.line).min(last));
+        self.viewport.set_left_col(saved.left_col.min(pos.col));
+    }
+}
@@ -0,0 +1,337 @@
+use std::time::Duration;
+
+use smash_core::buffer::BufferId;
+use smash_core::links::find_links;
+use smash_core::position::{Position, Range};
+use smash_input::{Key, KeyEvent};
+use smash_platform::{ShellJob, ShellOutput};
+use smash_tui::ListState;
+use tracing::{info, warn};
+
+use super::App;
+
+/// How long a shell command may run before it is killed.
+const SHELL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where a shell command's output goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ShellTarget {
+    /// The results pane.
+    Pane,
+    /// In place of `text`, found at `range` and fed to the command.
+    Replace {
+        buffer: BufferId,
+        range: Range,
+        text: String,
+    },
+    /// At `pos`, where the cursor was.
+    Insert { buffer: BufferId, pos: Position },
+}
+
+/// A shell command still running, and where its output goes.
+pub(crate) struct ShellRun {
+    job: ShellJob,
+    target: ShellTarget,
+}
+
+/// Output of a shell command, shown in the results pane.
+pub(crate) struct ShellOutputView {
+    command: String,
+    /// How it exited, e.g. `exit 1`.
+    status: String,
+    /// The command, then its stdout and its stderr.
+    lines: Vec<String>,
+    pub(crate) list: ListState,
+}
+
+impl ShellOutputView {
+    fn new(command: &str, output: &ShellOutput) -> Self {
+        let lines = std::iter::once(format!("$ {}", command))
+            .chain(output.stdout.lines().map(str::to_string))
+            .chain(output.stderr.lines().map(str::to_string))
+            .collect();
+        Self {
+            command: command.to_string(),
+            status: exit_label(output),
+            lines,
+            list: ListState::default(),
+        }
+    }
+
+    pub(crate) fn title(&self) -> String {
+        format!(
+            "Output of {} ({}) — Enter: follow link",
+            self.command, self.status
+        )
+    }
+
+    pub(crate) fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
+
+/// `ok`, `exit N`, or `killed` when a signal ended the command.
+fn exit_label(output: &ShellOutput) -> String {
+    match output.code {
+        _ if output.success => "ok".to_string(),
+        Some(code) => format!("exit {}", code),
+        None => "killed".to_string(),
+    }
+}
+
+/// Drop one trailing line break, which commands add after their last line.
+fn strip_trailing_newline(text: &mut String) {
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+}
+
+// =========================================================================
+// Shell commands
+// =========================================================================
+
+impl App {
+    /// Run the command typed at the shell prompt. Plain `cmd` shows its
+    /// output in the results pane, `|cmd` replaces the selection (or the
+    /// cursor line) with the output of piping it through `cmd`, and
+    /// `<cmd` inserts the output at the cursor.
+    pub(crate) fn confirm_shell_command(&mut self, input: &str) {
+        let input = input.trim();
+        if let Some(command) = input.strip_prefix('|') {
+            let cursor = self.buffer.cursors().primary();
+            let range = match cursor.selection_range().filter(|r| !r.is_empty()) {
+                Some(range) => range,
+                None => {
+                    let line = cursor.position().line;
+                    self.line_span(line, line)
+                }
+            };
+            self.filter_range(range, command);
+        } else if let Some(command) = input.strip_prefix('<') {
+            if self.buffer.is_read_only() {
+                self.warn_read_only();
+                return;
+            }
+            let target = ShellTarget::Insert {
+                buffer: self.buffer.id(),
+                pos: self.buffer.cursors().primary().position(),
+            };
+            self.start_shell(command, target);
+        } else {
+            self.start_shell(input, ShellTarget::Pane);
+        }
+    }
+
+    /// Pipe lines `first..=last` through `command` and put its output in
+    /// their place, as vim's `:{range}!cmd`.
+    pub(crate) fn filter_lines(&mut self, first: usize, last: usize, command: &str) {
+        let range = self.line_span(first, last);
+        self.filter_range(range, command);
+    }
+
+    /// Run `command` with its output shown in the results pane.
+    pub(crate) fn run_shell_in_pane(&mut self, command: &str) {
+        self.start_shell(command, ShellTarget::Pane);
+    }
+
+    /// Lines `first..=last` with the line break after them, if any.
+    fn line_span(&self, first: usize, last: usize) -> Range {
+        let end = if last < self.last_line() {
+            Position::new(last + 1, 0)
+        } else {
+            Position::new(last, self.line_len(last))
+        };
+        Range::new(Position::new(first, 0), end)
+    }
+
+    fn filter_range(&mut self, range: Range, command: &str) {
+        if self.buffer.is_read_only() {
+            self.warn_read_only();
+            return;
+        }
+        let Ok(text) = self.buffer.text_in_range(range) else {
+            return;
+        };
+        let target = ShellTarget::Replace {
+            buffer: self.buffer.id(),
+            range,
+            text,
+        };
+        self.start_shell(command, target);
+    }
+
+    /// Start `command` in the background, replacing any shell command
+    /// still running.
+    fn start_shell(&mut self, command: &str, target: ShellTarget) {
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+        // Filters read whole lines, so the last one gets a line break.
+        let input = match &target {
+            ShellTarget::Replace { text, .. } => {
+                let mut input = text.replace("\r\n", "\n");
+                if !input.ends_with('\n') {
+                    input.push('\n');
+                }
+                Some(input)
+            }
+            _ => None,
+        };
+        match ShellJob::spawn(command, input.as_deref(), SHELL_TIMEOUT) {
+            Ok(job) => {
+                info!("shell: running `{}`", command);
+                if let Some(previous) = self.shell_run.take() {
+                    self.finish_shell_run(previous.job.command());
+                }
+                self.running_commands.push(command.to_string());
+                self.shell_run = Some(ShellRun { job, target });
+            }
+            Err(e) => {
+                warn!("shell: failed to spawn `{}`: {}", command, e);
+                self.messages
+                    .error(format!("Cannot run {}: {}", command, e));
+            }
+        }
+    }
+
+    fn finish_shell_run(&mut self, command: &str) {
+        if let Some(i) = self.running_commands.iter().position(|c| c == command) {
+            self.running_commands.remove(i);
+        }
+    }
+
+    /// Check on the running shell command and use its output once it
+    /// is done. Returns `true` if anything visible changed.
+    pub(crate) fn poll_shell(&mut self) -> bool {
+        let Some(result) = self.shell_run.as_mut().and_then(|run| run.job.poll()) else {
+            return false;
+        };
+        let Some(ShellRun { job, target }) = self.shell_run.take() else {
+            return false;
+        };
+        let command = job.command().to_string();
+        self.finish_shell_run(&command);
+        match result {
+            Ok(output) => {
+                info!("shell: `{}` finished: {}", command, exit_label(&output));
+                self.use_shell_output(&command, target, output);
+            }
+            Err(e) => {
+                warn!("shell: `{}` failed: {}", command, e);
+                self.messages.error(format!("{}: {}", command, e));
+            }
+        }
+        true
+    }
+
+    fn use_shell_output(&mut self, command: &str, target: ShellTarget, output: ShellOutput) {
+        if target != ShellTarget::Pane && !output.success {
+            // Keep the buffer as it was rather than fill it with an error.
+            let reason = output
+                .stderr
+                .lines()
+                .map(str::trim)
+                .find(|l| !l.is_empty())
+                .map_or_else(|| exit_label(&output), str::to_string);
+            self.messages
+                .error(format!("{} failed: {}", command, reason));
+            return;
+        }
+        match target {
+            ShellTarget::Pane => {
+                if output.success && output.stdout.is_empty() && output.stderr.is_empty() {
+                    self.messages
+                        .info(format!("{} finished with no output", command));
+                    return;
+                }
+                self.search_results = None;
+                self.call_hierarchy = None;
+                self.diff_view = None;
+                self.shell_output = Some(ShellOutputView::new(command, &output));
+                self.search_results_focused = true;
+                self.file_tree_focused = false;
+            }
+            ShellTarget::Replace {
+                buffer,
+                range,
+                text,
+            } => {
+                if self.buffer.id() != buffer
+                    || self.buffer.text_in_range(range).ok() != Some(text.clone())
+                {
+                    self.messages.warn(format!(
+                        "Text changed while {} ran; left it as it was",
+                        command
+                    ));
+                    return;
+                }
+                let mut replacement = output.stdout;
+                if !text.ends_with('\n') {
+                    strip_trailing_newline(&mut replacement);
+                }
+                self.buffer.cursors_mut().clear_secondary();
+                let cursor = self.buffer.cursors_mut().primary_mut();
+                cursor.set_anchor(range.start);
+                cursor.set_position(range.end);
+                self.insert_text(&replacement);
+                self.set_cursor(range.start);
+                self.messages.info(format!("Filtered through {}", command));
+            }
+            ShellTarget::Insert { buffer, pos } => {
+                if self.buffer.id() != buffer
+                    || pos.line > self.last_line()
+                    || pos.col > self.line_len(pos.line)
+                {
+                    self.messages.warn(format!(
+                        "Text changed while {} ran; left it as it was",
+                        command
+                    ));
+                    return;
+                }
+                let mut text = output.stdout;
+                strip_trailing_newline(&mut text);
+                self.buffer.cursors_mut().clear_secondary();
+                let cursor = self.buffer.cursors_mut().primary_mut();
+                cursor.clear_selection();
+                cursor.set_position(pos);
+                self.insert_text(&text);
+            }
+        }
+    }
+
+    /// Handle a key while the pane shows shell output and has focus.
+    pub(crate) fn handle_shell_output_key(&mut self, key: &KeyEvent) -> bool {
+        let Some(view) = self.shell_output.as_mut() else {
+            return false;
+        };
+        if view.list.handle_key(&key.key, view.lines.len()) {
+            return true;
+        }
+        if key.key == Key::Enter {
+            self.follow_shell_output_link();
+        }
+        true
+    }
+
+    /// Follow the first link on the selected line, such as a
+    /// `file:line` in compiler output. Focus goes back to the editor.
+    fn follow_shell_output_link(&mut self) {
+        let Some(view) = self.shell_output.as_ref() else {
+            return;
+        };
+        let line = view
+            .lines
+            .get(view.list.selected())
+            .map_or("", String::as_str);
+        let Some(link) = find_links(line).into_iter().next() else {
+            self.messages.info("No link on this line");
+            return;
+        };
+        self.search_results_focused = false;
+        self.follow_link(link.target);
+    }
+}
@@ -0,0 +1,109 @@
+use smash_input::Command;
+use smash_lsp::SignatureHelp;
+use smash_tui::Popup;
+
+use super::{App, InputMode};
+use crate::lsp_types::LspCommand;
+
+// =========================================================================
+// Signature help
+// =========================================================================
+
+impl App {
+    /// Ask for the signatures of the call around the cursor.
+    fn lsp_signature_help(&mut self) {
+        let Some(uri) = self.current_uri() else {
+            return;
+        };
+        let pos = self.buffer.cursors().primary().position();
+        self.signature_request = Some(pos);
+        let _ = self.lsp_cmd_tx.try_send(LspCommand::SignatureHelp {
+            uri,
+            position: self.lsp_position(pos),
+        });
+    }
+
+    /// Keep signature help in step with a command just handled in Normal
+    /// mode. Typing one of the server's trigger characters asks for it;
+    /// while it is open, further edits ask again so the highlighted
+    /// parameter follows the cursor, and any other command closes it.
+    pub(crate) fn update_signature_help(&mut self, cmd: &Command) {
+        if !self.lsp_server_started {
+            return;
+        }
+        let open = self.signature_help.is_some() || self.signature_request.is_some();
+        match cmd {
+            Command::InsertChar(c) if self.is_signature_trigger(*c) => self.lsp_signature_help(),
+            Command::InsertChar(_)
+            | Command::InsertNewline
+            | Command::DeleteBackward
+            | Command::DeleteForward
+                if open =>
+            {
+                self.lsp_signature_help();
+            }
+            _ => self.close_signature_help(),
+        }
+    }
+
+    fn is_signature_trigger(&self, c: char) -> bool {
+        let mut buf = [0; 4];
+        let c = &*c.encode_utf8(&mut buf);
+        self.signature_triggers.iter().any(|t| t == c)
+    }
+
+    pub(crate) fn close_signature_help(&mut self) {
+        self.signature_help = None;
+        self.signature_request = None;
+    }
+
+    pub(super) fn handle_signature_help_result(&mut self, help: Option<SignatureHelp>) {
+        // Only the answer to the latest request, while still typing there.
+        let pos = self.buffer.cursors().primary().position();
+        if self.input_mode != InputMode::Normal || self.signature_request != Some(pos) {
+            return;
+        }
+        if help.is_none() {
+            // The cursor left the call.
+            self.signature_request = None;
+        }
+        self.signature_help = help;
+    }
+
+    /// The active signature as a popup, its current parameter highlighted.
+    pub(crate) fn signature_popup(&self) -> Option<Popup> {
+        let help = self.signature_help.as_ref()?;
+        let (signature, parameter) = help.active()?;
+        let title = match help.signatures.len() {
+            1 => "Signature".to_string(),
+            n => {
+                let index = help
+                    .signatures
+                    .iter()
+                    .position(|s| std::ptr::eq(s, signature))
+                    .unwrap_or(0);
+                format!("Signature {}/{}", index + 1, n)
+            }
+        };
+        let param_doc = signature
+            .parameters
+            .get(parameter)
+            .and_then(|p| p.documentation.as_ref());
+        let lines = std::iter::once(signature.label.as_str())
+            .chain(param_doc.map_or("", |d| d.text()).lines())
+            .chain(
+                signature
+                    .documentation
+                    .as_ref()
+                    .map_or("", |d| d.text())
+                    .lines(),
+            )
+            .map(str::to_string)
+            .collect();
+        let popup = Popup::new(title, lines).above();
+        Some(match signature.parameter_range(parameter) {
+            Some(range) => popup.with_highlight(0, range),
+            None => popup,
+        })
+    }
+}
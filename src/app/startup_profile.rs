@@ -0,0 +1,120 @@
+use std::time::{Duration, Instant};
+
+use super::render_stats::millis;
+
+/// A step of startup timed for `--profile-startup` and the health view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StartupPhase {
+    ConfigLoad,
+    /// Platform paths, terminal and async runtime.
+    PlatformInit,
+    BufferOpen,
+    /// Compiling the first file's syntax highlighter.
+    Highlighter,
+    FirstRender,
+    /// From asking for the first file's language server until it has
+    /// initialized.
+    LspSpawn,
+}
+
+impl StartupPhase {
+    /// Every phase, in the order startup runs them.
+    pub(crate) const ALL: [StartupPhase; 6] = [
+        StartupPhase::ConfigLoad,
+        StartupPhase::PlatformInit,
+        StartupPhase::BufferOpen,
+        StartupPhase::Highlighter,
+        StartupPhase::FirstRender,
+        StartupPhase::LspSpawn,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            StartupPhase::ConfigLoad => "Config load",
+            StartupPhase::PlatformInit => "Platform init",
+            StartupPhase::BufferOpen => "Buffer open",
+            StartupPhase::Highlighter => "Highlighter build",
+            StartupPhase::FirstRender => "First render",
+            StartupPhase::LspSpawn => "LSP spawn",
+        }
+    }
+}
+
+/// How long each startup phase took. Only the first run of a phase is
+/// kept, so opening more files later does not overwrite it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StartupProfile {
+    phases: Vec<(StartupPhase, Duration)>,
+    /// When the first language server was asked for.
+    lsp_requested: Option<Instant>,
+}
+
+impl StartupProfile {
+    /// Note that `phase` took `took`, unless it was already recorded.
+    pub(crate) fn record(&mut self, phase: StartupPhase, took: Duration) {
+        if self.get(phase).is_none() {
+            self.phases.push((phase, took));
+        }
+    }
+
+    /// Run `f`, recording how long it took as `phase`.
+    pub(crate) fn time<T>(&mut self, phase: StartupPhase, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(phase, start.elapsed());
+        result
+    }
+
+    pub(crate) fn get(&self, phase: StartupPhase) -> Option<Duration> {
+        self.phases
+            .iter()
+            .find(|(p, _)| *p == phase)
+            .map(|(_, took)| *took)
+    }
+
+    /// Note that a language server was asked for at `now`.
+    pub(crate) fn lsp_requested(&mut self, now: Instant) {
+        self.lsp_requested.get_or_insert(now);
+    }
+
+    /// Note that a language server finished starting at `now`.
+    pub(crate) fn lsp_started(&mut self, now: Instant) {
+        if let Some(requested) = self.lsp_requested {
+            self.record(
+                StartupPhase::LspSpawn,
+                now.saturating_duration_since(requested),
+            );
+        }
+    }
+
+    /// One line per phase, then the total of those that have run.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = StartupPhase::ALL
+            .iter()
+            .map(|&phase| {
+                let took = match self.get(phase) {
+                    Some(took) => millis(took),
+                    None if phase == StartupPhase::LspSpawn && self.lsp_requested.is_some() => {
+                        "waiting".to_string()
+                    }
+                    None => "not run".to_string(),
+                };
+                format!("{}: {}", phase.label(), took)
+            })
+            .collect();
+        let total: Duration = self.phases.iter().map(|(_, took)| *took).sum();
+        lines.push(format!("Total: {}", millis(total)));
+        lines
+    }
+
+    /// The profile as printed by `--profile-startup`.
+    pub(crate) fn report(&self) -> String {
+        let mut report = String::from("smash startup profile\n");
+        for line in self.lines() {
+            report.push_str("  ");
+            report.push_str(&line);
+            report.push('\n');
+        }
+        report
+    }
+}
@@ -0,0 +1,146 @@
+use smash_lsp::SymbolInformation;
+use smash_tui::{Picker, PickerItem};
+
+use super::{App, InputMode};
+use crate::lsp_types::LspCommand;
+
+/// The symbol picker overlay and the symbols behind its items.
+pub(crate) struct SymbolPicker {
+    pub(crate) picker: Picker,
+    /// Same order as the picker items.
+    symbols: Vec<SymbolInformation>,
+    /// Whether each query is sent to the server (`workspace/symbol`)
+    /// instead of only filtering the symbols already listed.
+    pub(super) workspace: bool,
+}
+
+/// Picker row for `symbol`: its name, then kind, container and location.
+/// The file is only named for workspace symbols.
+fn symbol_item(symbol: &SymbolInformation, workspace: bool) -> PickerItem {
+    let line = symbol.location.range.start.line + 1;
+    let mut detail = symbol.kind.label().to_string();
+    if let Some(container) = symbol.container_name.as_deref().filter(|c| !c.is_empty()) {
+        detail.push_str(" in ");
+        detail.push_str(container);
+    }
+    if workspace {
+        let path = App::uri_to_path(&symbol.location.uri);
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let shown = path.strip_prefix(&cwd).unwrap_or(&path);
+        detail.push_str(&format!(" · {}:{}", shown.display(), line));
+    } else {
+        detail.push_str(&format!(" · line {}", line));
+    }
+    PickerItem::new(symbol.name.clone(), detail)
+}
+
+// =========================================================================
+// Symbol picker
+// =========================================================================
+
+impl App {
+    /// Ask the server for the symbols in the current file; the picker
+    /// opens when they arrive.
+    pub(crate) fn lsp_document_symbols(&mut self) {
+        if !self.lsp_server_started {
+            self.messages.warn("No LSP server running");
+            return;
+        }
+        if let Some(uri) = self.current_uri() {
+            let _ = self
+                .lsp_cmd_tx
+                .try_send(LspCommand::DocumentSymbols { uri });
+        }
+    }
+
+    /// Open the workspace symbol picker. Every change to the query asks
+    /// the server again.
+    pub(crate) fn lsp_workspace_symbols(&mut self) {
+        if !self.lsp_server_started {
+            self.messages.warn("No LSP server running");
+            return;
+        }
+        self.symbol_picker = Some(SymbolPicker {
+            picker: Picker::new("Workspace symbols", Vec::new()),
+            symbols: Vec::new(),
+            workspace: true,
+        });
+        self.input_mode = InputMode::SymbolPicker;
+        self.prompt_input.clear();
+        let _ = self.lsp_cmd_tx.try_send(LspCommand::WorkspaceSymbols {
+            query: String::new(),
+            language_id: self.language_id.clone(),
+        });
+    }
+
+    pub(super) fn handle_document_symbols_result(&mut self, symbols: Vec<SymbolInformation>) {
+        // The user moved on to something else while waiting.
+        if self.input_mode != InputMode::Normal {
+            return;
+        }
+        if symbols.is_empty() {
+            self.messages.info("No symbols found");
+            return;
+        }
+        let items = symbols.iter().map(|s| symbol_item(s, false)).collect();
+        let title = match &self.filename {
+            Some(name) => format!("Symbols in {}", name),
+            None => "Symbols".to_string(),
+        };
+        self.symbol_picker = Some(SymbolPicker {
+            picker: Picker::new(title, items),
+            symbols,
+            workspace: false,
+        });
+        self.input_mode = InputMode::SymbolPicker;
+        self.prompt_input.clear();
+    }
+
+    pub(super) fn handle_workspace_symbols_result(
+        &mut self,
+        query: String,
+        symbols: Vec<SymbolInformation>,
+    ) {
+        if self.input_mode != InputMode::SymbolPicker || query != self.prompt_input {
+            return;
+        }
+        let Some(state) = self.symbol_picker.as_mut().filter(|s| s.workspace) else {
+            return;
+        };
+        state
+            .picker
+            .set_items(symbols.iter().map(|s| symbol_item(s, true)).collect());
+        state.symbols = symbols;
+    }
+
+    /// Ask the server for workspace symbols matching the query typed so
+    /// far. Document symbols are only filtered locally.
+    pub(super) fn requery_workspace_symbols(&mut self) {
+        if self.symbol_picker.as_ref().is_some_and(|s| s.workspace) {
+            let _ = self.lsp_cmd_tx.try_send(LspCommand::WorkspaceSymbols {
+                query: self.prompt_input.clone(),
+                language_id: self.language_id.clone(),
+            });
+        }
+    }
+
+    /// Jump to the selected symbol, opening its file if needed.
+    pub(crate) fn confirm_symbol_picker(&mut self) {
+        let Some(state) = self.symbol_picker.take() else {
+            return;
+        };
+        let Some(symbol) = state.picker.selected().and_then(|i| state.symbols.get(i)) else {
+            self.messages.info("No matching symbol");
+            return;
+        };
+        let target = self.location_position(&symbol.location.uri, symbol.location.range.start);
+        self.push_jump();
+        if self.is_current_uri(&symbol.location.uri) {
+            let cursor = self.buffer.cursors_mut().primary_mut();
+            cursor.clear_selection();
+            cursor.set_position(target);
+        } else {
+            self.open_path(Self::uri_to_path(&symbol.location.uri), Some(target), false);
+        }
+    }
+}
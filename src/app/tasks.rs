@@ -0,0 +1,163 @@
+use smash_core::quickfix::{parse_problems, Problem, Severity};
+use smash_lsp::DiagnosticSeverity;
+use tracing::info;
+
+use super::{App, InputMode};
+use crate::job::{JobStatus, OutputJob};
+
+/// A task from the config, run in the background with its output shown
+/// in the task pane.
+pub(crate) struct TaskRun {
+    name: String,
+    job: OutputJob,
+}
+
+impl TaskRun {
+    pub(crate) fn output(&self) -> &[String] {
+        self.job.output()
+    }
+
+    /// Short status for the statusline, e.g. `task build: ok`.
+    pub(crate) fn status_label(&self) -> String {
+        format!("task {}: {}", self.name, self.job.status().label())
+    }
+}
+
+/// `3 errors, 1 warning`, leaving out what there is none of.
+fn problem_counts(problems: &[Problem]) -> String {
+    let count = |severity| problems.iter().filter(|p| p.severity == severity).count();
+    let plural = |n: usize, what: &str| match n {
+        1 => format!("1 {}", what),
+        n => format!("{} {}s", n, what),
+    };
+    let parts: Vec<String> = [(Severity::Error, "error"), (Severity::Warning, "warning")]
+        .into_iter()
+        .map(|(severity, what)| (count(severity), what))
+        .filter(|&(n, _)| n > 0)
+        .map(|(n, what)| plural(n, what))
+        .collect();
+    if parts.is_empty() {
+        plural(problems.len(), "note")
+    } else {
+        parts.join(", ")
+    }
+}
+
+// =========================================================================
+// Tasks
+// =========================================================================
+
+impl App {
+    /// Ask which task to run, offering the last one again.
+    pub(crate) fn cmd_run_task(&mut self) {
+        if self.tasks.is_empty() {
+            self.messages
+                .warn("No tasks configured; add them under [tasks] in the config");
+            return;
+        }
+        self.input_mode = InputMode::PromptTask;
+        self.prompt_input = match &self.task {
+            Some(task) => task.name.clone(),
+            None if self.tasks.len() == 1 => self.tasks.keys().next().cloned().unwrap_or_default(),
+            None => String::new(),
+        };
+    }
+
+    /// Run the task named `input`. Empty input stops the running task
+    /// and closes the task pane.
+    pub(crate) fn confirm_task(&mut self, input: &str) {
+        let name = input.trim();
+        if name.is_empty() {
+            if self.task.take().is_some() {
+                self.messages.info("Task pane closed");
+            }
+            return;
+        }
+        let Some(command) = self.tasks.get(name) else {
+            let names: Vec<&str> = self.tasks.keys().map(String::as_str).collect();
+            self.messages.error(format!(
+                "No task named {} (tasks: {})",
+                name,
+                names.join(", ")
+            ));
+            return;
+        };
+        info!("task {}: `{}`", name, command);
+        let mut job = OutputJob::new(command.clone());
+        job.start();
+        self.task = Some(TaskRun {
+            name: name.to_string(),
+            job,
+        });
+    }
+
+    /// Drain task output. Once the task finishes, the problems in its
+    /// output go to the quickfix list. Returns `true` if anything visible
+    /// changed.
+    pub(crate) fn poll_task(&mut self) -> bool {
+        let Some(task) = self.task.as_mut() else {
+            return false;
+        };
+        let was_running = task.job.status() == JobStatus::Running;
+        let changed = task.job.poll();
+        let status = task.job.status();
+        if !was_running || status == JobStatus::Running {
+            return changed;
+        }
+        let name = task.name.clone();
+        let problems = parse_problems(task.output().iter().map(String::as_str));
+        if !problems.is_empty() {
+            self.show_quickfix(&format!("Task {}", name), &problems, false);
+        }
+        match (status, problems.is_empty()) {
+            (JobStatus::Succeeded, true) => self.messages.info(format!("Task {} succeeded", name)),
+            (JobStatus::Succeeded, false) => self.messages.warn(format!(
+                "Task {} succeeded with {}",
+                name,
+                problem_counts(&problems)
+            )),
+            (_, true) => self
+                .messages
+                .error(format!("Task {} {}", name, status.label())),
+            (_, false) => self.messages.error(format!(
+                "Task {} {}: {}",
+                name,
+                status.label(),
+                problem_counts(&problems)
+            )),
+        }
+        true
+    }
+
+    /// List the current file's diagnostics in the quickfix pane.
+    pub(crate) fn cmd_show_diagnostics(&mut self) {
+        if self.current_diagnostics.is_empty() {
+            self.messages.info("No diagnostics");
+            return;
+        }
+        let Some(path) = self.buffer.path() else {
+            return;
+        };
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let path = path.strip_prefix(&cwd).unwrap_or(path).to_string_lossy();
+        let mut problems: Vec<Problem> = self
+            .current_diagnostics
+            .iter()
+            .map(|d| Problem {
+                path: path.to_string(),
+                line: d.range.start.line as usize + 1,
+                col: Some(d.range.start.character as usize + 1),
+                severity: match d.severity {
+                    Some(DiagnosticSeverity::Warning) => Severity::Warning,
+                    Some(DiagnosticSeverity::Information | DiagnosticSeverity::Hint) => {
+                        Severity::Note
+                    }
+                    _ => Severity::Error,
+                },
+                message: d.message.lines().next().unwrap_or_default().to_string(),
+            })
+            .collect();
+        problems.sort_by_key(|p| (p.line, p.col));
+        self.show_quickfix("Diagnostics", &problems, true);
+    }
+}
@@ -0,0 +1,239 @@
+use std::path::PathBuf;
+
+use smash_input::{Command, Key, KeyEvent};
+use smash_terminal::{
+    ProcessStatus, Pty, Signal, TerminalError, TerminalEvent, TerminalPane, TerminalSize,
+};
+use smash_tui::Rect;
+use tracing::{info, warn};
+
+use super::{App, InputMode};
+
+// =========================================================================
+// Integrated terminal
+// =========================================================================
+
+impl App {
+    /// Show the terminal pane (starting a shell if needed) or hide it.
+    pub(crate) fn cmd_toggle_terminal(&mut self) {
+        self.file_tree_focused = false;
+        self.search_results_focused = false;
+        if self.terminal.is_some() {
+            self.terminal_visible = !self.terminal_visible;
+            return;
+        }
+        self.open_terminal();
+    }
+
+    /// Replace any running terminal with a fresh shell.
+    pub(crate) fn cmd_new_terminal(&mut self) {
+        if let Some(mut old) = self.terminal.take() {
+            let _ = old.close();
+        }
+        self.open_terminal();
+    }
+
+    /// Send `signal` to the terminal's process.
+    pub(crate) fn cmd_terminal_signal(&mut self, signal: Signal) {
+        let Some(term) = self.terminal.as_mut() else {
+            self.messages.warn("No terminal running");
+            return;
+        };
+        match term.signal(signal) {
+            Ok(()) => self
+                .messages
+                .info(format!("Sent {} to the terminal", signal.name())),
+            Err(TerminalError::Exited(_)) => self.messages.warn("The terminal has exited"),
+            Err(e) => self
+                .messages
+                .error(format!("Cannot signal the terminal: {}", e)),
+        }
+    }
+
+    /// Start a fresh shell in the terminal pane, keeping its size, and
+    /// show it.
+    pub(crate) fn cmd_terminal_respawn(&mut self) {
+        let Some(size) = self.terminal.as_ref().map(|t| t.grid().size()) else {
+            self.open_terminal();
+            return;
+        };
+        let result = self
+            .spawn_pty(size)
+            .and_then(|pty| match self.terminal.as_mut() {
+                Some(term) => term.respawn(pty),
+                None => Ok(()),
+            });
+        match result {
+            Ok(()) => {
+                info!("terminal respawned");
+                self.terminal_visible = true;
+                self.file_tree_focused = false;
+                self.search_results_focused = false;
+            }
+            Err(e) => {
+                self.messages
+                    .error(format!("Cannot restart terminal: {}", e));
+                warn!("terminal respawn failed: {}", e);
+            }
+        }
+    }
+
+    fn open_terminal(&mut self) {
+        let size = TerminalSize::new(
+            self.viewport.visible_cols().max(1) as u16,
+            (self.viewport.visible_lines() / 3).max(3) as u16,
+        );
+        match self.spawn_pty(size) {
+            Ok(pty) => {
+                info!("terminal started");
+                self.terminal = Some(TerminalPane::new(pty, size));
+                self.terminal_visible = true;
+            }
+            Err(e) => {
+                self.messages.error(format!("Cannot start terminal: {}", e));
+                warn!("terminal spawn failed: {}", e);
+            }
+        }
+    }
+
+    fn spawn_pty(&self, size: TerminalSize) -> Result<Box<dyn Pty>, TerminalError> {
+        let shell = self.terminal_shell_path();
+        let os = smash_platform::SystemInfo::detect();
+        smash_terminal::spawn_native(os.os(), &shell, &[], size)
+    }
+
+    /// Shell from `terminal_shell` in the config, else the platform default.
+    pub(super) fn terminal_shell_path(&self) -> PathBuf {
+        use smash_platform::paths::{DefaultPaths, PlatformPaths};
+
+        match &self.terminal_shell {
+            Some(shell) => PathBuf::from(shell),
+            None => DefaultPaths::new()
+                .map(|p| p.default_shell())
+                .unwrap_or_else(|_| PathBuf::from("/bin/sh")),
+        }
+    }
+
+    /// Whether keystrokes currently go to the terminal.
+    pub(crate) fn terminal_focused(&self) -> bool {
+        self.terminal_visible
+            && self.terminal.is_some()
+            && self.input_mode == InputMode::Normal
+            && !self.file_tree_focused()
+            && !self.search_results_focused()
+    }
+
+    /// Forward a key to the focused terminal. Returns `false` if the key
+    /// should be handled by the editor instead (no terminal focused, or
+    /// the key is bound to a terminal command). Once the shell has
+    /// exited, Enter restarts it and other keys are dropped.
+    pub(crate) fn handle_terminal_key(&mut self, key: &KeyEvent) -> bool {
+        if !self.terminal_focused() {
+            return false;
+        }
+        let pane_command = matches!(
+            self.resolver.keymap().resolve(std::slice::from_ref(key)),
+            Some(
+                Command::ToggleTerminal
+                    | Command::TerminalInterrupt
+                    | Command::TerminalTerminate
+                    | Command::TerminalKill
+                    | Command::TerminalRespawn
+            )
+        );
+        if pane_command {
+            return false;
+        }
+        let Some(term) = self.terminal.as_mut() else {
+            return false;
+        };
+        if term.status() != ProcessStatus::Running {
+            if key.key == Key::Enter && !key.modifiers.ctrl() && !key.modifiers.alt() {
+                self.cmd_terminal_respawn();
+            }
+            return true;
+        }
+        if let Err(e) = term.send_key(key) {
+            warn!("terminal write failed: {}", e);
+        }
+        true
+    }
+
+    /// Title bar text for the terminal pane: what runs in it and whether
+    /// it is still running.
+    pub(crate) fn terminal_title(&self) -> String {
+        let Some(term) = self.terminal.as_ref() else {
+            return String::new();
+        };
+        let name = match term.title() {
+            Some(title) if !title.is_empty() => title.to_string(),
+            _ => self
+                .terminal_shell_path()
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "shell".to_string()),
+        };
+        match term.status() {
+            ProcessStatus::Running => format!("Terminal: {}", name),
+            status => format!("Terminal: {} ({}, press Enter to restart)", name, status),
+        }
+    }
+
+    /// Drain terminal output and detect process exit. Returns `true` if
+    /// the screen needs a redraw.
+    pub(crate) fn poll_terminal(&mut self) -> bool {
+        let Some(term) = self.terminal.as_mut() else {
+            return false;
+        };
+        match term.process_output() {
+            Ok(events) => {
+                for event in events {
+                    if let TerminalEvent::Exited(code) = event {
+                        info!("terminal reported exit {}", code);
+                    }
+                }
+            }
+            // Reads fail once the shell has gone; that is reported below.
+            Err(e) if term.is_alive() => warn!("terminal read failed: {}", e),
+            Err(_) => {}
+        }
+        let skipped = term.take_skipped();
+        if skipped > 0 {
+            self.messages.warn(format!(
+                "Terminal output suppressed, {} bytes skipped",
+                skipped
+            ));
+        }
+        let Some(term) = self.terminal.as_mut() else {
+            return true;
+        };
+        let changed = term.take_dirty();
+        // The pane stays up with its last output; the title bar shows
+        // the exit status until the shell is restarted.
+        if let Some(code) = term.take_exit() {
+            self.messages
+                .info(format!("Terminal exited with code {}", code));
+            return true;
+        }
+        changed
+    }
+
+    /// Whether the terminal has read output it has not drawn yet.
+    pub(crate) fn terminal_backlogged(&self) -> bool {
+        self.terminal
+            .as_ref()
+            .is_some_and(TerminalPane::has_pending_output)
+    }
+
+    /// Match the terminal grid to the area it is drawn in.
+    pub(crate) fn resize_terminal(&mut self, area: Rect) {
+        if let Some(term) = self.terminal.as_mut() {
+            let size = TerminalSize::new(area.width, area.height);
+            if term.grid().size() != size {
+                if let Err(e) = term.resize(size) {
+                    warn!("terminal resize failed: {}", e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use smash_tui::{
+    builtin_theme, find_theme, theme_names, themes_dir, Picker, PickerItem, Theme, TuiError,
+    BUILTIN_THEMES,
+};
+
+use super::{App, InputMode};
+
+/// The theme picker overlay and the theme names behind its items.
+pub(crate) struct ThemePicker {
+    pub(crate) picker: Picker,
+    /// Same order as the picker items.
+    names: Vec<String>,
+}
+
+// =========================================================================
+// Themes
+// =========================================================================
+
+impl App {
+    /// The theme called `name`, from the config directory's `themes/`
+    /// files or the built-in themes.
+    pub(crate) fn find_theme(&self, name: &str) -> Result<Theme, TuiError> {
+        match self.themes_dir() {
+            Some(dir) => find_theme(name, &dir),
+            None => builtin_theme(name)
+                .ok_or_else(|| TuiError::Theme(format!("unknown theme '{}'", name))),
+        }
+    }
+
+    fn themes_dir(&self) -> Option<PathBuf> {
+        self.config_paths
+            .as_ref()
+            .map(|paths| themes_dir(&paths.config_dir))
+    }
+
+    /// Open a picker of every available theme.
+    pub(crate) fn cmd_select_theme(&mut self) {
+        let names = match self.themes_dir() {
+            Some(dir) => theme_names(&dir),
+            None => BUILTIN_THEMES.iter().map(|n| n.to_string()).collect(),
+        };
+        let items = names
+            .iter()
+            .map(|name| {
+                let detail = if *name == self.theme_name {
+                    "current"
+                } else {
+                    ""
+                };
+                PickerItem::new(name.clone(), detail)
+            })
+            .collect();
+        self.theme_picker = Some(ThemePicker {
+            picker: Picker::new("Themes", items),
+            names,
+        });
+        self.input_mode = InputMode::ThemePicker;
+        self.prompt_input.clear();
+    }
+
+    /// Switch to the selected theme for this session.
+    pub(crate) fn confirm_theme_picker(&mut self) {
+        let Some(state) = self.theme_picker.take() else {
+            return;
+        };
+        let Some(name) = state.picker.selected().and_then(|i| state.names.get(i)) else {
+            self.messages.info("No matching theme");
+            return;
+        };
+        match self.find_theme(name) {
+            Ok(theme) => {
+                self.theme = theme;
+                self.theme_name = name.clone();
+                self.messages.info(format!("Theme: {}", name));
+            }
+            Err(e) => self.messages.error(e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use smash_core::cursor::Cursor;
+use smash_core::edit::EditCommand;
+use smash_core::position::{Position, Range};
+
+use super::completion::is_word_char;
+use super::App;
+
+/// A change of letter case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TextCase {
+    Upper,
+    Lower,
+    /// First letter of each word upper case, the rest lower case.
+    Title,
+}
+
+impl TextCase {
+    fn apply(self, text: &str) -> String {
+        match self {
+            TextCase::Upper => text.to_uppercase(),
+            TextCase::Lower => text.to_lowercase(),
+            TextCase::Title => title_case(text),
+        }
+    }
+}
+
+fn title_case(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_word = false;
+    for c in text.chars() {
+        if in_word {
+            out.extend(c.to_lowercase());
+        } else {
+            out.extend(c.to_uppercase());
+        }
+        in_word = is_word_char(c);
+    }
+    out
+}
+
+/// Where `text` inserted at `start` ends.
+fn end_of(start: Position, text: &str) -> Position {
+    match text.rsplit_once('\n') {
+        Some((before, last)) => Position::new(
+            start.line + before.matches('\n').count() + 1,
+            last.chars().count(),
+        ),
+        None => Position::new(start.line, start.col + text.chars().count()),
+    }
+}
+
+/// The words around `col` swapped, Emacs style: the word the cursor is in
+/// or after (at the start of a line, the first word), and the next one. Returns the start and end column of the
+/// text replaced and its replacement.
+fn swap_words(line: &[char], col: usize) -> Option<(usize, usize, String)> {
+    let col = col.min(line.len());
+    let word_end = |mut i: usize| {
+        while i < line.len() && is_word_char(line[i]) {
+            i += 1;
+        }
+        i
+    };
+    let next_word = |mut i: usize| {
+        while i < line.len() && !is_word_char(line[i]) {
+            i += 1;
+        }
+        (i < line.len()).then_some(i)
+    };
+    let (first_start, second_start) = if col > 0 && is_word_char(line[col - 1]) {
+        let mut start = col - 1;
+        while start > 0 && is_word_char(line[start - 1]) {
+            start -= 1;
+        }
+        (start, next_word(word_end(col))?)
+    } else {
+        let next = next_word(col)?;
+        let mut end = next;
+        while end > 0 && !is_word_char(line[end - 1]) {
+            end -= 1;
+        }
+        if end == 0 {
+            // No word before: the next word is the first of the pair.
+            (next, next_word(word_end(next))?)
+        } else {
+            let mut start = end - 1;
+            while start > 0 && is_word_char(line[start - 1]) {
+                start -= 1;
+            }
+            (start, next)
+        }
+    };
+    let first_end = word_end(first_start);
+    let second_end = word_end(second_start);
+    let text: String = line[second_start..second_end]
+        .iter()
+        .chain(&line[first_end..second_start])
+        .chain(&line[first_start..first_end])
+        .collect();
+    Some((first_start, second_end, text))
+}
+
+// =========================================================================
+// Case changes and transposing
+// =========================================================================
+
+impl App {
+    /// Change the case of each cursor's selection, or of the word it is
+    /// on when nothing is selected, as one undo step.
+    pub(crate) fn cmd_change_case(&mut self, case: TextCase) {
+        self.buffer.begin_undo_group();
+        let step = |app: &mut Self| app.change_case_at_primary(case);
+        if !self.for_each_cursor(step) {
+            step(self);
+        }
+        self.buffer.end_undo_group();
+        self.lsp_did_change();
+    }
+
+    fn change_case_at_primary(&mut self, case: TextCase) {
+        let cursor = self.buffer.cursors().primary().clone();
+        let selection = cursor.selection_range().filter(|r| !r.is_empty());
+        let Some(range) = selection.or_else(|| self.word_range_at(cursor.position())) else {
+            return;
+        };
+        let Ok(text) = self.buffer.text_in_range(range) else {
+            return;
+        };
+        let changed = case.apply(&text);
+        if changed == text {
+            return;
+        }
+        let end = end_of(range.start, &changed);
+        let edit = EditCommand::Replace {
+            range,
+            text: changed,
+        };
+        if self.apply_edit(edit).is_err() {
+            return;
+        }
+        // Keep the selection on the changed text, which can be longer
+        // (`ß` is `SS` in upper case).
+        let restored = match cursor.anchor() {
+            Some(anchor) if selection.is_some() && anchor <= cursor.position() => {
+                Cursor::with_selection(end, range.start)
+            }
+            Some(_) if selection.is_some() => Cursor::with_selection(range.start, end),
+            _ => Cursor::new(self.buffer.clamp_position(cursor.position())),
+        };
+        *self.buffer.cursors_mut().primary_mut() = restored;
+    }
+
+    /// Swap the characters before and at the cursor and move past them;
+    /// at the end of a line, swap the two before it.
+    pub(crate) fn cmd_transpose_chars(&mut self) {
+        self.collapse_cursors();
+        let pos = self.buffer.cursors().primary().position();
+        let len = self.line_len(pos.line);
+        let col = if pos.col >= len { len } else { pos.col + 1 };
+        if col < 2 {
+            self.messages.info("Nothing to transpose");
+            return;
+        }
+        let range = Range::new(
+            Position::new(pos.line, col - 2),
+            Position::new(pos.line, col),
+        );
+        let Ok(text) = self.buffer.text_in_range(range) else {
+            return;
+        };
+        let swapped: String = text.chars().rev().collect();
+        if self
+            .apply_edit(EditCommand::Replace {
+                range,
+                text: swapped,
+            })
+            .is_ok()
+        {
+            self.set_cursor(range.end);
+            self.lsp_did_change();
+        }
+    }
+
+    /// Swap the word the cursor is in or after with the next word on the
+    /// line, and move past both.
+    pub(crate) fn cmd_transpose_words(&mut self) {
+        self.collapse_cursors();
+        let pos = self.buffer.cursors().primary().position();
+        let line: Vec<char> = self
+            .buffer
+            .line(pos.line)
+            .map(|l| l.chars().take(self.line_len(pos.line)).collect())
+            .unwrap_or_default();
+        let Some((start, end, text)) = swap_words(&line, pos.col) else {
+            self.messages.info("No words to transpose");
+            return;
+        };
+        let range = Range::new(Position::new(pos.line, start), Position::new(pos.line, end));
+        if self
+            .apply_edit(EditCommand::Replace { range, text })
+            .is_ok()
+        {
+            self.set_cursor(range.end);
+            self.lsp_did_change();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swapped(line: &str, col: usize) -> Option<String> {
+        let chars: Vec<char> = line.chars().collect();
+        swap_words(&chars, col).map(|(start, end, text)| {
+            let mut out: String = chars[..start].iter().collect();
+            out.push_str(&text);
+            out.extend(&chars[end..]);
+            out
+        })
+    }
+
+    #[test]
+    fn title_case_capitalises_each_word() {
+        assert_eq!(
+            title_case("hello WORLD, foo_bar 2nd"),
+            "Hello World, Foo_bar 2nd"
+        );
+    }
+
+    #[test]
+    fn swap_words_takes_the_word_at_or_before_the_cursor_and_the_next() {
+        assert_eq!(
+            swapped("one two three", 1).as_deref(),
+            Some("two one three")
+        );
+        assert_eq!(
+            swapped("one two three", 3).as_deref(),
+            Some("two one three")
+        );
+        // At the start of a word it is the second of the pair.
+        assert_eq!(
+            swapped("one two three", 4).as_deref(),
+            Some("two one three")
+        );
+        assert_eq!(swapped("one, two", 0).as_deref(), Some("two, one"));
+        assert_eq!(swapped("one two", 6), None);
+        assert_eq!(swapped("  one two", 0).as_deref(), Some("  two one"));
+        assert_eq!(swapped("  one", 0), None);
+    }
+}
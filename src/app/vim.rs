@@ -0,0 +1,498 @@
+use smash_core::edit::EditCommand;
+use smash_core::position::{Position, Range};
+use smash_core::registers::RegisterContent;
+use smash_input::vim::{InsertAt, Motion, Operator};
+use smash_input::{Command, Key, KeyEvent, VimAction, VimMode};
+
+use super::{line_content_len, App, InputMode};
+
+// =========================================================================
+// Vim modal editing
+// =========================================================================
+
+impl App {
+    /// Handle a key when the `vim` preset is active. Returns `false` if
+    /// the key should go through the regular keymap instead (Insert mode,
+    /// prompts, and modified keys such as Ctrl-S in Normal mode).
+    pub(crate) fn handle_vim_key(&mut self, key: &KeyEvent) -> bool {
+        let Some(vim) = self.vim.as_mut() else {
+            return false;
+        };
+        if self.input_mode != InputMode::Normal {
+            return false;
+        }
+        match vim.mode() {
+            // Left the command line (confirmed or cancelled).
+            VimMode::CommandLine => vim.set_mode(VimMode::Normal),
+            VimMode::Insert => {
+                if key.key != Key::Esc {
+                    return false;
+                }
+                vim.set_mode(VimMode::Normal);
+                let pos = self.buffer.cursors().primary().position();
+                if pos.col > 0 {
+                    self.set_cursor(self.buffer.prev_grapheme(pos));
+                }
+                return true;
+            }
+            _ => {}
+        }
+        let action = vim.feed(key);
+        if action == VimAction::Passthrough {
+            return false;
+        }
+        self.active_register = vim.register();
+        self.run_vim_action(action);
+        self.active_register = None;
+        true
+    }
+
+    /// Status bar label such as ` [NORMAL]` or ` [NORMAL 2d]`.
+    pub(crate) fn vim_status(&self) -> String {
+        let Some(vim) = self.vim.as_ref() else {
+            return String::new();
+        };
+        let pending = vim.pending_keys();
+        if pending.is_empty() {
+            format!(" [{}]", vim.mode().label())
+        } else {
+            format!(" [{} {}]", vim.mode().label(), pending)
+        }
+    }
+
+    fn set_vim_mode(&mut self, mode: VimMode) {
+        if let Some(vim) = self.vim.as_mut() {
+            vim.set_mode(mode);
+        }
+    }
+
+    fn vim_mode(&self) -> VimMode {
+        self.vim.as_ref().map(|v| v.mode()).unwrap_or_default()
+    }
+
+    fn run_vim_action(&mut self, action: VimAction) {
+        match action {
+            VimAction::Move(motion, count) => {
+                let target = self.motion_target(motion, count);
+                self.set_cursor(target);
+                if self.vim_mode().is_visual() {
+                    self.show_visual_selection();
+                }
+            }
+            VimAction::Operate(op, motion, count) => self.operate_motion(op, motion, count),
+            VimAction::OperateLines(op, count) => {
+                let first = self.buffer.cursors().primary().position().line;
+                let last = (first + count - 1).min(self.last_line());
+                self.operate_lines(op, first, last);
+            }
+            VimAction::OperateSelection(op) => {
+                let anchor = self.visual_anchor;
+                let head = self.buffer.cursors().primary().position();
+                let linewise = self.vim_mode() == VimMode::VisualLine;
+                self.exit_visual();
+                if linewise {
+                    self.operate_lines(op, anchor.line.min(head.line), anchor.line.max(head.line));
+                } else {
+                    let (start, end) = ordered(anchor, head);
+                    let end = self.next_char(end);
+                    self.operate_range(op, start, end);
+                }
+            }
+            VimAction::Insert(at) => self.vim_insert(at),
+            VimAction::DeleteChar(count) => {
+                let pos = self.buffer.cursors().primary().position();
+                let len = self.line_len(pos.line);
+                if pos.col < len {
+                    let end = Position::new(pos.line, (pos.col + count).min(len));
+                    self.operate_range(Operator::Delete, pos, end);
+                }
+            }
+            VimAction::Paste { before, count } => self.vim_paste(before, count),
+            VimAction::Undo(count) => {
+                for _ in 0..count {
+                    self.handle_command(Command::Undo);
+                }
+            }
+            VimAction::Redo(count) => {
+                for _ in 0..count {
+                    self.handle_command(Command::Redo);
+                }
+            }
+            VimAction::Visual(linewise) => {
+                let mode = if linewise {
+                    VimMode::VisualLine
+                } else {
+                    VimMode::Visual
+                };
+                let current = self.vim_mode();
+                if current == mode {
+                    self.exit_visual();
+                    return;
+                }
+                if !current.is_visual() {
+                    self.visual_anchor = self.buffer.cursors().primary().position();
+                }
+                self.set_vim_mode(mode);
+                self.show_visual_selection();
+            }
+            VimAction::Escape => {
+                if self.vim_mode().is_visual() {
+                    self.exit_visual();
+                }
+            }
+            VimAction::CommandLine => self.open_command_line(),
+            VimAction::SetMark(name) => self.handle_command(Command::SetMark(name)),
+            VimAction::GotoMark(name) => self.handle_command(Command::GotoMark(name)),
+            VimAction::OpenLink => self.handle_command(Command::OpenLinkUnderCursor),
+            VimAction::CenterCursor => self.handle_command(Command::CenterCursor),
+            VimAction::CursorToTop => self.handle_command(Command::CursorToTop),
+            VimAction::CursorToBottom => self.handle_command(Command::CursorToBottom),
+            VimAction::ScrollLines { up, count } => self.scroll_view_lines(up, count),
+            VimAction::Pending | VimAction::Ignored | VimAction::Passthrough => {}
+        }
+    }
+
+    /// Where `motion` repeated `count` times would put the cursor.
+    fn motion_target(&mut self, motion: Motion, count: Option<usize>) -> Position {
+        let start = self.buffer.cursors().primary().position();
+        let times = count.unwrap_or(1).max(1);
+        let last = self.last_line();
+        let on_line = |app: &Self, line: usize| Position::new(line, app.first_non_blank(line));
+        match motion {
+            Motion::FirstNonBlank => return on_line(self, start.line),
+            Motion::FirstLine => {
+                let line = count.map_or(0, |n| n.saturating_sub(1)).min(last);
+                return on_line(self, line);
+            }
+            Motion::LastLine => {
+                let line = count.map_or(last, |n| n.saturating_sub(1)).min(last);
+                return on_line(self, line);
+            }
+            Motion::LineEnd => {
+                let line = (start.line + times - 1).min(last);
+                return Position::new(line, self.line_len(line));
+            }
+            Motion::WordEnd => {
+                let mut pos = start;
+                for _ in 0..times {
+                    pos = self.word_end_after(pos);
+                }
+                return pos;
+            }
+            _ => {}
+        }
+        let cmd = match motion {
+            Motion::Left => Command::MoveLeft,
+            Motion::Right => Command::MoveRight,
+            Motion::Up => Command::MoveUp,
+            Motion::Down => Command::MoveDown,
+            Motion::WordForward => Command::MoveWordRight,
+            Motion::WordBackward => Command::MoveWordLeft,
+            _ => Command::MoveLineStart,
+        };
+        for _ in 0..times {
+            self.apply_motion(&cmd);
+        }
+        let target = self.buffer.cursors().primary().position();
+        self.set_cursor(start);
+        target
+    }
+
+    fn operate_motion(&mut self, op: Operator, motion: Motion, count: Option<usize>) {
+        let start = self.buffer.cursors().primary().position();
+        // `cw` changes to the end of the word, like `ce`.
+        let motion = if op == Operator::Change && motion == Motion::WordForward {
+            Motion::WordEnd
+        } else {
+            motion
+        };
+        let mut target = self.motion_target(motion, count);
+        if motion.is_linewise() {
+            self.operate_lines(op, start.line.min(target.line), start.line.max(target.line));
+            return;
+        }
+        // `dw` on the last word of a line stops at the line end.
+        if motion == Motion::WordForward && target.line > start.line {
+            target = Position::new(start.line, self.line_len(start.line));
+        }
+        let (from, mut to) = ordered(start, target);
+        if motion.is_inclusive() {
+            to = self.next_char(to);
+        }
+        if from != to {
+            self.operate_range(op, from, to);
+        } else if op == Operator::Change {
+            self.set_vim_mode(VimMode::Insert);
+        }
+    }
+
+    /// Apply `op` to lines `first..=last` as whole lines.
+    pub(crate) fn operate_lines(&mut self, op: Operator, first: usize, last: usize) {
+        let last_len = self.line_len(last);
+        let has_next = last < self.last_line();
+        let mut text = self
+            .buffer
+            .text_in_range(Range::new(
+                Position::new(first, 0),
+                Position::new(last, last_len),
+            ))
+            .unwrap_or_default();
+        text.push('\n');
+        self.set_register(&text, true, op != Operator::Yank);
+
+        let range = match op {
+            Operator::Yank => {
+                let n = last - first + 1;
+                self.set_cursor(Position::new(
+                    first,
+                    self.buffer.cursors().primary().position().col,
+                ));
+                self.messages.info(format!("{} line(s) yanked", n));
+                return;
+            }
+            // Keep one (empty) line to type into.
+            Operator::Change => Range::new(Position::new(first, 0), Position::new(last, last_len)),
+            Operator::Delete if has_next => {
+                Range::new(Position::new(first, 0), Position::new(last + 1, 0))
+            }
+            Operator::Delete if first > 0 => Range::new(
+                Position::new(first - 1, self.line_len(first - 1)),
+                Position::new(last, last_len),
+            ),
+            Operator::Delete => Range::new(Position::new(first, 0), Position::new(last, last_len)),
+        };
+        if self.apply_edit(EditCommand::Delete { range }).is_err() {
+            return;
+        }
+        self.lsp_did_change();
+        if op == Operator::Change {
+            self.set_cursor(Position::new(first, 0));
+            self.set_vim_mode(VimMode::Insert);
+        } else {
+            let line = first.min(self.last_line());
+            self.set_cursor(Position::new(line, self.first_non_blank(line)));
+        }
+    }
+
+    /// Apply `op` to the text between `start` and `end` (exclusive).
+    fn operate_range(&mut self, op: Operator, start: Position, end: Position) {
+        let range = Range::new(start, end);
+        let text = self.buffer.text_in_range(range).unwrap_or_default();
+        self.set_register(&text, false, op != Operator::Yank);
+        if op != Operator::Yank {
+            if self.apply_edit(EditCommand::Delete { range }).is_err() {
+                return;
+            }
+            self.lsp_did_change();
+        }
+        self.set_cursor(start);
+        if op == Operator::Change {
+            self.set_vim_mode(VimMode::Insert);
+        }
+    }
+
+    fn vim_insert(&mut self, at: InsertAt) {
+        let pos = self.buffer.cursors().primary().position();
+        match at {
+            InsertAt::Cursor => {}
+            InsertAt::AfterCursor => self.set_cursor(self.buffer.next_grapheme(pos)),
+            InsertAt::FirstNonBlank => {
+                self.set_cursor(Position::new(pos.line, self.first_non_blank(pos.line)));
+            }
+            InsertAt::LineEnd => self.set_cursor(Position::new(pos.line, self.line_len(pos.line))),
+            InsertAt::LineBelow => {
+                self.set_cursor(Position::new(pos.line, self.line_len(pos.line)));
+                self.insert_text("\n");
+            }
+            InsertAt::LineAbove => {
+                self.set_cursor(Position::new(pos.line, 0));
+                self.insert_text("\n");
+                self.set_cursor(Position::new(pos.line, 0));
+            }
+        }
+        self.set_vim_mode(VimMode::Insert);
+    }
+
+    fn vim_paste(&mut self, before: bool, count: usize) {
+        let Some(RegisterContent { text, linewise }) = self.register_for_paste() else {
+            return;
+        };
+        let pos = self.buffer.cursors().primary().position();
+        self.buffer.cursors_mut().primary_mut().clear_selection();
+        if linewise && text.ends_with('\n') {
+            let line = if before { pos.line } else { pos.line + 1 };
+            let block = text.repeat(count);
+            if line <= self.last_line() {
+                self.set_cursor(Position::new(line, 0));
+                self.insert_text(&block);
+            } else {
+                // Pasting below a last line that has no newline.
+                self.set_cursor(Position::new(pos.line, self.line_len(pos.line)));
+                self.insert_text(&format!("\n{}", &block[..block.len() - 1]));
+            }
+            self.set_cursor(Position::new(line, self.first_non_blank(line)));
+        } else {
+            if !before {
+                self.set_cursor(self.buffer.next_grapheme(pos));
+            }
+            self.insert_text(&text.repeat(count));
+            let end = self.buffer.cursors().primary().position();
+            if end.col > 0 {
+                self.set_cursor(self.buffer.prev_grapheme(end));
+            }
+        }
+    }
+
+    /// Open the `:` command line.
+    pub(crate) fn open_command_line(&mut self) {
+        if self.vim_mode().is_visual() {
+            self.exit_visual();
+        }
+        self.set_vim_mode(VimMode::CommandLine);
+        self.input_mode = InputMode::PromptVimCommand;
+        self.prompt_input.clear();
+    }
+
+    /// Run an ex command typed after `:`.
+    pub(crate) fn confirm_vim_command(&mut self, input: &str) {
+        self.set_vim_mode(VimMode::Normal);
+        let input = input.trim();
+        if let Some(command) = input.strip_prefix('!') {
+            self.run_shell_in_pane(command);
+            return;
+        }
+        if self.run_line_command(input) {
+            return;
+        }
+        let (cmd, arg) = match input.split_once(char::is_whitespace) {
+            Some((cmd, arg)) => (cmd, arg.trim()),
+            None => (input, ""),
+        };
+        match (cmd, arg) {
+            ("", _) => {}
+            ("w", "") => self.handle_command(Command::Save),
+            ("w", path) => self.confirm_save_as(path),
+            ("q", "") => {
+                if self.buffer.is_dirty() {
+                    self.messages
+                        .error("No write since last change (add ! to override)");
+                } else {
+                    self.running = false;
+                }
+            }
+            ("q!", "") => self.running = false,
+            ("wq" | "x", "") => {
+                self.handle_command(Command::Save);
+                if !self.buffer.is_dirty() {
+                    self.running = false;
+                }
+            }
+            ("e" | "edit", path) if !path.is_empty() => {
+                self.confirm_open(path);
+            }
+            ("enew", "") => self.cmd_new_buffer(),
+            ("mark" | "k", name) if name.chars().count() == 1 => {
+                self.handle_command(Command::SetMark(name.chars().next().unwrap_or_default()));
+            }
+            ("marks", "") => self.handle_command(Command::ListMarks),
+            ("task", "") => self.handle_command(Command::RunTask),
+            ("task", name) => self.confirm_task(name),
+            ("cn" | "cnext", "") => self.handle_command(Command::QuickfixNext),
+            ("cp" | "cprevious" | "cN" | "cNext", "") => self.handle_command(Command::QuickfixPrev),
+            ("reg" | "registers" | "di" | "display", "") => {
+                self.handle_command(Command::ShowRegisters)
+            }
+            _ => self
+                .messages
+                .error(format!("Not an editor command: {}", input)),
+        }
+    }
+
+    fn exit_visual(&mut self) {
+        self.buffer.cursors_mut().primary_mut().clear_selection();
+        self.set_vim_mode(VimMode::Normal);
+    }
+
+    /// Mirror the Visual selection onto the cursor so it is highlighted.
+    /// Vim selections include the character under the cursor, so the
+    /// far end is pushed one character past it.
+    fn show_visual_selection(&mut self) {
+        let head = self.buffer.cursors().primary().position();
+        let anchor = self.visual_anchor;
+        let anchor = if self.vim_mode() == VimMode::VisualLine {
+            if head.line >= anchor.line {
+                Position::new(anchor.line, 0)
+            } else {
+                Position::new(anchor.line, self.line_len(anchor.line))
+            }
+        } else if head < anchor {
+            self.next_char(anchor)
+        } else {
+            anchor
+        };
+        self.buffer.cursors_mut().primary_mut().set_anchor(anchor);
+    }
+
+    pub(crate) fn set_cursor(&mut self, pos: Position) {
+        let pos = self.buffer.clamp_position(pos);
+        self.buffer.cursors_mut().primary_mut().set_position(pos);
+    }
+
+    pub(crate) fn last_line(&self) -> usize {
+        self.buffer.line_count().saturating_sub(1)
+    }
+
+    pub(crate) fn line_len(&self, line: usize) -> usize {
+        self.buffer.line(line).map(line_content_len).unwrap_or(0)
+    }
+
+    pub(crate) fn first_non_blank(&self, line: usize) -> usize {
+        self.buffer
+            .line(line)
+            .map(|l| l.chars().take_while(|c| *c == ' ' || *c == '\t').count())
+            .unwrap_or(0)
+            .min(self.line_len(line))
+    }
+
+    /// The position one character after `pos`, staying on its line.
+    fn next_char(&self, pos: Position) -> Position {
+        self.buffer.next_grapheme(pos)
+    }
+
+    /// Last character of the word ending after `pos` (Vim's `e`).
+    fn word_end_after(&self, pos: Position) -> Position {
+        let rope = self.buffer.text();
+        let total = rope.len_chars();
+        let start = rope.line_to_char(pos.line) + pos.col;
+        let class = |c: char| {
+            if c.is_whitespace() {
+                0
+            } else if c.is_alphanumeric() || c == '_' {
+                1
+            } else {
+                2
+            }
+        };
+        let mut idx = start + 1;
+        while idx < total && class(rope.char(idx)) == 0 {
+            idx += 1;
+        }
+        if idx >= total {
+            return pos;
+        }
+        let word = class(rope.char(idx));
+        while idx + 1 < total && class(rope.char(idx + 1)) == word {
+            idx += 1;
+        }
+        let line = rope.char_to_line(idx);
+        Position::new(line, idx - rope.line_to_char(line))
+    }
+}
+
+fn ordered(a: Position, b: Position) -> (Position, Position) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
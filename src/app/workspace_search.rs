@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use smash_core::position::Position;
+use smash_core::quickfix::Problem;
+use smash_core::search::SearchQuery;
+use smash_core::workspace_search::{WorkspaceMatch, WorkspaceSearch};
+use smash_input::{Key, KeyEvent};
+use smash_tui::ListState;
+use tracing::info;
+
+use super::file_tree::absolute;
+use super::{App, InputMode};
+
+/// Matching lines kept for the results pane; the search stops there.
+const MAX_RESULTS: usize = 2000;
+
+/// Where the lines in the results pane come from.
+enum ResultsSource {
+    /// A workspace search, running or done.
+    Search {
+        /// The pattern as typed, offered again by the next search prompt.
+        pattern: String,
+        search: WorkspaceSearch,
+    },
+    /// Locations from the language server, e.g. `References`.
+    Locations { what: String },
+    /// Problems reported by a task or the language server, in the order
+    /// they were reported.
+    Quickfix { what: String },
+}
+
+/// The results pane: a workspace search and the matches it has found so
+/// far, or a list of locations such as a symbol's references.
+pub(crate) struct SearchResults {
+    source: ResultsSource,
+    root: PathBuf,
+    /// Sorted by path, then line.
+    matches: Vec<WorkspaceMatch>,
+    pub(crate) list: ListState,
+    /// Whether the selected match has been jumped to, so QuickfixNext
+    /// goes to it first on a list nothing was opened from yet.
+    jumped: bool,
+}
+
+impl SearchResults {
+    /// Pane title: the pattern and how far the search has got, or what
+    /// the locations are.
+    pub(crate) fn title(&self) -> String {
+        let mut files: Vec<&PathBuf> = self.matches.iter().map(|m| &m.path).collect();
+        files.dedup();
+        let (pattern, search) = match &self.source {
+            ResultsSource::Search { pattern, search } => (pattern, search),
+            ResultsSource::Locations { what } => {
+                return format!(
+                    "{}: {} locations in {} files",
+                    what,
+                    self.matches.len(),
+                    files.len()
+                );
+            }
+            ResultsSource::Quickfix { what } => {
+                files.sort();
+                files.dedup();
+                return format!(
+                    "{}: {} problems in {} files",
+                    what,
+                    self.matches.len(),
+                    files.len()
+                );
+            }
+        };
+        let state = if !search.is_done() {
+            " (searching…)"
+        } else if search.is_truncated() {
+            " (stopped at the result limit)"
+        } else {
+            ""
+        };
+        if self.matches.is_empty() && search.is_done() {
+            return format!(
+                "Search \"{}\": no matches in {} files",
+                pattern,
+                search.files_searched()
+            );
+        }
+        format!(
+            "Search \"{}\": {} matches in {} files{}",
+            pattern,
+            self.matches.len(),
+            files.len(),
+            state
+        )
+    }
+
+    /// Status bar tag while the pane has focus.
+    pub(crate) fn status_tag(&self) -> &'static str {
+        match self.source {
+            ResultsSource::Search { .. } => " [search]",
+            ResultsSource::Locations { .. } => " [locations]",
+            ResultsSource::Quickfix { .. } => " [quickfix]",
+        }
+    }
+
+    /// One `path:line:col: text` row per match.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        self.matches
+            .iter()
+            .map(|m| {
+                format!(
+                    "{}:{}:{}: {}",
+                    m.path.display(),
+                    m.line + 1,
+                    m.col + 1,
+                    m.text.trim()
+                )
+            })
+            .collect()
+    }
+}
+
+// =========================================================================
+// Workspace search
+// =========================================================================
+
+impl App {
+    /// Ask for a pattern to search the workspace for, starting from the
+    /// previous one.
+    pub(crate) fn cmd_workspace_search(&mut self) {
+        self.input_mode = InputMode::PromptWorkspaceSearch;
+        self.prompt_input = match self.search_results.as_ref().map(|r| &r.source) {
+            Some(ResultsSource::Search { pattern, .. }) => pattern.clone(),
+            _ => String::new(),
+        };
+    }
+
+    /// Search the working directory for `input`.
+    pub(crate) fn confirm_workspace_search(&mut self, input: &str) {
+        match std::env::current_dir() {
+            Ok(cwd) => self.search_workspace(cwd, input),
+            Err(e) => self
+                .messages
+                .error(format!("Cannot search the workspace: {}", e)),
+        }
+    }
+
+    /// Start searching the files under `root` for `input` and focus the
+    /// results pane. `/pattern/` is a regex; anything else is matched
+    /// literally, ignoring case. Empty input closes the pane.
+    pub(crate) fn search_workspace(&mut self, root: PathBuf, input: &str) {
+        let pattern = input.trim();
+        if pattern.is_empty() {
+            self.close_search_results();
+            return;
+        }
+        let query = match pattern
+            .strip_prefix('/')
+            .and_then(|p| p.strip_suffix('/'))
+            .filter(|p| !p.is_empty())
+        {
+            Some(re) => match Regex::new(re) {
+                Ok(re) => SearchQuery::Regex(re),
+                Err(e) => {
+                    self.messages.error(format!("Invalid regex: {}", e));
+                    return;
+                }
+            },
+            None => SearchQuery::Plain {
+                pattern: pattern.to_string(),
+                case_sensitive: false,
+            },
+        };
+        info!("workspace search: '{}' in {}", pattern, root.display());
+        self.show_results(SearchResults {
+            source: ResultsSource::Search {
+                pattern: pattern.to_string(),
+                search: WorkspaceSearch::spawn(root.clone(), query, MAX_RESULTS),
+            },
+            root,
+            matches: Vec::new(),
+            list: ListState::default(),
+            jumped: false,
+        });
+    }
+
+    /// Fill the results pane and focus it.
+    fn show_results(&mut self, results: SearchResults) {
+        self.search_results = Some(results);
+        self.call_hierarchy = None;
+        self.diff_view = None;
+        self.shell_output = None;
+        self.search_results_focused = true;
+        self.file_tree_focused = false;
+    }
+
+    /// Collect matches from a running search. Returns `true` if the pane
+    /// needs a redraw.
+    pub(crate) fn poll_workspace_search(&mut self) -> bool {
+        let Some(results) = self.search_results.as_mut() else {
+            return false;
+        };
+        let ResultsSource::Search { search, .. } = &mut results.source else {
+            return false;
+        };
+        if search.is_done() {
+            return false;
+        }
+        let found = search.poll();
+        if found.is_empty() && !search.is_done() {
+            return false;
+        }
+        // Files finish in any order; keep the list sorted and the
+        // selection on the same match.
+        let selected = results.matches.get(results.list.selected()).cloned();
+        results.matches.extend(found);
+        results
+            .matches
+            .sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+        if let Some(index) = selected.and_then(|s| results.matches.iter().position(|m| *m == s)) {
+            results.list.select(index, results.matches.len());
+        }
+        true
+    }
+
+    /// Whether keystrokes currently go to the results pane.
+    pub(crate) fn search_results_focused(&self) -> bool {
+        self.search_results_focused
+            && (self.search_results.is_some()
+                || self.call_hierarchy.is_some()
+                || self.diff_view.is_some()
+                || self.shell_output.is_some())
+            && self.input_mode == InputMode::Normal
+    }
+
+    /// Handle a key while the results pane has focus. As in the file
+    /// tree, keys with Ctrl or Alt fall through to the keymap.
+    pub(crate) fn handle_search_results_key(&mut self, key: &KeyEvent) -> bool {
+        if !self.search_results_focused() || key.modifiers.ctrl() || key.modifiers.alt() {
+            return false;
+        }
+        if key.key == Key::Esc {
+            self.close_results_pane();
+            return true;
+        }
+        if self.call_hierarchy.is_some() {
+            return self.handle_call_hierarchy_key(key);
+        }
+        if self.diff_view.is_some() {
+            return self.handle_diff_view_key(key);
+        }
+        if self.shell_output.is_some() {
+            return self.handle_shell_output_key(key);
+        }
+        let Some(results) = self.search_results.as_mut() else {
+            return false;
+        };
+        if results.list.handle_key(&key.key, results.matches.len()) {
+            return true;
+        }
+        if key.key == Key::Enter {
+            self.open_search_result();
+        }
+        true
+    }
+
+    /// Jump to the selected match, opening its file if needed. The pane
+    /// stays open but gives focus back to the editor.
+    pub(crate) fn open_search_result(&mut self) {
+        let Some(results) = self.search_results.as_mut() else {
+            return;
+        };
+        results.jumped = true;
+        let Some(m) = results.matches.get(results.list.selected()) else {
+            return;
+        };
+        let path = results.root.join(&m.path);
+        let target = Position::new(m.line, m.col);
+        self.push_jump();
+        if self.buffer.path().map(absolute).as_deref() == Some(path.as_path()) {
+            let cursor = self.buffer.cursors_mut().primary_mut();
+            cursor.clear_selection();
+            cursor.set_position(target);
+        } else {
+            self.open_path(path, Some(target), false);
+        }
+        self.search_results_focused = false;
+    }
+
+    /// Stop any running search and hide the results pane.
+    pub(crate) fn close_search_results(&mut self) {
+        self.search_results = None;
+        self.search_results_focused = false;
+    }
+
+    /// Hide whatever the results pane shows on top, uncovering the
+    /// search results beneath it, or close the pane if that was them.
+    fn close_results_pane(&mut self) {
+        if self.call_hierarchy.take().is_none()
+            && self.diff_view.take().is_none()
+            && self.shell_output.take().is_none()
+        {
+            self.search_results = None;
+        }
+        self.search_results_focused = false;
+    }
+}
+
+// =========================================================================
+// Location lists
+// =========================================================================
+
+impl App {
+    /// List `locations` in the results pane, titled `what`, with the
+    /// text of each line: from the buffer for the open file, otherwise
+    /// read from disk.
+    pub(crate) fn show_locations(&mut self, what: &str, locations: &[smash_lsp::Location]) {
+        let root = std::env::current_dir().unwrap_or_default();
+        let mut files: HashMap<PathBuf, Vec<String>> = HashMap::new();
+        let mut matches: Vec<WorkspaceMatch> = locations
+            .iter()
+            .map(|loc| {
+                let path = Self::uri_to_path(&loc.uri);
+                let line = loc.range.start.line as usize;
+                let text = if self.is_current_uri(&loc.uri) {
+                    self.buffer
+                        .line(line)
+                        .map(|l| l.to_string())
+                        .unwrap_or_default()
+                } else {
+                    files
+                        .entry(path.clone())
+                        .or_insert_with(|| read_lines(&path))
+                        .get(line)
+                        .cloned()
+                        .unwrap_or_default()
+                };
+                WorkspaceMatch {
+                    path: path
+                        .strip_prefix(&root)
+                        .map(Path::to_path_buf)
+                        .unwrap_or(path),
+                    line,
+                    col: loc.range.start.character as usize,
+                    text: text.trim_end_matches(['\n', '\r']).to_string(),
+                }
+            })
+            .collect();
+        matches.sort_by(|a, b| (&a.path, a.line, a.col).cmp(&(&b.path, b.line, b.col)));
+        matches.dedup();
+        self.show_results(SearchResults {
+            source: ResultsSource::Locations {
+                what: what.to_string(),
+            },
+            root,
+            matches,
+            list: ListState::default(),
+            jumped: false,
+        });
+    }
+}
+
+// =========================================================================
+// Quickfix list
+// =========================================================================
+
+impl App {
+    /// List `problems` in the results pane, titled `what`. Paths are
+    /// relative to the working directory. The pane only takes focus when
+    /// `focus` is set, so a task finishing does not take keys away from
+    /// the editor.
+    pub(crate) fn show_quickfix(&mut self, what: &str, problems: &[Problem], focus: bool) {
+        let root = std::env::current_dir().unwrap_or_default();
+        let mut matches: Vec<WorkspaceMatch> = problems
+            .iter()
+            .map(|p| WorkspaceMatch {
+                path: PathBuf::from(&p.path),
+                line: p.line.saturating_sub(1),
+                col: p.col.map_or(0, |c| c.saturating_sub(1)),
+                text: format!("{}: {}", p.severity.label(), p.message),
+            })
+            .collect();
+        matches.dedup();
+        info!(problems = matches.len(), "quickfix list: {}", what);
+        self.show_results(SearchResults {
+            source: ResultsSource::Quickfix {
+                what: what.to_string(),
+            },
+            root,
+            matches,
+            list: ListState::default(),
+            jumped: false,
+        });
+        self.search_results_focused = focus;
+    }
+
+    /// Jump to the next (`forward`) or previous item of the list in the
+    /// results pane: problems, search matches or locations alike.
+    pub(crate) fn cmd_quickfix_step(&mut self, forward: bool) {
+        let Some(results) = self.search_results.as_mut() else {
+            self.messages.info("No quickfix list");
+            return;
+        };
+        if results.matches.is_empty() {
+            self.messages.info("The list is empty");
+            return;
+        }
+        let last = results.matches.len() - 1;
+        let selected = results.list.selected();
+        let target = match (results.jumped, forward) {
+            (false, _) => selected,
+            (true, true) if selected < last => selected + 1,
+            (true, false) if selected > 0 => selected - 1,
+            (true, _) => {
+                self.messages.info(if forward {
+                    "No more items"
+                } else {
+                    "Already at the first item"
+                });
+                return;
+            }
+        };
+        let total = results.matches.len();
+        results.list.select(target, total);
+        self.open_search_result();
+        self.messages.info(format!("({} of {})", target + 1, total));
+    }
+}
+
+/// The lines of the file at `path`, or none if it cannot be read.
+fn read_lines(path: &Path) -> Vec<String> {
+    std::fs::read_to_string(path)
+        .map(|text| text.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
@@ -1,39 +1,57 @@
+use std::io::{BufWriter, Stdout, Write};
+
+use crossterm::style::Color;
 use smash_tui::TerminalBackend;
 
-/// Minimal crossterm backend for production use.
-pub(crate) struct CrosstermBackend {
-    stdout: std::io::Stdout,
+/// Bytes of escape sequences and text held back before a write to the
+/// terminal; a typical frame fits, so it goes out in one write.
+const OUTPUT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Crossterm backend for production use. Output is queued and sent once
+/// per frame by [`TerminalBackend::flush`].
+pub(crate) struct CrosstermBackend<W: Write = Stdout> {
+    out: BufWriter<W>,
+    /// Colours and attributes last sent, so runs in the same style do not
+    /// repeat them.
+    style: Option<(Color, Color, smash_tui::Attributes)>,
 }
 
 impl CrosstermBackend {
     pub(crate) fn new() -> Self {
+        Self::with_writer(std::io::stdout())
+    }
+}
+
+impl<W: Write> CrosstermBackend<W> {
+    fn with_writer(out: W) -> Self {
         Self {
-            stdout: std::io::stdout(),
+            out: BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, out),
+            style: None,
         }
     }
 }
 
-impl TerminalBackend for CrosstermBackend {
+impl<W: Write> TerminalBackend for CrosstermBackend<W> {
     fn size(&self) -> Result<(u16, u16), smash_tui::TuiError> {
         crossterm::terminal::size().map_err(smash_tui::TuiError::Io)
     }
 
     fn move_cursor(&mut self, col: u16, row: u16) -> Result<(), smash_tui::TuiError> {
         use crossterm::cursor::MoveTo;
-        crossterm::execute!(self.stdout, MoveTo(col, row)).map_err(smash_tui::TuiError::Io)
+        crossterm::queue!(self.out, MoveTo(col, row)).map_err(smash_tui::TuiError::Io)
     }
 
     fn show_cursor(&mut self) -> Result<(), smash_tui::TuiError> {
-        crossterm::execute!(self.stdout, crossterm::cursor::Show).map_err(smash_tui::TuiError::Io)
+        crossterm::queue!(self.out, crossterm::cursor::Show).map_err(smash_tui::TuiError::Io)
     }
 
     fn hide_cursor(&mut self) -> Result<(), smash_tui::TuiError> {
-        crossterm::execute!(self.stdout, crossterm::cursor::Hide).map_err(smash_tui::TuiError::Io)
+        crossterm::queue!(self.out, crossterm::cursor::Hide).map_err(smash_tui::TuiError::Io)
     }
 
     fn clear(&mut self) -> Result<(), smash_tui::TuiError> {
         use crossterm::terminal::{Clear, ClearType};
-        crossterm::execute!(self.stdout, Clear(ClearType::All)).map_err(smash_tui::TuiError::Io)
+        crossterm::queue!(self.out, Clear(ClearType::All)).map_err(smash_tui::TuiError::Io)
     }
 
     fn write_cell(
@@ -41,24 +59,61 @@ impl TerminalBackend for CrosstermBackend {
         col: u16,
         row: u16,
         cell: &smash_tui::Cell,
+    ) -> Result<(), smash_tui::TuiError> {
+        self.write_run(col, row, std::slice::from_ref(cell))
+    }
+
+    fn write_run(
+        &mut self,
+        col: u16,
+        row: u16,
+        cells: &[smash_tui::Cell],
     ) -> Result<(), smash_tui::TuiError> {
         use crossterm::cursor::MoveTo;
-        use crossterm::style::{Print, SetBackgroundColor, SetForegroundColor};
-        let fg = to_crossterm_color(cell.style.fg);
-        let bg = to_crossterm_color(cell.style.bg);
-        crossterm::execute!(
-            self.stdout,
-            MoveTo(col, row),
-            SetForegroundColor(fg),
-            SetBackgroundColor(bg),
-            Print(cell.ch)
-        )
-        .map_err(smash_tui::TuiError::Io)
+        use crossterm::style::{
+            Attribute, Print, SetAttribute, SetAttributes, SetBackgroundColor, SetForegroundColor,
+        };
+        // The wide character before a continuation cell already covers it.
+        let skipped = cells.iter().take_while(|c| c.is_continuation()).count();
+        let Some(first) = cells.get(skipped) else {
+            return Ok(());
+        };
+        let mut text = String::with_capacity(cells.len());
+        for cell in cells[skipped..].iter().filter(|c| !c.is_continuation()) {
+            text.push(cell.ch);
+            text.push_str(cell.combining.as_deref().unwrap_or_default());
+        }
+        let col = col.saturating_add(skipped as u16);
+        crossterm::queue!(self.out, MoveTo(col, row)).map_err(smash_tui::TuiError::Io)?;
+        let style = (
+            to_crossterm_color(first.style.fg),
+            to_crossterm_color(first.style.bg),
+            first.style.attrs,
+        );
+        if self.style != Some(style) {
+            // Attributes can only be switched off all together, which
+            // resets the colours too.
+            if self.style.map(|(_, _, attrs)| attrs) != Some(style.2) {
+                crossterm::queue!(
+                    self.out,
+                    SetAttribute(Attribute::Reset),
+                    SetAttributes(to_crossterm_attributes(style.2))
+                )
+                .map_err(smash_tui::TuiError::Io)?;
+            }
+            crossterm::queue!(
+                self.out,
+                SetForegroundColor(style.0),
+                SetBackgroundColor(style.1)
+            )
+            .map_err(smash_tui::TuiError::Io)?;
+            self.style = Some(style);
+        }
+        crossterm::queue!(self.out, Print(text)).map_err(smash_tui::TuiError::Io)
     }
 
     fn flush(&mut self) -> Result<(), smash_tui::TuiError> {
-        use std::io::Write;
-        self.stdout.flush().map_err(smash_tui::TuiError::Io)
+        self.out.flush().map_err(smash_tui::TuiError::Io)
     }
 
     fn enter_alternate_screen(&mut self) -> Result<(), smash_tui::TuiError> {
@@ -78,7 +133,7 @@ impl TerminalBackend for CrosstermBackend {
     }
 }
 
-fn to_crossterm_color(color: smash_tui::Color) -> crossterm::style::Color {
+fn to_crossterm_color(color: smash_tui::Color) -> Color {
     match color {
         smash_tui::Color::Reset => crossterm::style::Color::Reset,
         smash_tui::Color::Black => crossterm::style::Color::Black,
@@ -93,3 +148,75 @@ fn to_crossterm_color(color: smash_tui::Color) -> crossterm::style::Color {
         smash_tui::Color::Indexed(i) => crossterm::style::Color::AnsiValue(i),
     }
 }
+
+fn to_crossterm_attributes(attrs: smash_tui::Attributes) -> crossterm::style::Attributes {
+    use crossterm::style::Attribute;
+    let mut out = crossterm::style::Attributes::default();
+    for (on, attr) in [
+        (attrs.bold(), Attribute::Bold),
+        (attrs.dim(), Attribute::Dim),
+        (attrs.italic(), Attribute::Italic),
+        (attrs.underline(), Attribute::Underlined),
+        (attrs.reverse(), Attribute::Reverse),
+        (attrs.strikethrough(), Attribute::CrossedOut),
+    ] {
+        if on {
+            out.set(attr);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smash_tui::{Cell, Style};
+
+    #[test]
+    fn runs_are_queued_until_flush_and_colours_sent_once() {
+        let mut backend = CrosstermBackend::with_writer(Vec::new());
+        let red = Style::default().fg(smash_tui::Color::Red);
+        let cells: Vec<Cell> = "ab".chars().map(|c| Cell::new(c, red)).collect();
+        backend.write_run(0, 0, &cells).unwrap();
+        backend.write_run(4, 0, &cells).unwrap();
+        assert!(backend.out.get_ref().is_empty());
+
+        backend.flush().unwrap();
+        let out = String::from_utf8(backend.out.get_ref().clone()).unwrap();
+        assert_eq!(out.matches("ab").count(), 2);
+        assert_eq!(out.matches("\x1b[38;5;1m").count(), 1);
+        assert!(out.contains("\x1b[1;5H"));
+    }
+
+    #[test]
+    fn attributes_are_sent_and_reset_between_runs() {
+        let mut backend = CrosstermBackend::with_writer(Vec::new());
+        let link = Style::default().underline();
+        let plain = Style::default();
+        backend.write_run(0, 0, &[Cell::new('a', link)]).unwrap();
+        backend.write_run(1, 0, &[Cell::new('b', link)]).unwrap();
+        backend.write_run(2, 0, &[Cell::new('c', plain)]).unwrap();
+        backend.flush().unwrap();
+        let out = String::from_utf8(backend.out.get_ref().clone()).unwrap();
+        assert_eq!(out.matches("\x1b[4m").count(), 1);
+        let reset = out.rfind("\x1b[0m").expect("attributes reset");
+        assert!(reset > out.find('b').unwrap() && reset < out.find('c').unwrap());
+    }
+
+    #[test]
+    fn continuation_cells_are_not_printed() {
+        let mut backend = CrosstermBackend::with_writer(Vec::new());
+        let style = Style::default();
+        let cells = [
+            Cell::continuation(style),
+            Cell::new('字', style),
+            Cell::continuation(style),
+            Cell::new('!', style),
+        ];
+        backend.write_run(2, 1, &cells).unwrap();
+        backend.flush().unwrap();
+        let out = String::from_utf8(backend.out.get_ref().clone()).unwrap();
+        assert!(out.contains("\x1b[2;4H"));
+        assert!(out.ends_with("字!"));
+    }
+}
@@ -0,0 +1,209 @@
+//! Git blame for the open file: `git blame --porcelain` run on a
+//! background thread and parsed into one entry per line.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use tracing::{info, warn};
+
+/// The commit that last changed a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BlameLine {
+    /// Abbreviated commit hash.
+    pub(crate) commit: String,
+    pub(crate) author: String,
+    /// Author time, seconds since the Unix epoch.
+    pub(crate) time: i64,
+    pub(crate) summary: String,
+}
+
+impl BlameLine {
+    /// Whether the line has changes not yet committed.
+    pub(crate) fn is_uncommitted(&self) -> bool {
+        self.commit.bytes().all(|b| b == b'0')
+    }
+
+    /// `author, date • summary`, or a note that the line is not
+    /// committed.
+    pub(crate) fn annotation(&self) -> String {
+        if self.is_uncommitted() {
+            return "You, not committed yet".to_string();
+        }
+        format!(
+            "{}, {} • {}",
+            self.author,
+            format_date(self.time),
+            self.summary
+        )
+    }
+}
+
+/// Parse `git blame --porcelain` output into one entry per line of the
+/// file, in order.
+pub(crate) fn parse_porcelain(output: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<&str, BlameLine> = HashMap::new();
+    let mut lines: Vec<(usize, &str)> = Vec::new();
+    let mut current: Option<(&str, usize)> = None;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            // The line's content ends its entry.
+            if let Some((sha, final_line)) = current.take() {
+                lines.push((final_line, sha));
+            }
+            continue;
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        if key.len() == 40 && key.bytes().all(|b| b.is_ascii_hexdigit()) {
+            let final_line = value
+                .split(' ')
+                .nth(1)
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or(0);
+            current = Some((key, final_line));
+            commits.entry(key).or_insert_with(|| BlameLine {
+                commit: key[..8].to_string(),
+                author: String::new(),
+                time: 0,
+                summary: String::new(),
+            });
+            continue;
+        }
+        let Some(commit) = current.and_then(|(sha, _)| commits.get_mut(sha)) else {
+            continue;
+        };
+        match key {
+            "author" => commit.author = value.to_string(),
+            "author-time" => commit.time = value.parse().unwrap_or(0),
+            "summary" => commit.summary = value.to_string(),
+            _ => {}
+        }
+    }
+    lines.sort_by_key(|(n, _)| *n);
+    lines
+        .into_iter()
+        .filter_map(|(_, sha)| commits.get(sha).cloned())
+        .collect()
+}
+
+/// `YYYY-MM-DD` for `secs` since the Unix epoch, in UTC.
+pub(crate) fn format_date(secs: i64) -> String {
+    // Howard Hinnant's days-to-civil algorithm.
+    let days = secs.div_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// A `git blame` of one file, running on a background thread.
+pub(crate) struct BlameJob {
+    path: PathBuf,
+    rx: Receiver<Result<Vec<BlameLine>, String>>,
+}
+
+impl BlameJob {
+    /// Blame `path` with `contents` standing in for the file on disk, so
+    /// unsaved edits line up with the buffer.
+    pub(crate) fn spawn(path: &Path, contents: String) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let thread_path = path.to_path_buf();
+        std::thread::spawn(move || {
+            let _ = tx.send(run_blame(&thread_path, &contents));
+        });
+        Self {
+            path: path.to_path_buf(),
+            rx,
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The result, once the blame has finished.
+    pub(crate) fn poll(&self) -> Option<Result<Vec<BlameLine>, String>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err("git blame stopped".to_string())),
+        }
+    }
+}
+
+fn run_blame(path: &Path, contents: &str) -> Result<Vec<BlameLine>, String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().ok_or("not a file")?;
+    let mut child = Command::new("git")
+        .arg("blame")
+        .arg("--porcelain")
+        .arg("--contents")
+        .arg("-")
+        .arg("--")
+        .arg(name)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run git: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // git stops reading early for a file it cannot blame.
+        let _ = stdin.write_all(contents.as_bytes());
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("git blame failed: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().next().unwrap_or("git blame failed").trim();
+        warn!("git blame {} failed: {}", path.display(), reason);
+        return Err(reason.trim_start_matches("fatal: ").to_string());
+    }
+    let lines = parse_porcelain(&String::from_utf8_lossy(&output.stdout));
+    info!("blamed {} ({} lines)", path.display(), lines.len());
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA_A: &str = "1f2e3d4c5b6a79880f1e2d3c4b5a69788f1e2d3c";
+    const SHA_B: &str = "0000000000000000000000000000000000000000";
+
+    #[test]
+    fn porcelain_output_gives_one_entry_per_line() {
+        let output = format!(
+            "{a} 1 1 2\nauthor Ada\nauthor-mail <ada@example.com>\nauthor-time 1700000000\n\
+             author-tz +0000\nsummary Add the parser\nfilename src/lib.rs\n\tfn main() {{\n\
+             {a} 2 2\n\t}}\n\
+             {b} 3 3 1\nauthor Not Committed Yet\nauthor-time 1800000000\n\
+             summary Version of src/lib.rs from src/lib.rs\nfilename src/lib.rs\n\t// new\n",
+            a = SHA_A,
+            b = SHA_B
+        );
+        let lines = parse_porcelain(&output);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].commit, "1f2e3d4c");
+        assert_eq!(lines[1], lines[0]);
+        assert_eq!(lines[0].annotation(), "Ada, 2023-11-14 • Add the parser");
+        assert!(lines[2].is_uncommitted());
+        assert_eq!(lines[2].annotation(), "You, not committed yet");
+    }
+
+    #[test]
+    fn dates_are_formatted_in_utc() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(-86_400), "1969-12-31");
+    }
+}
@@ -0,0 +1,75 @@
+//! Deadlines for work the event loop runs once input settles.
+//!
+//! The loop polls every pending [`Debounce`] on each pass and shortens its
+//! input wait to the nearest deadline, so a debounced action runs on time
+//! without a thread or timer of its own.
+
+use std::time::{Duration, Instant};
+
+/// Fires once, `delay` after the last call to [`Debounce::schedule`].
+#[derive(Debug, Clone)]
+pub(crate) struct Debounce {
+    delay: Duration,
+    due: Option<Instant>,
+}
+
+impl Debounce {
+    pub(crate) fn new(delay: Duration) -> Self {
+        Self { delay, due: None }
+    }
+
+    /// Start the wait again from `now`.
+    pub(crate) fn schedule(&mut self, now: Instant) {
+        self.due = Some(now + self.delay);
+    }
+
+    pub(crate) fn cancel(&mut self) {
+        self.due = None;
+    }
+
+    pub(crate) fn is_pending(&self) -> bool {
+        self.due.is_some()
+    }
+
+    /// Time left before it fires, if scheduled.
+    pub(crate) fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.due.map(|due| due.saturating_duration_since(now))
+    }
+
+    /// Whether the deadline has passed. Firing clears it.
+    pub(crate) fn fire(&mut self, now: Instant) -> bool {
+        match self.due {
+            Some(due) if due <= now => {
+                self.due = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_once_after_the_last_schedule() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut d = Debounce::new(ms(150));
+        assert!(!d.fire(start + ms(1000)));
+
+        d.schedule(start);
+        d.schedule(start + ms(100));
+        assert_eq!(d.remaining(start + ms(200)), Some(ms(50)));
+        assert!(!d.fire(start + ms(200)));
+        assert!(d.fire(start + ms(250)));
+        assert!(!d.is_pending());
+        assert!(!d.fire(start + ms(300)));
+
+        d.schedule(start);
+        d.cancel();
+        assert_eq!(d.remaining(start), None);
+        assert!(!d.fire(start + ms(1000)));
+    }
+}
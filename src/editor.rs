@@ -1,29 +1,66 @@
-use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use crossterm::event::{self, Event};
-use tracing::{error, info};
+use tracing::{error, info, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 
 use smash_config::load_config;
+use smash_core::recent_files::{RecentFiles, RECENT_FILES_FILE};
+use smash_core::search_history::{SearchHistory, SEARCH_HISTORY_FILE};
+use smash_core::session::{Session, SESSION_FILE};
 use smash_input::ResolveResult;
+use smash_platform::instance::{self, InstanceServer, OpenRequest};
 use smash_platform::paths::DefaultPaths;
 use smash_platform::paths::PlatformPaths;
 use smash_platform::Platform;
 
-use crate::app::{App, InputMode};
+use crate::app::{
+    is_git_file, App, ConfigPaths, IdleTask, InputMode, RenderStats, StartupPhase, StartupProfile,
+};
 use crate::backend::CrosstermBackend;
+use crate::log_filter::{config_log_filter, LogFilter};
 use crate::lsp_types::LspCommand;
-
-/// Set up the editor, run the event loop, and clean up on exit.
-pub(crate) fn run_editor(file: Option<PathBuf>) -> Result<()> {
+use crate::Args;
+
+/// Set up the editor, run the event loop, and clean up on exit; returns
+/// the process exit status.
+pub(crate) fn run_editor(args: Args) -> Result<i32> {
+    let started = Instant::now();
+    let Args {
+        mut file,
+        line,
+        restore,
+        profile_startup,
+    } = args;
+    let mut profile = StartupProfile::default();
+    // Git waits for the editor to exit, so a commit message or rebase
+    // todo is never handed to another instance or kept in the session.
+    let git_editor = file.as_deref().is_some_and(is_git_file);
+    let platform_started = Instant::now();
     let paths = DefaultPaths::new().context("failed to detect platform paths")?;
+    // Before anything reads or creates the directories; logged below,
+    // once logging is up.
+    let migrations = paths.migrate_legacy_dirs();
+    let mut platform_time = platform_started.elapsed();
 
     // Load configuration first so we can honour log settings.
     let config_dir = paths.config_dir();
     let project_dir = std::env::current_dir().ok();
-    let config = load_config(&config_dir, project_dir.as_deref())
-        .unwrap_or_else(|_e| smash_config::Config::default());
+    let config = profile.time(StartupPhase::ConfigLoad, || {
+        load_config(&config_dir, project_dir.as_deref())
+            .unwrap_or_else(|_e| smash_config::Config::default())
+    });
+
+    // ── Single-instance forwarding ─────────────────────────────────────────
+    // Unix domain sockets only; the option is ignored elsewhere.
+    let instance_socket = match (&project_dir, config.editor.single_instance) {
+        (Some(workspace), true) if !git_editor && cfg!(unix) => {
+            Some(instance::socket_path(&paths.data_dir(), workspace))
+        }
+        _ => None,
+    };
 
     // ── Logging initialisation (REQ-NFR-020, REQ-NFR-021) ──────────────────
     let log_path = config.log.file.clone().unwrap_or_else(|| {
@@ -39,13 +76,7 @@ pub(crate) fn run_editor(file: Option<PathBuf>) -> Result<()> {
     )
     .ok();
 
-    let filter_str = match &config.log.level {
-        smash_config::config::LogLevel::Trace => "trace",
-        smash_config::config::LogLevel::Debug => "debug",
-        smash_config::config::LogLevel::Info => "info",
-        smash_config::config::LogLevel::Warn => "warn",
-        smash_config::config::LogLevel::Error => "error",
-    };
+    let filter_str = config_log_filter(&config);
 
     let log_file = std::fs::OpenOptions::new()
         .create(true)
@@ -53,17 +84,51 @@ pub(crate) fn run_editor(file: Option<PathBuf>) -> Result<()> {
         .open(&log_path)
         .unwrap_or_else(|_| std::fs::File::create("/dev/null").expect("cannot open /dev/null"));
 
-    let env_filter = tracing_subscriber::EnvFilter::try_new(filter_str)
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
-
-    tracing_subscriber::fmt()
-        .with_writer(std::sync::Mutex::new(log_file))
-        .with_ansi(false)
-        .with_env_filter(env_filter)
+    let (env_filter, filter_str) = match tracing_subscriber::EnvFilter::try_new(&filter_str) {
+        Ok(filter) => (filter, filter_str),
+        Err(_) => (
+            tracing_subscriber::EnvFilter::new("info"),
+            "info".to_string(),
+        ),
+    };
+    let (filter_layer, filter_handle) = tracing_subscriber::reload::Layer::new(env_filter);
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::sync::Mutex::new(log_file))
+                .with_ansi(false),
+        )
         .init();
 
-    info!("smash starting – log level: {}", filter_str);
+    info!("smash starting – log filter: {}", filter_str);
+    for moved in &migrations {
+        match &moved.error {
+            None => info!("moved {} to {}", moved.from.display(), moved.to.display()),
+            Some(e) => warn!(
+                "could not fully move {} to {}: {}",
+                moved.from.display(),
+                moved.to.display(),
+                e
+            ),
+        }
+    }
 
+    // Forward only once logging is up, so the hand-off is recorded.
+    if let (Some(socket), Some(path)) = (&instance_socket, &file) {
+        let abs = project_dir
+            .as_deref()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|| path.clone());
+        let request = OpenRequest::new(abs, line);
+        if let Ok(true) = instance::forward(socket, &request) {
+            info!("opened {} in running instance", path.display());
+            return Ok(0);
+        }
+    }
+
+    let platform_started = Instant::now();
     let _platform = Platform::default_platform().context("failed to initialize platform")?;
 
     let (width, height) = crossterm::terminal::size().context("failed to get terminal size")?;
@@ -78,23 +143,99 @@ pub(crate) fn run_editor(file: Option<PathBuf>) -> Result<()> {
         .enable_all()
         .build()
         .context("failed to create tokio runtime")?;
+    platform_time += platform_started.elapsed();
+    profile.record(StartupPhase::PlatformInit, platform_time);
 
     runtime.spawn(crate::lsp_task::lsp_manager_task(lsp_cmd_rx, lsp_evt_tx));
 
-    let mut app = App::new(
-        width,
-        height,
-        file,
-        &config.keymap.preset,
-        lsp_cmd_tx.clone(),
-        lsp_evt_rx,
-        config.lsp.enabled,
-        config.lsp.servers.clone(),
-        config.editor.option_as_alt,
-    )?;
+    // ── Session restore ───────────────────────────────────────────────────
+    let session_path = paths.state_dir().join(SESSION_FILE);
+    let session = if file.is_none() && (restore || config.editor.restore_session) {
+        match Session::load(&session_path) {
+            Ok(session) => session,
+            Err(e) => {
+                warn!("ignoring session {}: {}", session_path.display(), e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    if let Some(saved) = session.as_ref().and_then(Session::active) {
+        if saved.path.exists() {
+            info!("restoring session: {}", saved.path.display());
+            file = Some(saved.path.clone());
+        }
+    }
+
+    let mut app = profile.time(StartupPhase::BufferOpen, || {
+        App::new(
+            width,
+            height,
+            file,
+            &config.keymap.preset,
+            lsp_cmd_tx.clone(),
+            lsp_evt_rx,
+            config.lsp.enabled,
+            config.lsp.servers.clone(),
+            config.editor.option_as_alt,
+        )
+    })?;
+    app.startup_profile = profile;
+
+    app.render_stats = RenderStats::new(started);
+    app.use_system_clipboard(&config.editor.clipboard);
+    app.log_filter = Some(LogFilter::new(filter_handle, filter_str));
+    // Before applying the config, which looks up themes in the config dir.
+    app.config_paths = Some(ConfigPaths {
+        config_dir: config_dir.clone(),
+        project_dir: project_dir.clone(),
+        log_file: log_path.clone(),
+    });
+    app.apply_config(&config);
+
+    if let Some(session) = &session {
+        app.restore_session(session);
+    }
+    let search_history_path = paths.state_dir().join(SEARCH_HISTORY_FILE);
+    match SearchHistory::load(&search_history_path) {
+        Ok(history) => app.search_history = history,
+        Err(e) => warn!(
+            "ignoring search history {}: {}",
+            search_history_path.display(),
+            e
+        ),
+    }
+    let recent_files_path = paths.state_dir().join(RECENT_FILES_FILE);
+    match RecentFiles::load(&recent_files_path) {
+        Ok(recent) => app.recent_files = recent,
+        Err(e) => warn!(
+            "ignoring recent files {}: {}",
+            recent_files_path.display(),
+            e
+        ),
+    }
+    if let Some(path) = app.buffer.path().map(std::path::Path::to_path_buf) {
+        app.note_recent_file(&path);
+    }
+    if let Some(line) = line {
+        app.confirm_goto_line(&line.to_string());
+    }
 
-    // Start LSP for initial file if configured
-    app.start_lsp_for_current_file();
+    let server = instance_socket.and_then(|socket| match InstanceServer::bind(&socket) {
+        Ok(server) => {
+            info!("single-instance server listening on {}", socket.display());
+            Some(server)
+        }
+        Err(e) => {
+            warn!("single-instance server unavailable: {}", e);
+            None
+        }
+    });
+
+    // Left for after the first frame, which only needs the buffer
+    app.queue_idle(IdleTask::StartLsp);
+    app.queue_idle(IdleTask::IndexFiles);
 
     crossterm::terminal::enable_raw_mode()?;
     crossterm::execute!(
@@ -105,11 +246,34 @@ pub(crate) fn run_editor(file: Option<PathBuf>) -> Result<()> {
 
     let mut backend = CrosstermBackend::new();
 
+    let render_started = Instant::now();
     if let Err(e) = app.render(&mut backend) {
         error!("render error: {}", e);
     }
+    app.startup_profile
+        .record(StartupPhase::FirstRender, render_started.elapsed());
 
-    run_event_loop(&mut app, &mut backend)?;
+    run_event_loop(&mut app, &mut backend, server.as_ref())?;
+
+    if !git_editor {
+        if let Err(e) = app.session(project_dir.clone()).save(&session_path) {
+            warn!("failed to save session {}: {}", session_path.display(), e);
+        }
+        if let Err(e) = app.search_history.save(&search_history_path) {
+            warn!(
+                "failed to save search history {}: {}",
+                search_history_path.display(),
+                e
+            );
+        }
+        if let Err(e) = app.recent_files.save(&recent_files_path) {
+            warn!(
+                "failed to save recent files {}: {}",
+                recent_files_path.display(),
+                e
+            );
+        }
+    }
 
     crossterm::execute!(
         std::io::stdout(),
@@ -123,13 +287,40 @@ pub(crate) fn run_editor(file: Option<PathBuf>) -> Result<()> {
     drop(lsp_cmd_tx);
     runtime.shutdown_timeout(Duration::from_secs(2));
 
-    info!("smash exited cleanly");
-    Ok(())
+    if profile_startup {
+        print!("{}", app.startup_profile.report());
+    }
+    info!("smash exited with status {}", app.exit_code);
+    Ok(app.exit_code)
 }
 
 /// Main event loop — poll for terminal events and LSP updates.
-fn run_event_loop(app: &mut App, backend: &mut CrosstermBackend) -> Result<()> {
+fn run_event_loop(
+    app: &mut App,
+    backend: &mut CrosstermBackend,
+    server: Option<&InstanceServer>,
+) -> Result<()> {
     while app.running {
+        // Draw what changed, at most once per frame
+        if app.render_scheduler.fire(Instant::now()) {
+            if let Err(e) = app.render(backend) {
+                error!("render error: {}", e);
+            }
+        }
+
+        // Open files forwarded by other `smash` invocations
+        let forwarded = server.map(InstanceServer::poll).unwrap_or_default();
+        if !forwarded.is_empty() {
+            for req in forwarded {
+                info!("received forwarded open: {}", req.path.display());
+                app.open_forwarded(req);
+            }
+            // A terminal application cannot raise its own window, so the
+            // bell is all we can do to draw attention to the new file.
+            let _ = std::io::Write::write_all(&mut std::io::stdout(), b"\x07");
+            app.render_scheduler.mark_dirty();
+        }
+
         // Drain any pending LSP events
         let mut had_lsp_event = false;
         while let Ok(evt) = app.lsp_evt_rx.try_recv() {
@@ -137,21 +328,101 @@ fn run_event_loop(app: &mut App, backend: &mut CrosstermBackend) -> Result<()> {
             had_lsp_event = true;
         }
         if had_lsp_event {
-            if let Err(e) = app.render(backend) {
-                error!("render error: {}", e);
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Stream output from the watch command
+        if app.poll_watch() {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Stream output from the running task
+        if app.poll_task() {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Use the output of a finished shell command
+        if app.poll_shell() {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Fill the results pane of a running workspace search
+        if app.poll_workspace_search() {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Search the files the background index has found so far
+        if app.poll_file_index() {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Colour lines the background highlighter has finished
+        if app.poll_highlights() {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Show the blame of the cursor line once git has worked it out
+        if app.poll_git_blame(Instant::now()) {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Mark lines changed since the last commit on the scrollbar
+        if app.poll_git_changes(Instant::now()) {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Reload (or ask about) the open file if another program changed it
+        if app.poll_disk_changes(Instant::now()) {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Finish (or show progress of) a file being opened
+        if app.poll_pending_open() {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Draw new output from the integrated terminal
+        if app.poll_terminal() {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Ask for completions once typing pauses
+        let now = Instant::now();
+        app.poll_completion(now);
+
+        // Run idle callbacks once input pauses
+        app.poll_input_idle(now);
+
+        // Stop waiting on a server that has not sent its pre-save edits
+        if app.poll_pending_save(now) {
+            app.render_scheduler.mark_dirty();
+        }
+
+        // Run deferred startup work while no input is waiting
+        if app.has_idle_tasks() && !event::poll(Duration::ZERO)? {
+            if app.run_idle_task() {
+                app.render_scheduler.mark_dirty();
             }
+            continue;
         }
 
-        if event::poll(Duration::from_millis(50))? {
+        // Keep draining a busy terminal instead of waiting for input, and
+        // wake up in time for the next frame or debounced action.
+        let wait = if app.terminal_backlogged() {
+            Duration::ZERO
+        } else {
+            app.next_timer(now).map_or(Duration::from_millis(50), |t| {
+                t.min(Duration::from_millis(50))
+            })
+        };
+        if event::poll(wait)? {
             let raw_event = event::read()?;
+            app.note_input(Instant::now());
 
             if let Event::Resize(w, h) = raw_event {
                 app.viewport
                     .resize(h.saturating_sub(1) as usize, w as usize);
-                app.renderer.resize(w, h);
-                if let Err(e) = app.render(backend) {
-                    error!("render error: {}", e);
-                }
+                app.render_scheduler.mark_dirty();
                 continue;
             }
 
@@ -167,14 +438,61 @@ fn run_event_loop(app: &mut App, backend: &mut CrosstermBackend) -> Result<()> {
                     input
                 };
 
+                // Any key closes the popup; Esc only closes it
+                if let smash_input::InputEvent::Key(ke) = &input {
+                    if app.dismiss_popup(ke) {
+                        app.render_scheduler.mark_dirty();
+                        continue;
+                    }
+                }
+
+                if let smash_input::InputEvent::Mouse(me) = &input {
+                    app.close_signature_help();
+                    if app.handle_mouse(me) {
+                        app.render_scheduler.mark_dirty();
+                        continue;
+                    }
+                }
+
+                // Plain keys go to the focused file tree or results pane
+                if let smash_input::InputEvent::Key(ke) = &input {
+                    if app.handle_file_tree_key(ke) || app.handle_search_results_key(ke) {
+                        app.render_scheduler.mark_dirty();
+                        continue;
+                    }
+                }
+
+                // Keys go to the focused terminal, except the toggle key
+                if let smash_input::InputEvent::Key(ke) = &input {
+                    if app.handle_terminal_key(ke) {
+                        app.render_scheduler.mark_dirty();
+                        continue;
+                    }
+                }
+
                 // Handle Esc to cancel prompts
                 if let smash_input::InputEvent::Key(ke) = &input {
+                    if ke.key == smash_input::Key::Esc {
+                        app.close_signature_help();
+                    }
                     if ke.key == smash_input::Key::Esc && app.input_mode != InputMode::Normal {
+                        app.finish_replace();
                         app.input_mode = InputMode::Normal;
                         app.prompt_input.clear();
-                        if let Err(e) = app.render(backend) {
-                            error!("render error: {}", e);
-                        }
+                        app.render_scheduler.mark_dirty();
+                        continue;
+                    }
+                    // Esc with several cursors goes back to one
+                    if ke.key == smash_input::Key::Esc && app.collapse_cursors() {
+                        app.render_scheduler.mark_dirty();
+                        continue;
+                    }
+                }
+
+                // The vim preset handles Normal/Visual keys itself
+                if let smash_input::InputEvent::Key(ke) = &input {
+                    if app.handle_vim_key(ke) {
+                        app.render_scheduler.mark_dirty();
                         continue;
                     }
                 }
@@ -190,9 +508,7 @@ fn run_event_loop(app: &mut App, backend: &mut CrosstermBackend) -> Result<()> {
                 }
             }
 
-            if let Err(e) = app.render(backend) {
-                error!("render error: {}", e);
-            }
+            app.render_scheduler.mark_dirty();
         }
     }
     Ok(())
@@ -0,0 +1,240 @@
+//! A shell command run in the background with its output collected
+//! line by line, for the watch and task panes.
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use smash_platform::shell_command;
+use tracing::{info, warn};
+
+/// Maximum output lines kept for a pane; older lines are dropped.
+const MAX_OUTPUT_LINES: usize = 2000;
+
+/// Outcome of the most recent run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JobStatus {
+    /// Not started yet.
+    Idle,
+    Running,
+    Succeeded,
+    /// Finished unsuccessfully; holds the exit code if there was one.
+    Failed(Option<i32>),
+}
+
+impl JobStatus {
+    /// `idle`, `running`, `ok` or `failed (code)`.
+    pub(crate) fn label(self) -> String {
+        match self {
+            JobStatus::Idle => "idle".to_string(),
+            JobStatus::Running => "running".to_string(),
+            JobStatus::Succeeded => "ok".to_string(),
+            JobStatus::Failed(Some(code)) => format!("failed ({})", code),
+            JobStatus::Failed(None) => "failed".to_string(),
+        }
+    }
+}
+
+/// A line of output tagged with the run that produced it, or the end of
+/// one of that run's output streams.
+enum JobOutput {
+    Line(u64, String),
+    Eof(u64),
+}
+
+/// A shell command that can be run again and again, keeping the output
+/// of the latest run.
+pub(crate) struct OutputJob {
+    command: String,
+    status: JobStatus,
+    output: Vec<String>,
+    child: Option<Child>,
+    /// Incremented per run so output from a killed run is ignored.
+    generation: u64,
+    open_streams: usize,
+    tx: Sender<JobOutput>,
+    rx: Receiver<JobOutput>,
+}
+
+impl OutputJob {
+    pub(crate) fn new(command: impl Into<String>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            command: command.into(),
+            status: JobStatus::Idle,
+            output: Vec::new(),
+            child: None,
+            generation: 0,
+            open_streams: 0,
+            tx,
+            rx,
+        }
+    }
+
+    pub(crate) fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub(crate) fn status(&self) -> JobStatus {
+        self.status
+    }
+
+    /// The command line, then what the latest run wrote to stdout and
+    /// stderr, interleaved as it arrived.
+    pub(crate) fn output(&self) -> &[String] {
+        &self.output
+    }
+
+    /// Start a run, killing the previous one if it is still going.
+    pub(crate) fn start(&mut self) {
+        self.kill();
+        self.generation += 1;
+        self.output.clear();
+        self.output.push(format!("$ {}", self.command));
+
+        let mut cmd = shell_command(&self.command);
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        match cmd.spawn() {
+            Ok(mut child) => {
+                info!("job: running `{}`", self.command);
+                self.open_streams = 0;
+                if let Some(out) = child.stdout.take() {
+                    self.spawn_reader(out);
+                }
+                if let Some(err) = child.stderr.take() {
+                    self.spawn_reader(err);
+                }
+                self.child = Some(child);
+                self.status = JobStatus::Running;
+            }
+            Err(e) => {
+                warn!("job: failed to spawn `{}`: {}", self.command, e);
+                self.output.push(format!("failed to start: {}", e));
+                self.status = JobStatus::Failed(None);
+            }
+        }
+    }
+
+    fn spawn_reader(&mut self, stream: impl Read + Send + 'static) {
+        let tx = self.tx.clone();
+        let generation = self.generation;
+        self.open_streams += 1;
+        std::thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(JobOutput::Line(generation, line)).is_err() {
+                    return;
+                }
+            }
+            let _ = tx.send(JobOutput::Eof(generation));
+        });
+    }
+
+    /// Collect new output and detect completion. Returns `true` if
+    /// anything visible changed.
+    pub(crate) fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(msg) = self.rx.try_recv() {
+            match msg {
+                JobOutput::Line(gen, line) if gen == self.generation => {
+                    self.output.push(line);
+                    changed = true;
+                }
+                JobOutput::Eof(gen) if gen == self.generation => {
+                    self.open_streams = self.open_streams.saturating_sub(1);
+                }
+                _ => {}
+            }
+        }
+        if self.output.len() > MAX_OUTPUT_LINES {
+            let excess = self.output.len() - MAX_OUTPUT_LINES;
+            self.output.drain(..excess);
+        }
+
+        // Only report completion once all output has been read.
+        if self.open_streams == 0 {
+            if let Some(child) = self.child.as_mut() {
+                if let Ok(Some(status)) = child.try_wait() {
+                    self.finish(status);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    fn finish(&mut self, status: ExitStatus) {
+        self.child = None;
+        self.status = if status.success() {
+            JobStatus::Succeeded
+        } else {
+            JobStatus::Failed(status.code())
+        };
+        info!("job: `{}` finished: {}", self.command, status);
+    }
+
+    fn kill(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for OutputJob {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn run_to_completion(job: &mut OutputJob) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while job.status() == JobStatus::Running {
+            assert!(Instant::now() < deadline, "command timed out");
+            job.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn successful_run_collects_output() {
+        let mut job = OutputJob::new("echo out; echo err >&2");
+        assert_eq!(job.status(), JobStatus::Idle);
+        job.start();
+        run_to_completion(&mut job);
+        assert_eq!(job.status(), JobStatus::Succeeded);
+        assert_eq!(job.output()[0], "$ echo out; echo err >&2");
+        assert!(job.output().iter().any(|l| l == "out"));
+        assert!(job.output().iter().any(|l| l == "err"));
+        assert_eq!(job.status().label(), "ok");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_run_reports_exit_code() {
+        let mut job = OutputJob::new("exit 3");
+        job.start();
+        run_to_completion(&mut job);
+        assert_eq!(job.status(), JobStatus::Failed(Some(3)));
+        assert_eq!(job.status().label(), "failed (3)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restart_discards_previous_run() {
+        let mut job = OutputJob::new("sleep 5; echo stale");
+        job.start();
+        job.command = "echo fresh".to_string();
+        job.start();
+        run_to_completion(&mut job);
+        assert_eq!(job.output(), ["$ echo fresh", "fresh"]);
+    }
+}
@@ -0,0 +1,75 @@
+//! Runtime control of the `tracing` filter.
+//!
+//! The subscriber is installed with a reloadable `EnvFilter`; this wrapper
+//! keeps its handle so the editor can swap filters (e.g.
+//! `info,smash_lsp::rpc=debug`) without restarting.
+
+use smash_config::config::LogLevel;
+use smash_config::Config;
+use smash_core::logging::{build_log_filter, parse_log_filter};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Handle to the installed filter plus the spec it was built from.
+pub(crate) struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    current: String,
+}
+
+impl LogFilter {
+    pub(crate) fn new(handle: reload::Handle<EnvFilter, Registry>, current: String) -> Self {
+        Self { handle, current }
+    }
+
+    /// The active filter spec.
+    pub(crate) fn current(&self) -> &str {
+        &self.current
+    }
+
+    /// Parse `spec` and install it as the new filter.
+    pub(crate) fn set(&mut self, spec: &str) -> Result<(), String> {
+        let spec = parse_log_filter(spec)?;
+        let filter = EnvFilter::try_new(&spec).map_err(|e| e.to_string())?;
+        self.handle.reload(filter).map_err(|e| e.to_string())?;
+        self.current = spec;
+        Ok(())
+    }
+}
+
+/// Filter spec for the `[log]` section: the level plus any targets.
+pub(crate) fn config_log_filter(config: &Config) -> String {
+    let level = match config.log.level {
+        LogLevel::Trace => "trace",
+        LogLevel::Debug => "debug",
+        LogLevel::Info => "info",
+        LogLevel::Warn => "warn",
+        LogLevel::Error => "error",
+    };
+    build_log_filter(level, &config.log.targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_replaces_filter_and_rejects_bad_specs() {
+        let (_layer, handle) = reload::Layer::<EnvFilter, Registry>::new(EnvFilter::new("info"));
+        let mut filter = LogFilter::new(handle, "info".to_string());
+
+        filter
+            .set("warn, smash_lsp::rpc=DEBUG")
+            .expect("valid spec");
+        assert_eq!(filter.current(), "warn,smash_lsp::rpc=debug");
+
+        assert!(filter.set("chatty").is_err());
+        assert_eq!(filter.current(), "warn,smash_lsp::rpc=debug");
+    }
+
+    #[test]
+    fn config_filter_includes_targets() {
+        let mut config = Config::default();
+        config.log.level = LogLevel::Warn;
+        config.log.targets = vec!["smash_terminal::pty=trace".to_string()];
+        assert_eq!(config_log_filter(&config), "warn,smash_terminal::pty=trace");
+    }
+}
@@ -2,9 +2,10 @@ use std::sync::Arc;
 
 use tokio::sync::Mutex as TokioMutex;
 
-use smash_lsp::LspRegistry;
+use smash_lsp::dispatcher::{INVALID_PARAMS, METHOD_NOT_FOUND};
+use smash_lsp::{LspFeature, LspRegistry, ServerMessage, ServerRequest};
 
-use crate::lsp_types::{LspCommand, LspEvent};
+use crate::lsp_types::{CallDirection, LspCommand, LspEvent};
 
 /// Async task that manages LSP servers and processes commands.
 ///
@@ -15,45 +16,76 @@ pub(crate) async fn lsp_manager_task(
     evt_tx: std::sync::mpsc::Sender<LspEvent>,
 ) {
     let registry = Arc::new(TokioMutex::new(LspRegistry::new()));
+    let (sync_tx, sync_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(document_sync_task(
+        sync_rx,
+        registry.clone(),
+        evt_tx.clone(),
+    ));
 
     while let Some(cmd) = cmd_rx.recv().await {
         match cmd {
             LspCommand::StartServer(config) => {
                 handle_start_server(config, &registry, &evt_tx);
             }
-            LspCommand::DidOpen {
-                uri,
-                text,
-                language_id,
-            } => {
-                handle_did_open(uri, text, language_id, &registry, &evt_tx);
-            }
-            LspCommand::DidChange { uri, version, text } => {
-                handle_did_change(uri, version, text, &registry);
-            }
-            LspCommand::DidSave { uri } => {
-                handle_did_save(uri, &registry);
-            }
-            LspCommand::DidClose { uri } => {
-                handle_did_close(uri, &registry);
+            cmd @ (LspCommand::DidOpen { .. }
+            | LspCommand::DidChange { .. }
+            | LspCommand::DidSave { .. }
+            | LspCommand::DidClose { .. }
+            | LspCommand::WillSaveWaitUntil { .. }
+            | LspCommand::OrganizeImports { .. }
+            | LspCommand::Completion { .. }
+            | LspCommand::SignatureHelp { .. }
+            | LspCommand::ExecuteCommand { .. }) => {
+                let _ = sync_tx.send(cmd);
             }
             LspCommand::Hover { uri, position } => {
                 handle_hover(uri, position, &registry, &evt_tx);
             }
-            LspCommand::GotoDefinition { uri, position } => {
-                handle_goto_definition(uri, position, &registry, &evt_tx);
+            LspCommand::GotoDefinition {
+                uri,
+                position,
+                target,
+            } => {
+                handle_goto_definition(uri, position, target, &registry, &evt_tx);
             }
             LspCommand::FindReferences { uri, position } => {
                 handle_find_references(uri, position, &registry, &evt_tx);
             }
-            LspCommand::Completion { uri, position } => {
-                handle_completion(uri, position, &registry, &evt_tx);
+            LspCommand::DocumentSymbols { uri } => {
+                handle_document_symbols(uri, &registry, &evt_tx);
+            }
+            LspCommand::PrepareCallHierarchy {
+                uri,
+                position,
+                direction,
+            } => {
+                handle_prepare_call_hierarchy(uri, position, direction, &registry, &evt_tx);
+            }
+            LspCommand::CallHierarchyCalls {
+                uri,
+                item,
+                direction,
+                node,
+            } => {
+                handle_call_hierarchy_calls(uri, item, direction, node, &registry, &evt_tx);
+            }
+            LspCommand::WorkspaceSymbols { query, language_id } => {
+                handle_workspace_symbols(query, language_id, &registry, &evt_tx);
             }
             LspCommand::Format { uri } => {
                 handle_format(uri, &registry, &evt_tx);
             }
-            LspCommand::CodeAction { uri, range } => {
-                handle_code_action(uri, range, &registry, &evt_tx);
+            LspCommand::RangeFormat { uri, range } => {
+                handle_range_format(uri, range, &registry, &evt_tx);
+            }
+            LspCommand::CodeAction {
+                uri,
+                range,
+                diagnostics,
+                only,
+            } => {
+                handle_code_action(uri, range, diagnostics, only, &registry, &evt_tx);
             }
             LspCommand::Shutdown => {
                 let mut reg = registry.lock().await;
@@ -65,7 +97,8 @@ pub(crate) async fn lsp_manager_task(
 }
 
 // =========================================================================
-// Individual command handlers (spawned as tokio tasks)
+// Individual command handlers (spawned as tokio tasks, except document
+// sync which is serialized through `document_sync_task`)
 // =========================================================================
 
 fn handle_start_server(
@@ -78,19 +111,69 @@ fn handle_start_server(
     let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let mut reg = registry.lock().await;
-        match reg.start_server(config).await {
-            Ok(_id) => {
-                if let Some(client) = reg.get(&lang) {
-                    let diag_store = client.diagnostics();
-                    let diag_tx = evt_tx.clone();
-                    diag_store.lock().await.set_on_update(move |uri, diags| {
-                        let _ = diag_tx.send(LspEvent::DiagnosticsUpdated {
-                            uri: uri.to_string(),
-                            diagnostics: diags.to_vec(),
+        // Another file of the same language re-attaches to its server.
+        let started = match reg.running(&lang) {
+            Some(_) => Ok(false),
+            None => reg.start_server(config).await.map(|_| true),
+        };
+        match started {
+            Ok(fresh) => {
+                if let Some(client) = reg.get(&lang).filter(|_| fresh) {
+                    if client.config().enables(LspFeature::Diagnostics) {
+                        let diag_tx = evt_tx.clone();
+                        client
+                            .diagnostics()
+                            .lock()
+                            .await
+                            .set_on_update(move |uri, diags| {
+                                let _ = diag_tx.send(LspEvent::DiagnosticsUpdated {
+                                    uri: uri.to_string(),
+                                    diagnostics: diags.to_vec(),
+                                });
+                            });
+                    }
+                    let progress_tx = evt_tx.clone();
+                    client
+                        .progress()
+                        .lock()
+                        .await
+                        .set_on_update(move |progress| {
+                            let _ = progress_tx.send(LspEvent::Progress(progress.cloned()));
                         });
-                    });
+                    let message_tx = evt_tx.clone();
+                    client
+                        .set_notification_handler(Box::new(move |method, params| {
+                            if method != "window/showMessage" {
+                                return;
+                            }
+                            if let Some(message) = ServerMessage::from_params(&params) {
+                                let _ = message_tx.send(LspEvent::ServerMessage(message));
+                            }
+                        }))
+                        .await;
+                    let request_tx = evt_tx.clone();
+                    client
+                        .set_request_handler(Box::new(move |request| {
+                            forward_server_request(request, &request_tx)
+                        }))
+                        .await;
                 }
-                let _ = evt_tx.send(LspEvent::ServerStarted(lang));
+                let caps = reg
+                    .get(&lang)
+                    .map(|c| c.capabilities().clone())
+                    .unwrap_or_default();
+                let _ = evt_tx.send(LspEvent::ServerStarted {
+                    language: lang,
+                    sync: caps.text_document_sync,
+                    save: caps.save,
+                    will_save_wait_until: caps.will_save_wait_until,
+                    formatting: caps.formatting,
+                    range_formatting: caps.range_formatting,
+                    inlay_hints: caps.inlay_hints,
+                    completion_triggers: caps.completion_trigger_characters,
+                    signature_triggers: caps.signature_trigger_characters,
+                    position_encoding: caps.position_encoding,
+                });
             }
             Err(e) => {
                 let _ = evt_tx.send(LspEvent::Error(format!(
@@ -102,73 +185,296 @@ fn handle_start_server(
     });
 }
 
-fn handle_did_open(
+/// Pass requests the editor can answer on to the main thread, and refuse
+/// the rest.
+fn forward_server_request(request: ServerRequest, evt_tx: &std::sync::mpsc::Sender<LspEvent>) {
+    if request.method != "workspace/applyEdit" {
+        let message = format!("unsupported request: {}", request.method);
+        let _ = request.reply_error(METHOD_NOT_FOUND, &message);
+        return;
+    }
+    let label = request.params["label"].as_str().map(str::to_string);
+    match serde_json::from_value(request.params["edit"].clone()) {
+        // If the editor has gone, dropping the request answers it.
+        Ok(edit) => {
+            let _ = evt_tx.send(LspEvent::ApplyEdit {
+                label,
+                edit,
+                request,
+            });
+        }
+        Err(e) => {
+            let message = format!("invalid workspace edit: {}", e);
+            let _ = request.reply_error(INVALID_PARAMS, &message);
+        }
+    }
+}
+
+/// Applies document-sync notifications strictly in the order they were
+/// issued. Incremental `didChange` ranges are only meaningful relative to
+/// the previous change, so these must never race each other.
+async fn document_sync_task(
+    mut sync_rx: tokio::sync::mpsc::UnboundedReceiver<LspCommand>,
+    registry: Arc<TokioMutex<LspRegistry>>,
+    evt_tx: std::sync::mpsc::Sender<LspEvent>,
+) {
+    while let Some(cmd) = sync_rx.recv().await {
+        let mut reg = registry.lock().await;
+        match cmd {
+            LspCommand::DidOpen {
+                uri,
+                text,
+                language_id,
+            } => {
+                reg.open_document(&uri, &language_id);
+                if let Some(client) = reg.running(&language_id) {
+                    if let Err(e) = client.did_open(&uri, &text, &language_id).await {
+                        let _ = evt_tx.send(LspEvent::Error(format!("didOpen: {}", e)));
+                    }
+                }
+            }
+            LspCommand::DidChange {
+                uri,
+                version,
+                changes,
+            } => {
+                if let Some(client) = reg.client_for_document(&uri) {
+                    if let Err(e) = client.did_change(&uri, version, &changes).await {
+                        let _ = evt_tx.send(LspEvent::Error(format!("didChange: {}", e)));
+                    }
+                }
+            }
+            LspCommand::DidSave { uri, text } => {
+                if let Some(client) = reg.client_for_document(&uri) {
+                    let _ = client.did_save(&uri, text.as_deref()).await;
+                }
+            }
+            LspCommand::DidClose { uri } => {
+                if let Some(client) = reg.client_for_document(&uri) {
+                    let _ = client.did_close(&uri).await;
+                }
+                reg.close_document(&uri);
+            }
+            // Asked for right after typing, so these must follow the
+            // didChange carrying that text. Replies are awaited on their
+            // own tasks.
+            LspCommand::Completion { uri, position } => {
+                handle_completion(uri, position, &registry, &evt_tx);
+            }
+            LspCommand::SignatureHelp { uri, position } => {
+                handle_signature_help(uri, position, &registry, &evt_tx);
+            }
+            // Edits must be computed against the text about to be saved.
+            LspCommand::WillSaveWaitUntil { uri, format } => {
+                handle_will_save_wait_until(uri, format, &registry, &evt_tx);
+            }
+            LspCommand::OrganizeImports { uri, range } => {
+                handle_organize_imports(uri, range, &registry, &evt_tx);
+            }
+            // Usually follows an edit the command relies on.
+            LspCommand::ExecuteCommand {
+                command,
+                language_id,
+            } => {
+                handle_execute_command(command, language_id, &registry, &evt_tx);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn handle_signature_help(
     uri: String,
-    text: String,
-    language_id: String,
+    position: smash_lsp::LspPosition,
     registry: &Arc<TokioMutex<LspRegistry>>,
     evt_tx: &std::sync::mpsc::Sender<LspEvent>,
 ) {
     let registry = registry.clone();
     let evt_tx = evt_tx.clone();
-    let lang_id = language_id.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        if let Some(client) = reg.get(&lang_id) {
-            if let Err(e) = client.did_open(&uri, &text, &lang_id).await {
-                let _ = evt_tx.send(LspEvent::Error(format!("didOpen: {}", e)));
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::SignatureHelp) {
+            match client.signature_help(&uri, position).await {
+                Ok(help) => {
+                    let _ = evt_tx.send(LspEvent::SignatureHelpResult(help));
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("signatureHelp: {}", e)));
+                }
             }
         }
     });
 }
 
-fn handle_did_change(
+fn handle_will_save_wait_until(
+    uri: String,
+    format: bool,
+    registry: &Arc<TokioMutex<LspRegistry>>,
+    evt_tx: &std::sync::mpsc::Sender<LspEvent>,
+) {
+    let registry = registry.clone();
+    let evt_tx = evt_tx.clone();
+    tokio::spawn(async move {
+        let reg = registry.lock().await;
+        let feature = if format {
+            LspFeature::Formatting
+        } else {
+            LspFeature::WillSave
+        };
+        let edits = match reg.client_for_feature(&uri, feature) {
+            Some(client) if format => match client.format(&uri).await {
+                Ok(edits) => edits,
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("format on save: {}", e)));
+                    Vec::new()
+                }
+            },
+            Some(client) => match client.will_save_wait_until(&uri).await {
+                Ok(edits) => edits,
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("willSaveWaitUntil: {}", e)));
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        // Always answer: the editor holds the save until this arrives.
+        let _ = evt_tx.send(LspEvent::WillSaveEdits { uri, edits });
+    });
+}
+
+fn handle_organize_imports(
     uri: String,
-    version: i32,
-    text: String,
+    range: smash_lsp::LspRange,
     registry: &Arc<TokioMutex<LspRegistry>>,
+    evt_tx: &std::sync::mpsc::Sender<LspEvent>,
 ) {
     let registry = registry.clone();
+    let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        for lang in reg.active_languages() {
-            if let Some(client) = reg.get(lang) {
-                let _ = client.did_change(&uri, version, &text).await;
-                break;
+        let only = vec![smash_lsp::ORGANIZE_IMPORTS.to_string()];
+        let actions = match reg.client_for_feature(&uri, LspFeature::CodeActions) {
+            Some(client) => match client.code_action(&uri, range, Vec::new(), only).await {
+                Ok(actions) => actions,
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("organize imports: {}", e)));
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        // Actions that only carry a command would edit the file through
+        // the server after it is written, so they are skipped.
+        let edits = actions
+            .into_iter()
+            .filter(|action| action.is_organize_imports())
+            .find_map(|action| action.edit)
+            .map(|edit| edit.edits_for(&uri))
+            .unwrap_or_default();
+        let _ = evt_tx.send(LspEvent::WillSaveEdits { uri, edits });
+    });
+}
+
+fn handle_execute_command(
+    command: smash_lsp::ServerCommand,
+    language_id: Option<String>,
+    registry: &Arc<TokioMutex<LspRegistry>>,
+    evt_tx: &std::sync::mpsc::Sender<LspEvent>,
+) {
+    let registry = registry.clone();
+    let evt_tx = evt_tx.clone();
+    tokio::spawn(async move {
+        // The server may ask for edits while the command runs, so the
+        // registry is not held while waiting.
+        let pending = {
+            let reg = registry.lock().await;
+            language_id
+                .as_deref()
+                .and_then(|lang| reg.running(lang))
+                .map(|client| client.execute_command(&command))
+        };
+        let result = match pending {
+            Some(pending) => pending.await.map(|_| ()).map_err(|e| e.to_string()),
+            None => Err("no LSP server running".to_string()),
+        };
+        let _ = evt_tx.send(LspEvent::CommandFinished { command, result });
+    });
+}
+
+fn handle_hover(
+    uri: String,
+    position: smash_lsp::LspPosition,
+    registry: &Arc<TokioMutex<LspRegistry>>,
+    evt_tx: &std::sync::mpsc::Sender<LspEvent>,
+) {
+    let registry = registry.clone();
+    let evt_tx = evt_tx.clone();
+    tokio::spawn(async move {
+        let reg = registry.lock().await;
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::Hover) {
+            match client.hover(&uri, position).await {
+                Ok(hover) => {
+                    let text = hover.map(|h| h.contents.value);
+                    let _ = evt_tx.send(LspEvent::HoverResult(text));
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("hover: {}", e)));
+                }
             }
         }
     });
 }
 
-fn handle_did_save(uri: String, registry: &Arc<TokioMutex<LspRegistry>>) {
+fn handle_goto_definition(
+    uri: String,
+    position: smash_lsp::LspPosition,
+    target: smash_lsp::GotoTarget,
+    registry: &Arc<TokioMutex<LspRegistry>>,
+    evt_tx: &std::sync::mpsc::Sender<LspEvent>,
+) {
     let registry = registry.clone();
+    let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        for lang in reg.active_languages() {
-            if let Some(client) = reg.get(lang) {
-                let _ = client.did_save(&uri).await;
-                break;
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::Definition) {
+            match client.goto(target, &uri, position).await {
+                Ok(locations) => {
+                    let _ = evt_tx.send(LspEvent::GotoDefinitionResult { target, locations });
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("{}: {}", target.method(), e)));
+                }
             }
         }
     });
 }
 
-fn handle_did_close(uri: String, registry: &Arc<TokioMutex<LspRegistry>>) {
+fn handle_find_references(
+    uri: String,
+    position: smash_lsp::LspPosition,
+    registry: &Arc<TokioMutex<LspRegistry>>,
+    evt_tx: &std::sync::mpsc::Sender<LspEvent>,
+) {
     let registry = registry.clone();
+    let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        for lang in reg.active_languages() {
-            if let Some(client) = reg.get(lang) {
-                let _ = client.did_close(&uri).await;
-                break;
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::References) {
+            match client.find_references(&uri, position).await {
+                Ok(locations) => {
+                    let _ = evt_tx.send(LspEvent::ReferencesResult(locations));
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("findReferences: {}", e)));
+                }
             }
         }
     });
 }
 
-fn handle_hover(
+fn handle_document_symbols(
     uri: String,
-    position: smash_lsp::LspPosition,
     registry: &Arc<TokioMutex<LspRegistry>>,
     evt_tx: &std::sync::mpsc::Sender<LspEvent>,
 ) {
@@ -176,26 +482,23 @@ fn handle_hover(
     let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        for lang in reg.active_languages() {
-            if let Some(client) = reg.get(lang) {
-                match client.hover(&uri, position).await {
-                    Ok(hover) => {
-                        let text = hover.map(|h| h.contents.value);
-                        let _ = evt_tx.send(LspEvent::HoverResult(text));
-                    }
-                    Err(e) => {
-                        let _ = evt_tx.send(LspEvent::Error(format!("hover: {}", e)));
-                    }
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::DocumentSymbols) {
+            match client.document_symbols(&uri).await {
+                Ok(symbols) => {
+                    let _ = evt_tx.send(LspEvent::DocumentSymbolsResult(symbols));
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("documentSymbol: {}", e)));
                 }
-                break;
             }
         }
     });
 }
 
-fn handle_goto_definition(
+fn handle_prepare_call_hierarchy(
     uri: String,
     position: smash_lsp::LspPosition,
+    direction: CallDirection,
     registry: &Arc<TokioMutex<LspRegistry>>,
     evt_tx: &std::sync::mpsc::Sender<LspEvent>,
 ) {
@@ -203,25 +506,24 @@ fn handle_goto_definition(
     let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        for lang in reg.active_languages() {
-            if let Some(client) = reg.get(lang) {
-                match client.goto_definition(&uri, position).await {
-                    Ok(locations) => {
-                        let _ = evt_tx.send(LspEvent::GotoDefinitionResult(locations));
-                    }
-                    Err(e) => {
-                        let _ = evt_tx.send(LspEvent::Error(format!("gotoDefinition: {}", e)));
-                    }
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::CallHierarchy) {
+            match client.prepare_call_hierarchy(&uri, position).await {
+                Ok(items) => {
+                    let _ = evt_tx.send(LspEvent::CallHierarchyPrepared { direction, items });
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("prepareCallHierarchy: {}", e)));
                 }
-                break;
             }
         }
     });
 }
 
-fn handle_find_references(
+fn handle_call_hierarchy_calls(
     uri: String,
-    position: smash_lsp::LspPosition,
+    item: smash_lsp::CallHierarchyItem,
+    direction: CallDirection,
+    node: u64,
     registry: &Arc<TokioMutex<LspRegistry>>,
     evt_tx: &std::sync::mpsc::Sender<LspEvent>,
 ) {
@@ -229,17 +531,50 @@ fn handle_find_references(
     let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        for lang in reg.active_languages() {
-            if let Some(client) = reg.get(lang) {
-                match client.find_references(&uri, position).await {
-                    Ok(locations) => {
-                        let _ = evt_tx.send(LspEvent::ReferencesResult(locations));
-                    }
-                    Err(e) => {
-                        let _ = evt_tx.send(LspEvent::Error(format!("findReferences: {}", e)));
-                    }
+        let Some(client) = reg.client_for_feature(&uri, LspFeature::CallHierarchy) else {
+            return;
+        };
+        let items = match direction {
+            CallDirection::Incoming => client
+                .incoming_calls(&item)
+                .await
+                .map(|calls| calls.into_iter().map(|c| c.from).collect()),
+            CallDirection::Outgoing => client
+                .outgoing_calls(&item)
+                .await
+                .map(|calls| calls.into_iter().map(|c| c.to).collect()),
+        };
+        // A failed request still settles the node, as having no calls.
+        let items = items.unwrap_or_else(|e| {
+            let _ = evt_tx.send(LspEvent::Error(format!("callHierarchy: {}", e)));
+            Vec::new()
+        });
+        let _ = evt_tx.send(LspEvent::CallHierarchyCalls { node, items });
+    });
+}
+
+fn handle_workspace_symbols(
+    query: String,
+    language_id: Option<String>,
+    registry: &Arc<TokioMutex<LspRegistry>>,
+    evt_tx: &std::sync::mpsc::Sender<LspEvent>,
+) {
+    let registry = registry.clone();
+    let evt_tx = evt_tx.clone();
+    tokio::spawn(async move {
+        let reg = registry.lock().await;
+        let client = language_id
+            .as_deref()
+            .and_then(|lang| reg.running(lang))
+            .filter(|client| client.config().enables(LspFeature::WorkspaceSymbols));
+        if let Some(client) = client {
+            match client.workspace_symbols(&query).await {
+                Ok(symbols) => {
+                    let _ = evt_tx.send(LspEvent::WorkspaceSymbolsResult { query, symbols });
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("workspace/symbol: {}", e)));
                 }
-                break;
             }
         }
     });
@@ -255,17 +590,14 @@ fn handle_completion(
     let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        for lang in reg.active_languages() {
-            if let Some(client) = reg.get(lang) {
-                match client.completion(&uri, position).await {
-                    Ok(items) => {
-                        let _ = evt_tx.send(LspEvent::CompletionResult(items));
-                    }
-                    Err(e) => {
-                        let _ = evt_tx.send(LspEvent::Error(format!("completion: {}", e)));
-                    }
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::Completion) {
+            match client.completion(&uri, position).await {
+                Ok(items) => {
+                    let _ = evt_tx.send(LspEvent::CompletionResult(items));
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("completion: {}", e)));
                 }
-                break;
             }
         }
     });
@@ -280,17 +612,37 @@ fn handle_format(
     let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        for lang in reg.active_languages() {
-            if let Some(client) = reg.get(lang) {
-                match client.format(&uri).await {
-                    Ok(edits) => {
-                        let _ = evt_tx.send(LspEvent::FormatResult(edits));
-                    }
-                    Err(e) => {
-                        let _ = evt_tx.send(LspEvent::Error(format!("format: {}", e)));
-                    }
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::Formatting) {
+            match client.format(&uri).await {
+                Ok(edits) => {
+                    let _ = evt_tx.send(LspEvent::FormatResult(edits));
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("format: {}", e)));
+                }
+            }
+        }
+    });
+}
+
+fn handle_range_format(
+    uri: String,
+    range: smash_lsp::LspRange,
+    registry: &Arc<TokioMutex<LspRegistry>>,
+    evt_tx: &std::sync::mpsc::Sender<LspEvent>,
+) {
+    let registry = registry.clone();
+    let evt_tx = evt_tx.clone();
+    tokio::spawn(async move {
+        let reg = registry.lock().await;
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::Formatting) {
+            match client.range_format(&uri, range).await {
+                Ok(edits) => {
+                    let _ = evt_tx.send(LspEvent::FormatResult(edits));
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("range format: {}", e)));
                 }
-                break;
             }
         }
     });
@@ -299,6 +651,8 @@ fn handle_format(
 fn handle_code_action(
     uri: String,
     range: smash_lsp::LspRange,
+    diagnostics: Vec<smash_lsp::Diagnostic>,
+    only: Vec<String>,
     registry: &Arc<TokioMutex<LspRegistry>>,
     evt_tx: &std::sync::mpsc::Sender<LspEvent>,
 ) {
@@ -306,17 +660,14 @@ fn handle_code_action(
     let evt_tx = evt_tx.clone();
     tokio::spawn(async move {
         let reg = registry.lock().await;
-        for lang in reg.active_languages() {
-            if let Some(client) = reg.get(lang) {
-                match client.code_action(&uri, range, vec![]).await {
-                    Ok(actions) => {
-                        let _ = evt_tx.send(LspEvent::CodeActionResult(actions));
-                    }
-                    Err(e) => {
-                        let _ = evt_tx.send(LspEvent::Error(format!("codeAction: {}", e)));
-                    }
+        if let Some(client) = reg.client_for_feature(&uri, LspFeature::CodeActions) {
+            match client.code_action(&uri, range, diagnostics, only).await {
+                Ok(actions) => {
+                    let _ = evt_tx.send(LspEvent::CodeActionResult(actions));
+                }
+                Err(e) => {
+                    let _ = evt_tx.send(LspEvent::Error(format!("codeAction: {}", e)));
                 }
-                break;
             }
         }
     });
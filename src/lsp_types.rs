@@ -1,27 +1,100 @@
-use smash_lsp::{CompletionItem, Diagnostic, LspPosition, LspRange, LspServerConfig};
+use smash_lsp::{
+    CallHierarchyItem, CompletionItem, Diagnostic, GotoTarget, LspPosition, LspRange,
+    LspServerConfig, PositionEncoding, SaveNotification, TextDocumentContentChangeEvent,
+    TextDocumentSyncKind,
+};
+
+/// Which way a call hierarchy goes from a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CallDirection {
+    /// Its callers.
+    Incoming,
+    /// The functions it calls.
+    Outgoing,
+}
 
 /// Events sent from the async LSP task back to the main thread.
 #[allow(dead_code)]
 pub(crate) enum LspEvent {
-    /// LSP server started for a language.
-    ServerStarted(String),
+    /// LSP server started for a language, with its negotiated sync and
+    /// save handling, whether it formats documents and ranges and offers
+    /// inlay hints, and the
+    /// characters that trigger completion and signature help.
+    ServerStarted {
+        language: String,
+        sync: TextDocumentSyncKind,
+        save: SaveNotification,
+        will_save_wait_until: bool,
+        formatting: bool,
+        range_formatting: bool,
+        inlay_hints: bool,
+        completion_triggers: Vec<String>,
+        signature_triggers: Vec<String>,
+        position_encoding: PositionEncoding,
+    },
     /// Hover result (text to display).
     HoverResult(Option<String>),
-    /// Go-to-definition result (locations).
-    GotoDefinitionResult(Vec<smash_lsp::Location>),
+    /// Go-to-definition result (locations), or of a go-to type
+    /// definition, implementation or declaration.
+    GotoDefinitionResult {
+        target: GotoTarget,
+        locations: Vec<smash_lsp::Location>,
+    },
     /// Find-references result.
     ReferencesResult(Vec<smash_lsp::Location>),
+    /// Symbols in a document, flattened with their containers.
+    DocumentSymbolsResult(Vec<smash_lsp::SymbolInformation>),
+    /// Workspace symbols matching `query`.
+    WorkspaceSymbolsResult {
+        query: String,
+        symbols: Vec<smash_lsp::SymbolInformation>,
+    },
+    /// The functions at the cursor to start a call hierarchy from.
+    CallHierarchyPrepared {
+        direction: CallDirection,
+        items: Vec<CallHierarchyItem>,
+    },
+    /// Callers or callees of the call hierarchy node `node`.
+    CallHierarchyCalls {
+        node: u64,
+        items: Vec<CallHierarchyItem>,
+    },
+    /// Signatures of the call around the cursor, if it is in one.
+    SignatureHelpResult(Option<smash_lsp::SignatureHelp>),
     /// Completion result.
     CompletionResult(Vec<CompletionItem>),
     /// Format result (text edits).
     FormatResult(Vec<smash_lsp::TextEdit>),
+    /// Edits to make to `uri` before it is written; empty if the server
+    /// had none or failed to answer.
+    WillSaveEdits {
+        uri: String,
+        edits: Vec<smash_lsp::TextEdit>,
+    },
     /// Code actions available.
     CodeActionResult(Vec<smash_lsp::CodeAction>),
+    /// The server asks for `edit` to be applied (`workspace/applyEdit`),
+    /// typically while running a command. `request` must be answered.
+    ApplyEdit {
+        label: Option<String>,
+        edit: smash_lsp::WorkspaceEdit,
+        request: smash_lsp::ServerRequest,
+    },
+    /// A command sent with `ExecuteCommand` finished, or failed.
+    CommandFinished {
+        command: smash_lsp::ServerCommand,
+        result: Result<(), String>,
+    },
     /// Diagnostics updated for a URI.
     DiagnosticsUpdated {
         uri: String,
         diagnostics: Vec<Diagnostic>,
     },
+    /// The newest operation the server reports progress for changed, or
+    /// `None` once nothing is in progress.
+    Progress(Option<smash_lsp::Progress>),
+    /// The server asks for a message to be shown (`window/showMessage`).
+    ServerMessage(smash_lsp::ServerMessage),
     /// Error message from LSP.
     Error(String),
     /// Info message from LSP.
@@ -40,10 +113,26 @@ pub(crate) enum LspCommand {
     DidChange {
         uri: String,
         version: i32,
-        text: String,
+        changes: Vec<TextDocumentContentChangeEvent>,
     },
     DidSave {
         uri: String,
+        /// The saved text, for servers that asked for it.
+        text: Option<String>,
+    },
+    /// Ask for edits to make before `uri` is written: the server's
+    /// formatting if `format` is set, otherwise its `willSaveWaitUntil`
+    /// edits.
+    WillSaveWaitUntil {
+        uri: String,
+        format: bool,
+    },
+    /// Ask for the edits of the server's organize-imports action over
+    /// `range` (the whole document) before `uri` is written. Answered
+    /// like [`LspCommand::WillSaveWaitUntil`].
+    OrganizeImports {
+        uri: String,
+        range: LspRange,
     },
     DidClose {
         uri: String,
@@ -55,11 +144,39 @@ pub(crate) enum LspCommand {
     GotoDefinition {
         uri: String,
         position: LspPosition,
+        target: GotoTarget,
     },
     FindReferences {
         uri: String,
         position: LspPosition,
     },
+    DocumentSymbols {
+        uri: String,
+    },
+    PrepareCallHierarchy {
+        uri: String,
+        position: LspPosition,
+        direction: CallDirection,
+    },
+    /// Callers or callees of `item`, for the call hierarchy node `node`.
+    /// Asked of the server for `uri`, the document the hierarchy
+    /// started from.
+    CallHierarchyCalls {
+        uri: String,
+        item: CallHierarchyItem,
+        direction: CallDirection,
+        node: u64,
+    },
+    /// Symbols matching `query` across the workspace, from the server
+    /// for the current document's language.
+    WorkspaceSymbols {
+        query: String,
+        language_id: Option<String>,
+    },
+    SignatureHelp {
+        uri: String,
+        position: LspPosition,
+    },
     Completion {
         uri: String,
         position: LspPosition,
@@ -67,9 +184,23 @@ pub(crate) enum LspCommand {
     Format {
         uri: String,
     },
+    RangeFormat {
+        uri: String,
+        range: LspRange,
+    },
     CodeAction {
         uri: String,
         range: LspRange,
+        /// Diagnostics the actions should address.
+        diagnostics: Vec<smash_lsp::Diagnostic>,
+        /// Action kinds to ask for; empty means all.
+        only: Vec<String>,
+    },
+    /// Run a command from a code action on the server for `language_id`,
+    /// which offered it.
+    ExecuteCommand {
+        command: smash_lsp::ServerCommand,
+        language_id: Option<String>,
     },
     Shutdown,
 }
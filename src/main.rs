@@ -1,18 +1,115 @@
 mod app;
 mod backend;
+mod blame;
+mod debounce;
 mod editor;
+mod job;
+mod log_filter;
 mod lsp_task;
 mod lsp_types;
+mod render_schedule;
+mod watch;
 
 use std::env;
 use std::path::PathBuf;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let file = args.get(1).map(PathBuf::from);
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = parse_args(&args);
 
-    if let Err(e) = editor::run_editor(file) {
-        eprintln!("smash: {:#}", e);
-        std::process::exit(1);
+    match editor::run_editor(args) {
+        Ok(0) => {}
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("smash: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Command-line arguments.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Args {
+    /// File to open.
+    pub(crate) file: Option<PathBuf>,
+    /// 1-based line to jump to.
+    pub(crate) line: Option<usize>,
+    /// Reopen the last session (`--restore`).
+    pub(crate) restore: bool,
+    /// Print how long each startup phase took on exit
+    /// (`--profile-startup`).
+    pub(crate) profile_startup: bool,
+}
+
+/// Parse `smash [--restore] [--profile-startup] [+LINE] [FILE]`.
+fn parse_args(args: &[String]) -> Args {
+    let mut parsed = Args::default();
+    for arg in args {
+        if arg == "--restore" {
+            parsed.restore = true;
+            continue;
+        }
+        if arg == "--profile-startup" {
+            parsed.profile_startup = true;
+            continue;
+        }
+        match arg.strip_prefix('+').map(str::parse::<usize>) {
+            Some(Ok(n)) if n > 0 => parsed.line = Some(n),
+            _ => {
+                if parsed.file.is_none() {
+                    parsed.file = Some(PathBuf::from(arg));
+                }
+            }
+        }
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn parsed(file: Option<&str>, line: Option<usize>) -> Args {
+        Args {
+            file: file.map(PathBuf::from),
+            line,
+            restore: false,
+            profile_startup: false,
+        }
+    }
+
+    #[test]
+    fn parse_args_file_only() {
+        assert_eq!(parse_args(&args(&["a.rs"])), parsed(Some("a.rs"), None));
+    }
+
+    #[test]
+    fn parse_args_line_before_or_after_file() {
+        let expected = parsed(Some("a.rs"), Some(12));
+        assert_eq!(parse_args(&args(&["+12", "a.rs"])), expected);
+        assert_eq!(parse_args(&args(&["a.rs", "+12"])), expected);
+    }
+
+    #[test]
+    fn parse_args_non_numeric_plus_is_a_file() {
+        assert_eq!(parse_args(&args(&["+notes"])), parsed(Some("+notes"), None));
+    }
+
+    #[test]
+    fn parse_args_restore_flag() {
+        let args = parse_args(&args(&["--restore"]));
+        assert!(args.restore);
+        assert_eq!(args.file, None);
+    }
+
+    #[test]
+    fn parse_args_profile_startup_flag() {
+        let args = parse_args(&args(&["--profile-startup", "a.rs"]));
+        assert!(args.profile_startup);
+        assert_eq!(args.file, Some(PathBuf::from("a.rs")));
     }
 }
@@ -0,0 +1,91 @@
+//! Coalescing redraws into frames.
+//!
+//! Handling input, server messages or background results only marks the
+//! screen dirty; the event loop draws once a frame is due, so a burst of
+//! events costs one frame instead of one each, and an idle editor draws
+//! nothing at all.
+
+use std::time::{Duration, Instant};
+
+/// Decides when the event loop draws: only while something changed, and
+/// at most `max_fps` times a second.
+#[derive(Debug, Clone)]
+pub(crate) struct RenderScheduler {
+    interval: Duration,
+    dirty: bool,
+    last_frame: Option<Instant>,
+}
+
+impl RenderScheduler {
+    pub(crate) fn new(max_fps: u32) -> Self {
+        Self {
+            interval: frame_interval(max_fps),
+            dirty: false,
+            last_frame: None,
+        }
+    }
+
+    pub(crate) fn set_max_fps(&mut self, max_fps: u32) {
+        self.interval = frame_interval(max_fps);
+    }
+
+    /// Ask for the next frame.
+    pub(crate) fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Time left before the next frame is due, if one is wanted.
+    pub(crate) fn remaining(&self, now: Instant) -> Option<Duration> {
+        if !self.dirty {
+            return None;
+        }
+        Some(self.last_frame.map_or(Duration::ZERO, |last| {
+            (last + self.interval).saturating_duration_since(now)
+        }))
+    }
+
+    /// Whether to draw now. Drawing clears the mark and starts the next
+    /// frame's interval.
+    pub(crate) fn fire(&mut self, now: Instant) -> bool {
+        if self.remaining(now) != Some(Duration::ZERO) {
+            return false;
+        }
+        self.dirty = false;
+        self.last_frame = Some(now);
+        true
+    }
+}
+
+fn frame_interval(max_fps: u32) -> Duration {
+    Duration::from_secs(1) / max_fps.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_only_when_dirty_and_at_most_once_per_frame() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut frames = RenderScheduler::new(50);
+        assert!(!frames.fire(start));
+        assert_eq!(frames.remaining(start), None);
+
+        frames.mark_dirty();
+        assert!(frames.fire(start));
+        assert_eq!(frames.remaining(start), None);
+
+        // Changes within the frame are drawn together once it is due.
+        frames.mark_dirty();
+        frames.mark_dirty();
+        assert_eq!(frames.remaining(start + ms(5)), Some(ms(15)));
+        assert!(!frames.fire(start + ms(5)));
+        assert!(frames.fire(start + ms(20)));
+        assert!(!frames.fire(start + ms(100)));
+
+        frames.set_max_fps(10);
+        frames.mark_dirty();
+        assert_eq!(frames.remaining(start + ms(50)), Some(ms(70)));
+    }
+}
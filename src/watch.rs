@@ -0,0 +1,94 @@
+//! Watch mode: re-run a shell command whenever a matching file is saved
+//! and collect its output for the watch pane.
+
+use std::path::Path;
+
+use smash_core::glob::glob_match;
+
+use crate::job::{JobStatus, OutputJob};
+
+/// A shell command bound to a file glob.
+pub(crate) struct WatchJob {
+    glob: String,
+    job: OutputJob,
+}
+
+impl WatchJob {
+    pub(crate) fn new(glob: impl Into<String>, command: impl Into<String>) -> Self {
+        Self {
+            glob: glob.into(),
+            job: OutputJob::new(command),
+        }
+    }
+
+    pub(crate) fn glob(&self) -> &str {
+        &self.glob
+    }
+
+    pub(crate) fn command(&self) -> &str {
+        self.job.command()
+    }
+
+    pub(crate) fn status(&self) -> JobStatus {
+        self.job.status()
+    }
+
+    pub(crate) fn output(&self) -> &[String] {
+        self.job.output()
+    }
+
+    /// Whether a save of `path` should trigger the command. Paths under
+    /// the working directory are matched relative to it.
+    pub(crate) fn matches(&self, path: &Path) -> bool {
+        let cwd = std::env::current_dir().ok();
+        let relative = cwd
+            .as_deref()
+            .and_then(|cwd| path.strip_prefix(cwd).ok())
+            .unwrap_or(path);
+        glob_match(&self.glob, &relative.to_string_lossy())
+    }
+
+    /// Start a run, killing the previous one if it is still going.
+    pub(crate) fn start(&mut self) {
+        self.job.start();
+    }
+
+    /// Collect new output and detect completion. Returns `true` if
+    /// anything visible changed.
+    pub(crate) fn poll(&mut self) -> bool {
+        self.job.poll()
+    }
+
+    /// Short status for the statusline, e.g. `watch: ok`.
+    pub(crate) fn status_label(&self) -> String {
+        format!("watch: {}", self.status().label())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn matches_file_name_glob() {
+        let job = WatchJob::new("*.rs", "true");
+        assert!(job.matches(Path::new("/somewhere/else/main.rs")));
+        assert!(!job.matches(Path::new("/somewhere/else/notes.md")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn status_label_names_the_watch() {
+        let mut job = WatchJob::new("*", "exit 3");
+        assert_eq!(job.status_label(), "watch: idle");
+        job.start();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while job.status() == JobStatus::Running {
+            assert!(Instant::now() < deadline, "watch command timed out");
+            job.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(job.status_label(), "watch: failed (3)");
+    }
+}